@@ -0,0 +1,7 @@
+// Generates the gRPC service and messages from proto/toydb.proto, using a
+// vendored protoc binary such that it doesn't have to be installed.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::compile_protos("proto/toydb.proto")?;
+    Ok(())
+}
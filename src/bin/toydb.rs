@@ -1,7 +1,15 @@
 /*
  * toydb is the toyDB server. It takes configuration via a configuration file, command-line
  * parameters, and environment variables, then starts up a toyDB TCP server that communicates with
- * SQL clients (port 9605) and Raft peers (port 9705).
+ * SQL clients (port 9605) and Raft peers (port 9705), and optionally PostgreSQL, HTTP, and
+ * gRPC clients. SQL and Raft connections use TLS if tls_cert, tls_key, and tls_ca are set. If auth
+ * is enabled, clients must authenticate as a user or as root with the configured root_password.
+ *
+ * Clusters can either be configured statically via the peers setting, or formed dynamically:
+ * "toydb init" initializes a new single-node cluster, and "toydb join <address>" asks the cluster
+ * node with the given SQL address to add this node to the cluster, then starts it with the
+ * returned cluster members as peers. In both cases, the node's listen_raft address is advertised
+ * to peers, so it must be reachable by them. Statically configured clusters can't be joined.
  */
 
 #![warn(clippy::all)]
@@ -10,23 +18,33 @@ use serde_derive::Deserialize;
 use std::collections::HashMap;
 use toydb::error::{Error, Result};
 use toydb::raft;
+use toydb::server::Auth;
 use toydb::sql;
 use toydb::storage;
-use toydb::Server;
+use toydb::tls;
+use toydb::{Client, Server};
 
 const COMPACT_MIN_BYTES: u64 = 1024 * 1024;
 
+/// The retry interval when joining a cluster.
+const JOIN_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
 fn main() -> Result<()> {
-    let args = clap::command!()
-        .arg(
-            clap::Arg::new("config")
-                .short('c')
-                .long("config")
-                .help("Configuration file path")
-                .default_value("config/toydb.yaml"),
-        )
-        .get_matches();
-    let cfg = Config::new(args.get_one::<String>("config").unwrap().as_ref())?;
+    let args =
+        clap::command!()
+            .arg(
+                clap::Arg::new("config")
+                    .short('c')
+                    .long("config")
+                    .help("Configuration file path")
+                    .default_value("config/toydb.yaml"),
+            )
+            .subcommand(clap::Command::new("init").about("Initializes a new single-node cluster"))
+            .subcommand(clap::Command::new("join").about("Joins an existing cluster").arg(
+                clap::Arg::new("address").help("SQL address of a cluster node").required(true),
+            ))
+            .get_matches();
+    let mut cfg = Config::new(args.get_one::<String>("config").unwrap().as_ref())?;
 
     let loglevel = cfg.log_level.parse::<simplelog::LevelFilter>()?;
     let mut logconfig = simplelog::ConfigBuilder::new();
@@ -36,7 +54,7 @@ fn main() -> Result<()> {
     simplelog::SimpleLogger::init(loglevel, logconfig.build())?;
 
     let path = std::path::Path::new(&cfg.data_dir);
-    let raft_log = match cfg.storage_raft.as_str() {
+    let mut raft_log = match cfg.storage_raft.as_str() {
         "bitcask" | "" => raft::Log::new(
             storage::BitCask::new_compact(
                 path.join("log"),
@@ -55,44 +73,138 @@ fn main() -> Result<()> {
                 cfg.compact_threshold,
                 COMPACT_MIN_BYTES,
             )?;
-            Box::new(sql::engine::Raft::new_state(engine)?)
+            Box::new(sql::engine::Raft::new_state(engine, cfg.max_parallel_workers)?)
         }
         "memory" => {
             let engine = storage::Memory::new();
-            Box::new(sql::engine::Raft::new_state(engine)?)
+            Box::new(sql::engine::Raft::new_state(engine, cfg.max_parallel_workers)?)
         }
         name => return Err(Error::Config(format!("Unknown SQL storage engine {}", name))),
     };
 
-    Server::new(cfg.id, cfg.peers, raft_log, raft_state)?.serve(&cfg.listen_raft, &cfg.listen_sql)
+    let tls = match (&cfg.tls_cert, &cfg.tls_key, &cfg.tls_ca) {
+        (None, None, None) => None,
+        (Some(cert), Some(key), Some(ca)) => {
+            Some(tls::Config::new(cert.as_ref(), key.as_ref(), ca.as_ref(), cfg.tls_client_auth)?)
+        }
+        _ => return Err(Error::Config("TLS requires tls_cert, tls_key, and tls_ca".into())),
+    };
+
+    let auth = match cfg.auth {
+        true => Some(Auth::new(cfg.root_password.as_deref())?),
+        false => None,
+    };
+
+    let raft_opts = raft::Options {
+        forward_requests: cfg.forward_requests,
+        priorities: cfg.priorities,
+        ..Default::default()
+    };
+
+    match args.subcommand() {
+        // Write a committed AddNode entry for the local node as the first log
+        // entry, such that joining nodes learn its address.
+        Some(("init", _)) => {
+            if raft_log.get_last_index().0 > 0 {
+                return Err(Error::Config("Node is already initialized".into()));
+            }
+            let command = raft::Command::AddNode { id: cfg.id, address: cfg.listen_raft.clone() };
+            raft_log.set_term(1, None)?;
+            raft_log.append(1, command)?;
+            raft_log.commit(1)?;
+            log::info!("Initialized new cluster with node {}", cfg.id);
+        }
+
+        // Ask the cluster to add us before starting, and use the returned
+        // cluster members as peers, such that we know the full membership
+        // before taking part in elections. The request is retried until the
+        // cluster is available. Rejoining at the same address, e.g. after a
+        // restart, returns the members again.
+        Some(("join", args)) => {
+            let addr = args.get_one::<String>("address").unwrap();
+            let tls = tls.as_ref().map(tls::Config::client);
+            // Authenticate as root if authentication is enabled.
+            let root_password = cfg.root_password.as_deref().filter(|_| cfg.auth);
+            let members = loop {
+                let client = match &tls {
+                    Some(config) => Client::new_tls(addr, config.clone()),
+                    None => Client::new(addr),
+                };
+                let result = client.and_then(|mut c| {
+                    if let Some(password) = root_password {
+                        c.authenticate("root", password)?;
+                    }
+                    c.join(cfg.id, &cfg.listen_raft)
+                });
+                match result {
+                    Ok(members) => break members,
+                    Err(err @ (Error::Value(_) | Error::Unauthenticated)) => return Err(err),
+                    Err(err) => log::warn!("Failed joining cluster via {addr}, retrying: {err}"),
+                }
+                std::thread::sleep(JOIN_RETRY_INTERVAL);
+            };
+            log::info!("Joined cluster via {addr}");
+            cfg.peers = members.into_iter().filter(|(id, _)| *id != cfg.id).collect();
+        }
+
+        _ => {}
+    }
+
+    Server::new(cfg.id, cfg.peers, cfg.peers_sql, raft_log, raft_state, raft_opts, tls, auth)?
+        .serve(
+            &cfg.listen_raft,
+            &cfg.listen_sql,
+            cfg.listen_pg.as_deref(),
+            cfg.listen_http.as_deref(),
+            cfg.listen_grpc.as_deref(),
+        )
 }
 
 #[derive(Debug, Deserialize)]
 struct Config {
     id: raft::NodeID,
     peers: HashMap<raft::NodeID, String>,
+    peers_sql: HashMap<raft::NodeID, String>,
+    forward_requests: bool,
+    priorities: HashMap<raft::NodeID, u8>,
     listen_sql: String,
     listen_raft: String,
+    listen_pg: Option<String>,
+    listen_http: Option<String>,
+    listen_grpc: Option<String>,
+    tls_cert: Option<String>,
+    tls_key: Option<String>,
+    tls_ca: Option<String>,
+    tls_client_auth: bool,
+    auth: bool,
+    root_password: Option<String>,
     log_level: String,
     data_dir: String,
     compact_threshold: f64,
     sync: bool,
     storage_raft: String,
     storage_sql: String,
+    max_parallel_workers: usize,
 }
 
 impl Config {
     fn new(file: &str) -> Result<Self> {
         Ok(config::Config::builder()
             .set_default("id", "1")?
+            .set_default("peers_sql", HashMap::<String, String>::new())?
+            .set_default("forward_requests", true)?
+            .set_default("priorities", HashMap::<String, u8>::new())?
             .set_default("listen_sql", "0.0.0.0:9605")?
             .set_default("listen_raft", "0.0.0.0:9705")?
+            .set_default("tls_client_auth", false)?
+            .set_default("auth", false)?
             .set_default("log_level", "info")?
             .set_default("data_dir", "data")?
             .set_default("compact_threshold", 0.2)?
             .set_default("sync", true)?
             .set_default("storage_raft", "bitcask")?
             .set_default("storage_sql", "bitcask")?
+            .set_default("max_parallel_workers", 4)?
             .add_source(config::File::with_name(file))
             .add_source(config::Environment::with_prefix("TOYDB"))
             .build()?
@@ -1,6 +1,7 @@
 /*
  * toysql is a command-line client for toyDB. It connects to a toyDB cluster node and executes SQL
- * queries against it via a REPL interface.
+ * queries against it via a REPL interface. If the server requires authentication, the user is
+ * given via --user, and the password via --password or the TOYDB_PASSWORD environment variable.
  */
 
 #![warn(clippy::all)]
@@ -11,9 +12,13 @@ use rustyline::{error::ReadlineError, Editor, Modifiers};
 use rustyline_derive::{Completer, Helper, Highlighter, Hinter};
 use toydb::error::{Error, Result};
 use toydb::sql::execution::ResultSet;
-use toydb::sql::parser::{Lexer, Token};
+use toydb::sql::parser::{Lexer, Span, Token};
+use toydb::tls;
 use toydb::Client;
 
+/// The number of rows to fetch from the server at a time.
+const PAGE_SIZE: usize = 1000;
+
 fn main() -> Result<()> {
     let opts = clap::command!()
         .name("toysql")
@@ -31,11 +36,39 @@ fn main() -> Result<()> {
                 .help("Port number to connect to")
                 .value_parser(clap::value_parser!(u16))
                 .default_value("9605"),
+            clap::Arg::new("tls-ca")
+                .long("tls-ca")
+                .help("Connect via TLS, verifying the server with this CA certificate file"),
+            clap::Arg::new("tls-cert")
+                .long("tls-cert")
+                .requires_all(["tls-ca", "tls-key"])
+                .help("TLS client certificate file, if the server requires one"),
+            clap::Arg::new("tls-key")
+                .long("tls-key")
+                .requires_all(["tls-ca", "tls-cert"])
+                .help("TLS client private key file"),
+            clap::Arg::new("user").short('u').long("user").help("User to authenticate as"),
+            clap::Arg::new("password")
+                .long("password")
+                .requires("user")
+                .help("Password to authenticate with (defaults to $TOYDB_PASSWORD)"),
         ])
         .get_matches();
 
+    let path = |name| opts.get_one::<String>(name).map(std::path::Path::new);
+    let tls = match path("tls-ca") {
+        Some(ca) => Some(tls::client_config(ca, path("tls-cert").zip(path("tls-key")))?),
+        None => None,
+    };
     let mut toysql =
-        ToySQL::new(opts.get_one::<String>("host").unwrap(), *opts.get_one("port").unwrap())?;
+        ToySQL::new(opts.get_one::<String>("host").unwrap(), *opts.get_one("port").unwrap(), tls)?;
+    if let Some(user) = opts.get_one::<String>("user") {
+        let password = match opts.get_one::<String>("password") {
+            Some(password) => password.clone(),
+            None => std::env::var("TOYDB_PASSWORD").unwrap_or_default(),
+        };
+        toysql.client.authenticate(user, &password)?;
+    }
 
     if let Some(command) = opts.get_one::<&str>("command") {
         toysql.execute(command)
@@ -53,10 +86,19 @@ struct ToySQL {
 }
 
 impl ToySQL {
-    /// Creates a new ToySQL REPL for the given server host and port
-    fn new(host: &str, port: u16) -> Result<Self> {
+    /// Creates a new ToySQL REPL for the given server host and port, using
+    /// TLS if given a client configuration.
+    fn new(
+        host: &str,
+        port: u16,
+        tls: Option<std::sync::Arc<rustls::ClientConfig>>,
+    ) -> Result<Self> {
+        let client = match tls {
+            Some(config) => Client::new_tls(&format!("{host}:{port}"), config)?,
+            None => Client::new((host, port))?,
+        };
         Ok(Self {
-            client: Client::new((host, port))?,
+            client,
             editor: Editor::new()?,
             history_path: std::env::var_os("HOME")
                 .map(|home| std::path::Path::new(&home).join(".toysql.history")),
@@ -76,8 +118,8 @@ impl ToySQL {
     }
 
     /// Handles a REPL command (prefixed by !, e.g. !help)
-    fn execute_command(&mut self, input: &str) -> Result<()> {
-        let mut input = input.split_ascii_whitespace();
+    fn execute_command(&mut self, line: &str) -> Result<()> {
+        let mut input = line.split_ascii_whitespace();
         let command = input.next().ok_or_else(|| Error::Parse("Expected command.".to_string()))?;
 
         let getargs = |n| {
@@ -90,6 +132,21 @@ impl ToySQL {
         };
 
         match command {
+            "!copy" => {
+                let (path, query) = line
+                    .trim_start_matches("!copy")
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| Error::Parse("!copy: expected file and statement".into()))?;
+                let file = std::fs::File::open(path)
+                    .map_err(|err| Error::Value(format!("Can't open file {}: {}", path, err)))?;
+                match self.client.copy_from(query.trim(), std::io::BufReader::new(file))? {
+                    ResultSet::Copy { count } => println!("Copied {} rows", count),
+                    result => {
+                        return Err(Error::Internal(format!("Unexpected result {:?}", result)))
+                    }
+                }
+            }
             "!headers" => match getargs(1)?[0] {
                 "on" => {
                     self.show_headers = true;
@@ -106,11 +163,14 @@ impl ToySQL {
 Enter a SQL statement terminated by a semicolon (;) to execute it and display the result.
 The following commands are also available:
 
-    !headers <on|off>  Enable or disable column headers
-    !help              This help message
-    !status            Display server status
-    !table [table]     Display table schema, if it exists
-    !tables            List tables
+    !copy <file> <sql>  Execute a COPY FROM STDIN statement with input from a local file
+    !headers <on|off>   Enable or disable column headers
+    !help               This help message
+    !status             Display server status
+    !table [table]      Display table schema, if it exists
+    !tables             List tables
+    !view [view]        Display view definition, if it exists
+    !views              List views
 "#
             ),
             "!status" => {
@@ -172,30 +232,99 @@ Storage:   {keys} keys, {logical_size} MB logical, {nodes}x {disk_size} MB disk,
                     println!("{}", table)
                 }
             }
+            "!view" => {
+                let args = getargs(1)?;
+                println!("{}", self.client.get_view(args[0])?);
+            }
+            "!views" => {
+                getargs(0)?;
+                for view in self.client.list_views()? {
+                    println!("{}", view)
+                }
+            }
             c => return Err(Error::Parse(format!("Unknown command {}", c))),
         }
         Ok(())
     }
 
-    /// Runs a query and displays the results
+    /// Runs a query and displays the results. Rows are fetched and displayed
+    /// in pages, such that large results aren't buffered in memory.
     fn execute_query(&mut self, query: &str) -> Result<()> {
-        match self.client.execute(query)? {
+        let (cursor, resultset) = self.client.open(query)?;
+        match resultset {
             ResultSet::Begin { version, read_only } => match read_only {
                 false => println!("Began transaction at new version {}", version),
                 true => println!("Began read-only transaction at version {}", version),
             },
             ResultSet::Commit { version: id } => println!("Committed transaction {}", id),
             ResultSet::Rollback { version: id } => println!("Rolled back transaction {}", id),
+            ResultSet::Savepoint { name } => println!("Created savepoint {}", name),
+            ResultSet::RollbackToSavepoint { name } => {
+                println!("Rolled back to savepoint {}", name)
+            }
+            ResultSet::ReleaseSavepoint { name } => println!("Released savepoint {}", name),
+            ResultSet::Set { name } => println!("Set {}", name),
+            ResultSet::Use { database } => println!("Using database {}", database),
+            ResultSet::Kill { id } => println!("Cancelled query {}", id),
+            ResultSet::Copy { count } => println!("Copied {} rows", count),
             ResultSet::Create { count } => println!("Created {} rows", count),
             ResultSet::Delete { count } => println!("Deleted {} rows", count),
             ResultSet::Update { count } => println!("Updated {} rows", count),
+            ResultSet::CreateDatabase { name } => println!("Created database {}", name),
             ResultSet::CreateTable { name } => println!("Created table {}", name),
             ResultSet::DropTable { name, existed } => match existed {
                 true => println!("Dropped table {}", name),
                 false => println!("Table {} did not exit", name),
             },
+            ResultSet::AlterTable { name } => println!("Altered table {}", name),
+            ResultSet::CreateIndex { name } => println!("Created index {}", name),
+            ResultSet::DropIndex { name, existed } => match existed {
+                true => println!("Dropped index {}", name),
+                false => println!("Index {} did not exist", name),
+            },
+            ResultSet::CreateView { name } => println!("Created view {}", name),
+            ResultSet::CreateSequence { name } => println!("Created sequence {}", name),
+            ResultSet::CreateType { name } => println!("Created type {}", name),
+            ResultSet::DropType { name, existed } => match existed {
+                true => println!("Dropped type {}", name),
+                false => println!("Type {} did not exist", name),
+            },
+            ResultSet::DropSequence { name, existed } => match existed {
+                true => println!("Dropped sequence {}", name),
+                false => println!("Sequence {} did not exist", name),
+            },
+            ResultSet::CreateUser { name } => println!("Created user {}", name),
+            ResultSet::AlterUser { name } => println!("Altered user {}", name),
+            ResultSet::DropUser { name, existed } => match existed {
+                true => println!("Dropped user {}", name),
+                false => println!("User {} did not exist", name),
+            },
+            ResultSet::CreateRole { name } => println!("Created role {}", name),
+            ResultSet::Grant { grantee } => println!("Granted to {}", grantee),
+            ResultSet::Revoke { grantee } => println!("Revoked from {}", grantee),
+            ResultSet::CreateTrigger { name } => println!("Created trigger {}", name),
+            ResultSet::DropTrigger { name, existed } => match existed {
+                true => println!("Dropped trigger {}", name),
+                false => println!("Trigger {} did not exist", name),
+            },
+            ResultSet::Comment { name } => println!("Commented on {}", name),
+            ResultSet::Analyze { tables } => {
+                for table in tables {
+                    println!("Analyzed table {}", table)
+                }
+            }
+            ResultSet::DropView { name, existed } => match existed {
+                true => println!("Dropped view {}", name),
+                false => println!("View {} did not exist", name),
+            },
             ResultSet::Explain(plan) => println!("{}", plan),
-            ResultSet::Query { columns, mut rows } => {
+            ResultSet::ExplainAnalyze { plan, stats } => {
+                println!("{}", plan.format_analyzed(&stats))
+            }
+            ResultSet::ExplainVerbose { plan, estimates, stats } => {
+                println!("{}", plan.format_verbose(&estimates, &stats))
+            }
+            ResultSet::Query { columns, .. } => {
                 if self.show_headers {
                     println!(
                         "{}",
@@ -206,11 +335,18 @@ Storage:   {keys} keys, {logical_size} MB logical, {nodes}x {disk_size} MB disk,
                             .join("|")
                     );
                 }
-                while let Some(row) = rows.next().transpose()? {
-                    println!(
-                        "{}",
-                        row.into_iter().map(|v| format!("{}", v)).collect::<Vec<_>>().join("|")
-                    );
+                let Some(cursor) = cursor else { return Ok(()) };
+                loop {
+                    let (rows, done) = self.client.fetch(cursor, PAGE_SIZE)?;
+                    for row in rows {
+                        println!(
+                            "{}",
+                            row.into_iter().map(|v| format!("{}", v)).collect::<Vec<_>>().join("|")
+                        );
+                    }
+                    if done {
+                        break;
+                    }
                 }
             }
         }
@@ -234,6 +370,18 @@ Storage:   {keys} keys, {logical_size} MB logical, {nodes}x {disk_size} MB disk,
         }
     }
 
+    /// Prints the input line containing the given span, underlining the span.
+    fn print_span(input: &str, span: Span) {
+        let Some(line) = input.lines().nth(span.line.saturating_sub(1)) else { return };
+        let width = input
+            .get(span.start..span.end)
+            .and_then(|text| text.lines().next())
+            .map_or(0, |text| text.chars().count())
+            .max(1);
+        println!("{}", line);
+        println!("{}{}", " ".repeat(span.column - 1), "^".repeat(width));
+    }
+
     /// Runs the ToySQL REPL
     fn run(&mut self) -> Result<()> {
         if let Some(path) = &self.history_path {
@@ -257,7 +405,12 @@ Storage:   {keys} keys, {logical_size} MB logical, {nodes}x {disk_size} MB disk,
             match self.execute(&input) {
                 Ok(()) => {}
                 error @ Err(Error::Internal(_)) => return error,
-                Err(error) => println!("Error: {}", error),
+                Err(error) => {
+                    println!("Error: {}", error);
+                    if let Error::Syntax { span, .. } = error {
+                        Self::print_span(&input, span);
+                    }
+                }
             }
         }
 
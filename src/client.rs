@@ -1,33 +1,90 @@
 use crate::encoding::bincode;
 use crate::error::{Error, Result};
-use crate::server::{Request, Response, Status};
+use crate::raft::NodeID;
+use crate::server::{CursorID, Request, Response, Status};
 use crate::sql::execution::ResultSet;
-use crate::sql::schema::Table;
+use crate::sql::schema::{Table, View};
+use crate::sql::types::Row;
+use crate::tls;
 
 use rand::Rng;
-use std::io::Write as _;
+use std::collections::BTreeMap;
+use std::io::{Read, Write as _};
+use std::sync::Arc;
 
 /// A toyDB client
 pub struct Client {
-    reader: std::io::BufReader<std::net::TcpStream>,
-    writer: std::io::BufWriter<std::net::TcpStream>,
+    reader: std::io::BufReader<tls::Stream>,
+    writer: std::io::BufWriter<tls::Stream>,
     txn: Option<(u64, bool)>,
+    /// The TLS configuration, if any, used when reconnecting.
+    tls: Option<Arc<rustls::ClientConfig>>,
+    /// The authenticated user and password, if any, used when reconnecting.
+    credentials: Option<(String, String)>,
 }
 
 impl Client {
     /// Creates a new client
     pub fn new(addr: impl std::net::ToSocketAddrs) -> Result<Self> {
         let socket = std::net::TcpStream::connect(addr)?;
+        Self::with_stream(socket.into(), None)
+    }
+
+    /// Creates a new client connecting to the given host:port address via
+    /// TLS (see tls::client_config).
+    pub fn new_tls(addr: &str, config: Arc<rustls::ClientConfig>) -> Result<Self> {
+        let socket = tls::connect(&config, std::net::TcpStream::connect(addr)?, addr)?;
+        Self::with_stream(socket, Some(config))
+    }
+
+    /// Creates a new client using the given connection stream.
+    fn with_stream(socket: tls::Stream, tls: Option<Arc<rustls::ClientConfig>>) -> Result<Self> {
         let reader = std::io::BufReader::new(socket.try_clone()?);
         let writer = std::io::BufWriter::new(socket);
-        Ok(Self { reader, writer, txn: None })
+        Ok(Self { reader, writer, txn: None, tls, credentials: None })
     }
 
-    /// Call a server method
+    /// Call a server method. If the server isn't the Raft leader and tells us
+    /// where the leader is, reconnect to it (re-authenticating if needed) and
+    /// retry, unless we're in a transaction (which is bound to the server
+    /// session).
     fn call(&mut self, request: Request) -> Result<Response> {
-        bincode::serialize_into(&mut self.writer, &request)?;
-        self.writer.flush()?;
-        bincode::deserialize_from(&mut self.reader)?
+        const MAX_REDIRECTS: u32 = 3;
+
+        let mut redirects = 0;
+        loop {
+            bincode::serialize_into(&mut self.writer, &request)?;
+            self.writer.flush()?;
+            match bincode::deserialize_from(&mut self.reader)? {
+                Err(Error::NotLeader { address: Some(address), .. })
+                    if self.txn.is_none() && redirects < MAX_REDIRECTS =>
+                {
+                    let credentials = self.credentials.take();
+                    *self = match self.tls.clone() {
+                        Some(config) => Self::new_tls(&address, config)?,
+                        None => Self::new(address)?,
+                    };
+                    if let Some((user, password)) = credentials {
+                        self.authenticate(&user, &password)?;
+                    }
+                    redirects += 1;
+                }
+                response => return response,
+            }
+        }
+    }
+
+    /// Authenticates the session as the given user. This is required before
+    /// other requests if the server has authentication enabled.
+    pub fn authenticate(&mut self, user: &str, password: &str) -> Result<()> {
+        let request = Request::Authenticate { user: user.into(), password: password.into() };
+        match self.call(request)? {
+            Response::Authenticate => {
+                self.credentials = Some((user.into(), password.into()));
+                Ok(())
+            }
+            resp => Err(Error::Internal(format!("Unexpected response {:?}", resp))),
+        }
     }
 
     /// Executes a query
@@ -50,13 +107,89 @@ impl Client {
             }
             resultset = ResultSet::Query { columns, rows: Box::new(rows.into_iter().map(Ok)) }
         };
-        match &resultset {
+        self.track_txn(&resultset);
+        Ok(resultset)
+    }
+
+    /// Executes a query like execute(), but if it returns rows, returns a
+    /// cursor to fetch them from in pages via fetch() instead, along with a
+    /// result set containing the columns and no rows.
+    pub fn open(&mut self, query: &str) -> Result<(Option<CursorID>, ResultSet)> {
+        let (cursor, resultset) = match self.call(Request::Open(query.into()))? {
+            Response::Open { cursor, result } => (cursor, result),
+            resp => return Err(Error::Internal(format!("Unexpected response {:?}", resp))),
+        };
+        self.track_txn(&resultset);
+        Ok((cursor, resultset))
+    }
+
+    /// Fetches up to count rows from a cursor, returning them and whether the
+    /// cursor was exhausted. Exhausted cursors are closed, as are cursors that
+    /// return an error.
+    pub fn fetch(&mut self, cursor: CursorID, count: usize) -> Result<(Vec<Row>, bool)> {
+        match self.call(Request::Fetch { cursor, count })? {
+            Response::Fetch { rows, done } => Ok((rows, done)),
+            resp => Err(Error::Internal(format!("Unexpected response {:?}", resp))),
+        }
+    }
+
+    /// Closes a cursor before it is exhausted.
+    pub fn close(&mut self, cursor: CursorID) -> Result<()> {
+        match self.call(Request::Close(cursor))? {
+            Response::Close => Ok(()),
+            resp => Err(Error::Internal(format!("Unexpected response {:?}", resp))),
+        }
+    }
+
+    /// Executes a COPY FROM STDIN statement, streaming the given input to the
+    /// server in chunks. If reading the input fails, the statement is aborted.
+    pub fn copy_from(&mut self, query: &str, mut input: impl Read) -> Result<ResultSet> {
+        const CHUNK_SIZE: usize = 64 * 1024;
+
+        bincode::serialize_into(&mut self.writer, &Request::CopyIn(query.into()))?;
+        let mut buffer = vec![0; CHUNK_SIZE];
+        let mut input_err = None;
+        loop {
+            match input.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => bincode::serialize_into(
+                    &mut self.writer,
+                    &Request::CopyData(buffer[..n].to_vec()),
+                )?,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    input_err = Some(err);
+                    break;
+                }
+            }
+        }
+        match &input_err {
+            Some(err) => {
+                bincode::serialize_into(&mut self.writer, &Request::CopyFail(err.to_string()))?
+            }
+            None => bincode::serialize_into(&mut self.writer, &Request::CopyDone)?,
+        }
+        self.writer.flush()?;
+        let response = bincode::deserialize_from::<_, Result<Response>>(&mut self.reader)?;
+        if let Some(err) = input_err {
+            return Err(err.into());
+        }
+        let resultset = match response? {
+            Response::Execute(rs) => rs,
+            resp => return Err(Error::Internal(format!("Unexpected response {:?}", resp))),
+        };
+        self.track_txn(&resultset);
+        Ok(resultset)
+    }
+
+    /// Tracks the transaction state from a result set.
+    fn track_txn(&mut self, resultset: &ResultSet) {
+        match resultset {
             ResultSet::Begin { version, read_only } => self.txn = Some((*version, *read_only)),
             ResultSet::Commit { .. } => self.txn = None,
             ResultSet::Rollback { .. } => self.txn = None,
             _ => {}
         }
-        Ok(resultset)
     }
 
     /// Fetches the table schema as SQL
@@ -75,6 +208,22 @@ impl Client {
         }
     }
 
+    /// Fetches the view definition as SQL
+    pub fn get_view(&mut self, view: &str) -> Result<View> {
+        match self.call(Request::GetView(view.into()))? {
+            Response::GetView(v) => Ok(v),
+            resp => Err(Error::Value(format!("Unexpected response: {:?}", resp))),
+        }
+    }
+
+    /// Lists database views
+    pub fn list_views(&mut self) -> Result<Vec<String>> {
+        match self.call(Request::ListViews)? {
+            Response::ListViews(v) => Ok(v),
+            resp => Err(Error::Value(format!("Unexpected response: {:?}", resp))),
+        }
+    }
+
     /// Checks server status
     pub fn status(&mut self) -> Result<Status> {
         match self.call(Request::Status)? {
@@ -83,6 +232,15 @@ impl Client {
         }
     }
 
+    /// Adds a node to the cluster, with the given Raft address. Returns the
+    /// Raft addresses of all cluster members, including the new node.
+    pub fn join(&mut self, id: NodeID, address: &str) -> Result<BTreeMap<NodeID, String>> {
+        match self.call(Request::Join { id, address: address.into() })? {
+            Response::Join(members) => Ok(members),
+            resp => Err(Error::Value(format!("Unexpected response: {:?}", resp))),
+        }
+    }
+
     /// Returns the version and read-only state of the txn
     pub fn txn(&self) -> Option<(u64, bool)> {
         self.txn
@@ -23,6 +23,14 @@ pub fn deserialize<'de, T: serde::Deserialize<'de>>(bytes: &'de [u8]) -> Result<
     Ok(bincode().deserialize(bytes)?)
 }
 
+/// Deserializes a value using Bincode and the given deserialization seed.
+pub fn deserialize_seed<'de, S: serde::de::DeserializeSeed<'de>>(
+    bytes: &'de [u8],
+    seed: S,
+) -> Result<S::Value> {
+    Ok(bincode().deserialize_seed(seed, bytes)?)
+}
+
 /// Deserializes a value from a reader using Bincode.
 pub fn deserialize_from<R: std::io::Read, T: serde::de::DeserializeOwned>(reader: R) -> Result<T> {
     Ok(bincode().deserialize_from(reader)?)
@@ -74,7 +74,7 @@ struct Serializer {
     output: Vec<u8>,
 }
 
-impl<'a> serde::Serializer for &'a mut Serializer {
+impl serde::Serializer for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -271,7 +271,7 @@ impl<'a> serde::Serializer for &'a mut Serializer {
 }
 
 // Sequences simply concatenate the serialized elements, with no external structure.
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl ser::SerializeSeq for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -285,7 +285,7 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
 }
 
 // Tuples, like sequences, simply concatenate the serialized elements.
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl ser::SerializeTuple for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -299,7 +299,7 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
 }
 
 // Tuples, like sequences, simply concatenate the serialized elements.
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl ser::SerializeTupleVariant for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -363,7 +363,7 @@ impl<'de> Deserializer<'de> {
 }
 
 // For details on serialization formats, see Serializer.
-impl<'de, 'a> serde::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> serde::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = Error;
 
     fn deserialize_any<V: de::Visitor<'de>>(self, _: V) -> Result<V::Value> {
@@ -581,8 +581,10 @@ impl<'de> de::VariantAccess<'de> for &mut Deserializer<'de> {
 mod tests {
     use super::*;
     use crate::sql::types::Value;
+    use chrono::{NaiveDate, NaiveTime};
     use hex;
     use paste::paste;
+    use rust_decimal::Decimal;
     use serde::{Deserialize, Serialize};
     use serde_bytes::ByteBuf;
     use std::borrow::Cow;
@@ -708,6 +710,15 @@ mod tests {
         value_int: Value::Integer(-1) => "027fffffffffffffff",
         value_float: Value::Float(PI) => "03c00921fb54442d18",
         value_string: Value::String("foo".to_string()) => "04666f6f0000",
+        value_date: Value::Date(NaiveDate::from_ymd_opt(1969, 12, 31).unwrap()) => "057fffffffffffffff",
+        value_time: Value::Time(NaiveTime::from_hms_opt(0, 0, 1).unwrap()) => "0680000000000f4240",
+        value_timestamp: Value::Timestamp(NaiveDate::from_ymd_opt(1970, 1, 2).unwrap().into()) => "07800000141dd76000",
+        value_decimal: Value::Decimal(Decimal::new(150, 2)) => "080241020600ff020000",
+        value_decimal_negative: Value::Decimal(Decimal::new(-15, 1)) => "0800ffbe0905ff010000",
+        value_decimal_zero: Value::Decimal(Decimal::new(0, 2)) => "0801020000",
+        value_bytes: Value::Bytes(vec![0xde, 0xad, 0x00]) => "09dead00ff0000",
+        value_json: Value::Json(serde_json::json!({"b": 1, "a": null})) => "0a7b2261223a6e756c6c2c2262223a317d0000",
+        value_uuid: Value::Uuid(uuid::Uuid::from_u128(0xff)) => "0b00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ffff0000",
     }
 
     test_serialize_error! {
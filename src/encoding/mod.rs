@@ -2,5 +2,7 @@
 //!
 //! - keycode: used for keys in the key/value store.
 //! - bincode: used for values in the key/value store and network protocols.
+//! - row: used for SQL table rows in the key/value store.
 pub mod bincode;
 pub mod keycode;
+pub mod row;
@@ -0,0 +1,500 @@
+//! A compact binary encoding for SQL table rows, used for row values in the
+//! key/value store. Unlike Bincode, any column can be located without decoding
+//! the preceding values, so scans that only need a few columns can skip the
+//! rest entirely. A row with N columns, of which M are non-null, is encoded as:
+//!
+//! Header:  0xff, then a format byte with the format version (currently 1)
+//!          in the upper 4 bits and the width W of tail offsets (1, 2 or 4
+//!          bytes) in the lower 4 bits.
+//! Count:   The number of columns N as a u8, or if N >= 255 as 0xff followed
+//!          by N as a little-endian u16.
+//! Nulls:   A bitmap of ceil(N/8) bytes, where bit i%8 of byte i/8 is set if
+//!          column i is NULL.
+//! Types:   One type tag byte per non-null column, see Tag.
+//! Slots:   One fixed-width slot per non-null column, whose width is given by
+//!          the type tag. Variable-length values (strings, byte strings, JSON
+//!          documents and arrays) are stored in the tail, and their slot is the
+//!          W-byte end offset of the value within the tail.
+//! Tail:    The concatenated variable-length values.
+//!
+//! Booleans are stored in the tag itself, with an empty slot. Integers use the
+//! smallest of 1, 2, 4 or 8 bytes that fits the value. Numbers are stored
+//! little-endian, dates as the i32 number of days since the Unix epoch, times
+//! as the i64 number of microseconds since midnight, timestamps as the i64
+//! number of microseconds since the Unix epoch, intervals as the i32 months,
+//! i32 days and i64 microseconds, decimals in rust_decimal's 16-byte
+//! representation, and JSON documents in their canonical string form.
+//! Arrays are stored as a nested row of their elements in this same format.
+//!
+//! Bincode never begins an encoded Vec with 0xff, since that's not a valid
+//! varint length prefix. Rows written by earlier versions, which serialized
+//! the Vec<Value> with Bincode, are therefore recognized and decoded as such.
+//! They're rewritten in the current format when updated.
+
+use super::bincode;
+use crate::error::{Error, Result};
+use crate::sql::types::datetime::Interval;
+use crate::sql::types::{Row, Value};
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Timelike as _};
+use rust_decimal::Decimal;
+use serde::de::{DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// The header byte. Not a valid Bincode varint prefix.
+const HEADER: u8 = 0xff;
+
+/// The current format version.
+const VERSION: u8 = 1;
+
+/// Value type tags. Nulls are given by the bitmap and have no tag.
+#[derive(Clone, Copy)]
+#[repr(u8)]
+enum Tag {
+    False = 1,
+    True = 2,
+    Int8 = 3,
+    Int16 = 4,
+    Int32 = 5,
+    Int64 = 6,
+    Float = 7,
+    String = 8,
+    Date = 9,
+    Time = 10,
+    Timestamp = 11,
+    Decimal = 12,
+    Bytes = 13,
+    Json = 14,
+    Uuid = 15,
+    Array = 16,
+    Interval = 17,
+}
+
+impl Tag {
+    /// Returns the tag for a non-null value.
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => panic!("null values have no tag"),
+            Value::Boolean(false) => Self::False,
+            Value::Boolean(true) => Self::True,
+            Value::Integer(i) if i8::try_from(*i).is_ok() => Self::Int8,
+            Value::Integer(i) if i16::try_from(*i).is_ok() => Self::Int16,
+            Value::Integer(i) if i32::try_from(*i).is_ok() => Self::Int32,
+            Value::Integer(_) => Self::Int64,
+            Value::Float(_) => Self::Float,
+            Value::String(_) => Self::String,
+            Value::Date(_) => Self::Date,
+            Value::Time(_) => Self::Time,
+            Value::Timestamp(_) => Self::Timestamp,
+            Value::Decimal(_) => Self::Decimal,
+            Value::Bytes(_) => Self::Bytes,
+            Value::Json(_) => Self::Json,
+            Value::Uuid(_) => Self::Uuid,
+            Value::Array(_) => Self::Array,
+            Value::Interval(_) => Self::Interval,
+        }
+    }
+
+    /// Decodes a tag byte.
+    fn decode(byte: u8) -> Result<Self> {
+        Ok(match byte {
+            1 => Self::False,
+            2 => Self::True,
+            3 => Self::Int8,
+            4 => Self::Int16,
+            5 => Self::Int32,
+            6 => Self::Int64,
+            7 => Self::Float,
+            8 => Self::String,
+            9 => Self::Date,
+            10 => Self::Time,
+            11 => Self::Timestamp,
+            12 => Self::Decimal,
+            13 => Self::Bytes,
+            14 => Self::Json,
+            15 => Self::Uuid,
+            16 => Self::Array,
+            17 => Self::Interval,
+            b => return Err(Error::Internal(format!("Invalid row value type {}", b))),
+        })
+    }
+
+    /// Returns the slot width of the type, or None if the value is stored in
+    /// the tail and the slot holds its end offset.
+    fn width(self) -> Option<usize> {
+        match self {
+            Self::False | Self::True => Some(0),
+            Self::Int8 => Some(1),
+            Self::Int16 => Some(2),
+            Self::Int32 | Self::Date => Some(4),
+            Self::Int64 | Self::Float | Self::Time | Self::Timestamp => Some(8),
+            Self::Decimal | Self::Uuid | Self::Interval => Some(16),
+            Self::String | Self::Bytes | Self::Json | Self::Array => None,
+        }
+    }
+}
+
+/// Encodes a row.
+pub fn encode(row: &[Value]) -> Result<Vec<u8>> {
+    let count = u16::try_from(row.len())
+        .map_err(|_| Error::Value(format!("Row has too many columns ({})", row.len())))?;
+    let mut nulls = vec![0u8; row.len().div_ceil(8)];
+    let mut tags = Vec::with_capacity(row.len());
+    let mut slots: Vec<u8> = Vec::with_capacity(row.len() * 8);
+    let mut tail: Vec<u8> = Vec::new();
+    let mut offsets = Vec::new(); // (slot position, tail end offset)
+    for (i, value) in row.iter().enumerate() {
+        if value == &Value::Null {
+            nulls[i / 8] |= 1 << (i % 8);
+            continue;
+        }
+        tags.push(Tag::of(value) as u8);
+        match value {
+            Value::Null => unreachable!("nulls handled above"),
+            Value::Boolean(_) => {}
+            Value::Integer(i) => {
+                let width = Tag::of(value).width().expect("integers are fixed-width");
+                slots.extend(&i.to_le_bytes()[..width])
+            }
+            Value::Float(f) => slots.extend(f.to_le_bytes()),
+            Value::Date(d) => slots.extend(days_since_epoch(*d)?.to_le_bytes()),
+            Value::Time(t) => slots.extend(micros_since_midnight(*t).to_le_bytes()),
+            Value::Timestamp(ts) => slots.extend(ts.and_utc().timestamp_micros().to_le_bytes()),
+            Value::Decimal(d) => slots.extend(d.serialize()),
+            Value::Uuid(u) => slots.extend(u.as_bytes()),
+            Value::Interval(i) => {
+                slots.extend(i.months.to_le_bytes());
+                slots.extend(i.days.to_le_bytes());
+                slots.extend(i.micros.to_le_bytes());
+            }
+            Value::String(s) => tail.extend(s.as_bytes()),
+            Value::Bytes(b) => tail.extend(b),
+            Value::Json(j) => tail.extend(j.to_string().as_bytes()),
+            Value::Array(a) => tail.extend(encode(a)?),
+        }
+        if Tag::of(value).width().is_none() {
+            offsets.push((slots.len(), tail.len()));
+        }
+    }
+
+    // The offset width is only known once the tail is complete, so offsets
+    // are spliced into the slots afterwards.
+    let offset_width = match tail.len() {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        len if u32::try_from(len).is_ok() => 4,
+        _ => return Err(Error::Value("Row exceeds maximum size".into())),
+    };
+    let mut bytes = Vec::with_capacity(
+        5 + nulls.len() + tags.len() + slots.len() + offsets.len() * offset_width + tail.len(),
+    );
+    bytes.extend([HEADER, VERSION << 4 | offset_width as u8]);
+    match u8::try_from(count) {
+        Ok(count) if count < 0xff => bytes.push(count),
+        _ => bytes.extend([0xff].into_iter().chain(count.to_le_bytes())),
+    }
+    bytes.extend(nulls);
+    bytes.extend(tags);
+    let mut start = 0;
+    for (position, end) in offsets {
+        bytes.extend(&slots[start..position]);
+        bytes.extend(&(end as u32).to_le_bytes()[..offset_width]);
+        start = position;
+    }
+    bytes.extend(&slots[start..]);
+    bytes.extend(tail);
+    Ok(bytes)
+}
+
+/// Decodes a row. If columns are given (sorted), only these columns are
+/// decoded, and the others are returned as NULL.
+pub fn decode(bytes: &[u8], columns: Option<&[usize]>) -> Result<Row> {
+    if bytes.first() != Some(&HEADER) {
+        return decode_bincode(bytes, columns);
+    }
+    let invalid = || Error::Internal("Invalid encoded row".into());
+    let format = *bytes.get(1).ok_or_else(invalid)?;
+    if format >> 4 != VERSION {
+        return Err(Error::Internal(format!("Unsupported row format version {}", format >> 4)));
+    }
+    let offset_width = match format & 0x0f {
+        w @ (1 | 2 | 4) => w as usize,
+        _ => return Err(invalid()),
+    };
+    let (count, nulls_start) = match *bytes.get(2).ok_or_else(invalid)? {
+        0xff => (u16::from_le_bytes(bytes.get(3..5).ok_or_else(invalid)?.try_into()?) as usize, 5),
+        count => (count as usize, 3),
+    };
+    let nulls = bytes.get(nulls_start..nulls_start + count.div_ceil(8)).ok_or_else(invalid)?;
+    let is_null = |i: usize| nulls[i / 8] & (1 << (i % 8)) != 0;
+    let present = (0..count).filter(|i| !is_null(*i)).count();
+    let tags_start = nulls_start + nulls.len();
+    let tags = bytes.get(tags_start..tags_start + present).ok_or_else(invalid)?;
+    let tags = tags.iter().map(|b| Tag::decode(*b)).collect::<Result<Vec<_>>>()?;
+    let slots_start = tags_start + tags.len();
+    let slots_len: usize = tags.iter().map(|t| t.width().unwrap_or(offset_width)).sum();
+    let slots = bytes.get(slots_start..slots_start + slots_len).ok_or_else(invalid)?;
+    let tail = &bytes[slots_start + slots_len..];
+
+    let mut row = Vec::with_capacity(count);
+    let (mut tags, mut slot, mut tail_start) = (tags.into_iter(), 0, 0);
+    for i in 0..count {
+        if is_null(i) {
+            row.push(Value::Null);
+            continue;
+        }
+        let tag = tags.next().ok_or_else(invalid)?;
+        let width = tag.width().unwrap_or(offset_width);
+        let data = &slots[slot..slot + width];
+        slot += width;
+        // Variable-length values are located in the tail even when skipped,
+        // since the next value starts where this one ends.
+        let var = if tag.width().is_none() {
+            let mut end = [0; 4];
+            end[..width].copy_from_slice(data);
+            let end = u32::from_le_bytes(end) as usize;
+            let value = tail.get(tail_start..end).ok_or_else(invalid)?;
+            tail_start = end;
+            value
+        } else {
+            &[]
+        };
+        if columns.is_some_and(|c| c.binary_search(&i).is_err()) {
+            row.push(Value::Null);
+            continue;
+        }
+        row.push(decode_value(tag, data, var)?);
+    }
+    Ok(row)
+}
+
+/// Decodes a non-null value from its slot, and tail data if variable-length.
+fn decode_value(tag: Tag, slot: &[u8], var: &[u8]) -> Result<Value> {
+    let invalid = |kind: &str| Error::Internal(format!("Invalid {} in encoded row", kind));
+    Ok(match tag {
+        Tag::False => Value::Boolean(false),
+        Tag::True => Value::Boolean(true),
+        Tag::Int8 => Value::Integer(i8::from_le_bytes(slot.try_into()?) as i64),
+        Tag::Int16 => Value::Integer(i16::from_le_bytes(slot.try_into()?) as i64),
+        Tag::Int32 => Value::Integer(i32::from_le_bytes(slot.try_into()?) as i64),
+        Tag::Int64 => Value::Integer(i64::from_le_bytes(slot.try_into()?)),
+        Tag::Float => Value::Float(f64::from_le_bytes(slot.try_into()?)),
+        Tag::Date => {
+            let days = i32::from_le_bytes(slot.try_into()?);
+            Duration::try_days(days as i64)
+                .and_then(|d| DateTime::UNIX_EPOCH.date_naive().checked_add_signed(d))
+                .map(Value::Date)
+                .ok_or_else(|| invalid("date"))?
+        }
+        Tag::Time => {
+            let micros = i64::from_le_bytes(slot.try_into()?);
+            u32::try_from(micros.div_euclid(1_000_000))
+                .ok()
+                .and_then(|secs| {
+                    NaiveTime::from_num_seconds_from_midnight_opt(
+                        secs,
+                        micros.rem_euclid(1_000_000) as u32 * 1000,
+                    )
+                })
+                .map(Value::Time)
+                .ok_or_else(|| invalid("time"))?
+        }
+        Tag::Timestamp => DateTime::from_timestamp_micros(i64::from_le_bytes(slot.try_into()?))
+            .map(|ts| Value::Timestamp(ts.naive_utc()))
+            .ok_or_else(|| invalid("timestamp"))?,
+        Tag::Decimal => Value::Decimal(Decimal::deserialize(slot.try_into()?)),
+        Tag::Uuid => Value::Uuid(Uuid::from_bytes(slot.try_into()?)),
+        Tag::Interval => Value::Interval(Interval {
+            months: i32::from_le_bytes(slot[0..4].try_into()?),
+            days: i32::from_le_bytes(slot[4..8].try_into()?),
+            micros: i64::from_le_bytes(slot[8..16].try_into()?),
+        }),
+        Tag::String => {
+            Value::String(String::from_utf8(var.to_vec()).map_err(|_| invalid("string"))?)
+        }
+        Tag::Bytes => Value::Bytes(var.to_vec()),
+        Tag::Json => Value::Json(serde_json::from_slice(var).map_err(|_| invalid("JSON"))?),
+        Tag::Array => Value::Array(decode(var, None)?),
+    })
+}
+
+/// Returns the number of days since the Unix epoch for a date.
+fn days_since_epoch(date: NaiveDate) -> Result<i32> {
+    i32::try_from((date - DateTime::UNIX_EPOCH.date_naive()).num_days())
+        .map_err(|_| Error::Value(format!("Date {} out of range", date)))
+}
+
+/// Returns the number of microseconds since midnight for a time.
+fn micros_since_midnight(time: NaiveTime) -> i64 {
+    time.num_seconds_from_midnight() as i64 * 1_000_000 + time.nanosecond() as i64 / 1000
+}
+
+/// Decodes a row written with Bincode by earlier versions.
+fn decode_bincode(bytes: &[u8], columns: Option<&[usize]>) -> Result<Row> {
+    match columns {
+        Some(columns) => bincode::deserialize_seed(bytes, RowSeed(columns)),
+        None => bincode::deserialize(bytes),
+    }
+}
+
+/// Deserializes a Bincode row, skipping the values of columns not in the given
+/// sorted column list.
+struct RowSeed<'a>(&'a [usize]);
+
+impl<'de> DeserializeSeed<'de> for RowSeed<'_> {
+    type Value = Row;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Row, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for RowSeed<'_> {
+    type Value = Row;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a row")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Row, A::Error> {
+        let mut row = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        loop {
+            let value = match self.0.binary_search(&row.len()) {
+                Ok(_) => seq.next_element::<Value>()?,
+                Err(_) => seq.next_element::<SkippedValue>()?.map(|_| Value::Null),
+            };
+            match value {
+                Some(value) => row.push(value),
+                None => return Ok(row),
+            }
+        }
+    }
+}
+
+/// A Bincode-serialized value which is skipped rather than decoded: strings,
+/// JSON documents and byte values are borrowed from the input without
+/// allocating, validating or parsing them. The variants must match those of
+/// Value.
+#[derive(Deserialize)]
+#[allow(dead_code)]
+enum SkippedValue<'a> {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(&'a [u8]),
+    Date(i64),
+    Time(i64),
+    Timestamp(i64),
+    Decimal(&'a [u8]),
+    Bytes(&'a [u8]),
+    Json(&'a [u8]),
+    Uuid(&'a [u8]),
+    #[serde(borrow)]
+    Array(Vec<SkippedValue<'a>>),
+    Interval(i64, i64, i64),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+    use std::str::FromStr as _;
+
+    fn row() -> Row {
+        vec![
+            Value::Integer(-7),
+            Value::Null,
+            Value::String("foo".into()),
+            Value::Boolean(true),
+            Value::Float(2.5),
+            Value::Bytes(vec![0x00, 0xff]),
+            Value::Date(NaiveDate::from_ymd_opt(1969, 7, 20).unwrap()),
+            Value::Time(NaiveTime::from_hms_micro_opt(20, 17, 40, 123456).unwrap()),
+            Value::Timestamp(
+                NaiveDateTime::parse_from_str("2024-01-02 03:04:05.6", "%Y-%m-%d %H:%M:%S%.f")
+                    .unwrap(),
+            ),
+            Value::Decimal(Decimal::from_str("-12.3400").unwrap()),
+            Value::Json(serde_json::json!({"a": [1, null]})),
+            Value::Uuid(Uuid::from_u128(0x0123_4567_89ab_cdef)),
+            Value::String("".into()),
+            Value::Null,
+            Value::Boolean(false),
+            Value::Integer(1000),
+            Value::Integer(-100_000),
+            Value::Integer(i64::MIN),
+            Value::Array(vec![Value::Integer(1), Value::Null, Value::String("a".into())]),
+            Value::Interval(Interval { months: -1, days: 2, micros: 3 }),
+        ]
+    }
+
+    #[test]
+    fn roundtrip() -> Result<()> {
+        let row = row();
+        assert_eq!(decode(&encode(&row)?, None)?, row);
+        assert_eq!(decode(&encode(&[])?, None)?, Vec::<Value>::new());
+
+        // Long tails use wider offsets.
+        for len in [300, 70_000] {
+            let mut row = row.clone();
+            row.push(Value::String("x".repeat(len)));
+            row.push(Value::Bytes(vec![1; 3]));
+            assert_eq!(decode(&encode(&row)?, None)?, row);
+        }
+
+        // Wide rows use a longer column count.
+        let row: Row = (0..300).map(Value::Integer).collect();
+        assert_eq!(decode(&encode(&row)?, None)?, row);
+        Ok(())
+    }
+
+    #[test]
+    fn encode_layout() -> Result<()> {
+        let row = vec![Value::Integer(-2), Value::Null, Value::String("ab".into())];
+        assert_eq!(
+            hex::encode(encode(&row)?),
+            concat!("ff11", "03", "02", "0308", "fe", "02", "6162")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_columns() -> Result<()> {
+        let row = row();
+        let columns = [2, 5, 10, 12, 18, 19];
+        let expect: Row = row
+            .iter()
+            .enumerate()
+            .map(|(i, v)| if columns.contains(&i) { v.clone() } else { Value::Null })
+            .collect();
+        assert_eq!(decode(&encode(&row)?, Some(&columns))?, expect);
+        assert_eq!(decode(&encode(&row)?, Some(&[]))?, vec![Value::Null; row.len()]);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_bincode() -> Result<()> {
+        let row = row();
+        let bytes = bincode::serialize(&row)?;
+        assert_eq!(decode(&bytes, None)?, row);
+        assert_eq!(
+            decode(&bytes, Some(&[0]))?[..3],
+            [Value::Integer(-7), Value::Null, Value::Null]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_invalid() -> Result<()> {
+        let bytes = encode(&row())?;
+        assert!(decode(&bytes[..bytes.len() - 1], None).is_err());
+        assert!(decode(&[HEADER, 0x21, 0], None).is_err());
+        assert!(decode(&[HEADER, 0x11, 1, 0, 99], None).is_err());
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
+use crate::raft::NodeID;
+use crate::sql::parser::Span;
+
 use serde_derive::{Deserialize, Serialize};
 use std::fmt::{self, Display};
 
@@ -10,9 +13,33 @@ pub enum Error {
     Abort,
     Config(String),
     Internal(String),
+    /// The request was submitted to a Raft follower which does not forward
+    /// requests. The client should reconnect to the leader at the given SQL
+    /// address, if known, and retry.
+    NotLeader {
+        leader_id: NodeID,
+        address: Option<String>,
+    },
+    /// A query's buffered rows exceeded the session's query_memory budget,
+    /// and it was aborted.
+    OutOfMemory,
     Parse(String),
+    /// The session's user lacks the privilege required by a statement.
+    PermissionDenied(String),
     ReadOnly,
     Serialization,
+    /// An SQL syntax error at the given span of the input, near the given
+    /// input text (empty at the end of input).
+    Syntax {
+        message: String,
+        span: Span,
+        near: String,
+    },
+    /// A statement exceeded the session's statement_timeout, and was aborted.
+    Timeout,
+    /// The client didn't authenticate, or gave an invalid user name or
+    /// password.
+    Unauthenticated,
     Value(String),
 }
 
@@ -25,8 +52,26 @@ impl Display for Error {
                 write!(f, "{}", s)
             }
             Error::Abort => write!(f, "Operation aborted"),
+            Error::NotLeader { leader_id, address: Some(address) } => {
+                write!(f, "Not leader, leader is node {leader_id} at {address}")
+            }
+            Error::NotLeader { leader_id, address: None } => {
+                write!(f, "Not leader, leader is node {leader_id}")
+            }
             Error::Serialization => write!(f, "Serialization failure, retry transaction"),
+            Error::Syntax { message, span, near } if near.is_empty() => {
+                write!(f, "Syntax error at line {}, column {}: {}", span.line, span.column, message)
+            }
+            Error::Syntax { message, span, near } => write!(
+                f,
+                "Syntax error at line {}, column {} near '{}': {}",
+                span.line, span.column, near, message
+            ),
+            Error::OutOfMemory => write!(f, "Memory budget exceeded"),
             Error::ReadOnly => write!(f, "Read-only transaction"),
+            Error::Timeout => write!(f, "Statement timeout exceeded"),
+            Error::Unauthenticated => write!(f, "Authentication failed"),
+            Error::PermissionDenied(s) => write!(f, "Permission denied: {}", s),
         }
     }
 }
@@ -115,6 +160,12 @@ impl From<regex::Error> for Error {
     }
 }
 
+impl From<rustls::Error> for Error {
+    fn from(err: rustls::Error) -> Self {
+        Error::Internal(err.to_string())
+    }
+}
+
 impl From<rustyline::error::ReadlineError> for Error {
     fn from(err: rustyline::error::ReadlineError) -> Self {
         Error::Internal(err.to_string())
@@ -9,6 +9,7 @@ pub mod raft;
 pub mod server;
 pub mod sql;
 pub mod storage;
+pub mod tls;
 
 pub use client::Client;
 pub use server::Server;
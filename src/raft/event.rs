@@ -0,0 +1,70 @@
+use super::{Index, NodeID, Term, Ticks};
+
+/// A Raft node event, emitted to an Observer as the node's state changes. This
+/// allows callers to log, export metrics, or trigger automation without
+/// coupling to the node internals.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The node moved into a new term.
+    TermChange { from: Term, to: Term },
+    /// The node changed role, or followed a different leader.
+    RoleChange { term: Term, from: NodeRole, to: NodeRole },
+    /// The node's commit index advanced.
+    CommitAdvanced { from: Index, to: Index },
+    /// The leader hasn't heard from a peer in the given number of ticks.
+    PeerUnreachable { peer: NodeID, ticks: Ticks },
+    /// The leader heard from a previously unreachable peer again.
+    PeerReachable { peer: NodeID },
+    /// A node was added to the cluster, with the given Raft address.
+    PeerAdded { peer: NodeID, address: String },
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TermChange { from, to } => write!(f, "TermChange {from} → {to}"),
+            Self::RoleChange { term, from, to } => {
+                write!(f, "RoleChange {from} → {to} in term {term}")
+            }
+            Self::CommitAdvanced { from, to } => write!(f, "CommitAdvanced {from} → {to}"),
+            Self::PeerUnreachable { peer, ticks } => {
+                write!(f, "PeerUnreachable n{peer} after {ticks} ticks")
+            }
+            Self::PeerReachable { peer } => write!(f, "PeerReachable n{peer}"),
+            Self::PeerAdded { peer, address } => write!(f, "PeerAdded n{peer} at {address}"),
+        }
+    }
+}
+
+/// A node role, as reported in events.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeRole {
+    Candidate,
+    Follower { leader: Option<NodeID> },
+    Leader,
+}
+
+impl std::fmt::Display for NodeRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Candidate => write!(f, "candidate"),
+            Self::Follower { leader: Some(leader) } => write!(f, "follower(n{leader})"),
+            Self::Follower { leader: None } => write!(f, "follower()"),
+            Self::Leader => write!(f, "leader"),
+        }
+    }
+}
+
+/// Observes Raft node events. Called synchronously by the node, so
+/// implementations must be cheap and must not block.
+pub trait Observer: Send {
+    /// Handles a node event.
+    fn observe(&mut self, event: Event);
+}
+
+/// Forwards events to a channel. Events are dropped if the receiver is gone.
+impl Observer for crossbeam::channel::Sender<Event> {
+    fn observe(&mut self, event: Event) {
+        self.send(event).ok();
+    }
+}
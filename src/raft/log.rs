@@ -17,8 +17,20 @@ pub struct Entry {
     pub index: Index,
     /// The term in which the entry was added.
     pub term: Term,
-    /// The state machine command. None is used to commit noops during leader election.
-    pub command: Option<Vec<u8>>,
+    /// The entry command.
+    pub command: Command,
+}
+
+/// A log entry command.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    /// A noop, committed by new leaders to disambiguate previous entries.
+    Noop,
+    /// A state machine command.
+    Write(Vec<u8>),
+    /// Adds a node to the cluster, with the given Raft address. Takes effect
+    /// when applied.
+    AddNode { id: NodeID, address: String },
 }
 
 /// A log key, encoded using KeyCode.
@@ -155,9 +167,8 @@ impl Log {
         Ok(())
     }
 
-    /// Appends a command to the log, returning its index. None implies a noop
-    /// command, typically after Raft leader changes.
-    pub fn append(&mut self, term: Term, command: Option<Vec<u8>>) -> Result<Index> {
+    /// Appends a command to the log, returning its index.
+    pub fn append(&mut self, term: Term, command: Command) -> Result<Index> {
         let index = self.last_index + 1;
         self.engine.set(&Key::Entry(index).encode()?, bincode::serialize(&(term, command))?)?;
         self.maybe_flush()?;
@@ -229,7 +240,9 @@ impl Log {
     /// Splices a set of entries into the log. The entries must be contiguous,
     /// and the first entry must be at most last_index+1. If an entry does not
     /// exist, append it. If an existing entry has a term mismatch, replace it
-    /// and all following entries.
+    /// and all following entries. If all entries already exist, the log is left
+    /// as is, since they may come from a stale message and the following
+    /// entries may already have been acknowledged to the leader.
     pub fn splice(&mut self, entries: Vec<Entry>) -> Result<Index> {
         if entries.is_empty() {
             return Ok(self.last_index);
@@ -240,8 +253,6 @@ impl Log {
         if !entries.windows(2).all(|w| w[0].index + 1 == w[1].index) {
             return Err(Error::Internal("Spliced entries must be contiguous".into()));
         }
-        let (last_index, last_term) = entries.last().map(|e| (e.index, e.term)).unwrap();
-
         // Skip entries that are already in the log (identified by index and term).
         let mut entries = entries.as_slice();
         let mut scan = self.scan(entries[0].index..=entries.last().unwrap().index)?;
@@ -253,7 +264,10 @@ impl Log {
         }
         drop(scan);
 
-        if !entries.is_empty() && entries[0].index <= self.commit_index {
+        if entries.is_empty() {
+            return Ok(self.last_index);
+        }
+        if entries[0].index <= self.commit_index {
             return Err(Error::Internal("Spliced entries must begin after commit index".into()));
         }
 
@@ -264,6 +278,7 @@ impl Log {
         }
 
         // Remove the remaining tail of the old log, if any, and update the index.
+        let (last_index, last_term) = entries.last().map(|e| (e.index, e.term)).unwrap();
         for index in (last_index + 1)..=self.last_index {
             self.engine.delete(&Key::Entry(index).encode()?)?;
         }
@@ -298,15 +313,18 @@ mod tests {
         let mut l = setup();
         assert_eq!(l.get(1), Ok(None));
 
-        assert_eq!(l.append(3, Some(vec![0x01]))?, 1,);
-        assert_eq!(l.get(1)?, Some(Entry { index: 1, term: 3, command: Some(vec![0x01]) }));
+        assert_eq!(l.append(3, Command::Write(vec![0x01]))?, 1,);
+        assert_eq!(
+            l.get(1)?,
+            Some(Entry { index: 1, term: 3, command: Command::Write(vec![0x01]) })
+        );
         assert_eq!(l.get(2)?, None);
 
         assert_eq!(l.get_last_index(), (1, 3));
         assert_eq!(l.get_commit_index(), (0, 0));
 
-        assert_eq!(l.append(3, None)?, 2);
-        assert_eq!(l.get(2)?, Some(Entry { index: 2, term: 3, command: None }));
+        assert_eq!(l.append(3, Command::Noop)?, 2);
+        assert_eq!(l.get(2)?, Some(Entry { index: 2, term: 3, command: Command::Noop }));
         assert_eq!(l.get_last_index(), (2, 3));
         assert_eq!(l.get_commit_index(), (0, 0));
         Ok(())
@@ -315,9 +333,9 @@ mod tests {
     #[test]
     fn commit() -> Result<()> {
         let mut l = setup();
-        l.append(1, Some(vec![0x01]))?;
-        l.append(2, None)?;
-        l.append(2, Some(vec![0x03]))?;
+        l.append(1, Command::Write(vec![0x01]))?;
+        l.append(2, Command::Noop)?;
+        l.append(2, Command::Write(vec![0x03]))?;
 
         // Committing a missing entry should error.
         assert_eq!(
@@ -350,8 +368,11 @@ mod tests {
         let mut l = setup();
         assert_eq!(l.get(1)?, None);
 
-        l.append(3, Some(vec![0x01]))?;
-        assert_eq!(l.get(1)?, Some(Entry { index: 1, term: 3, command: Some(vec![0x01]) }));
+        l.append(3, Command::Write(vec![0x01]))?;
+        assert_eq!(
+            l.get(1)?,
+            Some(Entry { index: 1, term: 3, command: Command::Write(vec![0x01]) })
+        );
         assert_eq!(l.get(2)?, None);
         Ok(())
     }
@@ -359,7 +380,7 @@ mod tests {
     #[test]
     fn has() -> Result<()> {
         let mut l = setup();
-        l.append(2, Some(vec![0x01]))?;
+        l.append(2, Command::Write(vec![0x01]))?;
 
         assert!(l.has(1, 2)?);
         assert!(l.has(0, 0)?);
@@ -374,21 +395,21 @@ mod tests {
     #[test]
     fn scan() -> Result<()> {
         let mut l = setup();
-        l.append(1, Some(vec![0x01]))?;
-        l.append(1, Some(vec![0x02]))?;
-        l.append(1, Some(vec![0x03]))?;
+        l.append(1, Command::Write(vec![0x01]))?;
+        l.append(1, Command::Write(vec![0x02]))?;
+        l.append(1, Command::Write(vec![0x03]))?;
 
         assert_eq!(
             l.scan(..)?.collect::<Result<Vec<_>>>()?,
             vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
             ],
         );
         assert_eq!(
             l.scan(2..=2)?.collect::<Result<Vec<_>>>()?,
-            vec![Entry { index: 2, term: 1, command: Some(vec![0x02]) },],
+            vec![Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },],
         );
         assert!(l.scan(4..)?.collect::<Result<Vec<_>>>()?.is_empty());
         Ok(())
@@ -415,30 +436,30 @@ mod tests {
         assert_eq!(l.splice(vec![])?, 0);
 
         // It should error if the first index is not 1.
-        assert!(l.splice(vec![Entry { index: 0, term: 1, command: None }]).is_err());
-        assert!(l.splice(vec![Entry { index: 2, term: 1, command: None }]).is_err());
+        assert!(l.splice(vec![Entry { index: 0, term: 1, command: Command::Noop }]).is_err());
+        assert!(l.splice(vec![Entry { index: 2, term: 1, command: Command::Noop }]).is_err());
 
         // ...or the entries are not contiguous.
         assert!(l
             .splice(vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 3, term: 2, command: Some(vec![0x03]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 3, term: 2, command: Command::Write(vec![0x03]) },
             ])
             .is_err());
 
         // Splicing into an empty log should be fine.
         assert_eq!(
             l.splice(vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
             ])?,
             2
         );
         assert_eq!(
             l.scan(..)?.collect::<Result<Vec<_>>>()?,
             vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
             ]
         );
 
@@ -448,153 +469,163 @@ mod tests {
         assert_eq!(
             l.scan(..)?.collect::<Result<Vec<_>>>()?,
             vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
             ]
         );
 
         // Splicing with a gap after the last_index should error.
         assert!(l
             .splice(vec![
-                Entry { index: 4, term: 1, command: Some(vec![0x04]) },
-                Entry { index: 5, term: 1, command: Some(vec![0x05]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
+                Entry { index: 5, term: 1, command: Command::Write(vec![0x05]) },
             ])
             .is_err());
 
         // Splicing after the last index should be fine.
         assert_eq!(
             l.splice(vec![
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
-                Entry { index: 4, term: 1, command: Some(vec![0x04]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
             ])?,
             4
         );
         assert_eq!(
             l.scan(..)?.collect::<Result<Vec<_>>>()?,
             vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
-                Entry { index: 4, term: 1, command: Some(vec![0x04]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
             ]
         );
 
         // Splicing with overlap should be a noop.
         assert_eq!(
             l.splice(vec![
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
-                Entry { index: 4, term: 1, command: Some(vec![0x04]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
             ])?,
             4
         );
         assert_eq!(
             l.scan(..)?.collect::<Result<Vec<_>>>()?,
             vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
-                Entry { index: 4, term: 1, command: Some(vec![0x04]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
             ]
         );
 
         assert_eq!(
             l.splice(vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
-                Entry { index: 4, term: 1, command: Some(vec![0x04]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
             ])?,
             4
         );
         assert_eq!(
             l.scan(..)?.collect::<Result<Vec<_>>>()?,
             vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
-                Entry { index: 4, term: 1, command: Some(vec![0x04]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
             ]
         );
 
-        // Splicing in the middle should truncate the rest, even if the
-        // entries match.
+        // Splicing matching entries in the middle or at the start should not
+        // truncate the rest, since it may be a stale message.
         assert_eq!(
             l.splice(vec![
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
             ])?,
-            3
-        );
-        assert_eq!(
-            l.scan(..)?.collect::<Result<Vec<_>>>()?,
-            vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
-            ]
+            4
         );
-
-        // Splicing at the start should truncate the rest, even if the
-        // entries match.
         assert_eq!(
             l.splice(vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
             ])?,
-            2
+            4
         );
         assert_eq!(
             l.scan(..)?.collect::<Result<Vec<_>>>()?,
             vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
             ]
         );
 
         // Splicing a different command does nothing.
-        assert_eq!(l.splice(vec![Entry { index: 2, term: 1, command: Some(vec![0x00]) },])?, 2);
+        assert_eq!(
+            l.splice(vec![Entry { index: 2, term: 1, command: Command::Write(vec![0x00]) },])?,
+            4
+        );
         assert_eq!(
             l.scan(..)?.collect::<Result<Vec<_>>>()?,
             vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
             ]
         );
 
         // Splicing with overlap beyond the end works.
         assert_eq!(
             l.splice(vec![
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
-                Entry { index: 4, term: 1, command: Some(vec![0x04]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
             ])?,
             4
         );
         assert_eq!(
             l.scan(..)?.collect::<Result<Vec<_>>>()?,
             vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
-                Entry { index: 3, term: 1, command: Some(vec![0x03]) },
-                Entry { index: 4, term: 1, command: Some(vec![0x04]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 1, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 1, command: Command::Write(vec![0x04]) },
             ]
         );
 
         // Splicing with a different term replaces.
         assert_eq!(
             l.splice(vec![
-                Entry { index: 3, term: 2, command: Some(vec![0x03]) },
-                Entry { index: 4, term: 2, command: Some(vec![0x04]) },
+                Entry { index: 3, term: 2, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 2, command: Command::Write(vec![0x04]) },
             ])?,
             4
         );
         assert_eq!(
             l.scan(..)?.collect::<Result<Vec<_>>>()?,
             vec![
-                Entry { index: 1, term: 1, command: Some(vec![0x01]) },
-                Entry { index: 2, term: 1, command: Some(vec![0x02]) },
-                Entry { index: 3, term: 2, command: Some(vec![0x03]) },
-                Entry { index: 4, term: 2, command: Some(vec![0x04]) },
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 2, command: Command::Write(vec![0x03]) },
+                Entry { index: 4, term: 2, command: Command::Write(vec![0x04]) },
+            ]
+        );
+
+        // Splicing a term mismatch in the middle truncates the rest.
+        assert_eq!(
+            l.splice(vec![Entry { index: 3, term: 3, command: Command::Write(vec![0x03]) }])?,
+            3
+        );
+        assert_eq!(
+            l.scan(..)?.collect::<Result<Vec<_>>>()?,
+            vec![
+                Entry { index: 1, term: 1, command: Command::Write(vec![0x01]) },
+                Entry { index: 2, term: 1, command: Command::Write(vec![0x02]) },
+                Entry { index: 3, term: 3, command: Command::Write(vec![0x03]) },
             ]
         );
 
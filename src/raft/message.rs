@@ -70,12 +70,19 @@ pub enum Message {
     AppendResponse {
         /// If true, the follower rejected the leader's entries.
         reject: bool,
-        /// The index of the follower's last log entry.
+        /// The index of the last appended entry, or the follower's last log
+        /// entry if rejected.
         last_index: Index,
-        /// The term of the follower's last log entry.
+        /// The term of the last appended entry, or the follower's last log
+        /// entry if rejected.
         last_term: Term,
     },
 
+    /// Leaders transfer leadership to a follower by asking it to campaign
+    /// immediately, without waiting for its election timeout. Used to move
+    /// leadership to a preferred (higher-priority) node.
+    TimeoutNow,
+
     /// A client request. This can be submitted to the leader, or to a follower
     /// which will forward it to its leader. If there is no leader, or the
     /// leader or term changes, the request is aborted with an Error::Abort
@@ -114,6 +121,11 @@ pub enum Request {
     Write(Vec<u8>),
     /// Requests Raft cluster status from the leader.
     Status,
+    /// Adds a node with the given ID and Raft address to the cluster. The
+    /// cluster must have been formed via AddNode entries rather than static
+    /// peers. The leader replicates its entire log to the new node, starting
+    /// at index 1, since there are no snapshots.
+    AddNode { id: NodeID, address: String },
 }
 
 /// A client response. This will be wrapped in a Result to handle errors.
@@ -129,6 +141,9 @@ pub enum Response {
     Write(Vec<u8>),
     /// The current Raft leader status.
     Status(Status),
+    /// The node was added to the cluster. Contains the Raft addresses of all
+    /// cluster members, including the new node, which it must use as peers.
+    AddNode(BTreeMap<NodeID, String>),
 }
 
 /// Raft cluster status.
@@ -1,11 +1,15 @@
+mod event;
 mod log;
 mod message;
 mod node;
+#[cfg(test)]
+mod simulation;
 mod state;
 
-pub use log::{Entry, Index, Log};
+pub use event::{Event, NodeRole, Observer};
+pub use log::{Command, Entry, Index, Log};
 pub use message::{Envelope, Message, ReadSequence, Request, RequestID, Response, Status};
-pub use node::{Node, NodeID, Term, Ticks};
+pub use node::{Node, NodeID, Options, Term, Ticks};
 pub use state::State;
 
 /// The interval between Raft ticks. This is the unit of time for heartbeats and
@@ -1,12 +1,13 @@
 use super::{
-    Envelope, Index, Log, Message, ReadSequence, Request, RequestID, Response, State, Status,
+    Command, Envelope, Event, Index, Log, Message, NodeRole, Observer, ReadSequence, Request,
+    RequestID, Response, State, Status, ELECTION_TIMEOUT_RANGE, HEARTBEAT_INTERVAL,
 };
 use crate::error::{Error, Result};
 
 use itertools::Itertools as _;
 use log::{debug, info};
 use rand::Rng as _;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
 /// A node ID.
 pub type NodeID = u8;
@@ -17,10 +18,39 @@ pub type Term = u64;
 /// A logical clock interval as number of ticks.
 pub type Ticks = u8;
 
+/// Raft node options.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    /// The number of ticks between leader heartbeats.
+    pub heartbeat_interval: Ticks,
+    /// The range of randomized election timeouts, in ticks.
+    pub election_timeout_range: std::ops::Range<Ticks>,
+    /// If true, followers forward client requests to the leader. Otherwise,
+    /// they're rejected with Error::NotLeader, and the client must resubmit
+    /// them to the leader.
+    pub forward_requests: bool,
+    /// Election priorities by node ID (0 if unset), which must be the same on
+    /// all nodes. Higher-priority nodes use shorter election timeouts, and the
+    /// leader transfers leadership to the highest-priority healthy node.
+    pub priorities: HashMap<NodeID, u8>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: HEARTBEAT_INTERVAL,
+            election_timeout_range: ELECTION_TIMEOUT_RANGE,
+            forward_requests: true,
+            priorities: HashMap::new(),
+        }
+    }
+}
+
 /// A Raft node, with a dynamic role. The node is driven synchronously by
 /// processing inbound messages via step() or by advancing time via tick().
 /// These methods consume the current node, and return a new one with a possibly
-/// different role. Outbound messages are sent via the given node_tx channel.
+/// different role. Outbound messages are sent via the given node_tx channel,
+/// and state changes are reported to the given observer as events.
 ///
 /// This enum wraps the RawNode<Role> types, which implement the actual
 /// node logic. It exists for ergonomic use across role transitions, i.e
@@ -33,28 +63,37 @@ pub enum Node {
 
 impl Node {
     /// Creates a new Raft node, starting as a leaderless follower, or leader if
-    /// there are no peers.
+    /// there are no peers. Nodes that were added to the cluster in the
+    /// committed log are added to the given peers. A node joining a cluster
+    /// must be given the cluster members from the AddNode response as peers.
     pub fn new(
         id: NodeID,
         peers: HashSet<NodeID>,
         log: Log,
         state: Box<dyn State>,
         node_tx: crossbeam::channel::Sender<Envelope>,
-        heartbeat_interval: Ticks,
-        election_timeout_range: std::ops::Range<Ticks>,
+        observer: Box<dyn Observer>,
+        opts: Options,
     ) -> Result<Self> {
-        let node = RawNode::new(
-            id,
-            peers,
-            log,
-            state,
-            node_tx,
-            heartbeat_interval,
-            election_timeout_range,
-        )?;
+        let mut node = RawNode::new(id, peers, log, state, node_tx, observer, opts)?;
+
+        let commit_index = node.log.get_commit_index().0;
+        let mut added = Vec::new();
+        let mut scan = node.log.scan(..=commit_index)?;
+        while let Some(entry) = scan.next().transpose()? {
+            if let Command::AddNode { id, address } = entry.command {
+                added.push((id, address));
+            }
+        }
+        drop(scan);
+        for (id, address) in added {
+            node.add_node(id, address);
+        }
+
         if node.peers.is_empty() {
             // If there are no peers, become leader immediately.
-            return Ok(node.into_candidate()?.into_leader()?.into());
+            let observed = node.observed();
+            return Ok(Node::from(node.into_candidate()?.into_leader()?).notify(observed));
         }
         Ok(node.into())
     }
@@ -77,24 +116,86 @@ impl Node {
         }
     }
 
+    /// Returns the node role.
+    pub fn role(&self) -> NodeRole {
+        match self {
+            Node::Candidate(n) => n.role.node_role(),
+            Node::Follower(n) => n.role.node_role(),
+            Node::Leader(n) => n.role.node_role(),
+        }
+    }
+
+    /// Returns the node's log, for inspection by tests.
+    #[cfg(test)]
+    pub(super) fn log_mut(&mut self) -> &mut Log {
+        match self {
+            Node::Candidate(n) => &mut n.log,
+            Node::Follower(n) => &mut n.log,
+            Node::Leader(n) => &mut n.log,
+        }
+    }
+
     /// Processes a message from a peer.
     pub fn step(self, msg: Envelope) -> Result<Self> {
         debug!("Stepping {:?}", msg);
-        match self {
+        let observed = self.observed();
+        let node = match self {
             Node::Candidate(n) => n.step(msg),
             Node::Follower(n) => n.step(msg),
             Node::Leader(n) => n.step(msg),
-        }
+        }?;
+        Ok(node.notify(observed))
     }
 
     /// Moves time forward by a tick.
     pub fn tick(self) -> Result<Self> {
-        match self {
+        let observed = self.observed();
+        let node = match self {
             Node::Candidate(n) => n.tick(),
             Node::Follower(n) => n.tick(),
             Node::Leader(n) => n.tick(),
+        }?;
+        Ok(node.notify(observed))
+    }
+
+    /// Returns the observable node state.
+    fn observed(&self) -> Observed {
+        match self {
+            Node::Candidate(n) => n.observed(),
+            Node::Follower(n) => n.observed(),
+            Node::Leader(n) => n.observed(),
         }
     }
+
+    /// Emits events for any changes since the given observed state.
+    fn notify(mut self, before: Observed) -> Self {
+        let after = self.observed();
+        let observer = match &mut self {
+            Node::Candidate(n) => &mut n.observer,
+            Node::Follower(n) => &mut n.observer,
+            Node::Leader(n) => &mut n.observer,
+        };
+        if after.term != before.term {
+            observer.observe(Event::TermChange { from: before.term, to: after.term });
+        }
+        if after.role != before.role {
+            let (term, from, to) = (after.term, before.role, after.role);
+            observer.observe(Event::RoleChange { term, from, to });
+        }
+        if after.commit_index > before.commit_index {
+            let (from, to) = (before.commit_index, after.commit_index);
+            observer.observe(Event::CommitAdvanced { from, to });
+        }
+        self
+    }
+}
+
+/// Observable node state. Node transitions compare this before and after the
+/// transition, and emit events for any changes.
+struct Observed {
+    term: Term,
+    role: NodeRole,
+    commit_index: Index,
 }
 
 impl From<RawNode<Candidate>> for Node {
@@ -116,7 +217,10 @@ impl From<RawNode<Leader>> for Node {
 }
 
 /// A Raft role: leader, follower, or candidate.
-pub trait Role: Clone + std::fmt::Debug + PartialEq {}
+pub trait Role: Clone + std::fmt::Debug + PartialEq {
+    /// Returns the role as reported in events.
+    fn node_role(&self) -> NodeRole;
+}
 
 /// A Raft node with the concrete role R.
 ///
@@ -125,12 +229,16 @@ pub trait Role: Clone + std::fmt::Debug + PartialEq {}
 pub struct RawNode<R: Role = Follower> {
     id: NodeID,
     peers: HashSet<NodeID>,
+    /// The Raft addresses of cluster members that were added via AddNode log
+    /// entries, possibly including the local node. Statically configured
+    /// peers aren't included, and nodes can't be added to such clusters.
+    addresses: BTreeMap<NodeID, String>,
     term: Term,
     log: Log,
     state: Box<dyn State>,
     node_tx: crossbeam::channel::Sender<Envelope>,
-    heartbeat_interval: Ticks,
-    election_timeout_range: std::ops::Range<Ticks>,
+    observer: Box<dyn Observer>,
+    opts: Options,
     role: R,
 }
 
@@ -140,45 +248,93 @@ impl<R: Role> RawNode<R> {
         RawNode {
             id: self.id,
             peers: self.peers,
+            addresses: self.addresses,
             term: self.term,
             log: self.log,
             state: self.state,
             node_tx: self.node_tx,
-            heartbeat_interval: self.heartbeat_interval,
-            election_timeout_range: self.election_timeout_range,
+            observer: self.observer,
+            opts: self.opts,
             role,
         }
     }
 
-    /// Applies any pending, committed entries to the state machine. The command
-    /// responses are discarded, use maybe_apply_with() instead to access them.
+    /// Returns the observable node state.
+    fn observed(&self) -> Observed {
+        let commit_index = self.log.get_commit_index().0;
+        Observed { term: self.term, role: self.role.node_role(), commit_index }
+    }
+
+    /// Applies any pending, committed entries to the state machine, and adds
+    /// any new nodes to the cluster. The command responses are discarded, use
+    /// maybe_apply_with() instead to access them.
     fn maybe_apply(&mut self) -> Result<()> {
-        Self::maybe_apply_with(&mut self.log, &mut self.state, |_, _| Ok(()))
+        let added =
+            Self::maybe_apply_with(&mut self.log, &mut self.state, &mut self.addresses, |_, _| {
+                Ok(())
+            })?;
+        for (id, address) in added {
+            self.add_node(id, address);
+        }
+        Ok(())
     }
 
-    /// Like maybe_apply(), but calls the given closure with the result of every
-    /// applied command. Not a method, so that the closure can mutate the node.
-    fn maybe_apply_with<F>(log: &mut Log, state: &mut Box<dyn State>, mut on_apply: F) -> Result<()>
+    /// Like maybe_apply(), but calls the given closure with the response of
+    /// every applied command, and returns any added nodes without adding them
+    /// to the cluster. Their addresses are recorded though, such that AddNode
+    /// responses contain all cluster members. Not a method, so that the
+    /// closure can mutate the node.
+    fn maybe_apply_with<F>(
+        log: &mut Log,
+        state: &mut Box<dyn State>,
+        addresses: &mut BTreeMap<NodeID, String>,
+        mut on_apply: F,
+    ) -> Result<Vec<(NodeID, String)>>
     where
-        F: FnMut(Index, Result<Vec<u8>>) -> Result<()>,
+        F: FnMut(Index, Result<Response>) -> Result<()>,
     {
+        let mut added = Vec::new();
         let applied_index = state.get_applied_index();
         let commit_index = log.get_commit_index().0;
         assert!(commit_index >= applied_index, "Commit index below applied index");
         if applied_index >= commit_index {
-            return Ok(());
+            return Ok(added);
         }
 
         let mut scan = log.scan((applied_index + 1)..=commit_index)?;
         while let Some(entry) = scan.next().transpose()? {
             let index = entry.index;
             debug!("Applying {:?}", entry);
+            // Membership changes are applied to the state machine too, which
+            // treats them as noops, to keep track of the applied index.
+            let node = match &entry.command {
+                Command::AddNode { id, address } => Some((*id, address.clone())),
+                Command::Noop | Command::Write(_) => None,
+            };
             match state.apply(entry) {
                 Err(error @ Error::Internal(_)) => return Err(error),
-                result => on_apply(index, result)?,
+                result => match node {
+                    Some((id, address)) => {
+                        addresses.insert(id, address.clone());
+                        on_apply(index, result.map(|_| Response::AddNode(addresses.clone())))?;
+                        added.push((id, address));
+                    }
+                    None => on_apply(index, result.map(Response::Write))?,
+                },
             }
         }
-        Ok(())
+        Ok(added)
+    }
+
+    /// Adds a node to the cluster, returning false if it's already a member.
+    fn add_node(&mut self, id: NodeID, address: String) -> bool {
+        self.addresses.insert(id, address.clone());
+        if id == self.id || !self.peers.insert(id) {
+            return false;
+        }
+        info!("Added node {id} at {address} to the cluster");
+        self.observer.observe(Event::PeerAdded { peer: id, address });
+        true
     }
 
     /// Returns the size of the cluster.
@@ -214,9 +370,27 @@ impl<R: Role> RawNode<R> {
         Ok(())
     }
 
-    /// Generates a randomized election timeout.
+    /// Returns the election priority of the given node.
+    fn priority(&self, id: NodeID) -> u8 {
+        self.opts.priorities.get(&id).copied().unwrap_or(0)
+    }
+
+    /// Generates a randomized election timeout. Timeouts are staggered by
+    /// priority: every distinct priority higher than ours in the cluster delays
+    /// us by the width of the timeout range, such that higher-priority nodes
+    /// always time out (and campaign) first.
     fn gen_election_timeout(&self) -> Ticks {
-        rand::thread_rng().gen_range(self.election_timeout_range.clone())
+        let range = self.opts.election_timeout_range.clone();
+        let priority = self.priority(self.id);
+        let rank = self
+            .peers
+            .iter()
+            .map(|id| self.priority(*id))
+            .filter(|p| *p > priority)
+            .unique()
+            .count() as Ticks;
+        let stagger = rank * (range.end - range.start);
+        rand::thread_rng().gen_range(range) + stagger
     }
 
     /// Asserts common node invariants.
@@ -257,7 +431,11 @@ impl Candidate {
     }
 }
 
-impl Role for Candidate {}
+impl Role for Candidate {
+    fn node_role(&self) -> NodeRole {
+        NodeRole::Candidate
+    }
+}
 
 impl RawNode<Candidate> {
     /// Asserts internal invariants.
@@ -312,7 +490,7 @@ impl RawNode<Candidate> {
         //
         // We do this prior to the heartbeat, to avoid a wasted replication
         // roundtrip if the heartbeat response indicates the peer is behind.
-        node.propose(None)?;
+        node.propose(Command::Noop)?;
         node.heartbeat()?;
 
         Ok(node)
@@ -365,6 +543,9 @@ impl RawNode<Candidate> {
                 self.send(msg.from, Message::ClientResponse { id, response: Err(Error::Abort) })?;
             }
 
+            // We're already campaigning, ignore leadership transfers.
+            Message::TimeoutNow => {}
+
             // We're not a leader in this term, nor are we forwarding requests,
             // so we shouldn't see these.
             Message::HeartbeatResponse { .. }
@@ -424,7 +605,11 @@ impl Follower {
     }
 }
 
-impl Role for Follower {}
+impl Role for Follower {
+    fn node_role(&self) -> NodeRole {
+        NodeRole::Follower { leader: self.leader }
+    }
+}
 
 impl RawNode<Follower> {
     /// Creates a new node as a leaderless follower.
@@ -434,22 +619,14 @@ impl RawNode<Follower> {
         mut log: Log,
         state: Box<dyn State>,
         node_tx: crossbeam::channel::Sender<Envelope>,
-        heartbeat_interval: Ticks,
-        election_timeout_range: std::ops::Range<Ticks>,
+        observer: Box<dyn Observer>,
+        opts: Options,
     ) -> Result<Self> {
         let (term, voted_for) = log.get_term()?;
         let role = Follower::new(None, voted_for, 0);
-        let mut node = Self {
-            id,
-            peers,
-            term,
-            log,
-            state,
-            node_tx,
-            heartbeat_interval,
-            election_timeout_range,
-            role,
-        };
+        let addresses = BTreeMap::new();
+        let mut node =
+            Self { id, peers, addresses, term, log, state, node_tx, observer, opts, role };
         node.role.election_timeout = node.gen_election_timeout();
         Ok(node)
     }
@@ -579,12 +756,19 @@ impl RawNode<Follower> {
                     None => self = self.into_follower(Some(from), msg.term)?,
                 }
 
-                // Append the entries, if possible.
+                // Append the entries, if possible. If accepted, respond with
+                // the last appended entry rather than our last log entry: our
+                // log may have a stale tail beyond it that the leader hasn't
+                // verified yet, e.g. if the message was delayed.
                 let reject = base_index > 0 && !self.log.has(base_index, base_term)?;
+                let (last_index, last_term) = match entries.last() {
+                    _ if reject => self.log.get_last_index(),
+                    Some(entry) => (entry.index, entry.term),
+                    None => (base_index, base_term),
+                };
                 if !reject {
                     self.log.splice(entries)?;
                 }
-                let (last_index, last_term) = self.log.get_last_index();
                 self.send(msg.from, Message::AppendResponse { reject, last_index, last_term })?;
             }
 
@@ -616,16 +800,29 @@ impl RawNode<Follower> {
             // different leader. Ignore it.
             Message::CampaignResponse { .. } => {}
 
-            // Forward client requests to the leader, or abort them if there is
-            // none (the client must retry).
+            // The leader is transferring leadership to us. Campaign immediately.
+            Message::TimeoutNow => {
+                assert!(self.is_leader(msg.from), "Leadership transfer from non-leader");
+                info!("Leader {} transferring leadership to us", msg.from);
+                return Ok(self.into_candidate()?.into());
+            }
+
+            // Forward client requests to the leader, or reject them if we're
+            // not forwarding requests. Abort them if there is no leader (the
+            // client must retry).
             Message::ClientRequest { ref id, .. } => {
                 assert_eq!(msg.from, self.id, "Client request from other node");
 
                 let id = id.clone();
                 if let Some(leader) = self.role.leader {
-                    debug!("Forwarding request to leader {}: {:?}", leader, msg);
-                    self.role.forwarded.insert(id);
-                    self.send(leader, msg.message)?
+                    if self.opts.forward_requests {
+                        debug!("Forwarding request to leader {}: {:?}", leader, msg);
+                        self.role.forwarded.insert(id);
+                        self.send(leader, msg.message)?
+                    } else {
+                        let response = Err(Error::NotLeader { leader_id: leader, address: None });
+                        self.send(msg.from, Message::ClientResponse { id, response })?
+                    }
                 } else {
                     self.send(
                         msg.from,
@@ -690,6 +887,9 @@ struct Progress {
     last: Index,
     /// The last read sequence number confirmed by the peer.
     read_seq: ReadSequence,
+    /// The number of ticks since we last heard from the peer. Capped at the
+    /// maximum election timeout, at which point it's considered unreachable.
+    since_seen: Ticks,
 }
 
 /// A pending client write request.
@@ -747,8 +947,10 @@ impl Leader {
     /// Creates a new leader role.
     fn new(peers: HashSet<NodeID>, last_index: Index) -> Self {
         let next = last_index + 1;
-        let progress =
-            peers.into_iter().map(|p| (p, Progress { next, last: 0, read_seq: 0 })).collect();
+        let progress = peers
+            .into_iter()
+            .map(|p| (p, Progress { next, last: 0, read_seq: 0, since_seen: 0 }))
+            .collect();
         Self {
             progress,
             writes: HashMap::new(),
@@ -759,7 +961,11 @@ impl Leader {
     }
 }
 
-impl Role for Leader {}
+impl Role for Leader {
+    fn node_role(&self) -> NodeRole {
+        NodeRole::Leader
+    }
+}
 
 impl RawNode<Leader> {
     /// Asserts internal invariants.
@@ -822,6 +1028,14 @@ impl RawNode<Leader> {
             return self.into_follower(msg.term)?.step(msg);
         }
 
+        // Record that we heard from the peer (if any).
+        if let Some(progress) = self.role.progress.get_mut(&msg.from) {
+            if progress.since_seen >= self.opts.election_timeout_range.end {
+                self.observer.observe(Event::PeerReachable { peer: msg.from });
+            }
+            progress.since_seen = 0;
+        }
+
         match msg.message {
             // There can't be two leaders in the same term.
             Message::Heartbeat { .. } | Message::Append { .. } => {
@@ -909,13 +1123,48 @@ impl RawNode<Leader> {
             // A client submitted a write command. Propose it, and track it
             // until it's applied and the response is returned to the client.
             Message::ClientRequest { id, request: Request::Write(command) } => {
-                let index = self.propose(Some(command))?;
+                let index = self.propose(Command::Write(command))?;
                 self.role.writes.insert(index, Write { from: msg.from, id: id.clone() });
                 if self.peers.is_empty() {
                     self.maybe_commit_and_apply()?;
                 }
             }
 
+            // A client requested adding a node to the cluster. Only clusters
+            // formed via AddNode entries (i.e. initialized via toydb init) can
+            // be joined, since the new node must learn the addresses of all
+            // members. Only one membership change can be in flight at a time,
+            // so abort the request if there's already a pending one (the
+            // client must retry). If the node is already a member at the given
+            // address, e.g. because it restarted before it received any log
+            // entries, respond with the members again.
+            Message::ClientRequest { id, request: Request::AddNode { id: node_id, address } } => {
+                let is_static = std::iter::once(&self.id)
+                    .chain(&self.peers)
+                    .any(|id| !self.addresses.contains_key(id));
+                if is_static {
+                    let response =
+                        Err(Error::Value("Can't add nodes to a cluster with static peers".into()));
+                    self.send(msg.from, Message::ClientResponse { id, response })?;
+                } else if self.addresses.get(&node_id) == Some(&address) {
+                    let response = Ok(Response::AddNode(self.addresses.clone()));
+                    self.send(msg.from, Message::ClientResponse { id, response })?;
+                } else if self.addresses.contains_key(&node_id) {
+                    let response =
+                        Err(Error::Value(format!("Node {node_id} is already a cluster member")));
+                    self.send(msg.from, Message::ClientResponse { id, response })?;
+                } else if self.has_pending_membership_change()? {
+                    let response = Err(Error::Abort);
+                    self.send(msg.from, Message::ClientResponse { id, response })?;
+                } else {
+                    let index = self.propose(Command::AddNode { id: node_id, address })?;
+                    self.role.writes.insert(index, Write { from: msg.from, id });
+                    if self.peers.is_empty() {
+                        self.maybe_commit_and_apply()?;
+                    }
+                }
+            }
+
             Message::ClientRequest { id, request: Request::Status } => {
                 let status = Status {
                     leader: self.id,
@@ -947,8 +1196,11 @@ impl RawNode<Leader> {
             Message::CampaignResponse { .. } => {}
 
             // Leaders never proxy client requests, so we don't expect to see
-            // responses from other nodes.
-            Message::ClientResponse { .. } => panic!("Unexpected message {:?}", msg),
+            // responses from other nodes. Nor should other nodes transfer
+            // leadership to us, since there can only be one leader per term.
+            Message::ClientResponse { .. } | Message::TimeoutNow => {
+                panic!("Unexpected message {:?}", msg)
+            }
         }
 
         Ok(self.into())
@@ -959,13 +1211,49 @@ impl RawNode<Leader> {
         self.assert()?;
 
         self.role.since_heartbeat += 1;
-        if self.role.since_heartbeat >= self.heartbeat_interval {
+        if self.role.since_heartbeat >= self.opts.heartbeat_interval {
             self.heartbeat()?;
+            self.maybe_transfer_leadership()?;
             self.role.since_heartbeat = 0;
         }
+
+        // Report peers we haven't heard from in a full election timeout, once.
+        let unreachable = self.opts.election_timeout_range.end;
+        for (peer, progress) in self.role.progress.iter_mut().sorted_by_key(|(id, _)| **id) {
+            if progress.since_seen < unreachable {
+                progress.since_seen += 1;
+                if progress.since_seen == unreachable {
+                    let (peer, ticks) = (*peer, progress.since_seen);
+                    self.observer.observe(Event::PeerUnreachable { peer, ticks });
+                }
+            }
+        }
         Ok(self.into())
     }
 
+    /// Transfers leadership to the highest-priority peer (lowest ID on ties),
+    /// if it has a higher priority than us and is healthy: we've heard from it
+    /// recently, and its log is caught up so that it can win the election.
+    /// The peer campaigns immediately when receiving TimeoutNow.
+    fn maybe_transfer_leadership(&mut self) -> Result<()> {
+        let last_index = self.log.get_last_index().0;
+        let priority = self.priority(self.id);
+        let healthy = self.opts.election_timeout_range.start;
+        let Some(peer) = self
+            .role
+            .progress
+            .iter()
+            .filter(|(id, _)| self.priority(**id) > priority)
+            .filter(|(_, p)| p.since_seen < healthy && p.last == last_index)
+            .map(|(id, _)| *id)
+            .min_by_key(|id| (std::cmp::Reverse(self.priority(*id)), *id))
+        else {
+            return Ok(());
+        };
+        info!("Transferring leadership to preferred node {peer}");
+        self.send(peer, Message::TimeoutNow)
+    }
+
     /// Broadcasts a heartbeat to all peers.
     fn heartbeat(&mut self) -> Result<()> {
         let (commit_index, commit_term) = self.log.get_commit_index();
@@ -979,7 +1267,7 @@ impl RawNode<Leader> {
     /// Proposes a command for consensus by appending it to our log and
     /// replicating it to peers. If successful, it will eventually be committed
     /// and applied to the state machine.
-    fn propose(&mut self, command: Option<Vec<u8>>) -> Result<Index> {
+    fn propose(&mut self, command: Command) -> Result<Index> {
         let index = self.log.append(self.term, command)?;
         for peer in self.peers.iter().copied().sorted() {
             self.send_log(peer)?;
@@ -987,6 +1275,18 @@ impl RawNode<Leader> {
         Ok(index)
     }
 
+    /// Checks whether the log contains an unapplied membership change.
+    fn has_pending_membership_change(&mut self) -> Result<bool> {
+        let applied_index = self.state.get_applied_index();
+        let mut scan = self.log.scan(applied_index + 1..)?;
+        while let Some(entry) = scan.next().transpose()? {
+            if let Command::AddNode { .. } = entry.command {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     /// Commits any new log entries that have been replicated to a quorum, and
     /// applies them to the state machine.
     fn maybe_commit_and_apply(&mut self) -> Result<Index> {
@@ -1020,21 +1320,32 @@ impl RawNode<Leader> {
         self.log.commit(commit_index)?;
 
         // Apply entries and respond to client writers.
-        Self::maybe_apply_with(&mut self.log, &mut self.state, |index, result| -> Result<()> {
-            if let Some(write) = self.role.writes.remove(&index) {
-                // TODO: use self.send() or something.
-                self.node_tx.send(Envelope {
-                    from: self.id,
-                    to: write.from,
-                    term: self.term,
-                    message: Message::ClientResponse {
-                        id: write.id,
-                        response: result.map(Response::Write),
-                    },
-                })?;
+        let added = Self::maybe_apply_with(
+            &mut self.log,
+            &mut self.state,
+            &mut self.addresses,
+            |index, response| {
+                if let Some(write) = self.role.writes.remove(&index) {
+                    // TODO: use self.send() or something.
+                    self.node_tx.send(Envelope {
+                        from: self.id,
+                        to: write.from,
+                        term: self.term,
+                        message: Message::ClientResponse { id: write.id, response },
+                    })?;
+                }
+                Ok(())
+            },
+        )?;
+
+        // Add new nodes, and replicate the entire log to them.
+        for (id, address) in added {
+            if self.add_node(id, address) {
+                let progress = Progress { next: 1, last: 0, read_seq: 0, since_seen: 0 };
+                self.role.progress.insert(id, progress);
+                self.send_log(id)?;
             }
-            Ok(())
-        })?;
+        }
 
         Ok(commit_index)
     }
@@ -1107,9 +1418,7 @@ fn quorum_value<T: Ord + Copy>(mut values: Vec<T>) -> T {
 mod tests {
     use super::*;
     use crate::encoding::bincode;
-    use crate::raft::{
-        Entry, Request, RequestID, Response, ELECTION_TIMEOUT_RANGE, HEARTBEAT_INTERVAL,
-    };
+    use crate::raft::{Entry, Event, Request, RequestID, Response, ELECTION_TIMEOUT_RANGE};
     use crossbeam::channel::{Receiver, Sender};
     use pretty_assertions::assert_eq;
     use serde::{Deserialize, Serialize};
@@ -1155,10 +1464,17 @@ mod tests {
         nodes_pending: HashMap<NodeID, Vec<Envelope>>,
         /// Applied log entries for each node, after TestState application.
         applied_rx: HashMap<NodeID, Receiver<Entry>>,
+        /// Emitted events for each node, via the node observer.
+        events_rx: HashMap<NodeID, Receiver<Event>>,
         /// Network partitions, sender → receivers.
         disconnected: HashMap<NodeID, HashSet<NodeID>>,
         /// In-flight client requests.
         requests: HashMap<RequestID, Request>,
+        /// Cluster members returned by successful AddNode requests, by added
+        /// node ID. Used as peers when starting the node.
+        joined: HashMap<NodeID, BTreeMap<NodeID, String>>,
+        /// The node options, used when starting new nodes.
+        opts: Options,
         /// The request ID to use for the next client request.
         next_request_id: u8,
     }
@@ -1177,23 +1493,43 @@ mod tests {
                 }
 
                 // cluster nodes=N [leader=ID] [heartbeat_interval=N] [election_timeout=N]
+                //   [forward_requests=BOOL] [priority=ID:PRIORITY...] [init=BOOL]
                 //
-                // Creates a new Raft cluster.
+                // Creates a new Raft cluster. The priority argument can be
+                // given multiple times, to set election priorities per node.
+                // If init is true, a single node is initialized with an AddNode
+                // entry for itself, like toydb init, such that others can join.
                 "cluster" => {
                     let mut nodes = 0;
                     let mut leader = None;
-                    let mut heartbeat_interval = HEARTBEAT_INTERVAL;
-                    let mut election_timeout = ELECTION_TIMEOUT_RANGE.start;
+                    let mut init = false;
+                    let mut opts = Options {
+                        election_timeout_range: ELECTION_TIMEOUT_RANGE.start
+                            ..ELECTION_TIMEOUT_RANGE.start + 1,
+                        ..Options::default()
+                    };
                     for arg in &command.args {
                         match arg.key.as_deref() {
-                            Some("election_timeout") => election_timeout = arg.parse()?,
-                            Some("heartbeat_interval") => heartbeat_interval = arg.parse()?,
+                            Some("election_timeout") => {
+                                let timeout = arg.parse()?;
+                                opts.election_timeout_range = timeout..timeout + 1;
+                            }
+                            Some("forward_requests") => opts.forward_requests = arg.parse()?,
+                            Some("heartbeat_interval") => opts.heartbeat_interval = arg.parse()?,
+                            Some("init") => init = arg.parse()?,
                             Some("leader") => leader = Some(arg.parse()?),
                             Some("nodes") => nodes = arg.parse()?,
+                            Some("priority") => {
+                                let (id, priority) = arg
+                                    .value
+                                    .split_once(':')
+                                    .ok_or("priority must be given as ID:PRIORITY")?;
+                                opts.priorities.insert(id.parse()?, priority.parse()?);
+                            }
                             _ => return Err(format!("invalid argument '{}'", arg.name()).into()),
                         }
                     }
-                    self.cluster(nodes, leader, heartbeat_interval, election_timeout, &mut output)?;
+                    self.cluster(nodes, leader, opts, init, &mut output)?;
                 }
 
                 // deliver [from=ID] [ID...]
@@ -1213,6 +1549,14 @@ mod tests {
                     self.deliver(&ids, from, &mut output)?;
                 }
 
+                // events [ID...]
+                //
+                // Outputs events emitted by the given nodes since the last call.
+                "events" => {
+                    let ids = self.parse_ids_or_all(&command.args)?;
+                    self.events(&ids, &mut output)?;
+                }
+
                 // get ID KEY
                 //
                 // Sends a client request to the given node to read the given
@@ -1243,6 +1587,27 @@ mod tests {
                     self.heartbeat(&ids, &mut output)?;
                 }
 
+                // join ID NEW_ID [address=ADDR]
+                //
+                // Sends a client request to the given node to add the new node
+                // to the cluster, at address addrNEW_ID by default. The new
+                // node is started with start once added.
+                "join" => {
+                    let mut args = command.pos_args().into_iter();
+                    let id = args.next().ok_or("must specify node ID")?.parse()?;
+                    let new_id: NodeID = args.next().ok_or("must specify new node ID")?.parse()?;
+                    self.reject_args(&args.collect_vec())?;
+                    let mut address = format!("addr{new_id}");
+                    for arg in command.key_args() {
+                        match arg.key.as_deref().unwrap() {
+                            "address" => address = arg.value.clone(),
+                            key => return Err(format!("invalid argument '{key}'").into()),
+                        }
+                    }
+                    let request = Request::AddNode { id: new_id, address };
+                    self.request(id, request, &mut output)?;
+                }
+
                 // log [ID...]
                 //
                 // Outputs the current Raft log for the given nodes.
@@ -1294,6 +1659,17 @@ mod tests {
                     self.stabilize(&ids, heartbeat, &mut output)?;
                 }
 
+                // start ID
+                //
+                // Starts a node that was added to the cluster via join, using
+                // the cluster members from the AddNode response as peers.
+                "start" => {
+                    let mut args = command.args.iter();
+                    let id = args.next().ok_or("must specify node ID")?.parse()?;
+                    self.reject_args(&args.collect_vec())?;
+                    self.start(id, &mut output)?;
+                }
+
                 // state [ID...]
                 //
                 // Prints the current state machine contents on the given nodes.
@@ -1389,8 +1765,8 @@ mod tests {
             &mut self,
             nodes: u8,
             leader: Option<NodeID>,
-            heartbeat_interval: Ticks,
-            election_timeout: Ticks,
+            opts: Options,
+            init: bool,
             output: &mut String,
         ) -> Result<(), Box<dyn Error>> {
             if !self.ids.is_empty() {
@@ -1399,31 +1775,22 @@ mod tests {
             if nodes == 0 {
                 return Err("cluster can't have 0 nodes".into());
             }
+            if init && nodes != 1 {
+                return Err("init requires a single node".into());
+            }
 
             self.ids = (1..=nodes).collect();
+            self.opts = opts;
 
-            for id in self.ids.iter().copied() {
-                let (node_tx, node_rx) = crossbeam::channel::unbounded();
-                let (applied_tx, applied_rx) = crossbeam::channel::unbounded();
+            for id in self.ids.clone() {
                 let peers = self.ids.iter().copied().filter(|i| *i != id).collect();
-                let log = Log::new(crate::storage::Memory::new(), false)?;
-                let state = Box::new(TestState::new(applied_tx));
-                self.nodes.insert(
-                    id,
-                    Node::new(
-                        id,
-                        peers,
-                        log,
-                        state,
-                        node_tx,
-                        heartbeat_interval,
-                        election_timeout..election_timeout + 1,
-                    )?,
-                );
-                self.nodes_rx.insert(id, node_rx);
-                self.nodes_pending.insert(id, Vec::new());
-                self.applied_rx.insert(id, applied_rx);
-                self.disconnected.insert(id, HashSet::new());
+                let mut log = Log::new(crate::storage::Memory::new(), false)?;
+                if init {
+                    log.set_term(1, None)?;
+                    log.append(1, Command::AddNode { id, address: format!("addr{id}") })?;
+                    log.commit(1)?;
+                }
+                self.new_node(id, peers, log)?;
             }
 
             // Promote leader if requested. Suppress output.
@@ -1437,10 +1804,48 @@ mod tests {
                 self.stabilize(&self.ids.clone(), true, quiet)?;
             }
 
+            // Discard events from cluster setup.
+            self.events(&self.ids.clone(), &mut String::new())?;
+
             // Output final cluster status.
             self.status(&self.ids, output)
         }
 
+        /// Creates a new node with the given peers and log.
+        fn new_node(
+            &mut self,
+            id: NodeID,
+            peers: HashSet<NodeID>,
+            log: Log,
+        ) -> Result<(), Box<dyn Error>> {
+            let (node_tx, node_rx) = crossbeam::channel::unbounded();
+            let (applied_tx, applied_rx) = crossbeam::channel::unbounded();
+            let (events_tx, events_rx) = crossbeam::channel::unbounded();
+            let state = Box::new(TestState::new(applied_tx));
+            let observer = Box::new(events_tx);
+            let opts = self.opts.clone();
+            self.nodes.insert(id, Node::new(id, peers, log, state, node_tx, observer, opts)?);
+            self.nodes_rx.insert(id, node_rx);
+            self.nodes_pending.insert(id, Vec::new());
+            self.applied_rx.insert(id, applied_rx);
+            self.events_rx.insert(id, events_rx);
+            self.disconnected.insert(id, HashSet::new());
+            Ok(())
+        }
+
+        /// Starts a node that was added to the cluster, with the cluster
+        /// members from its AddNode response as peers.
+        fn start(&mut self, id: NodeID, output: &mut String) -> Result<(), Box<dyn Error>> {
+            if self.nodes.contains_key(&id) {
+                return Err(format!("node {id} already exists").into());
+            }
+            let members = self.joined.get(&id).ok_or(format!("node {id} hasn't joined"))?;
+            let peers = members.keys().copied().filter(|p| *p != id).collect();
+            self.ids.push(id);
+            self.new_node(id, peers, Log::new(crate::storage::Memory::new(), false)?)?;
+            self.status(&[id], output)
+        }
+
         /// Delivers pending inbound messages to the given nodes. If from is
         /// given, only delivers messages from the given node ID. Returns the
         /// number of delivered messages.
@@ -1473,6 +1878,18 @@ mod tests {
             Ok(delivered)
         }
 
+        /// Outputs events emitted by the given nodes since the last call.
+        fn events(&mut self, ids: &[NodeID], output: &mut String) -> Result<(), Box<dyn Error>> {
+            for id in ids {
+                let node = self.nodes.get(id).ok_or(format!("unknown node {id}"))?;
+                let nodefmt = Self::format_node(node);
+                for event in self.events_rx[id].try_iter() {
+                    output.push_str(&format!("{nodefmt} event {event}\n"));
+                }
+            }
+            Ok(())
+        }
+
         /// Heals the given partitioned nodes, restoring connectivity with all
         /// other nodes.
         fn heal(&mut self, ids: &[NodeID], output: &mut String) -> Result<(), Box<dyn Error>> {
@@ -1568,6 +1985,22 @@ mod tests {
                         Self::format_request(request),
                         Self::format_response(response),
                     ));
+                    if let (Request::AddNode { id, .. }, Ok(Response::AddNode(members))) =
+                        (request, response)
+                    {
+                        self.joined.insert(*id, members.clone());
+                    }
+                    continue;
+                }
+
+                // If the peer hasn't been started yet after joining the
+                // cluster, drop the message and output it. The leader will
+                // retry once the node responds to its heartbeats.
+                if !self.nodes.contains_key(&msg.to) {
+                    output.push_str(&format!(
+                        "n{from}@{term} ⇥ n{to} {}\n",
+                        Self::format_strikethrough(&Self::format_message(&msg.message)),
+                    ));
                     continue;
                 }
 
@@ -1900,9 +2333,12 @@ mod tests {
 
         /// Formats an entry.
         fn format_entry(entry: &Entry) -> String {
-            let command = match entry.command.as_ref() {
-                Some(raw) => TestCommand::decode(raw).expect("invalid command").to_string(),
-                None => "None".to_string(),
+            let command = match &entry.command {
+                Command::Write(raw) => {
+                    TestCommand::decode(raw).expect("invalid command").to_string()
+                }
+                Command::AddNode { id, address } => format!("add n{id} at {address}"),
+                Command::Noop => "None".to_string(),
             };
             format!("{}@{} {command}", entry.index, entry.term)
         }
@@ -1931,6 +2367,7 @@ mod tests {
                 Message::AppendResponse { reject, last_index, last_term } => {
                     format!("AppendResponse last={last_index}@{last_term} reject={reject}")
                 }
+                Message::TimeoutNow => "TimeoutNow".to_string(),
                 Message::ClientRequest { id, request } => {
                     format!(
                         "ClientRequest id=0x{} {}",
@@ -1939,6 +2376,7 @@ mod tests {
                             Request::Read(v) => format!("read 0x{}", hex::encode(v)),
                             Request::Write(v) => format!("write 0x{}", hex::encode(v)),
                             Request::Status => "status".to_string(),
+                            Request::AddNode { id, address } => format!("add n{id} at {address}"),
                         }
                     )
                 }
@@ -1950,6 +2388,9 @@ mod tests {
                             Ok(Response::Read(v)) => format!("read 0x{}", hex::encode(v)),
                             Ok(Response::Write(v)) => format!("write 0x{}", hex::encode(v)),
                             Ok(Response::Status(v)) => format!("status {v:?}"),
+                            Ok(Response::AddNode(members)) => {
+                                format!("add {}", Self::format_members(members))
+                            }
                             Err(e) => format!("Error::{e:#?}"),
                         }
                     )
@@ -1982,6 +2423,7 @@ mod tests {
             match request {
                 Request::Read(c) | Request::Write(c) => TestCommand::decode(c).unwrap().to_string(),
                 Request::Status => "status".to_string(),
+                Request::AddNode { id, address } => format!("add n{id} at {address}"),
             }
         }
 
@@ -1992,10 +2434,16 @@ mod tests {
                     TestResponse::decode(r).unwrap().to_string()
                 }
                 Ok(Response::Status(status)) => format!("{status:#?}"),
+                Ok(Response::AddNode(members)) => Self::format_members(members),
                 Err(e) => format!("Error::{e:?} ({e})"),
             }
         }
 
+        /// Formats cluster members.
+        fn format_members(members: &BTreeMap<NodeID, String>) -> String {
+            members.iter().map(|(id, address)| format!("n{id} at {address}")).join(", ")
+        }
+
         /// Strike-through formats the given string using a Unicode combining stroke.
         fn format_strikethrough(s: &str) -> String {
             s.chars().flat_map(|c| [c, '\u{0336}']).collect()
@@ -2051,9 +2499,11 @@ mod tests {
         }
 
         fn apply(&mut self, entry: Entry) -> crate::error::Result<Vec<u8>> {
-            let response = entry
-                .command
-                .as_deref()
+            let command = match &entry.command {
+                Command::Write(command) => Some(command.as_slice()),
+                Command::Noop | Command::AddNode { .. } => None,
+            };
+            let response = command
                 .map(TestCommand::decode)
                 .transpose()?
                 .map(|c| match c {
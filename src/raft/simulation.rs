@@ -0,0 +1,444 @@
+//! A deterministic Raft cluster simulator with fault injection.
+//!
+//! The simulator drives a set of in-memory Raft nodes via a seeded random
+//! scheduler, which ticks nodes, delivers messages, submits client writes, and
+//! injects faults: dropped, duplicated, and reordered messages, as well as
+//! network partitions. After every step, it asserts Raft's safety invariants:
+//!
+//! - Election safety: there is at most one leader per term.
+//! - Log matching: if two logs contain an entry with the same index and term,
+//!   the logs are identical up to and including that index.
+//! - Leader completeness: committed entries are never lost or replaced, and
+//!   are present in the logs of all later leaders.
+//! - State machine safety: nodes apply the same entries in the same order.
+//!
+//! Additionally, client writes that were acknowledged must be committed.
+//!
+//! Given the same seed, a simulation always runs the exact same schedule, so
+//! failures can be reproduced by rerunning the failing seed. Since nodes
+//! generate randomized election timeouts via a thread-local RNG, the simulator
+//! assigns each node a fixed election timeout drawn from the seeded RNG.
+
+use super::{
+    Command, Entry, Envelope, Index, Log, Message, Node, NodeID, NodeRole, Options, Request,
+    Response, State, Term, ELECTION_TIMEOUT_RANGE,
+};
+use crate::error::Result;
+use crate::storage;
+
+use crossbeam::channel::{Receiver, Sender};
+use itertools::Itertools as _;
+use rand::rngs::StdRng;
+use rand::{Rng as _, SeedableRng as _};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Fault injection probabilities.
+#[derive(Clone, Copy, Debug)]
+struct Faults {
+    /// The probability of dropping a delivered message.
+    drop: f64,
+    /// The probability of duplicating a delivered message.
+    duplicate: f64,
+    /// If true, deliver in-flight messages in random order, otherwise in the
+    /// order they were sent.
+    reorder: bool,
+    /// The probability of partitioning or healing the network at each step.
+    partition: f64,
+}
+
+impl Faults {
+    /// No faults.
+    const NONE: Faults = Faults { drop: 0.0, duplicate: 0.0, reorder: false, partition: 0.0 };
+
+    /// All faults enabled.
+    const ALL: Faults = Faults { drop: 0.05, duplicate: 0.05, reorder: true, partition: 0.01 };
+}
+
+/// A simulated Raft cluster.
+struct Simulation {
+    /// The seeded RNG driving the simulation.
+    rng: StdRng,
+    /// The injected faults.
+    faults: Faults,
+    /// IDs of all cluster nodes, in order.
+    ids: Vec<NodeID>,
+    /// The cluster nodes. A node is temporarily removed while it's stepped.
+    nodes: HashMap<NodeID, Node>,
+    /// Outbound messages from each node.
+    nodes_rx: HashMap<NodeID, Receiver<Envelope>>,
+    /// Entries applied by each node's state machine.
+    applied_rx: HashMap<NodeID, Receiver<Entry>>,
+    /// In-flight messages between nodes.
+    network: Vec<Envelope>,
+    /// Nodes that are partitioned away from the rest of the cluster.
+    partition: HashSet<NodeID>,
+    /// If true, submit client writes.
+    writes: bool,
+    /// The next client write request ID.
+    next_request: u64,
+
+    /// The leader of each term, for election safety.
+    leaders: HashMap<Term, NodeID>,
+    /// All committed entries seen so far, along with the term of the node
+    /// that first committed them, for leader completeness.
+    committed: BTreeMap<Index, (Entry, Term)>,
+    /// The last applied index of each node, for state machine safety.
+    applied: HashMap<NodeID, Index>,
+    /// The number of acknowledged client writes.
+    acked: u64,
+}
+
+impl Simulation {
+    /// Creates a new simulated cluster with the given size, seed and faults.
+    fn new(size: u8, seed: u64, faults: Faults) -> Result<Self> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let ids: Vec<NodeID> = (1..=size).collect();
+        let mut nodes = HashMap::new();
+        let mut nodes_rx = HashMap::new();
+        let mut applied_rx = HashMap::new();
+        for id in ids.iter().copied() {
+            let (node_tx, node_rx) = crossbeam::channel::unbounded();
+            let (applied_tx, rx) = crossbeam::channel::unbounded();
+            let peers = ids.iter().copied().filter(|p| *p != id).collect();
+            let log = Log::new(storage::Memory::new(), false)?;
+            let state = Box::new(SimState::new(applied_tx));
+            let timeout = rng.gen_range(ELECTION_TIMEOUT_RANGE);
+            let opts =
+                Options { election_timeout_range: timeout..timeout + 1, ..Options::default() };
+            let observer = Box::new(crossbeam::channel::unbounded().0);
+            nodes.insert(id, Node::new(id, peers, log, state, node_tx, observer, opts)?);
+            nodes_rx.insert(id, node_rx);
+            applied_rx.insert(id, rx);
+        }
+        Ok(Self {
+            rng,
+            faults,
+            applied: ids.iter().map(|id| (*id, 0)).collect(),
+            ids,
+            nodes,
+            nodes_rx,
+            applied_rx,
+            network: Vec::new(),
+            partition: HashSet::new(),
+            writes: true,
+            next_request: 1,
+            leaders: HashMap::new(),
+            committed: BTreeMap::new(),
+            acked: 0,
+        })
+    }
+
+    /// Runs the given number of random steps, checking invariants after each.
+    fn run(&mut self, steps: usize) -> Result<()> {
+        for _ in 0..steps {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Heals the network and disables faults and writes, then runs until all
+    /// nodes have committed and applied the same log, or panics after the
+    /// given number of steps.
+    fn converge(&mut self, steps: usize) -> Result<()> {
+        self.faults = Faults::NONE;
+        self.writes = false;
+        self.partition.clear();
+        for _ in 0..steps {
+            self.step()?;
+            if self.converged()? {
+                return Ok(());
+            }
+        }
+        panic!("cluster did not converge after {steps} steps");
+    }
+
+    /// Returns true if there is a leader, and all nodes have committed and
+    /// applied its entire log.
+    fn converged(&mut self) -> Result<bool> {
+        if !self.nodes.values().any(|n| n.role() == NodeRole::Leader) {
+            return Ok(false);
+        }
+        let mut indexes = HashSet::new();
+        for (id, node) in self.nodes.iter_mut() {
+            let log = node.log_mut();
+            let (last_index, commit_index) = (log.get_last_index().0, log.get_commit_index().0);
+            indexes.extend([last_index, commit_index, self.applied[id]]);
+        }
+        Ok(indexes.len() == 1)
+    }
+
+    /// Runs a single random step, then checks invariants.
+    fn step(&mut self) -> Result<()> {
+        match self.rng.gen_range(0..100) {
+            0..=29 => self.tick()?,
+            30..=89 => self.deliver()?,
+            _ if self.writes => self.write()?,
+            _ => {}
+        }
+        if self.rng.gen_bool(self.faults.partition) {
+            self.repartition();
+        }
+        let responses = self.route();
+        self.check()?;
+        for (id, response) in responses {
+            self.check_response(id, response)?;
+        }
+        Ok(())
+    }
+
+    /// Ticks a random node.
+    fn tick(&mut self) -> Result<()> {
+        let id = self.random_node();
+        self.transition(id, |node| node.tick())
+    }
+
+    /// Delivers a random (or the oldest) in-flight message, possibly dropping
+    /// or duplicating it. Messages across the partition are dropped.
+    fn deliver(&mut self) -> Result<()> {
+        if self.network.is_empty() {
+            return Ok(());
+        }
+        let index = match self.faults.reorder {
+            true => self.rng.gen_range(0..self.network.len()),
+            false => 0,
+        };
+        let msg = self.network.remove(index);
+        if self.partitioned(msg.from, msg.to) || self.rng.gen_bool(self.faults.drop) {
+            return Ok(());
+        }
+        if self.rng.gen_bool(self.faults.duplicate) {
+            self.network.push(msg.clone());
+        }
+        self.transition(msg.to, |node| node.step(msg))
+    }
+
+    /// Submits a client write to a random node. The request ID is also used as
+    /// the write command, so it can be identified in the log.
+    fn write(&mut self) -> Result<()> {
+        let id = self.random_node();
+        let request_id = self.next_request.to_be_bytes().to_vec();
+        self.next_request += 1;
+        let node = &self.nodes[&id];
+        let msg = Envelope {
+            from: id,
+            to: id,
+            term: node.term(),
+            message: Message::ClientRequest {
+                id: request_id.clone(),
+                request: Request::Write(request_id),
+            },
+        };
+        self.transition(id, |node| node.step(msg))
+    }
+
+    /// Partitions a random minority of nodes away from the rest, or heals the
+    /// current partition.
+    fn repartition(&mut self) {
+        if !self.partition.is_empty() {
+            self.partition.clear();
+            return;
+        }
+        let size = self.rng.gen_range(1..=(self.ids.len() - 1) / 2);
+        for _ in 0..size {
+            let id = self.random_node();
+            self.partition.insert(id);
+        }
+    }
+
+    /// Returns true if the given nodes are on different sides of a partition.
+    fn partitioned(&self, from: NodeID, to: NodeID) -> bool {
+        self.partition.contains(&from) != self.partition.contains(&to)
+    }
+
+    /// Returns a random node ID.
+    fn random_node(&mut self) -> NodeID {
+        self.ids[self.rng.gen_range(0..self.ids.len())]
+    }
+
+    /// Applies a node transition (a step or tick).
+    fn transition(&mut self, id: NodeID, f: impl FnOnce(Node) -> Result<Node>) -> Result<()> {
+        let node = self.nodes.remove(&id).expect("unknown node");
+        self.nodes.insert(id, f(node)?);
+        Ok(())
+    }
+
+    /// Moves outbound node messages into the network, and returns client
+    /// responses (i.e. messages from a node to itself).
+    fn route(&mut self) -> Vec<(NodeID, Response)> {
+        let mut responses = Vec::new();
+        for id in self.ids.iter().copied() {
+            for msg in self.nodes_rx[&id].try_iter() {
+                match msg.message {
+                    Message::ClientResponse { response: Ok(response), .. } if msg.to == id => {
+                        responses.push((id, response))
+                    }
+                    Message::ClientResponse { .. } if msg.to == id => {} // aborted
+                    _ => self.network.push(msg),
+                }
+            }
+        }
+        responses
+    }
+
+    /// Checks the Raft safety invariants.
+    fn check(&mut self) -> Result<()> {
+        // Fetch the logs of all nodes.
+        let mut logs = BTreeMap::new();
+        for id in self.ids.iter().copied() {
+            let node = self.nodes.get_mut(&id).unwrap();
+            let term = node.term();
+            let log = node.log_mut();
+            let commit_index = log.get_commit_index().0;
+            let entries: Vec<Entry> = log.scan(..)?.try_collect()?;
+            logs.insert(id, (entries, commit_index, term));
+        }
+
+        // Election safety: at most one leader per term.
+        for (id, node) in self.nodes.iter() {
+            if node.role() == NodeRole::Leader {
+                let leader = *self.leaders.entry(node.term()).or_insert(*id);
+                assert_eq!(leader, *id, "multiple leaders in term {}", node.term());
+            }
+        }
+
+        // Log matching: if two logs have an entry with the same index and
+        // term, the logs are identical up to that index.
+        for ((a, (a_log, _, _)), (b, (b_log, _, _))) in logs.iter().tuple_combinations() {
+            let matching = a_log.iter().zip(b_log.iter()).rposition(|(a, b)| a.term == b.term);
+            if let Some(index) = matching {
+                assert_eq!(a_log[..=index], b_log[..=index], "log mismatch between n{a} and n{b}");
+            }
+        }
+
+        // Leader completeness: committed entries never change, and leaders of
+        // later terms have all committed entries. Leaders of earlier terms may
+        // not have them, if they haven't discovered the new term yet.
+        for (id, (entries, commit_index, term)) in logs.iter() {
+            for entry in entries.iter().take(*commit_index as usize) {
+                let (committed, _) =
+                    self.committed.entry(entry.index).or_insert_with(|| (entry.clone(), *term));
+                assert_eq!(entry, committed, "n{id} committed entry changed");
+            }
+            if self.nodes[id].role() == NodeRole::Leader {
+                for (index, (committed, commit_term)) in self.committed.iter() {
+                    if commit_term < term {
+                        let entry = entries.get(*index as usize - 1);
+                        assert_eq!(entry, Some(committed), "leader n{id} missing committed entry");
+                    }
+                }
+            }
+        }
+
+        // State machine safety: all nodes apply the committed entries in order.
+        for (id, applied_rx) in self.applied_rx.iter() {
+            for entry in applied_rx.try_iter() {
+                let applied = self.applied.get_mut(id).unwrap();
+                assert_eq!(entry.index, *applied + 1, "n{id} applied entries out of order");
+                let committed = self.committed.get(&entry.index).map(|(e, _)| e);
+                assert_eq!(Some(&entry), committed, "n{id} applied uncommitted entry");
+                *applied = entry.index;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that an acknowledged client write was committed.
+    fn check_response(&mut self, id: NodeID, response: Response) -> Result<()> {
+        let Response::Write(index) = response else {
+            panic!("unexpected response {response:?}");
+        };
+        let index = Index::from_be_bytes(index.try_into().expect("invalid index"));
+        let (entry, _) = self.committed.get(&index).expect("acknowledged write not committed");
+        assert!(matches!(entry.command, Command::Write(_)), "n{id} acked non-write entry");
+        self.acked += 1;
+        Ok(())
+    }
+}
+
+/// A simulation state machine, which emits applied entries and returns the
+/// applied index for writes.
+struct SimState {
+    applied_index: Index,
+    applied_tx: Sender<Entry>,
+}
+
+impl SimState {
+    fn new(applied_tx: Sender<Entry>) -> Self {
+        Self { applied_index: 0, applied_tx }
+    }
+}
+
+impl State for SimState {
+    fn get_applied_index(&self) -> Index {
+        self.applied_index
+    }
+
+    fn apply(&mut self, entry: Entry) -> Result<Vec<u8>> {
+        self.applied_index = entry.index;
+        self.applied_tx.send(entry)?;
+        Ok(self.applied_index.to_be_bytes().to_vec())
+    }
+
+    fn read(&self, _: Vec<u8>) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Runs the given number of seeded simulations, reporting the failing seed if
+/// any of them panic.
+fn simulate(seeds: std::ops::Range<u64>, size: u8, faults: Faults, steps: usize) {
+    for seed in seeds {
+        let result = std::panic::catch_unwind(|| {
+            let mut sim = Simulation::new(size, seed, faults)?;
+            sim.run(steps)?;
+            sim.converge(10 * steps)?;
+            assert!(sim.acked > 0, "no writes were acknowledged");
+            Ok::<_, crate::error::Error>(sim.acked)
+        });
+        match result {
+            Ok(Ok(_)) => {}
+            Ok(Err(err)) => panic!("simulation seed {seed} failed: {err}"),
+            Err(panic) => std::panic::resume_unwind(Box::new(format!(
+                "simulation seed {seed} panicked: {}",
+                panic
+                    .downcast_ref::<String>()
+                    .map(String::as_str)
+                    .or_else(|| panic.downcast_ref::<&str>().copied())
+                    .unwrap_or("unknown")
+            ))),
+        }
+    }
+}
+
+#[test]
+fn no_faults() {
+    simulate(0..5, 3, Faults::NONE, 1000)
+}
+
+#[test]
+fn all_faults_3_nodes() {
+    simulate(0..10, 3, Faults::ALL, 1000)
+}
+
+#[test]
+fn all_faults_5_nodes() {
+    simulate(0..10, 5, Faults::ALL, 1000)
+}
+
+#[test]
+fn deterministic() -> Result<()> {
+    let run = |seed| -> Result<_> {
+        let mut sim = Simulation::new(5, seed, Faults::ALL)?;
+        sim.run(1000)?;
+        let mut logs = Vec::new();
+        for id in sim.ids.clone() {
+            let entries: Vec<Entry> =
+                sim.nodes.get_mut(&id).unwrap().log_mut().scan(..)?.try_collect()?;
+            logs.push(entries);
+        }
+        Ok((logs, sim.acked))
+    };
+    assert_eq!(run(7)?, run(7)?);
+    Ok(())
+}
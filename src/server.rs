@@ -1,18 +1,27 @@
+mod auth;
+mod grpc;
+mod http;
+mod postgres;
+
+pub use auth::Auth;
+pub use grpc::proto;
+
 use crate::encoding::bincode;
 use crate::error::{Error, Result};
 use crate::raft;
 use crate::sql;
 use crate::sql::engine::Engine as _;
 use crate::sql::execution::ResultSet;
-use crate::sql::schema::{Catalog as _, Table};
-use crate::sql::types::Row;
+use crate::sql::schema::{Catalog as _, Table, User, View};
+use crate::sql::types::{Row, Rows, Value};
 use crate::storage;
+use crate::tls;
 
 use crossbeam::channel::{Receiver, Sender};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::io::Write as _;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write as _};
 use std::net::{TcpListener, TcpStream, ToSocketAddrs};
 
 /// The outbound Raft peer channel capacity. This buffers messages when a Raft
@@ -32,24 +41,53 @@ const RAFT_PEER_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_
 ///
 /// - Listens for inbound SQL connections via TCP and passes requests to
 ///   the local Raft node.
+///
+/// - Optionally listens for inbound PostgreSQL wire protocol connections via
+///   TCP, and executes their statements like SQL connections.
+///
+/// - Optionally listens for inbound HTTP connections via TCP, and executes
+///   their statements or returns the server status as JSON.
+///
+/// - Optionally serves the gRPC API (see proto/toydb.proto) via TCP.
+///
+/// If TLS is configured, SQL and Raft connections use TLS. The PostgreSQL,
+/// HTTP, and gRPC listeners remain plaintext.
+///
+/// If authentication is enabled, SQL, PostgreSQL, HTTP, and gRPC clients must
+/// authenticate as a user before executing requests (see Auth).
 pub struct Server {
     /// The inner Raft node.
     node: raft::Node,
     /// Outbound messages from the Raft node.
     node_rx: Receiver<raft::Envelope>,
+    /// Events from the Raft node.
+    events_rx: Receiver<raft::Event>,
     /// Raft peer IDs and addresses.
     peers: HashMap<raft::NodeID, String>,
+    /// Raft peer IDs and SQL addresses, used to redirect clients to the leader
+    /// when the Raft node doesn't forward requests.
+    peers_sql: HashMap<raft::NodeID, String>,
+    /// The TLS configuration for SQL and Raft connections, if any.
+    tls: Option<tls::Config>,
+    /// The client authenticator, if authentication is enabled.
+    auth: Option<Auth>,
 }
 
 impl Server {
     /// Creates a new toyDB server.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: raft::NodeID,
         peers: HashMap<raft::NodeID, String>,
+        peers_sql: HashMap<raft::NodeID, String>,
         raft_log: raft::Log,
         raft_state: Box<dyn raft::State>,
+        raft_opts: raft::Options,
+        tls: Option<tls::Config>,
+        auth: Option<Auth>,
     ) -> Result<Self> {
         let (node_tx, node_rx) = crossbeam::channel::unbounded();
+        let (events_tx, events_rx) = crossbeam::channel::unbounded();
         Ok(Self {
             node: raft::Node::new(
                 id,
@@ -57,23 +95,47 @@ impl Server {
                 raft_log,
                 raft_state,
                 node_tx,
-                raft::HEARTBEAT_INTERVAL,
-                raft::ELECTION_TIMEOUT_RANGE,
+                Box::new(events_tx),
+                raft_opts,
             )?,
             peers,
+            peers_sql,
+            tls,
+            auth,
             node_rx,
+            events_rx,
         })
     }
 
-    /// Serves Raft and SQL requests indefinitely. Consumes the server.
-    pub fn serve(self, raft_addr: impl ToSocketAddrs, sql_addr: impl ToSocketAddrs) -> Result<()> {
+    /// Serves Raft and SQL requests indefinitely, and PostgreSQL, HTTP, and
+    /// gRPC requests if given their addresses. Consumes the server.
+    pub fn serve(
+        self,
+        raft_addr: impl ToSocketAddrs,
+        sql_addr: impl ToSocketAddrs,
+        pg_addr: Option<&str>,
+        http_addr: Option<&str>,
+        grpc_addr: Option<&str>,
+    ) -> Result<()> {
         let raft_listener = TcpListener::bind(raft_addr)?;
         let sql_listener = TcpListener::bind(sql_addr)?;
+        let pg_listener = pg_addr.map(TcpListener::bind).transpose()?;
+        let http_listener = http_addr.map(TcpListener::bind).transpose()?;
+        let grpc_listener = grpc_addr.map(TcpListener::bind).transpose()?;
         info!(
             "Listening on {} (SQL) and {} (Raft)",
             sql_listener.local_addr()?,
             raft_listener.local_addr()?
         );
+        if let Some(pg_listener) = &pg_listener {
+            info!("Listening on {} (PostgreSQL)", pg_listener.local_addr()?);
+        }
+        if let Some(http_listener) = &http_listener {
+            info!("Listening on {} (HTTP)", http_listener.local_addr()?);
+        }
+        if let Some(grpc_listener) = &grpc_listener {
+            info!("Listening on {} (gRPC)", grpc_listener.local_addr()?);
+        }
 
         std::thread::scope(move |s| {
             let id = self.node.id();
@@ -81,31 +143,62 @@ impl Server {
             let (raft_step_tx, raft_step_rx) = crossbeam::channel::unbounded();
 
             // Serve inbound Raft connections.
-            s.spawn(move || Self::raft_accept(raft_listener, raft_step_tx));
+            let tls = self.tls.clone();
+            s.spawn(move || Self::raft_accept(raft_listener, raft_step_tx, tls.as_ref()));
 
             // Establish outbound Raft connections.
             let mut raft_peers_tx = HashMap::new();
 
             for (id, addr) in self.peers.into_iter() {
-                let (raft_peer_tx, raft_peer_rx) =
-                    crossbeam::channel::bounded(RAFT_PEER_CHANNEL_CAPACITY);
-                raft_peers_tx.insert(id, raft_peer_tx);
-                s.spawn(move || Self::raft_send_peer(addr, raft_peer_rx));
+                Self::raft_connect_peer(id, addr, &mut raft_peers_tx, self.tls.as_ref());
             }
 
             // Route Raft messages between the local node, peers, and clients.
+            let tls = self.tls.clone();
             s.spawn(move || {
                 Self::raft_route(
                     self.node,
                     self.node_rx,
+                    self.events_rx,
                     raft_step_rx,
                     raft_peers_tx,
                     raft_request_rx,
+                    tls,
                 )
             });
 
-            // Serve inbound SQL connections.
-            s.spawn(move || Self::sql_accept(id, sql_listener, raft_request_tx));
+            // Serve inbound SQL, PostgreSQL, HTTP, and gRPC connections.
+            // Sessions share an engine, and thus its allocated sequence values.
+            let engine = sql::engine::Raft::new(raft_request_tx.clone());
+            if let Some(pg_listener) = pg_listener {
+                let (engine, auth) = (engine.clone(), self.auth.clone());
+                s.spawn(move || postgres::accept(pg_listener, engine, auth));
+            }
+            if let Some(http_listener) = http_listener {
+                let (engine, auth) = (engine.clone(), self.auth.clone());
+                s.spawn(move || http::accept(id, http_listener, engine, auth));
+            }
+            if let Some(grpc_listener) = grpc_listener {
+                let (engine, raft_request_tx) = (engine.clone(), raft_request_tx.clone());
+                let auth = self.auth.clone();
+                s.spawn(move || {
+                    if let Err(err) = grpc::serve(id, grpc_listener, engine, raft_request_tx, auth)
+                    {
+                        error!("{err}");
+                    }
+                });
+            }
+            s.spawn(move || {
+                Self::sql_accept(
+                    id,
+                    sql_listener,
+                    engine,
+                    raft_request_tx,
+                    self.peers_sql,
+                    self.tls.as_ref(),
+                    self.auth.as_ref(),
+                )
+            });
         });
 
         Ok(())
@@ -113,7 +206,11 @@ impl Server {
 
     /// Accepts new inbound Raft connections from peers and spawns threads
     /// routing inbound messages to the local Raft node.
-    fn raft_accept(listener: TcpListener, raft_step_tx: Sender<raft::Envelope>) {
+    fn raft_accept(
+        listener: TcpListener,
+        raft_step_tx: Sender<raft::Envelope>,
+        tls: Option<&tls::Config>,
+    ) {
         std::thread::scope(|s| loop {
             let (socket, peer) = match listener.accept() {
                 Ok(sp) => sp,
@@ -125,7 +222,11 @@ impl Server {
             let raft_step_tx = raft_step_tx.clone();
             s.spawn(move || {
                 debug!("Raft peer {peer} connected");
-                match Self::raft_receive_peer(socket, raft_step_tx) {
+                let result = match tls {
+                    Some(tls) => tls.accept(socket),
+                    None => Ok(socket.into()),
+                };
+                match result.and_then(|socket| Self::raft_receive_peer(socket, raft_step_tx)) {
                     Ok(()) => debug!("Raft peer {peer} disconnected"),
                     Err(err) => error!("Raft peer {peer} error: {err}"),
                 }
@@ -135,7 +236,7 @@ impl Server {
 
     /// Receives inbound messages from a peer via TCP, and queues them for
     /// stepping into the Raft node.
-    fn raft_receive_peer(socket: TcpStream, raft_step_tx: Sender<raft::Envelope>) -> Result<()> {
+    fn raft_receive_peer(socket: tls::Stream, raft_step_tx: Sender<raft::Envelope>) -> Result<()> {
         let mut socket = std::io::BufReader::new(socket);
         while let Some(message) = bincode::maybe_deserialize_from(&mut socket)? {
             raft_step_tx.send(message)?;
@@ -143,11 +244,35 @@ impl Server {
         Ok(())
     }
 
+    /// Spawns a thread sending outbound messages to the given peer, and
+    /// registers its channel in peers_tx. The thread is detached, since peers
+    /// can be added at runtime via cluster membership changes.
+    fn raft_connect_peer(
+        id: raft::NodeID,
+        addr: String,
+        peers_tx: &mut HashMap<raft::NodeID, Sender<raft::Envelope>>,
+        tls: Option<&tls::Config>,
+    ) {
+        let (raft_peer_tx, raft_peer_rx) = crossbeam::channel::bounded(RAFT_PEER_CHANNEL_CAPACITY);
+        peers_tx.insert(id, raft_peer_tx);
+        let tls = tls.cloned();
+        std::thread::spawn(move || Self::raft_send_peer(addr, raft_peer_rx, tls));
+    }
+
     /// Sends outbound messages to a peer via TCP. Retries indefinitely if the
     /// connection fails.
-    fn raft_send_peer(addr: String, raft_node_rx: Receiver<raft::Envelope>) {
+    fn raft_send_peer(
+        addr: String,
+        raft_node_rx: Receiver<raft::Envelope>,
+        tls: Option<tls::Config>,
+    ) {
         loop {
-            let mut socket = match TcpStream::connect(&addr) {
+            let result =
+                TcpStream::connect(&addr).map_err(Error::from).and_then(|socket| match &tls {
+                    Some(tls) => tls.connect(socket, &addr),
+                    None => Ok(socket.into()),
+                });
+            let mut socket = match result {
                 Ok(socket) => std::io::BufWriter::new(socket),
                 Err(err) => {
                     error!("Failed connecting to Raft peer {addr}: {err}");
@@ -182,14 +307,19 @@ impl Server {
     /// - peers_tx: outbound per-peer channels sent via TCP connections.
     ///   Messages from the local node's node_rx are sent here.
     ///
+    /// - events_rx: events from the local Raft node. These are logged, and
+    ///   new peers added via membership changes are connected to.
+    ///
     /// Panics on any errors, since the Raft node can't recover from failed
     /// state transitions.
     fn raft_route(
         mut node: raft::Node,
         node_rx: Receiver<raft::Envelope>,
+        events_rx: Receiver<raft::Event>,
         peers_rx: Receiver<raft::Envelope>,
         mut peers_tx: HashMap<raft::NodeID, Sender<raft::Envelope>>,
         request_rx: Receiver<(raft::Request, Sender<Result<raft::Response>>)>,
+        tls: Option<tls::Config>,
     ) {
         // Track response channels by request ID. The Raft node will emit
         // ClientResponse messages that we forward to the response channel.
@@ -197,6 +327,13 @@ impl Server {
 
         let ticker = crossbeam::channel::tick(raft::TICK_INTERVAL);
         loop {
+            // Process node events. This is done before routing any messages,
+            // such that peers added by the node are connected to before the
+            // node's messages to them are routed.
+            for event in events_rx.try_iter() {
+                Self::raft_event(event, &mut peers_tx, tls.as_ref());
+            }
+
             crossbeam::select! {
                 // Periodically tick the node.
                 recv(ticker) -> _ => node = node.tick().expect("tick failed"),
@@ -249,11 +386,35 @@ impl Server {
         }
     }
 
+    /// Handles a Raft node event. Role and term changes are already logged by
+    /// the Raft node itself, so only peer changes are logged at higher levels.
+    fn raft_event(
+        event: raft::Event,
+        peers_tx: &mut HashMap<raft::NodeID, Sender<raft::Envelope>>,
+        tls: Option<&tls::Config>,
+    ) {
+        match event {
+            raft::Event::PeerAdded { peer, ref address } => {
+                info!("Raft event: {event}");
+                if !peers_tx.contains_key(&peer) {
+                    Self::raft_connect_peer(peer, address.clone(), peers_tx, tls);
+                }
+            }
+            raft::Event::PeerUnreachable { .. } => warn!("Raft event: {event}"),
+            raft::Event::PeerReachable { .. } => info!("Raft event: {event}"),
+            event => debug!("Raft event: {event}"),
+        }
+    }
+
     /// Accepts new SQL client connections and spawns session threads for them.
     fn sql_accept(
         id: raft::NodeID,
         listener: TcpListener,
+        engine: sql::engine::Raft,
         raft_request_tx: Sender<(raft::Request, Sender<Result<raft::Response>>)>,
+        peers_sql: HashMap<raft::NodeID, String>,
+        tls: Option<&tls::Config>,
+        auth: Option<&Auth>,
     ) {
         std::thread::scope(|s| loop {
             let (socket, peer) = match listener.accept() {
@@ -263,10 +424,18 @@ impl Server {
                     continue;
                 }
             };
+            let engine = engine.clone();
             let raft_request_tx = raft_request_tx.clone();
+            let peers_sql = &peers_sql;
             s.spawn(move || {
                 debug!("Client {peer} connected");
-                match Self::sql_session(id, socket, raft_request_tx) {
+                let result = match tls {
+                    Some(tls) => tls.accept(socket),
+                    None => Ok(socket.into()),
+                };
+                match result.and_then(|socket| {
+                    Self::sql_session(id, socket, engine, raft_request_tx, peers_sql, auth)
+                }) {
                     Ok(()) => debug!("Client {peer} disconnected"),
                     Err(err) => error!("Client {peer} error: {err}"),
                 }
@@ -275,33 +444,94 @@ impl Server {
     }
 
     /// Processes a client SQL session, by executing SQL statements against the
-    /// Raft node.
+    /// Raft node. If authentication is enabled, the client must authenticate
+    /// before any other requests, and is disconnected if it fails to.
     fn sql_session(
         id: raft::NodeID,
-        socket: TcpStream,
+        socket: tls::Stream,
+        engine: sql::engine::Raft,
         raft_request_tx: Sender<(raft::Request, Sender<Result<raft::Response>>)>,
+        peers_sql: &HashMap<raft::NodeID, String>,
+        auth: Option<&Auth>,
     ) -> Result<()> {
-        let mut session = sql::engine::Raft::new(raft_request_tx).session();
+        let mut session = engine.session();
+        let mut authenticated = auth.is_none();
+        let mut cursors = HashMap::new();
+        let mut next_cursor: CursorID = 1;
         let mut reader = std::io::BufReader::new(socket.try_clone()?);
         let mut writer = std::io::BufWriter::new(socket);
 
         while let Some(request) = bincode::maybe_deserialize_from(&mut reader)? {
-            // Execute request.
-            debug!("Received request {request:?}");
+            // Execute request. Don't log passwords, including in statements.
+            match &request {
+                Request::Authenticate { user, .. } => debug!("Received authentication for {user}"),
+                Request::Execute(query) | Request::CopyIn(query) | Request::Open(query) => {
+                    debug!("Received query {}", sql::parser::redact(query))
+                }
+                request => debug!("Received request {request:?}"),
+            }
             let mut response = match request {
+                Request::Authenticate { user, password } => match auth {
+                    Some(auth) if !authenticated => {
+                        auth.authenticate(&mut session, &user, &password).map(|()| {
+                            authenticated = true;
+                            Response::Authenticate
+                        })
+                    }
+                    Some(_) => Err(Error::Value("Already authenticated".into())),
+                    None => Ok(Response::Authenticate),
+                },
+                _ if !authenticated => Err(Error::Unauthenticated),
                 Request::Execute(query) => session.execute(&query).map(Response::Execute),
-                Request::GetTable(table) => session
-                    .with_txn_read_only(|txn| txn.must_read_table(&table))
-                    .map(Response::GetTable),
-                Request::ListTables => session
-                    .with_txn_read_only(|txn| Ok(txn.scan_tables()?.map(|t| t.name).collect()))
-                    .map(Response::ListTables),
+                Request::CopyIn(query) => {
+                    let mut input = CopyInput::new(&mut reader);
+                    let result = session.execute_with_input(&query, &mut input);
+                    input.drain()?;
+                    result.map(Response::Execute)
+                }
+                Request::CopyData(_) | Request::CopyDone | Request::CopyFail(_) => {
+                    Err(Error::Value("Unexpected COPY input".into()))
+                }
+                Request::Open(query) => session.execute(&query).map(|result| match result {
+                    ResultSet::Query { columns, rows } => {
+                        let cursor = next_cursor;
+                        next_cursor += 1;
+                        cursors.insert(cursor, rows);
+                        let result =
+                            ResultSet::Query { columns, rows: Box::new(std::iter::empty()) };
+                        Response::Open { cursor: Some(cursor), result }
+                    }
+                    result => Response::Open { cursor: None, result },
+                }),
+                Request::Fetch { cursor, count } => Self::fetch(&mut cursors, cursor, count)
+                    .map(|(rows, done)| Response::Fetch { rows, done }),
+                Request::Close(cursor) => match cursors.remove(&cursor) {
+                    Some(_) => Ok(Response::Close),
+                    None => Err(Error::Value(format!("Cursor {cursor} does not exist"))),
+                },
+                Request::GetTable(table) => {
+                    Self::get_table(&mut session, &table).map(Response::GetTable)
+                }
+                Request::ListTables => Self::list_tables(&mut session).map(Response::ListTables),
+                Request::GetView(view) => {
+                    Self::get_view(&mut session, &view).map(Response::GetView)
+                }
+                Request::ListViews => Self::list_views(&mut session).map(Response::ListViews),
                 Request::Status => session
                     .status()
                     .map(|s| Status { server: id, raft: s.raft, mvcc: s.mvcc })
                     .map(Response::Status),
+                Request::Join { id, address } => {
+                    Self::join(&raft_request_tx, session.user(), id, address).map(Response::Join)
+                }
             };
 
+            // If the Raft node rejected the request because it isn't the
+            // leader, tell the client where to find the leader.
+            if let Err(Error::NotLeader { leader_id, address: address @ None }) = &mut response {
+                *address = peers_sql.get(leader_id).cloned();
+            }
+
             // Process response.
             debug!("Returning response {response:?}");
             let mut rows: Box<dyn Iterator<Item = Result<Response>> + Send> =
@@ -331,32 +561,280 @@ impl Server {
                 bincode::serialize_into(&mut writer, &row)?;
             }
             writer.flush()?;
+
+            // Disconnect unauthenticated clients.
+            if !authenticated {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fetches up to count rows from an open cursor, returning them and
+    /// whether the cursor is exhausted. Exhausted or failed cursors are closed.
+    fn fetch(
+        cursors: &mut HashMap<CursorID, Rows>,
+        cursor: CursorID,
+        count: usize,
+    ) -> Result<(Vec<Row>, bool)> {
+        let Some(rows) = cursors.get_mut(&cursor) else {
+            return Err(Error::Value(format!("Cursor {cursor} does not exist")));
+        };
+        let mut page = Vec::new();
+        while page.len() < count {
+            match rows.next() {
+                Some(Ok(row)) => page.push(row),
+                Some(Err(err)) => {
+                    cursors.remove(&cursor);
+                    return Err(err);
+                }
+                None => {
+                    cursors.remove(&cursor);
+                    return Ok((page, true));
+                }
+            }
+        }
+        Ok((page, false))
+    }
+
+    /// Reads a table's schema. If the session has a user, tables that the user
+    /// has no privileges on are reported as missing, like in SHOW TABLES.
+    fn get_table(
+        session: &mut sql::engine::Session<sql::engine::Raft>,
+        name: &str,
+    ) -> Result<Table> {
+        let user = session.user().map(str::to_string);
+        session.with_txn_read_only(|txn| {
+            let table = txn.must_read_table(name)?;
+            match user {
+                Some(user) if !txn.has_privilege(&user, &table, None)? => {
+                    Err(Error::Value(format!("Table {} does not exist", name)))
+                }
+                _ => Ok(table),
+            }
+        })
+    }
+
+    /// Lists the names of the tables that the session's user, if any, has
+    /// privileges on.
+    fn list_tables(session: &mut sql::engine::Session<sql::engine::Raft>) -> Result<Vec<String>> {
+        let user = session.user().map(str::to_string);
+        session.with_txn_read_only(|txn| {
+            let mut names = Vec::new();
+            for table in txn.scan_tables()? {
+                if let Some(user) = &user {
+                    if !txn.has_privilege(user, &table, None)? {
+                        continue;
+                    }
+                }
+                names.push(table.name);
+            }
+            Ok(names)
+        })
+    }
+
+    /// Reads a view's definition. If the session has a user, views that the
+    /// user doesn't own are reported as missing.
+    fn get_view(session: &mut sql::engine::Session<sql::engine::Raft>, name: &str) -> Result<View> {
+        let user = session.user().map(str::to_string);
+        session.with_txn_read_only(|txn| {
+            txn.read_view(name)?
+                .filter(|view| user.as_deref().is_none_or(|user| view.owned_by(user)))
+                .ok_or_else(|| Error::Value(format!("View {} does not exist", name)))
+        })
+    }
+
+    /// Lists the names of the views that the session's user, if any, owns.
+    fn list_views(session: &mut sql::engine::Session<sql::engine::Raft>) -> Result<Vec<String>> {
+        let user = session.user().map(str::to_string);
+        session.with_txn_read_only(|txn| {
+            Ok(txn
+                .scan_views()?
+                .filter(|view| user.as_deref().is_none_or(|user| view.owned_by(user)))
+                .map(|view| view.name)
+                .collect())
+        })
+    }
+
+    /// Adds a node to the cluster, with the given Raft address. Returns the
+    /// Raft addresses of all cluster members. If authentication is enabled,
+    /// this requires the root user.
+    fn join(
+        raft_request_tx: &Sender<(raft::Request, Sender<Result<raft::Response>>)>,
+        user: Option<&str>,
+        id: raft::NodeID,
+        address: String,
+    ) -> Result<BTreeMap<raft::NodeID, String>> {
+        if !matches!(user, None | Some(User::ROOT)) {
+            return Err(Error::PermissionDenied(
+                "adding cluster nodes requires the root user".into(),
+            ));
+        }
+        let (response_tx, response_rx) = crossbeam::channel::bounded(1);
+        raft_request_tx.send((raft::Request::AddNode { id, address }, response_tx))?;
+        match response_rx.recv()? {
+            Ok(raft::Response::AddNode(members)) => Ok(members),
+            Ok(response) => Err(Error::Internal(format!("Unexpected Raft response {response:?}"))),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Returns the column names and rows of a query result. EXPLAIN results are
+/// returned as a QUERY PLAN column with a row per line. Other results are
+/// returned as an error, for the caller to handle.
+fn query_rows(
+    result: ResultSet,
+) -> std::result::Result<(Vec<Option<String>>, Rows), Box<ResultSet>> {
+    let plan = match result {
+        ResultSet::Query { columns, rows } => {
+            return Ok((columns.into_iter().map(|c| c.name).collect(), rows))
+        }
+        ResultSet::Explain(plan) => plan.to_string(),
+        ResultSet::ExplainAnalyze { plan, stats } => plan.format_analyzed(&stats),
+        ResultSet::ExplainVerbose { plan, estimates, stats } => {
+            plan.format_verbose(&estimates, &stats)
+        }
+        result => return Err(Box::new(result)),
+    };
+    let rows: Vec<_> = plan.lines().map(|line| Ok(vec![Value::String(line.to_string())])).collect();
+    Ok((vec![Some("QUERY PLAN".to_string())], Box::new(rows.into_iter())))
+}
+
+/// Reads COPY FROM STDIN input from the client, streamed as CopyData requests
+/// following the CopyIn request until CopyDone or CopyFail.
+struct CopyInput<'a, R: Read> {
+    reader: &'a mut R,
+    chunk: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<'a, R: Read> CopyInput<'a, R> {
+    fn new(reader: &'a mut R) -> Self {
+        Self { reader, chunk: Vec::new(), pos: 0, done: false }
+    }
+
+    /// Receives the next input request, returning false once the input ends.
+    fn receive(&mut self) -> Result<bool> {
+        if self.done {
+            return Ok(false);
+        }
+        match bincode::deserialize_from(&mut *self.reader)? {
+            Request::CopyData(chunk) => {
+                self.chunk = chunk;
+                self.pos = 0;
+                Ok(true)
+            }
+            Request::CopyDone => {
+                self.done = true;
+                Ok(false)
+            }
+            Request::CopyFail(message) => {
+                self.done = true;
+                Err(Error::Value(format!("COPY aborted by client: {message}")))
+            }
+            request => Err(Error::Internal(format!("Unexpected COPY request {request:?}"))),
+        }
+    }
+
+    /// Discards any remaining input, e.g. if the statement failed before
+    /// reading all of it, such that the next request can be read.
+    fn drain(&mut self) -> Result<()> {
+        while !self.done {
+            match self.receive() {
+                Ok(_) | Err(Error::Value(_)) => {}
+                Err(err) => return Err(err),
+            }
         }
         Ok(())
     }
 }
 
+impl<R: Read> Read for CopyInput<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos >= self.chunk.len() {
+            match self.receive() {
+                Ok(true) => {}
+                Ok(false) => return Ok(0),
+                Err(err) => return Err(std::io::Error::other(err.to_string())),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// A cursor ID, identifying the open result rows of a query in a session.
+pub type CursorID = u64;
+
 /// A SQL client request.
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Request {
+    /// Authenticates the session as the given user. If authentication is
+    /// enabled, this must be the first request.
+    Authenticate { user: String, password: String },
     /// Executes a SQL statement.
     Execute(String),
+    /// Executes a SQL statement like Execute, but keeps the rows of a query
+    /// result open as a cursor instead of returning them. The rows are then
+    /// fetched in pages via Fetch, until exhausted or closed via Close.
+    Open(String),
+    /// Fetches up to count rows from an open cursor.
+    Fetch { cursor: CursorID, count: usize },
+    /// Closes an open cursor.
+    Close(CursorID),
+    /// Executes a COPY FROM STDIN statement, followed by its input as a stream
+    /// of CopyData chunks terminated by CopyDone or CopyFail. The statement
+    /// result is returned once the input has been read.
+    CopyIn(String),
+    /// A chunk of COPY FROM STDIN input.
+    CopyData(#[serde(with = "serde_bytes")] Vec<u8>),
+    /// Ends COPY FROM STDIN input.
+    CopyDone,
+    /// Aborts COPY FROM STDIN input with an error message, failing the statement.
+    CopyFail(String),
     /// Fetches the given table schema.
     GetTable(String),
     /// Lists all tables.
     ListTables,
+    /// Fetches the given view definition.
+    GetView(String),
+    /// Lists all views.
+    ListViews,
     /// Returns server status.
     Status,
+    /// Adds a node to the cluster, with the given Raft address.
+    Join { id: raft::NodeID, address: String },
 }
 
 /// A SQL server response.
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Response {
+    Authenticate,
     Execute(ResultSet),
     Row(Option<Row>),
+    /// The statement result, with no rows for queries. Queries return a
+    /// cursor to fetch the rows from.
+    Open {
+        cursor: Option<CursorID>,
+        result: ResultSet,
+    },
+    /// A page of rows, and whether the cursor was exhausted and closed.
+    Fetch {
+        rows: Vec<Row>,
+        done: bool,
+    },
+    Close,
     GetTable(Table),
     ListTables(Vec<String>),
+    GetView(View),
+    ListViews(Vec<String>),
     Status(Status),
+    /// The Raft addresses of all cluster members, including the joined node.
+    Join(BTreeMap<raft::NodeID, String>),
 }
 
 /// SQL server status.
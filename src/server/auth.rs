@@ -0,0 +1,67 @@
+//! Client authentication. When enabled, clients must authenticate as a user
+//! created via CREATE USER, or as the built-in root user whose password is
+//! given in the server configuration (e.g. to create the first users).
+
+use crate::error::{Error, Result};
+use crate::sql;
+use crate::sql::engine::Session;
+use crate::sql::schema::{Catalog as _, Password, User};
+
+use base64::Engine as _;
+
+/// Authenticates clients against the users catalog.
+#[derive(Clone)]
+pub struct Auth {
+    /// The root user's password hash, or None if root can't authenticate.
+    root: Option<Password>,
+}
+
+impl Auth {
+    /// Creates a new authenticator, with the given root password if any.
+    pub fn new(root_password: Option<&str>) -> Result<Self> {
+        Ok(Self { root: root_password.map(Password::new).transpose()? })
+    }
+
+    /// Authenticates a user with the given password, reading the user via the
+    /// session, and sets it as the session's user. Returns
+    /// Error::Unauthenticated if the user doesn't exist or the password is
+    /// invalid.
+    pub fn authenticate(
+        &self,
+        session: &mut Session<sql::engine::Raft>,
+        user: &str,
+        password: &str,
+    ) -> Result<()> {
+        let valid = match user {
+            User::ROOT => self.root.as_ref().is_some_and(|root| root.verify(password)),
+            user => session
+                .with_txn_read_only(|txn| txn.read_user(user))?
+                .is_some_and(|user| user.verify_password(password)),
+        };
+        if !valid {
+            return Err(Error::Unauthenticated);
+        }
+        session.set_user(user);
+        Ok(())
+    }
+
+    /// Authenticates HTTP Basic credentials given as an Authorization header
+    /// value, as used by the HTTP and gRPC listeners, like authenticate().
+    /// Returns the authenticated user name.
+    pub fn authenticate_basic(
+        &self,
+        session: &mut Session<sql::engine::Raft>,
+        header: Option<&str>,
+    ) -> Result<String> {
+        let (user, password) = header
+            .and_then(|header| header.strip_prefix("Basic "))
+            .and_then(|encoded| base64::prelude::BASE64_STANDARD.decode(encoded.trim()).ok())
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .and_then(|decoded| {
+                decoded.split_once(':').map(|(u, p)| (u.to_string(), p.to_string()))
+            })
+            .ok_or(Error::Unauthenticated)?;
+        self.authenticate(session, &user, &password)?;
+        Ok(user)
+    }
+}
@@ -0,0 +1,362 @@
+//! A gRPC server for the toyDB service in proto/toydb.proto, which is easier
+//! to use from other languages than the native bincode protocol. Each call
+//! executes in a new SQL session. Stream returns query rows in batches as
+//! they're produced, such that large results don't have to be buffered.
+//!
+//! If authentication is enabled, calls must have authorization metadata with
+//! HTTP Basic credentials for a user, like the HTTP API.
+//!
+//! The server runs on a Tokio runtime, while statements are executed on its
+//! blocking thread pool since the SQL engine is synchronous.
+
+use super::{Auth, Server};
+use crate::error::{Error, Result};
+use crate::raft;
+use crate::sql;
+use crate::sql::engine::{Engine as _, Session};
+use crate::sql::types::{Row, Rows, Value};
+use crate::storage;
+
+use crossbeam::channel::Sender;
+use proto::toy_db_server::{ToyDb, ToyDbServer};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+
+/// The generated gRPC service and messages.
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("toydb");
+}
+
+/// The number of rows in each Stream response.
+const STREAM_BATCH_SIZE: usize = 100;
+
+/// The number of Stream responses buffered before waiting for the client.
+const STREAM_BUFFER_SIZE: usize = 4;
+
+/// Serves gRPC requests until the server fails.
+pub fn serve(
+    id: raft::NodeID,
+    listener: std::net::TcpListener,
+    engine: sql::engine::Raft,
+    raft_request_tx: Sender<(raft::Request, Sender<Result<raft::Response>>)>,
+    auth: Option<Auth>,
+) -> Result<()> {
+    listener.set_nonblocking(true)?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::from_std(listener)?;
+        tonic::transport::Server::builder()
+            .add_service(ToyDbServer::new(Service { id, engine, raft_request_tx, auth }))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .map_err(|err| Error::Internal(format!("gRPC server failed: {err}")))
+    })
+}
+
+/// The gRPC service implementation.
+struct Service {
+    id: raft::NodeID,
+    engine: sql::engine::Raft,
+    raft_request_tx: Sender<(raft::Request, Sender<Result<raft::Response>>)>,
+    auth: Option<Auth>,
+}
+
+impl Service {
+    /// Authenticates a call via its authorization metadata, if authentication
+    /// is enabled. Returns the authenticated user, whose privileges are
+    /// checked by the call's statements.
+    async fn authenticate(
+        &self,
+        metadata: &tonic::metadata::MetadataMap,
+    ) -> std::result::Result<Option<String>, tonic::Status> {
+        let Some(auth) = self.auth.clone() else {
+            return Ok(None);
+        };
+        let header = metadata.get("authorization").and_then(|h| h.to_str().ok()).map(String::from);
+        self.with_session(move |session| auth.authenticate_basic(session, header.as_deref()))
+            .await
+            .map(Some)
+    }
+
+    /// Runs a closure with a new session on the blocking thread pool.
+    async fn with_session<F, R>(&self, f: F) -> std::result::Result<R, tonic::Status>
+    where
+        F: FnOnce(&mut Session<sql::engine::Raft>) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let engine = self.engine.clone();
+        tokio::task::spawn_blocking(move || f(&mut engine.session()))
+            .await
+            .map_err(|err| tonic::Status::internal(err.to_string()))?
+            .map_err(to_status)
+    }
+
+    /// Runs a closure with a new session for the given user, if any, on the
+    /// blocking thread pool.
+    async fn with_user_session<F, R>(
+        &self,
+        user: Option<String>,
+        f: F,
+    ) -> std::result::Result<R, tonic::Status>
+    where
+        F: FnOnce(&mut Session<sql::engine::Raft>) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.with_session(move |session| {
+            if let Some(user) = &user {
+                session.set_user(user);
+            }
+            f(session)
+        })
+        .await
+    }
+}
+
+#[tonic::async_trait]
+impl ToyDb for Service {
+    async fn execute(
+        &self,
+        request: tonic::Request<proto::ExecuteRequest>,
+    ) -> std::result::Result<tonic::Response<proto::ExecuteResponse>, tonic::Status> {
+        let user = self.authenticate(request.metadata()).await?;
+        let request = request.into_inner();
+        let response = self
+            .with_user_session(user, move |session| {
+                let (mut response, rows) = execute(session, request)?;
+                for row in rows {
+                    response.rows.push(to_proto_row(row?));
+                }
+                Ok(response)
+            })
+            .await?;
+        Ok(tonic::Response::new(response))
+    }
+
+    type StreamStream = ReceiverStream<std::result::Result<proto::ExecuteResponse, tonic::Status>>;
+
+    async fn stream(
+        &self,
+        request: tonic::Request<proto::ExecuteRequest>,
+    ) -> std::result::Result<tonic::Response<Self::StreamStream>, tonic::Status> {
+        let user = self.authenticate(request.metadata()).await?;
+        let request = request.into_inner();
+        let engine = self.engine.clone();
+        let (tx, rx) = tokio::sync::mpsc::channel(STREAM_BUFFER_SIZE);
+        // The session must outlive the rows, so it's owned by the thread.
+        tokio::task::spawn_blocking(move || {
+            let mut session = engine.session();
+            if let Some(user) = &user {
+                session.set_user(user);
+            }
+            if let Err(err) = stream(&mut session, request, &tx) {
+                _ = tx.blocking_send(Err(to_status(err)));
+            }
+        });
+        Ok(tonic::Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn status(
+        &self,
+        request: tonic::Request<proto::StatusRequest>,
+    ) -> std::result::Result<tonic::Response<proto::StatusResponse>, tonic::Status> {
+        self.authenticate(request.metadata()).await?;
+        let id = self.id;
+        let status = self.with_session(|session| session.status()).await?;
+        Ok(tonic::Response::new(proto::StatusResponse {
+            server: id.into(),
+            raft: Some(proto::RaftStatus {
+                leader: status.raft.leader.into(),
+                term: status.raft.term,
+                last_index: status
+                    .raft
+                    .last_index
+                    .into_iter()
+                    .map(|(k, v)| (k.into(), v))
+                    .collect(),
+                commit_index: status.raft.commit_index,
+                apply_index: status.raft.apply_index,
+                storage: Some(storage_status(status.raft.storage)),
+            }),
+            mvcc: Some(proto::MvccStatus {
+                versions: status.mvcc.versions,
+                active_txns: status.mvcc.active_txns,
+                storage: Some(storage_status(status.mvcc.storage)),
+            }),
+        }))
+    }
+
+    async fn admin(
+        &self,
+        request: tonic::Request<proto::AdminRequest>,
+    ) -> std::result::Result<tonic::Response<proto::AdminResponse>, tonic::Status> {
+        use proto::admin_request::Request;
+        let user = self.authenticate(request.metadata()).await?;
+        let mut response = proto::AdminResponse::default();
+        match request.into_inner().request {
+            Some(Request::GetTable(table)) => {
+                response.definition = self
+                    .with_user_session(user, move |session| Server::get_table(session, &table))
+                    .await?
+                    .to_string();
+            }
+            Some(Request::ListTables(_)) => {
+                response.names = self.with_user_session(user, Server::list_tables).await?;
+            }
+            Some(Request::GetView(view)) => {
+                response.definition = self
+                    .with_user_session(user, move |session| Server::get_view(session, &view))
+                    .await?
+                    .to_string();
+            }
+            Some(Request::ListViews(_)) => {
+                response.names = self.with_user_session(user, Server::list_views).await?;
+            }
+            Some(Request::Join(join)) => {
+                let raft_request_tx = self.raft_request_tx.clone();
+                response.members = tokio::task::spawn_blocking(move || {
+                    Server::join(
+                        &raft_request_tx,
+                        user.as_deref(),
+                        join.id.try_into()?,
+                        join.address,
+                    )
+                })
+                .await
+                .map_err(|err| tonic::Status::internal(err.to_string()))?
+                .map_err(to_status)?
+                .into_iter()
+                .map(|(id, address)| (id.into(), address))
+                .collect();
+            }
+            None => return Err(tonic::Status::invalid_argument("Missing admin request")),
+        }
+        Ok(tonic::Response::new(response))
+    }
+}
+
+/// Executes a statement, returning a response with its columns or result,
+/// and its rows (if any).
+fn execute(
+    session: &mut Session<sql::engine::Raft>,
+    request: proto::ExecuteRequest,
+) -> Result<(proto::ExecuteResponse, Rows)> {
+    let params = request.params.iter().map(literal).collect::<Vec<_>>();
+    let query = super::postgres::substitute(&request.query, &params)?;
+    let mut response = proto::ExecuteResponse::default();
+    match super::query_rows(session.execute(&query)?) {
+        Ok((columns, rows)) => {
+            response.columns = columns.into_iter().map(Option::unwrap_or_default).collect();
+            Ok((response, rows))
+        }
+        Err(result) => {
+            response.result =
+                serde_json::to_string(&result).map_err(|err| Error::Internal(err.to_string()))?;
+            Ok((response, Box::new(std::iter::empty())))
+        }
+    }
+}
+
+/// Executes a statement and sends its result as Stream responses, with up to
+/// STREAM_BATCH_SIZE rows each. If the client goes away, the statement is
+/// abandoned.
+fn stream(
+    session: &mut Session<sql::engine::Raft>,
+    request: proto::ExecuteRequest,
+    tx: &tokio::sync::mpsc::Sender<std::result::Result<proto::ExecuteResponse, tonic::Status>>,
+) -> Result<()> {
+    let (mut response, mut rows) = execute(session, request)?;
+    let mut first = true;
+    loop {
+        let mut done = false;
+        while response.rows.len() < STREAM_BATCH_SIZE {
+            let Some(row) = rows.next().transpose()? else {
+                done = true;
+                break;
+            };
+            response.rows.push(to_proto_row(row));
+        }
+        // Skip an empty final response, unless it's the only one.
+        if (first || !response.rows.is_empty())
+            && tx.blocking_send(Ok(std::mem::take(&mut response))).is_err()
+        {
+            return Ok(());
+        }
+        if done {
+            return Ok(());
+        }
+        first = false;
+    }
+}
+
+/// Converts an SQL row to a protobuf row.
+fn to_proto_row(row: Row) -> proto::Row {
+    proto::Row { values: row.into_iter().map(to_proto).collect() }
+}
+
+/// Converts an SQL value to a protobuf value.
+fn to_proto(value: Value) -> proto::Value {
+    use proto::value::Value as V;
+    let value = match value {
+        Value::Null => None,
+        Value::Boolean(b) => Some(V::Boolean(b)),
+        Value::Integer(i) => Some(V::Integer(i)),
+        Value::Float(f) => Some(V::Float(f)),
+        Value::String(s) => Some(V::String(s)),
+        Value::Bytes(b) => Some(V::Bytes(b)),
+        Value::Array(values) => {
+            Some(V::Array(proto::Array { values: values.into_iter().map(to_proto).collect() }))
+        }
+        value => Some(V::Text(value.to_string())),
+    };
+    proto::Value { value }
+}
+
+/// Converts a protobuf parameter value into an SQL literal.
+fn literal(value: &proto::Value) -> String {
+    use proto::value::Value as V;
+    match &value.value {
+        None => "NULL".to_string(),
+        Some(V::Boolean(true)) => "TRUE".to_string(),
+        Some(V::Boolean(false)) => "FALSE".to_string(),
+        Some(V::Integer(i)) => format!("({i})"),
+        Some(V::Float(f)) if f.is_nan() => "NAN".to_string(),
+        Some(V::Float(f)) if f.is_infinite() && *f > 0.0 => "INFINITY".to_string(),
+        Some(V::Float(f)) if f.is_infinite() => "(-INFINITY)".to_string(),
+        Some(V::Float(f)) => format!("({f:?})"),
+        Some(V::String(s) | V::Text(s)) => format!("'{}'", s.replace('\'', "''")),
+        Some(V::Bytes(b)) => format!("x'{}'", hex::encode(b)),
+        Some(V::Array(array)) => {
+            format!("ARRAY[{}]", array.values.iter().map(literal).collect::<Vec<_>>().join(", "))
+        }
+    }
+}
+
+/// Converts a storage engine status to a protobuf status.
+fn storage_status(status: storage::engine::Status) -> proto::StorageStatus {
+    proto::StorageStatus {
+        name: status.name,
+        keys: status.keys,
+        size: status.size,
+        total_disk_size: status.total_disk_size,
+        live_disk_size: status.live_disk_size,
+        garbage_disk_size: status.garbage_disk_size,
+    }
+}
+
+/// Converts an error to a gRPC status.
+fn to_status(err: Error) -> tonic::Status {
+    let message = err.to_string();
+    match err {
+        Error::Parse(_) | Error::Syntax { .. } | Error::Value(_) => {
+            tonic::Status::invalid_argument(message)
+        }
+        Error::ReadOnly => tonic::Status::failed_precondition(message),
+        Error::Abort | Error::Serialization => tonic::Status::aborted(message),
+        Error::NotLeader { .. } => tonic::Status::unavailable(message),
+        Error::Timeout => tonic::Status::deadline_exceeded(message),
+        Error::Unauthenticated => tonic::Status::unauthenticated(message),
+        Error::PermissionDenied(_) => tonic::Status::permission_denied(message),
+        Error::OutOfMemory => tonic::Status::resource_exhausted(message),
+        Error::Config(_) | Error::Internal(_) => tonic::Status::internal(message),
+    }
+}
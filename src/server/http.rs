@@ -0,0 +1,331 @@
+//! An HTTP listener with a JSON API, which allows toyDB to be used via curl
+//! and other HTTP clients instead of the toyDB client. It has the endpoints:
+//!
+//! - POST /query: executes a single SQL statement in a new session, i.e. in
+//!   its own implicit transaction. The body is either the statement text, or
+//!   a JSON object (with Content-Type: application/json) such as
+//!   {"query": "SELECT * FROM movies WHERE id = $1", "params": [1]}, where
+//!   parameters are substituted into the statement as SQL literals. Query
+//!   results are returned as {"columns": [...], "rows": [[...], ...]}, other
+//!   results as their JSON-encoded ResultSet, e.g. {"Create": {"count": 1}}.
+//!
+//! - GET /status: returns the server status as JSON, like the toyDB client's
+//!   !status command.
+//!
+//! - GET /healthz: returns 200 OK if the server is running.
+//!
+//! If authentication is enabled, /query and /status require HTTP Basic
+//! credentials for a user, e.g. via curl -u user:password.
+//!
+//! Errors are returned as {"error": "message"}, with a 4xx or 5xx status
+//! code. Only the HTTP/1.1 subset needed by common clients is supported: the
+//! request body must have a Content-Length, and there's no TLS.
+
+use super::{Auth, Status};
+use crate::error::{Error, Result};
+use crate::raft;
+use crate::sql;
+use crate::sql::engine::{Engine as _, Session};
+use crate::sql::types::{json, Value};
+
+use log::{debug, error};
+use serde_json::{json, Value as Json};
+use std::collections::HashMap;
+use std::io::{BufRead as _, BufReader, BufWriter, Read as _, Write as _};
+use std::net::{TcpListener, TcpStream};
+
+/// The maximum request line or header size accepted from clients.
+const MAX_HEADER_SIZE: u64 = 64 * 1024;
+
+/// The maximum request body size accepted from clients.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Accepts HTTP client connections and spawns threads serving them.
+pub fn accept(
+    id: raft::NodeID,
+    listener: TcpListener,
+    engine: sql::engine::Raft,
+    auth: Option<Auth>,
+) {
+    std::thread::scope(|s| loop {
+        let (socket, peer) = match listener.accept() {
+            Ok(sp) => sp,
+            Err(err) => {
+                error!("HTTP client accept failed: {err}");
+                continue;
+            }
+        };
+        let engine = engine.clone();
+        let auth = auth.as_ref();
+        s.spawn(move || {
+            debug!("HTTP client {peer} connected");
+            match serve(id, socket, engine, auth) {
+                Ok(()) => debug!("HTTP client {peer} disconnected"),
+                Err(err) => error!("HTTP client {peer} error: {err}"),
+            }
+        });
+    })
+}
+
+/// An HTTP request.
+struct Request {
+    method: String,
+    /// The request path, without the query string.
+    path: String,
+    /// Header values, by lowercase header name.
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+    /// If true, the connection should be closed after the response.
+    close: bool,
+}
+
+/// An HTTP response, with a JSON body.
+struct Response {
+    status: u16,
+    body: Json,
+}
+
+impl Response {
+    /// Creates a successful response.
+    fn ok(body: Json) -> Self {
+        Self { status: 200, body }
+    }
+
+    /// Creates an error response with the given status code.
+    fn error(status: u16, message: &str) -> Self {
+        Self { status, body: json!({ "error": message }) }
+    }
+}
+
+impl From<Error> for Response {
+    fn from(err: Error) -> Self {
+        let status = match err {
+            Error::Parse(_) | Error::Syntax { .. } | Error::Value(_) | Error::ReadOnly => 400,
+            Error::Unauthenticated => 401,
+            Error::PermissionDenied(_) => 403,
+            Error::Serialization => 409,
+            Error::Abort | Error::NotLeader { .. } => 503,
+            Error::Config(_) | Error::Internal(_) | Error::OutOfMemory | Error::Timeout => 500,
+        };
+        Self::error(status, &err.to_string())
+    }
+}
+
+/// Serves HTTP requests on a connection until the client closes it.
+fn serve(
+    id: raft::NodeID,
+    socket: TcpStream,
+    engine: sql::engine::Raft,
+    auth: Option<&Auth>,
+) -> Result<()> {
+    let mut reader = BufReader::new(socket.try_clone()?);
+    let mut writer = BufWriter::new(socket);
+    loop {
+        let (response, close) = match read_request(&mut reader) {
+            Ok(Some(request)) => {
+                debug!("Received HTTP request {} {}", request.method, request.path);
+                (handle(id, &engine, auth, &request), request.close)
+            }
+            Ok(None) => return Ok(()),
+            // Malformed requests can't be recovered from, since the start of
+            // the next request is unknown.
+            Err(err) => (Response::error(400, &err.to_string()), true),
+        };
+        write_response(&mut writer, &response, close)?;
+        if close {
+            return Ok(());
+        }
+    }
+}
+
+/// Handles a request in a new session, routing it to its endpoint. Health
+/// checks don't require authentication.
+fn handle(
+    id: raft::NodeID,
+    engine: &sql::engine::Raft,
+    auth: Option<&Auth>,
+    request: &Request,
+) -> Response {
+    let mut session = engine.session();
+    if let Some(auth) = auth.filter(|_| request.path != "/healthz") {
+        let header = request.headers.get("authorization").map(String::as_str);
+        if let Err(err) = auth.authenticate_basic(&mut session, header) {
+            return Response::from(err);
+        }
+    }
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/query") => query(&mut session, request),
+        ("GET", "/status") => status(id, &mut session),
+        ("GET", "/healthz") => Ok(json!("ok")),
+        (_, "/query") => return Response::error(405, "Method not allowed, use POST"),
+        (_, "/status" | "/healthz") => return Response::error(405, "Method not allowed, use GET"),
+        (_, path) => return Response::error(404, &format!("Path {path} not found")),
+    };
+    result.map_or_else(Response::from, Response::ok)
+}
+
+/// Executes a query in the request's session, returning its result as JSON.
+fn query(session: &mut Session<sql::engine::Raft>, request: &Request) -> Result<Json> {
+    let is_json = request
+        .headers
+        .get("content-type")
+        .is_some_and(|t| t.split(';').next().unwrap_or("").trim() == "application/json");
+    let (query, params) = if is_json {
+        let body: Json = serde_json::from_slice(&request.body)
+            .map_err(|err| Error::Value(format!("Invalid JSON body: {err}")))?;
+        let Some(query) = body.get("query").and_then(Json::as_str) else {
+            return Err(Error::Value("Missing query string in JSON body".into()));
+        };
+        let params = match body.get("params") {
+            None | Some(Json::Null) => Vec::new(),
+            Some(Json::Array(params)) => params.iter().map(literal).collect(),
+            Some(_) => return Err(Error::Value("Query params must be an array".into())),
+        };
+        (query.to_string(), params)
+    } else {
+        (String::from_utf8(request.body.clone())?, Vec::new())
+    };
+    let query = super::postgres::substitute(&query, &params)?;
+
+    match super::query_rows(session.execute(&query)?) {
+        Ok((columns, rows)) => {
+            let rows = rows
+                .map(|row| row?.into_iter().map(to_json).collect::<Result<Vec<_>>>())
+                .collect::<Result<Vec<_>>>()?;
+            Ok(json!({ "columns": columns, "rows": rows }))
+        }
+        Err(result) => {
+            serde_json::to_value(&result).map_err(|err| Error::Internal(err.to_string()))
+        }
+    }
+}
+
+/// Returns the server status as JSON.
+fn status(id: raft::NodeID, session: &mut Session<sql::engine::Raft>) -> Result<Json> {
+    let status = session.status()?;
+    let status = Status { server: id, raft: status.raft, mvcc: status.mvcc };
+    serde_json::to_value(status).map_err(|err| Error::Internal(err.to_string()))
+}
+
+/// Converts a result value to JSON. Floats without a JSON representation
+/// (NaN and infinity) are returned as strings, as are values without a JSON
+/// equivalent such as dates.
+fn to_json(value: Value) -> Result<Json> {
+    match value {
+        Value::Float(f) if !f.is_finite() => Ok(Json::String(f.to_string())),
+        value => json::to_json(value),
+    }
+}
+
+/// Converts a JSON query parameter into an SQL literal. Arrays and objects
+/// are passed as strings containing their JSON text.
+fn literal(param: &Json) -> String {
+    match param {
+        Json::Null => "NULL".to_string(),
+        Json::Bool(true) => "TRUE".to_string(),
+        Json::Bool(false) => "FALSE".to_string(),
+        Json::Number(n) => format!("({n})"),
+        Json::String(s) => format!("'{}'", s.replace('\'', "''")),
+        json => format!("'{}'", json.to_string().replace('\'', "''")),
+    }
+}
+
+/// Reads an HTTP request from the client, or None if the client closed the
+/// connection.
+fn read_request(reader: &mut BufReader<TcpStream>) -> Result<Option<Request>> {
+    let Some(line) = read_line(reader)? else {
+        return Ok(None);
+    };
+    let mut parts = line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(Error::Value(format!("Invalid request line {line}")));
+    };
+    if !matches!(version, "HTTP/1.0" | "HTTP/1.1") {
+        return Err(Error::Value(format!("Unsupported HTTP version {version}")));
+    }
+    let path = target.split_once('?').map_or(target, |(path, _)| path).to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let Some(line) = read_line(reader)? else {
+            return Err(Error::Value("Unexpected end of request headers".into()));
+        };
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(Error::Value(format!("Invalid header {line}")));
+        };
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    if headers.contains_key("transfer-encoding") {
+        return Err(Error::Value("Transfer-Encoding is not supported".into()));
+    }
+    let length = match headers.get("content-length") {
+        Some(length) => length.parse::<usize>()?,
+        None => 0,
+    };
+    if length > MAX_BODY_SIZE {
+        return Err(Error::Value(format!("Request body exceeds {MAX_BODY_SIZE} bytes")));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    let connection = headers.get("connection").map(|c| c.to_lowercase());
+    let close = match version {
+        "HTTP/1.0" => connection.as_deref() != Some("keep-alive"),
+        _ => connection.as_deref() == Some("close"),
+    };
+    Ok(Some(Request { method: method.to_string(), path, headers, body, close }))
+}
+
+/// Reads a CRLF-terminated line, without the line terminator. Returns None at
+/// the end of the stream.
+fn read_line(reader: &mut BufReader<TcpStream>) -> Result<Option<String>> {
+    let mut line = String::new();
+    if reader.by_ref().take(MAX_HEADER_SIZE).read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let Some(line) = line.strip_suffix('\n') else {
+        return Err(Error::Value("Incomplete or too long request line".into()));
+    };
+    Ok(Some(line.strip_suffix('\r').unwrap_or(line).to_string()))
+}
+
+/// Writes an HTTP response to the client.
+fn write_response(
+    writer: &mut BufWriter<TcpStream>,
+    response: &Response,
+    close: bool,
+) -> Result<()> {
+    let body = format!("{}\n", response.body);
+    write!(writer, "HTTP/1.1 {} {}\r\n", response.status, reason(response.status))?;
+    write!(writer, "Content-Type: application/json\r\n")?;
+    write!(writer, "Content-Length: {}\r\n", body.len())?;
+    if response.status == 401 {
+        write!(writer, "WWW-Authenticate: Basic realm=\"toydb\"\r\n")?;
+    }
+    if close {
+        write!(writer, "Connection: close\r\n")?;
+    }
+    write!(writer, "\r\n{body}")?;
+    Ok(writer.flush()?)
+}
+
+/// Returns the reason phrase for a status code.
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
@@ -0,0 +1,956 @@
+//! A PostgreSQL wire protocol (v3) listener, which allows psql and PostgreSQL
+//! drivers to talk to toyDB directly instead of via the toyDB client. See:
+//! https://www.postgresql.org/docs/current/protocol.html
+//!
+//! It supports startup (with cleartext password authentication if enabled,
+//! but without TLS), the simple query protocol, and the extended query protocol (Parse, Bind, Describe, Execute,
+//! Close, Sync, and Flush). It has a few limitations:
+//!
+//! - The binary format is only supported for parameters and results of
+//!   common scalar types: booleans, integers, floats, strings, bytes, JSON,
+//!   and (for results) UUIDs. Other types must use the text format.
+//!
+//! - Bind substitutes parameters ($1, $2, ...) into the statement as SQL
+//!   literals, since toyDB doesn't have user-facing bind parameters. Values
+//!   of numeric and boolean parameter types become number and boolean
+//!   literals, others (including unspecified types) become string literals,
+//!   which toyDB converts when compared with other types.
+//!
+//! - toyDB result columns don't have types, so the row description uses the
+//!   types of the values in the first row, or TEXT for NULL values.
+//!
+//! - Describing a prepared statement returns its parameters but no row
+//!   description, since this requires executing the statement. Describing a
+//!   portal executes it. Drivers that rely on statement descriptions to decode
+//!   rows (e.g. prepared queries in rust-postgres) should use simple queries.
+//!
+//! - COPY and query cancellation (CancelRequest) aren't supported.
+
+use super::Auth;
+use crate::error::{Error, Result};
+use crate::sql;
+use crate::sql::engine::{Engine as _, Session};
+use crate::sql::execution::ResultSet;
+use crate::sql::parser::{Lexer, Token};
+use crate::sql::schema::Catalog as _;
+use crate::sql::types::{Row, Rows, Value};
+
+use log::{debug, error};
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter, Read, Write as _};
+use std::net::{TcpListener, TcpStream};
+
+/// The protocol version 3.0 startup code.
+const PROTOCOL_VERSION: i32 = 196608;
+/// The SSLRequest startup code.
+const SSL_REQUEST: i32 = 80877103;
+/// The GSSENCRequest startup code.
+const GSSENC_REQUEST: i32 = 80877104;
+/// The CancelRequest startup code.
+const CANCEL_REQUEST: i32 = 80877102;
+
+/// The maximum message size accepted from clients.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// The server version reported to clients. Clients use it to determine which
+/// features are available, so this should be a PostgreSQL version.
+const SERVER_VERSION: &str = "14.0 (toyDB)";
+
+/// PostgreSQL type OIDs.
+mod oid {
+    pub const BOOL: u32 = 16;
+    pub const BYTEA: u32 = 17;
+    pub const INT8: u32 = 20;
+    pub const INT2: u32 = 21;
+    pub const INT4: u32 = 23;
+    pub const TEXT: u32 = 25;
+    pub const JSON: u32 = 114;
+    pub const FLOAT4: u32 = 700;
+    pub const FLOAT8: u32 = 701;
+    pub const DATE: u32 = 1082;
+    pub const TIME: u32 = 1083;
+    pub const TIMESTAMP: u32 = 1114;
+    pub const INTERVAL: u32 = 1186;
+    pub const NUMERIC: u32 = 1700;
+    pub const UUID: u32 = 2950;
+}
+
+/// Accepts PostgreSQL client connections and spawns session threads for them.
+pub fn accept(listener: TcpListener, engine: sql::engine::Raft, auth: Option<Auth>) {
+    std::thread::scope(|s| loop {
+        let (socket, peer) = match listener.accept() {
+            Ok(sp) => sp,
+            Err(err) => {
+                error!("PostgreSQL client accept failed: {err}");
+                continue;
+            }
+        };
+        let engine = engine.clone();
+        let auth = auth.as_ref();
+        s.spawn(move || {
+            debug!("PostgreSQL client {peer} connected");
+            match Connection::new(socket, engine.session()).and_then(|c| c.serve(auth)) {
+                Ok(()) => debug!("PostgreSQL client {peer} disconnected"),
+                Err(err) => error!("PostgreSQL client {peer} error: {err}"),
+            }
+        });
+    })
+}
+
+/// A prepared statement, created by Parse.
+struct Statement {
+    /// The statement text, or None if empty.
+    query: Option<String>,
+    /// The parameter type OIDs given by the client, 0 if unspecified.
+    types: Vec<u32>,
+}
+
+/// A portal, created by Bind from a prepared statement and parameters.
+struct Portal {
+    /// The statement text with parameters substituted, or None if empty.
+    query: Option<String>,
+    /// The result column format codes: empty for all text, one for all
+    /// columns, or one per column.
+    formats: Vec<i16>,
+    /// The statement output, once executed by Describe or Execute.
+    output: Option<Output>,
+}
+
+/// The output of an executed statement.
+enum Output {
+    /// Result rows, with column names and type OIDs, the command tag, and the
+    /// number of rows returned so far.
+    Rows { columns: Vec<(String, u32)>, rows: Rows, tag: &'static str, count: u64 },
+    /// A completed statement with the given command tag.
+    Complete(String),
+}
+
+/// A PostgreSQL client connection, executing statements in a SQL session.
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+    session: Session<sql::engine::Raft>,
+    /// Prepared statements by name. The unnamed statement has an empty name.
+    statements: HashMap<String, Statement>,
+    /// Portals by name. The unnamed portal has an empty name.
+    portals: HashMap<String, Portal>,
+    /// If true, an extended query message failed and messages are discarded
+    /// until the next Sync.
+    failed: bool,
+}
+
+impl Connection {
+    /// Creates a new connection for the given socket.
+    fn new(socket: TcpStream, session: Session<sql::engine::Raft>) -> Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(socket.try_clone()?),
+            writer: BufWriter::new(socket),
+            session,
+            statements: HashMap::new(),
+            portals: HashMap::new(),
+            failed: false,
+        })
+    }
+
+    /// Serves the connection until the client terminates it.
+    fn serve(mut self, auth: Option<&Auth>) -> Result<()> {
+        if !self.startup(auth)? {
+            return Ok(());
+        }
+        while let Some((tag, body)) = self.receive()? {
+            let mut body = Reader::new(&body);
+            if self.failed && tag != b'S' {
+                continue;
+            }
+            let result = match tag {
+                b'Q' => self.query(body.string()?),
+                b'P' => self.parse(body.string()?, body.string()?, &mut body),
+                b'B' => self.bind(body.string()?, body.string()?, &mut body),
+                b'D' => self.describe(body.byte()?, body.string()?),
+                b'E' => self.execute(body.string()?, body.i32()?),
+                b'C' => self.close(body.byte()?, body.string()?),
+                b'S' => self.sync(),
+                b'H' => Ok(()),
+                b'X' => return Ok(()),
+                b'd' | b'c' | b'f' => Ok(()), // stray COPY data, ignored like PostgreSQL
+                tag => Err(Error::Value(format!("Unsupported message type {}", tag as char))),
+            };
+            if let Err(err) = result {
+                self.send_error(&err)?;
+                // Extended query errors discard messages until Sync, simple
+                // queries are followed by ReadyForQuery.
+                match tag {
+                    b'Q' => self.send_ready()?,
+                    _ => self.failed = true,
+                }
+            }
+            // Extended query responses are buffered until Sync or Flush.
+            if matches!(tag, b'Q' | b'S' | b'H') {
+                self.writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the connection startup. Returns false if the connection
+    /// should be closed, e.g. for a CancelRequest or failed authentication.
+    fn startup(&mut self, auth: Option<&Auth>) -> Result<bool> {
+        let parameters = 'startup: loop {
+            let len = read_i32(&mut self.reader)?;
+            if !(8..=MAX_MESSAGE_SIZE as i32).contains(&len) {
+                return Err(Error::Value(format!("Invalid startup message length {len}")));
+            }
+            let mut body = vec![0; len as usize - 4];
+            self.reader.read_exact(&mut body)?;
+            let mut body = Reader::new(&body);
+            match body.i32()? {
+                // TLS and GSSAPI encryption aren't supported, tell the client
+                // to continue unencrypted.
+                SSL_REQUEST | GSSENC_REQUEST => {
+                    self.writer.write_all(b"N")?;
+                    self.writer.flush()?;
+                }
+                CANCEL_REQUEST => return Ok(false),
+                PROTOCOL_VERSION => {
+                    let mut parameters = HashMap::new();
+                    loop {
+                        let name = body.string()?;
+                        if name.is_empty() {
+                            break 'startup parameters;
+                        }
+                        parameters.insert(name, body.string()?);
+                    }
+                }
+                version => {
+                    let err = Error::Value(format!(
+                        "Unsupported protocol version {}.{}",
+                        version >> 16,
+                        version & 0xffff
+                    ));
+                    self.send_fatal("0A000", &err)?;
+                    return Ok(false);
+                }
+            }
+        };
+        // Only log known parameters, since e.g. options may contain secrets.
+        debug!(
+            "PostgreSQL startup for user {:?} database {:?}",
+            parameters.get("user"),
+            parameters.get("database")
+        );
+
+        // Request a cleartext password if authentication is enabled, and
+        // authenticate the startup user.
+        if let Some(auth) = auth {
+            self.send(b'R', &3i32.to_be_bytes())?; // AuthenticationCleartextPassword
+            self.writer.flush()?;
+            let result = match self.receive()? {
+                Some((b'p', body)) => Reader::new(&body).string().and_then(|password| {
+                    let user = parameters.get("user").map_or("", |u| u.as_str());
+                    auth.authenticate(&mut self.session, user, &password)
+                }),
+                Some(_) => Err(Error::Unauthenticated),
+                None => return Ok(false),
+            };
+            if let Err(err) = result {
+                self.send_fatal(error_code(&err), &err)?;
+                return Ok(false);
+            }
+        }
+
+        // Use the requested database, if any.
+        if let Some(database) = parameters.get("database").filter(|d| !d.is_empty()) {
+            if !self.session.with_txn_read_only(|txn| txn.has_database(database))? {
+                let err = Error::Value(format!("Database {database} does not exist"));
+                self.send_fatal("3D000", &err)?;
+                return Ok(false);
+            }
+            self.session.execute(&format!("USE \"{}\"", database.replace('"', "\"\"")))?;
+        }
+
+        self.send(b'R', &0i32.to_be_bytes())?; // AuthenticationOk
+        let application_name = parameters.get("application_name").cloned().unwrap_or_default();
+        for (name, value) in [
+            ("server_version", SERVER_VERSION),
+            ("server_encoding", "UTF8"),
+            ("client_encoding", "UTF8"),
+            ("DateStyle", "ISO, MDY"),
+            ("IntervalStyle", "postgres"),
+            ("TimeZone", "UTC"),
+            ("integer_datetimes", "on"),
+            ("standard_conforming_strings", "on"),
+            ("application_name", &application_name),
+        ] {
+            self.send(b'S', &Writer::new().string(name).string(value).0)?;
+        }
+        self.send_ready()?;
+        self.writer.flush()?;
+        Ok(true)
+    }
+
+    /// Handles a simple Query message, executing each statement in it until
+    /// one fails. Sends ReadyForQuery once done.
+    fn query(&mut self, query: String) -> Result<()> {
+        let statements = split(&query);
+        if statements.is_empty() {
+            self.send(b'I', &[])?; // EmptyQueryResponse
+        }
+        for statement in statements {
+            let mut output = self.run(statement)?;
+            if let Output::Rows { columns, .. } = &output {
+                self.send_row_description(columns, &[])?;
+            }
+            self.send_output(&mut output, &[], 0)?;
+        }
+        self.send_ready()
+    }
+
+    /// Handles a Parse message, creating a prepared statement.
+    fn parse(&mut self, name: String, query: String, body: &mut Reader) -> Result<()> {
+        let mut types = Vec::new();
+        for _ in 0..body.i16()? {
+            types.push(body.i32()? as u32);
+        }
+        if !name.is_empty() && self.statements.contains_key(&name) {
+            return Err(Error::Value(format!("Prepared statement {name} already exists")));
+        }
+        let query = match split(&query).as_slice() {
+            [] => None,
+            [query] => Some(query.to_string()),
+            _ => {
+                return Err(Error::Value(
+                    "Prepared statement can't contain multiple statements".into(),
+                ))
+            }
+        };
+        self.statements.insert(name, Statement { query, types });
+        self.send(b'1', &[]) // ParseComplete
+    }
+
+    /// Handles a Bind message, creating a portal from a prepared statement.
+    fn bind(&mut self, portal: String, statement: String, body: &mut Reader) -> Result<()> {
+        let Some(prepared) = self.statements.get(&statement) else {
+            return Err(Error::Value(format!("Prepared statement {statement} does not exist")));
+        };
+        let formats: Vec<i16> = (0..body.i16()?).map(|_| body.i16()).collect::<Result<_>>()?;
+        let mut parameters = Vec::new();
+        for i in 0..body.i16()? as usize {
+            let format = match formats.as_slice() {
+                [] => 0,
+                [format] => *format,
+                formats => *formats.get(i).ok_or_else(|| {
+                    Error::Value(format!("Missing format for parameter {}", i + 1))
+                })?,
+            };
+            let datatype = prepared.types.get(i).copied().unwrap_or(0);
+            let value = match body.i32()? {
+                -1 => None,
+                len => Some(body.bytes(len as usize)?),
+            };
+            parameters.push(literal(value, format, datatype)?);
+        }
+        let formats: Vec<i16> = (0..body.i16()?).map(|_| body.i16()).collect::<Result<_>>()?;
+        if let Some(format) = formats.iter().find(|f| !matches!(f, 0 | 1)) {
+            return Err(Error::Value(format!("Invalid format code {format}")));
+        }
+        let query = prepared.query.as_deref().map(|q| substitute(q, &parameters)).transpose()?;
+        if !portal.is_empty() && self.portals.contains_key(&portal) {
+            return Err(Error::Value(format!("Portal {portal} already exists")));
+        }
+        self.portals.insert(portal, Portal { query, formats, output: None });
+        self.send(b'2', &[]) // BindComplete
+    }
+
+    /// Handles a Describe message for a statement (S) or portal (P).
+    fn describe(&mut self, kind: u8, name: String) -> Result<()> {
+        match kind {
+            b'S' => {
+                let Some(statement) = self.statements.get(&name) else {
+                    return Err(Error::Value(format!("Prepared statement {name} does not exist")));
+                };
+                let count = statement.query.as_deref().map_or(0, parameter_count);
+                let mut types = statement.types.clone();
+                types.resize(types.len().max(count), oid::TEXT);
+                let mut message = Writer::new().i16(types.len() as i16);
+                for datatype in types {
+                    message = message.i32(if datatype == 0 { oid::TEXT } else { datatype } as i32);
+                }
+                self.send(b't', &message.0)?; // ParameterDescription
+                self.send(b'n', &[]) // NoData
+            }
+            b'P' => {
+                let Some(portal) = self.portals.get_mut(&name) else {
+                    return Err(Error::Value(format!("Portal {name} does not exist")));
+                };
+                if let Some(query) = portal.query.take_if(|_| portal.output.is_none()) {
+                    let output = self.run(&query)?;
+                    self.portals.get_mut(&name).unwrap().output = Some(output);
+                }
+                let portal = &self.portals[&name];
+                match &portal.output {
+                    Some(Output::Rows { columns, .. }) => {
+                        let (columns, formats) = (columns.clone(), portal.formats.clone());
+                        self.send_row_description(&columns, &formats)
+                    }
+                    _ => self.send(b'n', &[]), // NoData
+                }
+            }
+            kind => Err(Error::Value(format!("Invalid Describe type {}", kind as char))),
+        }
+    }
+
+    /// Handles an Execute message, returning up to max_rows rows from a
+    /// portal (all if 0).
+    fn execute(&mut self, name: String, max_rows: i32) -> Result<()> {
+        let Some(portal) = self.portals.get_mut(&name) else {
+            return Err(Error::Value(format!("Portal {name} does not exist")));
+        };
+        let formats = portal.formats.clone();
+        // The statement is only executed once, and is empty once completed.
+        let mut output = match (portal.output.take(), portal.query.take()) {
+            (Some(output), _) => output,
+            (None, Some(query)) => self.run(&query)?,
+            (None, None) => return self.send(b'I', &[]), // EmptyQueryResponse
+        };
+        if self.send_output(&mut output, &formats, max_rows.max(0) as u64)? {
+            self.portals.get_mut(&name).unwrap().output = Some(output);
+        }
+        Ok(())
+    }
+
+    /// Handles a Close message for a statement (S) or portal (P).
+    fn close(&mut self, kind: u8, name: String) -> Result<()> {
+        match kind {
+            b'S' => self.statements.remove(&name).map(|_| ()),
+            b'P' => self.portals.remove(&name).map(|_| ()),
+            kind => return Err(Error::Value(format!("Invalid Close type {}", kind as char))),
+        };
+        self.send(b'3', &[]) // CloseComplete
+    }
+
+    /// Handles a Sync message, ending an extended query. Portals are closed
+    /// when no transaction is active, like at the end of a transaction.
+    fn sync(&mut self) -> Result<()> {
+        self.failed = false;
+        if !self.session.in_txn() {
+            self.portals.clear();
+        }
+        self.send_ready()
+    }
+
+    /// Runs a statement, returning its output.
+    fn run(&mut self, query: &str) -> Result<Output> {
+        let result = self.session.execute(query)?;
+        let tag = if matches!(result, ResultSet::Query { .. }) { "SELECT" } else { "EXPLAIN" };
+        let (columns, rows) = match super::query_rows(result) {
+            Ok(columns_rows) => columns_rows,
+            Err(result) => return Ok(Output::Complete(command_tag(&result))),
+        };
+        // Peek at the first row to determine the column types.
+        let mut rows = rows.peekable();
+        let first = match rows.peek() {
+            Some(Ok(row)) => Some(row.clone()),
+            Some(Err(_)) => return Err(rows.next().unwrap().unwrap_err()),
+            None => None,
+        };
+        let columns = columns
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let datatype = first.as_ref().and_then(|r| r.get(i)).map_or(oid::TEXT, type_oid);
+                (name.unwrap_or_else(|| "?column?".to_string()), datatype)
+            })
+            .collect();
+        Ok(Output::Rows { columns, rows: Box::new(rows), tag, count: 0 })
+    }
+
+    /// Sends a statement's output in the given result formats, returning at
+    /// most limit rows (all if 0). Returns true if the output was suspended
+    /// with more rows remaining.
+    fn send_output(&mut self, output: &mut Output, formats: &[i16], limit: u64) -> Result<bool> {
+        match output {
+            Output::Rows { columns, rows, tag, count } => {
+                let mut sent = 0;
+                while limit == 0 || sent < limit {
+                    let Some(row) = rows.next().transpose()? else {
+                        self.send(b'C', &Writer::new().string(&format!("{tag} {count}")).0)?;
+                        return Ok(false);
+                    };
+                    self.send_data_row(&row, columns, formats)?;
+                    *count += 1;
+                    sent += 1;
+                }
+                self.send(b's', &[])?; // PortalSuspended
+                Ok(true)
+            }
+            Output::Complete(tag) => {
+                self.send(b'C', &Writer::new().string(tag).0)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Sends a RowDescription message.
+    fn send_row_description(&mut self, columns: &[(String, u32)], formats: &[i16]) -> Result<()> {
+        let mut message = Writer::new().i16(columns.len() as i16);
+        for (i, (name, datatype)) in columns.iter().enumerate() {
+            message = message
+                .string(name)
+                .i32(0) // table OID
+                .i16(0) // column number
+                .i32(*datatype as i32)
+                .i16(type_size(*datatype))
+                .i32(-1) // type modifier
+                .i16(result_format(formats, i));
+        }
+        self.send(b'T', &message.0)
+    }
+
+    /// Sends a DataRow message, with values in the given result formats.
+    fn send_data_row(
+        &mut self,
+        row: &Row,
+        columns: &[(String, u32)],
+        formats: &[i16],
+    ) -> Result<()> {
+        let mut message = Writer::new().i16(row.len() as i16);
+        for (i, value) in row.iter().enumerate() {
+            let bytes = match result_format(formats, i) {
+                1 => encode_binary(value, columns.get(i).map_or(oid::TEXT, |(_, t)| *t))?,
+                _ => format_value(value).map(String::into_bytes),
+            };
+            message = match bytes {
+                Some(bytes) => message.i32(bytes.len() as i32).bytes(&bytes),
+                None => message.i32(-1),
+            };
+        }
+        self.send(b'D', &message.0)
+    }
+
+    /// Sends a ReadyForQuery message with the transaction status.
+    fn send_ready(&mut self) -> Result<()> {
+        let status = if self.session.in_txn() { b'T' } else { b'I' };
+        self.send(b'Z', &[status])
+    }
+
+    /// Sends an ErrorResponse message for an error.
+    fn send_error(&mut self, err: &Error) -> Result<()> {
+        self.send_error_severity("ERROR", error_code(err), err)
+    }
+
+    /// Sends a fatal ErrorResponse message, after which the connection is
+    /// closed.
+    fn send_fatal(&mut self, code: &str, err: &Error) -> Result<()> {
+        self.send_error_severity("FATAL", code, err)?;
+        Ok(self.writer.flush()?)
+    }
+
+    /// Sends an ErrorResponse message with the given severity and SQLSTATE.
+    fn send_error_severity(&mut self, severity: &str, code: &str, err: &Error) -> Result<()> {
+        debug!("Returning PostgreSQL error {err:?}");
+        let message = Writer::new()
+            .byte(b'S')
+            .string(severity)
+            .byte(b'V')
+            .string(severity)
+            .byte(b'C')
+            .string(code)
+            .byte(b'M')
+            .string(&err.to_string())
+            .byte(0);
+        self.send(b'E', &message.0)
+    }
+
+    /// Sends a message with the given type and body.
+    fn send(&mut self, tag: u8, body: &[u8]) -> Result<()> {
+        self.writer.write_all(&[tag])?;
+        self.writer.write_all(&(body.len() as i32 + 4).to_be_bytes())?;
+        self.writer.write_all(body)?;
+        Ok(())
+    }
+
+    /// Receives a message as its type and body, or None if the client
+    /// disconnected.
+    fn receive(&mut self) -> Result<Option<(u8, Vec<u8>)>> {
+        let mut tag = [0];
+        match self.reader.read_exact(&mut tag) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let len = read_i32(&mut self.reader)?;
+        if !(4..=MAX_MESSAGE_SIZE as i32).contains(&len) {
+            return Err(Error::Value(format!("Invalid message length {len}")));
+        }
+        let mut body = vec![0; len as usize - 4];
+        self.reader.read_exact(&mut body)?;
+        Ok(Some((tag[0], body)))
+    }
+}
+
+/// Reads a big-endian i32.
+fn read_i32(reader: &mut impl Read) -> Result<i32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(i32::from_be_bytes(buf))
+}
+
+/// Reads the fields of a message body.
+struct Reader<'a> {
+    body: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(body: &'a [u8]) -> Self {
+        Self { body }
+    }
+
+    /// Reads the given number of bytes.
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.body.len() {
+            return Err(Error::Value("Unexpected end of message".into()));
+        }
+        let (bytes, rest) = self.body.split_at(len);
+        self.body = rest;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Reads a null-terminated string.
+    fn string(&mut self) -> Result<String> {
+        let Some(len) = self.body.iter().position(|b| *b == 0) else {
+            return Err(Error::Value("Unterminated string in message".into()));
+        };
+        let string = std::str::from_utf8(self.bytes(len)?)
+            .map_err(|_| Error::Value("Invalid UTF-8 string in message".into()))?;
+        self.bytes(1)?;
+        Ok(string.to_string())
+    }
+}
+
+/// Builds a message body.
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    fn bytes(mut self, bytes: &[u8]) -> Self {
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    fn byte(self, byte: u8) -> Self {
+        self.bytes(&[byte])
+    }
+
+    fn i16(self, value: i16) -> Self {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    fn i32(self, value: i32) -> Self {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    /// Writes a null-terminated string.
+    fn string(self, string: &str) -> Self {
+        self.bytes(string.as_bytes()).byte(0)
+    }
+}
+
+/// Splits a query string into its statements, at semicolons. Empty
+/// statements are skipped. If the query can't be lexed, the remainder is
+/// returned as a single statement, such that executing it reports the error.
+fn split(query: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let (mut start, mut empty) = (0, true);
+    let mut lexer = Lexer::new(query);
+    while let Some(token) = lexer.next() {
+        match token {
+            Ok(Token::Semicolon) => {
+                let end = lexer.span().end;
+                if !empty {
+                    statements.push(query[start..end].trim());
+                }
+                (start, empty) = (end, true);
+            }
+            Ok(_) => empty = false,
+            Err(_) => {
+                empty = false;
+                break;
+            }
+        }
+    }
+    if !empty {
+        statements.push(query[start..].trim());
+    }
+    statements
+}
+
+/// Calls a function for each parameter reference ($1, $2, ...) in a
+/// statement, with its byte range and 1-based number, skipping string
+/// literals, quoted identifiers, and comments. Like in the toyDB lexer, -- is
+/// a double negation rather than a comment.
+fn scan_parameters(query: &str, mut f: impl FnMut(std::ops::Range<usize>, usize)) {
+    let bytes = query.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !bytes[i..].starts_with(b"*/") {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'$' => {
+                let len = bytes[i + 1..].iter().take_while(|b| b.is_ascii_digit()).count();
+                if let Ok(n @ 1..) = query[i + 1..i + 1 + len].parse() {
+                    f(i..i + 1 + len, n);
+                    i += len;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// Returns the number of parameters referenced by a statement.
+fn parameter_count(query: &str) -> usize {
+    let mut count = 0;
+    scan_parameters(query, |_, n| count = count.max(n));
+    count
+}
+
+/// Substitutes parameter references in a statement with the given SQL
+/// literals.
+pub(super) fn substitute(query: &str, parameters: &[String]) -> Result<String> {
+    let mut result = String::with_capacity(query.len());
+    let (mut end, mut missing) = (0, None);
+    scan_parameters(query, |range, n| {
+        let Some(literal) = parameters.get(n - 1) else {
+            missing = missing.or(Some(n));
+            return;
+        };
+        result.push_str(&query[end..range.start]);
+        result.push_str(literal);
+        end = range.end;
+    });
+    if let Some(n) = missing {
+        return Err(Error::Value(format!("Missing value for parameter ${n}")));
+    }
+    result.push_str(&query[end..]);
+    Ok(result)
+}
+
+/// Converts a bound parameter value into a SQL literal, given its format
+/// code (0 for text, 1 for binary) and type OID (0 if unspecified). Text
+/// values are string literals, which toyDB converts to the type they're
+/// used as, except for numbers and booleans of a specified type.
+fn literal(value: Option<&[u8]>, format: i16, datatype: u32) -> Result<String> {
+    let Some(value) = value else {
+        return Ok("NULL".to_string());
+    };
+    let invalid = || Error::Value(format!("Invalid binary value for type {datatype}"));
+    let text = match (format, datatype) {
+        (0, _) => std::str::from_utf8(value)
+            .map_err(|_| Error::Value("Invalid UTF-8 parameter value".into()))?
+            .to_string(),
+        (1, oid::BOOL) => (value.first().ok_or_else(invalid)? != &0).to_string(),
+        (1, oid::INT2) => i16::from_be_bytes(value.try_into().map_err(|_| invalid())?).to_string(),
+        (1, oid::INT4) => i32::from_be_bytes(value.try_into().map_err(|_| invalid())?).to_string(),
+        (1, oid::INT8) => i64::from_be_bytes(value.try_into().map_err(|_| invalid())?).to_string(),
+        (1, oid::FLOAT4) => {
+            f32::from_be_bytes(value.try_into().map_err(|_| invalid())?).to_string()
+        }
+        (1, oid::FLOAT8) => {
+            f64::from_be_bytes(value.try_into().map_err(|_| invalid())?).to_string()
+        }
+        (1, oid::BYTEA) => return Ok(format!("x'{}'", hex::encode(value))),
+        (1, oid::TEXT | 0) => String::from_utf8(value.to_vec())
+            .map_err(|_| Error::Value("Invalid UTF-8 parameter value".into()))?,
+        (1, datatype) => {
+            return Err(Error::Value(format!("Binary format is not supported for type {datatype}")))
+        }
+        (format, _) => return Err(Error::Value(format!("Invalid format code {format}"))),
+    };
+    Ok(match datatype {
+        oid::INT2 | oid::INT4 | oid::INT8 => match text.trim().parse::<i64>() {
+            Ok(i) if i < 0 => format!("({i})"),
+            Ok(i) => i.to_string(),
+            Err(_) => return Err(Error::Value(format!("Invalid integer parameter {text}"))),
+        },
+        oid::FLOAT4 | oid::FLOAT8 | oid::NUMERIC => match text.trim().parse::<f64>() {
+            Ok(f) if f.is_finite() => format!("({})", text.trim()),
+            _ => format!("'{}'", text.replace('\'', "''")),
+        },
+        oid::BOOL => match text.trim().to_lowercase().as_str() {
+            "t" | "true" | "y" | "yes" | "on" | "1" => "TRUE".to_string(),
+            "f" | "false" | "n" | "no" | "off" | "0" => "FALSE".to_string(),
+            _ => return Err(Error::Value(format!("Invalid boolean parameter {text}"))),
+        },
+        oid::BYTEA => match text.strip_prefix("\\x").map(hex::decode) {
+            Some(Ok(bytes)) => format!("x'{}'", hex::encode(bytes)),
+            _ => return Err(Error::Value(format!("Invalid bytea parameter {text}"))),
+        },
+        _ => format!("'{}'", text.replace('\'', "''")),
+    })
+}
+
+/// Returns the command tag of a non-query statement result.
+fn command_tag(result: &ResultSet) -> String {
+    match result {
+        ResultSet::Begin { .. } => "BEGIN".into(),
+        ResultSet::Commit { .. } => "COMMIT".into(),
+        ResultSet::Rollback { .. } | ResultSet::RollbackToSavepoint { .. } => "ROLLBACK".into(),
+        ResultSet::Savepoint { .. } => "SAVEPOINT".into(),
+        ResultSet::ReleaseSavepoint { .. } => "RELEASE".into(),
+        ResultSet::Set { .. } => "SET".into(),
+        ResultSet::Use { .. } => "USE".into(),
+        ResultSet::Kill { .. } => "KILL".into(),
+        ResultSet::Copy { count } => format!("COPY {count}"),
+        ResultSet::Create { count } => format!("INSERT 0 {count}"),
+        ResultSet::Delete { count } => format!("DELETE {count}"),
+        ResultSet::Update { count } => format!("UPDATE {count}"),
+        ResultSet::CreateDatabase { .. } => "CREATE DATABASE".into(),
+        ResultSet::CreateTable { .. } => "CREATE TABLE".into(),
+        ResultSet::DropTable { .. } => "DROP TABLE".into(),
+        ResultSet::CreateIndex { .. } => "CREATE INDEX".into(),
+        ResultSet::DropIndex { .. } => "DROP INDEX".into(),
+        ResultSet::AlterTable { .. } => "ALTER TABLE".into(),
+        ResultSet::CreateView { .. } => "CREATE VIEW".into(),
+        ResultSet::DropView { .. } => "DROP VIEW".into(),
+        ResultSet::CreateSequence { .. } => "CREATE SEQUENCE".into(),
+        ResultSet::DropSequence { .. } => "DROP SEQUENCE".into(),
+        ResultSet::CreateType { .. } => "CREATE TYPE".into(),
+        ResultSet::DropType { .. } => "DROP TYPE".into(),
+        ResultSet::CreateUser { .. } => "CREATE ROLE".into(),
+        ResultSet::AlterUser { .. } => "ALTER ROLE".into(),
+        ResultSet::DropUser { .. } => "DROP ROLE".into(),
+        ResultSet::CreateRole { .. } => "CREATE ROLE".into(),
+        ResultSet::Grant { .. } => "GRANT".into(),
+        ResultSet::Revoke { .. } => "REVOKE".into(),
+        ResultSet::CreateTrigger { .. } => "CREATE TRIGGER".into(),
+        ResultSet::DropTrigger { .. } => "DROP TRIGGER".into(),
+        ResultSet::Comment { .. } => "COMMENT".into(),
+        ResultSet::Analyze { .. } => "ANALYZE".into(),
+        ResultSet::Query { .. }
+        | ResultSet::Explain(_)
+        | ResultSet::ExplainAnalyze { .. }
+        | ResultSet::ExplainVerbose { .. } => "SELECT".into(),
+    }
+}
+
+/// Returns the PostgreSQL type OID of a value. Arrays and NULLs are TEXT.
+fn type_oid(value: &Value) -> u32 {
+    match value {
+        Value::Boolean(_) => oid::BOOL,
+        Value::Integer(_) => oid::INT8,
+        Value::Float(_) => oid::FLOAT8,
+        Value::Decimal(_) => oid::NUMERIC,
+        Value::String(_) => oid::TEXT,
+        Value::Bytes(_) => oid::BYTEA,
+        Value::Date(_) => oid::DATE,
+        Value::Time(_) => oid::TIME,
+        Value::Timestamp(_) => oid::TIMESTAMP,
+        Value::Interval(_) => oid::INTERVAL,
+        Value::Json(_) => oid::JSON,
+        Value::Uuid(_) => oid::UUID,
+        Value::Null | Value::Array(_) => oid::TEXT,
+    }
+}
+
+/// Returns the size of a type, or -1 for variable-length types.
+fn type_size(datatype: u32) -> i16 {
+    match datatype {
+        oid::BOOL => 1,
+        oid::DATE => 4,
+        oid::INT8 | oid::FLOAT8 | oid::TIME | oid::TIMESTAMP => 8,
+        oid::INTERVAL | oid::UUID => 16,
+        _ => -1,
+    }
+}
+
+/// Formats a value in the PostgreSQL text format, or None if NULL.
+fn format_value(value: &Value) -> Option<String> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Boolean(true) => "t".to_string(),
+        Value::Boolean(false) => "f".to_string(),
+        Value::Float(f) if f.is_nan() => "NaN".to_string(),
+        Value::Float(f) if f.is_infinite() && *f > 0.0 => "Infinity".to_string(),
+        Value::Float(f) if f.is_infinite() => "-Infinity".to_string(),
+        value => value.to_string(),
+    })
+}
+
+/// Encodes a value in the PostgreSQL binary format for a column of the given
+/// type, or None if NULL. Values whose type differs from the column's type
+/// (e.g. when the first row was NULL) can only be encoded as TEXT.
+fn encode_binary(value: &Value, datatype: u32) -> Result<Option<Vec<u8>>> {
+    if datatype == oid::TEXT && type_oid(value) != oid::TEXT {
+        return Ok(format_value(value).map(String::into_bytes));
+    }
+    Ok(Some(match (value, datatype) {
+        (Value::Null, _) => return Ok(None),
+        (Value::Boolean(b), oid::BOOL) => vec![*b as u8],
+        (Value::Integer(i), oid::INT8) => i.to_be_bytes().to_vec(),
+        (Value::Float(f), oid::FLOAT8) => f.to_be_bytes().to_vec(),
+        (Value::String(s), oid::TEXT) => s.as_bytes().to_vec(),
+        (Value::Bytes(b), oid::BYTEA) => b.clone(),
+        (Value::Json(j), oid::JSON) => j.to_string().into_bytes(),
+        (Value::Uuid(u), oid::UUID) => u.as_bytes().to_vec(),
+        (value, datatype) if type_oid(value) == datatype => {
+            return Err(Error::Value(format!(
+                "Binary format is not supported for type {}",
+                value.datatype().map_or("NULL".to_string(), |t| t.to_string())
+            )))
+        }
+        (value, _) => {
+            return Err(Error::Value(format!("Value {value} does not match column type")))
+        }
+    }))
+}
+
+/// Returns the format code of a result column.
+fn result_format(formats: &[i16], column: usize) -> i16 {
+    match formats {
+        [format] => *format,
+        formats => formats.get(column).copied().unwrap_or(0),
+    }
+}
+
+/// Returns the SQLSTATE code for an error.
+fn error_code(err: &Error) -> &'static str {
+    match err {
+        Error::Parse(_) | Error::Syntax { .. } => "42601", // syntax_error
+        Error::Serialization => "40001",                   // serialization_failure
+        Error::ReadOnly => "25006",                        // read_only_sql_transaction
+        Error::Timeout => "57014",                         // query_canceled
+        Error::OutOfMemory => "53200",                     // out_of_memory
+        Error::Value(_) => "22000",                        // data_exception
+        Error::NotLeader { .. } => "57P03",                // cannot_connect_now
+        Error::Unauthenticated => "28P01",                 // invalid_password
+        Error::PermissionDenied(_) => "42501",             // insufficient_privilege
+        Error::Abort | Error::Config(_) | Error::Internal(_) => "XX000", // internal_error
+    }
+}
@@ -0,0 +1,94 @@
+//! A session's plan cache, which caches plans keyed by normalized statement
+//! text, such that repeated statements which only differ in their literal
+//! values skip parsing and planning. See Parser::normalize().
+use super::super::plan::Plan;
+use super::super::types::Value;
+
+use std::collections::HashMap;
+
+/// A plan cache, evicting the least recently used entries.
+#[derive(Default)]
+pub struct PlanCache {
+    entries: HashMap<String, Entry>,
+    /// A logical clock, incremented on every access.
+    clock: u64,
+}
+
+/// A plan cache entry.
+struct Entry {
+    /// The parameterized plan, or None if the statement can't be cached (e.g.
+    /// because planning failed with parameters). This avoids rebuilding it
+    /// every time.
+    plan: Option<Plan>,
+    /// The schema version the plan was built for.
+    schema_version: u64,
+    /// Whether the statement is read-only.
+    read_only: bool,
+    /// Parameter values the plan depends on, by parameter index.
+    pinned: Vec<(usize, Value)>,
+    /// The clock value of the last access.
+    used: u64,
+}
+
+impl PlanCache {
+    /// Returns whether a cached statement is read-only, or None if the
+    /// statement is not cached.
+    pub fn read_only(&self, key: &str) -> Option<bool> {
+        self.entries.get(key).map(|e| e.read_only)
+    }
+
+    /// Fetches a cached plan for the given schema version and parameter
+    /// values. Returns None if the statement isn't cached or the plan is
+    /// stale, and Some(None) if the statement can't be cached.
+    pub fn get(
+        &mut self,
+        key: &str,
+        schema_version: u64,
+        parameters: &[Value],
+    ) -> Option<Option<Plan>> {
+        let entry = self.entries.get_mut(key)?;
+        if entry.schema_version != schema_version
+            || !entry.pinned.iter().all(|(i, v)| parameters.get(*i) == Some(v))
+        {
+            return None;
+        }
+        self.clock += 1;
+        entry.used = self.clock;
+        Some(entry.plan.clone())
+    }
+
+    /// Caches a plan, or None if the statement can't be cached, evicting the
+    /// least recently used entry if the cache already holds size entries.
+    pub fn insert(
+        &mut self,
+        key: String,
+        plan: Option<(Plan, Vec<usize>)>,
+        schema_version: u64,
+        read_only: bool,
+        parameters: &[Value],
+        size: usize,
+    ) {
+        self.entries.remove(&key);
+        while !self.entries.is_empty() && self.entries.len() >= size {
+            let lru = self.entries.iter().min_by_key(|(_, e)| e.used).map(|(k, _)| k.clone());
+            self.entries.remove(&lru.expect("cache is not empty"));
+        }
+        if size == 0 {
+            return;
+        }
+        let (plan, pinned) = match plan {
+            Some((plan, pinned)) => {
+                (Some(plan), pinned.into_iter().map(|i| (i, parameters[i].clone())).collect())
+            }
+            None => (None, Vec::new()),
+        };
+        self.clock += 1;
+        let used = self.clock;
+        self.entries.insert(key, Entry { plan, schema_version, read_only, pinned, used });
+    }
+
+    /// Removes all cached plans.
+    pub fn clear(&mut self) {
+        self.entries.clear()
+    }
+}
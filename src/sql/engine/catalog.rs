@@ -0,0 +1,79 @@
+//! A cache of table schemas, shared by all sessions of an engine, such that
+//! statements don't have to read and deserialize catalog keys from storage (or
+//! via Raft) every time they're planned and executed. Every schema change
+//! increments the schema version, and concurrent schema changes conflict on
+//! it, so a schema version identifies a single committed catalog. Schemas are
+//! therefore cached by schema version, and a schema change invalidates them
+//! simply by changing the version that transactions see. Transactions that
+//! change the schema themselves don't use the cache, since their changes
+//! aren't committed yet.
+use super::super::schema::Table;
+use crate::error::Result;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// The number of schema versions to cache schemas for. Transactions that
+/// began before a schema change still use the previous version.
+const CATALOG_CACHE_VERSIONS: usize = 4;
+
+/// A cache of table schemas by schema version. Clones share the cache.
+#[derive(Clone, Default)]
+pub struct CatalogCache(Arc<Mutex<BTreeMap<u64, Entry>>>);
+
+/// Cached schemas for a schema version.
+#[derive(Default)]
+struct Entry {
+    /// Table schemas by name, or None if the table doesn't exist.
+    tables: HashMap<String, Option<Table>>,
+    /// All table schemas, in name order, if they have been scanned.
+    all: Option<Vec<Table>>,
+}
+
+impl CatalogCache {
+    /// Fetches a table schema at the given schema version, reading it with the
+    /// given closure if it isn't cached. The cache isn't locked while reading.
+    pub fn read_table<F>(&self, version: u64, table: &str, read: F) -> Result<Option<Table>>
+    where
+        F: FnOnce() -> Result<Option<Table>>,
+    {
+        if let Some(entry) = self.0.lock()?.get(&version) {
+            if let Some(schema) = entry.tables.get(table) {
+                return Ok(schema.clone());
+            }
+            if let Some(all) = &entry.all {
+                return Ok(all.iter().find(|t| t.name == table).cloned());
+            }
+        }
+        let schema = read()?;
+        self.update(version, |entry| {
+            entry.tables.insert(table.to_string(), schema.clone());
+        })?;
+        Ok(schema)
+    }
+
+    /// Fetches all table schemas at the given schema version, scanning them
+    /// with the given closure if they aren't cached.
+    pub fn scan_tables<F>(&self, version: u64, scan: F) -> Result<Vec<Table>>
+    where
+        F: FnOnce() -> Result<Vec<Table>>,
+    {
+        if let Some(all) = self.0.lock()?.get(&version).and_then(|e| e.all.clone()) {
+            return Ok(all);
+        }
+        let tables = scan()?;
+        self.update(version, |entry| entry.all = Some(tables.clone()))?;
+        Ok(tables)
+    }
+
+    /// Updates the entry for a schema version, evicting the oldest versions
+    /// if the cache is full.
+    fn update(&self, version: u64, f: impl FnOnce(&mut Entry)) -> Result<()> {
+        let mut versions = self.0.lock()?;
+        f(versions.entry(version).or_default());
+        while versions.len() > CATALOG_CACHE_VERSIONS {
+            versions.pop_first();
+        }
+        Ok(())
+    }
+}
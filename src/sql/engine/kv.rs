@@ -1,32 +1,77 @@
-use super::super::schema::{Catalog, Table, Tables};
-use super::super::types::{Expression, Row, Value};
-use super::Transaction as _;
-use crate::encoding::{bincode, keycode};
+use super::super::schema::{
+    split_name, Catalog, Column, ColumnChange, Compatibility, EnumType, Index, InformationSchema,
+    Privilege, ReferenceAction, Sequence, Statistics, Table, Tables, Trigger, User, View, Views,
+};
+use super::super::types::{
+    datetime, Collation, Compiled, DataType, Expression, Row, Sequences, Value,
+};
+use super::{CatalogCache, Engine as _, IndexRange, Queries, Transaction as _, WorkerPool};
+use crate::encoding::{bincode, keycode, row};
 use crate::error::{Error, Result};
 use crate::storage;
 
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::clone::Clone;
-use std::collections::HashSet;
+use std::collections::{btree_map, BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::ops::Bound;
+use std::sync::Arc;
+
+/// Index entries modified by a batch of row writes, by storage key, as the
+/// index position in Table::get_indexes() and the entry's primary keys and
+/// included values. Entries are written once when the batch is flushed,
+/// rather than once for each row, and in key order for write locality.
+type IndexBatch = BTreeMap<Vec<u8>, (usize, HashMap<Value, Vec<Value>>)>;
 
 /// A SQL engine based on an underlying MVCC key/value store.
 pub struct KV<E: storage::Engine> {
     /// The underlying key/value store.
     pub(super) kv: storage::mvcc::MVCC<E>,
+    /// The running queries, shared by clones.
+    queries: Queries,
+    /// The cached table schemas, shared by clones.
+    catalog: CatalogCache,
+    /// The worker pool for parallel scans, shared by clones.
+    pool: WorkerPool,
 }
 
 // FIXME Implement Clone manually due to https://github.com/rust-lang/rust/issues/26925
 impl<E: storage::Engine> Clone for KV<E> {
     fn clone(&self) -> Self {
-        KV { kv: self.kv.clone() }
+        KV {
+            kv: self.kv.clone(),
+            queries: self.queries.clone(),
+            catalog: self.catalog.clone(),
+            pool: self.pool.clone(),
+        }
     }
 }
 
 impl<E: storage::Engine> KV<E> {
-    /// Creates a new key/value-based SQL engine
+    /// Creates a new key/value-based SQL engine. Scans are serial, see
+    /// with_parallel_workers().
     pub fn new(engine: E) -> Self {
-        Self { kv: storage::mvcc::MVCC::new(engine) }
+        Self {
+            kv: storage::mvcc::MVCC::new(engine),
+            queries: Queries::default(),
+            catalog: CatalogCache::default(),
+            pool: WorkerPool::new(1),
+        }
+    }
+
+    /// Sets the maximum number of worker threads used by parallel scans,
+    /// across all transactions. Scans requesting more workers use this many.
+    pub fn with_parallel_workers(self, workers: usize) -> Self {
+        Self { pool: WorkerPool::new(workers), ..self }
+    }
+
+    /// Begins a read-write transaction, recording the given begin time (in
+    /// microseconds since the Unix epoch) for time-travel queries. The Raft
+    /// state machine uses the time given by the client, such that all replicas
+    /// record the same time.
+    pub fn begin_at(&self, time: i64) -> Result<<Self as super::Engine>::Transaction> {
+        Ok(<Self as super::Engine>::Transaction::new(self.clone(), self.kv.begin_at(time)?))
     }
 
     /// Resumes a transaction from the given state
@@ -34,7 +79,7 @@ impl<E: storage::Engine> KV<E> {
         &self,
         state: storage::mvcc::TransactionState,
     ) -> Result<<Self as super::Engine>::Transaction> {
-        Ok(<Self as super::Engine>::Transaction::new(self.kv.resume(state)?))
+        Ok(<Self as super::Engine>::Transaction::new(self.clone(), self.kv.resume(state)?))
     }
 
     /// Fetches an unversioned metadata value
@@ -46,21 +91,44 @@ impl<E: storage::Engine> KV<E> {
     pub fn set_metadata(&self, key: &[u8], value: Vec<u8>) -> Result<()> {
         self.kv.set_unversioned(key, value)
     }
+
+    /// Allocates a range of values from a column's auto-increment sequence,
+    /// returning the first. Sequences start at 1, and are stored as
+    /// unversioned keys such that they aren't affected by rollbacks.
+    pub fn allocate_sequence(&self, table: &str, column: &str, count: i64) -> Result<i64> {
+        let key = Key::AutoIncrement(table.into(), column.into()).encode()?;
+        let next =
+            self.kv.get_unversioned(&key)?.map(|v| deserialize(&v)).transpose()?.unwrap_or(1);
+        self.kv.set_unversioned(&key, serialize(&(next + count))?)?;
+        Ok(next)
+    }
 }
 
 impl<E: storage::Engine> super::Engine for KV<E> {
     type Transaction = Transaction<E>;
 
     fn begin(&self) -> Result<Self::Transaction> {
-        Ok(Self::Transaction::new(self.kv.begin()?))
+        self.begin_at(datetime::now().and_utc().timestamp_micros())
     }
 
     fn begin_read_only(&self) -> Result<Self::Transaction> {
-        Ok(Self::Transaction::new(self.kv.begin_read_only()?))
+        Ok(Self::Transaction::new(self.clone(), self.kv.begin_read_only()?))
     }
 
     fn begin_as_of(&self, version: u64) -> Result<Self::Transaction> {
-        Ok(Self::Transaction::new(self.kv.begin_as_of(version)?))
+        Ok(Self::Transaction::new(self.clone(), self.kv.begin_as_of(version)?))
+    }
+
+    fn version_at(&self, time: NaiveDateTime) -> Result<Option<u64>> {
+        self.kv.version_at(time.and_utc().timestamp_micros())
+    }
+
+    fn queries(&self) -> &Queries {
+        &self.queries
+    }
+
+    fn catalog_cache(&self) -> &CatalogCache {
+        &self.catalog
     }
 }
 
@@ -74,15 +142,132 @@ fn deserialize<'a, V: Deserialize<'a>>(bytes: &'a [u8]) -> Result<V> {
     bincode::deserialize(bytes)
 }
 
+/// Encodes a table row for storage, see Table::compact_row().
+fn encode_row(table: &Table, row: &[Value]) -> Result<Vec<u8>> {
+    row::encode(&table.compact_row(row)?)
+}
+
+/// Decodes a scanned table row and evaluates the compiled scan filter on it,
+/// if any, returning None if the row doesn't match. Columns are as for
+/// row::decode(), and must include the filter's columns.
+fn decode_row(
+    table: &Table,
+    bytes: &[u8],
+    filter: Option<&Compiled>,
+    columns: Option<&[usize]>,
+) -> Result<Option<Row>> {
+    let mut row = row::decode(bytes, columns)?;
+    // Rows containing a dropped column have shifted column positions, so they're decoded in full.
+    if columns.is_some() && row.len() > table.columns.len() {
+        row = row::decode(bytes, None)?;
+    }
+    let row = table.pad_row(row)?;
+    match filter.map(|f| f.evaluate(&row)).transpose()? {
+        None | Some(Value::Boolean(true)) => Ok(Some(row)),
+        Some(Value::Boolean(false)) | Some(Value::Null) => Ok(None),
+        Some(v) => Err(Error::Value(format!("Filter returned {}, expected boolean", v))),
+    }
+}
+
+/// The number of rows decoded by each parallel scan job.
+const PARALLEL_SCAN_CHUNK_ROWS: usize = 1024;
+
+/// Decodes and filters a chunk of encoded table rows.
+type DecodeChunk = Arc<dyn Fn(Vec<Vec<u8>>) -> Result<Vec<Row>> + Send + Sync>;
+
+/// A parallel scan's row iterator. Chunks of encoded rows are decoded on the
+/// worker pool, with at most the given number of chunks in flight, and their
+/// rows are yielded in key order as each chunk completes. The next chunk is
+/// only handed to the pool once a chunk's rows are fetched, such that rows
+/// are decoded as the query consumes them.
+struct ParallelScan {
+    pool: WorkerPool,
+    workers: usize,
+    decode: DecodeChunk,
+    /// Chunks of encoded rows that haven't been handed to the pool yet.
+    chunks: std::vec::IntoIter<Vec<Vec<u8>>>,
+    /// Chunks being decoded, in key order.
+    pending: VecDeque<super::pool::Handle<Vec<Row>>>,
+    /// The remaining rows of the current chunk.
+    rows: std::vec::IntoIter<Row>,
+    /// The remaining rows of the current chunk when iterating in reverse.
+    /// These are decoded inline, since reverse scans are rare.
+    back: std::vec::IntoIter<Row>,
+}
+
+impl ParallelScan {
+    /// Stops the scan on an error, dropping any remaining chunks.
+    fn stop(&mut self, err: Error) -> Error {
+        self.chunks = Vec::new().into_iter();
+        self.pending.clear();
+        self.rows = Vec::new().into_iter();
+        self.back = Vec::new().into_iter();
+        err
+    }
+
+    /// Fetches the next chunk's rows, or None if the scan is done.
+    fn next_chunk(&mut self) -> Result<Option<Vec<Row>>> {
+        if self.workers <= 1 {
+            return self.chunks.next().map(|chunk| (self.decode)(chunk)).transpose();
+        }
+        while self.pending.len() < self.workers {
+            let Some(chunk) = self.chunks.next() else { break };
+            let decode = self.decode.clone();
+            self.pending.push_back(self.pool.spawn(move || decode(chunk))?);
+        }
+        self.pending.pop_front().map(|handle| handle.join()).transpose()
+    }
+}
+
+impl Iterator for ParallelScan {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                return Some(Ok(row));
+            }
+            match self.next_chunk() {
+                Ok(Some(rows)) => self.rows = rows.into_iter(),
+                Ok(None) => return self.back.next().map(Ok),
+                Err(err) => return Some(Err(self.stop(err))),
+            }
+        }
+    }
+}
+
+impl DoubleEndedIterator for ParallelScan {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.back.next_back() {
+                return Some(Ok(row));
+            }
+            let result = if let Some(chunk) = self.chunks.next_back() {
+                (self.decode)(chunk)
+            } else if let Some(handle) = self.pending.pop_back() {
+                handle.join()
+            } else {
+                return self.rows.next_back().map(Ok);
+            };
+            match result {
+                Ok(rows) => self.back = rows.into_iter(),
+                Err(err) => return Some(Err(self.stop(err))),
+            }
+        }
+    }
+}
+
 /// An SQL transaction based on an MVCC key/value transaction
 pub struct Transaction<E: storage::Engine> {
+    /// The engine, for sequence allocation
+    engine: KV<E>,
     txn: storage::mvcc::Transaction<E>,
 }
 
 impl<E: storage::Engine> Transaction<E> {
     /// Creates a new SQL transaction from an MVCC transaction
-    fn new(txn: storage::mvcc::Transaction<E>) -> Self {
-        Self { txn }
+    fn new(engine: KV<E>, txn: storage::mvcc::Transaction<E>) -> Self {
+        Self { engine, txn }
     }
 
     /// Returns the transaction's serialized state.
@@ -90,30 +275,482 @@ impl<E: storage::Engine> Transaction<E> {
         self.txn.state()
     }
 
-    /// Loads an index entry
-    fn index_load(&self, table: &str, column: &str, value: &Value) -> Result<HashSet<Value>> {
-        Ok(self
+    /// Allocates a range of up to count values from a sequence, returning the
+    /// first value, the increment between values, and the number of values
+    /// allocated. Fewer values are allocated when the sequence approaches the
+    /// integer bounds. Like auto-increment sequences, the next value is stored
+    /// as an unversioned key such that it isn't affected by rollbacks, along
+    /// with the sequence's version such that a recreated sequence starts over.
+    /// A next value of None means the sequence is exhausted.
+    pub(super) fn allocate_sequence_values(
+        &self,
+        sequence: &str,
+        count: i64,
+    ) -> Result<(i64, i64, i64)> {
+        let schema = self.must_read_sequence(sequence)?;
+        let key = Key::SequenceValue(sequence.into()).encode()?;
+        let value: Option<(u64, Option<i64>)> =
+            self.engine.kv.get_unversioned(&key)?.map(|v| deserialize(&v)).transpose()?;
+        let next = match value {
+            Some((version, next)) if version == schema.version => next,
+            _ => Some(schema.start),
+        }
+        .ok_or_else(|| Error::Value(format!("Sequence {} is exhausted", sequence)))?;
+        let bound = if schema.increment > 0 { i64::MAX } else { i64::MIN };
+        let available = (bound as i128 - next as i128) / schema.increment as i128 + 1;
+        let count = (count as i128).min(available) as i64;
+        let last = next + (count - 1) * schema.increment;
+        let after = last.checked_add(schema.increment);
+        self.engine.kv.set_unversioned(&key, serialize(&(schema.version, after))?)?;
+        Ok((next, schema.increment, count))
+    }
+
+    /// Sets the current value of a sequence, such that the next allocated
+    /// value follows it.
+    pub(super) fn set_sequence_value(&self, sequence: &str, value: i64) -> Result<()> {
+        let schema = self.must_read_sequence(sequence)?;
+        let next = value.checked_add(schema.increment);
+        let key = Key::SequenceValue(sequence.into()).encode()?;
+        self.engine.kv.set_unversioned(&key, serialize(&(schema.version, next))?)
+    }
+
+    /// Reads a sequence, or errors if it does not exist.
+    fn must_read_sequence(&self, sequence: &str) -> Result<Sequence> {
+        self.read_sequence(sequence)?
+            .ok_or_else(|| Error::Value(format!("Sequence {} does not exist", sequence)))
+    }
+
+    /// Checks that the database of a new table or view exists.
+    fn check_database(&self, name: &str) -> Result<()> {
+        let (database, _) = split_name(name);
+        if !self.has_database(database)? {
+            return Err(Error::Value(format!("Database {} does not exist", database)));
+        }
+        Ok(())
+    }
+
+    /// Increments the schema version, see Catalog::schema_version(). Since
+    /// it's a single key, concurrent schema changes conflict with each other.
+    fn bump_schema_version(&mut self) -> Result<()> {
+        let version = self.schema_version()? + 1;
+        self.txn.set(&Key::SchemaVersion.encode()?, serialize(&version)?)
+    }
+
+    /// Checks that the table schema versions used by the transaction's row
+    /// writes are still valid when committing, see Compatibility. Rows written
+    /// with a table's previous version are recorded via Key::SchemaSkew, and
+    /// schema changes fail if such rows were committed concurrently, since they
+    /// couldn't see them to e.g. rewrite or index them.
+    fn check_schema_versions(&self) -> Result<()> {
+        let (mut written, mut altered) = (BTreeSet::new(), BTreeSet::new());
+        for key in self.txn.writes()? {
+            match Key::decode(&key)? {
+                Key::Row(table, _) => written.insert(table.into_owned()),
+                Key::Table(table) => altered.insert(table.into_owned()),
+                _ => false,
+            };
+        }
+        for table in &altered {
+            let key = Key::SchemaSkew(table.into()).encode()?;
+            let Some(version) = self.engine.kv.get_unversioned(&key)? else { continue };
+            let version: u64 = deserialize(&version)?;
+            if version > self.version() || self.concurrent().contains(&version) {
+                return Err(Error::Serialization);
+            }
+        }
+
+        // Tables altered by the transaction conflict with concurrent schema changes.
+        let mut skewed = Vec::new();
+        let latest = self.engine.begin_read_only()?;
+        for table in written.difference(&altered) {
+            // The table was dropped by the transaction, along with its rows.
+            let Some(schema) = self.read_table(table)? else { continue };
+            match latest.read_table(table)? {
+                Some(current) if current.version == schema.version => {}
+                Some(current)
+                    if current.version == schema.version + 1
+                        && current.compatibility != Compatibility::Incompatible =>
+                {
+                    skewed.push(table)
+                }
+                Some(_) | None => return Err(Error::Serialization),
+            }
+        }
+        for table in skewed {
+            let key = Key::SchemaSkew(table.into()).encode()?;
+            self.engine.kv.set_unversioned(&key, serialize(&self.version())?)?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites rows that still contain a dropped column, written with the
+    /// previous schema version, before a schema change replaces the table's
+    /// compatibility and they can no longer be read.
+    fn rewrite_dropped(&mut self, table: &Table) -> Result<()> {
+        let Compatibility::Dropped(_) = table.compatibility else { return Ok(()) };
+        let rows = self
             .txn
-            .get(&Key::Index(table.into(), column.into(), value.into()).encode()?)?
-            .map(|v| deserialize(&v))
-            .transpose()?
-            .unwrap_or_default())
+            .scan_prefix(&KeyPrefix::Row((&table.name).into()).encode()?)?
+            .iter()
+            .map(|r| r.and_then(|(key, value)| Ok((key, row::decode(&value, None)?))))
+            .filter(|r| r.as_ref().map_or(true, |(_, row)| row.len() > table.columns.len()))
+            .collect::<Result<Vec<_>>>()?;
+        for (key, row) in rows {
+            self.txn.set(&key, encode_row(table, &table.pad_row(row)?)?)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes a row and its index entries, without checking references.
+    fn delete_row(&mut self, table: &Table, id: &Value) -> Result<()> {
+        let indexes = table.get_indexes();
+        if !indexes.is_empty() {
+            if let Some(row) = self.read(&table.name, id)? {
+                for index in indexes {
+                    self.index_remove(table, &index, id, &row)?;
+                }
+            }
+        }
+        self.txn.delete(&Key::Row((&table.name).into(), id.into()).encode()?)
+    }
+
+    /// Finds rows referencing the given primary key, as the referencing
+    /// table, column index, and row primary key. A row referencing itself is
+    /// not included.
+    fn find_references(&self, table: &Table, id: &Value) -> Result<Vec<(Table, usize, Value)>> {
+        let mut references = Vec::new();
+        for (t, cs) in self.table_references(&table.name, true)? {
+            let t = self.must_read_table(&t)?;
+            let cs = cs.iter().map(|c| t.get_column_index(c)).collect::<Result<Vec<_>>>()?;
+            let mut columns = cs.clone();
+            columns.push(t.get_column_index(&t.get_primary_key()?.name)?);
+            let mut scan = self.scan(&t.name, None, false, None, Some(columns))?;
+            while let Some(row) = scan.next().transpose()? {
+                let ref_id = t.get_row_key(&row)?;
+                for c in &cs {
+                    if &row[*c] == id && (table.name != t.name || id != &ref_id) {
+                        references.push((t.clone(), *c, ref_id.clone()));
+                    }
+                }
+            }
+        }
+        Ok(references)
+    }
+
+    /// Updates a row whose primary key changes, applying ON UPDATE actions to
+    /// rows referencing it.
+    fn update_key(
+        &mut self,
+        table: &Table,
+        id: &Value,
+        new_id: &Value,
+        mut row: Row,
+    ) -> Result<()> {
+        let references = self.find_references(table, id)?;
+        for (t, c, _) in &references {
+            if t.columns[*c].on_update == ReferenceAction::Restrict {
+                return Err(Error::Value(format!(
+                    "Primary key {} is referenced by table {} column {}",
+                    id, t.name, t.columns[*c].name
+                )));
+            }
+        }
+        // The row itself may reference its old key, in which case the action applies to it too.
+        for (i, column) in table.columns.iter().enumerate() {
+            if column.references.as_deref() == Some(&table.name) && &row[i] == id {
+                row[i] = match column.on_update {
+                    ReferenceAction::Restrict => {
+                        return Err(Error::Value(format!(
+                            "Primary key {} is referenced by table {} column {}",
+                            id, table.name, column.name
+                        )))
+                    }
+                    ReferenceAction::Cascade => new_id.clone(),
+                    ReferenceAction::SetNull => Value::Null,
+                };
+            }
+        }
+        table.generate_row(&mut row)?;
+
+        // The old row is replaced before updating references to the new one.
+        self.delete_row(table, id)?;
+        self.create(&table.name, row)?;
+        for (t, c, ref_id) in references {
+            let Some(mut row) = self.read(&t.name, &ref_id)? else { continue };
+            if &row[c] != id {
+                continue;
+            }
+            row[c] = match t.columns[c].on_update {
+                ReferenceAction::Restrict => unreachable!("restrict checked above"),
+                ReferenceAction::Cascade => new_id.clone(),
+                ReferenceAction::SetNull => Value::Null,
+            };
+            t.generate_row(&mut row)?;
+            self.update(&t.name, &ref_id, row)?;
+        }
+        Ok(())
+    }
+
+    /// Loads an index entry, as the primary keys of its rows along with the
+    /// values of the index's included columns.
+    fn index_load(
+        &self,
+        table: &Table,
+        index: &Index,
+        values: &[Value],
+    ) -> Result<HashMap<Value, Vec<Value>>> {
+        self.txn
+            .get(&index_key(table, index, values)?)?
+            .map(|v| decode_index_entry(index, &v))
+            .transpose()
+            .map(|entry| entry.unwrap_or_default())
     }
 
     /// Saves an index entry.
     fn index_save(
         &mut self,
-        table: &str,
-        column: &str,
-        value: &Value,
-        index: HashSet<Value>,
+        table: &Table,
+        index: &Index,
+        values: &[Value],
+        ids: HashMap<Value, Vec<Value>>,
     ) -> Result<()> {
-        let key = Key::Index(table.into(), column.into(), value.into()).encode()?;
-        if index.is_empty() {
+        let key = index_key(table, index, values)?;
+        if ids.is_empty() {
             self.txn.delete(&key)
         } else {
-            self.txn.set(&key, serialize(&index)?)
+            self.txn.set(&key, encode_index_entry(index, ids)?)
+        }
+    }
+
+    /// Adds a row to an index.
+    fn index_insert(
+        &mut self,
+        table: &Table,
+        index: &Index,
+        id: &Value,
+        row: &[Value],
+    ) -> Result<()> {
+        if index.fulltext {
+            return self.fulltext_update(table, index, id, None, Some(row));
+        }
+        let values = index.get_row_key(table, row)?;
+        let mut ids = self.index_load(table, index, &values)?;
+        ids.insert(id.clone(), index.get_row_include(table, row)?);
+        self.index_save(table, index, &values, ids)
+    }
+
+    /// Checks that a row doesn't violate a unique index.
+    fn index_check_unique(
+        &self,
+        table: &Table,
+        index: &Index,
+        id: &Value,
+        row: &[Value],
+    ) -> Result<()> {
+        if !index.unique {
+            return Ok(());
+        }
+        let values = index.get_row_key(table, row)?;
+        let ids = self.index_load(table, index, &values)?.into_keys().collect();
+        index.check_unique(&values, &ids, id)
+    }
+
+    /// Removes a row from an index.
+    fn index_remove(
+        &mut self,
+        table: &Table,
+        index: &Index,
+        id: &Value,
+        row: &[Value],
+    ) -> Result<()> {
+        if index.fulltext {
+            return self.fulltext_update(table, index, id, Some(row), None);
+        }
+        let values = index.get_row_key(table, row)?;
+        let mut ids = self.index_load(table, index, &values)?;
+        ids.remove(id);
+        self.index_save(table, index, &values, ids)
+    }
+
+    /// Loads the primary keys of the rows containing a term from a FULLTEXT
+    /// index.
+    fn fulltext_load(&self, table: &Table, index: &Index, term: &str) -> Result<HashSet<Value>> {
+        self.txn
+            .scan_prefix(&fulltext_prefix(&table.name, &index.name, term)?)?
+            .iter()
+            .map(|r| match Key::decode(&r?.0)? {
+                Key::FullText(_, _, _, id) => Ok(id.into_owned()),
+                _ => Err(Error::Internal("Invalid FULLTEXT index key".into())),
+            })
+            .collect()
+    }
+
+    /// Updates a FULLTEXT index for a row write, given the old row (if any)
+    /// and the new row (if any). Only the entries of added or removed terms
+    /// are written.
+    fn fulltext_update(
+        &mut self,
+        table: &Table,
+        index: &Index,
+        id: &Value,
+        old: Option<&[Value]>,
+        new: Option<&[Value]>,
+    ) -> Result<()> {
+        let old = old.map(|row| index.get_row_terms(table, row)).transpose()?.unwrap_or_default();
+        let new = new.map(|row| index.get_row_terms(table, row)).transpose()?.unwrap_or_default();
+        let key = |term: &String| {
+            Key::FullText((&table.name).into(), (&index.name).into(), term.into(), id.into())
+                .encode()
+        };
+        for term in old.difference(&new) {
+            self.txn.delete(&key(term)?)?;
+        }
+        for term in new.difference(&old) {
+            self.txn.set(&key(term)?, Vec::new())?;
+        }
+        Ok(())
+    }
+
+    /// Fetches an index entry for a batch of row writes, loading it from
+    /// storage the first time it's used by the batch.
+    fn index_batch_entry<'a>(
+        &self,
+        batch: &'a mut IndexBatch,
+        table: &Table,
+        indexes: &[Index],
+        position: usize,
+        values: &[Value],
+    ) -> Result<&'a mut HashMap<Value, Vec<Value>>> {
+        let index = &indexes[position];
+        let key = index_key(table, index, values)?;
+        Ok(match batch.entry(key) {
+            btree_map::Entry::Occupied(entry) => &mut entry.into_mut().1,
+            btree_map::Entry::Vacant(entry) => {
+                let ids = self.index_load(table, index, values)?;
+                &mut entry.insert((position, ids)).1
+            }
+        })
+    }
+
+    /// Writes the index entries modified by a batch of row writes, once each
+    /// and in key order.
+    fn index_flush(&mut self, indexes: &[Index], batch: IndexBatch) -> Result<()> {
+        for (key, (position, ids)) in batch {
+            if ids.is_empty() {
+                self.txn.delete(&key)?;
+            } else {
+                self.txn.set(&key, encode_index_entry(&indexes[position], ids)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a row, adding its index entries to the batch. The row is
+    /// checked before anything is written.
+    fn create_batched(
+        &mut self,
+        table: &Table,
+        indexes: &[Index],
+        batch: &mut IndexBatch,
+        row: Row,
+    ) -> Result<()> {
+        table.validate_row(&row, self)?;
+        let id = table.get_row_key(&row)?;
+        if self.read(&table.name, &id)?.is_some() {
+            return Err(Error::Value(format!(
+                "Primary key {} already exists for table {}",
+                id, table.name
+            )));
+        }
+        // FULLTEXT index entries are written directly, once the row has been checked.
+        let mut keys = Vec::with_capacity(indexes.len());
+        for (position, index) in indexes.iter().enumerate().filter(|(_, i)| !i.fulltext) {
+            let values = index.get_row_key(table, &row)?;
+            if index.unique && !index.deferred {
+                let ids = self.index_batch_entry(batch, table, indexes, position, &values)?;
+                index.check_unique(&values, &ids.keys().cloned().collect(), &id)?;
+            }
+            keys.push((position, values));
+        }
+        for (position, values) in keys {
+            let include = indexes[position].get_row_include(table, &row)?;
+            self.index_batch_entry(batch, table, indexes, position, &values)?
+                .insert(id.clone(), include);
+        }
+        for index in indexes.iter().filter(|i| i.fulltext) {
+            self.fulltext_update(table, index, &id, None, Some(&row))?;
+        }
+        self.txn
+            .set(&Key::Row((&table.name).into(), (&id).into()).encode()?, encode_row(table, &row)?)
+    }
+
+    /// Replaces a row whose primary key doesn't change, adding its index
+    /// changes to the batch. The row is checked before anything is written.
+    fn update_batched(
+        &mut self,
+        table: &Table,
+        indexes: &[Index],
+        batch: &mut IndexBatch,
+        id: &Value,
+        row: Row,
+    ) -> Result<()> {
+        table.validate_row(&row, self)?;
+
+        // Only indexes whose key or included values change are updated, as the old key (if
+        // changed), the new key, and the new included values. FULLTEXT index entries are written
+        // directly, once the row has been checked.
+        let mut changed = Vec::new();
+        let old = match indexes.is_empty() {
+            true => None,
+            false => self.read(&table.name, id)?,
+        };
+        if let Some(old) = &old {
+            for (position, index) in indexes.iter().enumerate().filter(|(_, i)| !i.fulltext) {
+                let (old_values, values) =
+                    (index.get_row_key(table, old)?, index.get_row_key(table, &row)?);
+                let include = index.get_row_include(table, &row)?;
+                if old_values != values {
+                    if index.unique && !index.deferred {
+                        let ids =
+                            self.index_batch_entry(batch, table, indexes, position, &values)?;
+                        index.check_unique(&values, &ids.keys().cloned().collect(), id)?;
+                    }
+                    changed.push((position, Some(old_values), values, include));
+                } else if index.get_row_include(table, old)? != include {
+                    changed.push((position, None, values, include));
+                }
+            }
+        }
+        for (position, old_values, values, include) in changed {
+            if let Some(old_values) = old_values {
+                self.index_batch_entry(batch, table, indexes, position, &old_values)?.remove(id);
+            }
+            self.index_batch_entry(batch, table, indexes, position, &values)?
+                .insert(id.clone(), include);
+        }
+        for index in indexes.iter().filter(|i| i.fulltext) {
+            self.fulltext_update(table, index, id, old.as_deref(), Some(&row))?;
+        }
+        self.txn.set(&Key::Row((&table.name).into(), id.into()).encode()?, encode_row(table, &row)?)
+    }
+
+    /// Removes all entries of an index, including FULLTEXT index entries.
+    fn index_clear(&mut self, table: &str, index: &str) -> Result<()> {
+        for prefix in [
+            KeyPrefix::Index(table.into(), index.into()),
+            KeyPrefix::FullText(table.into(), index.into()),
+        ] {
+            let keys = self
+                .txn
+                .scan_prefix(&prefix.encode()?)?
+                .iter()
+                .map(|r| r.map(|(k, _)| k))
+                .collect::<Result<Vec<_>>>()?;
+            for key in keys {
+                self.txn.delete(&key)?;
+            }
         }
+        Ok(())
     }
 }
 
@@ -126,7 +763,15 @@ impl<E: storage::Engine> super::Transaction for Transaction<E> {
         self.txn.read_only()
     }
 
+    fn concurrent(&self) -> &HashSet<u64> {
+        &self.txn.state().active
+    }
+
     fn commit(self) -> Result<()> {
+        if let Err(error) = self.check_schema_versions() {
+            self.txn.rollback()?;
+            return Err(error);
+        }
         self.txn.commit()
     }
 
@@ -134,121 +779,261 @@ impl<E: storage::Engine> super::Transaction for Transaction<E> {
         self.txn.rollback()
     }
 
+    fn savepoint(&mut self, id: u64) -> Result<()> {
+        self.txn.savepoint(id)
+    }
+
+    fn rollback_to_savepoint(&mut self, id: u64) -> Result<()> {
+        self.txn.rollback_to_savepoint(id)
+    }
+
+    fn release_savepoint(&mut self, id: u64) -> Result<()> {
+        self.txn.release_savepoint(id)
+    }
+
     fn create(&mut self, table: &str, row: Row) -> Result<()> {
-        let table = self.must_read_table(table)?;
-        table.validate_row(&row, self)?;
-        let id = table.get_row_key(&row)?;
-        if self.read(&table.name, &id)?.is_some() {
-            return Err(Error::Value(format!(
-                "Primary key {} already exists for table {}",
-                id, table.name
-            )));
-        }
-        self.txn.set(&Key::Row((&table.name).into(), (&id).into()).encode()?, serialize(&row)?)?;
+        self.create_batch(table, vec![row])
+    }
 
-        // Update indexes
-        for (i, column) in table.columns.iter().enumerate().filter(|(_, c)| c.index) {
-            let mut index = self.index_load(&table.name, &column.name, &row[i])?;
-            index.insert(id.clone());
-            self.index_save(&table.name, &column.name, &row[i], index)?;
-        }
-        Ok(())
+    fn create_batch(&mut self, table: &str, rows: Vec<Row>) -> Result<()> {
+        let table = self.must_read_table(table)?;
+        let indexes = table.get_indexes();
+        let mut batch = IndexBatch::new();
+        let result = rows
+            .into_iter()
+            .try_for_each(|row| self.create_batched(&table, &indexes, &mut batch, row));
+        // Rows written before an error remain, so their index entries are written too.
+        self.index_flush(&indexes, batch)?;
+        result
     }
 
     fn delete(&mut self, table: &str, id: &Value) -> Result<()> {
         let table = self.must_read_table(table)?;
-        for (t, cs) in self.table_references(&table.name, true)? {
-            let t = self.must_read_table(&t)?;
-            let cs = cs
-                .into_iter()
-                .map(|c| Ok((t.get_column_index(&c)?, c)))
-                .collect::<Result<Vec<_>>>()?;
-            let mut scan = self.scan(&t.name, None)?;
-            while let Some(row) = scan.next().transpose()? {
-                for (i, c) in &cs {
-                    if &row[*i] == id && (table.name != t.name || id != &table.get_row_key(&row)?) {
-                        return Err(Error::Value(format!(
-                            "Primary key {} is referenced by table {} column {}",
-                            id, t.name, c
-                        )));
-                    }
-                }
+        let references = self.find_references(&table, id)?;
+        for (t, c, _) in &references {
+            if t.columns[*c].on_delete == ReferenceAction::Restrict {
+                return Err(Error::Value(format!(
+                    "Primary key {} is referenced by table {} column {}",
+                    id, t.name, t.columns[*c].name
+                )));
             }
         }
 
-        let indexes: Vec<_> = table.columns.iter().enumerate().filter(|(_, c)| c.index).collect();
-        if !indexes.is_empty() {
-            if let Some(row) = self.read(&table.name, id)? {
-                for (i, column) in indexes {
-                    let mut index = self.index_load(&table.name, &column.name, &row[i])?;
-                    index.remove(id);
-                    self.index_save(&table.name, &column.name, &row[i], index)?;
+        // The row is deleted before applying referential actions, such that cascades terminate
+        // when they loop back to it.
+        self.delete_row(&table, id)?;
+        for (t, c, ref_id) in references {
+            // The row may have been modified by a previous action.
+            let Some(mut row) = self.read(&t.name, &ref_id)? else { continue };
+            if &row[c] != id {
+                continue;
+            }
+            match t.columns[c].on_delete {
+                ReferenceAction::Restrict => unreachable!("restrict checked above"),
+                ReferenceAction::Cascade => self.delete(&t.name, &ref_id)?,
+                ReferenceAction::SetNull => {
+                    row[c] = Value::Null;
+                    t.generate_row(&mut row)?;
+                    self.update(&t.name, &ref_id, row)?;
                 }
             }
         }
-        self.txn.delete(&Key::Row(table.name.into(), id.into()).encode()?)
+        Ok(())
     }
 
     fn read(&self, table: &str, id: &Value) -> Result<Option<Row>> {
+        let table = self.must_read_table(table)?;
         self.txn
-            .get(&Key::Row(table.into(), id.into()).encode()?)?
-            .map(|v| deserialize(&v))
+            .get(&Key::Row((&table.name).into(), id.into()).encode()?)?
+            .map(|v| table.pad_row(row::decode(&v, None)?))
             .transpose()
     }
 
-    fn read_index(&self, table: &str, column: &str, value: &Value) -> Result<HashSet<Value>> {
-        if !self.must_read_table(table)?.get_column(column)?.index {
-            return Err(Error::Value(format!("No index on {}.{}", table, column)));
+    fn read_index(&self, table: &str, index: &str, values: &[Value]) -> Result<HashSet<Value>> {
+        let table = self.must_read_table(table)?;
+        let index = table.get_index(index)?;
+        if values.len() != index.columns.len() {
+            return Err(Error::Value(format!(
+                "Index {} has {} columns, got {} values",
+                index.name,
+                index.columns.len(),
+                values.len()
+            )));
+        }
+        if index.fulltext {
+            let [Value::String(term)] = values else {
+                return Err(Error::Value(format!("Invalid FULLTEXT index {} term", index.name)));
+            };
+            return self.fulltext_load(&table, &index, term);
+        }
+        Ok(self.index_load(&table, &index, values)?.into_keys().collect())
+    }
+
+    fn read_index_range(
+        &self,
+        table: &str,
+        index: &str,
+        range: (Bound<Value>, Bound<Value>),
+    ) -> Result<Vec<Value>> {
+        let table = self.must_read_table(table)?;
+        let index = table.get_index(index)?;
+        let range = (range.0.map(|v| vec![v]), range.1.map(|v| vec![v]));
+        let Some(range) = index_range(&table, &index, range)? else {
+            return Ok(Vec::new());
+        };
+        let mut ids = Vec::new();
+        for r in self.txn.scan(range)?.iter() {
+            let (_, v) = r?;
+            let mut entry: Vec<Value> = decode_index_entry(&index, &v)?.into_keys().collect();
+            entry.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            ids.extend(entry);
+        }
+        Ok(ids)
+    }
+
+    fn read_index_entries(
+        &self,
+        table: &str,
+        index: &str,
+        range: IndexRange,
+        limit: Option<u64>,
+    ) -> Result<Vec<super::IndexEntry>> {
+        let table = self.must_read_table(table)?;
+        let index = table.get_index(index)?;
+        let Some(range) = index_range(&table, &index, range)? else {
+            return Ok(Vec::new());
+        };
+        let limit = limit.map_or(usize::MAX, |l| l as usize);
+        let mut entries = Vec::new();
+        for r in self.txn.scan(range)?.iter() {
+            if entries.len() >= limit {
+                break;
+            }
+            let (k, v) = r?;
+            let values = match Key::decode(&k)? {
+                Key::Index(_, _, values) => values.into_owned(),
+                _ => return Err(Error::Internal("Invalid index key".into())),
+            };
+            let mut entry: Vec<_> = decode_index_entry(&index, &v)?.into_iter().collect();
+            entry.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            entries.extend(entry.into_iter().map(|(id, include)| (values.clone(), id, include)));
         }
-        self.index_load(table, column, value)
+        entries.truncate(limit);
+        Ok(entries)
     }
 
-    fn scan(&self, table: &str, filter: Option<Expression>) -> Result<super::Scan> {
+    fn scan(
+        &self,
+        table: &str,
+        filter: Option<Expression>,
+        reverse: bool,
+        limit: Option<u64>,
+        columns: Option<Vec<usize>>,
+    ) -> Result<super::Scan> {
         let table = self.must_read_table(table)?;
+        let Some(range) = row_range(&table, filter.as_ref())? else {
+            return Ok(Box::new(std::iter::empty()));
+        };
+        let columns = scan_columns(columns, filter.as_ref());
+        let filter = filter.as_ref().map(Expression::compile).transpose()?;
+        let mut scan = self.txn.scan(range)?;
+        let iter: Box<dyn Iterator<Item = _>> = match reverse {
+            true => Box::new(scan.iter().rev()),
+            false => Box::new(scan.iter()),
+        };
         Ok(Box::new(
-            self.txn
-                .scan_prefix(&KeyPrefix::Row((&table.name).into()).encode()?)?
+            iter.map(|r| {
+                r.and_then(|(_, v)| decode_row(&table, &v, filter.as_ref(), columns.as_deref()))
+            })
+            .filter_map(Result::transpose)
+            .take(limit.map_or(usize::MAX, |l| l as usize))
+            .collect::<Vec<_>>()
+            .into_iter(),
+        ))
+    }
+
+    fn scan_parallel(
+        &self,
+        table: &str,
+        filter: Option<Expression>,
+        columns: Option<Vec<usize>>,
+        workers: usize,
+    ) -> Result<super::Scan> {
+        let table = self.must_read_table(table)?;
+        let Some(range) = row_range(&table, filter.as_ref())? else {
+            return Ok(Box::new(std::iter::empty()));
+        };
+        let columns = scan_columns(columns, filter.as_ref());
+        let filter = filter.as_ref().map(Expression::compile).transpose()?;
+        let decode: DecodeChunk = Arc::new(move |values: Vec<Vec<u8>>| {
+            values
                 .iter()
-                .map(|r| r.and_then(|(_, v)| deserialize(&v)))
-                .filter_map(move |r| match r {
-                    Ok(row) => match &filter {
-                        Some(filter) => match filter.evaluate(Some(&row)) {
-                            Ok(Value::Boolean(b)) if b => Some(Ok(row)),
-                            Ok(Value::Boolean(_)) | Ok(Value::Null) => None,
-                            Ok(v) => Some(Err(Error::Value(format!(
-                                "Filter returned {}, expected boolean",
-                                v
-                            )))),
-                            Err(err) => Some(Err(err)),
-                        },
-                        None => Some(Ok(row)),
-                    },
-                    err => Some(err),
+                .filter_map(|v| {
+                    decode_row(&table, v, filter.as_ref(), columns.as_deref()).transpose()
                 })
-                .collect::<Vec<_>>()
-                .into_iter(),
-        ))
+                .collect::<Result<Vec<_>>>()
+        });
+
+        // Read the encoded rows in chunks of contiguous keys while holding the
+        // storage lock, which can't be held across calls. The chunks are then
+        // decoded and filtered lazily by the engine's worker pool as the rows
+        // are fetched, with at most the given number of chunks (capped by the
+        // pool size) in flight.
+        let mut scan = self.txn.scan(range)?;
+        let mut iter = scan.iter().map(|r| r.map(|(_, v)| v));
+        let mut chunks = Vec::new();
+        loop {
+            let chunk = iter.by_ref().take(PARALLEL_SCAN_CHUNK_ROWS).collect::<Result<Vec<_>>>()?;
+            if chunk.is_empty() {
+                break;
+            }
+            chunks.push(chunk);
+        }
+        Ok(Box::new(ParallelScan {
+            pool: self.engine.pool.clone(),
+            workers: workers.min(self.engine.pool.size()),
+            decode,
+            chunks: chunks.into_iter(),
+            pending: VecDeque::new(),
+            rows: Vec::new().into_iter(),
+            back: Vec::new().into_iter(),
+        }))
     }
 
-    fn scan_index(&self, table: &str, column: &str) -> Result<super::IndexScan> {
+    fn scan_index(&self, table: &str, index: &str) -> Result<super::IndexScan> {
         let table = self.must_read_table(table)?;
-        let column = table.get_column(column)?;
-        if !column.index {
-            return Err(Error::Value(format!("No index for {}.{}", table.name, column.name)));
+        let index = table.get_index(index)?;
+        if index.fulltext {
+            // Entries are grouped by term, as a single value.
+            let mut entries: Vec<(Vec<Value>, HashSet<Value>)> = Vec::new();
+            let prefix = KeyPrefix::FullText((&table.name).into(), (&index.name).into());
+            for r in self.txn.scan_prefix(&prefix.encode()?)?.iter() {
+                let Key::FullText(_, _, term, id) = Key::decode(&r?.0)? else {
+                    return Err(Error::Internal("Invalid FULLTEXT index key".into()));
+                };
+                let term = Value::String(term.into_owned());
+                match entries.last_mut() {
+                    Some((values, ids)) if values[0] == term => {
+                        ids.insert(id.into_owned());
+                    }
+                    _ => entries.push((vec![term], HashSet::from([id.into_owned()]))),
+                }
+            }
+            return Ok(Box::new(entries.into_iter().map(Ok)));
         }
         Ok(Box::new(
             self.txn
                 .scan_prefix(
-                    &KeyPrefix::Index((&table.name).into(), (&column.name).into()).encode()?,
+                    &KeyPrefix::Index((&table.name).into(), (&index.name).into()).encode()?,
                 )?
                 .iter()
-                .map(|r| -> Result<(Value, HashSet<Value>)> {
+                .map(|r| -> Result<(Vec<Value>, HashSet<Value>)> {
                     let (k, v) = r?;
-                    let value = match Key::decode(&k)? {
-                        Key::Index(_, _, pk) => pk.into_owned(),
+                    let values = match Key::decode(&k)? {
+                        Key::Index(_, _, values) => values.into_owned(),
                         _ => return Err(Error::Internal("Invalid index key".into())),
                     };
-                    Ok((value, deserialize(&v)?))
+                    Ok((values, decode_index_entry(&index, &v)?.into_keys().collect()))
                 })
                 .collect::<Vec<_>>()
                 .into_iter(),
@@ -256,43 +1041,67 @@ impl<E: storage::Engine> super::Transaction for Transaction<E> {
     }
 
     fn update(&mut self, table: &str, id: &Value, row: Row) -> Result<()> {
+        self.update_batch(table, vec![(id.clone(), row)])
+    }
+
+    fn update_batch(&mut self, table: &str, rows: Vec<(Value, Row)>) -> Result<()> {
         let table = self.must_read_table(table)?;
-        // If the primary key changes we do a delete and create, otherwise we replace the row
-        if id != &table.get_row_key(&row)? {
-            self.delete(&table.name, id)?;
-            self.create(&table.name, row)?;
-            return Ok(());
-        }
+        let indexes = table.get_indexes();
+        let mut batch = IndexBatch::new();
+        let result = rows.into_iter().try_for_each(|(id, row)| {
+            // If the primary key changes we do a delete and create, otherwise we replace the row.
+            // The former writes indexes directly, so pending index entries are written first.
+            let new_id = table.get_row_key(&row)?;
+            if id != new_id {
+                self.index_flush(&indexes, std::mem::take(&mut batch))?;
+                return self.update_key(&table, &id, &new_id, row);
+            }
+            self.update_batched(&table, &indexes, &mut batch, &id, row)
+        });
+        // Rows written before an error remain, so their index entries are written too.
+        self.index_flush(&indexes, batch)?;
+        result
+    }
 
-        // Update indexes, knowing that the primary key has not changed
-        let indexes: Vec<_> = table.columns.iter().enumerate().filter(|(_, c)| c.index).collect();
-        if !indexes.is_empty() {
-            let old = self.read(&table.name, id)?.unwrap();
-            for (i, column) in indexes {
-                if old[i] == row[i] {
-                    continue;
-                }
-                let mut index = self.index_load(&table.name, &column.name, &old[i])?;
-                index.remove(id);
-                self.index_save(&table.name, &column.name, &old[i], index)?;
+    fn next_sequence(&mut self, table: &str, column: &str) -> Result<i64> {
+        self.engine.allocate_sequence(table, column, 1)
+    }
+}
 
-                let mut index = self.index_load(&table.name, &column.name, &row[i])?;
-                index.insert(id.clone());
-                self.index_save(&table.name, &column.name, &row[i], index)?;
-            }
-        }
+impl<E: storage::Engine> Sequences for Transaction<E> {
+    fn next_value(&mut self, sequence: &str) -> Result<i64> {
+        Ok(self.allocate_sequence_values(sequence, 1)?.0)
+    }
 
-        table.validate_row(&row, self)?;
-        self.txn.set(&Key::Row(table.name.into(), id.into()).encode()?, serialize(&row)?)
+    // Current values are tracked by the session, see SessionTransaction.
+    fn current_value(&self, sequence: &str) -> Result<i64> {
+        Err(Error::Value(format!("Sequence {} has no current value in this session", sequence)))
+    }
+
+    fn set_value(&mut self, sequence: &str, value: i64) -> Result<()> {
+        self.set_sequence_value(sequence, value)
     }
 }
 
 impl<E: storage::Engine> Catalog for Transaction<E> {
     fn create_table(&mut self, table: Table) -> Result<()> {
+        self.check_database(&table.name)?;
         if self.read_table(&table.name)?.is_some() {
             return Err(Error::Value(format!("Table {} already exists", table.name)));
         }
+        if self.read_view(&table.name)?.is_some() {
+            return Err(Error::Value(format!("View {} already exists", table.name)));
+        }
+        for index in &table.indexes {
+            if let Some(other) = self.read_index_table(&index.name)? {
+                return Err(Error::Value(format!(
+                    "Index {} already exists on table {}",
+                    index.name, other.name
+                )));
+            }
+        }
         table.validate(self)?;
+        self.bump_schema_version()?;
         self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)
     }
 
@@ -304,13 +1113,395 @@ impl<E: storage::Engine> Catalog for Transaction<E> {
                 table.name, t, cs[0]
             )));
         }
-        let mut scan = self.scan(&table.name, None)?;
+        let mut scan = self.scan(&table.name, None, false, None, None)?;
         while let Some(row) = scan.next().transpose()? {
             self.delete(&table.name, &table.get_row_key(&row)?)?
         }
+        self.txn.delete(&Key::Statistics((&table.name).into()).encode()?)?;
+        self.bump_schema_version()?;
         self.txn.delete(&Key::Table(table.name.into()).encode()?)
     }
 
+    fn add_column(&mut self, table: &str, column: Column) -> Result<()> {
+        let mut table = self.must_read_table(table)?;
+        if table.get_column(&column.name).is_ok() {
+            return Err(Error::Value(format!(
+                "Column {} already exists in table {}",
+                column.name, table.name
+            )));
+        }
+        if column.primary_key {
+            return Err(Error::Value(format!(
+                "Can't add primary key column {} to table {}",
+                column.name, table.name
+            )));
+        }
+        self.rewrite_dropped(&table)?;
+        let rows = self.scan(&table.name, None, false, None, None)?.collect::<Result<Vec<_>>>()?;
+        if !rows.is_empty() && column.default.is_none() && column.generated.is_none() {
+            return Err(Error::Value(format!(
+                "Column {} must have a default value, since table {} has rows",
+                column.name, table.name
+            )));
+        }
+        // Concurrent writers pad rows with constant defaults, but don't index or check the column.
+        table.compatibility = match column.default {
+            Some(Expression::Constant(_))
+                if !column.index && !column.unique && column.references.is_none() =>
+            {
+                Compatibility::Compatible
+            }
+            _ => Compatibility::Incompatible,
+        };
+        table.columns.push(column);
+        table.version += 1;
+        table.validate(self)?;
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)?;
+
+        // Existing rows are padded with a constant default value when read, but non-constant
+        // defaults (e.g. NOW()) and generated values are evaluated and written for each row.
+        // Check that the value is valid for all rows, and index it.
+        let column = table.columns.last().expect("column was added");
+        let index = (column.index || column.unique).then(|| table.get_index(&column.name));
+        let index = index.transpose()?;
+        for mut row in rows {
+            match &column.default {
+                Some(default) => row.push(default.evaluate_sequences(None, self)?),
+                None => row.push(Value::Null),
+            }
+            table.generate_row(&mut row)?;
+            let id = table.get_row_key(&row)?;
+            if !matches!(column.default, Some(Expression::Constant(_))) {
+                self.txn.set(
+                    &Key::Row((&table.name).into(), (&id).into()).encode()?,
+                    encode_row(&table, &row)?,
+                )?;
+            }
+            // Deferred constraints are checked immediately for existing rows.
+            let value = row.last().expect("row was extended");
+            column.validate_value(&table, &id, value, self)?;
+            if column.deferred {
+                column.validate_reference(&table, &id, value, self)?;
+            }
+            if let Some(index) = &index {
+                self.index_check_unique(&table, index, &id, &row)?;
+                self.index_insert(&table, index, &id, &row)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn drop_column(&mut self, table: &str, column: &str) -> Result<()> {
+        let mut table = self.must_read_table(table)?;
+        if let Some(generated) = table.get_generated_by(column)?.first() {
+            return Err(Error::Value(format!(
+                "Can't drop column {}, used by generated column {}",
+                column, generated.name
+            )));
+        }
+        let position = table.get_column_index(column)?;
+        let dropped: Vec<String> = table
+            .indexes
+            .iter()
+            .filter(|i| i.has_column(&table, column))
+            .map(|i| i.name.clone())
+            .collect();
+        let column = table.columns.remove(position);
+        if column.primary_key {
+            return Err(Error::Value(format!(
+                "Can't drop primary key column {} from table {}",
+                column.name, table.name
+            )));
+        }
+        // Generated columns and key expressions refer to the following columns by position, which
+        // shifts.
+        let expressions = table.indexes.iter_mut().flat_map(|i| i.expressions.iter_mut().flatten());
+        for expr in table.columns.iter_mut().filter_map(|c| c.generated.as_mut()).chain(expressions)
+        {
+            *expr = std::mem::replace(expr, Expression::Constant(Value::Null)).transform(
+                &|e| match e {
+                    Expression::Field(i, label) if i > position => {
+                        Ok(Expression::Field(i - 1, label))
+                    }
+                    e => Ok(e),
+                },
+                &Ok,
+            )?;
+        }
+
+        // Column positions shift, so existing rows must be rewritten without the dropped value.
+        for row in self.scan(&table.name, None, false, None, None)? {
+            let mut row = row?;
+            row.remove(position);
+            let id = table.get_row_key(&row)?;
+            self.txn.set(
+                &Key::Row((&table.name).into(), (&id).into()).encode()?,
+                encode_row(&table, &row)?,
+            )?;
+        }
+        // Indexes containing the column are dropped along with it.
+        if column.index || column.unique {
+            self.index_clear(&table.name, &column.name)?;
+        }
+        for index in &dropped {
+            self.index_clear(&table.name, index)?;
+        }
+        // Concurrent writers still write the dropped column, which is removed when read, but they
+        // also write entries for its indexes.
+        table.compatibility = match column.index || column.unique || !dropped.is_empty() {
+            true => Compatibility::Incompatible,
+            false => Compatibility::Dropped(position),
+        };
+        table.indexes.retain(|i| !dropped.contains(&i.name));
+        table.version += 1;
+        if let Some(mut statistics) = self.read_statistics(&table.name)? {
+            statistics.columns.remove(&column.name);
+            self.write_statistics(&table.name, statistics)?;
+        }
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)
+    }
+
+    fn alter_column(&mut self, table: &str, column: &str, change: ColumnChange) -> Result<()> {
+        let mut table = self.must_read_table(table)?;
+        let position = table.get_column_index(column)?;
+        if let (ColumnChange::Type(_), Some(generated)) =
+            (&change, table.get_generated_by(column)?.first())
+        {
+            return Err(Error::Value(format!(
+                "Can't change datatype of column {}, used by generated column {}",
+                column, generated.name
+            )));
+        }
+        self.rewrite_dropped(&table)?;
+        let rows = self.scan(&table.name, None, false, None, None)?.collect::<Result<Vec<_>>>()?;
+
+        let column = &mut table.columns[position];
+        match &change {
+            ColumnChange::Type(datatype) => {
+                if column.primary_key {
+                    return Err(Error::Value(format!(
+                        "Can't change datatype of primary key column {} in table {}",
+                        column.name, table.name
+                    )));
+                }
+                if column.generated.is_some() {
+                    return Err(Error::Value(format!(
+                        "Can't change datatype of generated column {} in table {}",
+                        column.name, table.name
+                    )));
+                }
+                // Constant defaults are cast along with the existing values.
+                if let Some(Expression::Constant(default)) = &mut column.default {
+                    *default = default.clone().cast(datatype)?;
+                }
+                column.datatype = datatype.clone();
+            }
+            ColumnChange::Nullable(false) if rows.iter().any(|r| r[position] == Value::Null) => {
+                return Err(Error::Value(format!(
+                    "Column {} in table {} contains NULL values",
+                    column.name, table.name
+                )));
+            }
+            ColumnChange::Nullable(nullable) => {
+                // Nullable columns default to NULL, which isn't a valid default when NOT NULL.
+                match (nullable, &column.default) {
+                    (true, None) if !column.auto_increment => {
+                        column.default = Some(Expression::Constant(Value::Null))
+                    }
+                    (false, Some(Expression::Constant(Value::Null))) => column.default = None,
+                    _ => {}
+                }
+                column.nullable = *nullable;
+            }
+        }
+        // Concurrent writers may write values of the previous datatype, or NULLs, but rows valid
+        // for a NOT NULL column are also valid once it's nullable.
+        table.compatibility = match change {
+            ColumnChange::Nullable(true) => Compatibility::Compatible,
+            _ => Compatibility::Incompatible,
+        };
+        table.version += 1;
+        table.validate(self)?;
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)?;
+        let ColumnChange::Type(_) = change else { return Ok(()) };
+
+        // Existing values are cast to the new datatype, and the column's indexes are rebuilt
+        // with them. Statistics for the column are no longer valid.
+        let column = &table.columns[position];
+        let indexes: Vec<Index> = table
+            .get_indexes()
+            .into_iter()
+            .filter(|i| i.has_column(&table, &column.name))
+            .collect();
+        for index in &indexes {
+            self.index_clear(&table.name, &index.name)?;
+        }
+        for mut row in rows {
+            row[position] =
+                std::mem::replace(&mut row[position], Value::Null).cast(&column.datatype)?;
+            let id = table.get_row_key(&row)?;
+            column.validate_value(&table, &id, &row[position], self)?;
+            for index in &indexes {
+                self.index_check_unique(&table, index, &id, &row)?;
+                self.index_insert(&table, index, &id, &row)?;
+            }
+            self.txn.set(
+                &Key::Row((&table.name).into(), (&id).into()).encode()?,
+                encode_row(&table, &row)?,
+            )?;
+        }
+        if let Some(mut statistics) = self.read_statistics(&table.name)? {
+            statistics.columns.remove(&column.name);
+            self.write_statistics(&table.name, statistics)?;
+        }
+        Ok(())
+    }
+
+    fn create_index(&mut self, table: &str, index: Index) -> Result<()> {
+        let mut table = self.must_read_table(table)?;
+        if let Some(other) = self.read_index_table(&index.name)? {
+            return Err(Error::Value(format!(
+                "Index {} already exists on table {}",
+                index.name, other.name
+            )));
+        }
+        self.rewrite_dropped(&table)?;
+        // Concurrent writers don't write entries for the index, which a building index is
+        // only required to contain once it's built.
+        table.compatibility = match index.building {
+            true => Compatibility::Compatible,
+            false => Compatibility::Incompatible,
+        };
+        table.indexes.push(index.clone());
+        table.version += 1;
+        table.validate(self)?;
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)?;
+        if index.building {
+            return Ok(());
+        }
+        for row in self.scan(&table.name, None, false, None, None)? {
+            let row = row?;
+            let id = table.get_row_key(&row)?;
+            self.index_check_unique(&table, &index, &id, &row)?;
+            self.index_insert(&table, &index, &id, &row)?;
+        }
+        Ok(())
+    }
+
+    fn build_index(
+        &mut self,
+        index: &str,
+        after: Option<Value>,
+        limit: usize,
+    ) -> Result<Option<Value>> {
+        let mut table = self
+            .read_index_table(index)?
+            .ok_or_else(|| Error::Value(format!("Index {} does not exist", index)))?;
+        let position = table.indexes.iter().position(|i| i.name == index).expect("index exists");
+        if !table.indexes[position].building {
+            return Err(Error::Value(format!("Index {} has already been built", index)));
+        }
+
+        // Rows are scanned in key order from the one following the given key.
+        let prefix = KeyPrefix::Row((&table.name).into()).encode()?;
+        let start = match &after {
+            Some(id) => Bound::Excluded(Key::Row((&table.name).into(), id.into()).encode()?),
+            None => Bound::Included(prefix.clone()),
+        };
+        let rows = self
+            .txn
+            .scan((start, prefix_end(&prefix)))?
+            .iter()
+            .take(limit)
+            .map(|r| r.and_then(|(_, v)| decode_row(&table, &v, None, None)))
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>>>()?;
+        let mut last = None;
+        for row in &rows {
+            let id = table.get_row_key(row)?;
+            self.index_check_unique(&table, &table.indexes[position], &id, row)?;
+            self.index_insert(&table, &table.indexes[position], &id, row)?;
+            last = Some(id);
+        }
+        if rows.len() == limit {
+            return Ok(last);
+        }
+
+        self.rewrite_dropped(&table)?;
+        table.indexes[position].building = false;
+        table.compatibility = Compatibility::Compatible;
+        table.version += 1;
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)?;
+        Ok(None)
+    }
+
+    fn drop_index(&mut self, index: &str) -> Result<()> {
+        let mut table = self
+            .read_index_table(index)?
+            .ok_or_else(|| Error::Value(format!("Index {} does not exist", index)))?;
+        self.rewrite_dropped(&table)?;
+        // Concurrent writers would write entries for the dropped index.
+        table.indexes.retain(|i| i.name != index);
+        table.compatibility = Compatibility::Incompatible;
+        table.version += 1;
+        self.index_clear(&table.name, index)?;
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)
+    }
+
+    fn create_trigger(&mut self, table: &str, trigger: Trigger) -> Result<()> {
+        let mut table = self.must_read_table(table)?;
+        if table.triggers.iter().any(|t| t.name == trigger.name) {
+            return Err(Error::Value(format!(
+                "Trigger {} already exists on table {}",
+                trigger.name, table.name
+            )));
+        }
+        self.rewrite_dropped(&table)?;
+        // Concurrent writers wouldn't fire the trigger.
+        table.triggers.push(trigger);
+        table.compatibility = Compatibility::Incompatible;
+        table.version += 1;
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)
+    }
+
+    fn drop_trigger(&mut self, table: &str, trigger: &str) -> Result<()> {
+        let mut table = self.must_read_table(table)?;
+        table.get_trigger(trigger)?;
+        self.rewrite_dropped(&table)?;
+        // Concurrent writers would fire the dropped trigger.
+        table.triggers.retain(|t| t.name != trigger);
+        table.compatibility = Compatibility::Incompatible;
+        table.version += 1;
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)
+    }
+
+    fn set_comment(
+        &mut self,
+        table: &str,
+        column: Option<&str>,
+        comment: Option<String>,
+    ) -> Result<()> {
+        // Comments don't affect rows, so the table version is unchanged.
+        let mut table = self.must_read_table(table)?;
+        match column {
+            Some(column) => {
+                let position = table.get_column_index(column)?;
+                table.columns[position].comment = comment;
+            }
+            None => table.comment = comment,
+        }
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)
+    }
+
     fn read_table(&self, table: &str) -> Result<Option<Table>> {
         self.txn.get(&Key::Table(table.into()).encode()?)?.map(|v| deserialize(&v)).transpose()
     }
@@ -325,6 +1516,343 @@ impl<E: storage::Engine> Catalog for Transaction<E> {
                 .into_iter(),
         ))
     }
+
+    fn create_view(&mut self, view: View) -> Result<()> {
+        self.check_database(&view.name)?;
+        if self.read_view(&view.name)?.is_some() {
+            return Err(Error::Value(format!("View {} already exists", view.name)));
+        }
+        if self.read_table(&view.name)?.is_some() {
+            return Err(Error::Value(format!("Table {} already exists", view.name)));
+        }
+        self.bump_schema_version()?;
+        self.txn.set(&Key::View((&view.name).into()).encode()?, serialize(&view)?)
+    }
+
+    fn delete_view(&mut self, view: &str) -> Result<()> {
+        if self.read_view(view)?.is_none() {
+            return Err(Error::Value(format!("View {} does not exist", view)));
+        }
+        self.bump_schema_version()?;
+        self.txn.delete(&Key::View(view.into()).encode()?)
+    }
+
+    fn read_view(&self, view: &str) -> Result<Option<View>> {
+        self.txn.get(&Key::View(view.into()).encode()?)?.map(|v| deserialize(&v)).transpose()
+    }
+
+    fn scan_views(&self) -> Result<Views> {
+        Ok(Box::new(
+            self.txn
+                .scan_prefix(&KeyPrefix::View.encode()?)?
+                .iter()
+                .map(|r| r.and_then(|(_, v)| deserialize(&v)))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter(),
+        ))
+    }
+
+    fn create_sequence(&mut self, mut sequence: Sequence) -> Result<()> {
+        self.check_database(&sequence.name)?;
+        if self.read_sequence(&sequence.name)?.is_some() {
+            return Err(Error::Value(format!("Sequence {} already exists", sequence.name)));
+        }
+        sequence.version = self.txn.version();
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Sequence((&sequence.name).into()).encode()?, serialize(&sequence)?)
+    }
+
+    // The sequence's value is left behind, and ignored if it's recreated.
+    fn delete_sequence(&mut self, sequence: &str) -> Result<()> {
+        self.must_read_sequence(sequence)?;
+        self.bump_schema_version()?;
+        self.txn.delete(&Key::Sequence(sequence.into()).encode()?)
+    }
+
+    fn read_sequence(&self, sequence: &str) -> Result<Option<Sequence>> {
+        self.txn
+            .get(&Key::Sequence(sequence.into()).encode()?)?
+            .map(|v| deserialize(&v))
+            .transpose()
+    }
+
+    fn scan_sequences(&self) -> Result<Vec<Sequence>> {
+        self.txn
+            .scan_prefix(&KeyPrefix::Sequence.encode()?)?
+            .iter()
+            .map(|r| r.and_then(|(_, v)| deserialize(&v)))
+            .collect()
+    }
+
+    fn create_type(&mut self, enum_type: EnumType) -> Result<()> {
+        self.check_database(&enum_type.name)?;
+        if self.read_type(&enum_type.name)?.is_some() {
+            return Err(Error::Value(format!("Type {} already exists", enum_type.name)));
+        }
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Type((&enum_type.name).into()).encode()?, serialize(&enum_type)?)
+    }
+
+    fn delete_type(&mut self, name: &str) -> Result<()> {
+        if self.read_type(name)?.is_none() {
+            return Err(Error::Value(format!("Type {} does not exist", name)));
+        }
+        for table in self.scan_tables()? {
+            if let Some(column) = table.get_type_column(name) {
+                return Err(Error::Value(format!(
+                    "Type {} is used by column {}.{}",
+                    name, table.name, column.name
+                )));
+            }
+        }
+        self.bump_schema_version()?;
+        self.txn.delete(&Key::Type(name.into()).encode()?)
+    }
+
+    fn read_type(&self, name: &str) -> Result<Option<EnumType>> {
+        self.txn.get(&Key::Type(name.into()).encode()?)?.map(|v| deserialize(&v)).transpose()
+    }
+
+    fn scan_types(&self) -> Result<Vec<EnumType>> {
+        self.txn
+            .scan_prefix(&KeyPrefix::Type.encode()?)?
+            .iter()
+            .map(|r| r.and_then(|(_, v)| deserialize(&v)))
+            .collect()
+    }
+
+    fn write_statistics(&mut self, table: &str, statistics: Statistics) -> Result<()> {
+        self.must_read_table(table)?;
+        self.txn.set(&Key::Statistics(table.into()).encode()?, serialize(&statistics)?)
+    }
+
+    fn read_statistics(&self, table: &str) -> Result<Option<Statistics>> {
+        self.txn.get(&Key::Statistics(table.into()).encode()?)?.map(|v| deserialize(&v)).transpose()
+    }
+
+    fn schema_version(&self) -> Result<u64> {
+        Ok(self
+            .txn
+            .get(&Key::SchemaVersion.encode()?)?
+            .map(|v| deserialize(&v))
+            .transpose()?
+            .unwrap_or(0))
+    }
+
+    fn create_database(&mut self, database: &str) -> Result<()> {
+        if database.is_empty() || database.contains('.') {
+            return Err(Error::Value(format!("Invalid database name {}", database)));
+        }
+        if database == InformationSchema::NAMESPACE || self.has_database(database)? {
+            return Err(Error::Value(format!("Database {} already exists", database)));
+        }
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Database(database.into()).encode()?, serialize(&database)?)
+    }
+
+    fn scan_databases(&self) -> Result<Vec<String>> {
+        self.txn
+            .scan_prefix(&KeyPrefix::Database.encode()?)?
+            .iter()
+            .map(|r| r.and_then(|(_, v)| deserialize(&v)))
+            .collect()
+    }
+
+    fn create_user(&mut self, user: User) -> Result<()> {
+        if self.read_user(&user.name)?.is_some() {
+            return Err(Error::Value(format!("User {} already exists", user.name)));
+        }
+        self.txn.set(&Key::User((&user.name).into()).encode()?, serialize(&user)?)
+    }
+
+    fn alter_user(&mut self, user: User) -> Result<()> {
+        if self.read_user(&user.name)?.is_none() {
+            return Err(Error::Value(format!("User {} does not exist", user.name)));
+        }
+        self.txn.set(&Key::User((&user.name).into()).encode()?, serialize(&user)?)
+    }
+
+    fn delete_user(&mut self, name: &str) -> Result<()> {
+        if self.read_user(name)?.is_none() {
+            return Err(Error::Value(format!("User {} does not exist", name)));
+        }
+        self.txn.delete(&Key::User(name.into()).encode()?)
+    }
+
+    fn read_user(&self, name: &str) -> Result<Option<User>> {
+        self.txn.get(&Key::User(name.into()).encode()?)?.map(|v| deserialize(&v)).transpose()
+    }
+
+    fn scan_users(&self) -> Result<Vec<User>> {
+        self.txn
+            .scan_prefix(&KeyPrefix::User.encode()?)?
+            .iter()
+            .map(|r| r.and_then(|(_, v)| deserialize(&v)))
+            .collect()
+    }
+
+    fn set_privileges(
+        &mut self,
+        table: &str,
+        grantee: &str,
+        privileges: BTreeSet<Privilege>,
+    ) -> Result<()> {
+        // Privileges don't affect rows, so the table version is unchanged.
+        let mut table = self.must_read_table(table)?;
+        match privileges.is_empty() {
+            true => table.privileges.remove(grantee),
+            false => table.privileges.insert(grantee.to_string(), privileges),
+        };
+        self.bump_schema_version()?;
+        self.txn.set(&Key::Table((&table.name).into()).encode()?, serialize(&table)?)
+    }
+}
+
+/// Returns the columns to decode for a scan. The filter is evaluated against
+/// the decoded columns, so its columns are added.
+fn scan_columns(columns: Option<Vec<usize>>, filter: Option<&Expression>) -> Option<Vec<usize>> {
+    columns.map(|mut columns| {
+        columns.extend(filter.iter().flat_map(|f| f.fields()));
+        columns.sort();
+        columns.dedup();
+        columns
+    })
+}
+
+/// Returns the key range to scan for a table's rows, bounded by any primary key
+/// range in the scan filter, or None if the range is empty. Bounds are only used
+/// for datatypes whose key encoding sorts in the same order as their values.
+fn row_range(table: &Table, filter: Option<&Expression>) -> Result<Option<KeyRange>> {
+    let prefix = KeyPrefix::Row((&table.name).into()).encode()?;
+    let end = prefix_end(&prefix);
+    let mut range = (Bound::Included(prefix), end);
+
+    let pk = table.get_primary_key()?;
+    let field = table.get_column_index(&pk.name)?;
+    let Some((start, end)) = filter.and_then(|filter| filter.as_range(field)) else {
+        return Ok(Some(range));
+    };
+    let ordered = |bound: &Bound<Value>| match bound {
+        Bound::Included(v) | Bound::Excluded(v) => matches!(
+            (&pk.datatype, v),
+            (DataType::Boolean, Value::Boolean(_))
+                | (DataType::Integer, Value::Integer(_))
+                | (DataType::String, Value::String(_))
+        ),
+        Bound::Unbounded => true,
+    };
+    if !ordered(&start) || !ordered(&end) {
+        return Ok(Some(range));
+    }
+    let key = |v: &Value| Key::Row((&table.name).into(), v.into()).encode();
+    match start {
+        Bound::Included(v) => range.0 = Bound::Included(key(&v)?),
+        Bound::Excluded(v) => range.0 = Bound::Excluded(key(&v)?),
+        Bound::Unbounded => {}
+    }
+    match end {
+        Bound::Included(v) => range.1 = Bound::Included(key(&v)?),
+        Bound::Excluded(v) => range.1 = Bound::Excluded(key(&v)?),
+        Bound::Unbounded => {}
+    }
+    Ok((!is_empty(&range)).then_some(range))
+}
+
+/// Decodes an index entry. Entries of indexes without included columns are
+/// stored as a set of primary keys, otherwise as a map of primary keys to the
+/// values of the included columns.
+fn decode_index_entry(index: &Index, value: &[u8]) -> Result<HashMap<Value, Vec<Value>>> {
+    if index.include.is_empty() {
+        let ids: HashSet<Value> = deserialize(value)?;
+        return Ok(ids.into_iter().map(|id| (id, Vec::new())).collect());
+    }
+    deserialize(value)
+}
+
+/// Encodes an index entry, see decode_index_entry().
+fn encode_index_entry(index: &Index, ids: HashMap<Value, Vec<Value>>) -> Result<Vec<u8>> {
+    if index.include.is_empty() {
+        return serialize(&ids.into_keys().collect::<HashSet<_>>());
+    }
+    serialize(&ids)
+}
+
+/// Encodes the key of an index entry, given the values of its leading columns.
+/// Values of collated columns are encoded as their collation keys, such that
+/// values that are equal under the collation share an entry (e.g. to enforce
+/// unique indexes) and entries are ordered by the collation. Decoded index keys
+/// therefore contain collation keys rather than the original values.
+fn index_key(table: &Table, index: &Index, values: &[Value]) -> Result<Vec<u8>> {
+    let mut values = Cow::Borrowed(values);
+    for (i, column) in index.columns.iter().enumerate().take(values.len()) {
+        if index.get_expression(i).is_some() {
+            continue;
+        }
+        let collation = table.get_column(column)?.collation;
+        if collation != Collation::Binary {
+            let key = collation.key(values[i].clone());
+            values.to_mut()[i] = key;
+        }
+    }
+    Key::Index((&table.name).into(), (&index.name).into(), values).encode()
+}
+
+/// Returns the key prefix of a FULLTEXT index's entries for a term.
+fn fulltext_prefix(table: &str, index: &str, term: &str) -> Result<Vec<u8>> {
+    let mut prefix = KeyPrefix::FullText(table.into(), index.into()).encode()?;
+    prefix.extend(keycode::serialize(&term)?);
+    Ok(prefix)
+}
+
+/// Returns the key range to scan for an index's entries whose leading column
+/// values are in the given range, or None if the range is empty. Keys of
+/// multi-column indexes start with the leading columns' key encoding, so included
+/// bounds span all keys with that prefix while excluded bounds skip them.
+fn index_range(table: &Table, index: &Index, range: IndexRange) -> Result<Option<KeyRange>> {
+    let key = |values: Vec<Value>| index_key(table, index, &values);
+    let start = match range.0 {
+        Bound::Included(v) => Bound::Included(key(v)?),
+        Bound::Excluded(v) => match prefix_end(&key(v)?) {
+            Bound::Excluded(end) => Bound::Included(end),
+            _ => return Ok(None),
+        },
+        // NULL values never match a range, so they're skipped.
+        Bound::Unbounded => match prefix_end(&key(vec![Value::Null])?) {
+            Bound::Excluded(end) => Bound::Included(end),
+            _ => return Ok(None),
+        },
+    };
+    let end = match range.1 {
+        Bound::Included(v) => prefix_end(&key(v)?),
+        Bound::Excluded(v) => Bound::Excluded(key(v)?),
+        Bound::Unbounded => {
+            prefix_end(&KeyPrefix::Index((&table.name).into(), (&index.name).into()).encode()?)
+        }
+    };
+    let range = (start, end);
+    Ok((!is_empty(&range)).then_some(range))
+}
+
+/// A raw key range.
+type KeyRange = (Bound<Vec<u8>>, Bound<Vec<u8>>);
+
+/// Returns the exclusive end of the key range with the given prefix.
+fn prefix_end(prefix: &[u8]) -> Bound<Vec<u8>> {
+    match prefix.iter().rposition(|b| *b != 0xff) {
+        Some(i) => Bound::Excluded(prefix[..i].iter().copied().chain([prefix[i] + 1]).collect()),
+        None => Bound::Unbounded,
+    }
+}
+
+/// Returns true if a key range can't contain any keys.
+fn is_empty(range: &KeyRange) -> bool {
+    match range {
+        (Bound::Included(s), Bound::Included(e)) => s > e,
+        (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e)) => {
+            s >= e
+        }
+        _ => false,
+    }
 }
 
 /// SQL keys, using the KeyCode order-preserving encoding. Uses table and column
@@ -335,15 +1863,59 @@ impl<E: storage::Engine> Catalog for Transaction<E> {
 enum Key<'a> {
     /// A table schema by table name.
     Table(Cow<'a, str>),
-    /// An index entry, by table name, index name, and index value.
-    Index(Cow<'a, str>, Cow<'a, str>, Cow<'a, Value>),
+    /// An index entry, by table name, index name, and index values (one per
+    /// indexed column, encoded as their concatenation).
+    Index(Cow<'a, str>, Cow<'a, str>, Cow<'a, [Value]>),
+    /// A FULLTEXT index entry, by table name, index name, term, and the
+    /// primary key of a row containing the term. The value is empty.
+    FullText(Cow<'a, str>, Cow<'a, str>, Cow<'a, str>, Cow<'a, Value>),
     /// A table row, by table name and primary key value.
     Row(Cow<'a, str>, Cow<'a, Value>),
+    /// A view by view name.
+    View(Cow<'a, str>),
+    /// A database by name. The default database isn't stored.
+    Database(Cow<'a, str>),
+    /// The next value of a column's auto-increment sequence, by table and
+    /// column name. Stored as an unversioned key.
+    AutoIncrement(Cow<'a, str>, Cow<'a, str>),
+    /// Table statistics by table name, as collected by ANALYZE.
+    Statistics(Cow<'a, str>),
+    /// The schema version, see Catalog::schema_version().
+    SchemaVersion,
+    /// The version of the last transaction that committed row writes to a
+    /// table using its previous schema version, by table name. Stored as an
+    /// unversioned key.
+    SchemaSkew(Cow<'a, str>),
+    /// A sequence by sequence name.
+    Sequence(Cow<'a, str>),
+    /// The version and next value of a sequence, by sequence name. Stored as
+    /// an unversioned key.
+    SequenceValue(Cow<'a, str>),
+    /// An enum type by type name.
+    Type(Cow<'a, str>),
+    /// A user by user name.
+    User(Cow<'a, str>),
 }
 
 impl<'a> Key<'a> {
     fn encode(self) -> Result<Vec<u8>> {
-        keycode::serialize(&self)
+        // Equal values must have equal keys, so decimals are normalized to strip trailing zeros.
+        let is_decimal = |value: &Value| matches!(value, Value::Decimal(_));
+        let normalize = |value: &Value| match value {
+            Value::Decimal(d) => Value::Decimal(d.normalize()),
+            value => value.clone(),
+        };
+        let key = match self {
+            Self::Index(table, index, values) if values.iter().any(is_decimal) => {
+                Self::Index(table, index, values.iter().map(normalize).collect())
+            }
+            Self::Row(table, id) if is_decimal(&id) => Self::Row(table, Cow::Owned(normalize(&id))),
+            Self::FullText(table, index, term, id) if is_decimal(&id) => {
+                Self::FullText(table, index, term, Cow::Owned(normalize(&id)))
+            }
+            key => key,
+        };
+        keycode::serialize(&key)
     }
 
     fn decode(bytes: &[u8]) -> Result<Self> {
@@ -359,8 +1931,28 @@ enum KeyPrefix<'a> {
     Table,
     /// An entire table index, by table and index name.
     Index(Cow<'a, str>, Cow<'a, str>),
+    /// An entire FULLTEXT index, by table and index name. Followed by a term's
+    /// encoding, this is the prefix of the term's entries, see fulltext_prefix().
+    FullText(Cow<'a, str>, Cow<'a, str>),
     /// An entire table's rows, by table name.
     Row(Cow<'a, str>),
+    /// All views.
+    View,
+    /// All databases.
+    Database,
+    /// Placeholders for keys that aren't scanned by prefix, such that the
+    /// variant indexes of following prefixes match their keys.
+    AutoIncrement,
+    Statistics,
+    SchemaVersion,
+    SchemaSkew,
+    /// All sequences.
+    Sequence,
+    SequenceValue,
+    /// All enum types.
+    Type,
+    /// All users.
+    User,
 }
 
 impl<'a> KeyPrefix<'a> {
@@ -1,17 +1,42 @@
 //! The SQL engine provides fundamental CRUD storage operations.
+mod cache;
+mod catalog;
 mod kv;
+mod pool;
+mod queries;
 pub mod raft;
+mod settings;
+mod temporary;
+pub use catalog::CatalogCache;
 pub use kv::KV;
+pub use pool::WorkerPool;
+pub use queries::Queries;
 pub use raft::{Raft, Status};
+pub use settings::Settings;
+use temporary::CurrentValues;
+pub use temporary::SessionTransaction;
 
-use super::execution::ResultSet;
+use cache::PlanCache;
+
+use super::execution::{self, Cancel, ResultSet};
 use super::parser::{ast, Parser};
-use super::plan::Plan;
-use super::schema::Catalog;
-use super::types::{Expression, Row, Value};
+use super::plan::{Node, Plan};
+use super::schema::{Catalog, Index, Privilege, User, DEFAULT_DATABASE};
+use super::types::{self, datetime, DataType, Expression, Row, Sequences, Value};
 use crate::error::{Error, Result};
 
+use chrono::NaiveDateTime;
 use std::collections::HashSet;
+use std::io::Read;
+use std::ops::Bound;
+use std::time::{Duration, Instant};
+
+/// The number of rows indexed per transaction when building an index online.
+const INDEX_BUILD_BATCH_SIZE: usize = 1000;
+
+/// How often to check whether concurrent transactions have completed before
+/// building an index online.
+const INDEX_BUILD_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 /// The SQL engine interface
 pub trait Engine: Clone {
@@ -27,75 +52,239 @@ pub trait Engine: Clone {
     /// Begins a read-only transaction as of a historical version.
     fn begin_as_of(&self, version: u64) -> Result<Self::Transaction>;
 
+    /// Returns the version of the first read-write transaction that began
+    /// after the given time, if any, for time-travel queries by time.
+    fn version_at(&self, time: NaiveDateTime) -> Result<Option<u64>>;
+
+    /// Returns the registry of running queries, shared by all sessions.
+    fn queries(&self) -> &Queries;
+
+    /// Returns the cache of table schemas, shared by all sessions.
+    fn catalog_cache(&self) -> &CatalogCache;
+
     /// Begins a session for executing individual statements
     fn session(&self) -> Session<Self> {
-        Session { engine: self.clone(), txn: None }
+        Session {
+            engine: self.clone(),
+            temp: temporary::Engine::new(crate::storage::Memory::new()),
+            txn: None,
+            savepoints: Vec::new(),
+            settings: Settings::default(),
+            cache: PlanCache::default(),
+            sequences: CurrentValues::default(),
+            user: None,
+        }
     }
 }
 
 /// An SQL transaction
-pub trait Transaction: Catalog {
+pub trait Transaction: Catalog + Sequences {
     /// The transaction's version
     fn version(&self) -> u64;
     /// Whether the transaction is read-only
     fn read_only(&self) -> bool;
+    /// The versions of the read-write transactions that were active when the
+    /// transaction began, whose writes it can't see
+    fn concurrent(&self) -> &HashSet<u64>;
+    /// The session's authenticated user, if any, as whom privileges are
+    /// checked. Only set for session transactions, see SessionTransaction.
+    fn user(&self) -> Option<&str> {
+        None
+    }
 
     /// Commits the transaction
     fn commit(self) -> Result<()>;
     /// Rolls back the transaction
     fn rollback(self) -> Result<()>;
+    /// Creates a savepoint with the given ID, replacing any savepoints with the
+    /// same or higher IDs
+    fn savepoint(&mut self, id: u64) -> Result<()>;
+    /// Rolls back to a savepoint, undoing writes made since it was created
+    fn rollback_to_savepoint(&mut self, id: u64) -> Result<()>;
+    /// Releases a savepoint and any later savepoints, keeping their writes
+    fn release_savepoint(&mut self, id: u64) -> Result<()>;
 
     /// Creates a new table row
     fn create(&mut self, table: &str, row: Row) -> Result<()>;
+    /// Creates a batch of new table rows, as if by create() for each row, but
+    /// in a single request to the storage engine (e.g. a single Raft proposal).
+    /// Modified index entries are written once per batch, in key order.
+    fn create_batch(&mut self, table: &str, rows: Vec<Row>) -> Result<()>;
     /// Deletes a table row
     fn delete(&mut self, table: &str, id: &Value) -> Result<()>;
     /// Reads a table row, if it exists
     fn read(&self, table: &str, id: &Value) -> Result<Option<Row>>;
-    /// Reads an index entry, if it exists
-    fn read_index(&self, table: &str, column: &str, value: &Value) -> Result<HashSet<Value>>;
-    /// Scans a table's rows
-    fn scan(&self, table: &str, filter: Option<Expression>) -> Result<Scan>;
-    /// Scans a column's index entries
-    fn scan_index(&self, table: &str, column: &str) -> Result<IndexScan>;
+    /// Reads an index entry, given a value for each indexed column
+    fn read_index(&self, table: &str, index: &str, values: &[Value]) -> Result<HashSet<Value>>;
+    /// Reads the primary keys of the index entries whose first column value is in
+    /// the given range, ordered by index value and then primary key
+    fn read_index_range(
+        &self,
+        table: &str,
+        index: &str,
+        range: (Bound<Value>, Bound<Value>),
+    ) -> Result<Vec<Value>>;
+    /// Reads the index entries whose leading column values are in the given
+    /// range, one per row ordered by index value and then primary key, including
+    /// the values of the index's included columns. Stops after limit entries,
+    /// if given.
+    fn read_index_entries(
+        &self,
+        table: &str,
+        index: &str,
+        range: IndexRange,
+        limit: Option<u64>,
+    ) -> Result<Vec<IndexEntry>>;
+    /// Scans a table's rows in primary key order, or in reverse order, optionally
+    /// filtering by the given predicate and stopping after limit rows. Primary
+    /// key ranges in the predicate may be used to bound the scan. If columns
+    /// are given, only these (and the predicate's) columns are decoded, and
+    /// the others are returned as NULL.
+    fn scan(
+        &self,
+        table: &str,
+        filter: Option<Expression>,
+        reverse: bool,
+        limit: Option<u64>,
+        columns: Option<Vec<usize>>,
+    ) -> Result<Scan>;
+    /// Scans a table's rows in primary key order like scan(), but decodes and
+    /// filters them using up to the given number of worker threads, each
+    /// processing chunks of rows with contiguous primary keys.
+    fn scan_parallel(
+        &self,
+        table: &str,
+        filter: Option<Expression>,
+        columns: Option<Vec<usize>>,
+        workers: usize,
+    ) -> Result<Scan>;
+    /// Scans an index's entries
+    fn scan_index(&self, table: &str, index: &str) -> Result<IndexScan>;
     /// Updates a table row
     fn update(&mut self, table: &str, id: &Value, row: Row) -> Result<()>;
+    /// Updates a batch of table rows, given as primary key and new row, as if
+    /// by update() for each row, but in a single request to the storage
+    /// engine. Modified index entries are written once per batch, in key order.
+    fn update_batch(&mut self, table: &str, rows: Vec<(Value, Row)>) -> Result<()>;
+
+    /// Returns the next value of a column's auto-increment sequence. Sequences
+    /// are not transactional, so values are not reused after a rollback.
+    fn next_sequence(&mut self, table: &str, column: &str) -> Result<i64>;
 }
 
 /// An SQL session, which handles transaction control and simplified query execution
 pub struct Session<E: Engine + 'static> {
     /// The underlying engine
     engine: E,
+    /// The engine storing the session's temporary tables
+    temp: temporary::Engine,
     /// The current session transaction, if any
-    txn: Option<E::Transaction>,
+    txn: Option<SessionTransaction<E::Transaction>>,
+    /// The current transaction's savepoint names, by savepoint ID
+    savepoints: Vec<String>,
+    /// The session settings
+    settings: Settings,
+    /// The session's plan cache
+    cache: PlanCache,
+    /// The session's current sequence values, see CURRVAL()
+    sequences: CurrentValues,
+    /// The session's authenticated user, if any, whose privileges are checked
+    /// by statements. Sessions without a user have all privileges.
+    user: Option<String>,
 }
 
 impl<E: Engine + 'static> Session<E> {
-    /// Executes a query, managing transaction status for the session
+    /// Executes a query, managing transaction status for the session. The
+    /// query is registered with the engine until it completes, including
+    /// fetching its result rows, such that it can be cancelled via KILL. Its
+    /// executors time out once the statement timeout has passed, if any.
     pub fn execute(&mut self, query: &str) -> Result<ResultSet> {
+        self.execute_input(query, None)
+    }
+
+    /// Sets the session's authenticated user, see Catalog::authorize().
+    pub fn set_user(&mut self, user: &str) {
+        self.user = Some(user.to_string());
+    }
+
+    /// Returns the session's user, if any.
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    /// Returns whether the session has an active transaction.
+    pub fn in_txn(&self) -> bool {
+        self.txn.is_some()
+    }
+
+    /// Executes a query like execute(), reading client input for COPY FROM
+    /// STDIN from the given reader.
+    pub fn execute_with_input(&mut self, query: &str, input: &mut dyn Read) -> Result<ResultSet> {
+        self.execute_input(query, Some(input))
+    }
+
+    /// Executes a query, with client input if any.
+    fn execute_input(&mut self, query: &str, input: Option<&mut dyn Read>) -> Result<ResultSet> {
+        let deadline = self.settings.statement_timeout.map(|t| Instant::now() + t);
+        let registration = self.engine.queries().register(query, self.user.as_deref())?;
+        let cancel = registration.token().with_deadline(deadline);
+        let result = self.execute_registered(query, input, cancel)?;
+        Ok(registration.attach(result))
+    }
+
+    /// Executes a registered query, which errors once the given cancellation
+    /// token is cancelled.
+    fn execute_registered(
+        &mut self,
+        query: &str,
+        input: Option<&mut dyn Read>,
+        cancel: Cancel,
+    ) -> Result<ResultSet> {
+        // Statements found in the plan cache skip parsing.
+        let normalized = match self.settings.plan_cache_size {
+            0 => None,
+            _ => Parser::normalize(query).ok(),
+        };
+        if let Some((key, parameters)) = &normalized {
+            if let Some(read_only) = self.cache.read_only(key) {
+                let settings = self.settings.clone();
+                return self.execute_plan(read_only, cancel, |txn, cache| {
+                    Self::build_cached(txn, cache, query, key, parameters, read_only, &settings)
+                });
+            }
+        }
+
         // FIXME We should match on self.txn as well, but get this error:
         // error[E0009]: cannot bind by-move and by-ref in the same pattern
         // ...which seems like an arbitrary compiler limitation
-        match Parser::new(query).parse()? {
+        let statement = match Parser::new(query).parse()? {
+            ast::Statement::Begin { read_only: None, as_of } => ast::Statement::Begin {
+                read_only: Some(self.settings.default_transaction_read_only),
+                as_of,
+            },
+            statement => statement,
+        };
+        match statement {
             ast::Statement::Begin { .. } if self.txn.is_some() => {
                 Err(Error::Value("Already in a transaction".into()))
             }
-            ast::Statement::Begin { read_only: true, as_of: None } => {
-                let txn = self.engine.begin_read_only()?;
+            ast::Statement::Begin { read_only: Some(true), as_of: None } => {
+                let txn = self.begin_read_only()?;
                 let result = ResultSet::Begin { version: txn.version(), read_only: true };
                 self.txn = Some(txn);
                 Ok(result)
             }
-            ast::Statement::Begin { read_only: true, as_of: Some(version) } => {
-                let txn = self.engine.begin_as_of(version)?;
-                let result = ResultSet::Begin { version, read_only: true };
+            ast::Statement::Begin { read_only: Some(true), as_of: Some(as_of) } => {
+                let txn = self.begin_as_of(as_of)?;
+                let result = ResultSet::Begin { version: txn.version(), read_only: true };
                 self.txn = Some(txn);
                 Ok(result)
             }
-            ast::Statement::Begin { read_only: false, as_of: Some(_) } => {
+            ast::Statement::Begin { read_only: Some(false) | None, as_of: Some(_) } => {
                 Err(Error::Value("Can't start read-write transaction in a given version".into()))
             }
-            ast::Statement::Begin { read_only: false, as_of: None } => {
-                let txn = self.engine.begin()?;
+            ast::Statement::Begin { read_only: Some(false) | None, as_of: None } => {
+                let txn = self.begin()?;
                 let result = ResultSet::Begin { version: txn.version(), read_only: false };
                 self.txn = Some(txn);
                 Ok(result)
@@ -103,45 +292,537 @@ impl<E: Engine + 'static> Session<E> {
             ast::Statement::Commit | ast::Statement::Rollback if self.txn.is_none() => {
                 Err(Error::Value("Not in a transaction".into()))
             }
+            // Cached plans may depend on schema changes made by the
+            // transaction, so the cache is cleared if they're discarded.
             ast::Statement::Commit => {
+                self.savepoints.clear();
                 let txn = self.txn.take().unwrap();
                 let version = txn.version();
-                txn.commit()?;
+                txn.commit().inspect_err(|_| self.cache.clear())?;
                 Ok(ResultSet::Commit { version })
             }
             ast::Statement::Rollback => {
+                self.savepoints.clear();
+                self.cache.clear();
                 let txn = self.txn.take().unwrap();
                 let version = txn.version();
                 txn.rollback()?;
                 Ok(ResultSet::Rollback { version })
             }
-            ast::Statement::Explain(statement) => self.with_txn_read_only(|txn| {
-                Ok(ResultSet::Explain(Plan::build(*statement, txn)?.optimize(txn)?.0))
-            }),
-            statement if self.txn.is_some() => Plan::build(statement, self.txn.as_mut().unwrap())?
-                .optimize(self.txn.as_mut().unwrap())?
-                .execute(self.txn.as_mut().unwrap()),
-            statement @ ast::Statement::Select { .. } => {
-                let mut txn = self.engine.begin_read_only()?;
-                let result =
-                    Plan::build(statement, &mut txn)?.optimize(&mut txn)?.execute(&mut txn);
-                txn.rollback()?;
+            ast::Statement::Savepoint { name } => {
+                let Some(ref mut txn) = self.txn else {
+                    return Err(Error::Value("Not in a transaction".into()));
+                };
+                txn.savepoint(self.savepoints.len() as u64)?;
+                self.savepoints.push(name.clone());
+                Ok(ResultSet::Savepoint { name })
+            }
+            // Rolling back to a savepoint keeps it, but discards later ones.
+            ast::Statement::RollbackToSavepoint { name } => {
+                let id = self.lookup_savepoint(&name)?;
+                self.cache.clear();
+                self.txn.as_mut().unwrap().rollback_to_savepoint(id as u64)?;
+                self.savepoints.truncate(id + 1);
+                Ok(ResultSet::RollbackToSavepoint { name })
+            }
+            ast::Statement::ReleaseSavepoint { name } => {
+                let id = self.lookup_savepoint(&name)?;
+                self.txn.as_mut().unwrap().release_savepoint(id as u64)?;
+                self.savepoints.truncate(id);
+                Ok(ResultSet::ReleaseSavepoint { name })
+            }
+            ast::Statement::Set { name, value } => {
+                let value = match value {
+                    Some(ast::Expression::Literal(literal)) => Some(match literal {
+                        ast::Literal::Null => Value::Null,
+                        ast::Literal::Boolean(b) => Value::Boolean(b),
+                        ast::Literal::Integer(i) => Value::Integer(i),
+                        ast::Literal::Float(f) => Value::Float(f),
+                        ast::Literal::String(s) => Value::String(s),
+                        ast::Literal::Bytes(b) => Value::Bytes(b),
+                    }),
+                    // Fold other constant expressions, e.g. -5 or 2 * 1000.
+                    Some(expr) => Some(self.with_txn_read_only(|txn| Plan::evaluate(expr, txn))?),
+                    None => None,
+                };
+                // Settings may affect planning, e.g. strict_types.
+                self.settings.set(&name, value)?;
+                self.cache.clear();
+                Ok(ResultSet::Set { name })
+            }
+            ast::Statement::ShowSetting { name } => {
+                let value = self.settings.get(&name)?;
+                Ok(ResultSet::Query {
+                    columns: vec![types::Column { name: Some(name) }],
+                    rows: Box::new(std::iter::once(Ok(vec![value]))),
+                })
+            }
+            ast::Statement::ShowAll => {
+                let rows: Vec<Row> = self
+                    .settings
+                    .all()?
+                    .into_iter()
+                    .map(|(name, value)| vec![Value::String(name.to_string()), value])
+                    .collect();
+                Ok(ResultSet::Query {
+                    columns: vec![
+                        types::Column { name: Some("name".into()) },
+                        types::Column { name: Some("setting".into()) },
+                    ],
+                    rows: Box::new(rows.into_iter().map(Ok)),
+                })
+            }
+            ast::Statement::ShowQueries => {
+                let rows: Vec<Row> = self
+                    .engine
+                    .queries()
+                    .list(self.user.as_deref())?
+                    .into_iter()
+                    .map(|(id, query)| vec![Value::Integer(id as i64), Value::String(query)])
+                    .collect();
+                Ok(ResultSet::Query {
+                    columns: vec![
+                        types::Column { name: Some("id".into()) },
+                        types::Column { name: Some("query".into()) },
+                    ],
+                    rows: Box::new(rows.into_iter().map(Ok)),
+                })
+            }
+            ast::Statement::Kill { id } => {
+                self.engine.queries().cancel(id, self.user.as_deref())?;
+                Ok(ResultSet::Kill { id })
+            }
+            ast::Statement::ShowDatabases => {
+                let mut databases = self.with_txn_read_only(|txn| txn.scan_databases())?;
+                databases.push(DEFAULT_DATABASE.to_string());
+                databases.sort();
+                Ok(ResultSet::Query {
+                    columns: vec![types::Column { name: Some("database".into()) }],
+                    rows: Box::new(databases.into_iter().map(|d| Ok(vec![Value::String(d)]))),
+                })
+            }
+            // Plans resolve names in the current database, so the cache is cleared.
+            ast::Statement::Use { database } => {
+                if !self.with_txn_read_only(|txn| txn.has_database(&database))? {
+                    return Err(Error::Value(format!("Database {} does not exist", database)));
+                }
+                self.cache.clear();
+                self.settings.database = database.clone();
+                Ok(ResultSet::Use { database })
+            }
+            ast::Statement::CopyFrom { table, columns, source, options } => {
+                let input: Box<dyn Read + '_> = match (source, input) {
+                    (ast::CopySource::File(path), _) => {
+                        self.authorize_file()?;
+                        Box::new(std::fs::File::open(&path).map_err(|err| {
+                            Error::Value(format!("Can't open file {}: {}", path, err))
+                        })?)
+                    }
+                    (ast::CopySource::Stdin, Some(input)) => Box::new(input),
+                    (ast::CopySource::Stdin, None) => {
+                        return Err(Error::Value("COPY FROM STDIN requires client input".into()))
+                    }
+                };
+                let database = self.settings.database.clone();
+                self.with_txn(false, |txn| {
+                    let table = txn.resolve_table(&table, &database)?;
+                    if let Some(user) = txn.user() {
+                        txn.authorize(user, &table, Privilege::Insert)?;
+                    }
+                    execution::copy_from(txn, &table, columns, input, &options, &cancel)
+                })
+            }
+            // The rows are streamed to the file as they're emitted by the executors.
+            ast::Statement::CopyTo { query, path, options } => {
+                self.authorize_file()?;
+                let (database, strict_types) =
+                    (self.settings.database.clone(), self.settings.strict_types);
+                let result = self.execute_plan(true, cancel, |txn, _| {
+                    Plan::build((*query).clone(), txn, &database, strict_types)
+                })?;
+                let file = std::fs::File::create(&path)
+                    .map_err(|err| Error::Value(format!("Can't create file {}: {}", path, err)))?;
+                execution::copy_to(result, file, &options)
+            }
+            // Queries as of a past time run in their own read-only transaction.
+            ast::Statement::AsOf { .. } if self.txn.is_some() => {
+                Err(Error::Value("Can't use AS OF SYSTEM TIME in a transaction".into()))
+            }
+            ast::Statement::AsOf { query, as_of } => {
+                let (database, strict_types) =
+                    (self.settings.database.clone(), self.settings.strict_types);
+                self.txn = Some(self.begin_as_of(as_of)?);
+                let result = self.execute_plan(true, cancel, |txn, _| {
+                    Plan::build((*query).clone(), txn, &database, strict_types)
+                });
+                self.txn.take().unwrap().rollback()?;
                 result
             }
+            // EXPLAIN ANALYZE executes the statement, including any writes.
+            ast::Statement::Explain { statement, analyze, verbose, format } => {
+                let read_only = !analyze || statement.is_read_only();
+                let settings = self.settings.clone();
+                self.with_txn(read_only, |txn| {
+                    let plan =
+                        Plan::build(*statement, txn, &settings.database, settings.strict_types)?;
+                    Self::authorize(&plan, txn)?;
+                    let plan = plan.optimize(txn)?;
+                    let plan = Self::configure(plan, txn, &settings)?;
+                    let estimates = match verbose || format == ast::ExplainFormat::Json {
+                        true => plan.estimate(txn)?,
+                        false => Vec::new(),
+                    };
+                    let (plan, stats) = match analyze {
+                        true => plan.analyze(txn, cancel, settings.accountant())?,
+                        false => (plan.0, Vec::new()),
+                    };
+                    Ok(match format {
+                        ast::ExplainFormat::Json => ResultSet::Query {
+                            columns: vec![types::Column { name: Some("plan".into()) }],
+                            rows: Box::new(std::iter::once(Ok(vec![Value::Json(
+                                plan.to_json(&estimates, &stats),
+                            )]))),
+                        },
+                        ast::ExplainFormat::Text if verbose => {
+                            ResultSet::ExplainVerbose { plan, estimates, stats }
+                        }
+                        ast::ExplainFormat::Text if analyze => {
+                            ResultSet::ExplainAnalyze { plan, stats }
+                        }
+                        ast::ExplainFormat::Text => ResultSet::Explain(plan),
+                    })
+                })
+            }
+            // Outside of explicit transactions, indexes are built online.
+            statement @ ast::Statement::CreateIndex { .. } if self.txn.is_none() => {
+                self.cache.clear();
+                let (database, strict_types) =
+                    (self.settings.database.clone(), self.settings.strict_types);
+                let plan = self.with_txn_read_only(|txn| {
+                    let plan = Plan::build(statement, txn, &database, strict_types)?;
+                    Self::authorize(&plan, txn)?;
+                    Ok(plan)
+                })?;
+                let Plan(Node::CreateIndex { table, mut index }, _) = plan else {
+                    return Err(Error::Internal("Expected CreateIndex plan".into()));
+                };
+                index.building = true;
+                self.create_index_online(&table, index, &cancel)
+            }
+            // Queries and DML statements are cached. Temporary tables are
+            // local to the session, so changes to them aren't reflected in
+            // the schema version, and the cache is cleared on any schema
+            // change instead.
             statement => {
-                let mut txn = self.engine.begin()?;
-                match Plan::build(statement, &mut txn)?.optimize(&mut txn)?.execute(&mut txn) {
-                    Ok(result) => {
-                        txn.commit()?;
-                        Ok(result)
+                let read_only = statement.is_read_only();
+                let settings = self.settings.clone();
+                let cacheable = matches!(
+                    statement,
+                    ast::Statement::Select { .. }
+                        | ast::Statement::SetOperation { .. }
+                        | ast::Statement::Insert { .. }
+                        | ast::Statement::Update { .. }
+                        | ast::Statement::Delete { .. }
+                );
+                if !cacheable && !read_only {
+                    self.cache.clear();
+                }
+                self.execute_plan(read_only, cancel, |txn, cache| match &normalized {
+                    Some((key, parameters)) if cacheable => {
+                        Self::build_cached(txn, cache, query, key, parameters, read_only, &settings)
                     }
-                    Err(error) => {
-                        txn.rollback()?;
-                        Err(error)
+                    _ => Plan::build(
+                        statement.clone(),
+                        txn,
+                        &settings.database,
+                        settings.strict_types,
+                    ),
+                })
+            }
+        }
+    }
+
+    /// Builds, optimizes and executes a plan, in the session's transaction or
+    /// a new one. Outside of an explicit transaction, statements that fail
+    /// with a serialization error are retried as configured. In an explicit
+    /// transaction, the writes of a statement that times out are rolled back
+    /// via an internal savepoint, as they would be outside of one.
+    fn execute_plan<F>(
+        &mut self,
+        read_only: bool,
+        cancel: Cancel,
+        mut build: F,
+    ) -> Result<ResultSet>
+    where
+        F: FnMut(&mut SessionTransaction<E::Transaction>, &mut PlanCache) -> Result<Plan>,
+    {
+        let mut retries = self.settings.serialization_retries;
+        let settings = self.settings.clone();
+        let savepoint = match self.txn.as_mut() {
+            Some(txn) if settings.statement_timeout.is_some() && !read_only => {
+                let id = self.savepoints.len() as u64;
+                txn.savepoint(id)?;
+                Some(id)
+            }
+            _ => None,
+        };
+        let mut cache = std::mem::take(&mut self.cache);
+        let result = loop {
+            match self.with_txn(read_only, |txn| {
+                let plan = build(txn, &mut cache)?;
+                Self::authorize(&plan, txn)?;
+                let plan = plan.optimize(txn)?;
+                let plan = Self::configure(plan, txn, &settings)?;
+                plan.execute_with(txn, cancel.clone(), settings.accountant())
+            }) {
+                Err(Error::Serialization) if self.txn.is_none() && retries > 0 => retries -= 1,
+                result => break result,
+            }
+        };
+        self.cache = cache;
+        if let (Some(id), Some(txn)) = (savepoint, self.txn.as_mut()) {
+            if let Err(Error::Timeout) = result {
+                txn.rollback_to_savepoint(id)?;
+            }
+            txn.release_savepoint(id)?;
+        }
+        result
+    }
+
+    /// Creates an index without blocking concurrent writes to the table for
+    /// the duration of the build. The index is first added in a building
+    /// state, where writes maintain it but queries don't use it. Once all
+    /// transactions that began before it was added, and may write rows without
+    /// indexing them, have completed, the existing rows are indexed in batches
+    /// of separate transactions. Concurrent writes to the same index entries
+    /// conflict with a batch, which is then retried. The final batch marks the
+    /// index as built. If the build fails, e.g. on a unique violation or
+    /// timeout, the index is dropped again.
+    fn create_index_online(
+        &mut self,
+        table: &str,
+        index: Index,
+        cancel: &Cancel,
+    ) -> Result<ResultSet> {
+        let name = index.name.clone();
+        self.with_txn(false, |txn| txn.create_index(table, index))?;
+        if let Err(error) = self.build_index(&name, cancel) {
+            // The original error is more useful than any error dropping it.
+            self.with_txn(false, |txn| txn.drop_index(&name)).ok();
+            return Err(error);
+        }
+        Ok(ResultSet::CreateIndex { name })
+    }
+
+    /// Builds a building index's entries, see create_index_online().
+    fn build_index(&mut self, index: &str, cancel: &Cancel) -> Result<()> {
+        let concurrent = self.with_txn_read_only(|txn| Ok(txn.concurrent().clone()))?;
+        while self.with_txn_read_only(|txn| Ok(!txn.concurrent().is_disjoint(&concurrent)))? {
+            cancel.check()?;
+            std::thread::sleep(INDEX_BUILD_POLL_INTERVAL);
+        }
+        let mut after = None;
+        loop {
+            cancel.check()?;
+            match self.with_txn(false, |txn| {
+                txn.build_index(index, after.clone(), INDEX_BUILD_BATCH_SIZE)
+            }) {
+                Ok(Some(last)) => after = Some(last),
+                Ok(None) => return Ok(()),
+                Err(Error::Serialization) => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Builds a plan via the plan cache, given the normalized statement key
+    /// and parameter values. On a cache miss, the statement is parsed with
+    /// parameters and planned for the current schema version. If that fails,
+    /// e.g. where the grammar requires a literal, it is planned normally and
+    /// cached as uncacheable.
+    fn build_cached(
+        txn: &mut SessionTransaction<E::Transaction>,
+        cache: &mut PlanCache,
+        query: &str,
+        key: &str,
+        parameters: &[Value],
+        read_only: bool,
+        settings: &Settings,
+    ) -> Result<Plan> {
+        let (database, strict_types) = (settings.database.as_str(), settings.strict_types);
+        let size = settings.plan_cache_size;
+        let schema_version = txn.schema_version()?;
+        match cache.get(key, schema_version, parameters) {
+            Some(Some(plan)) => return plan.bind(parameters),
+            Some(None) => {
+                return Plan::build(Parser::new(query).parse()?, txn, database, strict_types)
+            }
+            None => {}
+        }
+        let key = key.to_string();
+        match Parser::parameterized(query).parse().and_then(|statement| {
+            Plan::build_parameterized(statement, parameters, txn, database, strict_types)
+        }) {
+            Ok((plan, pinned)) => {
+                let bound = plan.clone().bind(parameters)?;
+                cache.insert(
+                    key,
+                    Some((plan, pinned)),
+                    schema_version,
+                    read_only,
+                    parameters,
+                    size,
+                );
+                Ok(bound)
+            }
+            Err(_) => {
+                let plan = Plan::build(Parser::new(query).parse()?, txn, database, strict_types)?;
+                cache.insert(key, None, schema_version, read_only, parameters, size);
+                Ok(plan)
+            }
+        }
+    }
+
+    /// Checks that the session's user, if any, has the privileges required to
+    /// execute a plan. This is checked before optimization, and for cached
+    /// plans too, since the user's privileges may have changed.
+    fn authorize(plan: &Plan, txn: &SessionTransaction<E::Transaction>) -> Result<()> {
+        match txn.user() {
+            Some(user) => plan.authorize(txn, user),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that the session's user, if any, can read or write server-side
+    /// files via COPY. Since files are accessed with the server's own
+    /// permissions, this requires the root user, while other users can only
+    /// copy via STDIN.
+    fn authorize_file(&self) -> Result<()> {
+        match self.user.as_deref() {
+            None | Some(User::ROOT) => Ok(()),
+            Some(_) => {
+                Err(Error::PermissionDenied("COPY with a file requires the root user".into()))
+            }
+        }
+    }
+
+    /// Applies the session settings to an optimized plan. Only read-only
+    /// transactions scan in parallel, since their snapshot is unaffected by
+    /// their own writes.
+    fn configure(
+        plan: Plan,
+        txn: &SessionTransaction<E::Transaction>,
+        settings: &Settings,
+    ) -> Result<Plan> {
+        let workers = match txn.read_only() {
+            true => settings.parallel_workers,
+            false => 1,
+        };
+        plan.parallelize(workers)?.set_work_memory(settings.work_memory * 1024)
+    }
+
+    /// Runs a closure in the session's transaction, or a new transaction if
+    /// none is active. A new read-write transaction is committed if the
+    /// closure succeeds and rolled back otherwise, while a new read-only
+    /// transaction is always rolled back.
+    fn with_txn<F, R>(&mut self, read_only: bool, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut SessionTransaction<E::Transaction>) -> Result<R>,
+    {
+        if let Some(ref mut txn) = self.txn {
+            return f(txn);
+        }
+        if read_only || self.settings.default_transaction_read_only {
+            return self.with_txn_read_only(f);
+        }
+        let mut txn = self.begin()?;
+        match f(&mut txn) {
+            Ok(result) => {
+                txn.commit()?;
+                Ok(result)
+            }
+            Err(error) => {
+                txn.rollback()?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Looks up the ID of the most recent savepoint with the given name in the
+    /// session's transaction.
+    fn lookup_savepoint(&self, name: &str) -> Result<usize> {
+        if self.txn.is_none() {
+            return Err(Error::Value("Not in a transaction".into()));
+        }
+        self.savepoints
+            .iter()
+            .rposition(|s| s == name)
+            .ok_or_else(|| Error::Value(format!("Savepoint {} does not exist", name)))
+    }
+
+    /// Begins a read-write transaction, including temporary tables.
+    fn begin(&self) -> Result<SessionTransaction<E::Transaction>> {
+        SessionTransaction::begin(
+            self.engine.begin()?,
+            &self.temp,
+            self.engine.catalog_cache(),
+            &self.sequences,
+            self.user.as_deref(),
+        )
+    }
+
+    /// Begins a read-only transaction, including temporary tables.
+    fn begin_read_only(&self) -> Result<SessionTransaction<E::Transaction>> {
+        let txn = self.engine.begin_read_only()?;
+        let (catalog, sequences) = (self.engine.catalog_cache(), &self.sequences);
+        SessionTransaction::begin(txn, &self.temp, catalog, sequences, self.user.as_deref())
+    }
+
+    /// Begins a read-only transaction as of a historical version or time given
+    /// by AS OF SYSTEM TIME, including (current) temporary tables.
+    fn begin_as_of(
+        &mut self,
+        as_of: ast::Expression,
+    ) -> Result<SessionTransaction<E::Transaction>> {
+        let txn = match self.resolve_as_of(as_of)? {
+            Some(version) => self.engine.begin_as_of(version)?,
+            None => self.engine.begin_read_only()?,
+        };
+        let (catalog, sequences) = (self.engine.catalog_cache(), &self.sequences);
+        SessionTransaction::begin(txn, &self.temp, catalog, sequences, self.user.as_deref())
+    }
+
+    /// Resolves an AS OF SYSTEM TIME value to the version to read as of, or
+    /// None for the latest version. Integers are versions, timestamps are
+    /// wall-clock times, and intervals are offsets from the current time, e.g.
+    /// '-5m'. Strings are parsed as a timestamp or an interval.
+    fn resolve_as_of(&mut self, as_of: ast::Expression) -> Result<Option<u64>> {
+        let value = self.with_txn_read_only(|txn| Plan::evaluate(as_of, txn))?;
+        let now = datetime::now();
+        let time = match value {
+            Value::Integer(version) if version >= 0 => return Ok(Some(version as u64)),
+            Value::Timestamp(time) => time,
+            Value::Date(date) => date.into(),
+            Value::Interval(interval) => datetime::add_interval(now, interval)
+                .ok_or_else(|| Error::Value("Timestamp out of range".into()))?,
+            Value::String(ref s) => match value.clone().cast(&DataType::Timestamp) {
+                Ok(Value::Timestamp(time)) => time,
+                _ => {
+                    match datetime::Interval::parse(s).and_then(|i| datetime::add_interval(now, i))
+                    {
+                        Some(time) => time,
+                        None => {
+                            return Err(Error::Value(format!("Invalid AS OF SYSTEM TIME {}", s)))
+                        }
                     }
                 }
-            }
+            },
+            value => return Err(Error::Value(format!("Invalid AS OF SYSTEM TIME {}", value))),
+        };
+        if time > now {
+            return Err(Error::Value(format!("AS OF SYSTEM TIME {} is in the future", time)));
         }
+        self.engine.version_at(time)
     }
 
     /// Runs a read-only closure in the session's transaction, or a new
@@ -150,12 +831,12 @@ impl<E: Engine + 'static> Session<E> {
     /// TODO: reconsider this.
     pub fn with_txn_read_only<F, R>(&mut self, f: F) -> Result<R>
     where
-        F: FnOnce(&mut E::Transaction) -> Result<R>,
+        F: FnOnce(&mut SessionTransaction<E::Transaction>) -> Result<R>,
     {
         if let Some(ref mut txn) = self.txn {
             return f(txn);
         }
-        let mut txn = self.engine.begin_read_only()?;
+        let mut txn = self.begin_read_only()?;
         let result = f(&mut txn);
         txn.rollback()?;
         result
@@ -177,5 +858,13 @@ impl<E: Engine + 'static> Drop for Session<E> {
 /// A row scan iterator
 pub type Scan = Box<dyn DoubleEndedIterator<Item = Result<Row>> + Send>;
 
+/// An index entry for a single row, as the index key values, the row's primary
+/// key, and the values of the index's included columns.
+pub type IndexEntry = (Vec<Value>, Value, Vec<Value>);
+
+/// An index key range, as bounds on the values of the leading index columns.
+pub type IndexRange = (Bound<Vec<Value>>, Bound<Vec<Value>>);
+
 /// An index scan iterator
-pub type IndexScan = Box<dyn DoubleEndedIterator<Item = Result<(Value, HashSet<Value>)>> + Send>;
+pub type IndexScan =
+    Box<dyn DoubleEndedIterator<Item = Result<(Vec<Value>, HashSet<Value>)>> + Send>;
@@ -0,0 +1,74 @@
+use crate::error::{Error, Result};
+
+use crossbeam::channel::{Receiver, Sender};
+use std::panic::AssertUnwindSafe;
+
+/// A job run by a worker thread.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed-size pool of worker threads, shared by all of an engine's parallel
+/// scans such that concurrent queries can't spawn unbounded threads. Jobs are
+/// queued and run by the next idle worker. The workers exit once the pool and
+/// all of its clones are dropped.
+#[derive(Clone)]
+pub struct WorkerPool {
+    jobs: Option<Sender<Job>>,
+    size: usize,
+}
+
+impl WorkerPool {
+    /// Creates a pool with the given number of workers. A size of 1 spawns no
+    /// threads, and callers should run their work inline.
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1);
+        if size == 1 {
+            return Self { jobs: None, size };
+        }
+        let (jobs, rx) = crossbeam::channel::unbounded::<Job>();
+        for i in 0..size {
+            let rx = rx.clone();
+            std::thread::Builder::new()
+                .name(format!("scan-worker-{i}"))
+                .spawn(move || {
+                    // A panicking job drops its result sender, which errors
+                    // the caller, but doesn't take down the worker.
+                    while let Ok(job) = rx.recv() {
+                        let _ = std::panic::catch_unwind(AssertUnwindSafe(job));
+                    }
+                })
+                .expect("failed to spawn scan worker");
+        }
+        Self { jobs: Some(jobs), size }
+    }
+
+    /// Returns the number of workers.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Queues a job, returning a handle to wait for its result.
+    pub fn spawn<T: Send + 'static>(
+        &self,
+        job: impl FnOnce() -> Result<T> + Send + 'static,
+    ) -> Result<Handle<T>> {
+        let Some(jobs) = &self.jobs else {
+            return Err(Error::Internal("Worker pool has no workers".into()));
+        };
+        let (tx, rx) = crossbeam::channel::bounded(1);
+        jobs.send(Box::new(move || {
+            let _ = tx.send(job());
+        }))
+        .map_err(|_| Error::Internal("Worker pool is closed".into()))?;
+        Ok(Handle(rx))
+    }
+}
+
+/// A handle to a queued job's result.
+pub struct Handle<T>(Receiver<Result<T>>);
+
+impl<T> Handle<T> {
+    /// Waits for the job's result.
+    pub fn join(self) -> Result<T> {
+        self.0.recv().map_err(|_| Error::Internal("Scan worker panicked".into()))?
+    }
+}
@@ -0,0 +1,137 @@
+//! A registry of the queries running on an engine across all of its sessions,
+//! such that they can be listed via SHOW QUERIES and cancelled via KILL.
+use super::super::execution::{Cancel, ResultSet};
+use super::super::parser;
+use super::super::schema::User;
+use super::super::types::{Row, Rows};
+use crate::error::{Error, Result};
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// A registry of running queries. Clones share the registry.
+#[derive(Clone, Default)]
+pub struct Queries(Arc<Mutex<Registry>>);
+
+#[derive(Default)]
+struct Registry {
+    /// The last assigned query ID.
+    last_id: u64,
+    /// Running queries by ID.
+    queries: BTreeMap<u64, Query>,
+}
+
+/// A running query.
+struct Query {
+    /// The statement text, with passwords redacted.
+    text: String,
+    /// The user running the query, if any.
+    user: Option<String>,
+    /// The query's cancellation token.
+    cancel: Cancel,
+}
+
+impl Query {
+    /// Returns whether the given user can see and cancel the query. Users can
+    /// only access their own queries, while root and sessions without a user
+    /// can access all queries.
+    fn accessible(&self, user: Option<&str>) -> bool {
+        match user {
+            None | Some(User::ROOT) => true,
+            Some(user) => self.user.as_deref() == Some(user),
+        }
+    }
+}
+
+impl Queries {
+    /// Registers a running query for the given user, if any, returning its
+    /// registration. The query is deregistered when the registration is
+    /// dropped.
+    pub fn register(&self, query: &str, user: Option<&str>) -> Result<Registration> {
+        let mut registry = self.0.lock()?;
+        registry.last_id += 1;
+        let id = registry.last_id;
+        let cancel = Cancel::default();
+        let text = parser::redact(query);
+        let user = user.map(str::to_string);
+        registry.queries.insert(id, Query { text, user, cancel: cancel.clone() });
+        Ok(Registration { queries: self.clone(), id, cancel })
+    }
+
+    /// Lists the running queries accessible to the given user by ID and
+    /// statement text, in ID order.
+    pub fn list(&self, user: Option<&str>) -> Result<Vec<(u64, String)>> {
+        Ok(self
+            .0
+            .lock()?
+            .queries
+            .iter()
+            .filter(|(_, query)| query.accessible(user))
+            .map(|(id, query)| (*id, query.text.clone()))
+            .collect())
+    }
+
+    /// Cancels a running query on behalf of the given user, if any. It errors
+    /// at the next operator boundary.
+    pub fn cancel(&self, id: u64, user: Option<&str>) -> Result<()> {
+        match self.0.lock()?.queries.get(&id) {
+            Some(query) if query.accessible(user) => {
+                query.cancel.cancel();
+                Ok(())
+            }
+            Some(_) => Err(Error::PermissionDenied(format!(
+                "user {} does not own query {}",
+                user.unwrap_or_default(),
+                id
+            ))),
+            None => Err(Error::Value(format!("Query {} does not exist", id))),
+        }
+    }
+}
+
+/// A running query's registration, which deregisters it when dropped.
+pub struct Registration {
+    queries: Queries,
+    id: u64,
+    cancel: Cancel,
+}
+
+impl Registration {
+    /// Returns the query's cancellation token.
+    pub fn token(&self) -> Cancel {
+        self.cancel.clone()
+    }
+
+    /// Keeps the query registered until its result rows are dropped, if any,
+    /// since they're executed lazily as they're fetched.
+    pub fn attach(self, result: ResultSet) -> ResultSet {
+        match result {
+            ResultSet::Query { columns, rows } => {
+                ResultSet::Query { columns, rows: Box::new(RegisteredRows { rows, _query: self }) }
+            }
+            result => result,
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Ok(mut registry) = self.queries.0.lock() {
+            registry.queries.remove(&self.id);
+        }
+    }
+}
+
+/// Result rows which keep their query registered until dropped.
+struct RegisteredRows {
+    rows: Rows,
+    _query: Registration,
+}
+
+impl Iterator for RegisteredRows {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows.next()
+    }
+}
@@ -1,38 +1,123 @@
-use super::super::schema::{Catalog, Table, Tables};
-use super::super::types::{Expression, Row, Value};
-use super::{Engine as _, IndexScan, Scan, Transaction as _};
+use super::super::schema::{
+    Catalog, Column, ColumnChange, EnumType, Index, Privilege, Sequence, Statistics, Table, Tables,
+    Trigger, User, View, Views,
+};
+use super::super::types::{datetime, Expression, Row, Sequences, Value};
+use super::{
+    CatalogCache, Engine as _, IndexEntry, IndexRange, IndexScan, Queries, Scan, Transaction as _,
+};
 use crate::encoding::bincode;
 use crate::error::{Error, Result};
 use crate::raft::{self, Entry};
 use crate::storage::{self, mvcc::TransactionState};
 
+use chrono::NaiveDateTime;
 use crossbeam::channel::Sender;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::{Bound, Range};
+use std::sync::{Arc, Mutex};
+
+/// The number of auto-increment or sequence values to allocate per Raft
+/// mutation. Unused values are lost when the node restarts.
+const SEQUENCE_BATCH_SIZE: i64 = 100;
 
 /// A Raft state machine mutation.
 ///
 /// TODO: use Cows for these.
 #[derive(Clone, Serialize, Deserialize)]
 enum Mutation {
-    /// Begins a read-write transaction
-    Begin,
+    /// Begins a read-write transaction, at the given begin time in
+    /// microseconds since the Unix epoch
+    Begin { time: i64 },
     /// Commits the given transaction
     Commit(TransactionState),
     /// Rolls back the given transaction
     Rollback(TransactionState),
+    /// Creates a savepoint in the given transaction
+    Savepoint { txn: TransactionState, id: u64 },
+    /// Rolls back the given transaction to a savepoint
+    RollbackToSavepoint { txn: TransactionState, id: u64 },
+    /// Releases a savepoint in the given transaction
+    ReleaseSavepoint { txn: TransactionState, id: u64 },
 
     /// Creates a new row
     Create { txn: TransactionState, table: String, row: Row },
+    /// Creates a batch of new rows
+    CreateBatch { txn: TransactionState, table: String, rows: Vec<Row> },
     /// Deletes a row
     Delete { txn: TransactionState, table: String, id: Value },
     /// Updates a row
     Update { txn: TransactionState, table: String, id: Value, row: Row },
+    /// Updates a batch of rows
+    UpdateBatch { txn: TransactionState, table: String, rows: Vec<(Value, Row)> },
 
     /// Creates a table
     CreateTable { txn: TransactionState, schema: Table },
     /// Deletes a table
     DeleteTable { txn: TransactionState, table: String },
+    /// Adds a column to a table
+    AddColumn { txn: TransactionState, table: String, column: Column },
+    /// Drops a column from a table
+    DropColumn { txn: TransactionState, table: String, column: String },
+    /// Changes a column's datatype or nullability
+    AlterColumn { txn: TransactionState, table: String, column: String, change: ColumnChange },
+    /// Creates an index
+    CreateIndex { txn: TransactionState, table: String, index: Index },
+    /// Builds a batch of a building index's entries
+    BuildIndex { txn: TransactionState, index: String, after: Option<Value>, limit: usize },
+    /// Drops an index
+    DropIndex { txn: TransactionState, index: String },
+    /// Creates a trigger
+    CreateTrigger { txn: TransactionState, table: String, trigger: Trigger },
+    /// Drops a trigger
+    DropTrigger { txn: TransactionState, table: String, trigger: String },
+    /// Sets or removes a table or column comment
+    SetComment {
+        txn: TransactionState,
+        table: String,
+        column: Option<String>,
+        comment: Option<String>,
+    },
+    /// Creates a view
+    CreateView { txn: TransactionState, view: View },
+    /// Deletes a view
+    DeleteView { txn: TransactionState, view: String },
+    /// Creates a sequence
+    CreateSequence { txn: TransactionState, sequence: Sequence },
+    /// Deletes a sequence
+    DeleteSequence { txn: TransactionState, sequence: String },
+    /// Creates an enum type
+    CreateType { txn: TransactionState, enum_type: EnumType },
+    /// Deletes an enum type
+    DeleteType { txn: TransactionState, name: String },
+    /// Writes a table's statistics
+    WriteStatistics { txn: TransactionState, table: String, statistics: Statistics },
+    /// Creates a database
+    CreateDatabase { txn: TransactionState, database: String },
+    /// Creates a user
+    CreateUser { txn: TransactionState, user: User },
+    /// Replaces a user
+    AlterUser { txn: TransactionState, user: User },
+    /// Deletes a user
+    DeleteUser { txn: TransactionState, name: String },
+    /// Replaces the privileges granted on a table
+    SetPrivileges {
+        txn: TransactionState,
+        table: String,
+        grantee: String,
+        privileges: BTreeSet<Privilege>,
+    },
+
+    /// Allocates a range of auto-increment sequence values, returning the
+    /// first. This is not transactional.
+    AllocateSequence { table: String, column: String, count: i64 },
+    /// Allocates a range of values from a sequence, returning the first and
+    /// the increment. The sequence is read in the given transaction, but the
+    /// allocation is not transactional.
+    AllocateSequenceValues { txn: TransactionState, sequence: String, count: i64 },
+    /// Sets the current value of a sequence. This is not transactional.
+    SetSequenceValue { txn: TransactionState, sequence: String, value: i64 },
 }
 
 /// A Raft state machine query.
@@ -42,22 +127,76 @@ enum Mutation {
 enum Query {
     /// Begins a read-only transaction
     BeginReadOnly { as_of: Option<u64> },
+    /// Looks up the first version that began after the given time
+    VersionAt { time: NaiveDateTime },
     /// Fetches engine status
     Status,
 
     /// Reads a row
     Read { txn: TransactionState, table: String, id: Value },
     /// Reads an index entry
-    ReadIndex { txn: TransactionState, table: String, column: String, value: Value },
+    ReadIndex { txn: TransactionState, table: String, index: String, values: Vec<Value> },
+    /// Reads the primary keys of an index value range
+    ReadIndexRange {
+        txn: TransactionState,
+        table: String,
+        index: String,
+        range: (Bound<Value>, Bound<Value>),
+    },
+    /// Reads the index entries of an index key range
+    ReadIndexEntries {
+        txn: TransactionState,
+        table: String,
+        index: String,
+        range: IndexRange,
+        limit: Option<u64>,
+    },
     /// Scans a table's rows
-    Scan { txn: TransactionState, table: String, filter: Option<Expression> },
+    Scan {
+        txn: TransactionState,
+        table: String,
+        filter: Option<Expression>,
+        reverse: bool,
+        limit: Option<u64>,
+        columns: Option<Vec<usize>>,
+    },
+    /// Scans a table's rows, decoding them in parallel
+    ScanParallel {
+        txn: TransactionState,
+        table: String,
+        filter: Option<Expression>,
+        columns: Option<Vec<usize>>,
+        workers: usize,
+    },
     /// Scans an index
-    ScanIndex { txn: TransactionState, table: String, column: String },
+    ScanIndex { txn: TransactionState, table: String, index: String },
 
     /// Scans the tables
     ScanTables { txn: TransactionState },
     /// Reads a table
     ReadTable { txn: TransactionState, table: String },
+    /// Scans the views
+    ScanViews { txn: TransactionState },
+    /// Reads a view
+    ReadView { txn: TransactionState, view: String },
+    /// Reads a sequence
+    ReadSequence { txn: TransactionState, sequence: String },
+    /// Scans the sequences
+    ScanSequences { txn: TransactionState },
+    /// Reads an enum type
+    ReadType { txn: TransactionState, name: String },
+    /// Scans the enum types
+    ScanTypes { txn: TransactionState },
+    /// Reads a table's statistics
+    ReadStatistics { txn: TransactionState, table: String },
+    /// Reads the schema version
+    ReadSchemaVersion { txn: TransactionState },
+    /// Scans databases
+    ScanDatabases { txn: TransactionState },
+    /// Reads a user
+    ReadUser { txn: TransactionState, name: String },
+    /// Scans users
+    ScanUsers { txn: TransactionState },
 }
 
 /// Status for the Raft SQL engine.
@@ -113,21 +252,70 @@ impl Client {
     }
 }
 
+/// Auto-increment sequence values allocated to this node, by table and column
+/// name.
+type ColumnValues = HashMap<(String, String), Range<i64>>;
+
+/// Sequence values allocated to this node but not yet used. Clones share the
+/// values.
+#[derive(Clone, Default)]
+struct SequenceCache {
+    /// Auto-increment sequence values.
+    columns: Arc<Mutex<ColumnValues>>,
+    /// Sequence values, by sequence name. Values are dropped when this node
+    /// sets, creates or drops the sequence, but other nodes may still hand out
+    /// the values they have allocated.
+    sequences: Arc<Mutex<HashMap<String, SequenceBlock>>>,
+}
+
+/// A block of sequence values allocated to a node.
+#[derive(Default)]
+struct SequenceBlock {
+    next: i64,
+    increment: i64,
+    remaining: i64,
+}
+
+impl Iterator for SequenceBlock {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let value = self.next;
+        self.next = self.next.wrapping_add(self.increment);
+        self.remaining -= 1;
+        Some(value)
+    }
+}
+
 /// A SQL engine using a Raft state machine.
 #[derive(Clone)]
 pub struct Raft {
     client: Client,
+    sequences: SequenceCache,
+    queries: Queries,
+    catalog: CatalogCache,
 }
 
 impl Raft {
-    /// Creates a new Raft-based SQL engine.
+    /// Creates a new Raft-based SQL engine. Clones share allocated sequence
+    /// values, running queries and cached schemas, so a node should use a
+    /// single engine for all sessions.
     pub fn new(tx: Sender<(raft::Request, Sender<Result<raft::Response>>)>) -> Self {
-        Self { client: Client::new(tx) }
+        Self {
+            client: Client::new(tx),
+            sequences: SequenceCache::default(),
+            queries: Queries::default(),
+            catalog: CatalogCache::default(),
+        }
     }
 
-    /// Creates an underlying state machine for a Raft engine.
-    pub fn new_state<E: storage::Engine>(engine: E) -> Result<State<E>> {
-        State::new(engine)
+    /// Creates an underlying state machine for a Raft engine, whose parallel
+    /// scans share the given maximum number of worker threads.
+    pub fn new_state<E: storage::Engine>(engine: E, parallel_workers: usize) -> Result<State<E>> {
+        State::new(engine, parallel_workers)
     }
 
     /// Returns Raft SQL engine status.
@@ -140,15 +328,27 @@ impl super::Engine for Raft {
     type Transaction = Transaction;
 
     fn begin(&self) -> Result<Self::Transaction> {
-        Transaction::begin(self.client.clone(), false, None)
+        Transaction::begin(self.client.clone(), self.sequences.clone(), false, None)
     }
 
     fn begin_read_only(&self) -> Result<Self::Transaction> {
-        Transaction::begin(self.client.clone(), true, None)
+        Transaction::begin(self.client.clone(), self.sequences.clone(), true, None)
     }
 
     fn begin_as_of(&self, version: u64) -> Result<Self::Transaction> {
-        Transaction::begin(self.client.clone(), true, Some(version))
+        Transaction::begin(self.client.clone(), self.sequences.clone(), true, Some(version))
+    }
+
+    fn version_at(&self, time: NaiveDateTime) -> Result<Option<u64>> {
+        self.client.query(Query::VersionAt { time })
+    }
+
+    fn queries(&self) -> &Queries {
+        &self.queries
+    }
+
+    fn catalog_cache(&self) -> &CatalogCache {
+        &self.catalog
     }
 }
 
@@ -156,18 +356,25 @@ impl super::Engine for Raft {
 #[derive(Clone)]
 pub struct Transaction {
     client: Client,
+    sequences: SequenceCache,
     state: TransactionState,
 }
 
 impl Transaction {
     /// Starts a transaction in the given mode.
-    fn begin(client: Client, read_only: bool, as_of: Option<u64>) -> Result<Self> {
+    fn begin(
+        client: Client,
+        sequences: SequenceCache,
+        read_only: bool,
+        as_of: Option<u64>,
+    ) -> Result<Self> {
         let state = if read_only || as_of.is_some() {
             client.query(Query::BeginReadOnly { as_of })?
         } else {
-            client.mutate(Mutation::Begin)?
+            let time = datetime::now().and_utc().timestamp_micros();
+            client.mutate(Mutation::Begin { time })?
         };
-        Ok(Self { client, state })
+        Ok(Self { client, sequences, state })
     }
 }
 
@@ -180,6 +387,10 @@ impl super::Transaction for Transaction {
         self.state.read_only
     }
 
+    fn concurrent(&self) -> &HashSet<u64> {
+        &self.state.active
+    }
+
     fn commit(self) -> Result<()> {
         if !self.read_only() {
             self.client.mutate(Mutation::Commit(self.state.clone()))?
@@ -189,7 +400,28 @@ impl super::Transaction for Transaction {
 
     fn rollback(self) -> Result<()> {
         if !self.read_only() {
-            self.client.mutate(Mutation::Rollback(self.state.clone()))?;
+            self.client.mutate::<()>(Mutation::Rollback(self.state.clone()))?;
+        }
+        Ok(())
+    }
+
+    fn savepoint(&mut self, id: u64) -> Result<()> {
+        if !self.read_only() {
+            self.client.mutate(Mutation::Savepoint { txn: self.state.clone(), id })?
+        }
+        Ok(())
+    }
+
+    fn rollback_to_savepoint(&mut self, id: u64) -> Result<()> {
+        if !self.read_only() {
+            self.client.mutate(Mutation::RollbackToSavepoint { txn: self.state.clone(), id })?
+        }
+        Ok(())
+    }
+
+    fn release_savepoint(&mut self, id: u64) -> Result<()> {
+        if !self.read_only() {
+            self.client.mutate(Mutation::ReleaseSavepoint { txn: self.state.clone(), id })?
         }
         Ok(())
     }
@@ -202,6 +434,14 @@ impl super::Transaction for Transaction {
         })
     }
 
+    fn create_batch(&mut self, table: &str, rows: Vec<Row>) -> Result<()> {
+        self.client.mutate(Mutation::CreateBatch {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            rows,
+        })
+    }
+
     fn delete(&mut self, table: &str, id: &Value) -> Result<()> {
         self.client.mutate(Mutation::Delete {
             txn: self.state.clone(),
@@ -218,35 +458,96 @@ impl super::Transaction for Transaction {
         })
     }
 
-    fn read_index(&self, table: &str, column: &str, value: &Value) -> Result<HashSet<Value>> {
+    fn read_index(&self, table: &str, index: &str, values: &[Value]) -> Result<HashSet<Value>> {
         self.client.query(Query::ReadIndex {
             txn: self.state.clone(),
             table: table.to_string(),
-            column: column.to_string(),
-            value: value.clone(),
+            index: index.to_string(),
+            values: values.to_vec(),
         })
     }
 
-    fn scan(&self, table: &str, filter: Option<Expression>) -> Result<Scan> {
+    fn read_index_range(
+        &self,
+        table: &str,
+        index: &str,
+        range: (Bound<Value>, Bound<Value>),
+    ) -> Result<Vec<Value>> {
+        self.client.query(Query::ReadIndexRange {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            index: index.to_string(),
+            range,
+        })
+    }
+
+    fn read_index_entries(
+        &self,
+        table: &str,
+        index: &str,
+        range: IndexRange,
+        limit: Option<u64>,
+    ) -> Result<Vec<IndexEntry>> {
+        self.client.query(Query::ReadIndexEntries {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            index: index.to_string(),
+            range,
+            limit,
+        })
+    }
+
+    fn scan(
+        &self,
+        table: &str,
+        filter: Option<Expression>,
+        reverse: bool,
+        limit: Option<u64>,
+        columns: Option<Vec<usize>>,
+    ) -> Result<Scan> {
         Ok(Box::new(
             self.client
                 .query::<Vec<_>>(Query::Scan {
                     txn: self.state.clone(),
                     table: table.to_string(),
                     filter,
+                    reverse,
+                    limit,
+                    columns,
+                })?
+                .into_iter()
+                .map(Ok),
+        ))
+    }
+
+    fn scan_parallel(
+        &self,
+        table: &str,
+        filter: Option<Expression>,
+        columns: Option<Vec<usize>>,
+        workers: usize,
+    ) -> Result<Scan> {
+        Ok(Box::new(
+            self.client
+                .query::<Vec<_>>(Query::ScanParallel {
+                    txn: self.state.clone(),
+                    table: table.to_string(),
+                    filter,
+                    columns,
+                    workers,
                 })?
                 .into_iter()
                 .map(Ok),
         ))
     }
 
-    fn scan_index(&self, table: &str, column: &str) -> Result<IndexScan> {
+    fn scan_index(&self, table: &str, index: &str) -> Result<IndexScan> {
         Ok(Box::new(
             self.client
                 .query::<Vec<_>>(Query::ScanIndex {
                     txn: self.state.clone(),
                     table: table.to_string(),
-                    column: column.to_string(),
+                    index: index.to_string(),
                 })?
                 .into_iter()
                 .map(Ok),
@@ -261,6 +562,62 @@ impl super::Transaction for Transaction {
             row,
         })
     }
+
+    fn update_batch(&mut self, table: &str, rows: Vec<(Value, Row)>) -> Result<()> {
+        self.client.mutate(Mutation::UpdateBatch {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            rows,
+        })
+    }
+
+    fn next_sequence(&mut self, table: &str, column: &str) -> Result<i64> {
+        // Values are allocated in batches, to avoid a Raft roundtrip per row.
+        let mut sequences = self.sequences.columns.lock()?;
+        let values = sequences.entry((table.to_string(), column.to_string())).or_default();
+        if values.is_empty() {
+            let start = self.client.mutate(Mutation::AllocateSequence {
+                table: table.to_string(),
+                column: column.to_string(),
+                count: SEQUENCE_BATCH_SIZE,
+            })?;
+            *values = start..start + SEQUENCE_BATCH_SIZE;
+        }
+        Ok(values.next().expect("sequence values were allocated"))
+    }
+}
+
+impl Sequences for Transaction {
+    fn next_value(&mut self, sequence: &str) -> Result<i64> {
+        // Values are allocated in batches, like auto-increment values.
+        let mut sequences = self.sequences.sequences.lock()?;
+        let block = sequences.entry(sequence.to_string()).or_default();
+        if let Some(value) = block.next() {
+            return Ok(value);
+        }
+        let (next, increment, remaining) =
+            self.client.mutate(Mutation::AllocateSequenceValues {
+                txn: self.state.clone(),
+                sequence: sequence.to_string(),
+                count: SEQUENCE_BATCH_SIZE,
+            })?;
+        *block = SequenceBlock { next, increment, remaining };
+        Ok(block.next().expect("sequence values were allocated"))
+    }
+
+    // Current values are tracked by the session, see SessionTransaction.
+    fn current_value(&self, sequence: &str) -> Result<i64> {
+        Err(Error::Value(format!("Sequence {} has no current value in this session", sequence)))
+    }
+
+    fn set_value(&mut self, sequence: &str, value: i64) -> Result<()> {
+        self.sequences.sequences.lock()?.remove(sequence);
+        self.client.mutate(Mutation::SetSequenceValue {
+            txn: self.state.clone(),
+            sequence: sequence.to_string(),
+            value,
+        })
+    }
 }
 
 impl Catalog for Transaction {
@@ -273,6 +630,88 @@ impl Catalog for Transaction {
             .mutate(Mutation::DeleteTable { txn: self.state.clone(), table: table.to_string() })
     }
 
+    fn add_column(&mut self, table: &str, column: Column) -> Result<()> {
+        self.client.mutate(Mutation::AddColumn {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            column,
+        })
+    }
+
+    fn drop_column(&mut self, table: &str, column: &str) -> Result<()> {
+        self.client.mutate(Mutation::DropColumn {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            column: column.to_string(),
+        })
+    }
+
+    fn alter_column(&mut self, table: &str, column: &str, change: ColumnChange) -> Result<()> {
+        self.client.mutate(Mutation::AlterColumn {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            column: column.to_string(),
+            change,
+        })
+    }
+
+    fn create_index(&mut self, table: &str, index: Index) -> Result<()> {
+        self.client.mutate(Mutation::CreateIndex {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            index,
+        })
+    }
+
+    fn build_index(
+        &mut self,
+        index: &str,
+        after: Option<Value>,
+        limit: usize,
+    ) -> Result<Option<Value>> {
+        self.client.mutate(Mutation::BuildIndex {
+            txn: self.state.clone(),
+            index: index.to_string(),
+            after,
+            limit,
+        })
+    }
+
+    fn drop_index(&mut self, index: &str) -> Result<()> {
+        self.client
+            .mutate(Mutation::DropIndex { txn: self.state.clone(), index: index.to_string() })
+    }
+
+    fn create_trigger(&mut self, table: &str, trigger: Trigger) -> Result<()> {
+        self.client.mutate(Mutation::CreateTrigger {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            trigger,
+        })
+    }
+
+    fn drop_trigger(&mut self, table: &str, trigger: &str) -> Result<()> {
+        self.client.mutate(Mutation::DropTrigger {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            trigger: trigger.to_string(),
+        })
+    }
+
+    fn set_comment(
+        &mut self,
+        table: &str,
+        column: Option<&str>,
+        comment: Option<String>,
+    ) -> Result<()> {
+        self.client.mutate(Mutation::SetComment {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            column: column.map(|c| c.to_string()),
+            comment,
+        })
+    }
+
     fn read_table(&self, table: &str) -> Result<Option<Table>> {
         self.client.query(Query::ReadTable { txn: self.state.clone(), table: table.to_string() })
     }
@@ -282,6 +721,124 @@ impl Catalog for Transaction {
             self.client.query::<Vec<_>>(Query::ScanTables { txn: self.state.clone() })?.into_iter(),
         ))
     }
+
+    fn create_view(&mut self, view: View) -> Result<()> {
+        self.client.mutate(Mutation::CreateView { txn: self.state.clone(), view })
+    }
+
+    fn delete_view(&mut self, view: &str) -> Result<()> {
+        self.client.mutate(Mutation::DeleteView { txn: self.state.clone(), view: view.to_string() })
+    }
+
+    fn read_view(&self, view: &str) -> Result<Option<View>> {
+        self.client.query(Query::ReadView { txn: self.state.clone(), view: view.to_string() })
+    }
+
+    fn scan_views(&self) -> Result<Views> {
+        Ok(Box::new(
+            self.client.query::<Vec<_>>(Query::ScanViews { txn: self.state.clone() })?.into_iter(),
+        ))
+    }
+
+    fn create_sequence(&mut self, sequence: Sequence) -> Result<()> {
+        self.sequences.sequences.lock()?.remove(&sequence.name);
+        self.client.mutate(Mutation::CreateSequence { txn: self.state.clone(), sequence })
+    }
+
+    fn delete_sequence(&mut self, sequence: &str) -> Result<()> {
+        self.sequences.sequences.lock()?.remove(sequence);
+        self.client.mutate(Mutation::DeleteSequence {
+            txn: self.state.clone(),
+            sequence: sequence.to_string(),
+        })
+    }
+
+    fn read_sequence(&self, sequence: &str) -> Result<Option<Sequence>> {
+        self.client
+            .query(Query::ReadSequence { txn: self.state.clone(), sequence: sequence.to_string() })
+    }
+
+    fn scan_sequences(&self) -> Result<Vec<Sequence>> {
+        self.client.query(Query::ScanSequences { txn: self.state.clone() })
+    }
+
+    fn create_type(&mut self, enum_type: EnumType) -> Result<()> {
+        self.client.mutate(Mutation::CreateType { txn: self.state.clone(), enum_type })
+    }
+
+    fn delete_type(&mut self, name: &str) -> Result<()> {
+        self.client.mutate(Mutation::DeleteType { txn: self.state.clone(), name: name.to_string() })
+    }
+
+    fn read_type(&self, name: &str) -> Result<Option<EnumType>> {
+        self.client.query(Query::ReadType { txn: self.state.clone(), name: name.to_string() })
+    }
+
+    fn scan_types(&self) -> Result<Vec<EnumType>> {
+        self.client.query(Query::ScanTypes { txn: self.state.clone() })
+    }
+
+    fn write_statistics(&mut self, table: &str, statistics: Statistics) -> Result<()> {
+        self.client.mutate(Mutation::WriteStatistics {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            statistics,
+        })
+    }
+
+    fn read_statistics(&self, table: &str) -> Result<Option<Statistics>> {
+        self.client
+            .query(Query::ReadStatistics { txn: self.state.clone(), table: table.to_string() })
+    }
+
+    fn schema_version(&self) -> Result<u64> {
+        self.client.query(Query::ReadSchemaVersion { txn: self.state.clone() })
+    }
+
+    fn create_database(&mut self, database: &str) -> Result<()> {
+        self.client.mutate(Mutation::CreateDatabase {
+            txn: self.state.clone(),
+            database: database.to_string(),
+        })
+    }
+
+    fn scan_databases(&self) -> Result<Vec<String>> {
+        self.client.query(Query::ScanDatabases { txn: self.state.clone() })
+    }
+
+    fn create_user(&mut self, user: User) -> Result<()> {
+        self.client.mutate(Mutation::CreateUser { txn: self.state.clone(), user })
+    }
+
+    fn alter_user(&mut self, user: User) -> Result<()> {
+        self.client.mutate(Mutation::AlterUser { txn: self.state.clone(), user })
+    }
+
+    fn delete_user(&mut self, name: &str) -> Result<()> {
+        self.client.mutate(Mutation::DeleteUser { txn: self.state.clone(), name: name.to_string() })
+    }
+
+    fn read_user(&self, name: &str) -> Result<Option<User>> {
+        self.client.query(Query::ReadUser { txn: self.state.clone(), name: name.to_string() })
+    }
+
+    fn scan_users(&self) -> Result<Vec<User>> {
+        self.client.query(Query::ScanUsers { txn: self.state.clone() })
+    }
+
+    fn set_privileges(
+        &mut self,
+        table: &str,
+        grantee: &str,
+        privileges: BTreeSet<Privilege>,
+    ) -> Result<()> {
+        self.client.mutate(Mutation::SetPrivileges {
+            txn: self.state.clone(),
+            table: table.to_string(),
+            grantee: grantee.to_string(),
+            privileges,
+        })
+    }
 }
 
 /// The Raft state machine for the Raft-based SQL engine, using a KV SQL engine
@@ -293,9 +850,10 @@ pub struct State<E: storage::Engine> {
 }
 
 impl<E: storage::Engine> State<E> {
-    /// Creates a new Raft state maching using the given storage engine.
-    pub fn new(engine: E) -> Result<Self> {
-        let engine = super::KV::new(engine);
+    /// Creates a new Raft state maching using the given storage engine, and
+    /// the given maximum number of parallel scan worker threads.
+    pub fn new(engine: E, parallel_workers: usize) -> Result<Self> {
+        let engine = super::KV::new(engine).with_parallel_workers(parallel_workers);
         let applied_index = engine
             .get_metadata(b"applied_index")?
             .map(|b| bincode::deserialize(&b))
@@ -306,19 +864,34 @@ impl<E: storage::Engine> State<E> {
     /// Mutates the state machine.
     fn mutate(&mut self, mutation: Mutation) -> Result<Vec<u8>> {
         match mutation {
-            Mutation::Begin => bincode::serialize(&self.engine.begin()?.state()),
+            Mutation::Begin { time } => bincode::serialize(&self.engine.begin_at(time)?.state()),
             Mutation::Commit(txn) => bincode::serialize(&self.engine.resume(txn)?.commit()?),
             Mutation::Rollback(txn) => bincode::serialize(&self.engine.resume(txn)?.rollback()?),
+            Mutation::Savepoint { txn, id } => {
+                bincode::serialize(&self.engine.resume(txn)?.savepoint(id)?)
+            }
+            Mutation::RollbackToSavepoint { txn, id } => {
+                bincode::serialize(&self.engine.resume(txn)?.rollback_to_savepoint(id)?)
+            }
+            Mutation::ReleaseSavepoint { txn, id } => {
+                bincode::serialize(&self.engine.resume(txn)?.release_savepoint(id)?)
+            }
 
             Mutation::Create { txn, table, row } => {
                 bincode::serialize(&self.engine.resume(txn)?.create(&table, row)?)
             }
+            Mutation::CreateBatch { txn, table, rows } => {
+                bincode::serialize(&self.engine.resume(txn)?.create_batch(&table, rows)?)
+            }
             Mutation::Delete { txn, table, id } => {
                 bincode::serialize(&self.engine.resume(txn)?.delete(&table, &id)?)
             }
             Mutation::Update { txn, table, id, row } => {
                 bincode::serialize(&self.engine.resume(txn)?.update(&table, &id, row)?)
             }
+            Mutation::UpdateBatch { txn, table, rows } => {
+                bincode::serialize(&self.engine.resume(txn)?.update_batch(&table, rows)?)
+            }
 
             Mutation::CreateTable { txn, schema } => {
                 bincode::serialize(&self.engine.resume(txn)?.create_table(schema)?)
@@ -326,6 +899,79 @@ impl<E: storage::Engine> State<E> {
             Mutation::DeleteTable { txn, table } => {
                 bincode::serialize(&self.engine.resume(txn)?.delete_table(&table)?)
             }
+            Mutation::AddColumn { txn, table, column } => {
+                bincode::serialize(&self.engine.resume(txn)?.add_column(&table, column)?)
+            }
+            Mutation::DropColumn { txn, table, column } => {
+                bincode::serialize(&self.engine.resume(txn)?.drop_column(&table, &column)?)
+            }
+            Mutation::AlterColumn { txn, table, column, change } => {
+                bincode::serialize(&self.engine.resume(txn)?.alter_column(&table, &column, change)?)
+            }
+            Mutation::CreateIndex { txn, table, index } => {
+                bincode::serialize(&self.engine.resume(txn)?.create_index(&table, index)?)
+            }
+            Mutation::BuildIndex { txn, index, after, limit } => {
+                bincode::serialize(&self.engine.resume(txn)?.build_index(&index, after, limit)?)
+            }
+            Mutation::DropIndex { txn, index } => {
+                bincode::serialize(&self.engine.resume(txn)?.drop_index(&index)?)
+            }
+            Mutation::CreateTrigger { txn, table, trigger } => {
+                bincode::serialize(&self.engine.resume(txn)?.create_trigger(&table, trigger)?)
+            }
+            Mutation::DropTrigger { txn, table, trigger } => {
+                bincode::serialize(&self.engine.resume(txn)?.drop_trigger(&table, &trigger)?)
+            }
+            Mutation::SetComment { txn, table, column, comment } => bincode::serialize(
+                &self.engine.resume(txn)?.set_comment(&table, column.as_deref(), comment)?,
+            ),
+            Mutation::CreateView { txn, view } => {
+                bincode::serialize(&self.engine.resume(txn)?.create_view(view)?)
+            }
+            Mutation::DeleteView { txn, view } => {
+                bincode::serialize(&self.engine.resume(txn)?.delete_view(&view)?)
+            }
+            Mutation::CreateSequence { txn, sequence } => {
+                bincode::serialize(&self.engine.resume(txn)?.create_sequence(sequence)?)
+            }
+            Mutation::DeleteSequence { txn, sequence } => {
+                bincode::serialize(&self.engine.resume(txn)?.delete_sequence(&sequence)?)
+            }
+            Mutation::CreateType { txn, enum_type } => {
+                bincode::serialize(&self.engine.resume(txn)?.create_type(enum_type)?)
+            }
+            Mutation::DeleteType { txn, name } => {
+                bincode::serialize(&self.engine.resume(txn)?.delete_type(&name)?)
+            }
+            Mutation::WriteStatistics { txn, table, statistics } => {
+                bincode::serialize(&self.engine.resume(txn)?.write_statistics(&table, statistics)?)
+            }
+            Mutation::CreateDatabase { txn, database } => {
+                bincode::serialize(&self.engine.resume(txn)?.create_database(&database)?)
+            }
+            Mutation::CreateUser { txn, user } => {
+                bincode::serialize(&self.engine.resume(txn)?.create_user(user)?)
+            }
+            Mutation::AlterUser { txn, user } => {
+                bincode::serialize(&self.engine.resume(txn)?.alter_user(user)?)
+            }
+            Mutation::DeleteUser { txn, name } => {
+                bincode::serialize(&self.engine.resume(txn)?.delete_user(&name)?)
+            }
+            Mutation::SetPrivileges { txn, table, grantee, privileges } => bincode::serialize(
+                &self.engine.resume(txn)?.set_privileges(&table, &grantee, privileges)?,
+            ),
+
+            Mutation::AllocateSequence { table, column, count } => {
+                bincode::serialize(&self.engine.allocate_sequence(&table, &column, count)?)
+            }
+            Mutation::AllocateSequenceValues { txn, sequence, count } => bincode::serialize(
+                &self.engine.resume(txn)?.allocate_sequence_values(&sequence, count)?,
+            ),
+            Mutation::SetSequenceValue { txn, sequence, value } => {
+                bincode::serialize(&self.engine.resume(txn)?.set_sequence_value(&sequence, value)?)
+            }
         }
     }
 }
@@ -339,11 +985,11 @@ impl<E: storage::Engine> raft::State for State<E> {
         assert_eq!(entry.index, self.applied_index + 1, "entry index not after applied index");
 
         let result = match &entry.command {
-            Some(command) => match self.mutate(bincode::deserialize(command)?) {
+            raft::Command::Write(command) => match self.mutate(bincode::deserialize(command)?) {
                 error @ Err(Error::Internal(_)) => return error, // don't record as applied
                 result => result,
             },
-            None => Ok(Vec::new()),
+            raft::Command::Noop | raft::Command::AddNode { .. } => Ok(Vec::new()),
         };
         self.applied_index = entry.index;
         self.engine.set_metadata(b"applied_index", bincode::serialize(&entry.index)?)?;
@@ -360,21 +1006,39 @@ impl<E: storage::Engine> raft::State for State<E> {
                 };
                 bincode::serialize(&txn.state())
             }
+            Query::VersionAt { time } => bincode::serialize(&self.engine.version_at(time)?),
             Query::Read { txn, table, id } => {
                 bincode::serialize(&self.engine.resume(txn)?.read(&table, &id)?)
             }
-            Query::ReadIndex { txn, table, column, value } => {
-                bincode::serialize(&self.engine.resume(txn)?.read_index(&table, &column, &value)?)
+            Query::ReadIndex { txn, table, index, values } => {
+                bincode::serialize(&self.engine.resume(txn)?.read_index(&table, &index, &values)?)
             }
+            Query::ReadIndexRange { txn, table, index, range } => bincode::serialize(
+                &self.engine.resume(txn)?.read_index_range(&table, &index, range)?,
+            ),
+            Query::ReadIndexEntries { txn, table, index, range, limit } => bincode::serialize(
+                &self.engine.resume(txn)?.read_index_entries(&table, &index, range, limit)?,
+            ),
             // FIXME These need to stream rows somehow
-            Query::Scan { txn, table, filter } => bincode::serialize(
-                &self.engine.resume(txn)?.scan(&table, filter)?.collect::<Result<Vec<_>>>()?,
+            Query::Scan { txn, table, filter, reverse, limit, columns } => bincode::serialize(
+                &self
+                    .engine
+                    .resume(txn)?
+                    .scan(&table, filter, reverse, limit, columns)?
+                    .collect::<Result<Vec<_>>>()?,
             ),
-            Query::ScanIndex { txn, table, column } => bincode::serialize(
+            Query::ScanParallel { txn, table, filter, columns, workers } => bincode::serialize(
                 &self
                     .engine
                     .resume(txn)?
-                    .scan_index(&table, &column)?
+                    .scan_parallel(&table, filter, columns, workers)?
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            Query::ScanIndex { txn, table, index } => bincode::serialize(
+                &self
+                    .engine
+                    .resume(txn)?
+                    .scan_index(&table, &index)?
                     .collect::<Result<Vec<_>>>()?,
             ),
             Query::Status => bincode::serialize(&self.engine.kv.status()?),
@@ -385,6 +1049,35 @@ impl<E: storage::Engine> raft::State for State<E> {
             Query::ScanTables { txn } => {
                 bincode::serialize(&self.engine.resume(txn)?.scan_tables()?.collect::<Vec<_>>())
             }
+            Query::ReadView { txn, view } => {
+                bincode::serialize(&self.engine.resume(txn)?.read_view(&view)?)
+            }
+            Query::ReadSequence { txn, sequence } => {
+                bincode::serialize(&self.engine.resume(txn)?.read_sequence(&sequence)?)
+            }
+            Query::ScanSequences { txn } => {
+                bincode::serialize(&self.engine.resume(txn)?.scan_sequences()?)
+            }
+            Query::ReadType { txn, name } => {
+                bincode::serialize(&self.engine.resume(txn)?.read_type(&name)?)
+            }
+            Query::ScanTypes { txn } => bincode::serialize(&self.engine.resume(txn)?.scan_types()?),
+            Query::ScanViews { txn } => {
+                bincode::serialize(&self.engine.resume(txn)?.scan_views()?.collect::<Vec<_>>())
+            }
+            Query::ReadStatistics { txn, table } => {
+                bincode::serialize(&self.engine.resume(txn)?.read_statistics(&table)?)
+            }
+            Query::ReadSchemaVersion { txn } => {
+                bincode::serialize(&self.engine.resume(txn)?.schema_version()?)
+            }
+            Query::ScanDatabases { txn } => {
+                bincode::serialize(&self.engine.resume(txn)?.scan_databases()?)
+            }
+            Query::ReadUser { txn, name } => {
+                bincode::serialize(&self.engine.resume(txn)?.read_user(&name)?)
+            }
+            Query::ScanUsers { txn } => bincode::serialize(&self.engine.resume(txn)?.scan_users()?),
         }
     }
 }
@@ -0,0 +1,155 @@
+//! Session settings, which are local to a session and can be changed via SET
+//! and inspected via SHOW.
+use super::super::execution::Accountant;
+use super::super::schema::DEFAULT_DATABASE;
+use super::super::types::Value;
+use crate::error::{Error, Result};
+
+use std::time::Duration;
+
+/// Per-session settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// The maximum time a statement may run for, including fetching its
+    /// result rows, or None for no limit. Set in milliseconds or as a string
+    /// with a unit, e.g. '5s', where 0 disables the limit.
+    pub statement_timeout: Option<Duration>,
+    /// Whether transactions are read-only unless BEGIN specifies otherwise.
+    /// This also applies to statements run outside of an explicit
+    /// transaction, i.e. writes are rejected.
+    pub default_transaction_read_only: bool,
+    /// The number of times to retry a statement that fails with a
+    /// serialization error, when run outside of an explicit transaction.
+    pub serialization_retries: u64,
+    /// The maximum number of plans to cache, keyed by normalized statement
+    /// text. 0 disables the plan cache.
+    pub plan_cache_size: usize,
+    /// The maximum number of worker threads a table scan may use to decode
+    /// and filter rows, in read-only transactions, capped by the engine's
+    /// worker pool. 1 disables parallel scans.
+    pub parallel_workers: usize,
+    /// The memory budget of each sort and aggregation in kilobytes, beyond
+    /// which rows are spilled to temporary files.
+    pub work_memory: usize,
+    /// The memory limit of each statement's buffered rows in kilobytes,
+    /// across its sorts, hash joins and aggregations, beyond which it errors.
+    /// 0 disables the limit.
+    pub query_memory: usize,
+    /// Whether string literals must be explicitly cast when compared with or
+    /// applied to values of other types, rather than implicitly converted.
+    pub strict_types: bool,
+    /// The current database, in which unqualified names are resolved. This
+    /// is changed via USE rather than SET, since the database must exist.
+    pub database: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            statement_timeout: None,
+            default_transaction_read_only: false,
+            serialization_retries: 0,
+            plan_cache_size: 100,
+            parallel_workers: 1,
+            work_memory: 65536,
+            query_memory: 1048576,
+            strict_types: false,
+            database: DEFAULT_DATABASE.to_string(),
+        }
+    }
+}
+
+impl Settings {
+    /// All setting names, in display order.
+    const NAMES: [&'static str; 8] = [
+        "default_transaction_read_only",
+        "parallel_workers",
+        "plan_cache_size",
+        "query_memory",
+        "serialization_retries",
+        "statement_timeout",
+        "strict_types",
+        "work_memory",
+    ];
+
+    /// Returns the value of a setting.
+    pub fn get(&self, name: &str) -> Result<Value> {
+        Ok(match name {
+            "default_transaction_read_only" => Value::Boolean(self.default_transaction_read_only),
+            "parallel_workers" => Value::Integer(self.parallel_workers as i64),
+            "plan_cache_size" => Value::Integer(self.plan_cache_size as i64),
+            "query_memory" => Value::Integer(self.query_memory as i64),
+            "serialization_retries" => Value::Integer(self.serialization_retries as i64),
+            "statement_timeout" => {
+                Value::Integer(self.statement_timeout.map_or(0, |t| t.as_millis() as i64))
+            }
+            "strict_types" => Value::Boolean(self.strict_types),
+            "work_memory" => Value::Integer(self.work_memory as i64),
+            name => return Err(Error::Value(format!("Unknown setting {}", name))),
+        })
+    }
+
+    /// Sets the value of a setting, or resets it to its default if None.
+    pub fn set(&mut self, name: &str, value: Option<Value>) -> Result<()> {
+        let Some(value) = value else {
+            self.get(name)?; // check the name
+            let default = Self::default().get(name)?;
+            return self.set(name, Some(default));
+        };
+        let invalid = || Error::Value(format!("Invalid value {} for setting {}", value, name));
+        match (name, &value) {
+            ("default_transaction_read_only", Value::Boolean(b)) => {
+                self.default_transaction_read_only = *b
+            }
+            ("parallel_workers", Value::Integer(i)) if *i >= 1 => {
+                self.parallel_workers = *i as usize
+            }
+            ("plan_cache_size", Value::Integer(i)) if *i >= 0 => self.plan_cache_size = *i as usize,
+            ("query_memory", Value::Integer(i)) if *i >= 0 => self.query_memory = *i as usize,
+            ("serialization_retries", Value::Integer(i)) if *i >= 0 => {
+                self.serialization_retries = *i as u64
+            }
+            ("statement_timeout", Value::Integer(0)) => self.statement_timeout = None,
+            ("statement_timeout", Value::Integer(i)) if *i > 0 => {
+                self.statement_timeout = Some(Duration::from_millis(*i as u64))
+            }
+            ("statement_timeout", Value::String(s)) => {
+                let timeout = parse_duration(s).ok_or_else(invalid)?;
+                self.statement_timeout = Some(timeout).filter(|t| !t.is_zero())
+            }
+            ("strict_types", Value::Boolean(b)) => self.strict_types = *b,
+            ("work_memory", Value::Integer(i)) if *i >= 1 => self.work_memory = *i as usize,
+            (name, _) => {
+                self.get(name)?;
+                return Err(invalid());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a memory accountant for a statement, limited by query_memory.
+    pub fn accountant(&self) -> Accountant {
+        Accountant::new(Some(self.query_memory * 1024).filter(|limit| *limit > 0))
+    }
+
+    /// Returns all settings as name/value pairs.
+    pub fn all(&self) -> Result<Vec<(&'static str, Value)>> {
+        Self::NAMES.into_iter().map(|name| Ok((name, self.get(name)?))).collect()
+    }
+}
+
+/// Parses a duration given as an integer with an optional unit, one of ms
+/// (the default), s, min or h, e.g. '5s'. Returns None if invalid.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let number: u64 = number.parse().ok()?;
+    let millis = match unit.trim() {
+        "" | "ms" => 1,
+        "s" => 1000,
+        "min" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        _ => return None,
+    };
+    Some(Duration::from_millis(number.checked_mul(millis)?))
+}
@@ -0,0 +1,621 @@
+//! Temporary tables, which are local to a session. They are stored in an
+//! in-memory engine owned by the session rather than the main (possibly
+//! replicated) engine, and are dropped along with the session, e.g. when a
+//! client disconnects.
+use super::super::schema::{
+    Catalog, Column, ColumnChange, EnumType, Index, Privilege, Sequence, Statistics, Table, Tables,
+    Trigger, User, View, Views,
+};
+use super::super::types::{Expression, Row, Sequences, Value};
+use super::{CatalogCache, Engine as _, IndexEntry, IndexRange, IndexScan, Scan, Transaction, KV};
+use crate::error::{Error, Result};
+use crate::storage;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Bound;
+use std::sync::{Arc, Mutex};
+
+/// The engine used to store a session's temporary tables.
+pub type Engine = KV<storage::Memory>;
+
+/// The values most recently returned by NEXTVAL() in a session, by sequence
+/// name, as returned by CURRVAL().
+pub type CurrentValues = Arc<Mutex<HashMap<String, i64>>>;
+
+/// A transaction which overlays a session's temporary tables on a transaction
+/// in the main engine. Operations on temporary tables are routed to the
+/// temporary engine, and all others to the main transaction. Tables can't be
+/// created with conflicting names, but if another session later creates a
+/// table with the same name as a temporary table, the temporary table takes
+/// precedence.
+///
+/// The session transaction also collects rows written to tables with deferred
+/// constraints, and checks their constraints when it commits, reads main
+/// table schemas via the engine's catalog cache, and records the session's
+/// current sequence values. Tables, views, sequences, types and triggers it
+/// creates are owned by the session's authenticated user, if any, who must own
+/// sequences to use them.
+pub struct SessionTransaction<T: Transaction> {
+    txn: T,
+    temp: <Engine as super::Engine>::Transaction,
+    /// The engine's catalog cache.
+    catalog: CatalogCache,
+    /// The main transaction's schema version, or None once the transaction
+    /// changes the schema, in which case the catalog cache isn't used.
+    schema_version: Option<u64>,
+    /// Primary keys of written rows with deferred constraints, by table.
+    deferred: BTreeMap<String, Vec<Value>>,
+    /// Cached table schemas, if the table has deferred constraints. Cleared
+    /// when the transaction changes the schema.
+    deferred_tables: HashMap<String, Option<Table>>,
+    /// The session's current sequence values.
+    current: CurrentValues,
+    /// The session's authenticated user, if any, which owns created tables,
+    /// views, sequences, types and triggers.
+    user: Option<String>,
+}
+
+impl<T: Transaction> SessionTransaction<T> {
+    /// Begins a temporary transaction alongside the given main transaction,
+    /// read-only if the main transaction is. The temporary engine isn't
+    /// versioned with the main engine, so historical transactions see the
+    /// current temporary tables.
+    pub fn begin(
+        txn: T,
+        temp: &Engine,
+        catalog: &CatalogCache,
+        current: &CurrentValues,
+        user: Option<&str>,
+    ) -> Result<Self> {
+        let result = txn.schema_version().and_then(|version| {
+            let temp = if txn.read_only() { temp.begin_read_only() } else { temp.begin() }?;
+            Ok((temp, version))
+        });
+        match result {
+            Ok((temp, version)) => Ok(Self {
+                txn,
+                temp,
+                catalog: catalog.clone(),
+                schema_version: Some(version),
+                deferred: BTreeMap::new(),
+                deferred_tables: HashMap::new(),
+                current: current.clone(),
+                user: user.map(str::to_string),
+            }),
+            Err(err) => {
+                txn.rollback()?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Records that the transaction changed the schema, which invalidates
+    /// cached schemas.
+    fn schema_changed(&mut self) {
+        self.deferred_tables.clear();
+        self.schema_version = None;
+    }
+
+    /// Returns true if the given table is a temporary table.
+    fn is_temporary(&self, table: &str) -> Result<bool> {
+        Ok(self.temp.read_table(table)?.is_some())
+    }
+
+    /// Returns the table schema if it has deferred constraints.
+    fn deferred_table(&mut self, table: &str) -> Result<Option<Table>> {
+        if let Some(schema) = self.deferred_tables.get(table) {
+            return Ok(schema.clone());
+        }
+        let schema = self.read_table(table)?.filter(|t| t.has_deferred());
+        self.deferred_tables.insert(table.to_string(), schema.clone());
+        Ok(schema)
+    }
+
+    /// Records written rows whose deferred constraints must be checked on
+    /// commit, if the table has any.
+    fn defer<'a>(&mut self, table: &str, rows: impl IntoIterator<Item = &'a Row>) -> Result<()> {
+        let Some(schema) = self.deferred_table(table)? else { return Ok(()) };
+        let ids =
+            rows.into_iter().map(|row| schema.get_row_key(row)).collect::<Result<Vec<_>>>()?;
+        self.deferred.entry(schema.name).or_default().extend(ids);
+        Ok(())
+    }
+
+    /// Checks the deferred constraints of written rows that still exist.
+    fn check_deferred(&mut self) -> Result<()> {
+        for (table, ids) in std::mem::take(&mut self.deferred) {
+            let Some(table) = self.read_table(&table)? else { continue };
+            let mut checked = HashSet::new();
+            for id in ids {
+                if !checked.insert(id.clone()) {
+                    continue;
+                }
+                if let Some(row) = self.read(&table.name, &id)? {
+                    table.validate_deferred(&row, self)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Errors if an index name is used by a table in the other engine, since
+    /// index names are unique across all tables.
+    fn check_index(&self, index: &Index, temporary: bool) -> Result<()> {
+        let other = match temporary {
+            true => self.txn.read_index_table(&index.name)?,
+            false => self.temp.read_index_table(&index.name)?,
+        };
+        match other {
+            Some(other) => Err(Error::Value(format!(
+                "Index {} already exists on table {}",
+                index.name, other.name
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: Transaction> Catalog for SessionTransaction<T> {
+    fn create_table(&mut self, mut table: Table) -> Result<()> {
+        self.schema_changed();
+        if !table.temporary {
+            table.owner = self.user.clone();
+        }
+        if self.read_table(&table.name)?.is_some() {
+            return Err(Error::Value(format!("Table {} already exists", table.name)));
+        }
+        for index in &table.indexes {
+            self.check_index(index, table.temporary)?;
+        }
+        if table.temporary {
+            if self.txn.read_view(&table.name)?.is_some() {
+                return Err(Error::Value(format!("View {} already exists", table.name)));
+            }
+            self.temp.create_table(table)
+        } else {
+            self.txn.create_table(table)
+        }
+    }
+
+    fn delete_table(&mut self, table: &str) -> Result<()> {
+        self.schema_changed();
+        match self.is_temporary(table)? {
+            true => self.temp.delete_table(table),
+            false => self.txn.delete_table(table),
+        }
+    }
+
+    fn add_column(&mut self, table: &str, column: Column) -> Result<()> {
+        self.schema_changed();
+        match self.is_temporary(table)? {
+            true => self.temp.add_column(table, column),
+            false => self.txn.add_column(table, column),
+        }
+    }
+
+    fn drop_column(&mut self, table: &str, column: &str) -> Result<()> {
+        self.schema_changed();
+        match self.is_temporary(table)? {
+            true => self.temp.drop_column(table, column),
+            false => self.txn.drop_column(table, column),
+        }
+    }
+
+    fn alter_column(&mut self, table: &str, column: &str, change: ColumnChange) -> Result<()> {
+        self.schema_changed();
+        match self.is_temporary(table)? {
+            true => self.temp.alter_column(table, column, change),
+            false => self.txn.alter_column(table, column, change),
+        }
+    }
+
+    fn create_index(&mut self, table: &str, index: Index) -> Result<()> {
+        self.schema_changed();
+        let temporary = self.is_temporary(table)?;
+        self.check_index(&index, temporary)?;
+        match temporary {
+            true => self.temp.create_index(table, index),
+            false => self.txn.create_index(table, index),
+        }
+    }
+
+    fn build_index(
+        &mut self,
+        index: &str,
+        after: Option<Value>,
+        limit: usize,
+    ) -> Result<Option<Value>> {
+        self.schema_changed();
+        match self.temp.read_index_table(index)? {
+            Some(_) => self.temp.build_index(index, after, limit),
+            None => self.txn.build_index(index, after, limit),
+        }
+    }
+
+    fn drop_index(&mut self, index: &str) -> Result<()> {
+        self.schema_changed();
+        match self.temp.read_index_table(index)? {
+            Some(_) => self.temp.drop_index(index),
+            None => self.txn.drop_index(index),
+        }
+    }
+
+    fn create_trigger(&mut self, table: &str, mut trigger: Trigger) -> Result<()> {
+        self.schema_changed();
+        trigger.owner = self.user.clone();
+        match self.is_temporary(table)? {
+            true => self.temp.create_trigger(table, trigger),
+            false => self.txn.create_trigger(table, trigger),
+        }
+    }
+
+    fn drop_trigger(&mut self, table: &str, trigger: &str) -> Result<()> {
+        self.schema_changed();
+        match self.is_temporary(table)? {
+            true => self.temp.drop_trigger(table, trigger),
+            false => self.txn.drop_trigger(table, trigger),
+        }
+    }
+
+    fn set_comment(
+        &mut self,
+        table: &str,
+        column: Option<&str>,
+        comment: Option<String>,
+    ) -> Result<()> {
+        self.schema_changed();
+        match self.is_temporary(table)? {
+            true => self.temp.set_comment(table, column, comment),
+            false => self.txn.set_comment(table, column, comment),
+        }
+    }
+
+    fn read_table(&self, table: &str) -> Result<Option<Table>> {
+        match (self.temp.read_table(table)?, self.schema_version) {
+            (Some(table), _) => Ok(Some(table)),
+            (None, Some(version)) => {
+                self.catalog.read_table(version, table, || self.txn.read_table(table))
+            }
+            (None, None) => self.txn.read_table(table),
+        }
+    }
+
+    fn scan_tables(&self) -> Result<Tables> {
+        let main = match self.schema_version {
+            Some(version) => {
+                self.catalog.scan_tables(version, || Ok(self.txn.scan_tables()?.collect()))?
+            }
+            None => self.txn.scan_tables()?.collect(),
+        };
+        let mut tables: Vec<Table> = self.temp.scan_tables()?.chain(main).collect();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Box::new(tables.into_iter()))
+    }
+
+    fn create_view(&mut self, mut view: View) -> Result<()> {
+        view.owner = self.user.clone();
+        if self.is_temporary(&view.name)? {
+            return Err(Error::Value(format!("Table {} already exists", view.name)));
+        }
+        self.txn.create_view(view)
+    }
+
+    fn delete_view(&mut self, view: &str) -> Result<()> {
+        self.txn.delete_view(view)
+    }
+
+    fn read_view(&self, view: &str) -> Result<Option<View>> {
+        self.txn.read_view(view)
+    }
+
+    fn scan_views(&self) -> Result<Views> {
+        self.txn.scan_views()
+    }
+
+    // Sequences can't be temporary.
+    fn create_sequence(&mut self, mut sequence: Sequence) -> Result<()> {
+        self.schema_changed();
+        sequence.owner = self.user.clone();
+        self.txn.create_sequence(sequence)
+    }
+
+    fn delete_sequence(&mut self, sequence: &str) -> Result<()> {
+        self.schema_changed();
+        self.txn.delete_sequence(sequence)
+    }
+
+    fn read_sequence(&self, sequence: &str) -> Result<Option<Sequence>> {
+        self.txn.read_sequence(sequence)
+    }
+
+    fn scan_sequences(&self) -> Result<Vec<Sequence>> {
+        self.txn.scan_sequences()
+    }
+
+    // Types can't be temporary, but temporary tables can use them.
+    fn create_type(&mut self, mut enum_type: EnumType) -> Result<()> {
+        self.schema_changed();
+        enum_type.owner = self.user.clone();
+        self.txn.create_type(enum_type)
+    }
+
+    fn delete_type(&mut self, name: &str) -> Result<()> {
+        for table in self.temp.scan_tables()? {
+            if let Some(column) = table.get_type_column(name) {
+                return Err(Error::Value(format!(
+                    "Type {} is used by column {}.{}",
+                    name, table.name, column.name
+                )));
+            }
+        }
+        self.schema_changed();
+        self.txn.delete_type(name)
+    }
+
+    fn read_type(&self, name: &str) -> Result<Option<EnumType>> {
+        self.txn.read_type(name)
+    }
+
+    fn scan_types(&self) -> Result<Vec<EnumType>> {
+        self.txn.scan_types()
+    }
+
+    fn write_statistics(&mut self, table: &str, statistics: Statistics) -> Result<()> {
+        match self.is_temporary(table)? {
+            true => self.temp.write_statistics(table, statistics),
+            false => self.txn.write_statistics(table, statistics),
+        }
+    }
+
+    fn read_statistics(&self, table: &str) -> Result<Option<Statistics>> {
+        match self.is_temporary(table)? {
+            true => self.temp.read_statistics(table),
+            false => self.txn.read_statistics(table),
+        }
+    }
+
+    // Temporary tables are local to the session, which clears its plan cache
+    // when it changes them instead.
+    fn schema_version(&self) -> Result<u64> {
+        match self.schema_version {
+            Some(version) => Ok(version),
+            None => self.txn.schema_version(),
+        }
+    }
+
+    fn create_database(&mut self, database: &str) -> Result<()> {
+        self.schema_changed();
+        self.txn.create_database(database)
+    }
+
+    fn scan_databases(&self) -> Result<Vec<String>> {
+        self.txn.scan_databases()
+    }
+
+    fn create_user(&mut self, user: User) -> Result<()> {
+        self.txn.create_user(user)
+    }
+
+    fn alter_user(&mut self, user: User) -> Result<()> {
+        self.txn.alter_user(user)
+    }
+
+    fn delete_user(&mut self, name: &str) -> Result<()> {
+        self.txn.delete_user(name)
+    }
+
+    fn read_user(&self, name: &str) -> Result<Option<User>> {
+        self.txn.read_user(name)
+    }
+
+    fn scan_users(&self) -> Result<Vec<User>> {
+        self.txn.scan_users()
+    }
+
+    fn set_privileges(
+        &mut self,
+        table: &str,
+        grantee: &str,
+        privileges: BTreeSet<Privilege>,
+    ) -> Result<()> {
+        self.schema_changed();
+        match self.is_temporary(table)? {
+            true => self.temp.set_privileges(table, grantee, privileges),
+            false => self.txn.set_privileges(table, grantee, privileges),
+        }
+    }
+}
+
+impl<T: Transaction> Sequences for SessionTransaction<T> {
+    fn next_value(&mut self, sequence: &str) -> Result<i64> {
+        if let Some(user) = self.user.as_deref() {
+            self.authorize_sequence_owner(user, sequence)?;
+        }
+        let value = self.txn.next_value(sequence)?;
+        self.current.lock()?.insert(sequence.to_string(), value);
+        Ok(value)
+    }
+
+    fn current_value(&self, sequence: &str) -> Result<i64> {
+        self.current.lock()?.get(sequence).copied().ok_or_else(|| {
+            Error::Value(format!("Sequence {} has no current value in this session", sequence))
+        })
+    }
+
+    fn set_value(&mut self, sequence: &str, value: i64) -> Result<()> {
+        if let Some(user) = self.user.as_deref() {
+            self.authorize_sequence_owner(user, sequence)?;
+        }
+        self.txn.set_value(sequence, value)
+    }
+}
+
+impl<T: Transaction> Transaction for SessionTransaction<T> {
+    fn version(&self) -> u64 {
+        self.txn.version()
+    }
+
+    fn read_only(&self) -> bool {
+        self.txn.read_only()
+    }
+
+    fn concurrent(&self) -> &HashSet<u64> {
+        self.txn.concurrent()
+    }
+
+    fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    fn commit(mut self) -> Result<()> {
+        if let Err(err) = self.check_deferred() {
+            self.rollback()?;
+            return Err(err);
+        }
+        if let Err(err) = self.txn.commit() {
+            self.temp.rollback()?;
+            return Err(err);
+        }
+        self.temp.commit()
+    }
+
+    fn rollback(self) -> Result<()> {
+        let result = self.txn.rollback();
+        self.temp.rollback()?;
+        result
+    }
+
+    fn savepoint(&mut self, id: u64) -> Result<()> {
+        self.txn.savepoint(id)?;
+        self.temp.savepoint(id)
+    }
+
+    fn rollback_to_savepoint(&mut self, id: u64) -> Result<()> {
+        self.txn.rollback_to_savepoint(id)?;
+        self.temp.rollback_to_savepoint(id)
+    }
+
+    fn release_savepoint(&mut self, id: u64) -> Result<()> {
+        self.txn.release_savepoint(id)?;
+        self.temp.release_savepoint(id)
+    }
+
+    fn create(&mut self, table: &str, row: Row) -> Result<()> {
+        self.defer(table, [&row])?;
+        match self.is_temporary(table)? {
+            true => self.temp.create(table, row),
+            false => self.txn.create(table, row),
+        }
+    }
+
+    fn create_batch(&mut self, table: &str, rows: Vec<Row>) -> Result<()> {
+        self.defer(table, &rows)?;
+        match self.is_temporary(table)? {
+            true => self.temp.create_batch(table, rows),
+            false => self.txn.create_batch(table, rows),
+        }
+    }
+
+    fn delete(&mut self, table: &str, id: &Value) -> Result<()> {
+        match self.is_temporary(table)? {
+            true => self.temp.delete(table, id),
+            false => self.txn.delete(table, id),
+        }
+    }
+
+    fn read(&self, table: &str, id: &Value) -> Result<Option<Row>> {
+        match self.is_temporary(table)? {
+            true => self.temp.read(table, id),
+            false => self.txn.read(table, id),
+        }
+    }
+
+    fn read_index(&self, table: &str, index: &str, values: &[Value]) -> Result<HashSet<Value>> {
+        match self.is_temporary(table)? {
+            true => self.temp.read_index(table, index, values),
+            false => self.txn.read_index(table, index, values),
+        }
+    }
+
+    fn read_index_range(
+        &self,
+        table: &str,
+        index: &str,
+        range: (Bound<Value>, Bound<Value>),
+    ) -> Result<Vec<Value>> {
+        match self.is_temporary(table)? {
+            true => self.temp.read_index_range(table, index, range),
+            false => self.txn.read_index_range(table, index, range),
+        }
+    }
+
+    fn read_index_entries(
+        &self,
+        table: &str,
+        index: &str,
+        range: IndexRange,
+        limit: Option<u64>,
+    ) -> Result<Vec<IndexEntry>> {
+        match self.is_temporary(table)? {
+            true => self.temp.read_index_entries(table, index, range, limit),
+            false => self.txn.read_index_entries(table, index, range, limit),
+        }
+    }
+
+    fn scan(
+        &self,
+        table: &str,
+        filter: Option<Expression>,
+        reverse: bool,
+        limit: Option<u64>,
+        columns: Option<Vec<usize>>,
+    ) -> Result<Scan> {
+        match self.is_temporary(table)? {
+            true => self.temp.scan(table, filter, reverse, limit, columns),
+            false => self.txn.scan(table, filter, reverse, limit, columns),
+        }
+    }
+
+    fn scan_parallel(
+        &self,
+        table: &str,
+        filter: Option<Expression>,
+        columns: Option<Vec<usize>>,
+        workers: usize,
+    ) -> Result<Scan> {
+        match self.is_temporary(table)? {
+            true => self.temp.scan_parallel(table, filter, columns, workers),
+            false => self.txn.scan_parallel(table, filter, columns, workers),
+        }
+    }
+
+    fn scan_index(&self, table: &str, index: &str) -> Result<IndexScan> {
+        match self.is_temporary(table)? {
+            true => self.temp.scan_index(table, index),
+            false => self.txn.scan_index(table, index),
+        }
+    }
+
+    fn update(&mut self, table: &str, id: &Value, row: Row) -> Result<()> {
+        self.defer(table, [&row])?;
+        match self.is_temporary(table)? {
+            true => self.temp.update(table, id, row),
+            false => self.txn.update(table, id, row),
+        }
+    }
+
+    fn update_batch(&mut self, table: &str, rows: Vec<(Value, Row)>) -> Result<()> {
+        self.defer(table, rows.iter().map(|(_, row)| row))?;
+        match self.is_temporary(table)? {
+            true => self.temp.update_batch(table, rows),
+            false => self.txn.update_batch(table, rows),
+        }
+    }
+
+    fn next_sequence(&mut self, table: &str, column: &str) -> Result<i64> {
+        match self.is_temporary(table)? {
+            true => self.temp.next_sequence(table, column),
+            false => self.txn.next_sequence(table, column),
+        }
+    }
+}
@@ -1,56 +1,100 @@
 use super::super::engine::Transaction;
-use super::super::plan::Aggregate;
-use super::super::types::{Column, Value};
+use super::super::plan::{Aggregate, Direction, NullOrder};
+use super::super::types::{decimal, Column, Row, Value};
+use super::memory::{value_size, Accountant, Reservation};
+use super::query::compare_sort_keys;
 use super::{Executor, ResultSet};
+use crate::encoding::bincode;
 use crate::error::{Error, Result};
 
+use rust_decimal::Decimal;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::hash_map::{self, DefaultHasher};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::hash::{Hash as _, Hasher as _};
+use std::io::{BufReader, BufWriter, Seek as _, SeekFrom};
 
-/// An aggregation executor
+/// The number of partitions that rows of groups exceeding the memory budget
+/// are spilled to.
+const PARTITIONS: usize = 16;
+
+/// The estimated memory usage of an accumulator in bytes. Accumulators which
+/// buffer values, e.g. for STRING_AGG, may use more. Those for DISTINCT
+/// aggregates report the size of their value sets, which counts towards the
+/// memory budget.
+const ACCUMULATOR_SIZE: usize = 64;
+
+/// An aggregation executor. For ROLLUP and CUBE, each row is aggregated once
+/// per grouping set, with the group columns not in the set replaced by NULL.
+///
+/// With a memory budget, once the groups exceed it, rows of new groups are
+/// spilled to partitions in temporary files by hash of their group key, while
+/// rows of existing groups are still aggregated in memory. Once the in-memory
+/// groups have been emitted, the partitions are aggregated one at a time in
+/// the same way (i.e. a hybrid hash aggregation). The in-memory groups are
+/// reserved with the query's memory accountant.
 pub struct Aggregation<T: Transaction> {
     source: Box<dyn Executor<T>>,
     aggregates: Vec<Aggregate>,
-    accumulators: HashMap<Vec<Value>, Vec<Box<dyn Accumulator>>>,
+    grouping_sets: Vec<Vec<usize>>,
+    memory: Option<usize>,
+    accountant: Accountant,
 }
 
+/// The accumulators of a group, one per aggregate.
+type Accumulators = Vec<Box<dyn Accumulator>>;
+
 impl<T: Transaction> Aggregation<T> {
-    pub fn new(source: Box<dyn Executor<T>>, aggregates: Vec<Aggregate>) -> Box<Self> {
-        Box::new(Self { source, aggregates, accumulators: HashMap::new() })
+    pub fn new(
+        source: Box<dyn Executor<T>>,
+        aggregates: Vec<Aggregate>,
+        grouping_sets: Vec<Vec<usize>>,
+        memory: Option<usize>,
+        accountant: Accountant,
+    ) -> Box<Self> {
+        Box::new(Self { source, aggregates, grouping_sets, memory, accountant })
     }
 }
 
 impl<T: Transaction> Executor<T> for Aggregation<T> {
-    #[allow(clippy::or_fun_call)]
     fn execute(mut self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
         let agg_count = self.aggregates.len();
+        // Each aggregate consumes its input columns in order, followed by the group columns.
+        let inputs = self.aggregates.iter().map(|a| a.inputs()).sum();
         match self.source.execute(txn)? {
             ResultSet::Query { columns, mut rows } => {
+                let groups = columns.len() - inputs;
+                if self.grouping_sets.is_empty() {
+                    self.grouping_sets = vec![(0..groups).collect()];
+                }
+                let mut table = Groups::new(self.aggregates, self.memory, &self.accountant, 0);
                 while let Some(mut row) = rows.next().transpose()? {
-                    self.accumulators
-                        .entry(row.split_off(self.aggregates.len()))
-                        .or_insert(self.aggregates.iter().map(<dyn Accumulator>::from).collect())
-                        .iter_mut()
-                        .zip(row)
-                        .try_for_each(|(acc, value)| acc.accumulate(&value))?
+                    let group = row.split_off(inputs);
+                    for (i, set) in self.grouping_sets.iter().enumerate() {
+                        let key = group
+                            .iter()
+                            .enumerate()
+                            .map(|(j, v)| if set.contains(&j) { v.clone() } else { Value::Null })
+                            .collect();
+                        table.add(i, key, &row)?;
+                    }
                 }
-                // If there were no rows and no group-by columns, return a row of empty accumulators:
-                // SELECT COUNT(*) FROM t WHERE FALSE
-                if self.accumulators.is_empty() && self.aggregates.len() == columns.len() {
-                    self.accumulators.insert(
-                        Vec::new(),
-                        self.aggregates.iter().map(<dyn Accumulator>::from).collect(),
-                    );
+                // If there were no rows, return a row of empty accumulators for each empty
+                // grouping set, e.g. for no group-by columns: SELECT COUNT(*) FROM t WHERE FALSE
+                if table.groups.is_empty() {
+                    for (i, set) in self.grouping_sets.iter().enumerate() {
+                        if set.is_empty() {
+                            table.insert(i, vec![Value::Null; groups])?;
+                        }
+                    }
                 }
                 Ok(ResultSet::Query {
-                    columns: columns
-                        .into_iter()
-                        .enumerate()
-                        .map(|(i, c)| if i < agg_count { Column { name: None } } else { c })
+                    columns: (0..agg_count)
+                        .map(|_| Column { name: None })
+                        .chain(columns.into_iter().skip(inputs))
                         .collect(),
-                    rows: Box::new(self.accumulators.into_iter().map(|(bucket, accs)| {
-                        Ok(accs.into_iter().map(|acc| acc.aggregate()).chain(bucket).collect())
-                    })),
+                    rows: Box::new(table.into_rows(self.accountant)?),
                 })
             }
             r => Err(Error::Internal(format!("Unexpected result {:?}", r))),
@@ -58,24 +102,260 @@ impl<T: Transaction> Executor<T> for Aggregation<T> {
     }
 }
 
+/// An aggregation hash table, keyed by grouping set index and group values,
+/// such that NULL markers don't collide with NULL group values.
+struct Groups {
+    aggregates: Vec<Aggregate>,
+    groups: HashMap<(usize, Vec<Value>), Accumulators>,
+    /// The estimated memory usage of the groups in bytes.
+    size: usize,
+    memory: Option<usize>,
+    /// The groups' reservation with the query's memory accountant.
+    reservation: Reservation,
+    /// The number of times the rows have been partitioned, used to vary the
+    /// partitioning hash when partitions are spilled again.
+    depth: usize,
+    /// Spilled partitions, empty until the memory budget is exceeded.
+    partitions: Vec<Partition>,
+}
+
+impl Groups {
+    fn new(
+        aggregates: Vec<Aggregate>,
+        memory: Option<usize>,
+        accountant: &Accountant,
+        depth: usize,
+    ) -> Self {
+        Self {
+            aggregates,
+            groups: HashMap::new(),
+            size: 0,
+            memory,
+            reservation: accountant.reserve(),
+            depth,
+            partitions: Vec::new(),
+        }
+    }
+
+    /// Estimates the memory usage of a group in bytes.
+    fn group_size(&self, key: &[Value]) -> usize {
+        key.iter().map(value_size).sum::<usize>() + self.aggregates.len() * ACCUMULATOR_SIZE
+    }
+
+    /// Inserts a group with empty accumulators.
+    fn insert(&mut self, set: usize, key: Vec<Value>) -> Result<()> {
+        self.size += self.group_size(&key);
+        self.reservation.resize(self.size)?;
+        let accumulators = self.aggregates.iter().map(<dyn Accumulator>::from).collect();
+        self.groups.insert((set, key), accumulators);
+        Ok(())
+    }
+
+    /// Aggregates a row's aggregate inputs into a group, or spills them to a
+    /// partition if the group is new and the memory budget is exceeded. The
+    /// first group is always kept in memory, so every pass makes progress.
+    fn add(&mut self, set: usize, key: Vec<Value>, row: &[Value]) -> Result<()> {
+        let group = (set, key);
+        if !self.groups.contains_key(&group) {
+            if self.memory.is_some_and(|memory| self.size > memory) {
+                return self.spill(&group, row);
+            }
+            self.size += self.group_size(&group.1);
+            self.reservation.resize(self.size)?;
+        }
+        let accumulators = self
+            .groups
+            .entry(group)
+            .or_insert_with(|| self.aggregates.iter().map(<dyn Accumulator>::from).collect());
+        let mut values = row.iter().cloned();
+        let mut grown = 0;
+        for (acc, aggregate) in accumulators.iter_mut().zip(&self.aggregates) {
+            let value = values.next().unwrap_or(Value::Null);
+            let keys = values.by_ref().take(aggregate.inputs() - 1).collect();
+            let size = acc.size();
+            acc.accumulate_sorted(&value, keys)?;
+            grown += acc.size() - size;
+        }
+        if grown > 0 {
+            self.size += grown;
+            self.reservation.resize(self.size)?;
+        }
+        Ok(())
+    }
+
+    /// Spills a row to a partition by hash of its group.
+    fn spill(&mut self, group: &(usize, Vec<Value>), row: &[Value]) -> Result<()> {
+        if self.partitions.is_empty() {
+            self.partitions = (0..PARTITIONS).map(|_| Partition::new()).collect::<Result<_>>()?;
+        }
+        let mut hasher = DefaultHasher::new();
+        (self.depth, group).hash(&mut hasher);
+        let partition = &mut self.partitions[hasher.finish() as usize % PARTITIONS];
+        bincode::serialize_into(&mut partition.0, &(group, row))
+    }
+
+    /// Emits the aggregated rows of the in-memory groups, followed by those of
+    /// the spilled partitions, which are reserved with the given accountant.
+    fn into_rows(self, accountant: Accountant) -> Result<Output> {
+        let Self { aggregates, groups, memory, reservation, depth, partitions, .. } = self;
+        let partitions = partitions
+            .into_iter()
+            .map(|p| Ok((p.into_reader()?, depth + 1)))
+            .collect::<Result<_>>()?;
+        Ok(Output {
+            aggregates,
+            memory,
+            accountant,
+            groups: groups.into_iter(),
+            reservation,
+            partitions,
+        })
+    }
+}
+
+/// A spilled partition of rows, as bincode-encoded (group, row) tuples in a
+/// temporary file, which is removed once closed.
+struct Partition(BufWriter<File>);
+
+impl Partition {
+    fn new() -> Result<Self> {
+        Ok(Self(BufWriter::new(tempfile::tempfile()?)))
+    }
+
+    /// Flushes the partition and returns a reader from the start of it.
+    fn into_reader(self) -> Result<BufReader<File>> {
+        let mut file = self.0.into_inner().map_err(|err| err.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(BufReader::new(file))
+    }
+}
+
+/// Emits aggregated rows from in-memory groups, and then aggregates and emits
+/// the spilled partitions one at a time.
+struct Output {
+    aggregates: Vec<Aggregate>,
+    memory: Option<usize>,
+    accountant: Accountant,
+    groups: hash_map::IntoIter<(usize, Vec<Value>), Accumulators>,
+    /// The reservation of the groups being emitted.
+    reservation: Reservation,
+    /// Spilled partitions and their partitioning depth.
+    partitions: Vec<(BufReader<File>, usize)>,
+}
+
+impl Output {
+    /// Fallible next(), emitting the next row, or None if exhausted.
+    fn try_next(&mut self) -> Result<Option<Row>> {
+        loop {
+            if let Some(((_, key), accs)) = self.groups.next() {
+                return Ok(Some(accs.into_iter().map(|acc| acc.aggregate()).chain(key).collect()));
+            }
+            let Some((mut reader, depth)) = self.partitions.pop() else {
+                return Ok(None);
+            };
+            // Release the emitted groups before aggregating the partition.
+            self.reservation.resize(0)?;
+            let mut table =
+                Groups::new(self.aggregates.clone(), self.memory, &self.accountant, depth);
+            while let Some(((set, key), row)) =
+                bincode::maybe_deserialize_from::<_, ((usize, Vec<Value>), Row)>(&mut reader)?
+            {
+                table.add(set, key, &row)?;
+            }
+            let output = table.into_rows(self.accountant.clone())?;
+            self.groups = output.groups;
+            self.reservation = output.reservation;
+            self.partitions.extend(output.partitions);
+        }
+    }
+}
+
+impl Iterator for Output {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.try_next().transpose()
+    }
+}
+
 // An accumulator
 pub trait Accumulator: std::fmt::Debug + Send {
     // Accumulates a value
     fn accumulate(&mut self, value: &Value) -> Result<()>;
 
+    // Accumulates a value along with its sort keys, which are only used by
+    // order-sensitive accumulators
+    fn accumulate_sorted(&mut self, value: &Value, _keys: Vec<Value>) -> Result<()> {
+        self.accumulate(value)
+    }
+
     // Calculates a final aggregate
     fn aggregate(&self) -> Value;
+
+    // Returns the estimated memory usage of values buffered by the accumulator
+    // in bytes, beyond ACCUMULATOR_SIZE, if tracked
+    fn size(&self) -> usize {
+        0
+    }
 }
 
 impl dyn Accumulator {
     fn from(aggregate: &Aggregate) -> Box<dyn Accumulator> {
         match aggregate {
+            Aggregate::ArrayAgg { order } => Box::new(ArrayAgg::new(order.clone())),
             Aggregate::Average => Box::new(Average::new()),
+            Aggregate::BoolAnd => Box::new(BoolAnd::new()),
+            Aggregate::BoolOr => Box::new(BoolOr::new()),
             Aggregate::Count => Box::new(Count::new()),
             Aggregate::Max => Box::new(Max::new()),
             Aggregate::Min => Box::new(Min::new()),
+            Aggregate::StdDev => Box::new(StdDev::new()),
+            Aggregate::StringAgg { separator, order } => {
+                Box::new(StringAgg::new(separator.clone(), order.clone()))
+            }
             Aggregate::Sum => Box::new(Sum::new()),
+            Aggregate::Variance => Box::new(Variance::new()),
+            Aggregate::Distinct(aggregate) => Box::new(Distinct::new(Self::from(aggregate))),
+        }
+    }
+}
+
+// Applies an inner accumulator to distinct values only, tracking the values
+// seen so far in a set. NULL is passed through once, like any other value,
+// such that the inner accumulator handles it as usual.
+#[derive(Debug)]
+pub struct Distinct {
+    inner: Box<dyn Accumulator>,
+    seen: HashSet<Value>,
+    size: usize,
+}
+
+impl Distinct {
+    pub fn new(inner: Box<dyn Accumulator>) -> Self {
+        Self { inner, seen: HashSet::new(), size: 0 }
+    }
+}
+
+impl Accumulator for Distinct {
+    fn accumulate(&mut self, value: &Value) -> Result<()> {
+        self.accumulate_sorted(value, Vec::new())
+    }
+
+    fn accumulate_sorted(&mut self, value: &Value, keys: Vec<Value>) -> Result<()> {
+        if self.seen.contains(value) {
+            return Ok(());
         }
+        self.size += value_size(value);
+        self.seen.insert(value.clone());
+        self.inner.accumulate_sorted(value, keys)
+    }
+
+    fn aggregate(&self) -> Value {
+        self.inner.aggregate()
+    }
+
+    fn size(&self) -> usize {
+        self.size + self.inner.size()
     }
 }
 
@@ -129,6 +409,7 @@ impl Accumulator for Average {
         match (self.sum.aggregate(), self.count.aggregate()) {
             (Value::Integer(s), Value::Integer(c)) => Value::Integer(s / c),
             (Value::Float(s), Value::Integer(c)) => Value::Float(s / c as f64),
+            (Value::Decimal(s), Value::Integer(c)) => Value::Decimal(s / Decimal::from(c)),
             _ => Value::Null,
         }
     }
@@ -221,8 +502,12 @@ impl Accumulator for Sum {
         self.sum = match (&self.sum, value) {
             (Some(Value::Integer(s)), Value::Integer(i)) => Some(Value::Integer(s + i)),
             (Some(Value::Float(s)), Value::Float(f)) => Some(Value::Float(s + f)),
+            (Some(Value::Decimal(s)), Value::Decimal(d)) => Some(Value::Decimal(
+                s.checked_add(*d).ok_or_else(|| Error::Value("Decimal overflow".into()))?,
+            )),
             (None, Value::Integer(i)) => Some(Value::Integer(*i)),
             (None, Value::Float(f)) => Some(Value::Float(*f)),
+            (None, Value::Decimal(d)) => Some(Value::Decimal(*d)),
             _ => Some(Value::Null),
         };
         Ok(())
@@ -235,3 +520,205 @@ impl Accumulator for Sum {
         }
     }
 }
+
+// Sample variance of non-null numeric values, or NULL for fewer than two
+// values. Uses Welford's online algorithm for numerical stability.
+#[derive(Debug)]
+pub struct Variance {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Variance {
+    pub fn new() -> Self {
+        Self { count: 0, mean: 0.0, m2: 0.0 }
+    }
+}
+
+impl Accumulator for Variance {
+    fn accumulate(&mut self, value: &Value) -> Result<()> {
+        let value = match value {
+            Value::Null => return Ok(()),
+            Value::Integer(i) => *i as f64,
+            Value::Float(f) => *f,
+            Value::Decimal(d) => decimal::to_float(*d),
+            value => return Err(Error::Value(format!("Can't take VARIANCE of {}", value))),
+        };
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        Ok(())
+    }
+
+    fn aggregate(&self) -> Value {
+        match self.count {
+            0 | 1 => Value::Null,
+            count => Value::Float(self.m2 / (count - 1) as f64),
+        }
+    }
+}
+
+// Sample standard deviation of non-null numeric values
+#[derive(Debug)]
+pub struct StdDev {
+    variance: Variance,
+}
+
+impl StdDev {
+    pub fn new() -> Self {
+        Self { variance: Variance::new() }
+    }
+}
+
+impl Accumulator for StdDev {
+    fn accumulate(&mut self, value: &Value) -> Result<()> {
+        self.variance
+            .accumulate(value)
+            .map_err(|_| Error::Value(format!("Can't take STDDEV of {}", value)))
+    }
+
+    fn aggregate(&self) -> Value {
+        match self.variance.aggregate() {
+            Value::Float(f) => Value::Float(f.sqrt()),
+            value => value,
+        }
+    }
+}
+
+// Logical AND of non-null boolean values
+#[derive(Debug)]
+pub struct BoolAnd {
+    result: Option<bool>,
+}
+
+impl BoolAnd {
+    pub fn new() -> Self {
+        Self { result: None }
+    }
+}
+
+impl Accumulator for BoolAnd {
+    fn accumulate(&mut self, value: &Value) -> Result<()> {
+        match value {
+            Value::Null => {}
+            Value::Boolean(b) => self.result = Some(self.result.unwrap_or(true) && *b),
+            value => return Err(Error::Value(format!("Can't take BOOL_AND of {}", value))),
+        }
+        Ok(())
+    }
+
+    fn aggregate(&self) -> Value {
+        self.result.map(Value::Boolean).unwrap_or(Value::Null)
+    }
+}
+
+// Logical OR of non-null boolean values
+#[derive(Debug)]
+pub struct BoolOr {
+    result: Option<bool>,
+}
+
+impl BoolOr {
+    pub fn new() -> Self {
+        Self { result: None }
+    }
+}
+
+impl Accumulator for BoolOr {
+    fn accumulate(&mut self, value: &Value) -> Result<()> {
+        match value {
+            Value::Null => {}
+            Value::Boolean(b) => self.result = Some(self.result.unwrap_or(false) || *b),
+            value => return Err(Error::Value(format!("Can't take BOOL_OR of {}", value))),
+        }
+        Ok(())
+    }
+
+    fn aggregate(&self) -> Value {
+        self.result.map(Value::Boolean).unwrap_or(Value::Null)
+    }
+}
+
+// Concatenation of non-null string values with a separator, in input order
+// or ordered by the given sort keys
+#[derive(Debug)]
+pub struct StringAgg {
+    separator: String,
+    order: Vec<(Direction, NullOrder)>,
+    values: Vec<(String, Vec<Value>)>,
+}
+
+impl StringAgg {
+    pub fn new(separator: String, order: Vec<(Direction, NullOrder)>) -> Self {
+        Self { separator, order, values: Vec::new() }
+    }
+}
+
+impl Accumulator for StringAgg {
+    fn accumulate(&mut self, value: &Value) -> Result<()> {
+        self.accumulate_sorted(value, Vec::new())
+    }
+
+    fn accumulate_sorted(&mut self, value: &Value, keys: Vec<Value>) -> Result<()> {
+        match value {
+            Value::Null => {}
+            Value::String(s) => self.values.push((s.clone(), keys)),
+            value => return Err(Error::Value(format!("Can't take STRING_AGG of {}", value))),
+        }
+        Ok(())
+    }
+
+    fn aggregate(&self) -> Value {
+        if self.values.is_empty() {
+            return Value::Null;
+        }
+        let mut values: Vec<&(String, Vec<Value>)> = self.values.iter().collect();
+        values.sort_by(|(_, a), (_, b)| {
+            compare_sort_keys(a, b, self.order.iter().map(|(d, n)| (d, n)))
+        });
+        Value::String(
+            values.into_iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>().join(&self.separator),
+        )
+    }
+}
+
+// Collection of values into an array, including NULLs, in input order or
+// ordered by the given sort keys
+#[derive(Debug)]
+pub struct ArrayAgg {
+    order: Vec<(Direction, NullOrder)>,
+    values: Vec<(Value, Vec<Value>)>,
+}
+
+impl ArrayAgg {
+    pub fn new(order: Vec<(Direction, NullOrder)>) -> Self {
+        Self { order, values: Vec::new() }
+    }
+}
+
+impl Accumulator for ArrayAgg {
+    fn accumulate(&mut self, value: &Value) -> Result<()> {
+        self.accumulate_sorted(value, Vec::new())
+    }
+
+    fn accumulate_sorted(&mut self, value: &Value, keys: Vec<Value>) -> Result<()> {
+        if let Value::Array(_) = value {
+            return Err(Error::Value("Nested arrays are not supported".into()));
+        }
+        self.values.push((value.clone(), keys));
+        Ok(())
+    }
+
+    fn aggregate(&self) -> Value {
+        if self.values.is_empty() {
+            return Value::Null;
+        }
+        let mut values: Vec<&(Value, Vec<Value>)> = self.values.iter().collect();
+        values.sort_by(|(_, a), (_, b)| {
+            compare_sort_keys(a, b, self.order.iter().map(|(d, n)| (d, n)))
+        });
+        Value::Array(values.into_iter().map(|(v, _)| v.clone()).collect())
+    }
+}
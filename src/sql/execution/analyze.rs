@@ -0,0 +1,94 @@
+use super::super::engine::Transaction;
+use super::super::types::{Row, Rows};
+use super::{Executor, ResultSet};
+use crate::error::Result;
+
+use serde_derive::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Runtime statistics for a plan node, as collected by EXPLAIN ANALYZE.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    /// The number of rows produced by the node, or affected by a mutation.
+    pub rows: u64,
+    /// The number of times the node was executed.
+    pub loops: u64,
+    /// The wall time spent in the node, including its children.
+    pub time: Duration,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rows={} loops={} time={:.3}ms",
+            self.rows,
+            self.loops,
+            self.time.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// An executor which records runtime statistics for an inner executor,
+/// including the time spent fetching its rows.
+pub struct Analyze<T: Transaction> {
+    executor: Box<dyn Executor<T>>,
+    stats: Arc<Mutex<Stats>>,
+}
+
+impl<T: Transaction> Analyze<T> {
+    pub fn new(executor: Box<dyn Executor<T>>, stats: Arc<Mutex<Stats>>) -> Box<Self> {
+        Box::new(Self { executor, stats })
+    }
+}
+
+impl<T: Transaction> Executor<T> for Analyze<T> {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let start = Instant::now();
+        let result = self.executor.execute(txn);
+        let mut stats = self.stats.lock()?;
+        stats.loops += 1;
+        stats.time += start.elapsed();
+        match result? {
+            ResultSet::Query { columns, rows } => {
+                drop(stats);
+                let stats = self.stats;
+                Ok(ResultSet::Query { columns, rows: Box::new(AnalyzeRows { rows, stats }) })
+            }
+            result @ (ResultSet::Create { count }
+            | ResultSet::Delete { count }
+            | ResultSet::Update { count }) => {
+                stats.rows += count;
+                Ok(result)
+            }
+            result => Ok(result),
+        }
+    }
+}
+
+/// A row iterator which records the number of rows fetched and the time spent
+/// fetching them.
+struct AnalyzeRows {
+    rows: Rows,
+    stats: Arc<Mutex<Stats>>,
+}
+
+impl Iterator for AnalyzeRows {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = Instant::now();
+        let row = self.rows.next();
+        let mut stats = match self.stats.lock() {
+            Ok(stats) => stats,
+            Err(err) => return Some(Err(err.into())),
+        };
+        stats.time += start.elapsed();
+        if let Some(Ok(_)) = row {
+            stats.rows += 1;
+        }
+        row
+    }
+}
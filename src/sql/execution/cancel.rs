@@ -0,0 +1,84 @@
+use super::super::engine::Transaction;
+use super::super::types::{Row, Rows};
+use super::{Executor, ResultSet};
+use crate::error::{Error, Result};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A cancellation token for a running query, shared by its executors. Once
+/// cancelled or past its deadline, executors error at their next row.
+#[derive(Clone, Debug, Default)]
+pub struct Cancel {
+    cancelled: Arc<AtomicBool>,
+    /// The statement deadline, if any, after which the query times out.
+    deadline: Option<Instant>,
+}
+
+impl Cancel {
+    /// Sets the statement deadline, if any. The returned token still shares
+    /// the cancellation state.
+    pub fn with_deadline(self, deadline: Option<Instant>) -> Self {
+        Self { deadline, ..self }
+    }
+
+    /// Cancels the query.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed)
+    }
+
+    /// Errors if the query has been cancelled or its deadline has passed.
+    pub fn check(&self) -> Result<()> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(Error::Value("Query cancelled".into()));
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() > deadline) {
+            return Err(Error::Timeout);
+        }
+        Ok(())
+    }
+}
+
+/// An executor which checks a cancellation token before executing an inner
+/// executor and before fetching each of its rows.
+pub struct Cancellable<T: Transaction> {
+    executor: Box<dyn Executor<T>>,
+    cancel: Cancel,
+}
+
+impl<T: Transaction> Cancellable<T> {
+    pub fn new(executor: Box<dyn Executor<T>>, cancel: Cancel) -> Box<Self> {
+        Box::new(Self { executor, cancel })
+    }
+}
+
+impl<T: Transaction> Executor<T> for Cancellable<T> {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        self.cancel.check()?;
+        match self.executor.execute(txn)? {
+            ResultSet::Query { columns, rows } => {
+                let cancel = self.cancel;
+                Ok(ResultSet::Query { columns, rows: Box::new(CancellableRows { rows, cancel }) })
+            }
+            result => Ok(result),
+        }
+    }
+}
+
+/// A row iterator which errors once its query has been cancelled.
+struct CancellableRows {
+    rows: Rows,
+    cancel: Cancel,
+}
+
+impl Iterator for CancellableRows {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(err) = self.cancel.check() {
+            return Some(Err(err));
+        }
+        self.rows.next()
+    }
+}
@@ -0,0 +1,136 @@
+use super::super::engine::Transaction;
+use super::super::parser::ast::{CopyFormat, CopyOptions};
+use super::super::types::{json, Row, Value};
+use super::mutation::Insert;
+use super::{Cancel, ResultSet, BATCH_SIZE};
+use crate::error::{Error, Result};
+
+use std::io::{Read, Write};
+
+/// Bulk-loads CSV rows from a reader into a table, for COPY FROM. Fields are
+/// cast to the column datatypes, and fields equal to the NULL option are NULL.
+/// Omitted columns are filled with default values, like for INSERT, and
+/// generated columns are computed rather than read. Rows are
+/// written in batches, checking the cancellation token before each batch.
+pub fn copy_from(
+    txn: &mut impl Transaction,
+    table: &str,
+    columns: Option<Vec<String>>,
+    input: impl Read,
+    options: &CopyOptions,
+    cancel: &Cancel,
+) -> Result<ResultSet> {
+    let table = txn.must_read_table(table)?;
+    let columns = match columns {
+        Some(columns) => columns,
+        None => {
+            table.columns.iter().filter(|c| c.generated.is_none()).map(|c| c.name.clone()).collect()
+        }
+    };
+    let datatypes = columns
+        .iter()
+        .map(|c| Ok(table.get_column(c)?.datatype.clone()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(options.header)
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .from_reader(input);
+    let mut count = 0;
+    let mut batch: Vec<Row> = Vec::with_capacity(BATCH_SIZE);
+    for record in reader.records() {
+        let record = record.map_err(|err| match err.kind() {
+            csv::ErrorKind::Io(err) => Error::Value(format!("Failed to read COPY input: {}", err)),
+            _ => Error::Value(format!("Invalid CSV input: {}", err)),
+        })?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        if record.len() != columns.len() {
+            return Err(Error::Value(format!(
+                "Line {}: expected {} fields, got {}",
+                line,
+                columns.len(),
+                record.len()
+            )));
+        }
+        let values = record
+            .iter()
+            .zip(&datatypes)
+            .map(|(field, datatype)| match field {
+                f if f == options.null => Ok(Value::Null),
+                f => Value::String(f.to_string()).cast(datatype),
+            })
+            .collect::<Result<Vec<_>>>()
+            .map_err(|err| Error::Value(format!("Line {}: {}", line, err)))?;
+        batch.push(Insert::make_row(txn, &table, &columns, values)?);
+        if batch.len() >= BATCH_SIZE {
+            cancel.check()?;
+            count += batch.len() as u64;
+            txn.create_batch(&table.name, std::mem::take(&mut batch))?;
+        }
+    }
+    if !batch.is_empty() {
+        cancel.check()?;
+        count += batch.len() as u64;
+        txn.create_batch(&table.name, batch)?;
+    }
+    Ok(ResultSet::Copy { count })
+}
+
+/// Exports the rows of a query result to a writer, for COPY TO. Rows are
+/// written as they're emitted, without buffering the full result. In CSV
+/// format, NULL is written as the NULL option and other values as their string
+/// representation, such that they can be loaded again via COPY FROM.
+pub fn copy_to(result: ResultSet, output: impl Write, options: &CopyOptions) -> Result<ResultSet> {
+    let ResultSet::Query { columns, rows } = result else {
+        return Err(Error::Internal(format!("Unexpected result {:?}", result)));
+    };
+    let names: Vec<String> =
+        columns.into_iter().map(|c| c.name.unwrap_or_else(|| "?".into())).collect();
+    let write_err =
+        |err: &dyn std::fmt::Display| Error::Value(format!("Failed to write COPY output: {}", err));
+    let mut count = 0;
+    match options.format {
+        CopyFormat::Csv => {
+            let mut writer =
+                csv::WriterBuilder::new().delimiter(options.delimiter).from_writer(output);
+            if options.header {
+                writer.write_record(&names).map_err(|err| write_err(&err))?;
+            }
+            for row in rows {
+                let fields = row?.into_iter().map(|value| match value {
+                    Value::Null => options.null.clone(),
+                    value => value.to_string(),
+                });
+                writer.write_record(fields).map_err(|err| write_err(&err))?;
+                count += 1;
+            }
+            writer.flush().map_err(|err| write_err(&err))?;
+        }
+        // Objects are written by hand to keep the keys in column order.
+        CopyFormat::Json => {
+            let mut writer = std::io::BufWriter::new(output);
+            let keys = names
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|err| write_err(&err))?;
+            for row in rows {
+                let mut line = String::from("{");
+                for (i, (key, value)) in keys.iter().zip(row?).enumerate() {
+                    if i > 0 {
+                        line.push(',');
+                    }
+                    line.push_str(key);
+                    line.push(':');
+                    line.push_str(&json::to_json(value)?.to_string());
+                }
+                line.push('}');
+                writeln!(writer, "{}", line).map_err(|err| write_err(&err))?;
+                count += 1;
+            }
+            writer.flush().map_err(|err| write_err(&err))?;
+        }
+    }
+    Ok(ResultSet::Copy { count })
+}
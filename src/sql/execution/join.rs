@@ -1,9 +1,13 @@
 use super::super::engine::Transaction;
-use super::super::types::{Expression, Rows};
-use super::{Executor, ResultSet, Row, Value};
+use super::super::plan::Node;
+use super::super::types::{Columns, Compiled, Expression, Rows};
+use super::memory::{row_size, value_size, Accountant, Reservation};
+use super::{BuildContext, Cancel, Executor, ResultSet, Row, Stats, Value};
 use crate::error::{Error, Result};
 
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
 
 /// A nested loop join executor, which checks each row in the left source against every row in
 /// the right source using the given predicate.
@@ -39,7 +43,7 @@ impl<T: Transaction> Executor<T> for NestedLoopJoin<T> {
                         rows,
                         rrows.collect::<Result<Vec<_>>>()?,
                         right_width,
-                        self.predicate,
+                        self.predicate.as_ref().map(Expression::compile).transpose()?,
                         self.outer,
                     )),
                     columns,
@@ -57,7 +61,7 @@ struct NestedLoopRows {
     right_vec: Vec<Row>,
     right_empty: Vec<Value>,
     right_hit: bool,
-    predicate: Option<Expression>,
+    predicate: Option<Compiled>,
     outer: bool,
 }
 
@@ -66,7 +70,7 @@ impl NestedLoopRows {
         mut left: Rows,
         right: Vec<Row>,
         right_width: usize,
-        predicate: Option<Expression>,
+        predicate: Option<Compiled>,
         outer: bool,
     ) -> Self {
         Self {
@@ -74,7 +78,7 @@ impl NestedLoopRows {
             left,
             right: Box::new(right.clone().into_iter()),
             right_vec: right,
-            right_empty: std::iter::repeat(Value::Null).take(right_width).collect(),
+            right_empty: std::iter::repeat_n(Value::Null, right_width).collect(),
             right_hit: false,
             predicate,
             outer,
@@ -113,7 +117,7 @@ impl NestedLoopRows {
             let mut row = left_row.to_vec();
             row.extend(right_row);
             if let Some(predicate) = &self.predicate {
-                match predicate.evaluate(Some(&row))? {
+                match predicate.evaluate(&row)? {
                     Value::Boolean(true) => return Ok(Some(row)),
                     Value::Boolean(false) => {}
                     Value::Null => {}
@@ -140,15 +144,137 @@ impl Iterator for NestedLoopRows {
     }
 }
 
-/// A hash join executor
+/// A lateral join executor, which executes the right node once for each left row, with the
+/// row's values bound to the node's outer field references. The joined rows are buffered, since
+/// the right node must be executed with the transaction, and are reserved with the query's
+/// memory accountant.
+pub struct LateralJoin<T: Transaction> {
+    left: Box<dyn Executor<T>>,
+    right: Node,
+    /// Runtime statistics for the right node and its children, in pre-order,
+    /// accumulated across executions. Only used for EXPLAIN ANALYZE.
+    right_stats: Option<Vec<Arc<Mutex<Stats>>>>,
+    predicate: Option<Expression>,
+    outer: bool,
+    cancel: Option<Cancel>,
+    accountant: Accountant,
+}
+
+impl<T: Transaction + 'static> LateralJoin<T> {
+    pub fn new(
+        left: Box<dyn Executor<T>>,
+        right: Node,
+        right_stats: Option<Vec<Arc<Mutex<Stats>>>>,
+        predicate: Option<Expression>,
+        outer: bool,
+        cancel: Option<Cancel>,
+        accountant: Accountant,
+    ) -> Box<Self> {
+        Box::new(Self { left, right, right_stats, predicate, outer, cancel, accountant })
+    }
+
+    /// Executes the right node for a left row, returning its columns and rows.
+    fn execute_right(&self, txn: &mut T, row: &[Value]) -> Result<(Columns, Vec<Row>)> {
+        let mut stats = Vec::new();
+        let mut ctx = BuildContext {
+            stats: self.right_stats.as_ref().map(|_| &mut stats),
+            cancel: self.cancel.clone(),
+            accountant: self.accountant.clone(),
+            ..Default::default()
+        };
+        let right = self.right.clone().bind_outer(row)?;
+        let ResultSet::Query { columns, rows } =
+            <dyn Executor<T>>::build_with(right, &mut ctx).execute(txn)?
+        else {
+            return Err(Error::Internal("Unexpected result set".into()));
+        };
+        let rows = rows.collect::<Result<Vec<_>>>()?;
+        for (total, stats) in self.right_stats.iter().flatten().zip(stats) {
+            let (mut total, stats) = (total.lock()?, stats.lock()?);
+            total.rows += stats.rows;
+            total.loops += stats.loops;
+            total.time += stats.time;
+        }
+        Ok((columns, rows))
+    }
+}
+
+impl<T: Transaction + 'static> Executor<T> for LateralJoin<T> {
+    fn execute(mut self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let left = std::mem::replace(&mut self.left, super::Nothing::new());
+        let ResultSet::Query { mut columns, rows: lrows } = left.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result set".into()));
+        };
+        let predicate = self.predicate.as_ref().map(Expression::compile).transpose()?;
+        let mut reservation = self.accountant.reserve();
+        let mut size = 0;
+        let mut rows = Vec::new();
+        let mut right_columns = None;
+        for left_row in lrows {
+            let left_row = left_row?;
+            let (rcolumns, rrows) = self.execute_right(txn, &left_row)?;
+            let right_width = rcolumns.len();
+            right_columns.get_or_insert(rcolumns);
+            let mut hit = false;
+            for right_row in rrows {
+                let mut row = left_row.clone();
+                row.extend(right_row);
+                if let Some(predicate) = &predicate {
+                    match predicate.evaluate(&row)? {
+                        Value::Boolean(true) => {}
+                        Value::Boolean(false) | Value::Null => continue,
+                        value => {
+                            return Err(Error::Value(format!(
+                                "Join predicate returned {}, expected boolean",
+                                value
+                            )))
+                        }
+                    }
+                }
+                size += row_size(&row);
+                reservation.resize(size)?;
+                rows.push(row);
+                hit = true;
+            }
+            if self.outer && !hit {
+                let mut row = left_row;
+                row.extend(std::iter::repeat_n(Value::Null, right_width));
+                size += row_size(&row);
+                reservation.resize(size)?;
+                rows.push(row);
+            }
+        }
+        // Without any left rows, the right columns are found by executing the
+        // right node with NULL outer fields.
+        let right_columns = match right_columns {
+            Some(right_columns) => right_columns,
+            None => self.execute_right(txn, &[])?.0,
+        };
+        columns.extend(right_columns);
+        Ok(ResultSet::Query {
+            columns,
+            rows: reservation.attach(Box::new(rows.into_iter().map(Ok))),
+        })
+    }
+}
+
+/// A hash join executor, which builds a hash table from the rows of one source
+/// keyed by the join field, and probes it with the rows of the other. The right
+/// source is used as the hash table, except for inner joins where the left
+/// source turns out to have fewer rows. NULL join values never match. The
+/// buffered rows are reserved with the query's memory accountant.
 pub struct HashJoin<T: Transaction> {
     left: Box<dyn Executor<T>>,
     left_field: usize,
     right: Box<dyn Executor<T>>,
     right_field: usize,
     outer: bool,
+    accountant: Accountant,
 }
 
+/// A hash join table, with rows grouped by join value since values may be duplicated.
+type HashTable = HashMap<Value, Vec<Row>>;
+
 impl<T: Transaction> HashJoin<T> {
     pub fn new(
         left: Box<dyn Executor<T>>,
@@ -156,47 +282,388 @@ impl<T: Transaction> HashJoin<T> {
         right: Box<dyn Executor<T>>,
         right_field: usize,
         outer: bool,
+        accountant: Accountant,
     ) -> Box<Self> {
-        Box::new(Self { left, left_field, right, right_field, outer })
+        Box::new(Self { left, left_field, right, right_field, outer, accountant })
+    }
+
+    /// Builds a hash table from the given rows, keyed by the given field.
+    fn build(rows: Vec<Row>, field: usize, side: &str) -> Result<HashTable> {
+        let mut table = HashTable::new();
+        for row in rows {
+            match row.get(field) {
+                Some(Value::Null) => {}
+                Some(value) => table.entry(value.clone()).or_default().push(row),
+                None => {
+                    return Err(Error::Internal(format!("{} index {} out of bounds", side, field)))
+                }
+            }
+        }
+        Ok(table)
     }
 }
 
 impl<T: Transaction> Executor<T> for HashJoin<T> {
     fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
-        if let ResultSet::Query { mut columns, rows } = self.left.execute(txn)? {
-            if let ResultSet::Query { columns: rcolumns, rows: rrows } = self.right.execute(txn)? {
-                let (l, r, outer) = (self.left_field, self.right_field, self.outer);
-                let right: HashMap<Value, Row> = rrows
-                    .map(|res| match res {
-                        Ok(row) if row.len() <= r => {
-                            Err(Error::Internal(format!("Right index {} out of bounds", r)))
-                        }
-                        Ok(row) => Ok((row[r].clone(), row)),
-                        Err(err) => Err(err),
-                    })
-                    .collect::<Result<_>>()?;
-                let empty = std::iter::repeat(Value::Null).take(rcolumns.len());
-                columns.extend(rcolumns);
-                let rows = Box::new(rows.filter_map(move |res| match res {
-                    Ok(row) if row.len() <= l => {
-                        Some(Err(Error::Value(format!("Left index {} out of bounds", l))))
+        let ResultSet::Query { mut columns, rows: mut lrows } = self.left.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result set".into()));
+        };
+        let ResultSet::Query { columns: rcolumns, rows: rrows } = self.right.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result set".into()));
+        };
+        let (l, r, outer) = (self.left_field, self.right_field, self.outer);
+        let mut reservation = self.accountant.reserve();
+        let mut size = 0;
+        let mut right = Vec::new();
+        for row in rrows {
+            let row = row?;
+            size += row_size(&row);
+            reservation.resize(size)?;
+            right.push(row);
+        }
+        let width = rcolumns.len();
+        columns.extend(rcolumns);
+
+        // For inner joins, read up to as many left rows as there are right rows. If the left
+        // source runs out first, build the hash table from it instead and probe it with the
+        // right rows.
+        let mut buffered = Vec::new();
+        if !outer {
+            while buffered.len() < right.len() {
+                match lrows.next().transpose()? {
+                    Some(row) => {
+                        size += row_size(&row);
+                        reservation.resize(size)?;
+                        buffered.push(row)
                     }
-                    Ok(mut row) => match right.get(&row[l]) {
-                        Some(hit) => {
-                            row.extend(hit.clone());
-                            Some(Ok(row))
-                        }
-                        None if outer => {
-                            row.extend(empty.clone());
-                            Some(Ok(row))
-                        }
-                        None => None,
-                    },
-                    Err(err) => Some(Err(err)),
-                }));
-                return Ok(ResultSet::Query { columns, rows });
+                    None => {
+                        let left = Self::build(buffered, l, "Left")?;
+                        let rows = right.into_iter().flat_map(move |row| {
+                            let hits = row.get(r).and_then(|v| left.get(v));
+                            let hits = hits.map(Vec::as_slice).unwrap_or_default();
+                            hits.iter()
+                                .map(|hit| Ok(hit.iter().chain(&row).cloned().collect()))
+                                .collect::<Vec<_>>()
+                        });
+                        let rows = reservation.attach(Box::new(rows));
+                        return Ok(ResultSet::Query { columns, rows });
+                    }
+                }
             }
         }
-        Err(Error::Internal("Unexpected result set".into()))
+
+        let right = Self::build(right, r, "Right")?;
+        let empty: Row = std::iter::repeat_n(Value::Null, width).collect();
+        let rows = buffered.into_iter().map(Ok).chain(lrows).flat_map(move |res| {
+            let result: Vec<Result<Row>> = match res {
+                Ok(row) if row.len() <= l => {
+                    vec![Err(Error::Value(format!("Left index {} out of bounds", l)))]
+                }
+                Ok(row) => match right.get(&row[l]) {
+                    Some(hits) => hits
+                        .iter()
+                        .map(|hit| Ok(row.iter().chain(hit).cloned().collect()))
+                        .collect(),
+                    None if outer => vec![Ok(row.into_iter().chain(empty.clone()).collect())],
+                    None => Vec::new(),
+                },
+                Err(err) => vec![Err(err)],
+            };
+            result
+        });
+        Ok(ResultSet::Query { columns, rows: reservation.attach(Box::new(rows)) })
     }
 }
+
+/// A hash semi join executor, which emits the left rows that have a matching right row, or for
+/// anti joins the left rows that don't. The right rows contain the join keys, and are probed with
+/// the key expressions evaluated on each left row. NULL keys never match. Null-aware anti joins
+/// implement NOT IN, where the last key is the IN value: a left row whose other keys match is not
+/// emitted if its value is NULL, or if any of the matching right rows' values are NULL. The hash
+/// table is reserved with the query's memory accountant.
+pub struct HashSemiJoin<T: Transaction> {
+    left: Box<dyn Executor<T>>,
+    keys: Vec<Expression>,
+    right: Box<dyn Executor<T>>,
+    anti: bool,
+    null_aware: bool,
+    accountant: Accountant,
+}
+
+/// A semi join hash table, keyed by the join keys (except the value for null-aware joins),
+/// with the set of values and whether any of them are NULL.
+type SemiJoinTable = HashMap<Vec<Value>, (HashSet<Value>, bool)>;
+
+impl<T: Transaction> HashSemiJoin<T> {
+    pub fn new(
+        left: Box<dyn Executor<T>>,
+        keys: Vec<Expression>,
+        right: Box<dyn Executor<T>>,
+        anti: bool,
+        null_aware: bool,
+        accountant: Accountant,
+    ) -> Box<Self> {
+        Box::new(Self { left, keys, right, anti, null_aware, accountant })
+    }
+
+    /// Builds a hash table from the right rows, reserving the memory of its keys and values.
+    fn build(
+        rows: Rows,
+        keys: usize,
+        null_aware: bool,
+        reservation: &mut Reservation,
+    ) -> Result<SemiJoinTable> {
+        let mut table = SemiJoinTable::new();
+        let mut size = 0;
+        for row in rows {
+            let mut row = row?;
+            if row.len() != keys {
+                return Err(Error::Internal(format!(
+                    "Expected {} semi join keys, found {}",
+                    keys,
+                    row.len()
+                )));
+            }
+            let value = if null_aware { row.pop() } else { None };
+            if row.iter().any(|v| matches!(v, Value::Null)) {
+                continue;
+            }
+            if !table.contains_key(&row) {
+                size += row_size(&row);
+            }
+            let (values, null) = table.entry(row).or_default();
+            match value {
+                Some(Value::Null) => *null = true,
+                Some(value) if !values.contains(&value) => {
+                    size += value_size(&value);
+                    values.insert(value);
+                }
+                Some(_) | None => {}
+            }
+            reservation.resize(size)?;
+        }
+        Ok(table)
+    }
+
+    /// Returns true if the left row matches the hash table. For null-aware joins, a NULL
+    /// comparison is considered a match, since NOT IN then yields NULL rather than TRUE.
+    fn probe(
+        table: &SemiJoinTable,
+        keys: &[Compiled],
+        null_aware: bool,
+        row: &Row,
+    ) -> Result<bool> {
+        let mut key: Vec<Value> = keys.iter().map(|k| k.evaluate(row)).collect::<Result<_>>()?;
+        let value = if null_aware { key.pop() } else { None };
+        if key.iter().any(|v| matches!(v, Value::Null)) {
+            return Ok(false);
+        }
+        let Some((values, null)) = table.get(&key) else {
+            return Ok(false);
+        };
+        Ok(match value {
+            Some(Value::Null) => true,
+            Some(value) => *null || values.contains(&value),
+            None => true,
+        })
+    }
+}
+
+impl<T: Transaction> Executor<T> for HashSemiJoin<T> {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let ResultSet::Query { columns, rows } = self.left.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result set".into()));
+        };
+        let ResultSet::Query { rows: right, .. } = self.right.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result set".into()));
+        };
+        let keys = self.keys.iter().map(Expression::compile).collect::<Result<Vec<_>>>()?;
+        let (anti, null_aware) = (self.anti, self.null_aware);
+        let mut reservation = self.accountant.reserve();
+        let table = Self::build(right, keys.len(), null_aware, &mut reservation)?;
+        let rows = rows.filter_map(move |res| {
+            res.and_then(|row| {
+                let matched = Self::probe(&table, &keys, null_aware, &row)?;
+                Ok((matched != anti).then_some(row))
+            })
+            .transpose()
+        });
+        Ok(ResultSet::Query { columns, rows: reservation.attach(Box::new(rows)) })
+    }
+}
+
+/// A merge join executor, which joins two sources that are both sorted in ascending order by
+/// their join field. Both sources are streamed, only buffering the right rows that share the
+/// current join value. NULL join values never match, and may appear anywhere in the sources.
+pub struct MergeJoin<T: Transaction> {
+    left: Box<dyn Executor<T>>,
+    left_field: usize,
+    right: Box<dyn Executor<T>>,
+    right_field: usize,
+    outer: bool,
+}
+
+impl<T: Transaction> MergeJoin<T> {
+    pub fn new(
+        left: Box<dyn Executor<T>>,
+        left_field: usize,
+        right: Box<dyn Executor<T>>,
+        right_field: usize,
+        outer: bool,
+    ) -> Box<Self> {
+        Box::new(Self { left, left_field, right, right_field, outer })
+    }
+}
+
+impl<T: Transaction> Executor<T> for MergeJoin<T> {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let ResultSet::Query { mut columns, rows: lrows } = self.left.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result set".into()));
+        };
+        let ResultSet::Query { columns: rcolumns, rows: rrows } = self.right.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result set".into()));
+        };
+        let right_width = rcolumns.len();
+        columns.extend(rcolumns);
+        let rows = MergeJoinRows::new(
+            lrows,
+            self.left_field,
+            rrows,
+            self.right_field,
+            right_width,
+            self.outer,
+        )?;
+        Ok(ResultSet::Query { columns, rows: Box::new(rows) })
+    }
+}
+
+struct MergeJoinRows {
+    left: Rows,
+    left_field: usize,
+    left_last: Option<Value>,
+    right: Rows,
+    right_field: usize,
+    right_last: Option<Value>,
+    right_next: Option<Row>,
+    right_empty: Row,
+    group: Vec<Row>,
+    group_value: Option<Value>,
+    pending: VecDeque<Row>,
+    outer: bool,
+}
+
+impl MergeJoinRows {
+    fn new(
+        left: Rows,
+        left_field: usize,
+        right: Rows,
+        right_field: usize,
+        right_width: usize,
+        outer: bool,
+    ) -> Result<Self> {
+        let mut rows = Self {
+            left,
+            left_field,
+            left_last: None,
+            right,
+            right_field,
+            right_last: None,
+            right_next: None,
+            right_empty: std::iter::repeat_n(Value::Null, right_width).collect(),
+            group: Vec::new(),
+            group_value: None,
+            pending: VecDeque::new(),
+            outer,
+        };
+        rows.right_next = rows.next_right()?;
+        Ok(rows)
+    }
+
+    /// Returns the join value of a row, erroring if the field is out of bounds.
+    fn value<'a>(row: &'a Row, field: usize, side: &str) -> Result<&'a Value> {
+        row.get(field)
+            .ok_or_else(|| Error::Internal(format!("{} index {} out of bounds", side, field)))
+    }
+
+    /// Checks that a join value is not below the previous one of its source.
+    fn check_sorted(value: &Value, last: &mut Option<Value>, side: &str) -> Result<()> {
+        if let Some(last) = last {
+            if compare(value, last)? == Ordering::Less {
+                return Err(Error::Internal(format!("{} merge join input is not sorted", side)));
+            }
+        }
+        *last = Some(value.clone());
+        Ok(())
+    }
+
+    /// Reads the next right row with a non-NULL join value.
+    fn next_right(&mut self) -> Result<Option<Row>> {
+        while let Some(row) = self.right.next().transpose()? {
+            let value = Self::value(&row, self.right_field, "Right")?;
+            if *value == Value::Null {
+                continue;
+            }
+            Self::check_sorted(value, &mut self.right_last, "Right")?;
+            return Ok(Some(row));
+        }
+        Ok(None)
+    }
+
+    /// Advances the right source to the given join value, buffering the matching right rows.
+    fn load_group(&mut self, value: &Value) -> Result<()> {
+        self.group.clear();
+        self.group_value = Some(value.clone());
+        while let Some(row) = self.right_next.take() {
+            match compare(&row[self.right_field], value)? {
+                Ordering::Less => {}
+                Ordering::Equal => self.group.push(row),
+                Ordering::Greater => {
+                    self.right_next = Some(row);
+                    break;
+                }
+            }
+            self.right_next = self.next_right()?;
+        }
+        Ok(())
+    }
+
+    // Tries to get the next joined row, with error handling.
+    fn try_next(&mut self) -> Result<Option<Row>> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Ok(Some(row));
+            }
+            let Some(left_row) = self.left.next().transpose()? else {
+                return Ok(None);
+            };
+            let value = Self::value(&left_row, self.left_field, "Left")?.clone();
+            let hit = value != Value::Null && {
+                Self::check_sorted(&value, &mut self.left_last, "Left")?;
+                if self.group_value.as_ref() != Some(&value) {
+                    self.load_group(&value)?;
+                }
+                !self.group.is_empty()
+            };
+            if hit {
+                let rows =
+                    self.group.iter().map(|hit| left_row.iter().chain(hit).cloned().collect());
+                self.pending.extend(rows);
+            } else if self.outer {
+                return Ok(Some(left_row.into_iter().chain(self.right_empty.clone()).collect()));
+            }
+        }
+    }
+}
+
+impl Iterator for MergeJoinRows {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.try_next().transpose()
+    }
+}
+
+/// Compares two join values, erroring if they can't be compared.
+fn compare(a: &Value, b: &Value) -> Result<Ordering> {
+    a.partial_cmp(b).ok_or_else(|| Error::Internal(format!("Can't compare {} and {}", a, b)))
+}
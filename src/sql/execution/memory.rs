@@ -0,0 +1,100 @@
+use super::super::types::{Row, Rows, Value};
+use crate::error::{Error, Result};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A memory accountant for a running query, shared by its executors. The
+/// executors which buffer rows (i.e. sorts, hash joins and aggregations)
+/// reserve their estimated memory usage with it, and error once the query's
+/// total usage would exceed its limit.
+#[derive(Clone, Debug, Default)]
+pub struct Accountant {
+    /// The query's reserved memory in bytes, across all executors.
+    used: Arc<AtomicUsize>,
+    /// The query's memory limit in bytes, if any.
+    limit: Option<usize>,
+}
+
+impl Accountant {
+    /// Creates an accountant with the given memory limit in bytes, if any.
+    pub fn new(limit: Option<usize>) -> Self {
+        Self { used: Arc::default(), limit }
+    }
+
+    /// Returns a new, empty reservation.
+    pub fn reserve(&self) -> Reservation {
+        Reservation { accountant: self.clone(), size: 0 }
+    }
+}
+
+/// An executor's memory reservation, which is released when dropped.
+#[derive(Debug)]
+pub struct Reservation {
+    accountant: Accountant,
+    size: usize,
+}
+
+impl Reservation {
+    /// Resizes the reservation to the given number of bytes. Errors if growing
+    /// it would exceed the query's memory limit, leaving it unchanged.
+    pub fn resize(&mut self, size: usize) -> Result<()> {
+        let used = &self.accountant.used;
+        if size <= self.size {
+            used.fetch_sub(self.size - size, Ordering::Relaxed);
+        } else {
+            let total = used.fetch_add(size - self.size, Ordering::Relaxed) + size - self.size;
+            if self.accountant.limit.is_some_and(|limit| total > limit) {
+                used.fetch_sub(size - self.size, Ordering::Relaxed);
+                return Err(Error::OutOfMemory);
+            }
+        }
+        self.size = size;
+        Ok(())
+    }
+
+    /// Keeps the reservation until the given rows are dropped, for executors
+    /// which emit rows from their buffers.
+    pub fn attach(self, rows: Rows) -> Rows {
+        Box::new(ReservedRows { rows, _reservation: self })
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.accountant.used.fetch_sub(self.size, Ordering::Relaxed);
+    }
+}
+
+/// Rows which keep a memory reservation until dropped.
+struct ReservedRows {
+    rows: Rows,
+    _reservation: Reservation,
+}
+
+impl Iterator for ReservedRows {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows.next()
+    }
+}
+
+/// Estimates a value's memory usage in bytes, including heap allocations.
+/// Array elements are counted recursively, since the elements themselves are
+/// heap-allocated.
+pub fn value_size(value: &Value) -> usize {
+    std::mem::size_of::<Value>()
+        + match value {
+            Value::String(s) => s.len(),
+            Value::Bytes(b) => b.len(),
+            Value::Json(j) => j.to_string().len(),
+            Value::Array(elements) => elements.iter().map(value_size).sum(),
+            _ => 0,
+        }
+}
+
+/// Estimates a row's memory usage in bytes.
+pub fn row_size(row: &[Value]) -> usize {
+    row.iter().map(value_size).sum()
+}
@@ -1,24 +1,49 @@
 mod aggregation;
+mod analyze;
+mod cancel;
+mod copy;
 mod join;
+mod memory;
 mod mutation;
 mod query;
 mod schema;
+mod set;
 mod source;
 
 use aggregation::Aggregation;
-use join::{HashJoin, NestedLoopJoin};
-use mutation::{Delete, Insert, Update};
-use query::{Filter, Limit, Offset, Order, Projection};
-use schema::{CreateTable, DropTable};
-use source::{IndexLookup, KeyLookup, Nothing, Scan};
+use analyze::Analyze;
+pub use analyze::Stats;
+pub use cancel::Cancel;
+use cancel::Cancellable;
+pub use copy::{copy_from, copy_to};
+use join::{HashJoin, HashSemiJoin, LateralJoin, MergeJoin, NestedLoopJoin};
+pub use memory::Accountant;
+use mutation::{Delete, Insert, Triggers, Update};
+use query::{Distinct, Filter, Limit, Offset, Order, Projection};
+use schema::{
+    AddColumn, AlterColumn, AlterUser, AnalyzeTables, Comment, CreateDatabase, CreateIndex,
+    CreateRole, CreateSequence, CreateTable, CreateTableAs, CreateTrigger, CreateType, CreateUser,
+    CreateView, Describe, DropColumn, DropIndex, DropSequence, DropTable, DropTrigger, DropType,
+    DropUser, DropView, Grant, GrantRole, ShowCreateTable, ShowTables,
+};
+use set::SetOperation;
+use source::{
+    IndexIntersection, IndexLookup, IndexOnlyScan, IndexRangeScan, InformationSchema, KeyLookup,
+    Nothing, ParallelScan, ReverseScan, Scan, Unnest, Values,
+};
 
 use super::engine::Transaction;
-use super::plan::Node;
+use super::plan::{Estimate, Node};
 use super::types::{Columns, Row, Rows, Value};
 use crate::error::{Error, Result};
 
 use derivative::Derivative;
 use serde_derive::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// The number of rows written per storage request by bulk writes, e.g. per
+/// Raft proposal.
+const BATCH_SIZE: usize = 1000;
 
 /// A plan executor
 pub trait Executor<T: Transaction> {
@@ -29,46 +54,211 @@ pub trait Executor<T: Transaction> {
 impl<T: Transaction + 'static> dyn Executor<T> {
     /// Builds an executor for a plan node, consuming it
     pub fn build(node: Node) -> Box<dyn Executor<T>> {
-        match node {
-            Node::Aggregation { source, aggregates } => {
-                Aggregation::new(Self::build(*source), aggregates)
-            }
+        Self::build_with(node, &mut BuildContext::default())
+    }
+
+    /// Builds an executor for a plan node, consuming it, with the given
+    /// statistics, cancellation and memory accounting context.
+    pub fn build_with(node: Node, ctx: &mut BuildContext) -> Box<dyn Executor<T>> {
+        Self::build_node(node, None, ctx)
+    }
+
+    /// Builds an executor for a plan node. If limit is given, it is the limit
+    /// of the parent LIMIT node, and scans and sorts stop after emitting that
+    /// many rows.
+    fn build_node(node: Node, limit: Option<u64>, ctx: &mut BuildContext) -> Box<dyn Executor<T>> {
+        let node_stats = ctx.stats.as_mut().map(|stats| {
+            let node_stats = Arc::new(Mutex::new(Stats::default()));
+            stats.push(node_stats.clone());
+            node_stats
+        });
+        let executor: Box<dyn Executor<T>> = match node {
+            Node::AddColumn { table, column } => AddColumn::new(table, column),
+            Node::Aggregation { source, aggregates, grouping_sets, memory } => Aggregation::new(
+                Self::build_with(*source, ctx),
+                aggregates,
+                grouping_sets,
+                memory,
+                ctx.accountant.clone(),
+            ),
+            Node::AlterColumn { table, column, change } => AlterColumn::new(table, column, change),
+            Node::AlterUser { user } => AlterUser::new(user),
+            Node::Analyze { tables } => AnalyzeTables::new(tables),
+            Node::Comment { table, column, comment } => Comment::new(table, column, comment),
+            Node::CreateDatabase { database } => CreateDatabase::new(database),
+            Node::CreateIndex { table, index } => CreateIndex::new(table, index),
+            Node::CreateRole { role } => CreateRole::new(role),
+            Node::CreateSequence { sequence } => CreateSequence::new(sequence),
+            Node::CreateTrigger { table, trigger } => CreateTrigger::new(table, trigger),
+            Node::CreateType { enum_type } => CreateType::new(enum_type),
+            Node::CreateUser { user } => CreateUser::new(user),
             Node::CreateTable { schema } => CreateTable::new(schema),
-            Node::Delete { table, source } => Delete::new(table, Self::build(*source)),
+            Node::CreateTableAs { table, columns, temporary, source } => {
+                CreateTableAs::new(table, columns, temporary, Self::build_with(*source, ctx))
+            }
+            Node::CreateView { view } => CreateView::new(view),
+            Node::Delete { table, source, returning } => {
+                let triggers = Triggers::new(ctx);
+                Delete::new(table, Self::build_with(*source, ctx), returning, triggers)
+            }
+            Node::Describe { table } => Describe::new(table),
+            Node::Distinct { source, on } => Distinct::new(Self::build_with(*source, ctx), on),
+            Node::DropColumn { table, column } => DropColumn::new(table, column),
+            Node::DropIndex { index, if_exists } => DropIndex::new(index, if_exists),
+            Node::DropSequence { sequence, if_exists } => DropSequence::new(sequence, if_exists),
+            Node::DropTrigger { table, trigger, if_exists } => {
+                DropTrigger::new(table, trigger, if_exists)
+            }
+            Node::DropType { name, if_exists } => DropType::new(name, if_exists),
+            Node::DropUser { name, if_exists } => DropUser::new(name, if_exists),
             Node::DropTable { table, if_exists } => DropTable::new(table, if_exists),
-            Node::Filter { source, predicate } => Filter::new(Self::build(*source), predicate),
+            Node::DropView { view, if_exists } => DropView::new(view, if_exists),
+            Node::Filter { source, predicate } => {
+                Filter::new(Self::build_with(*source, ctx), predicate)
+            }
+            Node::Grant { table, grantee, privileges } => {
+                Grant::new(table, grantee, privileges, false)
+            }
+            Node::GrantRole { role, user } => GrantRole::new(role, user, false),
+            Node::Revoke { table, grantee, privileges } => {
+                Grant::new(table, grantee, privileges, true)
+            }
+            Node::RevokeRole { role, user } => GrantRole::new(role, user, true),
             Node::HashJoin { left, left_field, right, right_field, outer } => HashJoin::new(
-                Self::build(*left),
+                Self::build_with(*left, ctx),
                 left_field.0,
-                Self::build(*right),
+                Self::build_with(*right, ctx),
                 right_field.0,
                 outer,
+                ctx.accountant.clone(),
+            ),
+            Node::HashSemiJoin { left, keys, right, anti, null_aware } => HashSemiJoin::new(
+                Self::build_with(*left, ctx),
+                keys,
+                Self::build_with(*right, ctx),
+                anti,
+                null_aware,
+                ctx.accountant.clone(),
             ),
-            Node::IndexLookup { table, alias: _, column, values } => {
-                IndexLookup::new(table, column, values)
+            Node::IndexIntersection { table, alias: _, lookups } => {
+                IndexIntersection::new(table, lookups)
+            }
+            Node::IndexLookup { table, alias: _, index, values } => {
+                IndexLookup::new(table, index, values)
             }
-            Node::Insert { table, columns, expressions } => {
-                Insert::new(table, columns, expressions)
+            Node::IndexOnlyScan { table, alias: _, index, ranges } => {
+                IndexOnlyScan::new(table, index, ranges, limit)
+            }
+            Node::IndexRangeScan { table, alias: _, index, range } => {
+                IndexRangeScan::new(table, index, range)
+            }
+            Node::InformationSchema { table, alias: _ } => InformationSchema::new(table),
+            Node::MergeJoin { left, left_field, right, right_field, outer } => MergeJoin::new(
+                Self::build_with(*left, ctx),
+                left_field.0,
+                Self::build_with(*right, ctx),
+                right_field.0,
+                outer,
+            ),
+            Node::Insert { table, columns, expressions, on_conflict, returning } => {
+                let triggers = Triggers::new(ctx);
+                Insert::new(table, columns, expressions, on_conflict, returning, triggers)
             }
             Node::KeyLookup { table, alias: _, keys } => KeyLookup::new(table, keys),
-            Node::Limit { source, limit } => Limit::new(Self::build(*source), limit),
+            Node::LateralJoin { left, left_size: _, right, predicate, outer } => {
+                let left = Self::build_with(*left, ctx);
+                // The right node is built for each left row, accumulating its runtime statistics
+                // into entries registered here, in pre-order.
+                let right_stats = ctx.stats.as_mut().map(|stats| {
+                    let start = stats.len();
+                    stats.extend((0..Self::count(&right)).map(|_| Arc::default()));
+                    stats[start..].to_vec()
+                });
+                let (cancel, accountant) = (ctx.cancel.clone(), ctx.accountant.clone());
+                LateralJoin::new(left, *right, right_stats, predicate, outer, cancel, accountant)
+            }
+            Node::Limit { source, limit, ties } => {
+                // WITH TIES may need rows past the limit.
+                let source_limit = Some(limit).filter(|_| ties.is_empty());
+                let source = Self::build_node(*source, source_limit, ctx);
+                Limit::new(source, limit, ties)
+            }
             Node::NestedLoopJoin { left, left_size: _, right, predicate, outer } => {
-                NestedLoopJoin::new(Self::build(*left), Self::build(*right), predicate, outer)
+                NestedLoopJoin::new(
+                    Self::build_with(*left, ctx),
+                    Self::build_with(*right, ctx),
+                    predicate,
+                    outer,
+                )
             }
             Node::Nothing => Nothing::new(),
-            Node::Offset { source, offset } => Offset::new(Self::build(*source), offset),
-            Node::Order { source, orders } => Order::new(Self::build(*source), orders),
+            Node::Offset { source, offset } => Offset::new(Self::build_with(*source, ctx), offset),
+            Node::Order { source, orders, memory } => {
+                let source = Self::build_with(*source, ctx);
+                Order::new(source, orders, limit, memory, ctx.accountant.clone())
+            }
+            Node::ParallelScan { table, filter, columns, workers, alias: _ } => {
+                ParallelScan::new(table, filter, columns, workers)
+            }
             Node::Projection { source, expressions } => {
-                Projection::new(Self::build(*source), expressions)
+                Projection::new(Self::build_with(*source, ctx), expressions)
+            }
+            Node::ReverseScan { table, filter, columns, alias: _ } => {
+                ReverseScan::new(table, filter, limit, columns)
             }
-            Node::Scan { table, filter, alias: _ } => Scan::new(table, filter),
-            Node::Update { table, source, expressions } => Update::new(
+            Node::Scan { table, filter, columns, alias: _ } => {
+                Scan::new(table, filter, limit, columns)
+            }
+            Node::SetOperation { left, right, operator, all } => SetOperation::new(
+                Self::build_with(*left, ctx),
+                Self::build_with(*right, ctx),
+                operator,
+                all,
+            ),
+            Node::ShowCreateTable { table } => ShowCreateTable::new(table),
+            Node::ShowTables { database } => ShowTables::new(database),
+            Node::Update { table, source, expressions, returning } => Update::new(
                 table,
-                Self::build(*source),
+                Self::build_with(*source, ctx),
                 expressions.into_iter().map(|(i, _, e)| (i, e)).collect(),
+                returning,
+                Triggers::new(ctx),
             ),
+            Node::Unnest { expression, column } => Unnest::new(expression, column),
+            Node::Values { columns, rows } => Values::new(columns, rows),
+        };
+        let executor = match node_stats {
+            Some(node_stats) => Analyze::new(executor, node_stats),
+            None => executor,
+        };
+        match &ctx.cancel {
+            Some(cancel) => Cancellable::new(executor, cancel.clone()),
+            None => executor,
         }
     }
+
+    /// Returns the number of executors built for a plan node, i.e. the number
+    /// of nodes in its tree.
+    fn count(node: &Node) -> usize {
+        1 + node.children().into_iter().map(Self::count).sum::<usize>()
+    }
+}
+
+/// The context for building executors.
+#[derive(Default)]
+pub struct BuildContext<'a> {
+    /// If given, each executor records its runtime statistics in a new entry,
+    /// in pre-order, as for EXPLAIN ANALYZE.
+    pub stats: Option<&'a mut Vec<Arc<Mutex<Stats>>>>,
+    /// If given, each executor checks the cancellation token before executing
+    /// and before fetching each row, i.e. at every operator boundary.
+    pub cancel: Option<Cancel>,
+    /// The memory accountant that sorts, hash joins and aggregations reserve
+    /// their buffered rows with. Unlimited by default.
+    pub accountant: Accountant,
+    /// The nesting depth of trigger actions, i.e. 0 for a statement executed
+    /// by a session, and 1 for the action of a trigger fired by it.
+    pub depth: usize,
 }
 
 /// An executor result set
@@ -88,6 +278,34 @@ pub enum ResultSet {
     Rollback {
         version: u64,
     },
+    // Savepoint created
+    Savepoint {
+        name: String,
+    },
+    // Transaction rolled back to savepoint
+    RollbackToSavepoint {
+        name: String,
+    },
+    // Savepoint released
+    ReleaseSavepoint {
+        name: String,
+    },
+    // Session setting changed
+    Set {
+        name: String,
+    },
+    // Current database changed
+    Use {
+        database: String,
+    },
+    // Query cancelled
+    Kill {
+        id: u64,
+    },
+    // Rows bulk-loaded via COPY FROM or exported via COPY TO
+    Copy {
+        count: u64,
+    },
     // Rows created
     Create {
         count: u64,
@@ -100,6 +318,10 @@ pub enum ResultSet {
     Update {
         count: u64,
     },
+    // Database created
+    CreateDatabase {
+        name: String,
+    },
     // Table created
     CreateTable {
         name: String,
@@ -109,6 +331,88 @@ pub enum ResultSet {
         name: String,
         existed: bool,
     },
+    // Index created
+    CreateIndex {
+        name: String,
+    },
+    // Index dropped
+    DropIndex {
+        name: String,
+        existed: bool,
+    },
+    // Table altered
+    AlterTable {
+        name: String,
+    },
+    // View created
+    CreateView {
+        name: String,
+    },
+    // View dropped
+    DropView {
+        name: String,
+        existed: bool,
+    },
+    // Sequence created
+    CreateSequence {
+        name: String,
+    },
+    // Sequence dropped
+    DropSequence {
+        name: String,
+        existed: bool,
+    },
+    // Type created
+    CreateType {
+        name: String,
+    },
+    // Type dropped
+    DropType {
+        name: String,
+        existed: bool,
+    },
+    // User created
+    CreateUser {
+        name: String,
+    },
+    // User altered
+    AlterUser {
+        name: String,
+    },
+    // User dropped
+    DropUser {
+        name: String,
+        existed: bool,
+    },
+    // Role created
+    CreateRole {
+        name: String,
+    },
+    // Privileges or a role granted
+    Grant {
+        grantee: String,
+    },
+    // Privileges or a role revoked
+    Revoke {
+        grantee: String,
+    },
+    // Trigger created
+    CreateTrigger {
+        name: String,
+    },
+    // Trigger dropped
+    DropTrigger {
+        name: String,
+        existed: bool,
+    },
+    // Comment set
+    Comment {
+        name: String,
+    },
+    // Tables analyzed
+    Analyze {
+        tables: Vec<String>,
+    },
     // Query result
     Query {
         columns: Columns,
@@ -119,6 +423,18 @@ pub enum ResultSet {
     },
     // Explain result
     Explain(Node),
+    // Explain analyze result, with runtime statistics for each node in pre-order
+    ExplainAnalyze {
+        plan: Node,
+        stats: Vec<Stats>,
+    },
+    // Explain verbose result, with estimates for each node in pre-order, and
+    // runtime statistics if analyzed
+    ExplainVerbose {
+        plan: Node,
+        estimates: Vec<Estimate>,
+        stats: Vec<Stats>,
+    },
 }
 
 impl ResultSet {
@@ -1,31 +1,172 @@
 use super::super::engine::Transaction;
-use super::super::schema::Table;
-use super::super::types::{Expression, Row, Value};
-use super::{Executor, ResultSet};
+use super::super::plan::{ConflictKey, OnConflict, Plan, Returning, TriggerAction};
+use super::super::schema::{Table, Trigger, TriggerEvent, TriggerTiming};
+use super::super::types::{Column, Expression, Row, Value};
+use super::{Accountant, BuildContext, Cancel, Executor, ResultSet, BATCH_SIZE};
 use crate::error::{Error, Result};
 
 use std::collections::{HashMap, HashSet};
 
+/// Evaluates RETURNING expressions for the rows affected by an INSERT, UPDATE
+/// or DELETE. The rows are evaluated eagerly, such that any errors fail the
+/// statement before its transaction is committed.
+fn returning(expressions: Returning, rows: Vec<Row>) -> Result<ResultSet> {
+    let rows: Vec<Row> = rows
+        .into_iter()
+        .map(|row| expressions.iter().map(|(e, _)| e.evaluate(Some(&row))).collect())
+        .collect::<Result<_>>()?;
+    Ok(ResultSet::Query {
+        columns: expressions.into_iter().map(|(_, label)| Column { name: label }).collect(),
+        rows: Box::new(rows.into_iter().map(Ok)),
+    })
+}
+
+/// The maximum nesting depth of trigger actions, e.g. for a trigger whose
+/// action writes to its own table.
+const MAX_TRIGGER_DEPTH: usize = 16;
+
+/// Fires the row-level triggers of a table for the rows written by an INSERT,
+/// UPDATE or DELETE. The trigger actions are planned and authorized as their
+/// owner once per statement, and executed in the same transaction,
+/// cancellation and memory context as the statement.
+pub struct Triggers {
+    cancel: Option<Cancel>,
+    accountant: Accountant,
+    depth: usize,
+    /// The planned triggers for the statement's events, in creation order.
+    triggers: Vec<(Trigger, TriggerAction)>,
+}
+
+impl Triggers {
+    pub fn new(ctx: &BuildContext) -> Self {
+        Self {
+            cancel: ctx.cancel.clone(),
+            accountant: ctx.accountant.clone(),
+            depth: ctx.depth,
+            triggers: Vec::new(),
+        }
+    }
+
+    /// Plans the table's triggers for the given events.
+    fn load(
+        &mut self,
+        txn: &mut impl Transaction,
+        table: &Table,
+        events: &[TriggerEvent],
+    ) -> Result<()> {
+        for trigger in table.triggers.iter().filter(|t| events.contains(&t.event)) {
+            if self.depth >= MAX_TRIGGER_DEPTH {
+                return Err(Error::Value(format!(
+                    "Trigger {} exceeds the maximum nesting depth of {}",
+                    trigger.name, MAX_TRIGGER_DEPTH
+                )));
+            }
+            let action = Plan::build_trigger(table, trigger, txn)?;
+            // The owner's privileges may have been revoked since creation.
+            if let Some(owner) = &trigger.owner {
+                action.authorize(txn, owner)?;
+            }
+            self.triggers.push((trigger.clone(), action));
+        }
+        Ok(())
+    }
+
+    /// Returns true if any triggers fire for the given event. Rows are then
+    /// written individually rather than in batches, such that the triggers
+    /// fire for each row in turn.
+    fn has(&self, event: TriggerEvent) -> bool {
+        self.triggers.iter().any(|(t, _)| t.event == event)
+    }
+
+    /// Fires the triggers with the given timing for a row write, given the
+    /// row's old values (except for INSERT) and new values (except for
+    /// DELETE). SET actions of BEFORE triggers modify the new values, and
+    /// recompute their generated columns.
+    fn fire<T: Transaction + 'static>(
+        &self,
+        txn: &mut T,
+        table: &Table,
+        timing: TriggerTiming,
+        event: TriggerEvent,
+        old: Option<&[Value]>,
+        mut new: Option<&mut Row>,
+    ) -> Result<()> {
+        let triggers = self.triggers.iter().filter(|(t, _)| t.timing == timing && t.event == event);
+        for (_, action) in triggers {
+            let mut values = old.map(|old| old.to_vec()).unwrap_or_default();
+            values.extend(new.iter().flat_map(|new| new.iter()).cloned());
+            match action {
+                TriggerAction::Set(set) => {
+                    let Some(new) = new.as_deref_mut() else {
+                        return Err(Error::Internal("No new row for trigger".into()));
+                    };
+                    for (field, expr) in set {
+                        new[*field] = expr.evaluate_sequences(Some(&values), txn)?;
+                    }
+                    table.generate_row(new)?;
+                }
+                TriggerAction::Execute(node) => {
+                    // The plan is optimized once the row values are bound, e.g. to use index
+                    // lookups for them.
+                    let plan =
+                        Plan((**node).clone().bind_outer(&values)?, Vec::new()).optimize(txn)?;
+                    let mut ctx = BuildContext {
+                        stats: None,
+                        cancel: self.cancel.clone(),
+                        accountant: self.accountant.clone(),
+                        depth: self.depth + 1,
+                    };
+                    let executor = <dyn Executor<T>>::build_with(plan.0, &mut ctx);
+                    if let ResultSet::Query { rows, .. } = executor.execute(txn)? {
+                        for row in rows {
+                            row?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// An INSERT executor
 pub struct Insert {
     table: String,
     columns: Vec<String>,
     rows: Vec<Vec<Expression>>,
+    on_conflict: Option<OnConflict>,
+    returning: Option<Returning>,
+    triggers: Triggers,
 }
 
 impl Insert {
-    pub fn new(table: String, columns: Vec<String>, rows: Vec<Vec<Expression>>) -> Box<Self> {
-        Box::new(Self { table, columns, rows })
+    pub fn new(
+        table: String,
+        columns: Vec<String>,
+        rows: Vec<Vec<Expression>>,
+        on_conflict: Option<OnConflict>,
+        returning: Option<Returning>,
+        triggers: Triggers,
+    ) -> Box<Self> {
+        Box::new(Self { table, columns, rows, on_conflict, returning, triggers })
     }
 
-    // Builds a row from a set of column names and values, padding it with default values.
-    pub fn make_row(table: &Table, columns: &[String], values: Vec<Value>) -> Result<Row> {
+    // Builds a row from a set of column names and values, padding it with default values and
+    // computing generated values.
+    pub fn make_row(
+        txn: &mut impl Transaction,
+        table: &Table,
+        columns: &[String],
+        values: Vec<Value>,
+    ) -> Result<Row> {
         if columns.len() != values.len() {
             return Err(Error::Value("Column and value counts do not match".into()));
         }
         let mut inputs = HashMap::new();
-        for (c, v) in columns.iter().zip(values.into_iter()) {
-            table.get_column(c)?;
+        for (c, v) in columns.iter().zip(values) {
+            if table.get_column(c)?.generated.is_some() {
+                return Err(Error::Value(format!("Can't insert into generated column {}", c)));
+            }
             if inputs.insert(c.clone(), v).is_some() {
                 return Err(Error::Value(format!("Column {} given multiple times", c)));
             }
@@ -34,44 +175,186 @@ impl Insert {
         for column in table.columns.iter() {
             if let Some(value) = inputs.get(&column.name) {
                 row.push(value.clone())
-            } else if let Some(value) = &column.default {
-                row.push(value.clone())
+            } else if column.generated.is_some() {
+                row.push(Value::Null)
+            } else if column.auto_increment {
+                row.push(Value::Integer(txn.next_sequence(&table.name, &column.name)?))
+            } else if let Some(default) = &column.default {
+                row.push(default.evaluate_sequences(None, txn)?)
             } else {
                 return Err(Error::Value(format!("No value given for column {}", column.name)));
             }
         }
+        table.generate_row(&mut row)?;
         Ok(row)
     }
 
-    /// Pads a row with default values where possible.
-    fn pad_row(table: &Table, mut row: Row) -> Result<Row> {
+    /// Pads a row with default values where possible, and computes generated values.
+    fn pad_row(txn: &mut impl Transaction, table: &Table, mut row: Row) -> Result<Row> {
+        if let Some(column) = table.columns.iter().take(row.len()).find(|c| c.generated.is_some()) {
+            return Err(Error::Value(format!(
+                "Can't insert into generated column {}",
+                column.name
+            )));
+        }
         for column in table.columns.iter().skip(row.len()) {
-            if let Some(default) = &column.default {
-                row.push(default.clone())
+            if column.generated.is_some() {
+                row.push(Value::Null)
+            } else if column.auto_increment {
+                row.push(Value::Integer(txn.next_sequence(&table.name, &column.name)?))
+            } else if let Some(default) = &column.default {
+                row.push(default.evaluate_sequences(None, txn)?)
             } else {
                 return Err(Error::Value(format!("No default value for column {}", column.name)));
             }
         }
+        table.generate_row(&mut row)?;
         Ok(row)
     }
+
+    /// Finds an existing row that conflicts with a row on any of the given
+    /// unique keys. Index keys containing NULL never conflict.
+    fn find_conflict(
+        txn: &mut impl Transaction,
+        table: &Table,
+        keys: &[ConflictKey],
+        row: &[Value],
+    ) -> Result<Option<Row>> {
+        for key in keys {
+            let id = match key {
+                ConflictKey::PrimaryKey => Some(table.get_row_key(row)?),
+                ConflictKey::Index(name) => {
+                    let values = table.get_index(name)?.get_row_key(table, row)?;
+                    if values.contains(&Value::Null) {
+                        continue;
+                    }
+                    txn.read_index(&table.name, name, &values)?.into_iter().next()
+                }
+            };
+            if let Some(existing) = id.map(|id| txn.read(&table.name, &id)).transpose()?.flatten() {
+                return Ok(Some(existing));
+            }
+        }
+        Ok(None)
+    }
 }
 
-impl<T: Transaction> Executor<T> for Insert {
-    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+impl<T: Transaction + 'static> Executor<T> for Insert {
+    fn execute(mut self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
         let table = txn.must_read_table(&self.table)?;
+        // Like in Postgres, ON CONFLICT DO UPDATE fires update triggers for updated rows.
+        let events = match &self.on_conflict {
+            Some(OnConflict { update: Some(_), .. }) => {
+                vec![TriggerEvent::Insert, TriggerEvent::Update]
+            }
+            _ => vec![TriggerEvent::Insert],
+        };
+        self.triggers.load(txn, &table, &events)?;
         let mut count = 0;
+        // Primary keys of rows written by this statement, which can't be updated again on
+        // conflict, since the outcome would depend on the order of the inserted rows.
+        let mut written = HashSet::new();
+        // Inserted and updated rows, for RETURNING.
+        let mut returned = Vec::new();
+        // Rows to insert, written in batches. Rows are written individually with ON CONFLICT,
+        // since later rows may conflict with them.
+        let mut batch = Vec::new();
         for expressions in self.rows {
-            let mut row =
-                expressions.into_iter().map(|expr| expr.evaluate(None)).collect::<Result<_>>()?;
+            let mut row = expressions
+                .iter()
+                .map(|e| e.evaluate_sequences(None, txn))
+                .collect::<Result<_>>()?;
             if self.columns.is_empty() {
-                row = Self::pad_row(&table, row)?;
+                row = Self::pad_row(txn, &table, row)?;
             } else {
-                row = Self::make_row(&table, &self.columns, row)?;
+                row = Self::make_row(txn, &table, &self.columns, row)?;
             }
-            txn.create(&table.name, row)?;
+            // Like in Postgres, BEFORE INSERT triggers fire before checking for conflicts.
+            let (before, after) = (TriggerTiming::Before, TriggerTiming::After);
+            self.triggers.fire(txn, &table, before, TriggerEvent::Insert, None, Some(&mut row))?;
+            let Some(on_conflict) = &self.on_conflict else {
+                if self.returning.is_some() {
+                    returned.push(row.clone());
+                }
+                count += 1;
+                if self.triggers.has(TriggerEvent::Insert) {
+                    txn.create(&table.name, row.clone())?;
+                    self.triggers.fire(
+                        txn,
+                        &table,
+                        after,
+                        TriggerEvent::Insert,
+                        None,
+                        Some(&mut row),
+                    )?;
+                    continue;
+                }
+                batch.push(row);
+                if batch.len() >= BATCH_SIZE {
+                    txn.create_batch(&table.name, std::mem::take(&mut batch))?;
+                }
+                continue;
+            };
+            let Some(existing) = Self::find_conflict(txn, &table, &on_conflict.keys, &row)? else {
+                written.insert(table.get_row_key(&row)?);
+                if self.returning.is_some() {
+                    returned.push(row.clone());
+                }
+                txn.create(&table.name, row.clone())?;
+                self.triggers.fire(
+                    txn,
+                    &table,
+                    after,
+                    TriggerEvent::Insert,
+                    None,
+                    Some(&mut row),
+                )?;
+                count += 1;
+                continue;
+            };
+            let Some(update) = &on_conflict.update else { continue };
+            let id = table.get_row_key(&existing)?;
+            if !written.insert(id.clone()) {
+                return Err(Error::Value(format!(
+                    "ON CONFLICT DO UPDATE can't update row {} in table {} twice",
+                    id, table.name
+                )));
+            }
+            let mut input = existing.clone();
+            input.extend(row);
+            if let Some(predicate) = &on_conflict.predicate {
+                match predicate.evaluate(Some(&input))? {
+                    Value::Boolean(true) => {}
+                    Value::Boolean(false) | Value::Null => continue,
+                    value => {
+                        return Err(Error::Value(format!(
+                            "ON CONFLICT predicate returned {}, expected boolean",
+                            value
+                        )))
+                    }
+                }
+            }
+            let mut new = existing.clone();
+            for (field, _, expr) in update {
+                new[*field] = expr.evaluate(Some(&input))?;
+            }
+            table.generate_row(&mut new)?;
+            let (old, event) = (Some(existing.as_slice()), TriggerEvent::Update);
+            self.triggers.fire(txn, &table, before, event, old, Some(&mut new))?;
+            if self.returning.is_some() {
+                returned.push(new.clone());
+            }
+            txn.update(&table.name, &id, new.clone())?;
+            self.triggers.fire(txn, &table, after, event, old, Some(&mut new))?;
             count += 1;
         }
-        Ok(ResultSet::Create { count })
+        if !batch.is_empty() {
+            txn.create_batch(&table.name, batch)?;
+        }
+        match self.returning {
+            Some(expressions) => returning(expressions, returned),
+            None => Ok(ResultSet::Create { count }),
+        }
     }
 }
 
@@ -80,6 +363,8 @@ pub struct Update<T: Transaction> {
     table: String,
     source: Box<dyn Executor<T>>,
     expressions: Vec<(usize, Expression)>,
+    returning: Option<Returning>,
+    triggers: Triggers,
 }
 
 impl<T: Transaction> Update<T> {
@@ -87,16 +372,20 @@ impl<T: Transaction> Update<T> {
         table: String,
         source: Box<dyn Executor<T>>,
         expressions: Vec<(usize, Expression)>,
+        returning: Option<Returning>,
+        triggers: Triggers,
     ) -> Box<Self> {
-        Box::new(Self { table, source, expressions })
+        Box::new(Self { table, source, expressions, returning, triggers })
     }
 }
 
-impl<T: Transaction> Executor<T> for Update<T> {
-    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+impl<T: Transaction + 'static> Executor<T> for Update<T> {
+    fn execute(mut self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
         match self.source.execute(txn)? {
             ResultSet::Query { mut rows, .. } => {
                 let table = txn.must_read_table(&self.table)?;
+                self.triggers.load(txn, &table, &[TriggerEvent::Update])?;
+                let (before, after) = (TriggerTiming::Before, TriggerTiming::After);
 
                 // The iterator will see our changes, such that the same item may be iterated over
                 // multiple times. We keep track of the primary keys here to avoid that, althought
@@ -106,19 +395,46 @@ impl<T: Transaction> Executor<T> for Update<T> {
                 // multiple times - it should be possible to come up with a pathological case that
                 // loops forever (e.g. UPDATE test SET id = id + 1).
                 let mut updated = HashSet::new();
+                let mut returned = Vec::new();
+                let mut batch = Vec::new();
                 while let Some(row) = rows.next().transpose()? {
                     let id = table.get_row_key(&row)?;
                     if updated.contains(&id) {
                         continue;
                     }
-                    let mut new = row.clone();
+                    // Rows joined with FROM tables are truncated to the updated table's columns.
+                    // A row may be joined with several FROM rows, in which case only one of them
+                    // is used (whichever is seen first).
+                    let old = &row[..table.columns.len()];
+                    let mut new = old.to_vec();
                     for (field, expr) in &self.expressions {
-                        new[*field] = expr.evaluate(Some(&row))?;
+                        new[*field] = expr.evaluate_sequences(Some(&row), txn)?;
+                    }
+                    table.generate_row(&mut new)?;
+                    let event = TriggerEvent::Update;
+                    self.triggers.fire(txn, &table, before, event, Some(old), Some(&mut new))?;
+                    if self.returning.is_some() {
+                        returned.push(new.clone());
+                    }
+                    if self.triggers.has(event) {
+                        txn.update(&table.name, &id, new.clone())?;
+                        self.triggers.fire(txn, &table, after, event, Some(old), Some(&mut new))?;
+                        updated.insert(id);
+                        continue;
+                    }
+                    batch.push((id.clone(), new));
+                    if batch.len() >= BATCH_SIZE {
+                        txn.update_batch(&table.name, std::mem::take(&mut batch))?;
                     }
-                    txn.update(&table.name, &id, new)?;
                     updated.insert(id);
                 }
-                Ok(ResultSet::Update { count: updated.len() as u64 })
+                if !batch.is_empty() {
+                    txn.update_batch(&table.name, batch)?;
+                }
+                match self.returning {
+                    Some(expressions) => returning(expressions, returned),
+                    None => Ok(ResultSet::Update { count: updated.len() as u64 }),
+                }
             }
             r => Err(Error::Internal(format!("Unexpected response {:?}", r))),
         }
@@ -129,25 +445,52 @@ impl<T: Transaction> Executor<T> for Update<T> {
 pub struct Delete<T: Transaction> {
     table: String,
     source: Box<dyn Executor<T>>,
+    returning: Option<Returning>,
+    triggers: Triggers,
 }
 
 impl<T: Transaction> Delete<T> {
-    pub fn new(table: String, source: Box<dyn Executor<T>>) -> Box<Self> {
-        Box::new(Self { table, source })
+    pub fn new(
+        table: String,
+        source: Box<dyn Executor<T>>,
+        returning: Option<Returning>,
+        triggers: Triggers,
+    ) -> Box<Self> {
+        Box::new(Self { table, source, returning, triggers })
     }
 }
 
-impl<T: Transaction> Executor<T> for Delete<T> {
-    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+impl<T: Transaction + 'static> Executor<T> for Delete<T> {
+    fn execute(mut self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
         let table = txn.must_read_table(&self.table)?;
+        self.triggers.load(txn, &table, &[TriggerEvent::Delete])?;
+        let (before, after) = (TriggerTiming::Before, TriggerTiming::After);
         let mut count = 0;
         match self.source.execute(txn)? {
             ResultSet::Query { mut rows, .. } => {
-                while let Some(row) = rows.next().transpose()? {
-                    txn.delete(&table.name, &table.get_row_key(&row)?)?;
-                    count += 1
+                // With DELETE ... USING, a row may be joined with several USING rows, but must
+                // only be deleted once.
+                let mut deleted = HashSet::new();
+                let mut returned = Vec::new();
+                while let Some(mut row) = rows.next().transpose()? {
+                    let id = table.get_row_key(&row)?;
+                    if !deleted.insert(id.clone()) {
+                        continue;
+                    }
+                    row.truncate(table.columns.len());
+                    let (old, event) = (Some(row.as_slice()), TriggerEvent::Delete);
+                    self.triggers.fire(txn, &table, before, event, old, None)?;
+                    txn.delete(&table.name, &id)?;
+                    self.triggers.fire(txn, &table, after, event, old, None)?;
+                    count += 1;
+                    if self.returning.is_some() {
+                        returned.push(row);
+                    }
+                }
+                match self.returning {
+                    Some(expressions) => returning(expressions, returned),
+                    None => Ok(ResultSet::Delete { count }),
                 }
-                Ok(ResultSet::Delete { count })
             }
             r => Err(Error::Internal(format!("Unexpected result {:?}", r))),
         }
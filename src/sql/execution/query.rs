@@ -1,10 +1,57 @@
 use super::super::engine::Transaction;
-use super::super::plan::Direction;
+use super::super::plan::{Direction, NullOrder};
 use super::super::types::{Column, Expression, Row, Value};
+use super::memory::{row_size, Accountant};
 use super::{Executor, ResultSet};
+use crate::encoding::bincode;
 use crate::error::{Error, Result};
 
-/// A filter executor
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BinaryHeap;
+use std::io::{BufReader, BufWriter, Seek as _, SeekFrom};
+use std::sync::Arc;
+
+/// The maximum number of sorted runs merged at once. Once a sort has spilled
+/// this many runs, they're merged into a single run, bounding the number of
+/// open temporary files.
+const MERGE_FAN_IN: usize = 16;
+
+/// A DISTINCT ON executor. This streams rows that are ordered by the given
+/// expressions, keeping the first row of each run of equal values.
+pub struct Distinct<T: Transaction> {
+    source: Box<dyn Executor<T>>,
+    on: Vec<Expression>,
+}
+
+impl<T: Transaction> Distinct<T> {
+    pub fn new(source: Box<dyn Executor<T>>, on: Vec<Expression>) -> Box<Self> {
+        Box::new(Self { source, on })
+    }
+}
+
+impl<T: Transaction> Executor<T> for Distinct<T> {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let ResultSet::Query { columns, rows } = self.source.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result".into()));
+        };
+        let on = self.on.iter().map(Expression::compile).collect::<Result<Vec<_>>>()?;
+        let mut last: Option<Vec<Value>> = None;
+        let rows = rows.filter_map(move |r| {
+            r.and_then(|row| {
+                let values = on.iter().map(|e| e.evaluate(&row)).collect::<Result<_>>()?;
+                if last.as_ref() == Some(&values) {
+                    return Ok(None);
+                }
+                last = Some(values);
+                Ok(Some(row))
+            })
+            .transpose()
+        });
+        Ok(ResultSet::Query { columns, rows: Box::new(rows) })
+    }
+}
+
+/// A filter executor, which evaluates the compiled predicate for each row
 pub struct Filter<T: Transaction> {
     source: Box<dyn Executor<T>>,
     predicate: Expression,
@@ -19,29 +66,25 @@ impl<T: Transaction> Filter<T> {
 impl<T: Transaction> Executor<T> for Filter<T> {
     fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
         if let ResultSet::Query { columns, rows } = self.source.execute(txn)? {
-            let predicate = self.predicate;
-            Ok(ResultSet::Query {
-                columns,
-                rows: Box::new(rows.filter_map(move |r| {
-                    r.and_then(|row| match predicate.evaluate(Some(&row))? {
-                        Value::Boolean(true) => Ok(Some(row)),
-                        Value::Boolean(false) => Ok(None),
-                        Value::Null => Ok(None),
-                        value => Err(Error::Value(format!(
-                            "Filter returned {}, expected boolean",
-                            value
-                        ))),
-                    })
-                    .transpose()
-                })),
-            })
+            let predicate = self.predicate.compile()?;
+            let rows = rows.filter_map(move |row| {
+                row.and_then(|row| match predicate.evaluate(&row)? {
+                    Value::Boolean(true) => Ok(Some(row)),
+                    Value::Boolean(false) | Value::Null => Ok(None),
+                    value => {
+                        Err(Error::Value(format!("Filter returned {}, expected boolean", value)))
+                    }
+                })
+                .transpose()
+            });
+            Ok(ResultSet::Query { columns, rows: Box::new(rows) })
         } else {
             Err(Error::Internal("Unexpected result".into()))
         }
     }
 }
 
-/// A projection executor
+/// A projection executor, which evaluates the compiled expressions for each row
 pub struct Projection<T: Transaction> {
     source: Box<dyn Executor<T>>,
     expressions: Vec<(Expression, Option<String>)>,
@@ -74,27 +117,163 @@ impl<T: Transaction> Executor<T> for Projection<T> {
                     }
                 })
                 .collect();
-            let rows = Box::new(rows.map(move |r| {
-                r.and_then(|row| {
-                    expressions.iter().map(|e| e.evaluate(Some(&row))).collect::<Result<_>>()
-                })
-            }));
-            Ok(ResultSet::Query { columns, rows })
+            // Sequence functions need the transaction, so the rows are evaluated here.
+            if expressions.iter().any(Expression::calls_sequences) {
+                let rows = rows
+                    .map(|row| {
+                        let row = row?;
+                        expressions.iter().map(|e| e.evaluate_sequences(Some(&row), txn)).collect()
+                    })
+                    .collect::<Result<Vec<Row>>>()?;
+                return Ok(ResultSet::Query { columns, rows: Box::new(rows.into_iter().map(Ok)) });
+            }
+            let expressions =
+                expressions.iter().map(Expression::compile).collect::<Result<Vec<_>>>()?;
+            let rows = rows.map(move |row| {
+                let row = row?;
+                expressions.iter().map(|e| e.evaluate(&row)).collect()
+            });
+            Ok(ResultSet::Query { columns, rows: Box::new(rows) })
         } else {
             Err(Error::Internal("Unexpected result".into()))
         }
     }
 }
 
-/// An ORDER BY executor
+/// An ORDER BY executor. With a limit, only the first limit rows are emitted,
+/// and only those are kept in memory while sorting (i.e. a top-N sort). With
+/// a memory budget, sorted runs of rows are spilled to temporary files once
+/// the buffered rows exceed it, and then merged (i.e. an external merge sort).
+/// The buffered rows are reserved with the query's memory accountant.
 pub struct Order<T: Transaction> {
     source: Box<dyn Executor<T>>,
-    order: Vec<(Expression, Direction)>,
+    order: Vec<(Expression, Direction, NullOrder)>,
+    limit: Option<u64>,
+    memory: Option<usize>,
+    accountant: Accountant,
 }
 
 impl<T: Transaction> Order<T> {
-    pub fn new(source: Box<dyn Executor<T>>, order: Vec<(Expression, Direction)>) -> Box<Self> {
-        Box::new(Self { source, order })
+    pub fn new(
+        source: Box<dyn Executor<T>>,
+        order: Vec<(Expression, Direction, NullOrder)>,
+        limit: Option<u64>,
+        memory: Option<usize>,
+        accountant: Accountant,
+    ) -> Box<Self> {
+        Box::new(Self { source, order, limit, memory, accountant })
+    }
+}
+
+/// A row being sorted, along with its evaluated sort values.
+#[derive(Serialize, Deserialize)]
+struct Item {
+    row: Row,
+    values: Vec<Value>,
+}
+
+impl Item {
+    /// Estimates the item's memory usage in bytes.
+    fn size(&self) -> usize {
+        row_size(&self.row) + row_size(&self.values)
+    }
+}
+
+/// A sorted run of items, either spilled to a temporary file or in memory.
+enum Run {
+    File(BufReader<std::fs::File>),
+    Memory(std::vec::IntoIter<Item>),
+}
+
+impl Run {
+    /// Spills sorted items to a temporary file, which is removed once closed.
+    fn spill(items: impl IntoIterator<Item = Result<Item>>) -> Result<Self> {
+        let mut writer = BufWriter::new(tempfile::tempfile()?);
+        for item in items {
+            bincode::serialize_into(&mut writer, &item?)?;
+        }
+        let mut file = writer.into_inner().map_err(|err| err.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(Self::File(BufReader::new(file)))
+    }
+
+    /// Returns the run's next item, if any.
+    fn next(&mut self) -> Result<Option<Item>> {
+        match self {
+            Self::File(reader) => bincode::maybe_deserialize_from(reader),
+            Self::Memory(items) => Ok(items.next()),
+        }
+    }
+}
+
+/// The next item of a run being merged. Heads are ordered in reverse, such
+/// that the max-heap pops the least item, and ties pop the earliest run.
+struct Head {
+    item: Item,
+    run: usize,
+    order: Arc<[(Direction, NullOrder)]>,
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let order = self.order.iter().map(|(d, n)| (d, n));
+        compare_sort_keys(&self.item.values, &other.item.values, order)
+            .then(self.run.cmp(&other.run))
+            .reverse()
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Head {}
+
+/// Merges sorted runs, emitting the least of the runs' next items via a heap
+/// of the runs' heads. Ties are emitted from the earliest run first.
+struct Merge {
+    runs: Vec<Run>,
+    heads: BinaryHeap<Head>,
+}
+
+impl Merge {
+    fn new(mut runs: Vec<Run>, order: Arc<[(Direction, NullOrder)]>) -> Result<Self> {
+        let mut heads = BinaryHeap::with_capacity(runs.len());
+        for (i, run) in runs.iter_mut().enumerate() {
+            if let Some(item) = run.next()? {
+                heads.push(Head { item, run: i, order: order.clone() });
+            }
+        }
+        Ok(Self { runs, heads })
+    }
+
+    /// Fallible next(), emitting the next item, or None if exhausted.
+    fn try_next(&mut self) -> Result<Option<Item>> {
+        let Some(mut head) = self.heads.pop() else {
+            return Ok(None);
+        };
+        if let Some(next) = self.runs[head.run].next()? {
+            let item = std::mem::replace(&mut head.item, next);
+            self.heads.push(head);
+            return Ok(Some(item));
+        }
+        Ok(Some(head.item))
+    }
+}
+
+impl Iterator for Merge {
+    type Item = Result<Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.try_next().transpose()
     }
 }
 
@@ -106,65 +285,159 @@ impl<T: Transaction> Executor<T> for Order<T> {
                 // pre-evaluate all values. This means that we can't short-circuit evaluation,
                 // and have to temporarily store evaluated values, which is bad for performance
                 // and memory usage respectively
-                struct Item {
-                    row: Row,
-                    values: Vec<Value>,
-                }
+                let order = &self.order;
+                let sort = |items: &mut Vec<Item>| {
+                    items.sort_by(|a, b| {
+                        compare_sort_keys(
+                            &a.values,
+                            &b.values,
+                            order.iter().map(|(_, d, n)| (d, n)),
+                        )
+                    })
+                };
 
+                // With a limit, sort and truncate the buffered rows whenever the
+                // buffer is full. The sort is stable and kept rows precede new
+                // ones, so ties are resolved in input order as for a full sort.
+                let limit = self.limit.map_or(usize::MAX, |l| l as usize);
+                let merge_order: Arc<[_]> =
+                    self.order.iter().map(|(_, d, n)| (d.clone(), n.clone())).collect();
                 let mut items = Vec::new();
+                let mut size = 0;
+                let mut reservation = self.accountant.reserve();
+                let keys =
+                    self.order.iter().map(|(e, _, _)| e.compile()).collect::<Result<Vec<_>>>()?;
+                let mut runs = Vec::new();
                 while let Some(row) = rows.next().transpose()? {
-                    let mut values = Vec::new();
-                    for (expr, _) in self.order.iter() {
-                        values.push(expr.evaluate(Some(&row))?);
+                    let mut values = Vec::with_capacity(keys.len());
+                    for key in &keys {
+                        values.push(key.evaluate(&row)?);
                     }
-                    items.push(Item { row, values })
-                }
-
-                let order = &self.order;
-                items.sort_by(|a, b| {
-                    for (i, (_, order)) in order.iter().enumerate() {
-                        let value_a = &a.values[i];
-                        let value_b = &b.values[i];
-                        match value_a.partial_cmp(value_b) {
-                            Some(std::cmp::Ordering::Equal) => {}
-                            Some(o) => {
-                                return if *order == Direction::Ascending { o } else { o.reverse() }
-                            }
-                            None => {}
+                    let item = Item { row, values };
+                    size += item.size();
+                    items.push(item);
+                    reservation.resize(size)?;
+                    if items.len() >= limit.saturating_mul(2) {
+                        sort(&mut items);
+                        items.truncate(limit);
+                        size = items.iter().map(Item::size).sum();
+                        reservation.resize(size)?;
+                    }
+                    // Spill a sorted run once the buffered rows exceed the
+                    // memory budget.
+                    if self.memory.is_some_and(|memory| size > memory) {
+                        sort(&mut items);
+                        items.truncate(limit);
+                        runs.push(Run::spill(std::mem::take(&mut items).into_iter().map(Ok))?);
+                        size = 0;
+                        reservation.resize(size)?;
+                        // Once the fan-in is reached, merge the runs into a
+                        // single run. It precedes later runs, preserving ties.
+                        if runs.len() >= MERGE_FAN_IN {
+                            let merge = Merge::new(std::mem::take(&mut runs), merge_order.clone())?;
+                            runs.push(Run::spill(merge.take(limit))?);
                         }
                     }
-                    std::cmp::Ordering::Equal
-                });
+                }
+                sort(&mut items);
+                items.truncate(limit);
 
-                Ok(ResultSet::Query {
-                    columns,
-                    rows: Box::new(items.into_iter().map(|i| Ok(i.row))),
-                })
+                if runs.is_empty() {
+                    let rows = Box::new(items.into_iter().map(|i| Ok(i.row)));
+                    return Ok(ResultSet::Query { columns, rows: reservation.attach(rows) });
+                }
+
+                // Merge the spilled runs and the remaining rows. Runs are in
+                // input order, so ties are resolved as for an in-memory sort.
+                runs.push(Run::Memory(items.into_iter()));
+                let merge = Merge::new(runs, merge_order)?;
+                let rows = Box::new(merge.take(limit).map(|item| item.map(|i| i.row)));
+                Ok(ResultSet::Query { columns, rows: reservation.attach(rows) })
             }
             r => Err(Error::Internal(format!("Unexpected result {:?}", r))),
         }
     }
 }
 
-/// A LIMIT executor
+/// Compares two sets of sort key values in the given directions. NULLs are
+/// positioned regardless of direction.
+pub(super) fn compare_sort_keys<'a>(
+    a: &[Value],
+    b: &[Value],
+    order: impl Iterator<Item = (&'a Direction, &'a NullOrder)>,
+) -> std::cmp::Ordering {
+    for ((value_a, value_b), (direction, nulls)) in a.iter().zip(b).zip(order) {
+        let null_first = match nulls {
+            NullOrder::First => std::cmp::Ordering::Less,
+            NullOrder::Last => std::cmp::Ordering::Greater,
+        };
+        match (value_a, value_b) {
+            (Value::Null, Value::Null) => {}
+            (Value::Null, _) => return null_first,
+            (_, Value::Null) => return null_first.reverse(),
+            _ => match value_a.partial_cmp(value_b) {
+                Some(std::cmp::Ordering::Equal) => {}
+                Some(o) => return if *direction == Direction::Ascending { o } else { o.reverse() },
+                None => {}
+            },
+        }
+    }
+    std::cmp::Ordering::Equal
+}
+
+/// A LIMIT executor. With ties, rows following the limit are also returned
+/// as long as their tie expressions evaluate to the same values as for the
+/// last row within the limit, which requires sorted input.
 pub struct Limit<T: Transaction> {
     source: Box<dyn Executor<T>>,
     limit: u64,
+    ties: Vec<Expression>,
 }
 
 impl<T: Transaction> Limit<T> {
-    pub fn new(source: Box<dyn Executor<T>>, limit: u64) -> Box<Self> {
-        Box::new(Self { source, limit })
+    pub fn new(source: Box<dyn Executor<T>>, limit: u64, ties: Vec<Expression>) -> Box<Self> {
+        Box::new(Self { source, limit, ties })
     }
 }
 
 impl<T: Transaction> Executor<T> for Limit<T> {
     fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
-        if let ResultSet::Query { columns, rows } = self.source.execute(txn)? {
-            Ok(ResultSet::Query { columns, rows: Box::new(rows.take(self.limit as usize)) })
-        } else {
-            Err(Error::Internal("Unexpected result".into()))
+        let ResultSet::Query { columns, rows } = self.source.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result".into()));
+        };
+        if self.ties.is_empty() || self.limit == 0 {
+            return Ok(ResultSet::Query {
+                columns,
+                rows: Box::new(rows.take(self.limit as usize)),
+            });
         }
+        let (limit, ties) = (self.limit, self.ties);
+        let evaluate = move |row: &Row| -> Result<Vec<Value>> {
+            ties.iter().map(|e| e.evaluate(Some(row))).collect()
+        };
+        let mut rows = rows.enumerate().map(|(i, r)| (i as u64, r));
+        let mut last = None;
+        let rows = std::iter::from_fn(move || {
+            let (i, row) = rows.next()?;
+            let row = match row {
+                Ok(row) => row,
+                Err(err) => return Some(Err(err)),
+            };
+            if i + 1 < limit {
+                return Some(Ok(row));
+            }
+            let values = match evaluate(&row) {
+                Ok(values) => values,
+                Err(err) => return Some(Err(err)),
+            };
+            match &last {
+                None => last = Some(values),
+                Some(last) if *last == values => {}
+                Some(_) => return None,
+            }
+            Some(Ok(row))
+        });
+        Ok(ResultSet::Query { columns, rows: Box::new(rows.fuse()) })
     }
 }
 
@@ -1,7 +1,15 @@
 use super::super::engine::Transaction;
-use super::super::schema::Table;
+use super::super::plan::Plan;
+use super::super::schema::{
+    split_name, Column, ColumnChange, ColumnStatistics, EnumType, Index, Privilege,
+    ReferenceAction, Sequence, Statistics, Table, Trigger, User, View,
+};
+use super::super::types::{self, Collation, DataType, Expression, Row, Value};
 use super::{Executor, ResultSet};
-use crate::error::Result;
+use crate::error::{Error, Result};
+
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashSet};
 
 /// A CREATE TABLE executor
 pub struct CreateTable {
@@ -22,6 +30,82 @@ impl<T: Transaction> Executor<T> for CreateTable {
     }
 }
 
+/// A CREATE TABLE ... AS executor
+pub struct CreateTableAs<T: Transaction> {
+    table: String,
+    columns: Vec<String>,
+    temporary: bool,
+    source: Box<dyn Executor<T>>,
+}
+
+impl<T: Transaction> CreateTableAs<T> {
+    pub fn new(
+        table: String,
+        columns: Vec<String>,
+        temporary: bool,
+        source: Box<dyn Executor<T>>,
+    ) -> Box<Self> {
+        Box::new(Self { table, columns, temporary, source })
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateTableAs<T> {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let rows = self.source.execute(txn)?.into_rows()?.collect::<Result<Vec<Row>>>()?;
+
+        // Column datatypes are inferred from the query's values, since they aren't known during
+        // planning. Columns without any non-NULL values default to STRING.
+        let mut datatypes: Vec<Option<DataType>> = vec![None; self.columns.len()];
+        for row in &rows {
+            for (i, value) in row.iter().enumerate() {
+                match (&datatypes[i], value.datatype()) {
+                    (_, None) => {}
+                    (None, datatype) => datatypes[i] = datatype,
+                    (Some(a), Some(b)) if *a == b => {}
+                    (Some(a), Some(b)) => {
+                        return Err(Error::Value(format!(
+                            "Column {} has values of both type {} and {}",
+                            self.columns[i], a, b
+                        )))
+                    }
+                }
+            }
+        }
+
+        // The first column is used as the primary key, since tables must have one.
+        let columns = self
+            .columns
+            .into_iter()
+            .zip(datatypes)
+            .enumerate()
+            .map(|(i, (name, datatype))| Column {
+                name,
+                datatype: datatype.unwrap_or(DataType::String),
+                collation: Collation::Binary,
+                primary_key: i == 0,
+                nullable: i > 0,
+                default: (i > 0).then_some(Expression::Constant(Value::Null)),
+                generated: None,
+                unique: i == 0,
+                references: None,
+                on_delete: ReferenceAction::default(),
+                on_update: ReferenceAction::default(),
+                deferred: false,
+                index: false,
+                auto_increment: false,
+                comment: None,
+            })
+            .collect();
+        let mut table = Table::new(self.table.clone(), columns)?;
+        table.temporary = self.temporary;
+        txn.create_table(table)?;
+        for row in rows {
+            txn.create(&self.table, row)?;
+        }
+        Ok(ResultSet::CreateTable { name: self.table })
+    }
+}
+
 /// A DROP TABLE executor
 pub struct DropTable {
     table: String,
@@ -43,3 +127,672 @@ impl<T: Transaction> Executor<T> for DropTable {
         Ok(ResultSet::DropTable { name: self.table, existed: true })
     }
 }
+
+/// An ALTER TABLE ADD COLUMN executor
+pub struct AddColumn {
+    table: String,
+    column: Column,
+}
+
+impl AddColumn {
+    pub fn new(table: String, column: Column) -> Box<Self> {
+        Box::new(Self { table, column })
+    }
+}
+
+impl<T: Transaction> Executor<T> for AddColumn {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        txn.add_column(&self.table, self.column)?;
+        Ok(ResultSet::AlterTable { name: self.table })
+    }
+}
+
+/// An ALTER TABLE DROP COLUMN executor
+pub struct DropColumn {
+    table: String,
+    column: String,
+}
+
+impl DropColumn {
+    pub fn new(table: String, column: String) -> Box<Self> {
+        Box::new(Self { table, column })
+    }
+}
+
+impl<T: Transaction> Executor<T> for DropColumn {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        txn.drop_column(&self.table, &self.column)?;
+        Ok(ResultSet::AlterTable { name: self.table })
+    }
+}
+
+/// An ALTER TABLE ALTER COLUMN executor
+pub struct AlterColumn {
+    table: String,
+    column: String,
+    change: ColumnChange,
+}
+
+impl AlterColumn {
+    pub fn new(table: String, column: String, change: ColumnChange) -> Box<Self> {
+        Box::new(Self { table, column, change })
+    }
+}
+
+impl<T: Transaction> Executor<T> for AlterColumn {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        txn.alter_column(&self.table, &self.column, self.change)?;
+        Ok(ResultSet::AlterTable { name: self.table })
+    }
+}
+
+/// A CREATE INDEX executor
+pub struct CreateIndex {
+    table: String,
+    index: Index,
+}
+
+impl CreateIndex {
+    pub fn new(table: String, index: Index) -> Box<Self> {
+        Box::new(Self { table, index })
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateIndex {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let name = self.index.name.clone();
+        txn.create_index(&self.table, self.index)?;
+        Ok(ResultSet::CreateIndex { name })
+    }
+}
+
+/// A DROP INDEX executor
+pub struct DropIndex {
+    index: String,
+    if_exists: bool,
+}
+
+impl DropIndex {
+    pub fn new(index: String, if_exists: bool) -> Box<Self> {
+        Box::new(Self { index, if_exists })
+    }
+}
+
+impl<T: Transaction> Executor<T> for DropIndex {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        if self.if_exists && txn.read_index_table(&self.index)?.is_none() {
+            return Ok(ResultSet::DropIndex { name: self.index, existed: false });
+        }
+        txn.drop_index(&self.index)?;
+        Ok(ResultSet::DropIndex { name: self.index, existed: true })
+    }
+}
+
+/// A CREATE DATABASE executor
+pub struct CreateDatabase {
+    database: String,
+}
+
+impl CreateDatabase {
+    pub fn new(database: String) -> Box<Self> {
+        Box::new(Self { database })
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateDatabase {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        txn.create_database(&self.database)?;
+        Ok(ResultSet::CreateDatabase { name: self.database })
+    }
+}
+
+/// A CREATE SEQUENCE executor
+pub struct CreateSequence {
+    sequence: Sequence,
+}
+
+impl CreateSequence {
+    pub fn new(sequence: Sequence) -> Box<Self> {
+        Box::new(Self { sequence })
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateSequence {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let name = self.sequence.name.clone();
+        txn.create_sequence(self.sequence)?;
+        Ok(ResultSet::CreateSequence { name })
+    }
+}
+
+/// A DROP SEQUENCE executor
+pub struct DropSequence {
+    sequence: String,
+    if_exists: bool,
+}
+
+impl DropSequence {
+    pub fn new(sequence: String, if_exists: bool) -> Box<Self> {
+        Box::new(Self { sequence, if_exists })
+    }
+}
+
+impl<T: Transaction> Executor<T> for DropSequence {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        if self.if_exists && txn.read_sequence(&self.sequence)?.is_none() {
+            return Ok(ResultSet::DropSequence { name: self.sequence, existed: false });
+        }
+        txn.delete_sequence(&self.sequence)?;
+        Ok(ResultSet::DropSequence { name: self.sequence, existed: true })
+    }
+}
+
+/// A CREATE TYPE executor
+pub struct CreateType {
+    enum_type: EnumType,
+}
+
+impl CreateType {
+    pub fn new(enum_type: EnumType) -> Box<Self> {
+        Box::new(Self { enum_type })
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateType {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let name = self.enum_type.name.clone();
+        txn.create_type(self.enum_type)?;
+        Ok(ResultSet::CreateType { name })
+    }
+}
+
+/// A DROP TYPE executor
+pub struct DropType {
+    name: String,
+    if_exists: bool,
+}
+
+impl DropType {
+    pub fn new(name: String, if_exists: bool) -> Box<Self> {
+        Box::new(Self { name, if_exists })
+    }
+}
+
+impl<T: Transaction> Executor<T> for DropType {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        if self.if_exists && txn.read_type(&self.name)?.is_none() {
+            return Ok(ResultSet::DropType { name: self.name, existed: false });
+        }
+        txn.delete_type(&self.name)?;
+        Ok(ResultSet::DropType { name: self.name, existed: true })
+    }
+}
+
+/// A CREATE USER executor
+pub struct CreateUser {
+    user: User,
+}
+
+impl CreateUser {
+    pub fn new(user: User) -> Box<Self> {
+        Box::new(Self { user })
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateUser {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let name = self.user.name.clone();
+        txn.create_user(self.user)?;
+        Ok(ResultSet::CreateUser { name })
+    }
+}
+
+/// An ALTER USER executor
+pub struct AlterUser {
+    user: User,
+}
+
+impl AlterUser {
+    pub fn new(user: User) -> Box<Self> {
+        Box::new(Self { user })
+    }
+}
+
+impl<T: Transaction> Executor<T> for AlterUser {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let mut user = self.user;
+        let name = user.name.clone();
+        // Only the password is altered, so roles remain roles.
+        if let Some(existing) = txn.read_user(&name)? {
+            user.login = existing.login;
+            user.roles = existing.roles;
+        }
+        txn.alter_user(user)?;
+        Ok(ResultSet::AlterUser { name })
+    }
+}
+
+/// A DROP USER executor
+pub struct DropUser {
+    name: String,
+    if_exists: bool,
+}
+
+impl DropUser {
+    pub fn new(name: String, if_exists: bool) -> Box<Self> {
+        Box::new(Self { name, if_exists })
+    }
+}
+
+impl<T: Transaction> Executor<T> for DropUser {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        if self.if_exists && txn.read_user(&self.name)?.is_none() {
+            return Ok(ResultSet::DropUser { name: self.name, existed: false });
+        }
+        // Owned tables, views, sequences, types and triggers must be dropped first. Privileges
+        // granted to the user and memberships of the role are removed, such
+        // that they don't apply to a new user with the same name.
+        let owner = Some(self.name.as_str());
+        if let Some(view) = txn.scan_views()?.find(|v| v.owner.as_deref() == owner) {
+            return Err(Error::Value(format!("User {} owns view {}", self.name, view.name)));
+        }
+        if let Some(sequence) =
+            txn.scan_sequences()?.into_iter().find(|s| s.owner.as_deref() == owner)
+        {
+            return Err(Error::Value(format!(
+                "User {} owns sequence {}",
+                self.name, sequence.name
+            )));
+        }
+        if let Some(enum_type) = txn.scan_types()?.into_iter().find(|t| t.owner.as_deref() == owner)
+        {
+            return Err(Error::Value(format!("User {} owns type {}", self.name, enum_type.name)));
+        }
+        for table in txn.scan_tables()? {
+            if table.owner.as_deref() == owner {
+                return Err(Error::Value(format!("User {} owns table {}", self.name, table.name)));
+            }
+            if let Some(trigger) = table.triggers.iter().find(|t| t.owner.as_deref() == owner) {
+                return Err(Error::Value(format!(
+                    "User {} owns trigger {} on table {}",
+                    self.name, trigger.name, table.name
+                )));
+            }
+            if table.privileges.contains_key(&self.name) {
+                txn.set_privileges(&table.name, &self.name, BTreeSet::new())?;
+            }
+        }
+        for mut user in txn.scan_users()? {
+            if user.roles.remove(&self.name) {
+                txn.alter_user(user)?;
+            }
+        }
+        txn.delete_user(&self.name)?;
+        Ok(ResultSet::DropUser { name: self.name, existed: true })
+    }
+}
+
+/// A CREATE ROLE executor
+pub struct CreateRole {
+    role: User,
+}
+
+impl CreateRole {
+    pub fn new(role: User) -> Box<Self> {
+        Box::new(Self { role })
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateRole {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let name = self.role.name.clone();
+        txn.create_user(self.role)?;
+        Ok(ResultSet::CreateRole { name })
+    }
+}
+
+/// A GRANT or REVOKE executor for table privileges
+pub struct Grant {
+    table: String,
+    grantee: String,
+    privileges: BTreeSet<Privilege>,
+    revoke: bool,
+}
+
+impl Grant {
+    pub fn new(
+        table: String,
+        grantee: String,
+        privileges: BTreeSet<Privilege>,
+        revoke: bool,
+    ) -> Box<Self> {
+        Box::new(Self { table, grantee, privileges, revoke })
+    }
+}
+
+impl<T: Transaction> Executor<T> for Grant {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        if txn.read_user(&self.grantee)?.is_none() {
+            return Err(Error::Value(format!("User {} does not exist", self.grantee)));
+        }
+        let table = txn.must_read_table(&self.table)?;
+        let mut privileges = table.privileges.get(&self.grantee).cloned().unwrap_or_default();
+        match self.revoke {
+            true => privileges.retain(|p| !self.privileges.contains(p)),
+            false => privileges.extend(self.privileges),
+        }
+        txn.set_privileges(&table.name, &self.grantee, privileges)?;
+        Ok(match self.revoke {
+            true => ResultSet::Revoke { grantee: self.grantee },
+            false => ResultSet::Grant { grantee: self.grantee },
+        })
+    }
+}
+
+/// A GRANT or REVOKE executor for roles
+pub struct GrantRole {
+    role: String,
+    user: String,
+    revoke: bool,
+}
+
+impl GrantRole {
+    pub fn new(role: String, user: String, revoke: bool) -> Box<Self> {
+        Box::new(Self { role, user, revoke })
+    }
+}
+
+impl<T: Transaction> Executor<T> for GrantRole {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        match txn.read_user(&self.role)? {
+            Some(role) if !role.login => {}
+            Some(_) => return Err(Error::Value(format!("User {} is not a role", self.role))),
+            None => return Err(Error::Value(format!("Role {} does not exist", self.role))),
+        }
+        let Some(mut user) = txn.read_user(&self.user)? else {
+            return Err(Error::Value(format!("User {} does not exist", self.user)));
+        };
+        match self.revoke {
+            true => user.roles.remove(&self.role),
+            false => user.roles.insert(self.role),
+        };
+        txn.alter_user(user)?;
+        Ok(match self.revoke {
+            true => ResultSet::Revoke { grantee: self.user },
+            false => ResultSet::Grant { grantee: self.user },
+        })
+    }
+}
+
+/// A CREATE VIEW executor
+pub struct CreateView {
+    view: View,
+}
+
+impl CreateView {
+    pub fn new(view: View) -> Box<Self> {
+        Box::new(Self { view })
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateView {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let name = self.view.name.clone();
+        txn.create_view(self.view)?;
+        Ok(ResultSet::CreateView { name })
+    }
+}
+
+/// A DROP VIEW executor
+pub struct DropView {
+    view: String,
+    if_exists: bool,
+}
+
+impl DropView {
+    pub fn new(view: String, if_exists: bool) -> Box<Self> {
+        Box::new(Self { view, if_exists })
+    }
+}
+
+impl<T: Transaction> Executor<T> for DropView {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        if self.if_exists && txn.read_view(&self.view)?.is_none() {
+            return Ok(ResultSet::DropView { name: self.view, existed: false });
+        }
+        txn.delete_view(&self.view)?;
+        Ok(ResultSet::DropView { name: self.view, existed: true })
+    }
+}
+
+/// A CREATE TRIGGER executor
+pub struct CreateTrigger {
+    table: String,
+    trigger: Trigger,
+}
+
+impl CreateTrigger {
+    pub fn new(table: String, trigger: Trigger) -> Box<Self> {
+        Box::new(Self { table, trigger })
+    }
+}
+
+impl<T: Transaction> Executor<T> for CreateTrigger {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        // The action is authorized as its creator, who owns the trigger.
+        if let Some(user) = txn.user().map(str::to_string) {
+            let table = txn.must_read_table(&self.table)?;
+            Plan::build_trigger(&table, &self.trigger, txn)?.authorize(txn, &user)?;
+        }
+        let name = self.trigger.name.clone();
+        txn.create_trigger(&self.table, self.trigger)?;
+        Ok(ResultSet::CreateTrigger { name })
+    }
+}
+
+/// A DROP TRIGGER executor
+pub struct DropTrigger {
+    table: String,
+    trigger: String,
+    if_exists: bool,
+}
+
+impl DropTrigger {
+    pub fn new(table: String, trigger: String, if_exists: bool) -> Box<Self> {
+        Box::new(Self { table, trigger, if_exists })
+    }
+}
+
+impl<T: Transaction> Executor<T> for DropTrigger {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let exists = txn
+            .read_table(&self.table)?
+            .is_some_and(|t| t.triggers.iter().any(|t| t.name == self.trigger));
+        if self.if_exists && !exists {
+            return Ok(ResultSet::DropTrigger { name: self.trigger, existed: false });
+        }
+        txn.drop_trigger(&self.table, &self.trigger)?;
+        Ok(ResultSet::DropTrigger { name: self.trigger, existed: true })
+    }
+}
+
+/// A COMMENT ON TABLE or COMMENT ON COLUMN executor
+pub struct Comment {
+    table: String,
+    column: Option<String>,
+    comment: Option<String>,
+}
+
+impl Comment {
+    pub fn new(table: String, column: Option<String>, comment: Option<String>) -> Box<Self> {
+        Box::new(Self { table, column, comment })
+    }
+}
+
+impl<T: Transaction> Executor<T> for Comment {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        txn.set_comment(&self.table, self.column.as_deref(), self.comment)?;
+        let name = match self.column {
+            Some(column) => format!("{}.{}", self.table, column),
+            None => self.table,
+        };
+        Ok(ResultSet::Comment { name })
+    }
+}
+
+/// A SHOW TABLES executor
+pub struct ShowTables {
+    database: String,
+}
+
+impl ShowTables {
+    pub fn new(database: String) -> Box<Self> {
+        Box::new(Self { database })
+    }
+}
+
+impl<T: Transaction> Executor<T> for ShowTables {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        // Only tables that the user has any privilege on are listed.
+        let mut rows = Vec::new();
+        for table in txn.scan_tables()? {
+            let (database, name) = split_name(&table.name);
+            if database != self.database && !table.temporary {
+                continue;
+            }
+            if let Some(user) = txn.user() {
+                if !txn.has_privilege(user, &table, None)? {
+                    continue;
+                }
+            }
+            rows.push(vec![Value::String(name.to_string())]);
+        }
+        Ok(ResultSet::Query {
+            columns: vec![types::Column { name: Some("table".into()) }],
+            rows: Box::new(rows.into_iter().map(Ok)),
+        })
+    }
+}
+
+/// A SHOW CREATE TABLE executor
+pub struct ShowCreateTable {
+    table: String,
+}
+
+impl ShowCreateTable {
+    pub fn new(table: String) -> Box<Self> {
+        Box::new(Self { table })
+    }
+}
+
+impl<T: Transaction> Executor<T> for ShowCreateTable {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let table = txn.must_read_table(&self.table)?;
+        let row = vec![Value::String(table.name.clone()), Value::String(table.to_string())];
+        Ok(ResultSet::Query {
+            columns: vec![
+                types::Column { name: Some("table".into()) },
+                types::Column { name: Some("create_table".into()) },
+            ],
+            rows: Box::new(std::iter::once(Ok(row))),
+        })
+    }
+}
+
+/// A DESCRIBE executor, which returns a row for each table column
+pub struct Describe {
+    table: String,
+}
+
+impl Describe {
+    pub fn new(table: String) -> Box<Self> {
+        Box::new(Self { table })
+    }
+}
+
+impl<T: Transaction> Executor<T> for Describe {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let table = txn.must_read_table(&self.table)?;
+        let string = |s: Option<String>| s.map(Value::String).unwrap_or(Value::Null);
+        let rows: Vec<Row> = table
+            .columns
+            .into_iter()
+            .map(|c| {
+                let key = match c {
+                    Column { primary_key: true, .. } => Some("PRIMARY KEY"),
+                    Column { unique: true, .. } => Some("UNIQUE"),
+                    Column { index: true, .. } => Some("INDEX"),
+                    _ => None,
+                };
+                let default = match c.auto_increment {
+                    true => Some("AUTO_INCREMENT".to_string()),
+                    false => c.default.map(|d| d.to_string()),
+                };
+                vec![
+                    Value::String(c.name),
+                    Value::String(c.datatype.to_string()),
+                    Value::Boolean(c.nullable),
+                    string(default),
+                    string(key.map(|k| k.to_string())),
+                    string(c.references),
+                ]
+            })
+            .collect();
+        Ok(ResultSet::Query {
+            columns: ["column", "type", "nullable", "default", "key", "references"]
+                .into_iter()
+                .map(|name| types::Column { name: Some(name.into()) })
+                .collect(),
+            rows: Box::new(rows.into_iter().map(Ok)),
+        })
+    }
+}
+
+/// An ANALYZE executor, which scans each table and stores its statistics
+pub struct AnalyzeTables {
+    tables: Vec<String>,
+}
+
+impl AnalyzeTables {
+    /// The maximum number of histogram buckets per column.
+    const HISTOGRAM_BUCKETS: usize = 10;
+
+    pub fn new(tables: Vec<String>) -> Box<Self> {
+        Box::new(Self { tables })
+    }
+
+    /// Computes the statistics of a table's rows.
+    fn collect(table: &Table, rows: Vec<Row>) -> Statistics {
+        let mut statistics = Statistics { rows: rows.len() as u64, ..Default::default() };
+        for (i, column) in table.columns.iter().enumerate() {
+            let mut values: Vec<&Value> = rows.iter().map(|row| &row[i]).collect();
+            let nulls = values.len();
+            values.retain(|v| **v != Value::Null);
+            let nulls = (nulls - values.len()) as u64;
+            let distinct = values.iter().collect::<HashSet<_>>().len() as u64;
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            let buckets = Self::HISTOGRAM_BUCKETS.min(values.len().saturating_sub(1));
+            let histogram = match values.len() {
+                0 => Vec::new(),
+                1 => vec![values[0].clone()],
+                n => (0..=buckets).map(|b| values[b * (n - 1) / buckets].clone()).collect(),
+            };
+            statistics
+                .columns
+                .insert(column.name.clone(), ColumnStatistics { distinct, nulls, histogram });
+        }
+        statistics
+    }
+}
+
+impl<T: Transaction> Executor<T> for AnalyzeTables {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        for name in &self.tables {
+            let table = txn.must_read_table(name)?;
+            let rows =
+                txn.scan(&table.name, None, false, None, None)?.collect::<Result<Vec<_>>>()?;
+            txn.write_statistics(&table.name, Self::collect(&table, rows))?;
+        }
+        Ok(ResultSet::Analyze { tables: self.tables })
+    }
+}
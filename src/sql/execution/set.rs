@@ -0,0 +1,122 @@
+use super::super::engine::Transaction;
+use super::super::plan::SetOperator;
+use super::{Executor, ResultSet, Row, Value};
+use crate::error::{Error, Result};
+
+use std::collections::{HashMap, HashSet};
+
+/// A set operation executor, which combines the rows of two sources. Both
+/// sources are fully materialized, and duplicates are detected by hashing.
+/// Rows are emitted in the order they are first seen in the left source,
+/// followed by the right source for UNION.
+pub struct SetOperation<T: Transaction> {
+    left: Box<dyn Executor<T>>,
+    right: Box<dyn Executor<T>>,
+    operator: SetOperator,
+    all: bool,
+}
+
+impl<T: Transaction> SetOperation<T> {
+    pub fn new(
+        left: Box<dyn Executor<T>>,
+        right: Box<dyn Executor<T>>,
+        operator: SetOperator,
+        all: bool,
+    ) -> Box<Self> {
+        Box::new(Self { left, right, operator, all })
+    }
+}
+
+impl<T: Transaction> Executor<T> for SetOperation<T> {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let ResultSet::Query { columns, rows } = self.left.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result set".into()));
+        };
+        let mut left: Vec<Row> = rows.collect::<Result<_>>()?;
+        let ResultSet::Query { columns: right_columns, rows } = self.right.execute(txn)? else {
+            return Err(Error::Internal("Unexpected result set".into()));
+        };
+        let mut right: Vec<Row> = rows.collect::<Result<_>>()?;
+        if columns.len() != right_columns.len() {
+            return Err(Error::Internal("Set operation column count mismatch".into()));
+        }
+        unify(&self.operator, columns.len(), &mut left, &mut right)?;
+
+        let rows: Vec<Row> = match (self.operator, self.all) {
+            (SetOperator::Union, true) => left.into_iter().chain(right).collect(),
+            (SetOperator::Union, false) => {
+                let mut seen = HashSet::new();
+                left.into_iter().chain(right).filter(|row| seen.insert(row.clone())).collect()
+            }
+            (SetOperator::Intersect, all) => {
+                let mut counts = count(right);
+                left.into_iter()
+                    .filter(|row| match counts.get_mut(row) {
+                        Some(count) if *count > 0 => {
+                            // Without ALL, only emit the first occurrence.
+                            *count = if all { *count - 1 } else { 0 };
+                            true
+                        }
+                        _ => false,
+                    })
+                    .collect()
+            }
+            (SetOperator::Except, true) => {
+                let mut counts = count(right);
+                left.into_iter()
+                    .filter(|row| match counts.get_mut(row) {
+                        Some(count) if *count > 0 => {
+                            *count -= 1;
+                            false
+                        }
+                        _ => true,
+                    })
+                    .collect()
+            }
+            (SetOperator::Except, false) => {
+                let mut seen: HashSet<Row> = right.into_iter().collect();
+                left.into_iter().filter(|row| seen.insert(row.clone())).collect()
+            }
+        };
+        Ok(ResultSet::Query { columns, rows: Box::new(rows.into_iter().map(Ok)) })
+    }
+}
+
+/// Counts the occurrences of each row.
+fn count(rows: Vec<Row>) -> HashMap<Row, usize> {
+    let mut counts = HashMap::new();
+    for row in rows {
+        *counts.entry(row).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Unifies the datatypes of each column across the left and right rows, as
+/// given by their first non-NULL value, such that equal values compare equal.
+/// Numeric columns of different types are converted to the widest type, in
+/// the order INTEGER, DECIMAL, FLOAT. Other mismatched types error.
+fn unify(operator: &SetOperator, width: usize, left: &mut [Row], right: &mut [Row]) -> Result<()> {
+    for i in 0..width {
+        let left_type = left.iter().find_map(|row| row[i].datatype());
+        let right_type = right.iter().find_map(|row| row[i].datatype());
+        let (Some(left_type), Some(right_type)) = (left_type, right_type) else {
+            continue;
+        };
+        if left_type == right_type {
+            continue;
+        }
+        let Some(datatype) = left_type.common(&right_type) else {
+            return Err(Error::Value(format!(
+                "{} types {} and {} cannot be matched",
+                operator.to_string().to_uppercase(),
+                left_type,
+                right_type
+            )));
+        };
+        for row in left.iter_mut().chain(right.iter_mut()) {
+            let value = std::mem::replace(&mut row[i], Value::Null);
+            row[i] = value.cast(&datatype)?;
+        }
+    }
+    Ok(())
+}
@@ -1,19 +1,29 @@
-use super::super::engine::Transaction;
+use super::super::engine::{IndexRange, Transaction};
+use super::super::schema::InformationSchema as Table;
 use super::super::types::{Column, Expression, Row, Value};
 use super::{Executor, ResultSet};
-use crate::error::Result;
+use crate::error::{Error, Result};
 
 use std::collections::HashSet;
+use std::ops::Bound;
 
-/// A table scan executor
+/// A table scan executor, which stops after limit rows if given, and only
+/// decodes the given columns if any
 pub struct Scan {
     table: String,
     filter: Option<Expression>,
+    limit: Option<u64>,
+    columns: Option<Vec<usize>>,
 }
 
 impl Scan {
-    pub fn new(table: String, filter: Option<Expression>) -> Box<Self> {
-        Box::new(Self { table, filter })
+    pub fn new(
+        table: String,
+        filter: Option<Expression>,
+        limit: Option<u64>,
+        columns: Option<Vec<usize>>,
+    ) -> Box<Self> {
+        Box::new(Self { table, filter, limit, columns })
     }
 }
 
@@ -22,7 +32,68 @@ impl<T: Transaction> Executor<T> for Scan {
         let table = txn.must_read_table(&self.table)?;
         Ok(ResultSet::Query {
             columns: table.columns.iter().map(|c| Column { name: Some(c.name.clone()) }).collect(),
-            rows: Box::new(txn.scan(&table.name, self.filter)?),
+            rows: Box::new(txn.scan(&table.name, self.filter, false, self.limit, self.columns)?),
+        })
+    }
+}
+
+/// A reverse table scan executor, which emits rows in descending primary key
+/// order, stops after limit rows if given, and only decodes the given columns
+/// if any
+pub struct ReverseScan {
+    table: String,
+    filter: Option<Expression>,
+    limit: Option<u64>,
+    columns: Option<Vec<usize>>,
+}
+
+impl ReverseScan {
+    pub fn new(
+        table: String,
+        filter: Option<Expression>,
+        limit: Option<u64>,
+        columns: Option<Vec<usize>>,
+    ) -> Box<Self> {
+        Box::new(Self { table, filter, limit, columns })
+    }
+}
+
+impl<T: Transaction> Executor<T> for ReverseScan {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let table = txn.must_read_table(&self.table)?;
+        Ok(ResultSet::Query {
+            columns: table.columns.iter().map(|c| Column { name: Some(c.name.clone()) }).collect(),
+            rows: Box::new(txn.scan(&table.name, self.filter, true, self.limit, self.columns)?),
+        })
+    }
+}
+
+/// A parallel table scan executor, which decodes and filters rows on up to
+/// the given number of worker threads, and emits them in primary key order
+pub struct ParallelScan {
+    table: String,
+    filter: Option<Expression>,
+    columns: Option<Vec<usize>>,
+    workers: usize,
+}
+
+impl ParallelScan {
+    pub fn new(
+        table: String,
+        filter: Option<Expression>,
+        columns: Option<Vec<usize>>,
+        workers: usize,
+    ) -> Box<Self> {
+        Box::new(Self { table, filter, columns, workers })
+    }
+}
+
+impl<T: Transaction> Executor<T> for ParallelScan {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let table = txn.must_read_table(&self.table)?;
+        Ok(ResultSet::Query {
+            columns: table.columns.iter().map(|c| Column { name: Some(c.name.clone()) }).collect(),
+            rows: txn.scan_parallel(&table.name, self.filter, self.columns, self.workers)?,
         })
     }
 }
@@ -60,13 +131,13 @@ impl<T: Transaction> Executor<T> for KeyLookup {
 /// An index value lookup executor
 pub struct IndexLookup {
     table: String,
-    column: String,
-    values: Vec<Value>,
+    index: String,
+    values: Vec<Vec<Value>>,
 }
 
 impl IndexLookup {
-    pub fn new(table: String, column: String, values: Vec<Value>) -> Box<Self> {
-        Box::new(Self { table, column, values })
+    pub fn new(table: String, index: String, values: Vec<Vec<Value>>) -> Box<Self> {
+        Box::new(Self { table, index, values })
     }
 }
 
@@ -76,8 +147,151 @@ impl<T: Transaction> Executor<T> for IndexLookup {
 
         let mut pks: HashSet<Value> = HashSet::new();
         for value in self.values {
-            pks.extend(txn.read_index(&self.table, &self.column, &value)?);
+            pks.extend(txn.read_index(&self.table, &self.index, &value)?);
+        }
+
+        // FIXME Is there a way to pass the txn into an iterator closure instead?
+        let rows = pks
+            .into_iter()
+            .filter_map(|pk| txn.read(&table.name, &pk).transpose())
+            .collect::<Result<Vec<Row>>>()?;
+
+        Ok(ResultSet::Query {
+            columns: table.columns.iter().map(|c| Column { name: Some(c.name.clone()) }).collect(),
+            rows: Box::new(rows.into_iter().map(Ok)),
+        })
+    }
+}
+
+/// An index intersection executor, which looks up the rows matching all of the
+/// given index lookups by intersecting the primary keys of each lookup.
+pub struct IndexIntersection {
+    table: String,
+    lookups: Vec<(String, Vec<Vec<Value>>)>,
+}
+
+impl IndexIntersection {
+    pub fn new(table: String, lookups: Vec<(String, Vec<Vec<Value>>)>) -> Box<Self> {
+        Box::new(Self { table, lookups })
+    }
+}
+
+impl<T: Transaction> Executor<T> for IndexIntersection {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let table = txn.must_read_table(&self.table)?;
+
+        let mut pks: Option<HashSet<Value>> = None;
+        for (index, values) in self.lookups {
+            let mut lookup: HashSet<Value> = HashSet::new();
+            for value in values {
+                lookup.extend(txn.read_index(&self.table, &index, &value)?);
+            }
+            if let Some(pks) = pks.as_mut() {
+                pks.retain(|pk| lookup.contains(pk));
+            } else {
+                pks = Some(lookup);
+            }
+            // Skip the remaining lookups once nothing can match.
+            if pks.as_ref().is_some_and(|pks| pks.is_empty()) {
+                break;
+            }
+        }
+
+        let rows = pks
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|pk| txn.read(&table.name, &pk).transpose())
+            .collect::<Result<Vec<Row>>>()?;
+
+        Ok(ResultSet::Query {
+            columns: table.columns.iter().map(|c| Column { name: Some(c.name.clone()) }).collect(),
+            rows: Box::new(rows.into_iter().map(Ok)),
+        })
+    }
+}
+
+/// An index-only scan executor, which reads rows from index entries alone
+/// without reading the table rows. Columns that aren't stored in the index are
+/// NULL, so the plan must not reference them. Stops after limit rows if given.
+pub struct IndexOnlyScan {
+    table: String,
+    index: String,
+    ranges: Vec<IndexRange>,
+    limit: Option<u64>,
+}
+
+impl IndexOnlyScan {
+    pub fn new(
+        table: String,
+        index: String,
+        ranges: Vec<IndexRange>,
+        limit: Option<u64>,
+    ) -> Box<Self> {
+        Box::new(Self { table, index, ranges, limit })
+    }
+}
+
+impl<T: Transaction> Executor<T> for IndexOnlyScan {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let table = txn.must_read_table(&self.table)?;
+        let index = table.get_index(&self.index)?;
+        let pk = table.get_column_index(&table.get_primary_key()?.name)?;
+        let columns =
+            index.columns.iter().map(|c| table.get_column_index(c)).collect::<Result<Vec<_>>>()?;
+        let include =
+            index.include.iter().map(|c| table.get_column_index(c)).collect::<Result<Vec<_>>>()?;
+
+        // Ranges may overlap, e.g. for duplicate lookup values, so skip rows
+        // that have already been read. With a limit, reading limit entries per
+        // range is sufficient, since any entries past them in a range can only
+        // be reached once limit rows have been read.
+        let limit = self.limit.map_or(usize::MAX, |l| l as usize);
+        let mut seen = HashSet::new();
+        let mut rows = Vec::new();
+        for range in self.ranges {
+            if rows.len() >= limit {
+                break;
+            }
+            let entries = txn.read_index_entries(&table.name, &index.name, range, self.limit)?;
+            for (values, id, included) in entries {
+                if !seen.insert(id.clone()) {
+                    continue;
+                }
+                let mut row = vec![Value::Null; table.columns.len()];
+                for (i, value) in columns.iter().zip(values).chain(include.iter().zip(included)) {
+                    row[*i] = value;
+                }
+                row[pk] = id;
+                rows.push(row);
+            }
         }
+        rows.truncate(limit);
+
+        Ok(ResultSet::Query {
+            columns: table.columns.iter().map(|c| Column { name: Some(c.name.clone()) }).collect(),
+            rows: Box::new(rows.into_iter().map(Ok)),
+        })
+    }
+}
+
+/// An index range scan executor, which looks up the rows whose value of the
+/// index's first column is in the given range, in index order
+pub struct IndexRangeScan {
+    table: String,
+    index: String,
+    range: (Bound<Value>, Bound<Value>),
+}
+
+impl IndexRangeScan {
+    pub fn new(table: String, index: String, range: (Bound<Value>, Bound<Value>)) -> Box<Self> {
+        Box::new(Self { table, index, range })
+    }
+}
+
+impl<T: Transaction> Executor<T> for IndexRangeScan {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let table = txn.must_read_table(&self.table)?;
+        let pks = txn.read_index_range(&self.table, &self.index, self.range)?;
 
         // FIXME Is there a way to pass the txn into an iterator closure instead?
         let rows = pks
@@ -109,3 +323,92 @@ impl<T: Transaction> Executor<T> for Nothing {
         })
     }
 }
+
+/// An information_schema virtual table scan, which generates the rows from
+/// the catalog
+pub struct InformationSchema {
+    table: Table,
+}
+
+impl InformationSchema {
+    pub fn new(table: Table) -> Box<Self> {
+        Box::new(Self { table })
+    }
+}
+
+impl<T: Transaction> Executor<T> for InformationSchema {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let rows = self.table.rows(txn, txn.user())?;
+        Ok(ResultSet::Query {
+            columns: self
+                .table
+                .columns()
+                .iter()
+                .map(|(name, _)| Column { name: Some(name.to_string()) })
+                .collect(),
+            rows: Box::new(rows.into_iter().map(Ok)),
+        })
+    }
+}
+
+/// An executor that produces a row for each element of an array
+pub struct Unnest {
+    expression: Expression,
+    column: String,
+}
+
+impl Unnest {
+    pub fn new(expression: Expression, column: String) -> Box<Self> {
+        Box::new(Self { expression, column })
+    }
+}
+
+impl<T: Transaction> Executor<T> for Unnest {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        // A NULL array has no elements.
+        let elements = match self.expression.evaluate_sequences(None, txn)? {
+            Value::Array(elements) => elements,
+            Value::Null => Vec::new(),
+            value => return Err(Error::Value(format!("Can't unnest {}", value))),
+        };
+        Ok(ResultSet::Query {
+            columns: vec![Column { name: Some(self.column) }],
+            rows: Box::new(elements.into_iter().map(|value| Ok(vec![value]))),
+        })
+    }
+}
+
+/// An executor that produces rows from a VALUES list
+pub struct Values {
+    columns: Vec<String>,
+    rows: Vec<Vec<Expression>>,
+}
+
+impl Values {
+    pub fn new(columns: Vec<String>, rows: Vec<Vec<Expression>>) -> Box<Self> {
+        Box::new(Self { columns, rows })
+    }
+}
+
+impl<T: Transaction> Executor<T> for Values {
+    fn execute(self: Box<Self>, txn: &mut T) -> Result<ResultSet> {
+        let columns = self.columns.into_iter().map(|name| Column { name: Some(name) }).collect();
+        // Sequence functions need the transaction, so the rows are evaluated here.
+        if self.rows.iter().flatten().any(Expression::calls_sequences) {
+            let rows = self
+                .rows
+                .iter()
+                .map(|exprs| exprs.iter().map(|e| e.evaluate_sequences(None, txn)).collect())
+                .collect::<Result<Vec<Row>>>()?;
+            return Ok(ResultSet::Query { columns, rows: Box::new(rows.into_iter().map(Ok)) });
+        }
+        Ok(ResultSet::Query {
+            columns,
+            rows: Box::new(
+                self.rows
+                    .into_iter()
+                    .map(|exprs| exprs.into_iter().map(|e| e.evaluate(None)).collect()),
+            ),
+        })
+    }
+}
@@ -1,4 +1,5 @@
-use super::super::types::DataType;
+use super::super::schema::{ColumnChange, Privilege, ReferenceAction, TriggerEvent, TriggerTiming};
+use super::super::types::{Collation, DataType};
 use crate::error::Result;
 
 use std::collections::BTreeMap;
@@ -9,49 +10,352 @@ use std::mem::replace;
 #[allow(clippy::large_enum_variant)]
 pub enum Statement {
     Begin {
-        read_only: bool,
-        as_of: Option<u64>,
+        /// None if not given, using the session default.
+        read_only: Option<bool>,
+        /// The version or time to run a read-only transaction as of, given by
+        /// AS OF SYSTEM TIME.
+        as_of: Option<Expression>,
     },
     Commit,
     Rollback,
-    Explain(Box<Statement>),
+    Savepoint {
+        name: String,
+    },
+    RollbackToSavepoint {
+        name: String,
+    },
+    ReleaseSavepoint {
+        name: String,
+    },
+    /// Sets a session setting, or resets it to its default if None.
+    Set {
+        name: String,
+        value: Option<Expression>,
+    },
+    ShowSetting {
+        name: String,
+    },
+    ShowAll,
+    /// Lists the queries running on the server.
+    ShowQueries,
+    /// Cancels a running query.
+    Kill {
+        id: u64,
+    },
+    /// Lists the databases.
+    ShowDatabases,
+    /// Sets the session's current database.
+    Use {
+        database: String,
+    },
+    /// A query run in its own read-only transaction as of a past version or
+    /// time, given by a trailing AS OF SYSTEM TIME clause.
+    AsOf {
+        query: Box<Statement>,
+        as_of: Expression,
+    },
+    Explain {
+        statement: Box<Statement>,
+        analyze: bool,
+        /// Whether to show estimated rows and cost for each node.
+        verbose: bool,
+        format: ExplainFormat,
+    },
 
+    CreateDatabase {
+        name: String,
+    },
     CreateTable {
         name: String,
         columns: Vec<Column>,
+        /// Table-level UNIQUE constraints, as column lists
+        unique: Vec<Vec<String>>,
+        temporary: bool,
     },
     DropTable {
         name: String,
         if_exists: bool,
     },
+    AddColumn {
+        table: String,
+        column: Column,
+    },
+    CreateIndex {
+        /// The index name, or None to name it after the table and keys.
+        name: Option<String>,
+        table: String,
+        /// The index keys, as Field(None, column) for columns, or any other
+        /// expression for a parenthesized key expression.
+        columns: Vec<Expression>,
+        unique: bool,
+        /// Whether this is a FULLTEXT index.
+        fulltext: bool,
+        /// Non-key columns to store in the index, via INCLUDE.
+        include: Vec<String>,
+    },
+    DropIndex {
+        name: String,
+        if_exists: bool,
+    },
+    DropColumn {
+        table: String,
+        column: String,
+    },
+    AlterColumn {
+        table: String,
+        column: String,
+        change: ColumnChange,
+    },
+    CreateTableAs {
+        name: String,
+        query: Box<Statement>,
+        temporary: bool,
+    },
+    CreateView {
+        name: String,
+        /// Column names for the query's output columns, if given
+        columns: Vec<String>,
+        query: Box<Statement>,
+        /// The query's SQL text, as stored in the catalog
+        sql: String,
+    },
+    DropView {
+        name: String,
+        if_exists: bool,
+    },
+    /// Creates a sequence. The start defaults to 1 and the increment to 1.
+    CreateSequence {
+        name: String,
+        start: Option<i64>,
+        increment: Option<i64>,
+    },
+    DropSequence {
+        name: String,
+        if_exists: bool,
+    },
+    /// Creates an enum type with the given labels.
+    CreateType {
+        name: String,
+        labels: Vec<String>,
+    },
+    DropType {
+        name: String,
+        if_exists: bool,
+    },
+    /// Creates a user, with an optional password.
+    CreateUser {
+        name: String,
+        password: Option<String>,
+    },
+    /// Sets or removes (if None) a user's password.
+    AlterUser {
+        name: String,
+        password: Option<String>,
+    },
+    DropUser {
+        name: String,
+        if_exists: bool,
+    },
+    /// Creates a role, i.e. a user that can't authenticate.
+    CreateRole {
+        name: String,
+    },
+    /// Grants privileges on a table to a user or role.
+    Grant {
+        privileges: Vec<Privilege>,
+        table: String,
+        grantee: String,
+    },
+    /// Revokes privileges on a table from a user or role.
+    Revoke {
+        privileges: Vec<Privilege>,
+        table: String,
+        grantee: String,
+    },
+    /// Grants a role to a user.
+    GrantRole {
+        role: String,
+        user: String,
+    },
+    /// Revokes a role from a user.
+    RevokeRole {
+        role: String,
+        user: String,
+    },
+    /// Creates a row-level trigger on a table.
+    CreateTrigger {
+        name: String,
+        table: String,
+        timing: TriggerTiming,
+        event: TriggerEvent,
+        action: TriggerAction,
+        /// The action's SQL text, as stored in the catalog
+        sql: String,
+    },
+    DropTrigger {
+        name: String,
+        table: String,
+        if_exists: bool,
+    },
+    /// Sets the comment on a table, or on a column if given. A None comment
+    /// removes it.
+    Comment {
+        table: String,
+        column: Option<String>,
+        comment: Option<String>,
+    },
 
+    /// Bulk-loads rows into a table from a file on the server, or from input
+    /// streamed by the client for STDIN.
+    CopyFrom {
+        table: String,
+        columns: Option<Vec<String>>,
+        source: CopySource,
+        options: CopyOptions,
+    },
+    /// Exports the rows of a query, or all rows of a table, to a file on the
+    /// server.
+    CopyTo {
+        query: Box<Statement>,
+        path: String,
+        options: CopyOptions,
+    },
     Delete {
         table: String,
+        using: Vec<FromItem>,
         r#where: Option<Expression>,
+        returning: Option<Returning>,
     },
     Insert {
         table: String,
         columns: Option<Vec<String>>,
         values: Vec<Vec<Expression>>,
+        on_conflict: Option<OnConflict>,
+        returning: Option<Returning>,
     },
     Update {
         table: String,
         set: BTreeMap<String, Expression>,
+        from: Vec<FromItem>,
         r#where: Option<Expression>,
+        returning: Option<Returning>,
     },
 
     Select {
+        with: Vec<CommonTableExpression>,
+        /// Optimizer hints, given in a /*+ */ comment following SELECT.
+        hints: Vec<Hint>,
         select: Vec<(Expression, Option<String>)>,
+        /// Whether plain DISTINCT was given, removing duplicate rows.
+        distinct: bool,
+        /// DISTINCT ON expressions, keeping the first row for each distinct value.
+        distinct_on: Vec<Expression>,
         from: Vec<FromItem>,
         r#where: Option<Expression>,
         group_by: Vec<Expression>,
+        /// Additional grouping levels for GROUP BY ROLLUP or CUBE.
+        grouping_sets: Option<GroupingSets>,
         having: Option<Expression>,
-        order: Vec<(Expression, Order)>,
+        order: Vec<(Expression, Order, Option<NullOrder>)>,
+        offset: Option<Expression>,
+        limit: Option<Limit>,
+    },
+    /// A set operation combining the rows of two queries. The operands are
+    /// SELECT statements without ORDER BY, LIMIT or OFFSET, or nested set
+    /// operations, and the clauses apply to the combined rows.
+    SetOperation {
+        with: Vec<CommonTableExpression>,
+        operator: SetOperator,
+        all: bool,
+        left: Box<Statement>,
+        right: Box<Statement>,
+        order: Vec<(Expression, Order, Option<NullOrder>)>,
         offset: Option<Expression>,
-        limit: Option<Expression>,
+        limit: Option<Limit>,
+    },
+
+    ShowTables,
+    ShowCreateTable {
+        name: String,
+    },
+    Describe {
+        name: String,
+    },
+    /// Collects statistics for a table, or all tables if None.
+    Analyze {
+        table: Option<String>,
     },
 }
 
+/// An INSERT ... ON CONFLICT clause
+#[derive(Clone, Debug, PartialEq)]
+pub struct OnConflict {
+    /// The conflict target columns, if given
+    pub columns: Vec<String>,
+    /// Column values for DO UPDATE, or None for DO NOTHING
+    pub update: Option<BTreeMap<String, Expression>>,
+    /// The DO UPDATE predicate, if any
+    pub r#where: Option<Expression>,
+}
+
+/// A CREATE TRIGGER action, which can refer to the written row via the OLD
+/// and NEW table names.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TriggerAction {
+    /// Sets columns of the NEW row, e.g. SET updated = NOW().
+    Set(BTreeMap<String, Expression>),
+    /// Executes an INSERT, UPDATE or DELETE statement.
+    Statement(Box<Statement>),
+}
+
+/// The source of a COPY FROM statement.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CopySource {
+    /// A file path on the server.
+    File(String),
+    /// Input streamed by the client along with the statement.
+    Stdin,
+}
+
+/// COPY data formats
+#[derive(Clone, Debug, PartialEq)]
+pub enum CopyFormat {
+    Csv,
+    /// JSON lines, with one object per row keyed by column name. Only
+    /// supported for COPY TO.
+    Json,
+}
+
+/// COPY options, given as a parenthesized list following the source or target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CopyOptions {
+    pub format: CopyFormat,
+    /// Whether the first line is a header, which is skipped.
+    pub header: bool,
+    /// The field delimiter.
+    pub delimiter: u8,
+    /// The field value representing NULL.
+    pub null: String,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self { format: CopyFormat::Csv, header: false, delimiter: b',', null: String::new() }
+    }
+}
+
+/// A RETURNING clause of an INSERT, UPDATE or DELETE, as output expressions
+/// with optional labels like a SELECT clause. Empty for RETURNING *.
+pub type Returning = Vec<(Expression, Option<String>)>;
+
+/// A common table expression, i.e. a named query in a WITH clause
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommonTableExpression {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub query: Box<Statement>,
+}
+
 /// A FROM item
 #[derive(Clone, Debug, PartialEq)]
 pub enum FromItem {
@@ -65,6 +369,27 @@ pub enum FromItem {
         r#type: JoinType,
         predicate: Option<Expression>,
     },
+    /// A VALUES list, with optional column names for its columns
+    Values {
+        rows: Vec<Vec<Expression>>,
+        alias: String,
+        columns: Vec<String>,
+    },
+    /// An unnest() call, yielding a row for each element of an array
+    Unnest {
+        expression: Expression,
+        alias: String,
+        column: String,
+    },
+    /// A subquery, with optional column names for its columns
+    Subquery {
+        query: Box<Statement>,
+        alias: String,
+        columns: Vec<String>,
+    },
+    /// A LATERAL subquery or unnest() call, which can reference the columns
+    /// of the preceding FROM items
+    Lateral(Box<FromItem>),
 }
 
 /// A JOIN type
@@ -81,12 +406,68 @@ pub enum JoinType {
 pub struct Column {
     pub name: String,
     pub datatype: DataType,
+    pub collation: Collation,
     pub primary_key: bool,
     pub nullable: Option<bool>,
     pub default: Option<Expression>,
+    pub generated: Option<Expression>,
     pub unique: bool,
     pub index: bool,
+    pub auto_increment: bool,
     pub references: Option<String>,
+    pub on_delete: ReferenceAction,
+    pub on_update: ReferenceAction,
+    pub deferred: bool,
+}
+
+/// Grouping sets, generated from the GROUP BY expressions
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupingSets {
+    /// All prefixes of the expressions, e.g. (a, b), (a), ().
+    Rollup,
+    /// All subsets of the expressions, e.g. (a, b), (a), (b), ().
+    Cube,
+}
+
+/// Set operators
+#[derive(Clone, Debug, PartialEq)]
+pub enum SetOperator {
+    Except,
+    Intersect,
+    Union,
+}
+
+/// A LIMIT or FETCH clause
+#[derive(Clone, Debug, PartialEq)]
+pub struct Limit {
+    /// The maximum number of rows to return
+    pub count: Expression,
+    /// If true, also return rows that tie with the last row in the sort order
+    pub with_ties: bool,
+}
+
+/// Optimizer hints, which override the optimizer's choices for the whole
+/// statement. Tables are referenced by alias if they have one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Hint {
+    /// FORCE_INDEX(table index): look up rows in the table via the given
+    /// secondary index whenever the filter allows it, regardless of cost.
+    ForceIndex { table: String, index: String },
+    /// NO_INDEX(table): don't use secondary indexes for the table.
+    NoIndex { table: String },
+    /// NO_HASH_JOIN: don't use hash joins.
+    NoHashJoin,
+    /// NO_MERGE_JOIN: don't use merge joins.
+    NoMergeJoin,
+    /// FIXED_JOIN_ORDER: join tables in the written order.
+    FixedJoinOrder,
+}
+
+/// EXPLAIN output formats
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExplainFormat {
+    Text,
+    Json,
 }
 
 /// Sort orders
@@ -96,14 +477,49 @@ pub enum Order {
     Descending,
 }
 
+/// Sort positions of NULL values
+#[derive(Clone, Debug, PartialEq)]
+pub enum NullOrder {
+    First,
+    Last,
+}
+
 /// Expressions
 #[derive(Clone, Debug, PartialEq)]
 pub enum Expression {
     Field(Option<String>, String),
     Column(usize), // only used during plan building to break off expression subtrees
     Literal(Literal),
+    // a literal replaced by a numbered parameter, see Parser::parameterized()
+    Parameter(usize),
     Function(String, Vec<Expression>),
+    // an aggregate function call with ORDER BY, e.g. STRING_AGG(a, ',' ORDER BY b)
+    OrderedFunction(String, Vec<Expression>, Vec<(Expression, Order, Option<NullOrder>)>),
+    // an aggregate function call over distinct values, with an optional ORDER BY, e.g.
+    // COUNT(DISTINCT a)
+    DistinctFunction(String, Vec<Expression>, Vec<(Expression, Order, Option<NullOrder>)>),
     Operation(Operation),
+    // a scalar subquery, e.g. (SELECT MAX(rating) FROM movies)
+    Subquery(Box<Statement>),
+    // an EXISTS subquery, e.g. EXISTS (SELECT * FROM movies)
+    Exists(Box<Statement>),
+}
+
+impl Statement {
+    /// Returns true if the statement only reads data, and can be executed in
+    /// a read-only transaction.
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Self::Select { .. }
+                | Self::SetOperation { .. }
+                | Self::ShowTables
+                | Self::ShowCreateTable { .. }
+                | Self::Describe { .. }
+                | Self::CopyTo { .. }
+                | Self::AsOf { .. }
+        )
+    }
 }
 
 impl From<Literal> for Expression {
@@ -126,6 +542,7 @@ pub enum Literal {
     Integer(i64),
     Float(f64),
     String(String),
+    Bytes(Vec<u8>),
 }
 
 /// Operations (done by operators)
@@ -137,9 +554,13 @@ pub enum Operation {
     Or(Box<Expression>, Box<Expression>),
 
     // Comparison operators
+    Any(Box<Expression>, Box<Expression>),
+    Between(Box<Expression>, Box<Expression>, Box<Expression>),
     Equal(Box<Expression>, Box<Expression>),
     GreaterThan(Box<Expression>, Box<Expression>),
     GreaterThanOrEqual(Box<Expression>, Box<Expression>),
+    In(Box<Expression>, Vec<Expression>),
+    InSubquery(Box<Expression>, Box<Statement>),
     IsNull(Box<Expression>),
     LessThan(Box<Expression>, Box<Expression>),
     LessThanOrEqual(Box<Expression>, Box<Expression>),
@@ -157,7 +578,22 @@ pub enum Operation {
     Subtract(Box<Expression>, Box<Expression>),
 
     // String operators
+    Concat(Box<Expression>, Box<Expression>),
     Like(Box<Expression>, Box<Expression>),
+    // text @@ query
+    Match(Box<Expression>, Box<Expression>),
+    // MATCH (text) AGAINST (query)
+    Relevance(Box<Expression>, Box<Expression>),
+
+    // JSON operators
+    JsonExtract(Box<Expression>, Box<Expression>),
+    JsonExtractText(Box<Expression>, Box<Expression>),
+
+    // Array operators
+    ArrayElement(Box<Expression>, Box<Expression>),
+
+    // Type operators
+    Cast(Box<Expression>, DataType),
 }
 
 impl Expression {
@@ -187,38 +623,72 @@ impl Expression {
         match &mut self {
             Self::Operation(Add(lhs, rhs))
             | Self::Operation(And(lhs, rhs))
+            | Self::Operation(Any(lhs, rhs))
+            | Self::Operation(ArrayElement(lhs, rhs))
+            | Self::Operation(Concat(lhs, rhs))
             | Self::Operation(Divide(lhs, rhs))
             | Self::Operation(Equal(lhs, rhs))
             | Self::Operation(Exponentiate(lhs, rhs))
             | Self::Operation(GreaterThan(lhs, rhs))
             | Self::Operation(GreaterThanOrEqual(lhs, rhs))
+            | Self::Operation(JsonExtract(lhs, rhs))
+            | Self::Operation(JsonExtractText(lhs, rhs))
             | Self::Operation(LessThan(lhs, rhs))
             | Self::Operation(LessThanOrEqual(lhs, rhs))
             | Self::Operation(Like(lhs, rhs))
+            | Self::Operation(Match(lhs, rhs))
             | Self::Operation(Modulo(lhs, rhs))
             | Self::Operation(Multiply(lhs, rhs))
             | Self::Operation(NotEqual(lhs, rhs))
             | Self::Operation(Or(lhs, rhs))
+            | Self::Operation(Relevance(lhs, rhs))
             | Self::Operation(Subtract(lhs, rhs)) => {
                 Self::replace_with(lhs, |e| e.transform(before, after))?;
                 Self::replace_with(rhs, |e| e.transform(before, after))?;
             }
 
             Self::Operation(Assert(expr))
+            | Self::Operation(Cast(expr, _))
             | Self::Operation(Factorial(expr))
+            | Self::Operation(InSubquery(expr, _))
             | Self::Operation(IsNull(expr))
             | Self::Operation(Negate(expr))
             | Self::Operation(Not(expr)) => {
                 Self::replace_with(expr, |e| e.transform(before, after))?
             }
 
+            Self::Operation(Between(expr, low, high)) => {
+                Self::replace_with(expr, |e| e.transform(before, after))?;
+                Self::replace_with(low, |e| e.transform(before, after))?;
+                Self::replace_with(high, |e| e.transform(before, after))?;
+            }
+
+            Self::Operation(In(expr, exprs)) => {
+                Self::replace_with(expr, |e| e.transform(before, after))?;
+                for expr in exprs {
+                    Self::replace_with(expr, |e| e.transform(before, after))?;
+                }
+            }
+
             Self::Function(_, exprs) => {
                 for expr in exprs {
                     Self::replace_with(expr, |e| e.transform(before, after))?;
                 }
             }
 
-            Self::Literal(_) | Self::Field(_, _) | Self::Column(_) => {}
+            Self::OrderedFunction(_, exprs, order) | Self::DistinctFunction(_, exprs, order) => {
+                for expr in exprs.iter_mut().chain(order.iter_mut().map(|(e, _, _)| e)) {
+                    Self::replace_with(expr, |e| e.transform(before, after))?;
+                }
+            }
+
+            // Subqueries have their own scope, and are not descended into.
+            Self::Literal(_)
+            | Self::Parameter(_)
+            | Self::Field(_, _)
+            | Self::Column(_)
+            | Self::Subquery(_)
+            | Self::Exists(_) => {}
         };
         after(self)
     }
@@ -239,26 +709,43 @@ impl Expression {
             && match self {
                 Self::Operation(Add(lhs, rhs))
                 | Self::Operation(And(lhs, rhs))
+                | Self::Operation(Any(lhs, rhs))
+                | Self::Operation(ArrayElement(lhs, rhs))
+                | Self::Operation(Concat(lhs, rhs))
                 | Self::Operation(Divide(lhs, rhs))
                 | Self::Operation(Equal(lhs, rhs))
                 | Self::Operation(Exponentiate(lhs, rhs))
                 | Self::Operation(GreaterThan(lhs, rhs))
                 | Self::Operation(GreaterThanOrEqual(lhs, rhs))
+                | Self::Operation(JsonExtract(lhs, rhs))
+                | Self::Operation(JsonExtractText(lhs, rhs))
                 | Self::Operation(LessThan(lhs, rhs))
                 | Self::Operation(LessThanOrEqual(lhs, rhs))
                 | Self::Operation(Like(lhs, rhs))
+                | Self::Operation(Match(lhs, rhs))
                 | Self::Operation(Modulo(lhs, rhs))
                 | Self::Operation(Multiply(lhs, rhs))
                 | Self::Operation(NotEqual(lhs, rhs))
                 | Self::Operation(Or(lhs, rhs))
+                | Self::Operation(Relevance(lhs, rhs))
                 | Self::Operation(Subtract(lhs, rhs)) => lhs.walk(visitor) && rhs.walk(visitor),
 
                 Self::Operation(Assert(expr))
+                | Self::Operation(Cast(expr, _))
                 | Self::Operation(Factorial(expr))
+                | Self::Operation(InSubquery(expr, _))
                 | Self::Operation(IsNull(expr))
                 | Self::Operation(Negate(expr))
                 | Self::Operation(Not(expr)) => expr.walk(visitor),
 
+                Self::Operation(Between(expr, low, high)) => {
+                    expr.walk(visitor) && low.walk(visitor) && high.walk(visitor)
+                }
+
+                Self::Operation(In(expr, exprs)) => {
+                    expr.walk(visitor) && exprs.iter().all(|e| e.walk(visitor))
+                }
+
                 Self::Function(_, exprs) => {
                     for expr in exprs {
                         if !expr.walk(visitor) {
@@ -268,7 +755,17 @@ impl Expression {
                     true
                 }
 
-                Self::Literal(_) | Self::Field(_, _) | Self::Column(_) => true,
+                Self::OrderedFunction(_, exprs, order)
+                | Self::DistinctFunction(_, exprs, order) => {
+                    exprs.iter().chain(order.iter().map(|(e, _, _)| e)).all(|e| e.walk(visitor))
+                }
+
+                Self::Literal(_)
+                | Self::Parameter(_)
+                | Self::Field(_, _)
+                | Self::Column(_)
+                | Self::Subquery(_)
+                | Self::Exists(_) => true,
             }
     }
 }
@@ -1,6 +1,6 @@
 use crate::error::{Error, Result};
 
-use std::iter::Peekable;
+use serde_derive::{Deserialize, Serialize};
 use std::str::Chars;
 
 // A lexer token
@@ -8,6 +8,7 @@ use std::str::Chars;
 pub enum Token {
     Number(String),
     String(String),
+    HexString(String),
     Ident(String),
     Keyword(Keyword),
     Period,
@@ -19,17 +20,31 @@ pub enum Token {
     LessOrGreaterThan,
     Plus,
     Minus,
+    Arrow,
+    DoubleArrow,
+    DoubleAt,
     Asterisk,
     Slash,
     Caret,
     Percent,
     Exclamation,
     NotEqual,
+    Colon,
+    DoubleColon,
+    Pipe,
+    DoublePipe,
     Question,
     OpenParen,
     CloseParen,
+    OpenBracket,
+    CloseBracket,
     Comma,
     Semicolon,
+    /// A literal replaced by a numbered parameter, see Parser::parameterized().
+    Parameter(usize),
+    /// An optimizer hint comment following SELECT, e.g. /*+ NO_HASH_JOIN */,
+    /// containing the text between the comment delimiters.
+    Hint(String),
 }
 
 impl std::fmt::Display for Token {
@@ -37,6 +52,7 @@ impl std::fmt::Display for Token {
         f.write_str(match self {
             Token::Number(n) => n,
             Token::String(s) => s,
+            Token::HexString(s) => s,
             Token::Ident(s) => s,
             Token::Keyword(k) => k.to_str(),
             Token::Period => ".",
@@ -48,17 +64,28 @@ impl std::fmt::Display for Token {
             Token::LessOrGreaterThan => "<>",
             Token::Plus => "+",
             Token::Minus => "-",
+            Token::Arrow => "->",
+            Token::DoubleArrow => "->>",
+            Token::DoubleAt => "@@",
             Token::Asterisk => "*",
             Token::Slash => "/",
             Token::Caret => "^",
             Token::Percent => "%",
             Token::Exclamation => "!",
             Token::NotEqual => "!=",
+            Token::Colon => ":",
+            Token::DoubleColon => "::",
+            Token::Pipe => "|",
+            Token::DoublePipe => "||",
             Token::Question => "?",
             Token::OpenParen => "(",
             Token::CloseParen => ")",
+            Token::OpenBracket => "[",
+            Token::CloseBracket => "]",
             Token::Comma => ",",
             Token::Semicolon => ";",
+            Token::Parameter(index) => return write!(f, "${}", index),
+            Token::Hint(hint) => return write!(f, "/*+{}*/", hint),
         })
     }
 }
@@ -72,46 +99,83 @@ impl From<Keyword> for Token {
 /// Lexer keywords
 #[derive(Clone, Debug, PartialEq)]
 pub enum Keyword {
+    Add,
+    All,
+    Alter,
+    Analyze,
     And,
+    Any,
+    Array,
     As,
     Asc,
+    AutoIncrement,
     Begin,
+    Between,
     Bool,
     Boolean,
     By,
+    Bytea,
+    Cascade,
+    Cast,
     Char,
+    Collate,
+    Column,
     Commit,
+    Conflict,
     Create,
     Cross,
+    CurrentTimestamp,
+    Date,
+    Decimal,
     Default,
+    Deferrable,
+    Deferred,
     Delete,
     Desc,
+    Describe,
+    Distinct,
+    Do,
     Double,
     Drop,
+    Except,
     Exists,
     Explain,
     False,
+    Fetch,
     Float,
+    For,
     From,
+    Generated,
     Group,
     Having,
     If,
+    Immediate,
+    In,
+    Include,
     Index,
     Infinity,
+    Initially,
     Inner,
     Insert,
     Int,
     Integer,
+    Intersect,
+    Interval,
     Into,
     Is,
     Join,
+    Json,
     Key,
+    Kill,
+    Lateral,
     Left,
     Like,
     Limit,
     NaN,
     Not,
+    Nothing,
     Null,
+    Numeric,
     Of,
     Offset,
     On,
@@ -120,24 +184,41 @@ pub enum Keyword {
     Order,
     Outer,
     Primary,
+    Queries,
     Read,
     References,
+    Release,
+    Restrict,
+    Returning,
     Right,
     Rollback,
+    Savepoint,
     Select,
+    Serial,
     Set,
+    Show,
     String,
     System,
     Table,
+    Tables,
+    Temp,
+    Temporary,
     Text,
     Time,
+    Timestamp,
+    To,
     Transaction,
     True,
+    Union,
     Unique,
     Update,
+    Using,
+    Uuid,
     Values,
     Varchar,
+    View,
     Where,
+    With,
     Write,
 }
 
@@ -145,46 +226,83 @@ impl Keyword {
     #[allow(clippy::should_implement_trait)]
     pub fn from_str(ident: &str) -> Option<Self> {
         Some(match ident.to_uppercase().as_ref() {
+            "ADD" => Self::Add,
+            "ALL" => Self::All,
+            "ALTER" => Self::Alter,
+            "ANALYZE" => Self::Analyze,
             "AS" => Self::As,
             "ASC" => Self::Asc,
             "AND" => Self::And,
+            "ANY" => Self::Any,
+            "ARRAY" => Self::Array,
+            "AUTO_INCREMENT" => Self::AutoIncrement,
             "BEGIN" => Self::Begin,
+            "BETWEEN" => Self::Between,
             "BOOL" => Self::Bool,
             "BOOLEAN" => Self::Boolean,
             "BY" => Self::By,
+            "BYTEA" => Self::Bytea,
+            "CASCADE" => Self::Cascade,
+            "CAST" => Self::Cast,
             "CHAR" => Self::Char,
+            "COLLATE" => Self::Collate,
+            "COLUMN" => Self::Column,
             "COMMIT" => Self::Commit,
+            "CONFLICT" => Self::Conflict,
             "CREATE" => Self::Create,
             "CROSS" => Self::Cross,
+            "CURRENT_TIMESTAMP" => Self::CurrentTimestamp,
+            "DATE" => Self::Date,
+            "DECIMAL" => Self::Decimal,
             "DEFAULT" => Self::Default,
+            "DEFERRABLE" => Self::Deferrable,
+            "DEFERRED" => Self::Deferred,
             "DELETE" => Self::Delete,
             "DESC" => Self::Desc,
+            "DESCRIBE" => Self::Describe,
+            "DISTINCT" => Self::Distinct,
+            "DO" => Self::Do,
             "DOUBLE" => Self::Double,
             "DROP" => Self::Drop,
+            "EXCEPT" => Self::Except,
             "EXISTS" => Self::Exists,
             "EXPLAIN" => Self::Explain,
             "FALSE" => Self::False,
+            "FETCH" => Self::Fetch,
             "FLOAT" => Self::Float,
+            "FOR" => Self::For,
             "FROM" => Self::From,
+            "GENERATED" => Self::Generated,
             "GROUP" => Self::Group,
             "HAVING" => Self::Having,
             "IF" => Self::If,
+            "IMMEDIATE" => Self::Immediate,
+            "IN" => Self::In,
+            "INCLUDE" => Self::Include,
             "INDEX" => Self::Index,
             "INFINITY" => Self::Infinity,
+            "INITIALLY" => Self::Initially,
             "INNER" => Self::Inner,
             "INSERT" => Self::Insert,
             "INT" => Self::Int,
             "INTEGER" => Self::Integer,
+            "INTERSECT" => Self::Intersect,
+            "INTERVAL" => Self::Interval,
             "INTO" => Self::Into,
             "IS" => Self::Is,
             "JOIN" => Self::Join,
+            "JSON" => Self::Json,
             "KEY" => Self::Key,
+            "KILL" => Self::Kill,
+            "LATERAL" => Self::Lateral,
             "LEFT" => Self::Left,
             "LIKE" => Self::Like,
             "LIMIT" => Self::Limit,
             "NAN" => Self::NaN,
             "NOT" => Self::Not,
+            "NOTHING" => Self::Nothing,
             "NULL" => Self::Null,
+            "NUMERIC" => Self::Numeric,
             "OF" => Self::Of,
             "OFFSET" => Self::Offset,
             "ON" => Self::On,
@@ -193,24 +311,41 @@ impl Keyword {
             "ORDER" => Self::Order,
             "OUTER" => Self::Outer,
             "PRIMARY" => Self::Primary,
+            "QUERIES" => Self::Queries,
             "READ" => Self::Read,
             "REFERENCES" => Self::References,
+            "RELEASE" => Self::Release,
+            "RESTRICT" => Self::Restrict,
+            "RETURNING" => Self::Returning,
             "RIGHT" => Self::Right,
             "ROLLBACK" => Self::Rollback,
+            "SAVEPOINT" => Self::Savepoint,
             "SELECT" => Self::Select,
+            "SERIAL" => Self::Serial,
             "SET" => Self::Set,
+            "SHOW" => Self::Show,
             "STRING" => Self::String,
             "SYSTEM" => Self::System,
             "TABLE" => Self::Table,
+            "TABLES" => Self::Tables,
+            "TEMP" => Self::Temp,
+            "TEMPORARY" => Self::Temporary,
             "TEXT" => Self::Text,
             "TIME" => Self::Time,
+            "TIMESTAMP" => Self::Timestamp,
+            "TO" => Self::To,
             "TRANSACTION" => Self::Transaction,
             "TRUE" => Self::True,
+            "UNION" => Self::Union,
             "UNIQUE" => Self::Unique,
             "UPDATE" => Self::Update,
+            "USING" => Self::Using,
+            "UUID" => Self::Uuid,
             "VALUES" => Self::Values,
             "VARCHAR" => Self::Varchar,
+            "VIEW" => Self::View,
             "WHERE" => Self::Where,
+            "WITH" => Self::With,
             "WRITE" => Self::Write,
             _ => return None,
         })
@@ -218,46 +353,83 @@ impl Keyword {
 
     pub fn to_str(&self) -> &str {
         match self {
+            Self::Add => "ADD",
+            Self::All => "ALL",
+            Self::Alter => "ALTER",
+            Self::Analyze => "ANALYZE",
             Self::As => "AS",
             Self::Asc => "ASC",
             Self::And => "AND",
+            Self::Any => "ANY",
+            Self::Array => "ARRAY",
+            Self::AutoIncrement => "AUTO_INCREMENT",
             Self::Begin => "BEGIN",
+            Self::Between => "BETWEEN",
             Self::Bool => "BOOL",
             Self::Boolean => "BOOLEAN",
             Self::By => "BY",
+            Self::Bytea => "BYTEA",
+            Self::Cascade => "CASCADE",
+            Self::Cast => "CAST",
             Self::Char => "CHAR",
+            Self::Collate => "COLLATE",
+            Self::Column => "COLUMN",
             Self::Commit => "COMMIT",
+            Self::Conflict => "CONFLICT",
             Self::Create => "CREATE",
             Self::Cross => "CROSS",
+            Self::CurrentTimestamp => "CURRENT_TIMESTAMP",
+            Self::Date => "DATE",
+            Self::Decimal => "DECIMAL",
             Self::Default => "DEFAULT",
+            Self::Deferrable => "DEFERRABLE",
+            Self::Deferred => "DEFERRED",
             Self::Delete => "DELETE",
             Self::Desc => "DESC",
+            Self::Describe => "DESCRIBE",
+            Self::Distinct => "DISTINCT",
+            Self::Do => "DO",
             Self::Double => "DOUBLE",
             Self::Drop => "DROP",
+            Self::Except => "EXCEPT",
             Self::Exists => "EXISTS",
             Self::Explain => "EXPLAIN",
             Self::False => "FALSE",
+            Self::Fetch => "FETCH",
             Self::Float => "FLOAT",
+            Self::For => "FOR",
             Self::From => "FROM",
+            Self::Generated => "GENERATED",
             Self::Group => "GROUP",
             Self::Having => "HAVING",
             Self::If => "IF",
+            Self::Immediate => "IMMEDIATE",
+            Self::In => "IN",
+            Self::Include => "INCLUDE",
             Self::Index => "INDEX",
             Self::Infinity => "INFINITY",
+            Self::Initially => "INITIALLY",
             Self::Inner => "INNER",
             Self::Insert => "INSERT",
             Self::Int => "INT",
             Self::Integer => "INTEGER",
+            Self::Intersect => "INTERSECT",
+            Self::Interval => "INTERVAL",
             Self::Into => "INTO",
             Self::Is => "IS",
             Self::Join => "JOIN",
+            Self::Json => "JSON",
             Self::Key => "KEY",
+            Self::Kill => "KILL",
+            Self::Lateral => "LATERAL",
             Self::Left => "LEFT",
             Self::Like => "LIKE",
             Self::Limit => "LIMIT",
             Self::NaN => "NAN",
             Self::Not => "NOT",
+            Self::Nothing => "NOTHING",
             Self::Null => "NULL",
+            Self::Numeric => "NUMERIC",
             Self::Of => "OF",
             Self::Offset => "OFFSET",
             Self::On => "ON",
@@ -266,24 +438,41 @@ impl Keyword {
             Self::Or => "OR",
             Self::Order => "ORDER",
             Self::Primary => "PRIMARY",
+            Self::Queries => "QUERIES",
             Self::Read => "READ",
             Self::References => "REFERENCES",
+            Self::Release => "RELEASE",
+            Self::Restrict => "RESTRICT",
+            Self::Returning => "RETURNING",
             Self::Right => "RIGHT",
             Self::Rollback => "ROLLBACK",
+            Self::Savepoint => "SAVEPOINT",
             Self::Select => "SELECT",
+            Self::Serial => "SERIAL",
             Self::Set => "SET",
+            Self::Show => "SHOW",
             Self::String => "STRING",
             Self::System => "SYSTEM",
             Self::Table => "TABLE",
+            Self::Tables => "TABLES",
+            Self::Temp => "TEMP",
+            Self::Temporary => "TEMPORARY",
             Self::Text => "TEXT",
             Self::Time => "TIME",
+            Self::Timestamp => "TIMESTAMP",
+            Self::To => "TO",
             Self::Transaction => "TRANSACTION",
             Self::True => "TRUE",
+            Self::Union => "UNION",
             Self::Unique => "UNIQUE",
             Self::Update => "UPDATE",
+            Self::Using => "USING",
+            Self::Uuid => "UUID",
             Self::Values => "VALUES",
             Self::Varchar => "VARCHAR",
+            Self::View => "VIEW",
             Self::Where => "WHERE",
+            Self::With => "WITH",
             Self::Write => "WRITE",
         }
     }
@@ -295,22 +484,55 @@ impl std::fmt::Display for Keyword {
     }
 }
 
+/// A span of input text, e.g. a token, used to report errors. Offsets are in
+/// bytes, while lines and columns are 1-based and count characters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    /// The byte offset of the start of the span.
+    pub start: usize,
+    /// The byte offset of the end of the span, exclusive.
+    pub end: usize,
+    /// The line of the start of the span.
+    pub line: usize,
+    /// The column of the start of the span.
+    pub column: usize,
+}
+
 /// A lexer tokenizes an input string as an iterator
 pub struct Lexer<'a> {
-    iter: Peekable<Chars<'a>>,
+    input: &'a str,
+    iter: Chars<'a>,
+    /// The line and column of the next character.
+    line: usize,
+    column: usize,
+    /// The span of the last scanned token, or of the input that failed to
+    /// scan. At the end of input, this is an empty span at the end.
+    span: Span,
+    /// Whether the last scanned token was SELECT, in which case a /*+ */
+    /// comment is scanned as a hint token rather than skipped.
+    hint: bool,
 }
 
 impl<'a> Iterator for Lexer<'a> {
     type Item = Result<Token>;
 
     fn next(&mut self) -> Option<Result<Token>> {
-        match self.scan() {
+        if let Err(err) = self.consume_whitespace() {
+            self.span.end = self.offset();
+            return Some(Err(err));
+        }
+        self.span = Span { start: self.offset(), end: 0, line: self.line, column: self.column };
+        let result = match self.scan() {
             Ok(Some(token)) => Some(Ok(token)),
-            Ok(None) => {
-                self.iter.peek().map(|c| Err(Error::Parse(format!("Unexpected character {}", c))))
-            }
+            Ok(None) => self.peek().map(|c| {
+                self.next_char();
+                Err(Error::Parse(format!("Unexpected character {}", c)))
+            }),
             Err(err) => Some(Err(err)),
-        }
+        };
+        self.span.end = self.offset();
+        self.hint = matches!(result, Some(Ok(Token::Keyword(Keyword::Select))));
+        result
     }
 }
 
@@ -318,24 +540,69 @@ impl<'a> Lexer<'a> {
     /// Creates a new lexer for the given input string
     #[allow(dead_code)]
     pub fn new(input: &'a str) -> Lexer<'a> {
-        Lexer { iter: input.chars().peekable() }
+        Lexer { input, iter: input.chars(), line: 1, column: 1, span: Span::default(), hint: false }
+    }
+
+    /// Returns the full input string
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    /// Returns the remaining, unscanned input
+    pub fn remaining(&self) -> &'a str {
+        self.iter.as_str()
+    }
+
+    /// Returns the span of the last scanned token
+    pub fn span(&self) -> Span {
+        self.span
     }
 
-    /// Consumes any whitespace characters
-    fn consume_whitespace(&mut self) {
-        self.next_while(|c| c.is_whitespace());
+    /// Returns the byte offset of the next character
+    fn offset(&self) -> usize {
+        self.input.len() - self.iter.as_str().len()
+    }
+
+    /// Grabs the next character, tracking its line and column
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.iter.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Peeks the next character, if any
+    fn peek(&self) -> Option<char> {
+        self.iter.clone().next()
+    }
+
+    /// Consumes any whitespace characters and /* */ comments. Hint comments
+    /// are left for scan_hint(). -- is a double negation, not a comment.
+    fn consume_whitespace(&mut self) -> Result<()> {
+        loop {
+            self.next_while(|c| c.is_whitespace());
+            let remaining = self.remaining();
+            if !remaining.starts_with("/*") || self.hint && remaining.starts_with("/*+") {
+                return Ok(());
+            }
+            self.scan_comment()?;
+        }
     }
 
     /// Grabs the next character if it matches the predicate function
     fn next_if<F: Fn(char) -> bool>(&mut self, predicate: F) -> Option<char> {
-        self.iter.peek().filter(|&c| predicate(*c))?;
-        self.iter.next()
+        self.peek().filter(|&c| predicate(c))?;
+        self.next_char()
     }
 
     /// Grabs the next single-character token if the tokenizer function returns one
     fn next_if_token<F: Fn(char) -> Option<Token>>(&mut self, tokenizer: F) -> Option<Token> {
-        let token = self.iter.peek().and_then(|&c| tokenizer(c))?;
-        self.iter.next();
+        let token = self.peek().and_then(tokenizer)?;
+        self.next_char();
         Some(token)
     }
 
@@ -350,26 +617,58 @@ impl<'a> Lexer<'a> {
 
     /// Scans the input for the next token if any, ignoring leading whitespace
     fn scan(&mut self) -> Result<Option<Token>> {
-        self.consume_whitespace();
-        match self.iter.peek() {
+        self.consume_whitespace()?;
+        if self.remaining().starts_with("/*+") {
+            return self.scan_hint();
+        }
+        match self.peek() {
             Some('\'') => self.scan_string(),
             Some('"') => self.scan_ident_quoted(),
             Some(c) if c.is_ascii_digit() => Ok(self.scan_number()),
-            Some(c) if c.is_alphabetic() => Ok(self.scan_ident()),
+            Some(c) if c.is_alphabetic() => self.scan_ident(),
             Some(_) => Ok(self.scan_symbol()),
             None => Ok(None),
         }
     }
 
-    /// Scans the input for the next ident or keyword token, if any
-    fn scan_ident(&mut self) -> Option<Token> {
-        let mut name = self.next_if(|c| c.is_alphabetic())?.to_string();
+    /// Scans a block comment, returning its text. Comments don't nest.
+    fn scan_comment(&mut self) -> Result<String> {
+        self.span = Span { start: self.offset(), end: 0, line: self.line, column: self.column };
+        self.next_char();
+        self.next_char();
+        let mut comment = String::new();
+        loop {
+            match self.next_char() {
+                Some('*') if self.next_if(|c| c == '/').is_some() => return Ok(comment),
+                Some(c) => comment.push(c),
+                None => return Err(Error::Parse("Unexpected end of comment".into())),
+            }
+        }
+    }
+
+    /// Scans a hint comment, i.e. a block comment starting with /*+
+    fn scan_hint(&mut self) -> Result<Option<Token>> {
+        let hint = self.scan_comment()?;
+        Ok(Some(Token::Hint(hint[1..].to_string())))
+    }
+
+    /// Scans the input for the next ident or keyword token, if any. An x
+    /// followed by a string literal is a hex string, e.g. x'DEADBEEF'.
+    fn scan_ident(&mut self) -> Result<Option<Token>> {
+        let Some(c) = self.next_if(|c| c.is_alphabetic()) else { return Ok(None) };
+        let mut name = c.to_string();
         while let Some(c) = self.next_if(|c| c.is_alphanumeric() || c == '_') {
             name.push(c)
         }
-        Keyword::from_str(&name)
+        if (name == "x" || name == "X") && self.peek() == Some('\'') {
+            return match self.scan_string()? {
+                Some(Token::String(s)) => Ok(Some(Token::HexString(s))),
+                token => Ok(token),
+            };
+        }
+        Ok(Keyword::from_str(&name)
             .map(Token::Keyword)
-            .or_else(|| Some(Token::Ident(name.to_lowercase())))
+            .or_else(|| Some(Token::Ident(name.to_lowercase()))))
     }
 
     /// Scans the input for the next quoted ident, if any
@@ -379,7 +678,7 @@ impl<'a> Lexer<'a> {
         }
         let mut ident = String::new();
         loop {
-            match self.iter.next() {
+            match self.next_char() {
                 Some('"') if self.next_if(|c| c == '"').is_some() => ident.push('"'),
                 Some('"') => break,
                 Some(c) => ident.push(c),
@@ -417,7 +716,7 @@ impl<'a> Lexer<'a> {
         }
         let mut s = String::new();
         loop {
-            match self.iter.next() {
+            match self.next_char() {
                 Some('\'') if self.next_if(|c| c == '\'').is_some() => s.push('\''),
                 Some('\'') => break,
                 Some(c) => s.push(c),
@@ -430,6 +729,7 @@ impl<'a> Lexer<'a> {
     /// Scans the input for the next symbol token, if any, and
     /// handle any multi-symbol tokens
     fn scan_symbol(&mut self) -> Option<Token> {
+        let double_at = self.remaining().starts_with("@@");
         self.next_if_token(|c| match c {
             '.' => Some(Token::Period),
             '=' => Some(Token::Equal),
@@ -443,8 +743,13 @@ impl<'a> Lexer<'a> {
             '%' => Some(Token::Percent),
             '!' => Some(Token::Exclamation),
             '?' => Some(Token::Question),
+            '@' if double_at => Some(Token::DoubleAt),
+            ':' => Some(Token::Colon),
+            '|' => Some(Token::Pipe),
             '(' => Some(Token::OpenParen),
             ')' => Some(Token::CloseParen),
+            '[' => Some(Token::OpenBracket),
+            ']' => Some(Token::CloseBracket),
             ',' => Some(Token::Comma),
             ';' => Some(Token::Semicolon),
             _ => None,
@@ -457,6 +762,24 @@ impl<'a> Lexer<'a> {
                     token
                 }
             }
+            Token::DoubleAt => {
+                self.next_char();
+                token
+            }
+            Token::Colon => {
+                if self.next_if(|c| c == ':').is_some() {
+                    Token::DoubleColon
+                } else {
+                    token
+                }
+            }
+            Token::Pipe => {
+                if self.next_if(|c| c == '|').is_some() {
+                    Token::DoublePipe
+                } else {
+                    token
+                }
+            }
             Token::LessThan => {
                 if self.next_if(|c| c == '>').is_some() {
                     Token::LessOrGreaterThan
@@ -466,6 +789,17 @@ impl<'a> Lexer<'a> {
                     token
                 }
             }
+            Token::Minus => {
+                if self.next_if(|c| c == '>').is_some() {
+                    if self.next_if(|c| c == '>').is_some() {
+                        Token::DoubleArrow
+                    } else {
+                        Token::Arrow
+                    }
+                } else {
+                    token
+                }
+            }
             Token::GreaterThan => {
                 if self.next_if(|c| c == '=').is_some() {
                     Token::GreaterThanOrEqual
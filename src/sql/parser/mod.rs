@@ -1,8 +1,9 @@
 pub mod ast;
 mod lexer;
-pub use lexer::{Keyword, Lexer, Token};
+pub use lexer::{Keyword, Lexer, Span, Token};
 
-use super::types::DataType;
+use super::schema::{ColumnChange, Privilege, ReferenceAction, TriggerEvent, TriggerTiming};
+use super::types::{Collation, DataType, Value};
 use crate::error::{Error, Result};
 
 use regex::Regex;
@@ -10,26 +11,111 @@ use std::collections::BTreeMap;
 
 /// An SQL parser
 pub struct Parser<'a> {
-    lexer: std::iter::Peekable<Lexer<'a>>,
+    lexer: Lexer<'a>,
+    /// A token peeked from the lexer, if any
+    peeked: Option<Option<Result<Token>>>,
+    /// For a parameterized parser, the number of literals replaced by
+    /// parameters so far
+    parameters: Option<usize>,
 }
 
 impl<'a> Parser<'a> {
     /// Creates a new parser for the given string input
-    pub fn new(query: &str) -> Parser {
-        Parser { lexer: Lexer::new(query).peekable() }
+    pub fn new(query: &str) -> Parser<'_> {
+        Parser { lexer: Lexer::new(query), peeked: None, parameters: None }
     }
 
-    /// Parses the input string into an AST statement
+    /// Creates a new parser which replaces number, string and hex string
+    /// literals with parameters, numbered in order of appearance. The
+    /// parameter values are given by normalize().
+    pub fn parameterized(query: &str) -> Parser<'_> {
+        Parser { lexer: Lexer::new(query), peeked: None, parameters: Some(0) }
+    }
+
+    /// Normalizes a query for plan caching, returning a key for its token
+    /// sequence with literals replaced by parameters, along with the
+    /// parameter values as parsed by Parser::parameterized(). The key also
+    /// includes the parameter types, since these can affect planning, e.g.
+    /// string literals are converted to the types they're compared with.
+    pub fn normalize(query: &str) -> Result<(String, Vec<Value>)> {
+        let mut tokens = Vec::new();
+        let mut values = Vec::new();
+        for token in Lexer::new(query) {
+            let value = match token? {
+                Token::Number(n) if n.chars().all(|c| c.is_ascii_digit()) => {
+                    Value::Integer(n.parse()?)
+                }
+                Token::Number(n) => Value::Float(n.parse()?),
+                Token::String(s) => Value::String(s),
+                Token::HexString(s) => Value::Bytes(
+                    hex::decode(&s)
+                        .map_err(|_| Error::Parse(format!("Invalid hex string {}", s)))?,
+                ),
+                token => {
+                    tokens.push(token);
+                    continue;
+                }
+            };
+            tokens.push(Token::Parameter(values.len()));
+            values.push(value);
+        }
+        let types: Vec<_> = values.iter().map(|v| v.datatype()).collect();
+        Ok((format!("{:?} {:?}", tokens, types), values))
+    }
+
+    /// Parses the input string into an AST statement. Parse errors are
+    /// returned as syntax errors at the last token scanned by the lexer.
     pub fn parse(&mut self) -> Result<ast::Statement> {
-        let statement = self.parse_statement()?;
-        self.next_if_token(Token::Semicolon);
-        self.next_expect(None)?;
-        Ok(statement)
+        let result = self.parse_statement().and_then(|statement| {
+            self.next_if_token(Token::Semicolon);
+            self.next_expect(None)?;
+            Ok(statement)
+        });
+        result.map_err(|err| self.syntax_error(err))
+    }
+
+    /// Parses the input string into a trigger action, as stored in the
+    /// catalog by CREATE TRIGGER.
+    pub fn parse_trigger_action(&mut self) -> Result<ast::TriggerAction> {
+        let result = self.parse_ddl_trigger_action().and_then(|action| {
+            self.next_expect(None)?;
+            Ok(action)
+        });
+        result.map_err(|err| self.syntax_error(err))
+    }
+
+    /// Converts a parse error into a syntax error at the last token scanned
+    /// by the lexer.
+    fn syntax_error(&self, err: Error) -> Error {
+        match err {
+            Error::Parse(message) => {
+                let span = self.lexer.span();
+                let near = self.lexer.input()[span.start..span.end].to_string();
+                Error::Syntax { message, span, near }
+            }
+            err => err,
+        }
     }
 
     /// Grabs the next lexer token, or throws an error if none is found.
     fn next(&mut self) -> Result<Token> {
-        self.lexer.next().unwrap_or_else(|| Err(Error::Parse("Unexpected end of input".into())))
+        self.peeked
+            .take()
+            .unwrap_or_else(|| self.lex())
+            .unwrap_or_else(|| Err(Error::Parse("Unexpected end of input".into())))
+    }
+
+    /// Scans the next lexer token, replacing literals with parameters if the
+    /// parser is parameterized.
+    fn lex(&mut self) -> Option<Result<Token>> {
+        let token = self.lexer.next();
+        match (token, &mut self.parameters) {
+            (Some(Ok(Token::Number(_) | Token::String(_) | Token::HexString(_))), Some(count)) => {
+                *count += 1;
+                Some(Ok(Token::Parameter(*count - 1)))
+            }
+            (token, _) => token,
+        }
     }
 
     /// Grabs the next lexer token, and returns it if it was expected or
@@ -57,6 +143,23 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Grabs the next identifier as a table, view or index name, optionally
+    /// qualified by a database (e.g. db.table), or errors. The unqualified
+    /// name may be a keyword, e.g. information_schema.tables.
+    fn next_name(&mut self) -> Result<String> {
+        let name = self.next_ident()?;
+        if self.next_if_token(Token::Period).is_none() {
+            return Ok(name);
+        }
+        match self.next()? {
+            Token::Ident(ident) => Ok(format!("{}.{}", name, ident)),
+            Token::Keyword(keyword) => {
+                Ok(format!("{}.{}", name, keyword.to_string().to_lowercase()))
+            }
+            token => Err(Error::Parse(format!("Expected identifier, got {}", token))),
+        }
+    }
+
     /// Grabs the next lexer token if it satisfies the predicate function
     fn next_if<F: Fn(&Token) -> bool>(&mut self, predicate: F) -> Option<Token> {
         self.peek().unwrap_or(None).filter(|t| predicate(t))?;
@@ -92,7 +195,19 @@ impl<'a> Parser<'a> {
     /// Option<Result<Token>> to Result<Option<Token>> which is
     /// more convenient to work with (the Iterator trait requires Option<T>).
     fn peek(&mut self) -> Result<Option<Token>> {
-        self.lexer.peek().cloned().transpose()
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex());
+        }
+        self.peeked.clone().flatten().transpose()
+    }
+
+    /// Returns the remaining input following the last token, which must not
+    /// have been peeked.
+    fn remaining(&self) -> Result<&'a str> {
+        match self.peeked {
+            None => Ok(self.lexer.remaining()),
+            Some(_) => Err(Error::Internal("Can't take remaining input after peeking".into())),
+        }
     }
 
     /// Parses an SQL statement
@@ -101,16 +216,37 @@ impl<'a> Parser<'a> {
             Some(Token::Keyword(Keyword::Begin)) => self.parse_transaction(),
             Some(Token::Keyword(Keyword::Commit)) => self.parse_transaction(),
             Some(Token::Keyword(Keyword::Rollback)) => self.parse_transaction(),
+            Some(Token::Keyword(Keyword::Savepoint)) => self.parse_transaction(),
+            Some(Token::Keyword(Keyword::Release)) => self.parse_transaction(),
+
+            Some(Token::Keyword(Keyword::Set)) => self.parse_statement_set(),
 
+            Some(Token::Keyword(Keyword::Alter)) => self.parse_ddl(),
             Some(Token::Keyword(Keyword::Create)) => self.parse_ddl(),
             Some(Token::Keyword(Keyword::Drop)) => self.parse_ddl(),
 
+            // COPY isn't a reserved keyword, so tables can still be named copy.
+            Some(Token::Ident(ident)) if ident == "copy" => self.parse_statement_copy(),
+            // Nor are COMMENT and USE.
+            Some(Token::Ident(ident)) if ident == "comment" => self.parse_statement_comment(),
+            Some(Token::Ident(ident)) if ident == "use" => self.parse_statement_use(),
+            // Nor are GRANT and REVOKE.
+            Some(Token::Ident(ident)) if ident == "grant" || ident == "revoke" => {
+                self.parse_statement_grant()
+            }
             Some(Token::Keyword(Keyword::Delete)) => self.parse_statement_delete(),
             Some(Token::Keyword(Keyword::Insert)) => self.parse_statement_insert(),
-            Some(Token::Keyword(Keyword::Select)) => self.parse_statement_select(),
+            Some(Token::Keyword(Keyword::Select)) => self.parse_statement_select_as_of(),
             Some(Token::Keyword(Keyword::Update)) => self.parse_statement_update(),
+            Some(Token::Keyword(Keyword::Values)) => self.parse_statement_values(),
+            Some(Token::Keyword(Keyword::With)) => self.parse_statement_select_as_of(),
 
             Some(Token::Keyword(Keyword::Explain)) => self.parse_statement_explain(),
+            Some(Token::Keyword(Keyword::Analyze)) => self.parse_statement_analyze(),
+
+            Some(Token::Keyword(Keyword::Describe)) => self.parse_statement_describe(),
+            Some(Token::Keyword(Keyword::Show)) => self.parse_statement_show(),
+            Some(Token::Keyword(Keyword::Kill)) => self.parse_statement_kill(),
 
             Some(token) => Err(Error::Parse(format!("Unexpected token {}", token))),
             None => Err(Error::Parse("Unexpected end of input".into())),
@@ -120,33 +256,187 @@ impl<'a> Parser<'a> {
     /// Parses a DDL statement
     fn parse_ddl(&mut self) -> Result<ast::Statement> {
         match self.next()? {
+            Token::Keyword(Keyword::Alter) => match self.next()? {
+                Token::Keyword(Keyword::Table) => self.parse_ddl_alter_table(),
+                Token::Ident(ident) if ident == "user" => self.parse_ddl_alter_user(),
+                token => Err(Error::Parse(format!("Unexpected token {}", token))),
+            },
             Token::Keyword(Keyword::Create) => match self.next()? {
-                Token::Keyword(Keyword::Table) => self.parse_ddl_create_table(),
+                Token::Ident(ident) if ident == "database" => {
+                    Ok(ast::Statement::CreateDatabase { name: self.next_ident()? })
+                }
+                Token::Ident(ident) if ident == "role" => {
+                    Ok(ast::Statement::CreateRole { name: self.next_ident()? })
+                }
+                Token::Ident(ident) if ident == "fulltext" => {
+                    self.next_expect(Some(Keyword::Index.into()))?;
+                    self.parse_ddl_create_index(false, true)
+                }
+                Token::Keyword(Keyword::Index) => self.parse_ddl_create_index(false, false),
+                Token::Ident(ident) if ident == "sequence" => self.parse_ddl_create_sequence(),
+                Token::Keyword(Keyword::Table) => self.parse_ddl_create_table(false),
+                Token::Ident(ident) if ident == "trigger" => self.parse_ddl_create_trigger(),
+                Token::Keyword(Keyword::Temp | Keyword::Temporary) => {
+                    self.next_expect(Some(Keyword::Table.into()))?;
+                    self.parse_ddl_create_table(true)
+                }
+                Token::Ident(ident) if ident == "type" => self.parse_ddl_create_type(),
+                Token::Ident(ident) if ident == "user" => self.parse_ddl_create_user(),
+                Token::Keyword(Keyword::View) => self.parse_ddl_create_view(),
+                Token::Keyword(Keyword::Unique) => {
+                    self.next_expect(Some(Keyword::Index.into()))?;
+                    self.parse_ddl_create_index(true, false)
+                }
                 token => Err(Error::Parse(format!("Unexpected token {}", token))),
             },
             Token::Keyword(Keyword::Drop) => match self.next()? {
+                Token::Keyword(Keyword::Index) => self.parse_ddl_drop_index(),
+                Token::Ident(ident) if ident == "sequence" => self.parse_ddl_drop_sequence(),
                 Token::Keyword(Keyword::Table) => self.parse_ddl_drop_table(),
+                Token::Ident(ident) if ident == "trigger" => self.parse_ddl_drop_trigger(),
+                Token::Ident(ident) if ident == "type" => self.parse_ddl_drop_type(),
+                // Roles are users, so DROP ROLE is an alias for DROP USER.
+                Token::Ident(ident) if ident == "user" || ident == "role" => {
+                    self.parse_ddl_drop_user()
+                }
+                Token::Keyword(Keyword::View) => self.parse_ddl_drop_view(),
                 token => Err(Error::Parse(format!("Unexpected token {}", token))),
             },
             token => Err(Error::Parse(format!("Unexpected token {}", token))),
         }
     }
 
-    /// Parses a CREATE TABLE DDL statement. The CREATE TABLE prefix has
+    /// Parses an ALTER TABLE DDL statement. The ALTER TABLE prefix has
     /// already been consumed.
-    fn parse_ddl_create_table(&mut self) -> Result<ast::Statement> {
-        let name = self.next_ident()?;
+    fn parse_ddl_alter_table(&mut self) -> Result<ast::Statement> {
+        let table = self.next_name()?;
+        match self.next()? {
+            Token::Keyword(Keyword::Add) => {
+                self.next_if_token(Keyword::Column.into());
+                Ok(ast::Statement::AddColumn { table, column: self.parse_ddl_columnspec()? })
+            }
+            Token::Keyword(Keyword::Drop) => {
+                self.next_if_token(Keyword::Column.into());
+                Ok(ast::Statement::DropColumn { table, column: self.next_ident()? })
+            }
+            Token::Keyword(Keyword::Alter) => {
+                self.next_if_token(Keyword::Column.into());
+                let column = self.next_ident()?;
+                let change = match self.next()? {
+                    Token::Keyword(Keyword::Set)
+                        if self.next_if_token(Keyword::Not.into()).is_some() =>
+                    {
+                        self.next_expect(Some(Keyword::Null.into()))?;
+                        ColumnChange::Nullable(false)
+                    }
+                    Token::Keyword(Keyword::Set) => {
+                        self.next_expect(Some(Token::Ident("data".into())))?;
+                        self.next_expect(Some(Token::Ident("type".into())))?;
+                        ColumnChange::Type(self.parse_datatype()?)
+                    }
+                    Token::Keyword(Keyword::Drop) => {
+                        self.next_expect(Some(Keyword::Not.into()))?;
+                        self.next_expect(Some(Keyword::Null.into()))?;
+                        ColumnChange::Nullable(true)
+                    }
+                    Token::Ident(ident) if ident == "type" => {
+                        ColumnChange::Type(self.parse_datatype()?)
+                    }
+                    token => return Err(Error::Parse(format!("Unexpected token {}", token))),
+                };
+                Ok(ast::Statement::AlterColumn { table, column, change })
+            }
+            token => Err(Error::Parse(format!("Unexpected token {}", token))),
+        }
+    }
+
+    /// Parses a CREATE [UNIQUE | FULLTEXT] INDEX DDL statement, with an
+    /// optional name and INCLUDE columns. Keys are column names or
+    /// parenthesized expressions. The CREATE [UNIQUE | FULLTEXT] INDEX prefix
+    /// has already been consumed.
+    fn parse_ddl_create_index(&mut self, unique: bool, fulltext: bool) -> Result<ast::Statement> {
+        let name = match self.next_if_token(Keyword::On.into()) {
+            Some(_) => None,
+            None => {
+                let name = self.next_name()?;
+                self.next_expect(Some(Keyword::On.into()))?;
+                Some(name)
+            }
+        };
+        let table = self.next_name()?;
         self.next_expect(Some(Token::OpenParen))?;
+        let mut columns = Vec::new();
+        loop {
+            columns.push(match self.next_if_token(Token::OpenParen) {
+                Some(_) => {
+                    let expr = self.parse_expression(0)?;
+                    self.next_expect(Some(Token::CloseParen))?;
+                    expr
+                }
+                None => ast::Expression::Field(None, self.next_ident()?),
+            });
+            if self.next_if_token(Token::Comma).is_none() {
+                break;
+            }
+        }
+        self.next_expect(Some(Token::CloseParen))?;
+        let mut include = Vec::new();
+        if self.next_if_token(Keyword::Include.into()).is_some() {
+            include = self.parse_ddl_column_list()?;
+        }
+        Ok(ast::Statement::CreateIndex { name, table, columns, unique, fulltext, include })
+    }
 
+    /// Parses a parenthesized list of column names, e.g. for an index
+    fn parse_ddl_column_list(&mut self) -> Result<Vec<String>> {
+        self.next_expect(Some(Token::OpenParen))?;
         let mut columns = Vec::new();
         loop {
-            columns.push(self.parse_ddl_columnspec()?);
+            columns.push(self.next_ident()?);
             if self.next_if_token(Token::Comma).is_none() {
                 break;
             }
         }
         self.next_expect(Some(Token::CloseParen))?;
-        Ok(ast::Statement::CreateTable { name, columns })
+        Ok(columns)
+    }
+
+    /// Parses a DROP INDEX DDL statement. The DROP INDEX prefix has
+    /// already been consumed.
+    fn parse_ddl_drop_index(&mut self) -> Result<ast::Statement> {
+        let mut if_exists = false;
+        if let Some(Token::Keyword(Keyword::If)) = self.next_if_keyword() {
+            self.next_expect(Some(Token::Keyword(Keyword::Exists)))?;
+            if_exists = true;
+        }
+        let name = self.next_name()?;
+        Ok(ast::Statement::DropIndex { name, if_exists })
+    }
+
+    /// Parses a CREATE [TEMPORARY] TABLE DDL statement. The CREATE
+    /// [TEMPORARY] TABLE prefix has already been consumed.
+    fn parse_ddl_create_table(&mut self, temporary: bool) -> Result<ast::Statement> {
+        let name = self.next_name()?;
+        if self.next_if_token(Keyword::As.into()).is_some() {
+            let query = Box::new(self.parse_query()?);
+            return Ok(ast::Statement::CreateTableAs { name, query, temporary });
+        }
+        self.next_expect(Some(Token::OpenParen))?;
+
+        let mut columns = Vec::new();
+        let mut unique = Vec::new();
+        loop {
+            if self.next_if_token(Keyword::Unique.into()).is_some() {
+                unique.push(self.parse_ddl_column_list()?);
+            } else {
+                columns.push(self.parse_ddl_columnspec()?);
+            }
+            if self.next_if_token(Token::Comma).is_none() {
+                break;
+            }
+        }
+        self.next_expect(Some(Token::CloseParen))?;
+        Ok(ast::Statement::CreateTable { name, columns, unique, temporary })
     }
 
     /// Parses a DROP TABLE DDL statement. The DROP TABLE prefix has
@@ -157,34 +447,264 @@ impl<'a> Parser<'a> {
             self.next_expect(Some(Token::Keyword(Keyword::Exists)))?;
             if_exists = true;
         }
-        let name = self.next_ident()?;
+        let name = self.next_name()?;
         Ok(ast::Statement::DropTable { name, if_exists })
     }
 
+    /// Parses a CREATE VIEW DDL statement. The CREATE VIEW prefix has
+    /// already been consumed. The query runs to the end of the statement, and
+    /// its text is kept for storing in the catalog.
+    fn parse_ddl_create_view(&mut self) -> Result<ast::Statement> {
+        let name = self.next_name()?;
+        let mut columns = Vec::new();
+        if self.peek()? == Some(Token::OpenParen) {
+            columns = self.parse_ddl_column_list()?;
+        }
+        self.next_expect(Some(Keyword::As.into()))?;
+        let sql = self.remaining()?.trim();
+        let sql = sql.strip_suffix(';').unwrap_or(sql).trim_end().to_string();
+        let query = self.parse_query()?;
+        Ok(ast::Statement::CreateView { name, columns, query: Box::new(query), sql })
+    }
+
+    /// Parses a SELECT or VALUES query, e.g. for CREATE TABLE ... AS, CREATE
+    /// VIEW, or a common table expression.
+    fn parse_query(&mut self) -> Result<ast::Statement> {
+        match self.peek()? {
+            Some(Token::Keyword(Keyword::Select | Keyword::With)) => self.parse_statement_select(),
+            Some(Token::Keyword(Keyword::Values)) => self.parse_statement_values(),
+            Some(token) => Err(Error::Parse(format!("Unexpected token {}", token))),
+            None => Err(Error::Parse("Unexpected end of input".into())),
+        }
+    }
+
+    /// Parses a DROP VIEW DDL statement. The DROP VIEW prefix has already
+    /// been consumed.
+    fn parse_ddl_drop_view(&mut self) -> Result<ast::Statement> {
+        let mut if_exists = false;
+        if let Some(Token::Keyword(Keyword::If)) = self.next_if_keyword() {
+            self.next_expect(Some(Token::Keyword(Keyword::Exists)))?;
+            if_exists = true;
+        }
+        let name = self.next_name()?;
+        Ok(ast::Statement::DropView { name, if_exists })
+    }
+
+    /// Parses a CREATE TRIGGER DDL statement. The CREATE TRIGGER prefix has
+    /// already been consumed. TRIGGER, BEFORE, AFTER, EACH, ROW and EXECUTE
+    /// aren't reserved keywords. The action runs to the end of the statement,
+    /// and its text is kept for storing in the catalog.
+    fn parse_ddl_create_trigger(&mut self) -> Result<ast::Statement> {
+        let name = self.next_ident()?;
+        let timing = match self.next()? {
+            Token::Ident(ident) if ident == "before" => TriggerTiming::Before,
+            Token::Ident(ident) if ident == "after" => TriggerTiming::After,
+            token => {
+                return Err(Error::Parse(format!(
+                    "Unexpected token {}, wanted BEFORE or AFTER",
+                    token
+                )))
+            }
+        };
+        let event = match self.next()? {
+            Token::Keyword(Keyword::Insert) => TriggerEvent::Insert,
+            Token::Keyword(Keyword::Update) => TriggerEvent::Update,
+            Token::Keyword(Keyword::Delete) => TriggerEvent::Delete,
+            token => {
+                return Err(Error::Parse(format!(
+                    "Unexpected token {}, wanted INSERT, UPDATE or DELETE",
+                    token
+                )))
+            }
+        };
+        self.next_expect(Some(Keyword::On.into()))?;
+        let table = self.next_name()?;
+        self.next_expect(Some(Keyword::For.into()))?;
+        self.next_expect(Some(Token::Ident("each".into())))?;
+        self.next_expect(Some(Token::Ident("row".into())))?;
+        self.next_expect(Some(Token::Ident("execute".into())))?;
+        let sql = self.remaining()?.trim();
+        let sql = sql.strip_suffix(';').unwrap_or(sql).trim_end().to_string();
+        let action = self.parse_ddl_trigger_action()?;
+        Ok(ast::Statement::CreateTrigger { name, table, timing, event, action, sql })
+    }
+
+    /// Parses a trigger action, i.e. SET assignments or a parenthesized
+    /// INSERT, UPDATE or DELETE statement.
+    fn parse_ddl_trigger_action(&mut self) -> Result<ast::TriggerAction> {
+        if self.peek()? == Some(Keyword::Set.into()) {
+            return Ok(ast::TriggerAction::Set(self.parse_clause_set()?));
+        }
+        self.next_expect(Some(Token::OpenParen))?;
+        let statement = match self.peek()? {
+            Some(Token::Keyword(Keyword::Insert | Keyword::Update | Keyword::Delete)) => {
+                self.parse_statement()?
+            }
+            _ => {
+                return Err(Error::Parse(
+                    "Trigger action must be SET or an INSERT, UPDATE or DELETE statement".into(),
+                ))
+            }
+        };
+        self.next_expect(Some(Token::CloseParen))?;
+        Ok(ast::TriggerAction::Statement(Box::new(statement)))
+    }
+
+    /// Parses a DROP TRIGGER DDL statement. The DROP TRIGGER prefix has
+    /// already been consumed.
+    fn parse_ddl_drop_trigger(&mut self) -> Result<ast::Statement> {
+        let mut if_exists = false;
+        if let Some(Token::Keyword(Keyword::If)) = self.next_if_keyword() {
+            self.next_expect(Some(Token::Keyword(Keyword::Exists)))?;
+            if_exists = true;
+        }
+        let name = self.next_ident()?;
+        self.next_expect(Some(Keyword::On.into()))?;
+        let table = self.next_name()?;
+        Ok(ast::Statement::DropTrigger { name, table, if_exists })
+    }
+
+    /// Parses a CREATE SEQUENCE DDL statement, with optional START [WITH] and
+    /// INCREMENT [BY] options in any order. The CREATE SEQUENCE prefix has
+    /// already been consumed.
+    fn parse_ddl_create_sequence(&mut self) -> Result<ast::Statement> {
+        let name = self.next_name()?;
+        let (mut start, mut increment) = (None, None);
+        loop {
+            if self.next_if_ident(&["start"]).is_some() {
+                self.next_if_token(Keyword::With.into());
+                start = Some(self.next_integer()?);
+            } else if self.next_if_ident(&["increment"]).is_some() {
+                self.next_if_token(Keyword::By.into());
+                increment = Some(self.next_integer()?);
+            } else {
+                break;
+            }
+        }
+        Ok(ast::Statement::CreateSequence { name, start, increment })
+    }
+
+    /// Parses a DROP SEQUENCE DDL statement. The DROP SEQUENCE prefix has
+    /// already been consumed.
+    fn parse_ddl_drop_sequence(&mut self) -> Result<ast::Statement> {
+        let mut if_exists = false;
+        if let Some(Token::Keyword(Keyword::If)) = self.next_if_keyword() {
+            self.next_expect(Some(Token::Keyword(Keyword::Exists)))?;
+            if_exists = true;
+        }
+        let name = self.next_name()?;
+        Ok(ast::Statement::DropSequence { name, if_exists })
+    }
+
+    /// Parses a CREATE TYPE ... AS ENUM DDL statement. The CREATE TYPE prefix
+    /// has already been consumed.
+    fn parse_ddl_create_type(&mut self) -> Result<ast::Statement> {
+        let name = self.next_name()?;
+        self.next_expect(Some(Keyword::As.into()))?;
+        self.next_expect(Some(Token::Ident("enum".into())))?;
+        self.next_expect(Some(Token::OpenParen))?;
+        let mut labels = Vec::new();
+        loop {
+            match self.next()? {
+                Token::String(label) => labels.push(label),
+                token => return Err(Error::Parse(format!("Unexpected token {}", token))),
+            }
+            if self.next_if_token(Token::Comma).is_none() {
+                break;
+            }
+        }
+        self.next_expect(Some(Token::CloseParen))?;
+        Ok(ast::Statement::CreateType { name, labels })
+    }
+
+    /// Parses a DROP TYPE DDL statement. The DROP TYPE prefix has already
+    /// been consumed.
+    fn parse_ddl_drop_type(&mut self) -> Result<ast::Statement> {
+        let mut if_exists = false;
+        if let Some(Token::Keyword(Keyword::If)) = self.next_if_keyword() {
+            self.next_expect(Some(Token::Keyword(Keyword::Exists)))?;
+            if_exists = true;
+        }
+        let name = self.next_name()?;
+        Ok(ast::Statement::DropType { name, if_exists })
+    }
+
+    /// Parses a CREATE USER DDL statement, with an optional [WITH] PASSWORD
+    /// clause. The CREATE USER prefix has already been consumed.
+    fn parse_ddl_create_user(&mut self) -> Result<ast::Statement> {
+        let name = self.next_ident()?;
+        self.next_if_token(Keyword::With.into());
+        let mut password = None;
+        if self.next_if_ident(&["password"]).is_some() {
+            password = self.parse_ddl_password()?;
+        }
+        Ok(ast::Statement::CreateUser { name, password })
+    }
+
+    /// Parses an ALTER USER ... [WITH] PASSWORD DDL statement. The ALTER USER
+    /// prefix has already been consumed.
+    fn parse_ddl_alter_user(&mut self) -> Result<ast::Statement> {
+        let name = self.next_ident()?;
+        self.next_if_token(Keyword::With.into());
+        self.next_expect(Some(Token::Ident("password".into())))?;
+        Ok(ast::Statement::AlterUser { name, password: self.parse_ddl_password()? })
+    }
+
+    /// Parses a password string literal, or NULL for no password.
+    fn parse_ddl_password(&mut self) -> Result<Option<String>> {
+        match self.next()? {
+            Token::String(password) => Ok(Some(password)),
+            Token::Keyword(Keyword::Null) => Ok(None),
+            token => Err(Error::Parse(format!("Expected password string, got {}", token))),
+        }
+    }
+
+    /// Parses a DROP USER DDL statement. The DROP USER prefix has already
+    /// been consumed.
+    fn parse_ddl_drop_user(&mut self) -> Result<ast::Statement> {
+        let mut if_exists = false;
+        if let Some(Token::Keyword(Keyword::If)) = self.next_if_keyword() {
+            self.next_expect(Some(Token::Keyword(Keyword::Exists)))?;
+            if_exists = true;
+        }
+        let name = self.next_ident()?;
+        Ok(ast::Statement::DropUser { name, if_exists })
+    }
+
+    /// Parses an integer literal, optionally negative.
+    fn next_integer(&mut self) -> Result<i64> {
+        let negative = self.next_if_token(Token::Minus).is_some();
+        match self.next()? {
+            Token::Number(n) if n.chars().all(|c| c.is_ascii_digit()) => {
+                let n: i64 = n.parse()?;
+                Ok(if negative { -n } else { n })
+            }
+            token => Err(Error::Parse(format!("Unexpected token {}, wanted integer", token))),
+        }
+    }
+
     /// Parses a column specification
     fn parse_ddl_columnspec(&mut self) -> Result<ast::Column> {
+        let name = self.next_ident()?;
+        // SERIAL is shorthand for INTEGER AUTO_INCREMENT.
+        let serial = self.next_if_token(Keyword::Serial.into()).is_some();
         let mut column = ast::Column {
-            name: self.next_ident()?,
-            datatype: match self.next()? {
-                Token::Keyword(Keyword::Bool) => DataType::Boolean,
-                Token::Keyword(Keyword::Boolean) => DataType::Boolean,
-                Token::Keyword(Keyword::Char) => DataType::String,
-                Token::Keyword(Keyword::Double) => DataType::Float,
-                Token::Keyword(Keyword::Float) => DataType::Float,
-                Token::Keyword(Keyword::Int) => DataType::Integer,
-                Token::Keyword(Keyword::Integer) => DataType::Integer,
-                Token::Keyword(Keyword::String) => DataType::String,
-                Token::Keyword(Keyword::Text) => DataType::String,
-                Token::Keyword(Keyword::Varchar) => DataType::String,
-                token => return Err(Error::Parse(format!("Unexpected token {}", token))),
-            },
+            name,
+            datatype: if serial { DataType::Integer } else { self.parse_datatype()? },
+            collation: Collation::Binary,
             primary_key: false,
             nullable: None,
             default: None,
+            generated: None,
             unique: false,
             index: false,
+            auto_increment: serial,
             references: None,
+            on_delete: ReferenceAction::Restrict,
+            on_update: ReferenceAction::Restrict,
+            deferred: false,
         };
+        let mut deferrable = None;
         while let Some(Token::Keyword(keyword)) = self.next_if_keyword() {
             match keyword {
                 Keyword::Primary => {
@@ -200,6 +720,9 @@ impl<'a> Parser<'a> {
                     }
                     column.nullable = Some(true)
                 }
+                Keyword::Not if self.next_if_token(Keyword::Deferrable.into()).is_some() => {
+                    deferrable = Some(false)
+                }
                 Keyword::Not => {
                     self.next_expect(Some(Keyword::Null.into()))?;
                     if let Some(true) = column.nullable {
@@ -210,38 +733,457 @@ impl<'a> Parser<'a> {
                     }
                     column.nullable = Some(false)
                 }
+                Keyword::Collate => {
+                    let name = self.next_ident()?;
+                    column.collation = Collation::lookup(&name)
+                        .ok_or_else(|| Error::Value(format!("Unknown collation {}", name)))?;
+                }
                 Keyword::Default => column.default = Some(self.parse_expression(0)?),
+                Keyword::Generated => {
+                    self.next_expect(Some(Token::Ident("always".into())))?;
+                    self.next_expect(Some(Keyword::As.into()))?;
+                    self.next_expect(Some(Token::OpenParen))?;
+                    column.generated = Some(self.parse_expression(0)?);
+                    self.next_expect(Some(Token::CloseParen))?;
+                    self.next_expect(Some(Token::Ident("stored".into())))?;
+                }
                 Keyword::Unique => column.unique = true,
                 Keyword::Index => column.index = true,
-                Keyword::References => column.references = Some(self.next_ident()?),
+                Keyword::AutoIncrement => column.auto_increment = true,
+                Keyword::References => {
+                    column.references = Some(self.next_name()?);
+                    while self.next_if_token(Keyword::On.into()).is_some() {
+                        match self.next()? {
+                            Token::Keyword(Keyword::Delete) => {
+                                column.on_delete = self.parse_ddl_reference_action()?
+                            }
+                            Token::Keyword(Keyword::Update) => {
+                                column.on_update = self.parse_ddl_reference_action()?
+                            }
+                            token => {
+                                return Err(Error::Parse(format!("Unexpected token {}", token)))
+                            }
+                        }
+                    }
+                }
+                Keyword::Deferrable => deferrable = Some(true),
+                Keyword::Initially => match self.next()? {
+                    Token::Keyword(Keyword::Deferred) => column.deferred = true,
+                    Token::Keyword(Keyword::Immediate) => column.deferred = false,
+                    token => return Err(Error::Parse(format!("Unexpected token {}", token))),
+                },
                 keyword => return Err(Error::Parse(format!("Unexpected keyword {}", keyword))),
             }
         }
+        if column.deferred && deferrable == Some(false) {
+            return Err(Error::Value(format!(
+                "Column {} can't be both not deferrable and initially deferred",
+                column.name
+            )));
+        }
         Ok(column)
     }
 
+    /// Parses a foreign key referential action, for ON DELETE or ON UPDATE
+    fn parse_ddl_reference_action(&mut self) -> Result<ReferenceAction> {
+        match self.next()? {
+            Token::Keyword(Keyword::Cascade) => Ok(ReferenceAction::Cascade),
+            Token::Keyword(Keyword::Restrict) => Ok(ReferenceAction::Restrict),
+            Token::Keyword(Keyword::Set) => {
+                self.next_expect(Some(Keyword::Null.into()))?;
+                Ok(ReferenceAction::SetNull)
+            }
+            token => Err(Error::Parse(format!("Unexpected token {}", token))),
+        }
+    }
+
+    /// Parses a data type name, optionally followed by [] for an array type
+    fn parse_datatype(&mut self) -> Result<DataType> {
+        let datatype = self.parse_datatype_scalar()?;
+        if self.next_if_token(Token::OpenBracket).is_none() {
+            return Ok(datatype);
+        }
+        self.next_expect(Some(Token::CloseBracket))?;
+        if self.peek()? == Some(Token::OpenBracket) {
+            return Err(Error::Parse("Multidimensional arrays are not supported".into()));
+        }
+        Ok(DataType::Array(Box::new(datatype)))
+    }
+
+    /// Parses a scalar data type name
+    fn parse_datatype_scalar(&mut self) -> Result<DataType> {
+        Ok(match self.next()? {
+            Token::Keyword(Keyword::Bool) => DataType::Boolean,
+            Token::Keyword(Keyword::Boolean) => DataType::Boolean,
+            Token::Keyword(Keyword::Bytea) => DataType::Bytes,
+            Token::Keyword(Keyword::Char) => DataType::String,
+            Token::Keyword(Keyword::Date) => DataType::Date,
+            Token::Keyword(Keyword::Decimal) => DataType::Decimal,
+            Token::Keyword(Keyword::Double) => DataType::Float,
+            Token::Keyword(Keyword::Float) => DataType::Float,
+            Token::Keyword(Keyword::Int) => DataType::Integer,
+            Token::Keyword(Keyword::Integer) => DataType::Integer,
+            Token::Keyword(Keyword::Interval) => DataType::Interval,
+            Token::Keyword(Keyword::Json) => DataType::Json,
+            Token::Keyword(Keyword::Numeric) => DataType::Decimal,
+            Token::Keyword(Keyword::String) => DataType::String,
+            Token::Keyword(Keyword::Text) => DataType::String,
+            Token::Keyword(Keyword::Time) => DataType::Time,
+            Token::Keyword(Keyword::Timestamp) => DataType::Timestamp,
+            Token::Keyword(Keyword::Uuid) => DataType::Uuid,
+            Token::Keyword(Keyword::Varchar) => DataType::String,
+            // Other names refer to enum types, whose labels are resolved by the planner.
+            Token::Ident(name) => match self.next_if_token(Token::Period) {
+                Some(_) => DataType::Enum(format!("{}.{}", name, self.next_ident()?), Vec::new()),
+                None => DataType::Enum(name, Vec::new()),
+            },
+            token => return Err(Error::Parse(format!("Unexpected token {}", token))),
+        })
+    }
+
+    /// Parses a COPY statement
+    fn parse_statement_copy(&mut self) -> Result<ast::Statement> {
+        self.next_ident()?;
+        if self.next_if_token(Token::OpenParen).is_some() {
+            let query = self.parse_statement_select()?;
+            self.next_expect(Some(Token::CloseParen))?;
+            self.next_expect(Some(Keyword::To.into()))?;
+            return self.parse_statement_copy_to(query);
+        }
+        let table = self.next_name()?;
+        let columns = match self.peek()? {
+            Some(Token::OpenParen) => Some(self.parse_ddl_column_list()?),
+            _ => None,
+        };
+        if self.next_if_token(Keyword::To.into()).is_some() {
+            let select = columns
+                .unwrap_or_default()
+                .into_iter()
+                .map(|column| (ast::Expression::Field(None, column), None))
+                .collect();
+            return self.parse_statement_copy_to(ast::Statement::Select {
+                with: Vec::new(),
+                hints: Vec::new(),
+                select,
+                distinct: false,
+                distinct_on: Vec::new(),
+                from: vec![ast::FromItem::Table { name: table, alias: None }],
+                r#where: None,
+                group_by: Vec::new(),
+                grouping_sets: None,
+                having: None,
+                order: Vec::new(),
+                limit: None,
+                offset: None,
+            });
+        }
+        self.next_expect(Some(Keyword::From.into()))?;
+        let source = match self.next()? {
+            Token::String(path) => ast::CopySource::File(path),
+            Token::Ident(ident) if ident == "stdin" => ast::CopySource::Stdin,
+            token => {
+                return Err(Error::Parse(format!(
+                    "Unexpected token {}, wanted file path or STDIN",
+                    token
+                )))
+            }
+        };
+        let options = self.parse_copy_options()?;
+        if options.format != ast::CopyFormat::Csv {
+            return Err(Error::Parse("COPY FROM only supports the CSV format".into()));
+        }
+        Ok(ast::Statement::CopyFrom { table, columns, source, options })
+    }
+
+    /// Parses a USE statement, which sets the session's current database.
+    fn parse_statement_use(&mut self) -> Result<ast::Statement> {
+        self.next_ident()?;
+        Ok(ast::Statement::Use { database: self.next_ident()? })
+    }
+
+    /// Parses a COMMENT ON TABLE or COMMENT ON COLUMN statement.
+    fn parse_statement_comment(&mut self) -> Result<ast::Statement> {
+        self.next_ident()?;
+        self.next_expect(Some(Keyword::On.into()))?;
+        let (table, column) = match self.next()? {
+            Token::Keyword(Keyword::Table) => (self.next_name()?, None),
+            // The column is qualified by the table, which may be qualified by the database.
+            Token::Keyword(Keyword::Column) => {
+                let mut table = self.next_ident()?;
+                self.next_expect(Some(Token::Period))?;
+                let mut column = self.next_ident()?;
+                if self.next_if_token(Token::Period).is_some() {
+                    table = format!("{}.{}", table, column);
+                    column = self.next_ident()?;
+                }
+                (table, Some(column))
+            }
+            token => {
+                return Err(Error::Parse(format!(
+                    "Unexpected token {}, wanted TABLE or COLUMN",
+                    token
+                )))
+            }
+        };
+        self.next_expect(Some(Keyword::Is.into()))?;
+        let comment = match self.next()? {
+            Token::String(comment) => Some(comment),
+            Token::Keyword(Keyword::Null) => None,
+            token => {
+                return Err(Error::Parse(format!(
+                    "Unexpected token {}, wanted string or NULL",
+                    token
+                )))
+            }
+        };
+        Ok(ast::Statement::Comment { table, column, comment })
+    }
+
+    /// Parses a GRANT or REVOKE statement, for either table privileges or a
+    /// role.
+    fn parse_statement_grant(&mut self) -> Result<ast::Statement> {
+        let grant = self.next_ident()? == "grant";
+        let target = match grant {
+            true => Keyword::To,
+            false => Keyword::From,
+        };
+        let Some(privileges) = self.parse_privileges()? else {
+            let role = self.next_ident()?;
+            self.next_expect(Some(target.into()))?;
+            let user = self.next_ident()?;
+            return Ok(match grant {
+                true => ast::Statement::GrantRole { role, user },
+                false => ast::Statement::RevokeRole { role, user },
+            });
+        };
+        self.next_expect(Some(Keyword::On.into()))?;
+        self.next_if_token(Keyword::Table.into());
+        let table = self.next_name()?;
+        self.next_expect(Some(target.into()))?;
+        let grantee = self.next_ident()?;
+        Ok(match grant {
+            true => ast::Statement::Grant { privileges, table, grantee },
+            false => ast::Statement::Revoke { privileges, table, grantee },
+        })
+    }
+
+    /// Parses a comma-separated list of table privileges, or ALL [PRIVILEGES].
+    /// Returns None if the next token isn't a privilege, e.g. for a role name.
+    fn parse_privileges(&mut self) -> Result<Option<Vec<Privilege>>> {
+        if self.next_if_token(Keyword::All.into()).is_some() {
+            self.next_if_ident(&["privileges"]);
+            return Ok(Some(Privilege::ALL.to_vec()));
+        }
+        let mut privileges = Vec::new();
+        loop {
+            let privilege = match self.peek()? {
+                Some(Token::Keyword(Keyword::Select)) => Privilege::Select,
+                Some(Token::Keyword(Keyword::Insert)) => Privilege::Insert,
+                Some(Token::Keyword(Keyword::Update)) => Privilege::Update,
+                Some(Token::Keyword(Keyword::Delete)) => Privilege::Delete,
+                Some(Token::Ident(ident)) if ident == "ddl" => Privilege::Ddl,
+                Some(Token::Keyword(Keyword::References)) => Privilege::References,
+                Some(token) if !privileges.is_empty() => {
+                    return Err(Error::Parse(format!("Expected privilege, got {}", token)))
+                }
+                _ => return Ok(None),
+            };
+            self.next()?;
+            privileges.push(privilege);
+            if self.next_if_token(Token::Comma).is_none() {
+                return Ok(Some(privileges));
+            }
+        }
+    }
+
+    /// Parses the target file and options of a COPY TO statement, given the
+    /// query to export.
+    fn parse_statement_copy_to(&mut self, query: ast::Statement) -> Result<ast::Statement> {
+        let path = match self.next()? {
+            Token::String(path) => path,
+            token => {
+                return Err(Error::Parse(format!("Unexpected token {}, wanted file path", token)))
+            }
+        };
+        let options = self.parse_copy_options()?;
+        Ok(ast::Statement::CopyTo { query: Box::new(query), path, options })
+    }
+
+    /// Parses the options of a COPY statement, if any, as an optionally
+    /// WITH-prefixed parenthesized list.
+    fn parse_copy_options(&mut self) -> Result<ast::CopyOptions> {
+        let mut options = ast::CopyOptions::default();
+        let with = self.next_if_token(Keyword::With.into()).is_some();
+        if self.next_if_token(Token::OpenParen).is_none() {
+            return match with {
+                true => Err(self.unexpected("(")),
+                false => Ok(options),
+            };
+        }
+        loop {
+            match self.next()? {
+                Token::Ident(option) if option == "format" => {
+                    options.format = match self.next()? {
+                        Token::Ident(format) if format == "csv" => ast::CopyFormat::Csv,
+                        Token::Keyword(Keyword::Json) => ast::CopyFormat::Json,
+                        token => {
+                            return Err(Error::Parse(format!("Unknown COPY format {}", token)))
+                        }
+                    }
+                }
+                Token::Ident(option) if option == "header" => {
+                    options.header = self.parse_option_flag()?
+                }
+                Token::Ident(option) if option == "delimiter" => match self.next()? {
+                    Token::String(s) if s.len() == 1 => options.delimiter = s.as_bytes()[0],
+                    token => {
+                        return Err(Error::Parse(format!(
+                            "COPY delimiter must be a single-byte character, found {}",
+                            token
+                        )))
+                    }
+                },
+                Token::Keyword(Keyword::Null) => match self.next()? {
+                    Token::String(s) => options.null = s,
+                    token => return Err(Error::Parse(format!("Expected string, found {}", token))),
+                },
+                token => return Err(Error::Parse(format!("Unknown COPY option {}", token))),
+            }
+            if self.next_if_token(Token::Comma).is_none() {
+                break;
+            }
+        }
+        self.next_expect(Some(Token::CloseParen))?;
+        Ok(options)
+    }
+
     /// Parses a delete statement
     fn parse_statement_delete(&mut self) -> Result<ast::Statement> {
         self.next_expect(Some(Keyword::Delete.into()))?;
         self.next_expect(Some(Keyword::From.into()))?;
-        let table = self.next_ident()?;
-        Ok(ast::Statement::Delete { table, r#where: self.parse_clause_where()? })
+        let table = self.next_name()?;
+        let using = match self.next_if_token(Keyword::Using.into()) {
+            Some(_) => self.parse_clause_from_items()?,
+            None => Vec::new(),
+        };
+        let r#where = self.parse_clause_where()?;
+        let returning = self.parse_clause_returning()?;
+        Ok(ast::Statement::Delete { table, using, r#where, returning })
     }
 
-    /// Parses a delete statement
+    /// Parses an ANALYZE statement
+    fn parse_statement_analyze(&mut self) -> Result<ast::Statement> {
+        self.next_expect(Some(Keyword::Analyze.into()))?;
+        let table = match self.peek()? {
+            Some(Token::Ident(_)) => Some(self.next_name()?),
+            _ => None,
+        };
+        Ok(ast::Statement::Analyze { table })
+    }
+
+    /// Parses a DESCRIBE statement
+    fn parse_statement_describe(&mut self) -> Result<ast::Statement> {
+        self.next_expect(Some(Keyword::Describe.into()))?;
+        Ok(ast::Statement::Describe { name: self.next_name()? })
+    }
+
+    /// Parses a SHOW statement
+    fn parse_statement_show(&mut self) -> Result<ast::Statement> {
+        self.next_expect(Some(Keyword::Show.into()))?;
+        match self.next()? {
+            Token::Keyword(Keyword::Tables) => Ok(ast::Statement::ShowTables),
+            Token::Keyword(Keyword::Create) => {
+                self.next_expect(Some(Keyword::Table.into()))?;
+                Ok(ast::Statement::ShowCreateTable { name: self.next_name()? })
+            }
+            Token::Keyword(Keyword::All) => Ok(ast::Statement::ShowAll),
+            Token::Keyword(Keyword::Queries) => Ok(ast::Statement::ShowQueries),
+            Token::Ident(name) if name == "databases" => Ok(ast::Statement::ShowDatabases),
+            Token::Ident(name) => Ok(ast::Statement::ShowSetting { name }),
+            token => Err(Error::Parse(format!("Unexpected token {}", token))),
+        }
+    }
+
+    /// Parses a KILL statement
+    fn parse_statement_kill(&mut self) -> Result<ast::Statement> {
+        self.next_expect(Some(Keyword::Kill.into()))?;
+        match self.next()? {
+            Token::Number(n) => Ok(ast::Statement::Kill { id: n.parse::<u64>()? }),
+            token => Err(Error::Parse(format!("Unexpected token {}, wanted number", token))),
+        }
+    }
+
+    /// Parses a SET statement, for session settings
+    fn parse_statement_set(&mut self) -> Result<ast::Statement> {
+        self.next_expect(Some(Keyword::Set.into()))?;
+        let name = self.next_ident()?;
+        if self.next_if_token(Keyword::To.into()).is_none() {
+            self.next_expect(Some(Token::Equal))?;
+        }
+        let value = match self.next_if_token(Keyword::Default.into()) {
+            Some(_) => None,
+            None => Some(self.parse_expression(0)?),
+        };
+        Ok(ast::Statement::Set { name, value })
+    }
+
+    /// Parses an explain statement, with either a parenthesized option list
+    /// or the bare ANALYZE and VERBOSE options.
     fn parse_statement_explain(&mut self) -> Result<ast::Statement> {
         self.next_expect(Some(Keyword::Explain.into()))?;
+        let (mut analyze, mut verbose, mut format) = (false, false, ast::ExplainFormat::Text);
+        if self.next_if_token(Token::OpenParen).is_some() {
+            loop {
+                match self.next()? {
+                    Token::Keyword(Keyword::Analyze) => analyze = self.parse_option_flag()?,
+                    Token::Ident(option) if option == "verbose" => {
+                        verbose = self.parse_option_flag()?
+                    }
+                    Token::Ident(option) if option == "format" => {
+                        format = match self.next()? {
+                            Token::Keyword(Keyword::Text) => ast::ExplainFormat::Text,
+                            Token::Keyword(Keyword::Json) => ast::ExplainFormat::Json,
+                            token => {
+                                return Err(Error::Parse(format!(
+                                    "Unknown EXPLAIN format {}",
+                                    token
+                                )))
+                            }
+                        }
+                    }
+                    token => return Err(Error::Parse(format!("Unknown EXPLAIN option {}", token))),
+                }
+                if self.next_if_token(Token::Comma).is_none() {
+                    break;
+                }
+            }
+            self.next_expect(Some(Token::CloseParen))?;
+        } else {
+            analyze = self.next_if_token(Keyword::Analyze.into()).is_some();
+            verbose = self.next_if(|t| matches!(t, Token::Ident(i) if i == "verbose")).is_some();
+        }
         if let Some(Token::Keyword(Keyword::Explain)) = self.peek()? {
             return Err(Error::Parse("Cannot nest EXPLAIN statements".into()));
         }
-        Ok(ast::Statement::Explain(Box::new(self.parse_statement()?)))
+        let statement = Box::new(self.parse_statement()?);
+        Ok(ast::Statement::Explain { statement, analyze, verbose, format })
+    }
+
+    /// Parses the optional boolean value of an EXPLAIN or COPY option,
+    /// defaulting to true.
+    fn parse_option_flag(&mut self) -> Result<bool> {
+        Ok(match self.next_if_keyword() {
+            Some(Token::Keyword(Keyword::True)) | None => true,
+            Some(Token::Keyword(Keyword::False)) => false,
+            Some(token) => return Err(Error::Parse(format!("Expected boolean, found {}", token))),
+        })
     }
 
     /// Parses an insert statement
     fn parse_statement_insert(&mut self) -> Result<ast::Statement> {
         self.next_expect(Some(Keyword::Insert.into()))?;
         self.next_expect(Some(Keyword::Into.into()))?;
-        let table = self.next_ident()?;
+        let table = self.next_name()?;
 
         let columns = if self.next_if_token(Token::OpenParen).is_some() {
             let mut cols = Vec::new();
@@ -258,115 +1200,301 @@ impl<'a> Parser<'a> {
             None
         };
 
-        self.next_expect(Some(Keyword::Values.into()))?;
-        let mut values = Vec::new();
-        loop {
-            self.next_expect(Some(Token::OpenParen))?;
-            let mut exprs = Vec::new();
-            loop {
-                exprs.push(self.parse_expression(0)?);
-                match self.next()? {
-                    Token::CloseParen => break,
-                    Token::Comma => {}
-                    token => return Err(Error::Parse(format!("Unexpected token {}", token))),
+        let values = self.parse_clause_values()?;
+        let on_conflict = match self.next_if_token(Keyword::On.into()) {
+            Some(_) => Some(self.parse_clause_on_conflict()?),
+            None => None,
+        };
+        let returning = self.parse_clause_returning()?;
+
+        Ok(ast::Statement::Insert { table, columns, values, on_conflict, returning })
+    }
+
+    /// Parses a standalone VALUES statement, as a query selecting all columns
+    /// from the VALUES list
+    fn parse_statement_values(&mut self) -> Result<ast::Statement> {
+        let rows = self.parse_clause_values()?;
+        let order = self.parse_clause_order()?;
+        let (limit, offset) = self.parse_clause_limit()?;
+        Ok(ast::Statement::Select {
+            with: Vec::new(),
+            hints: Vec::new(),
+            select: Vec::new(),
+            distinct: false,
+            distinct_on: Vec::new(),
+            from: vec![ast::FromItem::Values {
+                rows,
+                alias: "*VALUES*".into(),
+                columns: Vec::new(),
+            }],
+            r#where: None,
+            group_by: Vec::new(),
+            grouping_sets: None,
+            having: None,
+            order,
+            limit,
+            offset,
+        })
+    }
+
+    /// Parses a select statement, possibly combining several SELECT queries with set operators.
+    /// Any WITH, ORDER BY, LIMIT and OFFSET clauses apply to the combined query.
+    fn parse_statement_select(&mut self) -> Result<ast::Statement> {
+        let with = self.parse_clause_with()?;
+        if !with.is_empty() {
+            match self.peek()? {
+                Some(Token::Keyword(Keyword::Select)) => {}
+                Some(token) => {
+                    return Err(Error::Parse(format!("Expected token SELECT, found {}", token)))
                 }
+                None => return Err(Error::Parse("Unexpected end of input".into())),
             }
-            values.push(exprs);
-            if self.next_if_token(Token::Comma).is_none() {
-                break;
+        }
+        let mut query = self.parse_query_set(0)?;
+        match &mut query {
+            ast::Statement::Select { with: w, order, offset, limit, .. }
+            | ast::Statement::SetOperation { with: w, order, offset, limit, .. } => {
+                *w = with;
+                *order = self.parse_clause_order()?;
+                (*limit, *offset) = self.parse_clause_limit()?;
             }
+            statement => {
+                return Err(Error::Internal(format!("Unexpected query {:?}", statement)));
+            }
+        }
+        Ok(query)
+    }
+
+    /// Parses a top-level select statement, which may be followed by an AS OF
+    /// SYSTEM TIME clause.
+    fn parse_statement_select_as_of(&mut self) -> Result<ast::Statement> {
+        let query = self.parse_statement_select()?;
+        match self.parse_clause_as_of()? {
+            Some(as_of) => Ok(ast::Statement::AsOf { query: Box::new(query), as_of }),
+            None => Ok(query),
         }
+    }
 
-        Ok(ast::Statement::Insert { table, columns, values })
+    /// Parses SELECT queries combined by set operators, using precedence climbing: INTERSECT binds
+    /// tighter than UNION and EXCEPT, and all are left-associative.
+    fn parse_query_set(&mut self, min_prec: u8) -> Result<ast::Statement> {
+        let mut left = self.parse_query_select()?;
+        loop {
+            let (operator, prec) = match self.peek()? {
+                Some(Token::Keyword(Keyword::Except)) => (ast::SetOperator::Except, 1),
+                Some(Token::Keyword(Keyword::Intersect)) => (ast::SetOperator::Intersect, 2),
+                Some(Token::Keyword(Keyword::Union)) => (ast::SetOperator::Union, 1),
+                _ => break,
+            };
+            if prec < min_prec {
+                break;
+            }
+            self.next()?;
+            let all = match self.next_if_token(Keyword::All.into()) {
+                Some(_) => true,
+                None => {
+                    self.next_if_token(Keyword::Distinct.into());
+                    false
+                }
+            };
+            let right = self.parse_query_set(prec + 1)?;
+            left = ast::Statement::SetOperation {
+                with: Vec::new(),
+                operator,
+                all,
+                left: Box::new(left),
+                right: Box::new(right),
+                order: Vec::new(),
+                offset: None,
+                limit: None,
+            };
+        }
+        Ok(left)
     }
 
-    /// Parses a select statement
-    fn parse_statement_select(&mut self) -> Result<ast::Statement> {
+    /// Parses a single SELECT query, without ORDER BY, LIMIT or OFFSET clauses.
+    fn parse_query_select(&mut self) -> Result<ast::Statement> {
+        self.next_expect(Some(Keyword::Select.into()))?;
+        let hints = self.parse_hints()?;
+        let (distinct, distinct_on) = self.parse_clause_distinct()?;
+        let select = self.parse_clause_select()?;
+        let from = self.parse_clause_from()?;
+        let r#where = self.parse_clause_where()?;
+        let (group_by, grouping_sets) = self.parse_clause_group_by()?;
+        let having = self.parse_clause_having()?;
         Ok(ast::Statement::Select {
-            select: self.parse_clause_select()?,
-            from: self.parse_clause_from()?,
-            r#where: self.parse_clause_where()?,
-            group_by: self.parse_clause_group_by()?,
-            having: self.parse_clause_having()?,
-            order: self.parse_clause_order()?,
-            limit: if self.next_if_token(Keyword::Limit.into()).is_some() {
-                Some(self.parse_expression(0)?)
-            } else {
-                None
-            },
-            offset: if self.next_if_token(Keyword::Offset.into()).is_some() {
-                Some(self.parse_expression(0)?)
-            } else {
-                None
-            },
+            with: Vec::new(),
+            hints,
+            select,
+            distinct,
+            distinct_on,
+            from,
+            r#where,
+            group_by,
+            grouping_sets,
+            having,
+            order: Vec::new(),
+            limit: None,
+            offset: None,
         })
     }
 
-    /// Parses an update statement
-    fn parse_statement_update(&mut self) -> Result<ast::Statement> {
-        self.next_expect(Some(Keyword::Update.into()))?;
-        let table = self.next_ident()?;
-        self.next_expect(Some(Keyword::Set.into()))?;
-
-        let mut set = BTreeMap::new();
-        loop {
-            let column = self.next_ident()?;
-            self.next_expect(Some(Token::Equal))?;
-            let expr = self.parse_expression(0)?;
-            if set.contains_key(&column) {
-                return Err(Error::Value(format!("Duplicate values given for column {}", column)));
-            }
-            set.insert(column, expr);
-            if self.next_if_token(Token::Comma).is_none() {
-                break;
+    /// Parses optimizer hints from a hint comment, if any, e.g.
+    /// /*+ FORCE_INDEX(movies genre_id) NO_HASH_JOIN */. Hints are separated
+    /// by whitespace or commas.
+    fn parse_hints(&mut self) -> Result<Vec<ast::Hint>> {
+        let Some(Token::Hint(text)) = self.next_if(|t| matches!(t, Token::Hint(_))) else {
+            return Ok(Vec::new());
+        };
+        let mut parser = Parser::new(&text);
+        let mut hints = Vec::new();
+        while parser.peek()?.is_some() {
+            let name = parser.next_ident()?;
+            let mut args = Vec::new();
+            if parser.next_if_token(Token::OpenParen).is_some() {
+                while parser.next_if_token(Token::CloseParen).is_none() {
+                    args.push(parser.next_ident()?);
+                    parser.next_if_token(Token::Comma);
+                }
             }
+            hints.push(match (name.as_str(), args.len()) {
+                ("force_index", 2) => {
+                    let index = args.pop().expect("index argument");
+                    ast::Hint::ForceIndex { table: args.pop().expect("table argument"), index }
+                }
+                ("no_index", 1) => {
+                    ast::Hint::NoIndex { table: args.pop().expect("table argument") }
+                }
+                ("no_hash_join", 0) => ast::Hint::NoHashJoin,
+                ("no_merge_join", 0) => ast::Hint::NoMergeJoin,
+                ("fixed_join_order", 0) => ast::Hint::FixedJoinOrder,
+                (
+                    "force_index" | "no_index" | "no_hash_join" | "no_merge_join"
+                    | "fixed_join_order",
+                    _,
+                ) => {
+                    return Err(Error::Parse(format!(
+                        "Wrong number of arguments for hint {}",
+                        name
+                    )))
+                }
+                _ => return Err(Error::Parse(format!("Unknown hint {}", name))),
+            });
+            parser.next_if_token(Token::Comma);
         }
+        Ok(hints)
+    }
 
-        Ok(ast::Statement::Update { table, set, r#where: self.parse_clause_where()? })
+    /// Parses an update statement
+    fn parse_statement_update(&mut self) -> Result<ast::Statement> {
+        self.next_expect(Some(Keyword::Update.into()))?;
+        let table = self.next_name()?;
+        let set = self.parse_clause_set()?;
+        let from = self.parse_clause_from()?;
+        let r#where = self.parse_clause_where()?;
+        let returning = self.parse_clause_returning()?;
+        Ok(ast::Statement::Update { table, set, from, r#where, returning })
     }
 
     /// Parses a transaction statement
     fn parse_transaction(&mut self) -> Result<ast::Statement> {
         match self.next()? {
             Token::Keyword(Keyword::Begin) => {
-                let mut readonly = false;
-                let mut version = None;
+                let mut readonly = None;
                 self.next_if_token(Keyword::Transaction.into());
                 if self.next_if_token(Keyword::Read.into()).is_some() {
                     match self.next()? {
-                        Token::Keyword(Keyword::Only) => readonly = true,
-                        Token::Keyword(Keyword::Write) => readonly = false,
+                        Token::Keyword(Keyword::Only) => readonly = Some(true),
+                        Token::Keyword(Keyword::Write) => readonly = Some(false),
                         token => return Err(Error::Parse(format!("Unexpected token {}", token))),
                     }
                 }
-                if self.next_if_token(Keyword::As.into()).is_some() {
-                    self.next_expect(Some(Keyword::Of.into()))?;
-                    self.next_expect(Some(Keyword::System.into()))?;
-                    self.next_expect(Some(Keyword::Time.into()))?;
+                let as_of = self.parse_clause_as_of()?;
+                Ok(ast::Statement::Begin { read_only: readonly, as_of })
+            }
+            Token::Keyword(Keyword::Commit) => Ok(ast::Statement::Commit),
+            Token::Keyword(Keyword::Rollback) => {
+                if self.next_if_token(Keyword::To.into()).is_none() {
+                    return Ok(ast::Statement::Rollback);
+                }
+                self.next_if_token(Keyword::Savepoint.into());
+                Ok(ast::Statement::RollbackToSavepoint { name: self.next_ident()? })
+            }
+            Token::Keyword(Keyword::Savepoint) => {
+                Ok(ast::Statement::Savepoint { name: self.next_ident()? })
+            }
+            Token::Keyword(Keyword::Release) => {
+                self.next_if_token(Keyword::Savepoint.into());
+                Ok(ast::Statement::ReleaseSavepoint { name: self.next_ident()? })
+            }
+            token => Err(Error::Parse(format!("Unexpected token {}", token))),
+        }
+    }
+
+    /// Parses a WITH clause
+    fn parse_clause_with(&mut self) -> Result<Vec<ast::CommonTableExpression>> {
+        let mut ctes = Vec::new();
+        if self.next_if_token(Keyword::With.into()).is_none() {
+            return Ok(ctes);
+        }
+        loop {
+            let name = self.next_ident()?;
+            let mut columns = Vec::new();
+            if self.next_if_token(Token::OpenParen).is_some() {
+                loop {
+                    columns.push(self.next_ident()?);
                     match self.next()? {
-                        Token::Number(n) => version = Some(n.parse::<u64>()?),
-                        token => {
-                            return Err(Error::Parse(format!(
-                                "Unexpected token {}, wanted number",
-                                token
-                            )))
-                        }
+                        Token::CloseParen => break,
+                        Token::Comma => {}
+                        token => return Err(Error::Parse(format!("Unexpected token {}", token))),
                     }
                 }
-                Ok(ast::Statement::Begin { read_only: readonly, as_of: version })
             }
-            Token::Keyword(Keyword::Commit) => Ok(ast::Statement::Commit),
-            Token::Keyword(Keyword::Rollback) => Ok(ast::Statement::Rollback),
-            token => Err(Error::Parse(format!("Unexpected token {}", token))),
+            self.next_expect(Some(Keyword::As.into()))?;
+            self.next_expect(Some(Token::OpenParen))?;
+            let query = Box::new(self.parse_query()?);
+            self.next_expect(Some(Token::CloseParen))?;
+            ctes.push(ast::CommonTableExpression { name, columns, query });
+            if self.next_if_token(Token::Comma).is_none() {
+                break;
+            }
+        }
+        Ok(ctes)
+    }
+
+    /// Parses an AS OF SYSTEM TIME clause, if any, e.g. AS OF SYSTEM TIME '-5m'.
+    fn parse_clause_as_of(&mut self) -> Result<Option<ast::Expression>> {
+        if !self.peek_as_of()? {
+            return Ok(None);
         }
+        self.next_expect(Some(Keyword::As.into()))?;
+        self.next_expect(Some(Keyword::Of.into()))?;
+        self.next_expect(Some(Keyword::System.into()))?;
+        self.next_expect(Some(Keyword::Time.into()))?;
+        Ok(Some(self.parse_expression(0)?))
+    }
+
+    /// Returns true if the next tokens are AS OF, which starts an AS OF SYSTEM
+    /// TIME clause rather than an alias.
+    fn peek_as_of(&mut self) -> Result<bool> {
+        if self.peek()? != Some(Keyword::As.into()) {
+            return Ok(false);
+        }
+        let mut lexer = Lexer::new(self.lexer.remaining());
+        Ok(matches!(lexer.next(), Some(Ok(Token::Keyword(Keyword::Of)))))
     }
 
     /// Parses a from clause
     fn parse_clause_from(&mut self) -> Result<Vec<ast::FromItem>> {
-        let mut from = Vec::new();
         if self.next_if_token(Keyword::From.into()).is_none() {
-            return Ok(from);
+            return Ok(Vec::new());
         }
+        self.parse_clause_from_items()
+    }
+
+    /// Parses a comma-separated list of from items, e.g. following FROM or USING
+    fn parse_clause_from_items(&mut self) -> Result<Vec<ast::FromItem>> {
+        let mut from = Vec::new();
         loop {
             let mut item = self.parse_clause_from_item()?;
             while let Some(jointype) = self.parse_clause_from_jointype()? {
@@ -392,13 +1520,96 @@ impl<'a> Parser<'a> {
 
     /// Parses a from clause item
     fn parse_clause_from_item(&mut self) -> Result<ast::FromItem> {
+        if self.next_if_token(Keyword::Lateral.into()).is_some() {
+            return match self.parse_clause_from_item()? {
+                item @ (ast::FromItem::Subquery { .. } | ast::FromItem::Unnest { .. }) => {
+                    Ok(ast::FromItem::Lateral(Box::new(item)))
+                }
+                _ => Err(Error::Parse("LATERAL must be followed by a subquery or unnest()".into())),
+            };
+        }
+        if self.peek()? == Some(Token::OpenParen) {
+            // A subquery is told apart from a VALUES list by the keyword
+            // following the (peeked) parenthesis.
+            let mut lexer = Lexer::new(self.lexer.remaining());
+            if let Some(Ok(Token::Keyword(Keyword::Select | Keyword::With))) = lexer.next() {
+                return self.parse_clause_from_subquery();
+            }
+            return self.parse_clause_from_values();
+        }
+        // An unnest() call is told apart from a table named unnest by the
+        // parenthesis following the (peeked) name.
+        if self.peek()? == Some(Token::Ident("unnest".into()))
+            && self.lexer.remaining().trim_start().starts_with('(')
+        {
+            return self.parse_clause_from_unnest();
+        }
         self.parse_clause_from_table()
     }
 
+    // Parses a from clause subquery, which must have an alias
+    fn parse_clause_from_subquery(&mut self) -> Result<ast::FromItem> {
+        self.next_expect(Some(Token::OpenParen))?;
+        let query = Box::new(self.parse_statement_select()?);
+        self.next_expect(Some(Token::CloseParen))?;
+        self.next_if_token(Keyword::As.into());
+        let alias = match self.peek()? {
+            Some(Token::Ident(_)) => self.next_ident()?,
+            _ => return Err(Error::Parse("Subquery in FROM must have an alias".into())),
+        };
+        let mut columns = Vec::new();
+        if self.peek()? == Some(Token::OpenParen) {
+            columns = self.parse_ddl_column_list()?;
+        }
+        Ok(ast::FromItem::Subquery { query, alias, columns })
+    }
+
+    // Parses a from clause unnest() call, with an optional alias and column name
+    fn parse_clause_from_unnest(&mut self) -> Result<ast::FromItem> {
+        let alias = self.next_ident()?;
+        self.next_expect(Some(Token::OpenParen))?;
+        let expression = self.parse_expression(0)?;
+        self.next_expect(Some(Token::CloseParen))?;
+        let alias = if self.next_if_token(Keyword::As.into()).is_some() {
+            self.next_ident()?
+        } else if let Some(Token::Ident(_)) = self.peek()? {
+            self.next_ident()?
+        } else {
+            alias
+        };
+        let column = match self.peek()? {
+            Some(Token::OpenParen) => match self.parse_ddl_column_list()?.as_slice() {
+                [column] => column.clone(),
+                _ => return Err(Error::Parse("unnest() yields a single column".into())),
+            },
+            _ => alias.clone(),
+        };
+        Ok(ast::FromItem::Unnest { expression, alias, column })
+    }
+
+    // Parses a from clause VALUES list, which must have an alias
+    fn parse_clause_from_values(&mut self) -> Result<ast::FromItem> {
+        self.next_expect(Some(Token::OpenParen))?;
+        let rows = self.parse_clause_values()?;
+        self.next_expect(Some(Token::CloseParen))?;
+        self.next_if_token(Keyword::As.into());
+        let alias = match self.peek()? {
+            Some(Token::Ident(_)) => self.next_ident()?,
+            _ => return Err(Error::Parse("VALUES in FROM must have an alias".into())),
+        };
+        let mut columns = Vec::new();
+        if self.peek()? == Some(Token::OpenParen) {
+            columns = self.parse_ddl_column_list()?;
+        }
+        Ok(ast::FromItem::Values { rows, alias, columns })
+    }
+
     // Parses a from clause table
     fn parse_clause_from_table(&mut self) -> Result<ast::FromItem> {
-        let name = self.next_ident()?;
-        let alias = if self.next_if_token(Keyword::As.into()).is_some() {
+        let name = self.next_name()?;
+        let alias = if self.peek_as_of()? {
+            None
+        } else if self.next_if_token(Keyword::As.into()).is_some() {
             Some(self.next_ident()?)
         } else if let Some(Token::Ident(_)) = self.peek()? {
             Some(self.next_ident()?)
@@ -432,10 +1643,12 @@ impl<'a> Parser<'a> {
     }
 
     /// Parses a group by clause
-    fn parse_clause_group_by(&mut self) -> Result<Vec<ast::Expression>> {
+    fn parse_clause_group_by(
+        &mut self,
+    ) -> Result<(Vec<ast::Expression>, Option<ast::GroupingSets>)> {
         let mut exprs = Vec::new();
         if self.next_if_token(Keyword::Group.into()).is_none() {
-            return Ok(exprs);
+            return Ok((exprs, None));
         }
         self.next_expect(Some(Keyword::By.into()))?;
         loop {
@@ -444,7 +1657,28 @@ impl<'a> Parser<'a> {
                 break;
             }
         }
-        Ok(exprs)
+        // ROLLUP and CUBE aren't reserved keywords, so they're parsed as function calls.
+        if let [ast::Expression::Function(name, args)] = exprs.as_mut_slice() {
+            let sets = match name.as_str() {
+                "rollup" => Some(ast::GroupingSets::Rollup),
+                "cube" => Some(ast::GroupingSets::Cube),
+                _ => None,
+            };
+            if sets.is_some() {
+                return Ok((std::mem::take(args), sets));
+            }
+        }
+        for expr in &exprs {
+            if let ast::Expression::Function(name, _) = expr {
+                if name == "rollup" || name == "cube" {
+                    return Err(Error::Parse(format!(
+                        "{} must be the only GROUP BY expression",
+                        name.to_uppercase()
+                    )));
+                }
+            }
+        }
+        Ok((exprs, None))
     }
 
     /// Parses a HAVING clause
@@ -456,23 +1690,38 @@ impl<'a> Parser<'a> {
     }
 
     /// Parses an order clause
-    fn parse_clause_order(&mut self) -> Result<Vec<(ast::Expression, ast::Order)>> {
+    fn parse_clause_order(
+        &mut self,
+    ) -> Result<Vec<(ast::Expression, ast::Order, Option<ast::NullOrder>)>> {
         if self.next_if_token(Keyword::Order.into()).is_none() {
             return Ok(Vec::new());
         }
         self.next_expect(Some(Keyword::By.into()))?;
         let mut orders = Vec::new();
         loop {
-            orders.push((
-                self.parse_expression(0)?,
-                if self.next_if_token(Keyword::Asc.into()).is_some() {
-                    ast::Order::Ascending
-                } else if self.next_if_token(Keyword::Desc.into()).is_some() {
-                    ast::Order::Descending
-                } else {
-                    ast::Order::Ascending
+            let expr = self.parse_expression(0)?;
+            let order = if self.next_if_token(Keyword::Asc.into()).is_some() {
+                ast::Order::Ascending
+            } else if self.next_if_token(Keyword::Desc.into()).is_some() {
+                ast::Order::Descending
+            } else {
+                ast::Order::Ascending
+            };
+            // NULLS, FIRST and LAST aren't reserved keywords, to allow them as names.
+            let nulls = match self.next_if_ident(&["nulls"]) {
+                Some(_) => match self.next()? {
+                    Token::Ident(ident) if ident == "first" => Some(ast::NullOrder::First),
+                    Token::Ident(ident) if ident == "last" => Some(ast::NullOrder::Last),
+                    token => {
+                        return Err(Error::Parse(format!(
+                            "Unexpected token {}, wanted FIRST or LAST",
+                            token
+                        )))
+                    }
                 },
-            ));
+                None => None,
+            };
+            orders.push((expr, order, nulls));
             if self.next_if_token(Token::Comma).is_none() {
                 break;
             }
@@ -480,19 +1729,107 @@ impl<'a> Parser<'a> {
         Ok(orders)
     }
 
-    /// Parses a select clause
+    /// Parses LIMIT, OFFSET and FETCH clauses, returning the limit and
+    /// offset. FETCH is the standard form of LIMIT, and can only be given
+    /// after OFFSET:
+    ///
+    /// [ LIMIT count ] [ OFFSET start [ ROW | ROWS ] ]
+    /// [ FETCH { FIRST | NEXT } [ count ] { ROW | ROWS } { ONLY | WITH TIES } ]
+    ///
+    /// The FIRST, NEXT, ROW, ROWS and TIES words aren't reserved keywords.
+    fn parse_clause_limit(&mut self) -> Result<(Option<ast::Limit>, Option<ast::Expression>)> {
+        let mut limit = match self.next_if_token(Keyword::Limit.into()) {
+            Some(_) => Some(ast::Limit { count: self.parse_expression(0)?, with_ties: false }),
+            None => None,
+        };
+        let offset = match self.next_if_token(Keyword::Offset.into()) {
+            Some(_) => {
+                let offset = self.parse_expression(0)?;
+                self.next_if_ident(&["row", "rows"]);
+                Some(offset)
+            }
+            None => None,
+        };
+        if self.next_if_token(Keyword::Fetch.into()).is_some() {
+            if limit.is_some() {
+                return Err(Error::Parse("Can't use both LIMIT and FETCH".into()));
+            }
+            if self.next_if_ident(&["first", "next"]).is_none() {
+                return Err(self.unexpected("FIRST or NEXT"));
+            }
+            let count = match self.next_if_ident(&["row", "rows"]) {
+                Some(_) => ast::Literal::Integer(1).into(),
+                None => {
+                    let count = self.parse_expression(0)?;
+                    if self.next_if_ident(&["row", "rows"]).is_none() {
+                        return Err(self.unexpected("ROW or ROWS"));
+                    }
+                    count
+                }
+            };
+            let with_ties = match self.next()? {
+                Token::Keyword(Keyword::Only) => false,
+                Token::Keyword(Keyword::With) if self.next_if_ident(&["ties"]).is_some() => true,
+                token => {
+                    return Err(Error::Parse(format!(
+                        "Unexpected token {}, wanted ONLY or WITH TIES",
+                        token
+                    )))
+                }
+            };
+            limit = Some(ast::Limit { count, with_ties });
+        }
+        Ok((limit, offset))
+    }
+
+    /// Grabs the next token if it's an identifier in the given list of
+    /// (lowercase) names, for words that aren't reserved keywords.
+    fn next_if_ident(&mut self, names: &[&str]) -> Option<String> {
+        match self.next_if(|t| matches!(t, Token::Ident(i) if names.contains(&i.as_str())))? {
+            Token::Ident(ident) => Some(ident),
+            _ => None,
+        }
+    }
+
+    /// Returns an error for an unexpected next token, wanting the given tokens.
+    fn unexpected(&mut self, wanted: &str) -> Error {
+        match self.next() {
+            Ok(token) => Error::Parse(format!("Unexpected token {}, wanted {}", token, wanted)),
+            Err(err) => err,
+        }
+    }
+
+    /// Parses a DISTINCT [ON] clause, following SELECT. Returns whether plain
+    /// DISTINCT was given, and any DISTINCT ON expressions.
+    fn parse_clause_distinct(&mut self) -> Result<(bool, Vec<ast::Expression>)> {
+        let mut distinct_on = Vec::new();
+        if self.next_if_token(Keyword::Distinct.into()).is_none() {
+            return Ok((false, distinct_on));
+        }
+        if self.next_if_token(Keyword::On.into()).is_none() {
+            return Ok((true, distinct_on));
+        }
+        self.next_expect(Some(Token::OpenParen))?;
+        loop {
+            distinct_on.push(self.parse_expression(0)?);
+            if self.next_if_token(Token::Comma).is_none() {
+                break;
+            }
+        }
+        self.next_expect(Some(Token::CloseParen))?;
+        Ok((false, distinct_on))
+    }
+
+    /// Parses the expressions of a select clause, following SELECT
     fn parse_clause_select(&mut self) -> Result<Vec<(ast::Expression, Option<String>)>> {
         let mut select = Vec::new();
-        if self.next_if_token(Keyword::Select.into()).is_none() {
-            return Ok(select);
-        }
         loop {
             if self.next_if_token(Token::Asterisk).is_some() && select.is_empty() {
                 break;
             }
             let expr = self.parse_expression(0)?;
             let label = match self.peek()? {
-                Some(Token::Keyword(Keyword::As)) => {
+                Some(Token::Keyword(Keyword::As)) if !self.peek_as_of()? => {
                     self.next()?;
                     Some(self.next_ident()?)
                 }
@@ -507,6 +1844,78 @@ impl<'a> Parser<'a> {
         Ok(select)
     }
 
+    /// Parses a RETURNING clause, if any
+    fn parse_clause_returning(&mut self) -> Result<Option<ast::Returning>> {
+        if self.next_if_token(Keyword::Returning.into()).is_none() {
+            return Ok(None);
+        }
+        Ok(Some(self.parse_clause_select()?))
+    }
+
+    /// Parses a SET clause, e.g. for UPDATE
+    fn parse_clause_set(&mut self) -> Result<BTreeMap<String, ast::Expression>> {
+        self.next_expect(Some(Keyword::Set.into()))?;
+        let mut set = BTreeMap::new();
+        loop {
+            let column = self.next_ident()?;
+            self.next_expect(Some(Token::Equal))?;
+            let expr = self.parse_expression(0)?;
+            if set.contains_key(&column) {
+                return Err(Error::Value(format!("Duplicate values given for column {}", column)));
+            }
+            set.insert(column, expr);
+            if self.next_if_token(Token::Comma).is_none() {
+                break;
+            }
+        }
+        Ok(set)
+    }
+
+    /// Parses an INSERT ... ON CONFLICT clause. The ON keyword has already
+    /// been consumed.
+    fn parse_clause_on_conflict(&mut self) -> Result<ast::OnConflict> {
+        self.next_expect(Some(Keyword::Conflict.into()))?;
+        let mut columns = Vec::new();
+        if self.peek()? == Some(Token::OpenParen) {
+            columns = self.parse_ddl_column_list()?;
+        }
+        self.next_expect(Some(Keyword::Do.into()))?;
+        match self.next()? {
+            Token::Keyword(Keyword::Nothing) => {
+                Ok(ast::OnConflict { columns, update: None, r#where: None })
+            }
+            Token::Keyword(Keyword::Update) => Ok(ast::OnConflict {
+                columns,
+                update: Some(self.parse_clause_set()?),
+                r#where: self.parse_clause_where()?,
+            }),
+            token => Err(Error::Parse(format!("Unexpected token {}", token))),
+        }
+    }
+
+    /// Parses a VALUES list of parenthesized expression rows
+    fn parse_clause_values(&mut self) -> Result<Vec<Vec<ast::Expression>>> {
+        self.next_expect(Some(Keyword::Values.into()))?;
+        let mut values = Vec::new();
+        loop {
+            self.next_expect(Some(Token::OpenParen))?;
+            let mut exprs = Vec::new();
+            loop {
+                exprs.push(self.parse_expression(0)?);
+                match self.next()? {
+                    Token::CloseParen => break,
+                    Token::Comma => {}
+                    token => return Err(Error::Parse(format!("Unexpected token {}", token))),
+                }
+            }
+            values.push(exprs);
+            if self.next_if_token(Token::Comma).is_none() {
+                break;
+            }
+        }
+        Ok(values)
+    }
+
     /// Parses a WHERE clause
     fn parse_clause_where(&mut self) -> Result<Option<ast::Expression>> {
         if self.next_if_token(Keyword::Where.into()).is_none() {
@@ -523,11 +1932,16 @@ impl<'a> Parser<'a> {
         } else {
             self.parse_expression_atom()?
         };
-        while let Some(postfix) = self.next_if_operator::<PostfixOperator>(min_prec)? {
-            lhs = postfix.build(lhs)
-        }
-        while let Some(infix) = self.next_if_operator::<InfixOperator>(min_prec)? {
-            lhs = infix.build(lhs, self.parse_expression(infix.prec() + infix.assoc())?)
+        // Postfix operators may bind looser than infix operators (e.g. a + b IN (c)), so we
+        // alternate between them until neither applies.
+        loop {
+            if let Some(postfix) = self.next_if_operator::<PostfixOperator>(min_prec)? {
+                lhs = postfix.build(lhs)
+            } else if let Some(infix) = self.next_if_operator::<InfixOperator>(min_prec)? {
+                lhs = infix.build(lhs, self.parse_expression(infix.prec() + infix.assoc())?)
+            } else {
+                break;
+            }
         }
         Ok(lhs)
     }
@@ -537,19 +1951,7 @@ impl<'a> Parser<'a> {
         Ok(match self.next()? {
             Token::Ident(i) => {
                 if self.next_if_token(Token::OpenParen).is_some() {
-                    let mut args = Vec::new();
-                    while self.next_if_token(Token::CloseParen).is_none() {
-                        if !args.is_empty() {
-                            self.next_expect(Some(Token::Comma))?;
-                        }
-                        if i == "count" && self.next_if_token(Token::Asterisk).is_some() {
-                            // FIXME Ugly hack to handle COUNT(*)
-                            args.push(ast::Expression::Literal(ast::Literal::Boolean(true)));
-                        } else {
-                            args.push(self.parse_expression(0)?);
-                        }
-                    }
-                    ast::Expression::Function(i, args)
+                    self.parse_expression_function(i)?
                 } else {
                     let mut relation = None;
                     let mut field = i;
@@ -560,6 +1962,69 @@ impl<'a> Parser<'a> {
                     ast::Expression::Field(relation, field)
                 }
             }
+            // LEFT and RIGHT are keywords due to joins, but also function names.
+            Token::Keyword(Keyword::Left) if self.next_if_token(Token::OpenParen).is_some() => {
+                self.parse_expression_function("left".into())?
+            }
+            Token::Keyword(Keyword::Right) if self.next_if_token(Token::OpenParen).is_some() => {
+                self.parse_expression_function("right".into())?
+            }
+            Token::Keyword(Keyword::Cast) => {
+                self.next_expect(Some(Token::OpenParen))?;
+                let expr = self.parse_expression(0)?;
+                self.next_expect(Some(Keyword::As.into()))?;
+                let datatype = self.parse_datatype()?;
+                self.next_expect(Some(Token::CloseParen))?;
+                ast::Operation::Cast(Box::new(expr), datatype).into()
+            }
+            // Typed literals, e.g. DATE '2024-01-31', are casts of string literals.
+            Token::Keyword(
+                k @ (Keyword::Bytea
+                | Keyword::Date
+                | Keyword::Decimal
+                | Keyword::Interval
+                | Keyword::Json
+                | Keyword::Numeric
+                | Keyword::Time
+                | Keyword::Timestamp
+                | Keyword::Uuid),
+            ) => {
+                let datatype = match k {
+                    Keyword::Bytea => DataType::Bytes,
+                    Keyword::Date => DataType::Date,
+                    Keyword::Decimal | Keyword::Numeric => DataType::Decimal,
+                    Keyword::Interval => DataType::Interval,
+                    Keyword::Json => DataType::Json,
+                    Keyword::Time => DataType::Time,
+                    Keyword::Timestamp => DataType::Timestamp,
+                    _ => DataType::Uuid,
+                };
+                match self.next()? {
+                    Token::String(s) => {
+                        ast::Operation::Cast(Box::new(ast::Literal::String(s).into()), datatype)
+                            .into()
+                    }
+                    Token::Parameter(i) => {
+                        ast::Operation::Cast(Box::new(ast::Expression::Parameter(i)), datatype)
+                            .into()
+                    }
+                    t => return Err(Error::Parse(format!("Expected string literal, found {}", t))),
+                }
+            }
+            Token::Keyword(Keyword::CurrentTimestamp) => {
+                ast::Expression::Function("now".into(), Vec::new())
+            }
+            Token::Keyword(Keyword::Array) => {
+                self.next_expect(Some(Token::OpenBracket))?;
+                let mut elements = Vec::new();
+                while self.next_if_token(Token::CloseBracket).is_none() {
+                    if !elements.is_empty() {
+                        self.next_expect(Some(Token::Comma))?;
+                    }
+                    elements.push(self.parse_expression(0)?);
+                }
+                ast::Expression::Function("array".into(), elements)
+            }
             Token::Number(n) => {
                 if n.chars().all(|c| c.is_ascii_digit()) {
                     ast::Literal::Integer(n.parse()?).into()
@@ -567,20 +2032,109 @@ impl<'a> Parser<'a> {
                     ast::Literal::Float(n.parse()?).into()
                 }
             }
+            Token::OpenParen
+                if matches!(
+                    self.peek()?,
+                    Some(Token::Keyword(Keyword::Select | Keyword::With))
+                ) =>
+            {
+                let query = self.parse_statement_select()?;
+                self.next_expect(Some(Token::CloseParen))?;
+                ast::Expression::Subquery(Box::new(query))
+            }
+            Token::Keyword(Keyword::Exists) => {
+                self.next_expect(Some(Token::OpenParen))?;
+                let query = self.parse_statement_select()?;
+                self.next_expect(Some(Token::CloseParen))?;
+                ast::Expression::Exists(Box::new(query))
+            }
             Token::OpenParen => {
                 let expr = self.parse_expression(0)?;
                 self.next_expect(Some(Token::CloseParen))?;
                 expr
             }
             Token::String(s) => ast::Literal::String(s).into(),
+            Token::Parameter(i) => ast::Expression::Parameter(i),
+            Token::HexString(s) => ast::Literal::Bytes(
+                hex::decode(&s).map_err(|_| Error::Parse(format!("Invalid hex string {}", s)))?,
+            )
+            .into(),
             Token::Keyword(Keyword::False) => ast::Literal::Boolean(false).into(),
-            Token::Keyword(Keyword::Infinity) => ast::Literal::Float(std::f64::INFINITY).into(),
-            Token::Keyword(Keyword::NaN) => ast::Literal::Float(std::f64::NAN).into(),
+            Token::Keyword(Keyword::Infinity) => ast::Literal::Float(f64::INFINITY).into(),
+            Token::Keyword(Keyword::NaN) => ast::Literal::Float(f64::NAN).into(),
             Token::Keyword(Keyword::Null) => ast::Literal::Null.into(),
             Token::Keyword(Keyword::True) => ast::Literal::Boolean(true).into(),
             t => return Err(Error::Parse(format!("Expected expression atom, found {}", t))),
         })
     }
+
+    /// Parses the arguments of a function call, after the opening parenthesis. In addition to
+    /// comma-separated arguments, this handles the SQL syntax POSITION(substring IN string),
+    /// SUBSTRING(string FROM start [FOR length]) and EXTRACT(field FROM value), as well as
+    /// DISTINCT before and an ORDER BY clause after the arguments of aggregate functions.
+    /// MATCH (text) AGAINST (query) is parsed as a full-text relevance operation.
+    fn parse_expression_function(&mut self, name: String) -> Result<ast::Expression> {
+        if name == "match" {
+            let text = self.parse_expression(0)?;
+            self.next_expect(Some(Token::CloseParen))?;
+            self.next_expect(Some(Token::Ident("against".into())))?;
+            self.next_expect(Some(Token::OpenParen))?;
+            let query = self.parse_expression(0)?;
+            self.next_expect(Some(Token::CloseParen))?;
+            return Ok(ast::Operation::Relevance(Box::new(text), Box::new(query)).into());
+        }
+        let mut args = Vec::new();
+        if name == "extract" && matches!(self.peek()?, Some(Token::Ident(_))) {
+            args.push(ast::Literal::String(self.next_ident()?).into());
+            self.next_expect(Some(Keyword::From.into()))?;
+            args.push(self.parse_expression(0)?);
+            self.next_expect(Some(Token::CloseParen))?;
+            return Ok(ast::Expression::Function(name, args));
+        }
+        if (name == "position" || name == "substring") && self.peek()? != Some(Token::CloseParen) {
+            // Parse POSITION's first argument above IN precedence, so it isn't taken as an IN
+            // operator.
+            args.push(self.parse_expression(if name == "position" { 5 } else { 0 })?);
+            if name == "position" && self.next_if_token(Keyword::In.into()).is_some() {
+                args.push(self.parse_expression(0)?);
+                self.next_expect(Some(Token::CloseParen))?;
+                return Ok(ast::Expression::Function(name, args));
+            }
+            if name == "substring" && self.next_if_token(Keyword::From.into()).is_some() {
+                args.push(self.parse_expression(0)?);
+                if self.next_if_token(Keyword::For.into()).is_some() {
+                    args.push(self.parse_expression(0)?);
+                }
+                self.next_expect(Some(Token::CloseParen))?;
+                return Ok(ast::Expression::Function(name, args));
+            }
+        }
+        let distinct = self.next_if_token(Keyword::Distinct.into()).is_some();
+        while self.next_if_token(Token::CloseParen).is_none() {
+            // Aggregate functions can take an ORDER BY clause after the arguments.
+            if !args.is_empty() && self.peek()? == Some(Keyword::Order.into()) {
+                let order = self.parse_clause_order()?;
+                self.next_expect(Some(Token::CloseParen))?;
+                return Ok(match distinct {
+                    true => ast::Expression::DistinctFunction(name, args, order),
+                    false => ast::Expression::OrderedFunction(name, args, order),
+                });
+            }
+            if !args.is_empty() {
+                self.next_expect(Some(Token::Comma))?;
+            }
+            if name == "count" && !distinct && self.next_if_token(Token::Asterisk).is_some() {
+                // FIXME Ugly hack to handle COUNT(*)
+                args.push(ast::Expression::Literal(ast::Literal::Boolean(true)));
+            } else {
+                args.push(self.parse_expression(0)?);
+            }
+        }
+        Ok(match distinct {
+            true => ast::Expression::DistinctFunction(name, args, Vec::new()),
+            false => ast::Expression::Function(name, args),
+        })
+    }
 }
 
 /// An operator trait, to help with parsing of operators
@@ -641,14 +2195,19 @@ impl Operator for PrefixOperator {
 enum InfixOperator {
     Add,
     And,
+    Concat,
     Divide,
     Equal,
+    EqualAny,
     Exponentiate,
     GreaterThan,
     GreaterThanOrEqual,
+    JsonExtract,
+    JsonExtractText,
     LessThan,
     LessThanOrEqual,
     Like,
+    Match,
     Modulo,
     Multiply,
     NotEqual,
@@ -662,14 +2221,19 @@ impl InfixOperator {
         match self {
             Self::Add => ast::Operation::Add(lhs, rhs),
             Self::And => ast::Operation::And(lhs, rhs),
+            Self::Concat => ast::Operation::Concat(lhs, rhs),
             Self::Divide => ast::Operation::Divide(lhs, rhs),
             Self::Equal => ast::Operation::Equal(lhs, rhs),
+            Self::EqualAny => ast::Operation::Any(lhs, rhs),
             Self::Exponentiate => ast::Operation::Exponentiate(lhs, rhs),
             Self::GreaterThan => ast::Operation::GreaterThan(lhs, rhs),
             Self::GreaterThanOrEqual => ast::Operation::GreaterThanOrEqual(lhs, rhs),
+            Self::JsonExtract => ast::Operation::JsonExtract(lhs, rhs),
+            Self::JsonExtractText => ast::Operation::JsonExtractText(lhs, rhs),
             Self::LessThan => ast::Operation::LessThan(lhs, rhs),
             Self::LessThanOrEqual => ast::Operation::LessThanOrEqual(lhs, rhs),
             Self::Like => ast::Operation::Like(lhs, rhs),
+            Self::Match => ast::Operation::Match(lhs, rhs),
             Self::Modulo => ast::Operation::Modulo(lhs, rhs),
             Self::Multiply => ast::Operation::Multiply(lhs, rhs),
             Self::NotEqual => ast::Operation::NotEqual(lhs, rhs),
@@ -683,8 +2247,12 @@ impl InfixOperator {
 impl Operator for InfixOperator {
     fn from(token: &Token) -> Option<Self> {
         Some(match token {
+            Token::Arrow => Self::JsonExtract,
             Token::Asterisk => Self::Multiply,
             Token::Caret => Self::Exponentiate,
+            Token::DoubleArrow => Self::JsonExtractText,
+            Token::DoubleAt => Self::Match,
+            Token::DoublePipe => Self::Concat,
             Token::Equal => Self::Equal,
             Token::GreaterThan => Self::GreaterThan,
             Token::GreaterThanOrEqual => Self::GreaterThanOrEqual,
@@ -703,7 +2271,13 @@ impl Operator for InfixOperator {
         })
     }
 
-    fn augment(self, _parser: &mut Parser) -> Result<Self> {
+    fn augment(self, parser: &mut Parser) -> Result<Self> {
+        // = ANY (array) compares with the array elements.
+        if let Self::Equal = self {
+            if parser.next_if_token(Keyword::Any.into()).is_some() {
+                return Ok(Self::EqualAny);
+            }
+        }
         Ok(self)
     }
 
@@ -718,20 +2292,34 @@ impl Operator for InfixOperator {
         match self {
             Self::Or => 1,
             Self::And => 2,
-            Self::Equal | Self::NotEqual | Self::Like => 3,
+            Self::Equal | Self::EqualAny | Self::NotEqual | Self::Like | Self::Match => 3,
             Self::GreaterThan
             | Self::GreaterThanOrEqual
             | Self::LessThan
             | Self::LessThanOrEqual => 4,
-            Self::Add | Self::Subtract => 5,
+            Self::Add | Self::Concat | Self::Subtract => 5,
             Self::Multiply | Self::Divide | Self::Modulo => 6,
             Self::Exponentiate => 7,
+            // Binds tighter than other infix operators, e.g. doc->>'a' || 'b'.
+            Self::JsonExtract | Self::JsonExtractText => 8,
         }
     }
 }
 
 enum PostfixOperator {
+    Between {
+        not: bool,
+        low: ast::Expression,
+        high: ast::Expression,
+    },
+    Cast(Option<DataType>),
+    Element(ast::Expression),
     Factorial,
+    In {
+        not: bool,
+        list: Vec<ast::Expression>,
+        query: Option<Box<ast::Statement>>,
+    },
     // FIXME Compiler bug? Why is this considered dead code?
     #[allow(dead_code)]
     IsNull {
@@ -740,31 +2328,95 @@ enum PostfixOperator {
 }
 
 impl PostfixOperator {
-    fn build(&self, lhs: ast::Expression) -> ast::Expression {
+    fn build(self, lhs: ast::Expression) -> ast::Expression {
         let lhs = Box::new(lhs);
-        match self {
-            Self::IsNull { not } => match not {
-                true => ast::Operation::Not(Box::new(ast::Operation::IsNull(lhs).into())),
-                false => ast::Operation::IsNull(lhs),
-            },
-            Self::Factorial => ast::Operation::Factorial(lhs),
+        let (not, operation) = match self {
+            Self::Between { not, low, high } => {
+                (not, ast::Operation::Between(lhs, Box::new(low), Box::new(high)))
+            }
+            Self::Cast(datatype) => {
+                (false, ast::Operation::Cast(lhs, datatype.expect("cast without datatype")))
+            }
+            Self::Element(index) => (false, ast::Operation::ArrayElement(lhs, Box::new(index))),
+            Self::Factorial => (false, ast::Operation::Factorial(lhs)),
+            Self::In { not, query: Some(query), .. } => {
+                (not, ast::Operation::InSubquery(lhs, query))
+            }
+            Self::In { not, list, query: None } => (not, ast::Operation::In(lhs, list)),
+            Self::IsNull { not } => (not, ast::Operation::IsNull(lhs)),
+        };
+        match not {
+            true => ast::Operation::Not(Box::new(operation.into())).into(),
+            false => operation.into(),
         }
-        .into()
     }
 }
 
 impl Operator for PostfixOperator {
     fn from(token: &Token) -> Option<Self> {
         match token {
+            Token::DoubleColon => Some(Self::Cast(None)),
             Token::Exclamation => Some(Self::Factorial),
+            Token::OpenBracket => Some(Self::Element(ast::Literal::Null.into())),
+            Token::Keyword(Keyword::Between) => Some(Self::Between {
+                not: false,
+                low: ast::Literal::Null.into(),
+                high: ast::Literal::Null.into(),
+            }),
+            Token::Keyword(Keyword::In) => {
+                Some(Self::In { not: false, list: Vec::new(), query: None })
+            }
             Token::Keyword(Keyword::Is) => Some(Self::IsNull { not: false }),
+            // NOT BETWEEN or NOT IN, resolved by augment().
+            Token::Keyword(Keyword::Not) => {
+                Some(Self::In { not: true, list: Vec::new(), query: None })
+            }
             _ => None,
         }
     }
 
     fn augment(mut self, parser: &mut Parser) -> Result<Self> {
-        #[allow(clippy::single_match)]
+        if let Self::In { not: true, .. } = self {
+            match parser.next()? {
+                Token::Keyword(Keyword::Between) => {
+                    self = Self::Between {
+                        not: true,
+                        low: ast::Literal::Null.into(),
+                        high: ast::Literal::Null.into(),
+                    }
+                }
+                Token::Keyword(Keyword::In) => {}
+                token => return Err(Error::Parse(format!("Unexpected token {}", token))),
+            }
+        }
         match &mut self {
+            Self::Between { ref mut low, ref mut high, .. } => {
+                // The bounds bind tighter than comparisons, so that AND terminates the low bound.
+                *low = parser.parse_expression(5)?;
+                parser.next_expect(Some(Keyword::And.into()))?;
+                *high = parser.parse_expression(5)?;
+            }
+            Self::Cast(ref mut datatype) => *datatype = Some(parser.parse_datatype()?),
+            Self::Element(ref mut index) => {
+                *index = parser.parse_expression(0)?;
+                parser.next_expect(Some(Token::CloseBracket))?;
+            }
+            Self::In { ref mut list, ref mut query, .. } => {
+                parser.next_expect(Some(Token::OpenParen))?;
+                if let Some(Token::Keyword(Keyword::Select | Keyword::With)) = parser.peek()? {
+                    *query = Some(Box::new(parser.parse_statement_select()?));
+                    parser.next_expect(Some(Token::CloseParen))?;
+                    return Ok(self);
+                }
+                loop {
+                    list.push(parser.parse_expression(0)?);
+                    match parser.next()? {
+                        Token::CloseParen => break,
+                        Token::Comma => {}
+                        token => return Err(Error::Parse(format!("Unexpected token {}", token))),
+                    }
+                }
+            }
             Self::IsNull { ref mut not } => {
                 if parser.next_if_token(Keyword::Not.into()).is_some() {
                     *not = true
@@ -781,8 +2433,53 @@ impl Operator for PostfixOperator {
     }
 
     fn prec(&self) -> u8 {
-        8
+        match self {
+            // Binds tighter than prefix operators, e.g. -1::STRING is -(1::STRING) like Postgres.
+            Self::Cast(_) | Self::Element(_) => 10,
+            Self::Factorial | Self::IsNull { .. } => 8,
+            Self::Between { .. } | Self::In { .. } => 4,
+        }
+    }
+}
+
+/// Redacts password string literals in a query, i.e. those following a
+/// PASSWORD identifier as in CREATE USER and ALTER USER, such that the query
+/// can be listed or logged. Input that fails to lex is elided, since it may
+/// contain an unterminated password.
+pub fn redact(query: &str) -> String {
+    let mut redacted = String::with_capacity(query.len());
+    let mut lexer = Lexer::new(query);
+    let mut copied = 0;
+    let mut password = false;
+    while let Some(result) = lexer.next() {
+        let span = lexer.span();
+        match result {
+            Ok(Token::String(_)) if password => {
+                redacted.push_str(&query[copied..span.start]);
+                redacted.push_str("'***'");
+                copied = span.end;
+                password = false;
+            }
+            Ok(token) => password = matches!(token, Token::Ident(ident) if ident == "password"),
+            Err(_) => {
+                redacted.push_str(&query[copied..span.start]);
+                redacted.push_str("...");
+                return redacted;
+            }
+        }
     }
+    redacted.push_str(&query[copied..]);
+    redacted
+}
+
+// Formats a string as a quoted string literal
+pub(super) fn format_string(string: &str) -> String {
+    format!("'{}'", string.replace('\'', "''"))
+}
+
+// Formats a possibly qualified name, e.g. db.table, by quoting its parts as appropriate
+pub(super) fn format_name(name: &str) -> String {
+    name.split('.').map(format_ident).collect::<Vec<_>>().join(".")
 }
 
 // Formats an identifier by quoting it as appropriate
@@ -796,3 +2493,26 @@ pub(super) fn format_ident(ident: &str) -> String {
         format!("\"{}\"", ident.replace('\"', "\"\""))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn redact() {
+        assert_eq!(
+            super::redact("CREATE USER alice WITH PASSWORD 'it''s secret'"),
+            "CREATE USER alice WITH PASSWORD '***'"
+        );
+        assert_eq!(
+            super::redact("ALTER USER alice password 'secret'; SELECT 'a', password 'b'"),
+            "ALTER USER alice password '***'; SELECT 'a', password '***'"
+        );
+        assert_eq!(
+            super::redact("ALTER USER alice PASSWORD NULL"),
+            "ALTER USER alice PASSWORD NULL"
+        );
+        assert_eq!(
+            super::redact("CREATE USER alice PASSWORD 'secr"),
+            "CREATE USER alice PASSWORD ..."
+        );
+    }
+}
@@ -0,0 +1,485 @@
+//! Cost estimation, using table statistics collected by ANALYZE. Row counts
+//! are only estimated when all tables read by a node have been analyzed, such
+//! that the optimizer falls back to fixed heuristics otherwise.
+use super::super::schema::{Catalog, ColumnStatistics, Statistics, Table};
+use super::super::types::{Expression, Value};
+use super::Node;
+use crate::error::Result;
+
+use serde_derive::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::ops::Bound;
+
+/// The cost of reading a row via a secondary index, relative to reading a
+/// row during a table scan. Each lookup reads the index entry and then the
+/// row by primary key, rather than reading rows sequentially.
+pub const INDEX_LOOKUP_COST: f64 = 4.0;
+
+/// The cost of inserting a row into a hash join's hash table, relative to
+/// probing it or evaluating a nested loop join predicate.
+pub const HASH_BUILD_COST: f64 = 2.0;
+
+/// The selectivity of equality predicates without column statistics.
+const DEFAULT_EQUALITY: f64 = 0.1;
+
+/// The selectivity of other predicates without column statistics.
+const DEFAULT_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/// The selectivity of ranges bounded on both ends without column statistics,
+/// as for a pair of inequality predicates.
+const DEFAULT_RANGE: f64 = DEFAULT_SELECTIVITY * DEFAULT_SELECTIVITY;
+
+/// The estimated row count and cost of a plan node, if known, as shown by
+/// EXPLAIN VERBOSE.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Estimate {
+    /// The number of rows emitted by the node.
+    pub rows: Option<f64>,
+    /// The cost of the node including its children, see Estimator::cost().
+    pub cost: Option<f64>,
+}
+
+impl Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self.rows.map_or("?".to_string(), |r| format!("{:.0}", r));
+        let cost = self.cost.map_or("?".to_string(), |c| format!("{:.1}", c));
+        write!(f, "estimated rows={} cost={}", rows, cost)
+    }
+}
+
+/// Estimates row counts of plan nodes.
+pub struct Estimator<'a, C: Catalog> {
+    catalog: &'a C,
+}
+
+impl<'a, C: Catalog> Estimator<'a, C> {
+    pub fn new(catalog: &'a C) -> Self {
+        Self { catalog }
+    }
+
+    /// Returns a table's schema and statistics, if it has been analyzed.
+    pub fn statistics(&self, table: &str) -> Result<Option<(Table, Statistics)>> {
+        match self.catalog.read_statistics(table)? {
+            Some(statistics) => Ok(Some((self.catalog.must_read_table(table)?, statistics))),
+            None => Ok(None),
+        }
+    }
+
+    /// Estimates the number of rows emitted by a node, if known.
+    pub fn rows(&self, node: &Node) -> Result<Option<f64>> {
+        Ok(match node {
+            Node::Scan { table, filter, .. }
+            | Node::ParallelScan { table, filter, .. }
+            | Node::ReverseScan { table, filter, .. } => self.statistics(table)?.map(|(t, s)| {
+                let selectivity = filter.as_ref().map_or(1.0, |f| selectivity(f, Some((&t, &s))));
+                s.rows as f64 * selectivity
+            }),
+            Node::KeyLookup { keys, .. } => Some(keys.len() as f64),
+            Node::IndexLookup { table, index, values, .. } => match self.statistics(table)? {
+                Some((t, s)) => Some(lookup_rows(&s, &t.get_index(index)?.columns, values)),
+                None => None,
+            },
+            Node::IndexIntersection { table, lookups, .. } => match self.statistics(table)? {
+                Some((t, s)) => {
+                    let rows = s.rows as f64;
+                    let mut fraction = 1.0;
+                    for (index, values) in lookups {
+                        let columns = &t.get_index(index)?.columns;
+                        fraction *= lookup_rows(&s, columns, values) / rows.max(1.0);
+                    }
+                    Some(rows * fraction)
+                }
+                None => None,
+            },
+            Node::IndexOnlyScan { table, index, ranges, .. } => match self.statistics(table)? {
+                Some((t, s)) => {
+                    let columns = &t.get_index(index)?.columns;
+                    let field = t.get_column_index(&columns[0])?;
+                    let mut rows = 0.0;
+                    for range in ranges {
+                        rows += match range {
+                            (Bound::Included(a), Bound::Included(b)) if a == b => {
+                                lookup_rows(&s, columns, std::slice::from_ref(a))
+                            }
+                            (start, end) => {
+                                let first =
+                                    |b: &Bound<Vec<Value>>| b.as_ref().map(|v| v[0].clone());
+                                let range = (first(start), first(end));
+                                s.rows as f64 * range_selectivity(field, &range, Some((&t, &s)))
+                            }
+                        }
+                    }
+                    Some(rows.min(s.rows as f64))
+                }
+                None => None,
+            },
+            Node::IndexRangeScan { table, index, range, .. } => match self.statistics(table)? {
+                Some((t, s)) => {
+                    let column = &t.get_index(index)?.columns[0];
+                    let field = t.get_column_index(column)?;
+                    Some(s.rows as f64 * range_selectivity(field, range, Some((&t, &s))))
+                }
+                None => None,
+            },
+            Node::Filter { source, predicate } => {
+                let statistics = match &**source {
+                    Node::Scan { table, .. }
+                    | Node::ParallelScan { table, .. }
+                    | Node::ReverseScan { table, .. }
+                    | Node::KeyLookup { table, .. }
+                    | Node::IndexIntersection { table, .. }
+                    | Node::IndexLookup { table, .. }
+                    | Node::IndexOnlyScan { table, .. }
+                    | Node::IndexRangeScan { table, .. } => self.statistics(table)?,
+                    _ => None,
+                };
+                let selectivity = selectivity(predicate, statistics.as_ref().map(|(t, s)| (t, s)));
+                match (&**source, statistics) {
+                    // Range scans are filtered by the predicate they were
+                    // planned from, so apply it to the whole table instead.
+                    (Node::IndexRangeScan { .. }, Some((_, s))) => {
+                        Some(s.rows as f64 * selectivity)
+                    }
+                    _ => self.rows(source)?.map(|rows| rows * selectivity),
+                }
+            }
+            Node::NestedLoopJoin { left, right, predicate, outer, .. } => {
+                match (self.rows(left)?, self.rows(right)?) {
+                    (Some(l), Some(r)) => {
+                        let rows = match predicate {
+                            Some(Expression::Equal(a, b))
+                                if matches!(
+                                    (&**a, &**b),
+                                    (Expression::Field(..), Expression::Field(..))
+                                ) =>
+                            {
+                                equijoin_rows(l, r)
+                            }
+                            Some(predicate) => l * r * selectivity(predicate, None),
+                            None => l * r,
+                        };
+                        Some(if *outer { rows.max(l) } else { rows })
+                    }
+                    _ => None,
+                }
+            }
+            Node::HashJoin { left, right, outer, .. }
+            | Node::MergeJoin { left, right, outer, .. } => {
+                match (self.rows(left)?, self.rows(right)?) {
+                    (Some(l), Some(r)) if *outer => Some(equijoin_rows(l, r).max(l)),
+                    (Some(l), Some(r)) => Some(equijoin_rows(l, r)),
+                    _ => None,
+                }
+            }
+            // The right rows are estimated per left row.
+            Node::LateralJoin { left, right, predicate, outer, .. } => {
+                match (self.rows(left)?, self.rows(right)?) {
+                    (Some(l), Some(r)) => {
+                        let selectivity = predicate.as_ref().map_or(1.0, |p| selectivity(p, None));
+                        let rows = l * r * selectivity;
+                        Some(if *outer { rows.max(l) } else { rows })
+                    }
+                    _ => None,
+                }
+            }
+            // Semi joins emit at most the left rows.
+            Node::HashSemiJoin { left, right, .. } => match (self.rows(left)?, self.rows(right)?) {
+                (Some(l), Some(_)) => Some(l),
+                _ => None,
+            },
+            Node::Aggregation { source, .. }
+            | Node::Distinct { source, .. }
+            | Node::Order { source, .. }
+            | Node::Projection { source, .. } => self.rows(source)?,
+            Node::Limit { source, limit, .. } => self.rows(source)?.map(|r| r.min(*limit as f64)),
+            Node::Offset { source, offset } => {
+                self.rows(source)?.map(|r| (r - *offset as f64).max(0.0))
+            }
+            Node::SetOperation { left, right, .. } => match (self.rows(left)?, self.rows(right)?) {
+                (Some(l), Some(r)) => Some(l + r),
+                _ => None,
+            },
+            Node::Values { rows, .. } => Some(rows.len() as f64),
+            Node::Nothing => Some(0.0),
+            _ => None,
+        })
+    }
+
+    /// Estimates the cost of executing a node including its children, if
+    /// known. The unit is the cost of reading a row during a table scan, and
+    /// every row processed by a node costs the same.
+    pub fn cost(&self, node: &Node) -> Result<Option<f64>> {
+        // The cost of a node's source plus processing each of its rows.
+        let process = |source: &Node| -> Result<Option<f64>> {
+            Ok(match (self.cost(source)?, self.rows(source)?) {
+                (Some(c), Some(r)) => Some(c + r),
+                _ => None,
+            })
+        };
+        // The cost of both join inputs, given the cost of joining their rows.
+        let join = |left: &Node, right: &Node, f: &dyn Fn(f64, f64) -> f64| -> Result<_> {
+            Ok(match (self.cost(left)?, self.cost(right)?, self.rows(left)?, self.rows(right)?) {
+                (Some(lc), Some(rc), Some(l), Some(r)) => Some(lc + rc + f(l, r)),
+                _ => None,
+            })
+        };
+        Ok(match node {
+            Node::Scan { table, .. }
+            | Node::ParallelScan { table, .. }
+            | Node::ReverseScan { table, .. } => {
+                self.statistics(table)?.map(|(_, s)| s.rows as f64)
+            }
+            Node::KeyLookup { keys, .. } => Some(keys.len() as f64),
+            Node::IndexOnlyScan { .. } => self.rows(node)?,
+            Node::IndexIntersection { .. }
+            | Node::IndexLookup { .. }
+            | Node::IndexRangeScan { .. } => self.rows(node)?.map(|r| r * INDEX_LOOKUP_COST),
+            Node::Aggregation { source, .. }
+            | Node::CreateTableAs { source, .. }
+            | Node::Delete { source, .. }
+            | Node::Distinct { source, .. }
+            | Node::Filter { source, .. }
+            | Node::Limit { source, .. }
+            | Node::Offset { source, .. }
+            | Node::Projection { source, .. }
+            | Node::Update { source, .. } => process(source)?,
+            Node::Order { source, .. } => match (self.cost(source)?, self.rows(source)?) {
+                (Some(c), Some(r)) => Some(c + r * r.max(2.0).log2()),
+                _ => None,
+            },
+            Node::HashJoin { left, right, .. } | Node::HashSemiJoin { left, right, .. } => {
+                join(left, right, &|l, r| l + r * HASH_BUILD_COST)?
+            }
+            Node::MergeJoin { left, right, .. } => join(left, right, &|l, r| l + r)?,
+            Node::NestedLoopJoin { left, right, .. } => join(left, right, &|l, r| l * r)?,
+            // The right node is executed once per left row.
+            Node::LateralJoin { left, right, .. } => {
+                match (self.cost(left)?, self.cost(right)?, self.rows(left)?, self.rows(right)?) {
+                    (Some(lc), Some(rc), Some(l), Some(r)) => Some(lc + l * (rc + r)),
+                    _ => None,
+                }
+            }
+            Node::SetOperation { left, right, .. } => join(left, right, &|l, r| l + r)?,
+            Node::Insert { expressions, .. } => Some(expressions.len() as f64),
+            Node::Values { rows, .. } => Some(rows.len() as f64),
+            Node::Nothing => Some(0.0),
+            _ => None,
+        })
+    }
+
+    /// Returns the estimates for a node and its children, in pre-order.
+    pub fn estimates(&self, node: &Node) -> Result<Vec<Estimate>> {
+        let mut estimates = vec![Estimate { rows: self.rows(node)?, cost: self.cost(node)? }];
+        for child in node.children() {
+            estimates.extend(self.estimates(child)?);
+        }
+        Ok(estimates)
+    }
+
+    /// Returns the number of columns emitted by a node, if known.
+    pub fn width(&self, node: &Node) -> Result<Option<usize>> {
+        Ok(match node {
+            Node::Scan { table, .. }
+            | Node::ParallelScan { table, .. }
+            | Node::ReverseScan { table, .. }
+            | Node::KeyLookup { table, .. }
+            | Node::IndexIntersection { table, .. }
+            | Node::IndexLookup { table, .. }
+            | Node::IndexOnlyScan { table, .. }
+            | Node::IndexRangeScan { table, .. } => {
+                Some(self.catalog.must_read_table(table)?.columns.len())
+            }
+            Node::Distinct { source, .. }
+            | Node::Filter { source, .. }
+            | Node::Limit { source, .. }
+            | Node::Offset { source, .. }
+            | Node::Order { source, .. } => self.width(source)?,
+            Node::HashSemiJoin { left, .. } => self.width(left)?,
+            Node::HashJoin { left, right, .. } | Node::MergeJoin { left, right, .. } => {
+                match (self.width(left)?, self.width(right)?) {
+                    (Some(l), Some(r)) => Some(l + r),
+                    _ => None,
+                }
+            }
+            Node::LateralJoin { left_size, right, .. }
+            | Node::NestedLoopJoin { left_size, right, .. } => {
+                self.width(right)?.map(|r| left_size + r)
+            }
+            Node::InformationSchema { table, .. } => Some(table.columns().len()),
+            Node::Projection { expressions, .. } => Some(expressions.len()),
+            Node::Unnest { .. } => Some(1),
+            Node::Values { columns, .. } => Some(columns.len()),
+            _ => None,
+        })
+    }
+}
+
+/// Estimates the number of rows an index lookup of the given values emits,
+/// where each value has one item per index column.
+pub fn lookup_rows(statistics: &Statistics, columns: &[String], values: &[Vec<Value>]) -> f64 {
+    let rows = statistics.rows as f64;
+    values
+        .iter()
+        .map(|items| {
+            let fractions = columns.iter().zip(items).map(|(column, value)| {
+                match statistics.columns.get(column) {
+                    Some(c) => equal_fraction(c, rows, value),
+                    None => DEFAULT_EQUALITY,
+                }
+            });
+            rows * fractions.product::<f64>()
+        })
+        .sum()
+}
+
+/// Estimates the number of rows of an equijoin, assuming the join field is
+/// unique on one of the sides, e.g. a foreign key join.
+pub fn equijoin_rows(left: f64, right: f64) -> f64 {
+    if left == 0.0 || right == 0.0 {
+        return 0.0;
+    }
+    left.max(right)
+}
+
+/// Estimates the fraction of rows that match a predicate, using the given
+/// table statistics for field references if any.
+pub fn selectivity(predicate: &Expression, table: Option<(&Table, &Statistics)>) -> f64 {
+    use Expression::*;
+    // Returns the column statistics and row count for a field, if any.
+    let column = |expr: &Expression| -> Option<(&ColumnStatistics, f64)> {
+        let (Field(i, _), Some((table, statistics))) = (expr, table) else {
+            return None;
+        };
+        let column = statistics.columns.get(&table.columns.get(*i)?.name)?;
+        Some((column, statistics.rows as f64))
+    };
+    match predicate {
+        Constant(Value::Boolean(true)) => 1.0,
+        Constant(_) => 0.0,
+        And(lhs, rhs) => selectivity(lhs, table) * selectivity(rhs, table),
+        Or(lhs, rhs) => {
+            let (l, r) = (selectivity(lhs, table), selectivity(rhs, table));
+            l + r - l * r
+        }
+        Not(expr) => 1.0 - selectivity(expr, table),
+        Equal(lhs, rhs) => match (&**lhs, &**rhs) {
+            (field, Constant(value)) | (Constant(value), field) => match column(field) {
+                Some((c, rows)) => equal_fraction(c, rows, value),
+                None => DEFAULT_EQUALITY,
+            },
+            _ => DEFAULT_EQUALITY,
+        },
+        IsNull(expr) => match column(expr) {
+            Some((c, rows)) => equal_fraction(c, rows, &Value::Null),
+            None => DEFAULT_EQUALITY,
+        },
+        In(expr, list) => match column(expr) {
+            Some((c, rows)) => list
+                .iter()
+                .map(|item| match item {
+                    Constant(value) => equal_fraction(c, rows, value),
+                    _ => DEFAULT_EQUALITY,
+                })
+                .sum::<f64>()
+                .min(1.0),
+            None => (DEFAULT_EQUALITY * list.len() as f64).min(1.0),
+        },
+        LessThan(lhs, rhs) | GreaterThan(rhs, lhs) => match (&**lhs, &**rhs) {
+            (field, Constant(value)) => match column(field) {
+                Some((c, rows)) => fraction_below(c, value)
+                    .map_or(DEFAULT_SELECTIVITY, |f| f * non_null_fraction(c, rows)),
+                None => DEFAULT_SELECTIVITY,
+            },
+            (Constant(value), field) => match column(field) {
+                Some((c, rows)) => fraction_below(c, value)
+                    .map_or(DEFAULT_SELECTIVITY, |f| (1.0 - f) * non_null_fraction(c, rows)),
+                None => DEFAULT_SELECTIVITY,
+            },
+            _ => DEFAULT_SELECTIVITY,
+        },
+        Between(expr, low, high) => match (&**expr, &**low, &**high) {
+            (Field(i, _), Constant(low), Constant(high)) => {
+                let range = (Bound::Included(low.clone()), Bound::Included(high.clone()));
+                range_selectivity(*i, &range, table)
+            }
+            _ => DEFAULT_SELECTIVITY,
+        },
+        Like(lhs, _) => match &**lhs {
+            Field(i, _) => match predicate.as_range(*i) {
+                Some(range) => range_selectivity(*i, &range, table),
+                None => DEFAULT_SELECTIVITY,
+            },
+            _ => DEFAULT_SELECTIVITY,
+        },
+        _ => DEFAULT_SELECTIVITY,
+    }
+}
+
+/// Estimates the fraction of rows with a field value in the given range, using
+/// the given table statistics if any.
+pub fn range_selectivity(
+    field: usize,
+    range: &(Bound<Value>, Bound<Value>),
+    table: Option<(&Table, &Statistics)>,
+) -> f64 {
+    let column = table.and_then(|(table, statistics)| {
+        let column = statistics.columns.get(&table.columns.get(field)?.name)?;
+        Some((column, statistics.rows as f64))
+    });
+    match (range, column) {
+        (range, None) => match range {
+            (Bound::Included(l), Bound::Included(h)) if l == h => DEFAULT_EQUALITY,
+            (Bound::Unbounded, Bound::Unbounded) => 1.0,
+            (Bound::Unbounded, _) | (_, Bound::Unbounded) => DEFAULT_SELECTIVITY,
+            _ => DEFAULT_RANGE,
+        },
+        ((Bound::Included(l), Bound::Included(h)), Some((c, rows))) if l == h => {
+            equal_fraction(c, rows, l)
+        }
+        ((low, high), Some((c, rows))) => {
+            let below = |bound: &Bound<Value>, unbounded: f64| match bound {
+                Bound::Included(v) | Bound::Excluded(v) => fraction_below(c, v),
+                Bound::Unbounded => Some(unbounded),
+            };
+            match (below(low, 0.0), below(high, 1.0)) {
+                (Some(l), Some(h)) => (h - l).max(0.0) * non_null_fraction(c, rows),
+                _ => DEFAULT_SELECTIVITY,
+            }
+        }
+    }
+}
+
+/// Returns the fraction of rows with a non-NULL value in a column.
+fn non_null_fraction(column: &ColumnStatistics, rows: f64) -> f64 {
+    if rows == 0.0 {
+        return 0.0;
+    }
+    (rows - column.nulls as f64) / rows
+}
+
+/// Estimates the fraction of rows equal to a value in a column, assuming
+/// distinct values are uniformly distributed. NULL matches NULL values, as
+/// for index lookups.
+fn equal_fraction(column: &ColumnStatistics, rows: f64, value: &Value) -> f64 {
+    match value {
+        Value::Null if rows == 0.0 => 0.0,
+        Value::Null => column.nulls as f64 / rows,
+        _ if column.distinct == 0 => 0.0,
+        _ => non_null_fraction(column, rows) / column.distinct as f64,
+    }
+}
+
+/// Estimates the fraction of non-NULL values below a value in a column, by
+/// locating its histogram bucket and assuming it's in the middle of it.
+fn fraction_below(column: &ColumnStatistics, value: &Value) -> Option<f64> {
+    let histogram = &column.histogram;
+    if histogram.is_empty() || *value == Value::Null {
+        return None;
+    }
+    let below = histogram.iter().filter(|bound| *bound < value).count();
+    Some(match below {
+        0 => 0.0,
+        n if n == histogram.len() => 1.0,
+        n => (n as f64 - 0.5) / (histogram.len() - 1) as f64,
+    })
+}
@@ -1,21 +1,29 @@
+mod cost;
 mod optimizer;
 mod planner;
+pub use cost::Estimate;
 use optimizer::Optimizer as _;
 use planner::Planner;
 
-use super::engine::Transaction;
-use super::execution::{Executor, ResultSet};
-use super::parser::ast;
-use super::schema::{Catalog, Table};
+use super::engine::{IndexRange, Transaction};
+use super::execution::{Accountant, BuildContext, Cancel, Executor, ResultSet, Stats};
+use super::parser::{ast, Parser};
+use super::schema::{
+    Catalog, Column, ColumnChange, EnumType, Index, InformationSchema, Privilege, Sequence, Table,
+    Trigger, User, View,
+};
 use super::types::{Expression, Value};
-use crate::error::Result;
+use crate::error::{Error, Result};
 
 use serde_derive::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::fmt::{self, Display};
+use std::ops::Bound;
 
-/// A query plan
-#[derive(Debug)]
-pub struct Plan(pub Node);
+/// A query plan, with the optimizer hints given in the statement.
+#[derive(Clone, Debug)]
+pub struct Plan(pub Node, pub Vec<ast::Hint>);
 
 impl Display for Plan {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -24,9 +32,68 @@ impl Display for Plan {
 }
 
 impl Plan {
-    /// Builds a plan from an AST statement.
-    pub fn build<C: Catalog>(statement: ast::Statement, catalog: &mut C) -> Result<Self> {
-        Planner::new(catalog).build(statement)
+    /// Builds a plan from an AST statement, resolving unqualified names in
+    /// the given database. With strict types, string literals aren't
+    /// implicitly converted to the types of other operands.
+    pub fn build<C: Catalog>(
+        statement: ast::Statement,
+        catalog: &mut C,
+        database: &str,
+        strict_types: bool,
+    ) -> Result<Self> {
+        Planner::new(catalog)
+            .with_database(database)
+            .with_strict_types(strict_types)
+            .build(statement)
+    }
+
+    /// Builds a plan from an AST statement parsed by Parser::parameterized(),
+    /// given the parameter values. The plan contains parameter placeholders,
+    /// which must be bound via bind() before it is optimized. Also returns the
+    /// parameters whose values were used while planning (e.g. as LIMIT
+    /// counts), which the plan is only valid for.
+    pub fn build_parameterized<C: Catalog>(
+        statement: ast::Statement,
+        parameters: &[Value],
+        catalog: &mut C,
+        database: &str,
+        strict_types: bool,
+    ) -> Result<(Self, Vec<usize>)> {
+        let mut planner = Planner::new(catalog)
+            .with_parameters(parameters)
+            .with_database(database)
+            .with_strict_types(strict_types);
+        let plan = planner.build(statement)?;
+        Ok((plan, planner.pinned()))
+    }
+
+    /// Builds the action of a table's trigger from its SQL text, resolving
+    /// names in the table's database.
+    pub fn build_trigger<C: Catalog>(
+        table: &Table,
+        trigger: &Trigger,
+        catalog: &mut C,
+    ) -> Result<TriggerAction> {
+        let action = Parser::new(&trigger.action).parse_trigger_action()?;
+        Planner::new(catalog).build_trigger(table, trigger, action)
+    }
+
+    /// Evaluates a constant AST expression outside of a statement, e.g. an AS
+    /// OF SYSTEM TIME clause.
+    pub fn evaluate<C: Catalog>(expr: ast::Expression, catalog: &mut C) -> Result<Value> {
+        Planner::new(catalog).evaluate_constant(expr)
+    }
+
+    /// Binds the plan's parameter placeholders to the given values.
+    pub fn bind(self, parameters: &[Value]) -> Result<Self> {
+        let bind = |expr| match expr {
+            Expression::Parameter(i) => match parameters.get(i) {
+                Some(value) => Ok(Expression::Constant(value.clone())),
+                None => Err(Error::Internal(format!("Unbound parameter ${}", i))),
+            },
+            expr => Ok(expr),
+        };
+        Ok(Plan(self.0.transform(&|n| n.transform_expressions(&Ok, &bind), &Ok)?, self.1))
     }
 
     /// Executes the plan, consuming it.
@@ -34,40 +101,240 @@ impl Plan {
         <dyn Executor<T>>::build(self.0).execute(txn)
     }
 
+    /// Executes the plan like execute(), but every executor errors once the
+    /// given cancellation token is cancelled, and executors which buffer rows
+    /// error once they exceed the accountant's memory limit.
+    pub fn execute_with<T: Transaction + 'static>(
+        self,
+        txn: &mut T,
+        cancel: Cancel,
+        accountant: Accountant,
+    ) -> Result<ResultSet> {
+        let mut ctx =
+            BuildContext { stats: None, cancel: Some(cancel), accountant, ..Default::default() };
+        <dyn Executor<T>>::build_with(self.0, &mut ctx).execute(txn)
+    }
+
+    /// Executes the plan, consuming it, and returns its root node along with
+    /// runtime statistics for each node in pre-order, as for EXPLAIN ANALYZE.
+    /// Any result rows are fetched and discarded. Execution is cancelled and
+    /// memory accounted as for execute_with().
+    pub fn analyze<T: Transaction + 'static>(
+        self,
+        txn: &mut T,
+        cancel: Cancel,
+        accountant: Accountant,
+    ) -> Result<(Node, Vec<Stats>)> {
+        let plan = self.0.clone();
+        let mut stats = Vec::new();
+        let mut ctx = BuildContext {
+            stats: Some(&mut stats),
+            cancel: Some(cancel),
+            accountant,
+            ..Default::default()
+        };
+        let executor = <dyn Executor<T>>::build_with(self.0, &mut ctx);
+        if let ResultSet::Query { rows, .. } = executor.execute(txn)? {
+            for row in rows {
+                row?;
+            }
+        }
+        let stats = stats.iter().map(|s| Ok(s.lock()?.clone())).collect::<Result<_>>()?;
+        Ok((plan, stats))
+    }
+
+    /// Returns the estimated rows and cost of each plan node, in pre-order.
+    pub fn estimate<C: Catalog>(&self, catalog: &C) -> Result<Vec<Estimate>> {
+        cost::Estimator::new(catalog).estimates(&self.0)
+    }
+
+    /// Checks that the given user has the privileges required to execute the
+    /// plan, see Catalog::authorize(). Must be called before optimize().
+    pub fn authorize<C: Catalog>(&self, catalog: &C, user: &str) -> Result<()> {
+        self.0.authorize(catalog, user, None)
+    }
+
     /// Optimizes the plan, consuming it.
     pub fn optimize<C: Catalog>(self, catalog: &mut C) -> Result<Self> {
-        let mut root = self.0;
+        let Plan(mut root, hints) = self;
         root = optimizer::ConstantFolder.optimize(root)?;
         root = optimizer::FilterPushdown.optimize(root)?;
-        root = optimizer::IndexLookup::new(catalog).optimize(root)?;
+        root = optimizer::IndexLookup::new(catalog, &hints).optimize(root)?;
         root = optimizer::NoopCleaner.optimize(root)?;
-        root = optimizer::JoinType.optimize(root)?;
-        Ok(Plan(root))
+        if !hints.contains(&ast::Hint::FixedJoinOrder) {
+            root = optimizer::JoinOrder::new(catalog).optimize(root)?;
+        }
+        root = optimizer::JoinType::new(catalog, &hints).optimize(root)?;
+        root = optimizer::IndexOnly::new(catalog).optimize(root)?;
+        root = optimizer::OrderElimination::new(catalog).optimize(root)?;
+        root = optimizer::SubexpressionEliminator::new(catalog).optimize(root)?;
+        root = optimizer::LimitPushdown.optimize(root)?;
+        root = optimizer::ColumnPruning::new(catalog).optimize(root)?;
+        Ok(Plan(root, hints))
+    }
+
+    /// Converts table scans into parallel scans using up to the given number
+    /// of worker threads, consuming the plan. This must only be used for plans
+    /// executed in read-only transactions, and after optimize().
+    pub fn parallelize(self, workers: usize) -> Result<Self> {
+        if workers <= 1 {
+            return Ok(self);
+        }
+        let Plan(root, hints) = self;
+        Ok(Plan(optimizer::ParallelScan::new(workers).optimize(root)?, hints))
+    }
+
+    /// Sets the memory budget in bytes of sorts and aggregations, beyond
+    /// which they spill to temporary files, consuming the plan.
+    pub fn set_work_memory(self, memory: usize) -> Result<Self> {
+        let Plan(root, hints) = self;
+        let root = root.transform(&Ok, &|n| match n {
+            Node::Order { source, orders, memory: _ } => {
+                Ok(Node::Order { source, orders, memory: Some(memory) })
+            }
+            Node::Aggregation { source, aggregates, grouping_sets, memory: _ } => {
+                Ok(Node::Aggregation { source, aggregates, grouping_sets, memory: Some(memory) })
+            }
+            n => Ok(n),
+        })?;
+        Ok(Plan(root, hints))
     }
 }
 
 /// A plan node
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Node {
+    AddColumn {
+        table: String,
+        column: Column,
+    },
     Aggregation {
         source: Box<Node>,
         aggregates: Vec<Aggregate>,
+        /// For ROLLUP and CUBE, the group columns to aggregate by in each
+        /// grouping set, with other group columns set to NULL. Empty for a
+        /// plain GROUP BY, which aggregates by all group columns.
+        grouping_sets: Vec<Vec<usize>>,
+        /// The memory budget in bytes for groups, beyond which rows of new
+        /// groups are spilled to temporary files, or None for no limit. See
+        /// Plan::set_work_memory().
+        memory: Option<usize>,
+    },
+    AlterColumn {
+        table: String,
+        column: String,
+        change: ColumnChange,
+    },
+    AlterUser {
+        user: User,
+    },
+    Analyze {
+        tables: Vec<String>,
+    },
+    /// Sets or removes a table comment, or a column comment if given.
+    Comment {
+        table: String,
+        column: Option<String>,
+        comment: Option<String>,
+    },
+    CreateDatabase {
+        database: String,
+    },
+    CreateIndex {
+        table: String,
+        index: Index,
+    },
+    CreateRole {
+        role: User,
     },
     CreateTable {
         schema: Table,
     },
+    CreateTableAs {
+        table: String,
+        columns: Vec<String>,
+        temporary: bool,
+        source: Box<Node>,
+    },
+    CreateSequence {
+        sequence: Sequence,
+    },
+    CreateTrigger {
+        table: String,
+        trigger: Trigger,
+    },
+    CreateType {
+        enum_type: EnumType,
+    },
+    CreateUser {
+        user: User,
+    },
+    CreateView {
+        view: View,
+    },
     Delete {
         table: String,
         source: Box<Node>,
+        /// RETURNING expressions and labels, if any.
+        returning: Option<Returning>,
+    },
+    Describe {
+        table: String,
+    },
+    /// Keeps the first row of each run of rows with equal values for the
+    /// given expressions, i.e. the input must be ordered by them.
+    Distinct {
+        source: Box<Node>,
+        on: Vec<Expression>,
+    },
+    DropColumn {
+        table: String,
+        column: String,
+    },
+    DropIndex {
+        index: String,
+        if_exists: bool,
+    },
+    DropSequence {
+        sequence: String,
+        if_exists: bool,
     },
     DropTable {
         table: String,
         if_exists: bool,
     },
+    DropTrigger {
+        table: String,
+        trigger: String,
+        if_exists: bool,
+    },
+    DropType {
+        name: String,
+        if_exists: bool,
+    },
+    DropUser {
+        name: String,
+        if_exists: bool,
+    },
+    DropView {
+        view: String,
+        if_exists: bool,
+    },
     Filter {
         source: Box<Node>,
         predicate: Expression,
     },
+    /// Grants privileges on a table to a user or role.
+    Grant {
+        table: String,
+        grantee: String,
+        privileges: BTreeSet<Privilege>,
+    },
+    /// Grants a role to a user.
+    GrantRole {
+        role: String,
+        user: String,
+    },
     HashJoin {
         left: Box<Node>,
         left_field: (usize, Option<(Option<String>, String)>),
@@ -75,25 +342,89 @@ pub enum Node {
         right_field: (usize, Option<(Option<String>, String)>),
         outer: bool,
     },
+    /// Emits the left rows that have a matching right row (a semi join), or
+    /// that don't (an anti join), by hashing the right rows. The keys are
+    /// evaluated on the left rows and compared with the right columns, and
+    /// NULL keys never match. A null-aware anti join implements NOT IN, where
+    /// the last key is the IN value compared with SQL NULL semantics.
+    HashSemiJoin {
+        left: Box<Node>,
+        keys: Vec<Expression>,
+        right: Box<Node>,
+        anti: bool,
+        null_aware: bool,
+    },
+    /// Looks up the rows matching all of the given index lookups, as index
+    /// names and values, by intersecting their primary keys.
+    IndexIntersection {
+        table: String,
+        alias: Option<String>,
+        lookups: Vec<(String, Vec<Vec<Value>>)>,
+    },
     IndexLookup {
         table: String,
         alias: Option<String>,
-        column: String,
-        values: Vec<Value>,
+        index: String,
+        values: Vec<Vec<Value>>,
+    },
+    /// Reads rows from the index entries whose leading key values are in any
+    /// of the ranges, without reading the table rows. Only the index key,
+    /// included, and primary key columns are set, other columns are NULL.
+    IndexOnlyScan {
+        table: String,
+        alias: Option<String>,
+        index: String,
+        ranges: Vec<IndexRange>,
+    },
+    /// Scans an information_schema virtual table, generated from the catalog.
+    InformationSchema {
+        table: InformationSchema,
+        alias: Option<String>,
+    },
+    /// Looks up the rows whose value of the index's first column is in the range.
+    IndexRangeScan {
+        table: String,
+        alias: Option<String>,
+        index: String,
+        range: (Bound<Value>, Bound<Value>),
     },
     Insert {
         table: String,
         columns: Vec<String>,
         expressions: Vec<Vec<Expression>>,
+        on_conflict: Option<OnConflict>,
+        /// RETURNING expressions and labels, if any.
+        returning: Option<Returning>,
     },
     KeyLookup {
         table: String,
         alias: Option<String>,
         keys: Vec<Value>,
     },
+    /// Joins each left row with the rows of the right node, which is executed
+    /// once per left row with its outer field references (Expression::Outer)
+    /// bound to the row's values, as for LATERAL. The predicate is evaluated
+    /// like for NestedLoopJoin.
+    LateralJoin {
+        left: Box<Node>,
+        left_size: usize,
+        right: Box<Node>,
+        predicate: Option<Expression>,
+        outer: bool,
+    },
     Limit {
         source: Box<Node>,
         limit: u64,
+        /// For WITH TIES, the order expressions which rows past the limit
+        /// must match the last row on to be included.
+        ties: Vec<Expression>,
+    },
+    MergeJoin {
+        left: Box<Node>,
+        left_field: (usize, Option<(Option<String>, String)>),
+        right: Box<Node>,
+        right_field: (usize, Option<(Option<String>, String)>),
+        outer: bool,
     },
     NestedLoopJoin {
         left: Box<Node>,
@@ -109,21 +440,82 @@ pub enum Node {
     },
     Order {
         source: Box<Node>,
-        orders: Vec<(Expression, Direction)>,
+        orders: Vec<(Expression, Direction, NullOrder)>,
+        /// The memory budget in bytes for buffered rows, beyond which sorted
+        /// runs are spilled to temporary files, or None for no limit. See
+        /// Plan::set_work_memory().
+        memory: Option<usize>,
+    },
+    /// Scans a table like Scan, but decodes and filters its rows on up to the
+    /// given number of worker threads. See Plan::parallelize().
+    ParallelScan {
+        table: String,
+        alias: Option<String>,
+        filter: Option<Expression>,
+        /// The columns to decode, or None for all, see Scan.
+        columns: Option<Vec<usize>>,
+        workers: usize,
     },
     Projection {
         source: Box<Node>,
         expressions: Vec<(Expression, Option<String>)>,
     },
+    /// Revokes privileges on a table from a user or role.
+    Revoke {
+        table: String,
+        grantee: String,
+        privileges: BTreeSet<Privilege>,
+    },
+    /// Revokes a role from a user.
+    RevokeRole {
+        role: String,
+        user: String,
+    },
+    /// Scans a table in descending primary key order.
+    ReverseScan {
+        table: String,
+        alias: Option<String>,
+        filter: Option<Expression>,
+        /// The columns to decode, or None for all, see Scan.
+        columns: Option<Vec<usize>>,
+    },
     Scan {
         table: String,
         alias: Option<String>,
         filter: Option<Expression>,
+        /// The columns referenced by ancestor nodes, in order, or None if all
+        /// columns may be referenced. Other columns aren't decoded, and are
+        /// NULL in the emitted rows. See ColumnPruning.
+        columns: Option<Vec<usize>>,
+    },
+    SetOperation {
+        left: Box<Node>,
+        right: Box<Node>,
+        operator: SetOperator,
+        all: bool,
+    },
+    ShowCreateTable {
+        table: String,
+    },
+    /// Lists the tables of the given database, and temporary tables.
+    ShowTables {
+        database: String,
+    },
+    /// Emits a row for each element of an array, in a single column.
+    Unnest {
+        expression: Expression,
+        column: String,
     },
     Update {
         table: String,
         source: Box<Node>,
         expressions: Vec<(usize, Option<String>, Expression)>,
+        /// RETURNING expressions and labels, if any.
+        returning: Option<Returning>,
+    },
+    Values {
+        columns: Vec<String>,
+        rows: Vec<Vec<Expression>>,
     },
 }
 
@@ -136,19 +528,66 @@ impl Node {
     {
         self = before(self)?;
         self = match self {
-            n @ Self::CreateTable { .. }
+            n @ Self::AddColumn { .. }
+            | n @ Self::AlterColumn { .. }
+            | n @ Self::AlterUser { .. }
+            | n @ Self::Analyze { .. }
+            | n @ Self::Comment { .. }
+            | n @ Self::CreateDatabase { .. }
+            | n @ Self::CreateIndex { .. }
+            | n @ Self::CreateRole { .. }
+            | n @ Self::CreateSequence { .. }
+            | n @ Self::CreateTable { .. }
+            | n @ Self::CreateTrigger { .. }
+            | n @ Self::CreateType { .. }
+            | n @ Self::CreateUser { .. }
+            | n @ Self::CreateView { .. }
+            | n @ Self::Describe { .. }
+            | n @ Self::DropColumn { .. }
+            | n @ Self::DropIndex { .. }
+            | n @ Self::DropSequence { .. }
             | n @ Self::DropTable { .. }
+            | n @ Self::DropTrigger { .. }
+            | n @ Self::DropType { .. }
+            | n @ Self::DropUser { .. }
+            | n @ Self::DropView { .. }
+            | n @ Self::Grant { .. }
+            | n @ Self::GrantRole { .. }
+            | n @ Self::IndexIntersection { .. }
             | n @ Self::IndexLookup { .. }
+            | n @ Self::IndexOnlyScan { .. }
+            | n @ Self::IndexRangeScan { .. }
+            | n @ Self::InformationSchema { .. }
             | n @ Self::Insert { .. }
             | n @ Self::KeyLookup { .. }
             | n @ Self::Nothing
-            | n @ Self::Scan { .. } => n,
+            | n @ Self::ParallelScan { .. }
+            | n @ Self::ReverseScan { .. }
+            | n @ Self::Revoke { .. }
+            | n @ Self::RevokeRole { .. }
+            | n @ Self::Scan { .. }
+            | n @ Self::ShowCreateTable { .. }
+            | n @ Self::ShowTables { .. }
+            | n @ Self::Unnest { .. }
+            | n @ Self::Values { .. } => n,
 
-            Self::Aggregation { source, aggregates } => {
-                Self::Aggregation { source: source.transform(before, after)?.into(), aggregates }
+            Self::Aggregation { source, aggregates, grouping_sets, memory } => Self::Aggregation {
+                source: source.transform(before, after)?.into(),
+                aggregates,
+                grouping_sets,
+                memory,
+            },
+            Self::CreateTableAs { table, columns, temporary, source } => Self::CreateTableAs {
+                table,
+                columns,
+                temporary,
+                source: source.transform(before, after)?.into(),
+            },
+            Self::Delete { table, source, returning } => {
+                Self::Delete { table, source: source.transform(before, after)?.into(), returning }
             }
-            Self::Delete { table, source } => {
-                Self::Delete { table, source: source.transform(before, after)?.into() }
+            Self::Distinct { source, on } => {
+                Self::Distinct { source: source.transform(before, after)?.into(), on }
             }
             Self::Filter { source, predicate } => {
                 Self::Filter { source: source.transform(before, after)?.into(), predicate }
@@ -160,9 +599,30 @@ impl Node {
                 right_field,
                 outer,
             },
-            Self::Limit { source, limit } => {
-                Self::Limit { source: source.transform(before, after)?.into(), limit }
+            Self::HashSemiJoin { left, keys, right, anti, null_aware } => Self::HashSemiJoin {
+                left: left.transform(before, after)?.into(),
+                keys,
+                right: right.transform(before, after)?.into(),
+                anti,
+                null_aware,
+            },
+            Self::LateralJoin { left, left_size, right, predicate, outer } => Self::LateralJoin {
+                left: left.transform(before, after)?.into(),
+                left_size,
+                right: right.transform(before, after)?.into(),
+                predicate,
+                outer,
+            },
+            Self::Limit { source, limit, ties } => {
+                Self::Limit { source: source.transform(before, after)?.into(), limit, ties }
             }
+            Self::MergeJoin { left, left_field, right, right_field, outer } => Self::MergeJoin {
+                left: left.transform(before, after)?.into(),
+                left_field,
+                right: right.transform(before, after)?.into(),
+                right_field,
+                outer,
+            },
             Self::NestedLoopJoin { left, left_size, right, predicate, outer } => {
                 Self::NestedLoopJoin {
                     left: left.transform(before, after)?.into(),
@@ -175,15 +635,24 @@ impl Node {
             Self::Offset { source, offset } => {
                 Self::Offset { source: source.transform(before, after)?.into(), offset }
             }
-            Self::Order { source, orders } => {
-                Self::Order { source: source.transform(before, after)?.into(), orders }
+            Self::Order { source, orders, memory } => {
+                Self::Order { source: source.transform(before, after)?.into(), orders, memory }
             }
             Self::Projection { source, expressions } => {
                 Self::Projection { source: source.transform(before, after)?.into(), expressions }
             }
-            Self::Update { table, source, expressions } => {
-                Self::Update { table, source: source.transform(before, after)?.into(), expressions }
-            }
+            Self::SetOperation { left, right, operator, all } => Self::SetOperation {
+                left: left.transform(before, after)?.into(),
+                right: right.transform(before, after)?.into(),
+                operator,
+                all,
+            },
+            Self::Update { table, source, expressions, returning } => Self::Update {
+                table,
+                source: source.transform(before, after)?.into(),
+                expressions,
+                returning,
+            },
         };
         after(self)
     }
@@ -195,37 +664,132 @@ impl Node {
         A: Fn(Expression) -> Result<Expression>,
     {
         Ok(match self {
-            n @ Self::Aggregation { .. }
+            n @ Self::AddColumn { .. }
+            | n @ Self::Aggregation { .. }
+            | n @ Self::AlterColumn { .. }
+            | n @ Self::AlterUser { .. }
+            | n @ Self::Analyze { .. }
+            | n @ Self::Comment { .. }
+            | n @ Self::CreateDatabase { .. }
+            | n @ Self::CreateIndex { .. }
+            | n @ Self::CreateRole { .. }
+            | n @ Self::CreateSequence { .. }
             | n @ Self::CreateTable { .. }
-            | n @ Self::Delete { .. }
+            | n @ Self::CreateTableAs { .. }
+            | n @ Self::CreateTrigger { .. }
+            | n @ Self::CreateType { .. }
+            | n @ Self::CreateUser { .. }
+            | n @ Self::CreateView { .. }
+            | n @ Self::Describe { .. }
+            | n @ Self::DropColumn { .. }
+            | n @ Self::DropIndex { .. }
+            | n @ Self::DropSequence { .. }
             | n @ Self::DropTable { .. }
+            | n @ Self::DropTrigger { .. }
+            | n @ Self::DropType { .. }
+            | n @ Self::DropUser { .. }
+            | n @ Self::DropView { .. }
+            | n @ Self::Grant { .. }
+            | n @ Self::GrantRole { .. }
             | n @ Self::HashJoin { .. }
+            | n @ Self::IndexIntersection { .. }
             | n @ Self::IndexLookup { .. }
+            | n @ Self::IndexOnlyScan { .. }
+            | n @ Self::IndexRangeScan { .. }
+            | n @ Self::InformationSchema { .. }
             | n @ Self::KeyLookup { .. }
-            | n @ Self::Limit { .. }
+            | n @ Self::LateralJoin { predicate: None, .. }
+            | n @ Self::MergeJoin { .. }
             | n @ Self::NestedLoopJoin { predicate: None, .. }
             | n @ Self::Nothing
             | n @ Self::Offset { .. }
-            | n @ Self::Scan { filter: None, .. } => n,
+            | n @ Self::ParallelScan { filter: None, .. }
+            | n @ Self::ReverseScan { filter: None, .. }
+            | n @ Self::Revoke { .. }
+            | n @ Self::RevokeRole { .. }
+            | n @ Self::Scan { filter: None, .. }
+            | n @ Self::SetOperation { .. }
+            | n @ Self::ShowCreateTable { .. }
+            | n @ Self::ShowTables { .. } => n,
 
+            Self::Delete { table, source, returning } => Self::Delete {
+                table,
+                source,
+                returning: transform_returning(returning, before, after)?,
+            },
+            Self::Distinct { source, on } => Self::Distinct {
+                source,
+                on: on.into_iter().map(|e| e.transform(before, after)).collect::<Result<_>>()?,
+            },
             Self::Filter { source, predicate } => {
                 Self::Filter { source, predicate: predicate.transform(before, after)? }
             }
-            Self::Insert { table, columns, expressions } => Self::Insert {
+            Self::HashSemiJoin { left, keys, right, anti, null_aware } => Self::HashSemiJoin {
+                left,
+                keys: keys
+                    .into_iter()
+                    .map(|e| e.transform(before, after))
+                    .collect::<Result<_>>()?,
+                right,
+                anti,
+                null_aware,
+            },
+            Self::Limit { source, limit, ties } => Self::Limit {
+                source,
+                limit,
+                ties: ties
+                    .into_iter()
+                    .map(|e| e.transform(before, after))
+                    .collect::<Result<_>>()?,
+            },
+            Self::Insert { table, columns, expressions, on_conflict, returning } => Self::Insert {
+                returning: transform_returning(returning, before, after)?,
                 table,
                 columns,
                 expressions: expressions
                     .into_iter()
                     .map(|exprs| exprs.into_iter().map(|e| e.transform(before, after)).collect())
                     .collect::<Result<_>>()?,
+                on_conflict: on_conflict
+                    .map(|c| -> Result<_> {
+                        Ok(OnConflict {
+                            keys: c.keys,
+                            update: c
+                                .update
+                                .map(|update| {
+                                    update
+                                        .into_iter()
+                                        .map(|(i, l, e)| {
+                                            e.transform(before, after).map(|e| (i, l, e))
+                                        })
+                                        .collect::<Result<_>>()
+                                })
+                                .transpose()?,
+                            predicate: c
+                                .predicate
+                                .map(|p| p.transform(before, after))
+                                .transpose()?,
+                        })
+                    })
+                    .transpose()?,
             },
-            Self::Order { source, orders } => Self::Order {
+            Self::Order { source, orders, memory } => Self::Order {
                 source,
                 orders: orders
                     .into_iter()
-                    .map(|(e, o)| e.transform(before, after).map(|e| (e, o)))
+                    .map(|(e, o, n)| e.transform(before, after).map(|e| (e, o, n)))
                     .collect::<Result<_>>()?,
+                memory,
             },
+            Self::LateralJoin { left, left_size, right, predicate: Some(predicate), outer } => {
+                Self::LateralJoin {
+                    left,
+                    left_size,
+                    right,
+                    predicate: Some(predicate.transform(before, after)?),
+                    outer,
+                }
+            }
             Self::NestedLoopJoin { left, left_size, right, predicate: Some(predicate), outer } => {
                 Self::NestedLoopJoin {
                     left,
@@ -235,6 +799,10 @@ impl Node {
                     outer,
                 }
             }
+            Self::ParallelScan { table, alias, filter: Some(filter), columns, workers } => {
+                let filter = Some(filter.transform(before, after)?);
+                Self::ParallelScan { table, alias, filter, columns, workers }
+            }
             Self::Projection { source, expressions } => Self::Projection {
                 source,
                 expressions: expressions
@@ -242,55 +810,401 @@ impl Node {
                     .map(|(e, l)| Ok((e.transform(before, after)?, l)))
                     .collect::<Result<_>>()?,
             },
-            Self::Scan { table, alias, filter: Some(filter) } => {
-                Self::Scan { table, alias, filter: Some(filter.transform(before, after)?) }
+            Self::ReverseScan { table, alias, filter: Some(filter), columns } => {
+                let filter = Some(filter.transform(before, after)?);
+                Self::ReverseScan { table, alias, filter, columns }
+            }
+            Self::Scan { table, alias, filter: Some(filter), columns } => {
+                Self::Scan { table, alias, filter: Some(filter.transform(before, after)?), columns }
             }
-            Self::Update { table, source, expressions } => Self::Update {
+            Self::Update { table, source, expressions, returning } => Self::Update {
                 table,
                 source,
                 expressions: expressions
                     .into_iter()
                     .map(|(i, l, e)| e.transform(before, after).map(|e| (i, l, e)))
                     .collect::<Result<_>>()?,
+                returning: transform_returning(returning, before, after)?,
+            },
+            Self::Unnest { expression, column } => {
+                Self::Unnest { expression: expression.transform(before, after)?, column }
+            }
+            Self::Values { columns, rows } => Self::Values {
+                columns,
+                rows: rows
+                    .into_iter()
+                    .map(|exprs| exprs.into_iter().map(|e| e.transform(before, after)).collect())
+                    .collect::<Result<_>>()?,
             },
         })
     }
 
-    // Displays the node, where prefix gives the node prefix.
-    pub fn format(&self, mut indent: String, root: bool, last: bool) -> String {
-        let mut s = indent.clone();
-        if !last {
-            s += "├─ ";
-            indent += "│  "
-        } else if !root {
-            s += "└─ ";
-            indent += "   ";
+    /// Binds the node's outer field references (Expression::Outer) to the
+    /// given row values, or NULL if missing, e.g. to a lateral join's left
+    /// row. The right nodes of nested lateral joins refer to their own left
+    /// rows instead, so they're set aside while transforming the node.
+    pub fn bind_outer(self, row: &[Value]) -> Result<Self> {
+        let nested = RefCell::new(Vec::new());
+        let bind = |e| match e {
+            Expression::Outer(i, _) => {
+                Ok(Expression::Constant(row.get(i).cloned().unwrap_or(Value::Null)))
+            }
+            e => Ok(e),
+        };
+        self.transform(
+            &|n| match n {
+                Self::LateralJoin { left, left_size, right, predicate, outer } => {
+                    nested.borrow_mut().push(right);
+                    let right = Box::new(Self::Nothing);
+                    Self::LateralJoin { left, left_size, right, predicate, outer }
+                        .transform_expressions(&Ok, &bind)
+                }
+                n => n.transform_expressions(&Ok, &bind),
+            },
+            &|n| match n {
+                Self::LateralJoin { left, left_size, right: _, predicate, outer } => {
+                    let right = nested.borrow_mut().pop().expect("nested lateral join");
+                    Ok(Self::LateralJoin { left, left_size, right, predicate, outer })
+                }
+                n => Ok(n),
+            },
+        )
+    }
+
+    /// Checks that the user has the privileges required to execute the node
+    /// and its children. Scanning the target table of an UPDATE or DELETE only
+    /// requires the UPDATE or DELETE privilege, unless its column values are
+    /// read by the WHERE clause, SET expressions or RETURNING, which also
+    /// requires the SELECT privilege, like in Postgres. Foreign keys require the
+    /// REFERENCES privilege on the referenced table, and seeing a table's
+    /// schema requires any privilege on it. User and role management and
+    /// creating databases require the root user, except for users changing
+    /// their own password.
+    fn authorize<C: Catalog>(&self, catalog: &C, user: &str, target: Option<&str>) -> Result<()> {
+        let root = |statement: &str| match user {
+            User::ROOT => Ok(()),
+            _ => Err(Error::PermissionDenied(format!("{} requires the root user", statement))),
+        };
+        let references = |table: &str, column: &Column| match &column.references {
+            Some(referenced) if referenced != table => {
+                catalog.authorize(user, referenced, Privilege::References)
+            }
+            _ => Ok(()),
+        };
+        match self {
+            Self::IndexIntersection { table, .. }
+            | Self::IndexLookup { table, .. }
+            | Self::IndexOnlyScan { table, .. }
+            | Self::IndexRangeScan { table, .. }
+            | Self::KeyLookup { table, .. }
+            | Self::ParallelScan { table, .. }
+            | Self::ReverseScan { table, .. }
+            | Self::Scan { table, .. }
+                if target != Some(table.as_str()) =>
+            {
+                catalog.authorize(user, table, Privilege::Select)?
+            }
+            Self::Analyze { tables } => {
+                for table in tables {
+                    catalog.authorize(user, table, Privilege::Select)?;
+                }
+            }
+            Self::Insert { table, on_conflict, returning, .. } => {
+                catalog.authorize(user, table, Privilege::Insert)?;
+                // DO UPDATE reads the existing row, like an UPDATE.
+                if let Some(OnConflict { update: Some(update), predicate, .. }) = on_conflict {
+                    catalog.authorize(user, table, Privilege::Update)?;
+                    if returning.is_some()
+                        || predicate.as_ref().is_some_and(|p| !p.fields().is_empty())
+                        || update.iter().any(|(_, _, expr)| !expr.fields().is_empty())
+                    {
+                        catalog.authorize(user, table, Privilege::Select)?;
+                    }
+                }
+            }
+            Self::Update { table, source, expressions, returning } => {
+                catalog.authorize(user, table, Privilege::Update)?;
+                let reads = returning.is_some()
+                    || source.reads_target()
+                    || expressions.iter().any(|(_, _, expr)| !expr.fields().is_empty());
+                return source.authorize(catalog, user, (!reads).then_some(table));
+            }
+            Self::Delete { table, source, returning } => {
+                catalog.authorize(user, table, Privilege::Delete)?;
+                let reads = returning.is_some() || source.reads_target();
+                return source.authorize(catalog, user, (!reads).then_some(table));
+            }
+            Self::CreateTable { schema } => {
+                for column in &schema.columns {
+                    references(&schema.name, column)?;
+                }
+            }
+            Self::AddColumn { table, column } => {
+                catalog.authorize(user, table, Privilege::Ddl)?;
+                references(table, column)?;
+            }
+            // Dropping a missing table or trigger with IF EXISTS is a noop.
+            Self::DropTable { table, if_exists: true }
+            | Self::DropTrigger { table, if_exists: true, .. }
+                if catalog.read_table(table)?.is_none() => {}
+            Self::AlterColumn { table, .. }
+            | Self::Comment { table, .. }
+            | Self::CreateIndex { table, .. }
+            | Self::CreateTrigger { table, .. }
+            | Self::DropColumn { table, .. }
+            | Self::DropTable { table, .. }
+            | Self::DropTrigger { table, .. } => catalog.authorize(user, table, Privilege::Ddl)?,
+            Self::DropView { view, if_exists: true } if catalog.read_view(view)?.is_none() => {}
+            Self::DropView { view, .. } => catalog.authorize_view_owner(user, view)?,
+            Self::DropSequence { sequence, if_exists: true }
+                if catalog.read_sequence(sequence)?.is_none() => {}
+            Self::DropSequence { sequence, .. } => {
+                catalog.authorize_sequence_owner(user, sequence)?
+            }
+            Self::DropType { name, if_exists: true } if catalog.read_type(name)?.is_none() => {}
+            Self::DropType { name, .. } => catalog.authorize_type_owner(user, name)?,
+            Self::Describe { table } | Self::ShowCreateTable { table } => {
+                catalog.authorize_any(user, table)?
+            }
+            Self::DropIndex { index, .. } => {
+                if let Some(table) = catalog.read_index_table(index)? {
+                    catalog.authorize(user, &table.name, Privilege::Ddl)?;
+                }
+            }
+            Self::Grant { table, .. } | Self::Revoke { table, .. } => {
+                catalog.authorize_owner(user, table)?
+            }
+            Self::AlterUser { user: altered } if altered.name != user => root("ALTER USER")?,
+            Self::CreateDatabase { .. } => root("CREATE DATABASE")?,
+            Self::CreateUser { .. } => root("CREATE USER")?,
+            Self::CreateRole { .. } => root("CREATE ROLE")?,
+            Self::DropUser { .. } => root("DROP USER")?,
+            Self::GrantRole { .. } => root("GRANT role")?,
+            Self::RevokeRole { .. } => root("REVOKE role")?,
+            _ => {}
+        }
+        for child in self.children() {
+            child.authorize(catalog, user, target)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if the source of an UPDATE or DELETE reads the target
+    /// table's column values, i.e. unless it's a scan without a filter on
+    /// them. Sources joined with USING tables always read them.
+    fn reads_target(&self) -> bool {
+        match self {
+            Self::Scan { filter, .. } => filter.as_ref().is_some_and(|f| !f.fields().is_empty()),
+            _ => true,
+        }
+    }
+
+    /// Returns the node's children, in display order.
+    pub(crate) fn children(&self) -> Vec<&Node> {
+        match self {
+            Self::AddColumn { .. }
+            | Self::AlterColumn { .. }
+            | Self::AlterUser { .. }
+            | Self::Analyze { .. }
+            | Self::Comment { .. }
+            | Self::CreateDatabase { .. }
+            | Self::CreateIndex { .. }
+            | Self::CreateRole { .. }
+            | Self::CreateSequence { .. }
+            | Self::CreateTable { .. }
+            | Self::CreateTrigger { .. }
+            | Self::CreateType { .. }
+            | Self::CreateUser { .. }
+            | Self::CreateView { .. }
+            | Self::Describe { .. }
+            | Self::DropColumn { .. }
+            | Self::DropIndex { .. }
+            | Self::DropSequence { .. }
+            | Self::DropTable { .. }
+            | Self::DropTrigger { .. }
+            | Self::DropType { .. }
+            | Self::DropUser { .. }
+            | Self::DropView { .. }
+            | Self::Grant { .. }
+            | Self::GrantRole { .. }
+            | Self::IndexIntersection { .. }
+            | Self::IndexLookup { .. }
+            | Self::IndexOnlyScan { .. }
+            | Self::IndexRangeScan { .. }
+            | Self::InformationSchema { .. }
+            | Self::Insert { .. }
+            | Self::KeyLookup { .. }
+            | Self::Nothing
+            | Self::ParallelScan { .. }
+            | Self::ReverseScan { .. }
+            | Self::Revoke { .. }
+            | Self::RevokeRole { .. }
+            | Self::Scan { .. }
+            | Self::ShowCreateTable { .. }
+            | Self::ShowTables { .. }
+            | Self::Unnest { .. }
+            | Self::Values { .. } => Vec::new(),
+
+            Self::Aggregation { source, .. }
+            | Self::CreateTableAs { source, .. }
+            | Self::Delete { source, .. }
+            | Self::Distinct { source, .. }
+            | Self::Filter { source, .. }
+            | Self::Limit { source, .. }
+            | Self::Offset { source, .. }
+            | Self::Order { source, .. }
+            | Self::Projection { source, .. }
+            | Self::Update { source, .. } => vec![source],
+
+            Self::HashJoin { left, right, .. }
+            | Self::HashSemiJoin { left, right, .. }
+            | Self::LateralJoin { left, right, .. }
+            | Self::MergeJoin { left, right, .. }
+            | Self::NestedLoopJoin { left, right, .. }
+            | Self::SetOperation { left, right, .. } => vec![left, right],
         }
+    }
+
+    /// Describes the node itself, as displayed in plans.
+    fn describe(&self) -> String {
+        let mut s = String::new();
         match self {
-            Self::Aggregation { source, aggregates } => {
+            Self::AddColumn { table, column } => {
+                s += &format!("AddColumn: {}.{}", table, column.name);
+            }
+            Self::Aggregation { aggregates, grouping_sets, .. } => {
                 s += &format!(
-                    "Aggregation: {}\n",
+                    "Aggregation: {}",
                     aggregates.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")
                 );
-                s += &source.format(indent, false, true);
+                if !grouping_sets.is_empty() {
+                    s += &format!(
+                        " grouping sets {}",
+                        grouping_sets
+                            .iter()
+                            .map(|set| format!(
+                                "({})",
+                                set.iter()
+                                    .map(|i| format!("#{}", i))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+            }
+            Self::AlterColumn { table, column, change } => {
+                s += &format!("AlterColumn: {}.{} {}", table, column, change);
+            }
+            Self::AlterUser { user } => {
+                s += &format!("AlterUser: {}", user.name);
+            }
+            Self::Analyze { tables } => {
+                s += &format!("Analyze: {}", tables.join(", "));
+            }
+            Self::Comment { table, column, comment: _ } => match column {
+                Some(column) => s += &format!("Comment: {}.{}", table, column),
+                None => s += &format!("Comment: {}", table),
+            },
+            Self::CreateDatabase { database } => {
+                s += &format!("CreateDatabase: {}", database);
+            }
+            Self::CreateIndex { table, index } => {
+                s += &format!(
+                    "CreateIndex: {} on {} ({}){}",
+                    index.name,
+                    table,
+                    index.columns.join(", "),
+                    if index.unique { " unique" } else { "" }
+                );
+                if !index.include.is_empty() {
+                    s += &format!(" include ({})", index.include.join(", "));
+                }
+            }
+            Self::CreateRole { role } => {
+                s += &format!("CreateRole: {}", role.name);
+            }
+            Self::CreateSequence { sequence } => {
+                s += &format!("CreateSequence: {}", sequence.name);
             }
             Self::CreateTable { schema } => {
-                s += &format!("CreateTable: {}\n", schema.name);
+                s += &format!("CreateTable: {}", schema.name);
+            }
+            Self::CreateTableAs { table, columns, .. } => {
+                s += &format!("CreateTableAs: {} ({})", table, columns.join(", "));
+            }
+            Self::CreateTrigger { table, trigger } => {
+                s += &format!(
+                    "CreateTrigger: {} {} {} on {}",
+                    trigger.name, trigger.timing, trigger.event, table
+                );
+            }
+            Self::CreateType { enum_type } => {
+                s += &format!("CreateType: {}", enum_type.name);
+            }
+            Self::CreateUser { user } => {
+                s += &format!("CreateUser: {}", user.name);
             }
-            Self::Delete { source, table } => {
-                s += &format!("Delete: {}\n", table);
-                s += &source.format(indent, false, true);
+            Self::CreateView { view } => {
+                s += &format!("CreateView: {}", view.name);
+            }
+            Self::Delete { table, returning, .. } => {
+                s += &format!("Delete: {}", table);
+                s += &format_returning(returning);
+            }
+            Self::Describe { table } => {
+                s += &format!("Describe: {}", table);
+            }
+            Self::Distinct { on, .. } => {
+                s += &format!(
+                    "Distinct: on {}",
+                    on.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", ")
+                );
+            }
+            Self::DropColumn { table, column } => {
+                s += &format!("DropColumn: {}.{}", table, column);
+            }
+            Self::DropIndex { index, if_exists: _ } => {
+                s += &format!("DropIndex: {}", index);
+            }
+            Self::DropSequence { sequence, if_exists: _ } => {
+                s += &format!("DropSequence: {}", sequence);
             }
             Self::DropTable { table, if_exists: _ } => {
-                s += &format!("DropTable: {}\n", table);
+                s += &format!("DropTable: {}", table);
             }
-            Self::Filter { source, predicate } => {
-                s += &format!("Filter: {}\n", predicate);
-                s += &source.format(indent, false, true);
+            Self::DropTrigger { table, trigger, if_exists: _ } => {
+                s += &format!("DropTrigger: {} on {}", trigger, table);
+            }
+            Self::DropType { name, if_exists: _ } => {
+                s += &format!("DropType: {}", name);
+            }
+            Self::DropUser { name, if_exists: _ } => {
+                s += &format!("DropUser: {}", name);
+            }
+            Self::DropView { view, if_exists: _ } => {
+                s += &format!("DropView: {}", view);
+            }
+            Self::Filter { predicate, .. } => {
+                s += &format!("Filter: {}", predicate);
             }
-            Self::HashJoin { left, left_field, right, right_field, outer } => {
+            Self::Grant { table, grantee, privileges } => {
                 s += &format!(
-                    "HashJoin: {} on {} = {}\n",
+                    "Grant: {} on {} to {}",
+                    format_privileges(privileges),
+                    table,
+                    grantee
+                );
+            }
+            Self::GrantRole { role, user } => {
+                s += &format!("GrantRole: {} to {}", role, user);
+            }
+            Self::HashJoin { left_field, right_field, outer, .. }
+            | Self::MergeJoin { left_field, right_field, outer, .. } => {
+                s += &format!(
+                    "{}: {} on {} = {}",
+                    if let Self::HashJoin { .. } = self { "HashJoin" } else { "MergeJoin" },
                     if *outer { "outer" } else { "inner" },
                     match left_field {
                         (_, Some((Some(t), n))) => format!("{}.{}", t, n),
@@ -303,27 +1217,113 @@ impl Node {
                         (i, None) => format!("right #{}", i),
                     },
                 );
-                s += &left.format(indent.clone(), false, false);
-                s += &right.format(indent, false, true);
             }
-            Self::IndexLookup { table, column, alias, values } => {
+            Self::HashSemiJoin { keys, anti, null_aware, .. } => {
+                s += &format!(
+                    "HashSemiJoin: {}",
+                    match (anti, null_aware) {
+                        (false, _) => "semi",
+                        (true, false) => "anti",
+                        (true, true) => "anti null-aware",
+                    }
+                );
+                if !keys.is_empty() {
+                    s += &format!(
+                        " on {}",
+                        keys.iter()
+                            .enumerate()
+                            .map(|(i, key)| format!("{} = right #{}", key, i))
+                            .collect::<Vec<_>>()
+                            .join(" AND ")
+                    );
+                }
+            }
+            Self::IndexIntersection { table, alias, lookups } => {
+                s += &format!("IndexIntersection: {}", table);
+                if let Some(alias) = alias {
+                    s += &format!(" as {}", alias);
+                }
+                s += &lookups
+                    .iter()
+                    .map(|(index, values)| format!(" index {}{}", index, format_lookup(values)))
+                    .collect::<Vec<_>>()
+                    .join(" and");
+            }
+            Self::IndexLookup { table, index, alias, values } => {
                 s += &format!("IndexLookup: {}", table);
                 if let Some(alias) = alias {
                     s += &format!(" as {}", alias);
                 }
-                s += &format!(" column {}", column);
-                if !values.is_empty() && values.len() < 10 {
+                s += &format!(" index {}{}", index, format_lookup(values));
+            }
+            Self::InformationSchema { table, alias } => {
+                s += &format!("InformationSchema: {}", table.name());
+                if let Some(alias) = alias {
+                    s += &format!(" as {}", alias);
+                }
+            }
+            Self::IndexRangeScan { table, alias, index, range } => {
+                s += &format!("IndexRangeScan: {}", table);
+                if let Some(alias) = alias {
+                    s += &format!(" as {}", alias);
+                }
+                s += &format!(" index {} {}", index, format_range(range, |v| v.to_string()));
+            }
+            Self::IndexOnlyScan { table, alias, index, ranges } => {
+                s += &format!("IndexOnlyScan: {}", table);
+                if let Some(alias) = alias {
+                    s += &format!(" as {}", alias);
+                }
+                s += &format!(" index {}", index);
+                let points: Option<Vec<Vec<Value>>> = ranges
+                    .iter()
+                    .map(|range| match range {
+                        (Bound::Included(a), Bound::Included(b)) if a == b => Some(a.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                match points {
+                    Some(values) => s += &format_lookup(&values),
+                    None => {
+                        let ranges = ranges.iter().map(|r| format_range(r, |key| format_key(key)));
+                        s += &format!(" {}", ranges.collect::<Vec<_>>().join(", "));
+                    }
+                }
+            }
+            Self::Insert { table, columns: _, expressions, on_conflict, returning } => {
+                s += &format!("Insert: {} ({} rows)", table, expressions.len());
+                if let Some(on_conflict) = on_conflict {
                     s += &format!(
-                        " ({})",
-                        values.iter().map(|k| k.to_string()).collect::<Vec<_>>().join(", ")
+                        " on conflict ({})",
+                        on_conflict
+                            .keys
+                            .iter()
+                            .map(|k| k.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
                     );
-                } else {
-                    s += &format!(" ({} values)", values.len());
+                    match &on_conflict.update {
+                        Some(update) => {
+                            s += &format!(
+                                " do update ({})",
+                                update
+                                    .iter()
+                                    .map(|(i, l, e)| format!(
+                                        "{}={}",
+                                        l.clone().unwrap_or_else(|| format!("#{}", i)),
+                                        e
+                                    ))
+                                    .collect::<Vec<_>>()
+                                    .join(",")
+                            );
+                            if let Some(predicate) = &on_conflict.predicate {
+                                s += &format!(" where {}", predicate);
+                            }
+                        }
+                        None => s += " do nothing",
+                    }
                 }
-                s += "\n";
-            }
-            Self::Insert { table, columns: _, expressions } => {
-                s += &format!("Insert: {} ({} rows)\n", table, expressions.len());
+                s += &format_returning(returning);
             }
             Self::KeyLookup { table, alias, keys } => {
                 s += &format!("KeyLookup: {}", table);
@@ -338,51 +1338,85 @@ impl Node {
                 } else {
                     s += &format!(" ({} keys)", keys.len());
                 }
-                s += "\n";
             }
-            Self::Limit { source, limit } => {
-                s += &format!("Limit: {}\n", limit);
-                s += &source.format(indent, false, true);
+            Self::LateralJoin { predicate, outer, .. } => {
+                s += &format!("LateralJoin: {}", if *outer { "outer" } else { "inner" });
+                if let Some(expr) = predicate {
+                    s += &format!(" on {}", expr);
+                }
+            }
+            Self::Limit { limit, ties, .. } => {
+                s += &format!("Limit: {}", limit);
+                if !ties.is_empty() {
+                    s += " with ties";
+                }
             }
-            Self::NestedLoopJoin { left, left_size: _, right, predicate, outer } => {
+            Self::NestedLoopJoin { predicate, outer, .. } => {
                 s += &format!("NestedLoopJoin: {}", if *outer { "outer" } else { "inner" });
                 if let Some(expr) = predicate {
                     s += &format!(" on {}", expr);
                 }
-                s += "\n";
-                s += &left.format(indent.clone(), false, false);
-                s += &right.format(indent, false, true);
             }
             Self::Nothing {} => {
-                s += "Nothing\n";
+                s += "Nothing";
             }
-            Self::Offset { source, offset } => {
-                s += &format!("Offset: {}\n", offset);
-                s += &source.format(indent, false, true);
+            Self::Offset { offset, .. } => {
+                s += &format!("Offset: {}", offset);
             }
-            Self::Order { source, orders } => {
+            Self::Order { orders, .. } => {
                 s += &format!(
-                    "Order: {}\n",
+                    "Order: {}",
                     orders
                         .iter()
-                        .map(|(expr, dir)| format!("{} {}", expr, dir))
+                        .map(|(expr, dir, nulls)| match nulls {
+                            n if *n == NullOrder::default_for(dir) => format!("{} {}", expr, dir),
+                            n => format!("{} {} {}", expr, dir, n),
+                        })
                         .collect::<Vec<_>>()
                         .join(", ")
                 );
-                s += &source.format(indent, false, true);
             }
-            Self::Projection { source, expressions } => {
+            Self::ParallelScan { table, alias, filter, workers, .. } => {
+                s += &format!("ParallelScan: {}", table);
+                if let Some(alias) = alias {
+                    s += &format!(" as {}", alias);
+                }
+                if let Some(expr) = filter {
+                    s += &format!(" ({})", expr);
+                }
+                s += &format!(" workers {}", workers);
+            }
+            Self::Projection { expressions, .. } => {
                 s += &format!(
-                    "Projection: {}\n",
+                    "Projection: {}",
                     expressions
                         .iter()
                         .map(|(expr, _)| expr.to_string())
                         .collect::<Vec<_>>()
                         .join(", ")
                 );
-                s += &source.format(indent, false, true);
             }
-            Self::Scan { table, alias, filter } => {
+            Self::Revoke { table, grantee, privileges } => {
+                s += &format!(
+                    "Revoke: {} on {} from {}",
+                    format_privileges(privileges),
+                    table,
+                    grantee
+                );
+            }
+            Self::RevokeRole { role, user } => {
+                s += &format!("RevokeRole: {} from {}", role, user);
+            }
+            Self::ReverseScan { table, alias, filter, .. } => {
+                s += &format!("ReverseScan: {}", table);
+                if let Some(alias) = alias {
+                    s += &format!(" as {}", alias);
+                }
+                if let Some(expr) = filter {
+                    s += &format!(" ({})", expr);
+                }
+            }
+            Self::Scan { table, alias, filter, .. } => {
                 s += &format!("Scan: {}", table);
                 if let Some(alias) = alias {
                     s += &format!(" as {}", alias);
@@ -390,11 +1424,19 @@ impl Node {
                 if let Some(expr) = filter {
                     s += &format!(" ({})", expr);
                 }
-                s += "\n";
             }
-            Self::Update { source, table, expressions } => {
+            Self::SetOperation { operator, all, .. } => {
+                s += &format!("SetOperation: {}{}", operator, if *all { " all" } else { "" });
+            }
+            Self::ShowCreateTable { table } => {
+                s += &format!("ShowCreateTable: {}", table);
+            }
+            Self::ShowTables { .. } => {
+                s += "ShowTables";
+            }
+            Self::Update { table, expressions, returning, .. } => {
                 s += &format!(
-                    "Update: {} ({})\n",
+                    "Update: {} ({})",
                     table,
                     expressions
                         .iter()
@@ -406,9 +1448,93 @@ impl Node {
                         .collect::<Vec<_>>()
                         .join(",")
                 );
-                s += &source.format(indent, false, true);
+                s += &format_returning(returning);
+            }
+            Self::Unnest { expression, column } => {
+                s += &format!("Unnest: {} ({})", expression, column);
+            }
+            Self::Values { columns, rows } => {
+                s += &format!("Values: {} ({} rows)", columns.join(", "), rows.len());
             }
         };
+        s
+    }
+
+    /// Displays the node along with the runtime statistics of each node, as
+    /// collected by EXPLAIN ANALYZE in pre-order.
+    pub fn format_analyzed(&self, stats: &[Stats]) -> String {
+        self.format("".into(), true, true, &mut stats.iter().map(|s| format!(" ({})", s)))
+    }
+
+    /// Displays the node along with the estimates of each node, and any
+    /// runtime statistics, as for EXPLAIN VERBOSE. Both are given in
+    /// pre-order.
+    pub fn format_verbose(&self, estimates: &[Estimate], stats: &[Stats]) -> String {
+        let mut stats = stats.iter();
+        let mut annotations = estimates.iter().map(|estimate| match stats.next() {
+            Some(stats) => format!(" ({}) ({})", estimate, stats),
+            None => format!(" ({})", estimate),
+        });
+        self.format("".into(), true, true, &mut annotations)
+    }
+
+    /// Converts the node into a JSON document, as for EXPLAIN (FORMAT JSON).
+    /// Each node is an object with the node type, its details, the estimated
+    /// rows and cost (null if unknown), any runtime statistics, and its
+    /// children. Estimates and statistics are given in pre-order.
+    pub fn to_json(&self, estimates: &[Estimate], stats: &[Stats]) -> serde_json::Value {
+        self.to_json_inner(&mut estimates.iter(), &mut stats.iter())
+    }
+
+    fn to_json_inner<'a>(
+        &self,
+        estimates: &mut impl Iterator<Item = &'a Estimate>,
+        stats: &mut impl Iterator<Item = &'a Stats>,
+    ) -> serde_json::Value {
+        let description = self.describe();
+        let (name, details) = description.split_once(": ").unwrap_or((&description, ""));
+        let mut json = serde_json::Map::new();
+        json.insert("node".into(), name.into());
+        json.insert("details".into(), details.into());
+        let estimate = estimates.next().cloned().unwrap_or_default();
+        json.insert("estimated_rows".into(), estimate.rows.into());
+        json.insert("estimated_cost".into(), estimate.cost.into());
+        if let Some(stats) = stats.next() {
+            json.insert("rows".into(), stats.rows.into());
+            json.insert("loops".into(), stats.loops.into());
+            json.insert("time_ms".into(), (stats.time.as_secs_f64() * 1000.0).into());
+        }
+        let children = self.children().into_iter().map(|c| c.to_json_inner(estimates, stats));
+        json.insert("children".into(), children.collect::<Vec<_>>().into());
+        json.into()
+    }
+
+    // Displays the node, where prefix gives the node prefix. Any annotations
+    // are consumed in pre-order and appended to each node.
+    fn format(
+        &self,
+        mut indent: String,
+        root: bool,
+        last: bool,
+        annotations: &mut impl Iterator<Item = String>,
+    ) -> String {
+        let mut s = indent.clone();
+        if !last {
+            s += "├─ ";
+            indent += "│  "
+        } else if !root {
+            s += "└─ ";
+            indent += "   ";
+        }
+        s += &self.describe();
+        if let Some(annotation) = annotations.next() {
+            s += &annotation;
+        }
+        s += "\n";
+        let children = self.children();
+        for (i, child) in children.iter().enumerate() {
+            s += &child.format(indent.clone(), false, i == children.len() - 1, annotations);
+        }
         if root {
             s = s.trim_end().to_string()
         }
@@ -416,20 +1542,170 @@ impl Node {
     }
 }
 
+/// Transforms RETURNING expressions, if any.
+fn transform_returning<B, A>(
+    returning: Option<Returning>,
+    before: &B,
+    after: &A,
+) -> Result<Option<Returning>>
+where
+    B: Fn(Expression) -> Result<Expression>,
+    A: Fn(Expression) -> Result<Expression>,
+{
+    returning
+        .map(|exprs| exprs.into_iter().map(|(e, l)| Ok((e.transform(before, after)?, l))).collect())
+        .transpose()
+}
+
+/// Formats RETURNING expressions for display, if any.
+fn format_returning(returning: &Option<Returning>) -> String {
+    match returning {
+        Some(exprs) => format!(
+            " returning {}",
+            exprs.iter().map(|(e, _)| e.to_string()).collect::<Vec<_>>().join(", ")
+        ),
+        None => String::new(),
+    }
+}
+
+/// Formats table privileges for display.
+fn format_privileges(privileges: &BTreeSet<Privilege>) -> String {
+    privileges.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Formats index lookup values for display, or their count if there are many.
+fn format_lookup(values: &[Vec<Value>]) -> String {
+    if values.is_empty() || values.len() >= 10 {
+        return format!(" ({} values)", values.len());
+    }
+    format!(" ({})", values.iter().map(|key| format_key(key)).collect::<Vec<_>>().join(", "))
+}
+
+/// Formats an index key for display, parenthesized if it has multiple values.
+fn format_key(key: &[Value]) -> String {
+    match key {
+        [value] => value.to_string(),
+        key => format!("({})", key.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+/// Formats a range for display in interval notation, using the given function
+/// to format the bound values.
+fn format_range<T>(range: &(Bound<T>, Bound<T>), format: impl Fn(&T) -> String) -> String {
+    let start = match &range.0 {
+        Bound::Included(v) => format!("[{}", format(v)),
+        Bound::Excluded(v) => format!("({}", format(v)),
+        Bound::Unbounded => "(-inf".to_string(),
+    };
+    let end = match &range.1 {
+        Bound::Included(v) => format!("{}]", format(v)),
+        Bound::Excluded(v) => format!("{})", format(v)),
+        Bound::Unbounded => "inf)".to_string(),
+    };
+    format!("{}, {}", start, end)
+}
+
 impl Display for Node {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.format("".into(), true, true))
+        write!(f, "{}", self.format("".into(), true, true, &mut std::iter::empty()))
+    }
+}
+
+/// RETURNING expressions and labels of an INSERT, UPDATE or DELETE.
+pub type Returning = Vec<(Expression, Option<String>)>;
+
+/// A trigger action, built by Plan::build_trigger(). It's executed for each
+/// written row, given the row's OLD values followed by its NEW values, with
+/// OLD omitted for INSERT and NEW for DELETE.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TriggerAction {
+    /// Sets columns of the NEW row, by table column index, to expressions
+    /// evaluated on the OLD and NEW values.
+    Set(Vec<(usize, Expression)>),
+    /// Executes a statement, whose outer fields (Expression::Outer) refer to
+    /// the OLD and NEW values, see Node::bind_outer().
+    Execute(Box<Node>),
+}
+
+impl TriggerAction {
+    /// Checks that the given user has the privileges required to execute the
+    /// action, see Plan::authorize(). SET actions only write the NEW row.
+    pub fn authorize<C: Catalog>(&self, catalog: &C, user: &str) -> Result<()> {
+        match self {
+            Self::Set(_) => Ok(()),
+            Self::Execute(node) => node.authorize(catalog, user, None),
+        }
+    }
+}
+
+/// An INSERT ... ON CONFLICT action, taken instead of inserting a row that
+/// conflicts with an existing row.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OnConflict {
+    /// The unique keys to check for conflicting rows: the conflict target,
+    /// or all unique keys of the table if no target was given.
+    pub keys: Vec<ConflictKey>,
+    /// Column updates for DO UPDATE, or None for DO NOTHING. Expressions are
+    /// evaluated on the existing row followed by the inserted row.
+    pub update: Option<Vec<(usize, Option<String>, Expression)>>,
+    /// A DO UPDATE predicate, evaluated like the update expressions. Rows
+    /// are left as is unless it evaluates to true.
+    pub predicate: Option<Expression>,
+}
+
+/// A unique key checked for INSERT ... ON CONFLICT
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ConflictKey {
+    PrimaryKey,
+    Index(String),
+}
+
+impl Display for ConflictKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PrimaryKey => write!(f, "primary key"),
+            Self::Index(index) => write!(f, "index {}", index),
+        }
     }
 }
 
 /// An aggregate operation
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Aggregate {
+    /// Collects values into an array, including NULLs, optionally ordered by
+    /// the given sort keys, which follow the value in the input row.
+    ArrayAgg {
+        order: Vec<(Direction, NullOrder)>,
+    },
     Average,
+    BoolAnd,
+    BoolOr,
     Count,
     Max,
     Min,
+    StdDev,
+    /// Concatenates strings with a separator, optionally ordered by the given
+    /// sort keys, which follow the string value in the input row.
+    StringAgg {
+        separator: String,
+        order: Vec<(Direction, NullOrder)>,
+    },
     Sum,
+    Variance,
+    /// Applies the inner aggregate to distinct input values only, e.g. for
+    /// COUNT(DISTINCT a). The values are deduplicated per group.
+    Distinct(Box<Aggregate>),
+}
+
+impl Aggregate {
+    /// Returns the number of input columns the aggregate consumes.
+    pub fn inputs(&self) -> usize {
+        match self {
+            Self::ArrayAgg { order } | Self::StringAgg { order, .. } => 1 + order.len(),
+            Self::Distinct(aggregate) => aggregate.inputs(),
+            _ => 1,
+        }
+    }
 }
 
 impl Display for Aggregate {
@@ -438,11 +1714,20 @@ impl Display for Aggregate {
             f,
             "{}",
             match self {
+                Self::ArrayAgg { order } if !order.is_empty() => "array_agg ordered",
+                Self::ArrayAgg { .. } => "array_agg",
                 Self::Average => "average",
+                Self::BoolAnd => "bool_and",
+                Self::BoolOr => "bool_or",
                 Self::Count => "count",
                 Self::Max => "maximum",
                 Self::Min => "minimum",
+                Self::StdDev => "stddev",
+                Self::StringAgg { order, .. } if !order.is_empty() => "string_agg ordered",
+                Self::StringAgg { .. } => "string_agg",
                 Self::Sum => "sum",
+                Self::Variance => "variance",
+                Self::Distinct(aggregate) => return write!(f, "{} distinct", aggregate),
             }
         )
     }
@@ -450,8 +1735,30 @@ impl Display for Aggregate {
 
 pub type Aggregates = Vec<Aggregate>;
 
+/// A set operator
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SetOperator {
+    Except,
+    Intersect,
+    Union,
+}
+
+impl Display for SetOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Except => "except",
+                Self::Intersect => "intersect",
+                Self::Union => "union",
+            }
+        )
+    }
+}
+
 /// A sort order direction
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Direction {
     Ascending,
     Descending,
@@ -469,3 +1776,34 @@ impl Display for Direction {
         )
     }
 }
+
+/// The sort position of NULL values, regardless of direction
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum NullOrder {
+    First,
+    Last,
+}
+
+impl NullOrder {
+    /// Returns the default NULL position for a direction. NULLs sort before
+    /// all other values, i.e. first when ascending and last when descending.
+    pub fn default_for(direction: &Direction) -> Self {
+        match direction {
+            Direction::Ascending => Self::First,
+            Direction::Descending => Self::Last,
+        }
+    }
+}
+
+impl Display for NullOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::First => "nulls first",
+                Self::Last => "nulls last",
+            }
+        )
+    }
+}
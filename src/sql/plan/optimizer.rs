@@ -1,9 +1,11 @@
-use super::super::schema::Catalog;
-use super::super::types::{Expression, Value};
-use super::Node;
-use crate::error::Result;
+use super::super::parser::ast::Hint;
+use super::super::schema::{Catalog, Column, Index, Table};
+use super::super::types::{fulltext, Collation, DataType, Expression, Value};
+use super::{cost, Direction, Node, NullOrder};
+use crate::error::{Error, Result};
 
 use std::mem::replace;
+use std::ops::Bound;
 
 /// A plan optimizer
 pub trait Optimizer {
@@ -19,7 +21,12 @@ impl Optimizer for ConstantFolder {
         node.transform(&Ok, &|n| {
             n.transform_expressions(
                 &|e| {
-                    if !e.contains(&|expr| matches!(expr, Expression::Field(_, _))) {
+                    // Volatile functions must be evaluated for every row.
+                    if !e.contains(&|expr| match expr {
+                        Expression::Field(_, _) | Expression::Outer(_, _) => true,
+                        Expression::Function(f, _) => f.is_volatile(),
+                        _ => false,
+                    }) {
                         Ok(Expression::Constant(e.evaluate(None)?))
                     } else {
                         Ok(e)
@@ -58,7 +65,8 @@ impl Optimizer for FilterPushdown {
                     predicate: Some(predicate),
                     outer,
                 } => {
-                    let predicate = self.pushdown_join(predicate, &mut left, &mut right, left_size);
+                    let predicate =
+                        self.pushdown_join(predicate, &mut left, &mut right, left_size, outer);
                     Ok(Node::NestedLoopJoin { left, left_size, right, predicate, outer })
                 }
                 n => Ok(n),
@@ -78,12 +86,31 @@ impl FilterPushdown {
                 }
                 filter.replace(expression)
             }
-            Node::NestedLoopJoin { ref mut predicate, .. } => {
+            Node::NestedLoopJoin { ref mut predicate, outer: false, .. } => {
                 if let Some(predicate) = predicate.take() {
                     expression = Expression::And(Box::new(expression), Box::new(predicate));
                 }
                 predicate.replace(expression)
             }
+            // Outer joins emit unmatched left rows joined with NULLs, so filters can't be moved
+            // into the join predicate. Filters on the left rows can be pushed below the join, as
+            // can those of lateral joins, whose right node is executed for each left row.
+            Node::NestedLoopJoin { ref mut left, left_size, outer: true, .. }
+            | Node::LateralJoin { ref mut left, left_size, .. } => {
+                let boundary = *left_size;
+                let (push, mut cnf): (Vec<Expression>, Vec<Expression>) =
+                    expression.into_cnf_vec().into_iter().partition(|e| {
+                        !e.contains(&|e| matches!(e, Expression::Field(i, _) if i >= &boundary))
+                    });
+                if let Some(push) = Expression::from_cnf_vec(push) {
+                    if let Some(remainder) = self.pushdown(push, left) {
+                        cnf.push(remainder)
+                    }
+                }
+                Expression::from_cnf_vec(cnf)
+            }
+            // Semi joins emit left rows, so filters apply to the left source.
+            Node::HashSemiJoin { ref mut left, .. } => self.pushdown(expression, left),
             Node::Filter { ref mut predicate, .. } => {
                 let p = replace(predicate, Expression::Constant(Value::Null));
                 *predicate = Expression::And(Box::new(p), Box::new(expression));
@@ -94,13 +121,15 @@ impl FilterPushdown {
     }
 
     /// Attempts to partition a join predicate and push parts of it down into either source,
-    /// returning any remaining expression.
+    /// returning any remaining expression. Outer joins must emit all left rows, so only the
+    /// right source can be filtered.
     fn pushdown_join(
         &self,
         predicate: Expression,
         left: &mut Node,
         right: &mut Node,
         boundary: usize,
+        outer: bool,
     ) -> Option<Expression> {
         // Convert the predicate into conjunctive normal form, and partition into expressions
         // only referencing the left or right sources, leaving cross-source expressions.
@@ -108,7 +137,7 @@ impl FilterPushdown {
         let (mut push_left, cnf): (Vec<Expression>, Vec<Expression>) =
             cnf.into_iter().partition(|e| {
                 // Partition only if no expressions reference the right-hand source.
-                !e.contains(&|e| matches!(e, Expression::Field(i, _) if i >= &boundary))
+                !outer && !e.contains(&|e| matches!(e, Expression::Field(i, _) if i >= &boundary))
             });
         let (mut push_right, mut cnf): (Vec<Expression>, Vec<Expression>) =
             cnf.into_iter().partition(|e| {
@@ -125,7 +154,9 @@ impl FilterPushdown {
                     let (l, ln, r, rn) = if l > r { (r, rn, l, ln) } else { (l, ln, r, rn) };
                     if let Some(lvals) = push_left.iter().find_map(|e| e.as_lookup(*l)) {
                         push_right.push(Expression::from_lookup(*r, rn.clone(), lvals));
-                    } else if let Some(rvals) = push_right.iter().find_map(|e| e.as_lookup(*r)) {
+                    } else if let Some(rvals) =
+                        push_right.iter().find_map(|e| e.as_lookup(*r)).filter(|_| !outer)
+                    {
                         push_left.push(Expression::from_lookup(*l, ln.clone(), rvals));
                     }
                 }
@@ -150,6 +181,18 @@ impl FilterPushdown {
                 )
                 .unwrap();
             if let Some(remainder) = self.pushdown(push_right, right) {
+                // Shift any remainder back to the joined field references.
+                let remainder = remainder
+                    .transform(
+                        &|e| match e {
+                            Expression::Field(i, label) => {
+                                Ok(Expression::Field(i + boundary, label))
+                            }
+                            e => Ok(e),
+                        },
+                        &Ok,
+                    )
+                    .unwrap();
                 cnf.push(remainder)
             }
         }
@@ -157,14 +200,40 @@ impl FilterPushdown {
     }
 }
 
-/// An index lookup optimizer, which converts table scans to index lookups.
+/// An index lookup candidate, as the index, lookup values, and the positions of the CNF
+/// expressions it replaces.
+type IndexCandidate = (Index, Vec<Vec<Value>>, Vec<usize>);
+
+/// An index lookup optimizer, which converts table scans to index lookups. The FORCE_INDEX and
+/// NO_INDEX hints restrict the secondary indexes considered for a table, and a forced index is
+/// used whenever possible regardless of cost.
 pub struct IndexLookup<'a, C: Catalog> {
     catalog: &'a mut C,
+    hints: &'a [Hint],
 }
 
 impl<'a, C: Catalog> IndexLookup<'a, C> {
-    pub fn new(catalog: &'a mut C) -> Self {
-        Self { catalog }
+    pub fn new(catalog: &'a mut C, hints: &'a [Hint]) -> Self {
+        Self { catalog, hints }
+    }
+
+    // Returns the secondary indexes to consider for a table, given its name or alias, and whether
+    // the index is forced by a hint.
+    fn indexes(&self, schema: &Table, name: &str) -> Result<(Vec<Index>, bool)> {
+        for hint in self.hints {
+            match hint {
+                Hint::ForceIndex { table, index } if table == name => {
+                    let index = schema.get_index(index)?;
+                    if index.building {
+                        return Err(Error::Value(format!("Index {} is being built", index.name)));
+                    }
+                    return Ok((vec![index], true));
+                }
+                Hint::NoIndex { table } if table == name => return Ok((Vec::new(), false)),
+                _ => {}
+            }
+        }
+        Ok((schema.get_indexes().into_iter().filter(|i| !i.building).collect(), false))
     }
 
     // Wraps a node in a filter for the given CNF vector, if any, otherwise returns the bare node.
@@ -175,40 +244,224 @@ impl<'a, C: Catalog> IndexLookup<'a, C> {
             node
         }
     }
+
+    // Converts a filtered scan into a secondary index range scan, if the filter bounds the first
+    // column of one of the given indexes, e.g. via inequalities, BETWEEN, or prefix LIKE. The
+    // whole filter is still applied to the scanned rows. Key expressions have no known datatype,
+    // so indexes starting with one aren't range scanned. The index with the narrowest range is
+    // used, unless scanning the table (possibly bounded by a primary key range) is estimated to be
+    // cheaper and the index isn't forced.
+    fn range_scan(
+        &self,
+        schema: &Table,
+        alias: Option<String>,
+        filter: Expression,
+        indexes: Vec<Index>,
+        forced: bool,
+    ) -> Result<Node> {
+        let statistics = cost::Estimator::new(&*self.catalog).statistics(&schema.name)?;
+        let statistics = statistics.as_ref().map(|(t, s)| (t, s));
+        let pk = schema.columns.iter().position(|c| c.primary_key).unwrap();
+        let scan_cost = match filter.as_range(pk) {
+            Some(range) if is_key_range(&range, &schema.columns[pk].datatype) => {
+                cost::range_selectivity(pk, &range, statistics)
+            }
+            _ => 1.0,
+        };
+
+        let (mut best, mut best_cost) = (None, if forced { f64::INFINITY } else { scan_cost });
+        for index in indexes.into_iter().filter(|i| i.get_expression(0).is_none() && !i.fulltext) {
+            let ci = schema.get_column_index(&index.columns[0])?;
+            let Some(range) = filter.as_range(ci) else {
+                continue;
+            };
+            if matches!(range, (Bound::Unbounded, Bound::Unbounded))
+                || !is_key_range(&range, &schema.columns[ci].datatype)
+            {
+                continue;
+            }
+            let cost = cost::range_selectivity(ci, &range, statistics) * cost::INDEX_LOOKUP_COST;
+            if cost < best_cost {
+                (best, best_cost) = (Some((index, range)), cost);
+            }
+        }
+
+        let table = schema.name.clone();
+        let Some((index, range)) = best else {
+            return Ok(Node::Scan { table, alias, filter: Some(filter), columns: None });
+        };
+        let source = Node::IndexRangeScan { table, alias, index: index.name, range };
+        Ok(Node::Filter { source: Box::new(source), predicate: filter })
+    }
 }
 
 impl<'a, C: Catalog> Optimizer for IndexLookup<'a, C> {
     fn optimize(&self, node: Node) -> Result<Node> {
         node.transform(&Ok, &|n| match n {
-            Node::Scan { table, alias, filter: Some(filter) } => {
-                let columns = self.catalog.must_read_table(&table)?.columns;
-                let pk = columns.iter().position(|c| c.primary_key).unwrap();
+            Node::Scan { table, alias, filter: Some(filter), columns: None } => {
+                let schema = self.catalog.must_read_table(&table)?;
+                let pk = schema.columns.iter().position(|c| c.primary_key).unwrap();
+                let (indexes, forced) =
+                    self.indexes(&schema, alias.as_deref().unwrap_or(&table))?;
 
                 // Convert the filter into conjunctive normal form, and try to convert each
                 // sub-expression into a lookup. If a lookup is found, return a lookup node and then
                 // apply the remaining conjunctions as a filter node, if any.
                 let mut cnf = filter.clone().into_cnf_vec();
-                for i in 0..cnf.len() {
-                    if let Some(keys) = cnf[i].as_lookup(pk) {
-                        cnf.remove(i);
-                        return Ok(self.wrap_cnf(Node::KeyLookup { table, alias, keys }, cnf));
+                if let Some(i) =
+                    cnf.iter().position(|e| e.as_lookup(pk).is_some()).filter(|_| !forced)
+                {
+                    let keys = cnf.remove(i).as_lookup(pk).expect("lookup found");
+                    return Ok(self.wrap_cnf(Node::KeyLookup { table, alias, keys }, cnf));
+                }
+
+                // Collect candidate index lookups, as the index name, lookup values, and the
+                // positions of the CNF expressions they replace. Key expressions are matched
+                // against the CNF expressions via their key fields.
+                let mut candidates: Vec<IndexCandidate> = Vec::new();
+                let keys = KeyExpressions::new(&schema, &indexes)?;
+                let matched =
+                    cnf.iter().map(|e| keys.replace(e.clone())).collect::<Result<Vec<_>>>()?;
+
+                // Multi-column indexes can be used when all indexed columns are looked up, by
+                // looking up all combinations of the column values.
+                for index in indexes.iter().filter(|i| i.columns.len() > 1 && !i.fulltext).cloned()
+                {
+                    let mut lookups = Vec::new();
+                    for k in 0..index.columns.len() {
+                        let ci = keys.field(&schema, &index, k)?;
+                        if let Some(lookup) = matched
+                            .iter()
+                            .enumerate()
+                            .find_map(|(i, e)| Some((i, e.as_lookup(ci)?)))
+                        {
+                            lookups.push(lookup);
+                        }
                     }
-                    for (ci, column) in columns.iter().enumerate().filter(|(_, c)| c.index) {
-                        if let Some(values) = cnf[i].as_lookup(ci) {
-                            cnf.remove(i);
-                            return Ok(self.wrap_cnf(
-                                Node::IndexLookup {
-                                    table,
-                                    alias,
-                                    column: column.name.clone(),
-                                    values,
-                                },
-                                cnf,
-                            ));
+                    if lookups.len() < index.columns.len() {
+                        continue;
+                    }
+                    let mut values = vec![Vec::new()];
+                    for (_, column_values) in &lookups {
+                        values = values
+                            .into_iter()
+                            .flat_map(|key: Vec<Value>| {
+                                column_values.iter().map(move |v| {
+                                    let mut key = key.clone();
+                                    key.push(v.clone());
+                                    key
+                                })
+                            })
+                            .collect();
+                    }
+                    let positions = lookups.into_iter().map(|(i, _)| i).collect();
+                    candidates.push((index, values, positions));
+                }
+
+                for (i, expr) in matched.iter().enumerate() {
+                    for index in
+                        indexes.iter().filter(|i| i.columns.len() == 1 && !i.fulltext).cloned()
+                    {
+                        let ci = keys.field(&schema, &index, 0)?;
+                        if let Some(values) = expr.as_lookup(ci) {
+                            let values = values.into_iter().map(|v| vec![v]).collect();
+                            candidates.push((index, values, vec![i]));
+                        }
+                    }
+                }
+
+                // FULLTEXT indexes can be used for matches against a constant query, by looking up
+                // the rows containing any of its terms.
+                for (i, expr) in cnf.iter().enumerate() {
+                    let Expression::Match(lhs, rhs) = expr else { continue };
+                    let (Expression::Field(ci, _), Expression::Constant(query)) = (&**lhs, &**rhs)
+                    else {
+                        continue;
+                    };
+                    let terms = match query {
+                        Value::String(query) => fulltext::terms(query),
+                        Value::Null => Default::default(),
+                        _ => continue,
+                    };
+                    for index in indexes.iter().filter(|i| i.fulltext) {
+                        if schema.get_column_index(&index.columns[0])? == *ci {
+                            let values = terms.iter().map(|t| vec![Value::String(t.clone())]);
+                            candidates.push((index.clone(), values.collect(), vec![i]));
                         }
                     }
                 }
-                Ok(Node::Scan { table, alias, filter: Some(filter) })
+
+                // Choose the candidates to look up. If several candidates use distinct indexes and predicates,
+                // the primary keys of each lookup can be intersected to only read the rows matching
+                // all of them. Without statistics, all such candidates are intersected. Otherwise,
+                // candidates are added in order of fewest rows as long as this reduces the
+                // estimated cost, unless scanning the table is cheaper. Reading an index entry costs
+                // the same as scanning a row, the remainder of the lookup cost is reading the row.
+                // A forced index is always looked up if possible.
+                let mut chosen: Vec<IndexCandidate> = Vec::new();
+                let disjoint =
+                    |chosen: &[IndexCandidate], (index, _, positions): &IndexCandidate| {
+                        chosen.iter().all(|(i, _, p)| {
+                            i.name != index.name && p.iter().all(|p| !positions.contains(p))
+                        })
+                    };
+                match cost::Estimator::new(&*self.catalog).statistics(&table)? {
+                    _ if forced => chosen.extend(candidates.into_iter().take(1)),
+                    None => {
+                        for candidate in candidates {
+                            if disjoint(&chosen, &candidate) {
+                                chosen.push(candidate);
+                            }
+                        }
+                    }
+                    Some((_, statistics)) => {
+                        let rows = statistics.rows as f64;
+                        let mut candidates: Vec<_> = candidates
+                            .into_iter()
+                            .map(|c| (cost::lookup_rows(&statistics, &c.0.columns, &c.1), c))
+                            .collect();
+                        candidates.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+                        let (mut cost, mut read, mut fraction) = (rows, 0.0, 1.0);
+                        for (lookup_rows, candidate) in candidates {
+                            if !disjoint(&chosen, &candidate) {
+                                continue;
+                            }
+                            let (r, f) =
+                                (read + lookup_rows, fraction * lookup_rows / rows.max(1.0));
+                            let c = r + rows * f * (cost::INDEX_LOOKUP_COST - 1.0);
+                            if c < cost {
+                                (cost, read, fraction) = (c, r, f);
+                                chosen.push(candidate);
+                            }
+                        }
+                    }
+                }
+                if chosen.is_empty() {
+                    return self.range_scan(&schema, alias, filter, indexes, forced);
+                }
+
+                let mut positions: Vec<usize> =
+                    chosen.iter().flat_map(|(_, _, positions)| positions.clone()).collect();
+                positions.sort();
+                positions.dedup();
+                for i in positions.into_iter().rev() {
+                    cnf.remove(i);
+                }
+                let node = match chosen.len() {
+                    1 => {
+                        let (index, values, _) = chosen.remove(0);
+                        Node::IndexLookup { table, alias, index: index.name, values }
+                    }
+                    _ => Node::IndexIntersection {
+                        table,
+                        alias,
+                        lookups: chosen
+                            .into_iter()
+                            .map(|(i, values, _)| (i.name, values))
+                            .collect(),
+                    },
+                };
+                Ok(self.wrap_cnf(node, cnf))
             }
             n => Ok(n),
         })
@@ -262,40 +515,1137 @@ impl Optimizer for NoopCleaner {
     }
 }
 
-// Optimizes join types, currently by swapping nested-loop joins with hash joins where appropriate.
-pub struct JoinType;
+// Reorders inner joins, by greedily joining the inputs that yield the fewest estimated rows first
+// rather than joining them in the written order. This requires all join inputs to have been
+// analyzed, otherwise the written order is kept. The joined columns are projected back into their
+// original order.
+pub struct JoinOrder<'a, C: Catalog> {
+    catalog: &'a C,
+}
+
+/// An inner join input, with its column offset and width in the joined rows, and its estimated
+/// row count.
+struct JoinInput {
+    node: Node,
+    offset: usize,
+    width: usize,
+    rows: f64,
+}
+
+impl<'a, C: Catalog> JoinOrder<'a, C> {
+    pub fn new(catalog: &'a C) -> Self {
+        Self { catalog }
+    }
+
+    /// Returns the number of inputs of an inner join tree, if the widths and row counts of all
+    /// inputs are known.
+    fn count_inputs(&self, node: &Node) -> Result<Option<usize>> {
+        match node {
+            Node::NestedLoopJoin { left, right, outer: false, .. } => {
+                match (self.count_inputs(left)?, self.count_inputs(right)?) {
+                    (Some(l), Some(r)) => Ok(Some(l + r)),
+                    _ => Ok(None),
+                }
+            }
+            node => {
+                let estimator = cost::Estimator::new(self.catalog);
+                let known = estimator.width(node)?.is_some() && estimator.rows(node)?.is_some();
+                Ok(known.then_some(1))
+            }
+        }
+    }
+
+    /// Flattens an inner join tree into its inputs and predicate conjunctions, with field
+    /// references relative to the joined rows.
+    fn flatten(
+        &self,
+        node: Node,
+        offset: usize,
+        inputs: &mut Vec<JoinInput>,
+        predicates: &mut Vec<Expression>,
+    ) -> Result<()> {
+        match node {
+            Node::NestedLoopJoin { left, left_size, right, predicate, outer: false } => {
+                self.flatten(*left, offset, inputs, predicates)?;
+                self.flatten(*right, offset + left_size, inputs, predicates)?;
+                if let Some(predicate) = predicate {
+                    predicates.extend(remap(predicate, |i| i + offset)?.into_cnf_vec());
+                }
+            }
+            node => {
+                let estimator = cost::Estimator::new(self.catalog);
+                let (Some(width), Some(rows)) = (estimator.width(&node)?, estimator.rows(&node)?)
+                else {
+                    return Err(Error::Internal("Unknown join input estimates".into()));
+                };
+                inputs.push(JoinInput { node, offset, width, rows });
+            }
+        }
+        Ok(())
+    }
 
-impl Optimizer for JoinType {
+    /// Returns the order to join the inputs in. Starting with the smallest input, it repeatedly
+    /// picks the input that yields the fewest estimated rows when joined with the previous
+    /// inputs, preferring inputs that are connected to them by a predicate to avoid cross joins.
+    fn order(inputs: &[JoinInput], predicates: &[Expression]) -> Vec<usize> {
+        // The inputs referenced by each predicate.
+        let references: Vec<Vec<usize>> = predicates
+            .iter()
+            .map(|p| {
+                let columns = |i: usize| inputs[i].offset..inputs[i].offset + inputs[i].width;
+                (0..inputs.len())
+                    .filter(|i| {
+                        p.contains(
+                            &|e| matches!(e, Expression::Field(f, _) if columns(*i).contains(f)),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut order: Vec<usize> = Vec::new();
+        let mut rows = 0.0;
+        while order.len() < inputs.len() {
+            // The best candidate, as (connected, rows, input).
+            let mut best: Option<(bool, f64, usize)> = None;
+            for (i, input) in inputs.iter().enumerate().filter(|(i, _)| !order.contains(i)) {
+                let (connected, estimate) = match order.is_empty() {
+                    true => (false, input.rows),
+                    false => {
+                        // The predicates that can first be evaluated when joining the input.
+                        let applicable: Vec<&Expression> = predicates
+                            .iter()
+                            .zip(&references)
+                            .filter(|(_, refs)| refs.contains(&i))
+                            .filter(|(_, refs)| refs.iter().all(|r| *r == i || order.contains(r)))
+                            .map(|(p, _)| p)
+                            .collect();
+                        let connected = references.iter().any(|refs| {
+                            refs.contains(&i) && refs.iter().any(|r| order.contains(r))
+                        });
+                        let equijoin = applicable.iter().position(|p| {
+                            let Expression::Equal(lhs, rhs) = p else { return false };
+                            matches!(
+                                (&**lhs, &**rhs),
+                                (Expression::Field(..), Expression::Field(..))
+                            )
+                        });
+                        let mut estimate = match equijoin {
+                            Some(_) => cost::equijoin_rows(rows, input.rows),
+                            None => rows * input.rows,
+                        };
+                        for (j, predicate) in applicable.into_iter().enumerate() {
+                            if Some(j) != equijoin {
+                                estimate *= cost::selectivity(predicate, None);
+                            }
+                        }
+                        (connected, estimate)
+                    }
+                };
+                let better = match best {
+                    None => true,
+                    Some((c, r, _)) => (connected, -estimate) > (c, -r),
+                };
+                if better {
+                    best = Some((connected, estimate, i));
+                }
+            }
+            let (_, estimate, i) = best.expect("no join input left");
+            order.push(i);
+            rows = estimate;
+        }
+        // Join the first two inputs with the larger one on the left, such that a hash join
+        // builds its hash table from the smaller one.
+        if inputs[order[0]].rows < inputs[order[1]].rows {
+            order.swap(0, 1);
+        }
+        order
+    }
+}
+
+impl<'a, C: Catalog> Optimizer for JoinOrder<'a, C> {
     fn optimize(&self, node: Node) -> Result<Node> {
         node.transform(
             &|n| match n {
-                // Replace nested-loop equijoins with hash joins.
-                Node::NestedLoopJoin {
-                    left,
-                    left_size,
-                    right,
-                    predicate: Some(Expression::Equal(a, b)),
-                    outer,
-                } => match (*a, *b) {
-                    (Expression::Field(a, a_label), Expression::Field(b, b_label)) => {
-                        let (left_field, right_field) = if a < left_size {
-                            ((a, a_label), (b - left_size, b_label))
-                        } else {
-                            ((b, b_label), (a - left_size, a_label))
+                // The inputs of a single join are instead swapped by JoinType if needed.
+                n @ Node::NestedLoopJoin { outer: false, .. }
+                    if self.count_inputs(&n)?.is_some_and(|count| count > 2) =>
+                {
+                    let (mut inputs, mut predicates) = (Vec::new(), Vec::new());
+                    self.flatten(n, 0, &mut inputs, &mut predicates)?;
+                    let order = Self::order(&inputs, &predicates);
+
+                    // Map the original column positions to the reordered ones.
+                    let width = inputs.iter().map(|input| input.width).sum();
+                    let mut positions = vec![0; width];
+                    let mut offset = 0;
+                    for input in order.iter().map(|i| &inputs[*i]) {
+                        for column in 0..input.width {
+                            positions[input.offset + column] = offset + column;
+                        }
+                        offset += input.width;
+                    }
+                    let mut predicates = predicates
+                        .into_iter()
+                        .map(|p| remap(p, |i| positions[i]))
+                        .collect::<Result<Vec<_>>>()?;
+
+                    // Join the inputs in order, evaluating each predicate in the first join that
+                    // has all of its columns.
+                    let reordered = order.iter().enumerate().any(|(i, j)| i != *j);
+                    let mut inputs: Vec<Option<JoinInput>> = inputs.into_iter().map(Some).collect();
+                    let mut order = order.into_iter();
+                    let first = inputs[order.next().expect("join inputs")].take().expect("input");
+                    let (mut node, mut size) = (first.node, first.width);
+                    for i in order {
+                        let input = inputs[i].take().expect("input");
+                        let joined = size + input.width;
+                        let (predicate, rest) = predicates.into_iter().partition(|p| {
+                            !p.contains(&|e| matches!(e, Expression::Field(f, _) if *f >= joined))
+                        });
+                        predicates = rest;
+                        node = Node::NestedLoopJoin {
+                            left: Box::new(node),
+                            left_size: size,
+                            right: Box::new(input.node),
+                            predicate: Expression::from_cnf_vec(predicate),
+                            outer: false,
                         };
-                        Ok(Node::HashJoin { left, left_field, right, right_field, outer })
+                        size += input.width;
+                    }
+                    if !reordered {
+                        return Ok(node);
+                    }
+                    let expressions =
+                        positions.into_iter().map(|i| (Expression::Field(i, None), None)).collect();
+                    Ok(Node::Projection { source: Box::new(node), expressions })
+                }
+                n => Ok(n),
+            },
+            &Ok,
+        )
+    }
+}
+
+/// Remaps the field references of an expression.
+fn remap(expr: Expression, f: impl Fn(usize) -> usize) -> Result<Expression> {
+    expr.transform(
+        &|e| match e {
+            Expression::Field(i, label) => Ok(Expression::Field(f(i), label)),
+            e => Ok(e),
+        },
+        &Ok,
+    )
+}
+
+// Optimizes join types, by swapping nested-loop joins with hash joins where appropriate, or merge
+// joins if both inputs are already sorted by the join fields. If the join inputs have been
+// analyzed, their estimated row counts are used to pick the cheapest join algorithm, and to build
+// the hash table from the smaller input of inner joins. The NO_HASH_JOIN and NO_MERGE_JOIN hints
+// disable the respective join algorithms, and FIXED_JOIN_ORDER prevents swapping join inputs.
+pub struct JoinType<'a, C: Catalog> {
+    catalog: &'a C,
+    hints: &'a [Hint],
+}
+
+impl<'a, C: Catalog> JoinType<'a, C> {
+    pub fn new(catalog: &'a C, hints: &'a [Hint]) -> Self {
+        Self { catalog, hints }
+    }
+
+    /// Returns true if a nested-loop join of the inputs is estimated to be cheaper than a hash
+    /// join. A nested-loop join materializes the right rows and evaluates the predicate for each
+    /// pair of rows, while a hash join builds a hash table from one input and probes the other.
+    fn prefer_nested_loop(&self, left: &Node, right: &Node) -> Result<bool> {
+        let estimator = cost::Estimator::new(self.catalog);
+        let (Some(l), Some(r)) = (estimator.rows(left)?, estimator.rows(right)?) else {
+            return Ok(false);
+        };
+        Ok(r + l * r <= l.min(r) * cost::HASH_BUILD_COST + l.max(r))
+    }
+
+    /// Returns the column counts of the inputs if they should be swapped, such that the hash
+    /// table is built from the smaller input (the right one).
+    fn swap_inputs(&self, left: &Node, right: &Node) -> Result<Option<(usize, usize)>> {
+        let estimator = cost::Estimator::new(self.catalog);
+        let (Some(l), Some(r)) = (estimator.rows(left)?, estimator.rows(right)?) else {
+            return Ok(None);
+        };
+        if l >= r {
+            return Ok(None);
+        }
+        Ok(match (estimator.width(left)?, estimator.width(right)?) {
+            (Some(left_size), Some(right_size)) => Some((left_size, right_size)),
+            _ => None,
+        })
+    }
+
+    /// Returns true if both inputs are sorted by their join fields, with the same datatype.
+    fn can_merge(
+        &self,
+        left: &Node,
+        left_field: usize,
+        right: &Node,
+        right_field: usize,
+    ) -> Result<bool> {
+        let left = ordering(self.catalog, left)?.into_iter().find(|(i, _)| *i == left_field);
+        let right = ordering(self.catalog, right)?.into_iter().find(|(i, _)| *i == right_field);
+        Ok(matches!((left, right), (Some((_, l)), Some((_, r))) if l == r))
+    }
+}
+
+impl<'a, C: Catalog> Optimizer for JoinType<'a, C> {
+    fn optimize(&self, node: Node) -> Result<Node> {
+        // Joins are replaced while ascending the node tree, such that the orderings of merge
+        // join inputs are known.
+        node.transform(&Ok, &|n| match n {
+            // Replace nested-loop equijoins with merge joins if the inputs are sorted by the join
+            // fields, or hash joins, unless a nested-loop join is estimated to be cheaper. Inner
+            // joins can apply any other conjunctions as a filter on the joined rows, but outer
+            // joins must evaluate them during the join.
+            Node::NestedLoopJoin { left, left_size, right, predicate: Some(predicate), outer } => {
+                let mut cnf = predicate.clone().into_cnf_vec();
+                let position = cnf.iter().position(|e| equijoin(e, left_size).is_some());
+                let Some(i) = position.filter(|_| !outer || cnf.len() == 1) else {
+                    let predicate = Some(predicate);
+                    return Ok(Node::NestedLoopJoin { left, left_size, right, predicate, outer });
+                };
+                if self.prefer_nested_loop(&left, &right)? {
+                    let predicate = Some(predicate);
+                    return Ok(Node::NestedLoopJoin { left, left_size, right, predicate, outer });
+                }
+                let (left_field, right_field) =
+                    equijoin(&cnf[i], left_size).expect("equijoin found");
+                let merge = !self.hints.contains(&Hint::NoMergeJoin)
+                    && self.can_merge(&left, left_field.0, &right, right_field.0)?;
+                if !merge && self.hints.contains(&Hint::NoHashJoin) {
+                    let predicate = Some(predicate);
+                    return Ok(Node::NestedLoopJoin { left, left_size, right, predicate, outer });
+                }
+                cnf.remove(i);
+                // For inner hash joins, swap the inputs if the hash table should be built from
+                // the left input, and project the columns back into their original order.
+                let swap = match outer || merge || self.hints.contains(&Hint::FixedJoinOrder) {
+                    true => None,
+                    false => self.swap_inputs(&left, &right)?,
+                };
+                let join = match swap {
+                    Some((left_size, right_size)) => Node::Projection {
+                        source: Box::new(Node::HashJoin {
+                            left: right,
+                            left_field: right_field,
+                            right: left,
+                            right_field: left_field,
+                            outer,
+                        }),
+                        expressions: (right_size..right_size + left_size)
+                            .chain(0..right_size)
+                            .map(|i| (Expression::Field(i, None), None))
+                            .collect(),
+                    },
+                    None if merge => {
+                        Node::MergeJoin { left, left_field, right, right_field, outer }
+                    }
+                    None => Node::HashJoin { left, left_field, right, right_field, outer },
+                };
+                match Expression::from_cnf_vec(cnf) {
+                    Some(predicate) => Ok(Node::Filter { source: Box::new(join), predicate }),
+                    None => Ok(join),
+                }
+            }
+            // Collapse projections of projections that only reorder columns, as produced when
+            // swapping and reordering joins.
+            Node::Projection { source, expressions } => match *source {
+                Node::Projection { source: inner, expressions: columns }
+                    if columns.iter().all(|c| matches!(c, (Expression::Field(_, None), None))) =>
+                {
+                    let expressions = expressions
+                        .into_iter()
+                        .map(|(expr, alias)| {
+                            let expr = expr.transform(
+                                &|e| match e {
+                                    Expression::Field(i, label) => match columns.get(i) {
+                                        Some((Expression::Field(i, _), _)) => {
+                                            Ok(Expression::Field(*i, label))
+                                        }
+                                        _ => Err(Error::Internal(format!("Unknown field {}", i))),
+                                    },
+                                    e => Ok(e),
+                                },
+                                &Ok,
+                            )?;
+                            Ok((expr, alias))
+                        })
+                        .collect::<Result<_>>()?;
+                    Ok(Node::Projection { source: inner, expressions })
+                }
+                source => Ok(Node::Projection { source: Box::new(source), expressions }),
+            },
+            n => Ok(n),
+        })
+    }
+}
+
+/// An index-only scan optimizer, which converts index lookups and range scans into index-only
+/// scans when the query only references columns stored in the index, i.e. its key columns,
+/// included columns, and the primary key. Only the nodes between a projection and the index
+/// lookup are considered, since the projection determines which columns are referenced.
+pub struct IndexOnly<'a, C: Catalog> {
+    catalog: &'a C,
+}
+
+impl<'a, C: Catalog> IndexOnly<'a, C> {
+    pub fn new(catalog: &'a C) -> Self {
+        Self { catalog }
+    }
+
+    /// Converts an index lookup or range scan below a projection into an index-only scan, given
+    /// the expressions evaluated against its rows so far.
+    fn convert(&self, node: Node, mut exprs: Vec<Expression>) -> Result<Node> {
+        Ok(match node {
+            Node::Filter { source, predicate } => {
+                exprs.push(predicate.clone());
+                Node::Filter { source: Box::new(self.convert(*source, exprs)?), predicate }
+            }
+            Node::Order { source, orders, memory } => {
+                exprs.extend(orders.iter().map(|(e, _, _)| e.clone()));
+                Node::Order { source: Box::new(self.convert(*source, exprs)?), orders, memory }
+            }
+            Node::Limit { source, limit, ties } => {
+                exprs.extend(ties.iter().cloned());
+                Node::Limit { source: Box::new(self.convert(*source, exprs)?), limit, ties }
+            }
+            Node::Offset { source, offset } => {
+                Node::Offset { source: Box::new(self.convert(*source, exprs)?), offset }
+            }
+            Node::HashSemiJoin { left, keys, right, anti, null_aware } => {
+                exprs.extend(keys.iter().cloned());
+                let left = Box::new(self.convert(*left, exprs)?);
+                Node::HashSemiJoin { left, keys, right, anti, null_aware }
+            }
+            // Plain DISTINCT compares entire rows, including unreferenced columns.
+            Node::Distinct { source, on } if !on.is_empty() => {
+                exprs.extend(on.iter().cloned());
+                Node::Distinct { source: Box::new(self.convert(*source, exprs)?), on }
+            }
+            Node::IndexLookup { table, alias, index, values } => {
+                if !self.covers(&table, &index, &exprs)? {
+                    return Ok(Node::IndexLookup { table, alias, index, values });
+                }
+                let ranges =
+                    values.into_iter().map(|v| (Bound::Included(v.clone()), Bound::Included(v)));
+                Node::IndexOnlyScan { table, alias, index, ranges: ranges.collect() }
+            }
+            Node::IndexRangeScan { table, alias, index, range } => {
+                if !self.covers(&table, &index, &exprs)? {
+                    return Ok(Node::IndexRangeScan { table, alias, index, range });
+                }
+                let range = (range.0.map(|v| vec![v]), range.1.map(|v| vec![v]));
+                Node::IndexOnlyScan { table, alias, index, ranges: vec![range] }
+            }
+            node => node,
+        })
+    }
+
+    /// Returns true if the expressions only reference columns stored in the given index. Index
+    /// keys of collated columns contain their collation keys rather than their values, and key
+    /// expressions don't contain column values at all, so such indexes can't be used. Neither can
+    /// FULLTEXT indexes, which only contain terms.
+    fn covers(&self, table: &str, index: &str, exprs: &[Expression]) -> Result<bool> {
+        let table = self.catalog.must_read_table(table)?;
+        let index = table.get_index(index)?;
+        if index.fulltext {
+            return Ok(false);
+        }
+        for (i, column) in index.columns.iter().enumerate() {
+            if index.get_expression(i).is_some()
+                || table.get_column(column)?.collation != Collation::Binary
+            {
+                return Ok(false);
+            }
+        }
+        let mut covered = vec![table.get_column_index(&table.get_primary_key()?.name)?];
+        for column in index.columns.iter().chain(&index.include) {
+            covered.push(table.get_column_index(column)?);
+        }
+        Ok(!exprs.iter().any(|expr| {
+            expr.contains(&|e| matches!(e, Expression::Field(i, _) if !covered.contains(i)))
+        }))
+    }
+}
+
+impl<'a, C: Catalog> Optimizer for IndexOnly<'a, C> {
+    fn optimize(&self, node: Node) -> Result<Node> {
+        node.transform(
+            &|n| match n {
+                Node::Projection { source, expressions } => {
+                    let exprs = expressions.iter().map(|(e, _)| e.clone()).collect();
+                    let source = Box::new(self.convert(*source, exprs)?);
+                    Ok(Node::Projection { source, expressions })
+                }
+                n => Ok(n),
+            },
+            &Ok,
+        )
+    }
+}
+
+/// Returns the columns that a node's rows are sorted by in ascending order, along with their
+/// datatypes. Table scans are sorted by primary key, and index lookups of a single value
+/// have the same value for every index column, except FULLTEXT index lookups of a term.
+fn ordering<C: Catalog>(catalog: &C, node: &Node) -> Result<Vec<(usize, DataType)>> {
+    Ok(match node {
+        Node::Scan { table, .. } => {
+            let table = catalog.must_read_table(table)?;
+            let column = table.get_primary_key()?;
+            match is_key_ordered(&column.datatype) {
+                true => vec![(table.get_column_index(&column.name)?, column.datatype.clone())],
+                false => Vec::new(),
+            }
+        }
+        Node::IndexLookup { table, index, values, .. } if values.len() == 1 => {
+            let table = catalog.must_read_table(table)?;
+            let index = table.get_index(index)?;
+            let mut ordering = Vec::new();
+            for (i, name) in index.columns.iter().enumerate().filter(|_| !index.fulltext) {
+                if index.get_expression(i).is_some() {
+                    continue;
+                }
+                let column = table.get_column(name)?;
+                if is_index_ordered(column) {
+                    ordering.push((table.get_column_index(name)?, column.datatype.clone()));
+                }
+            }
+            ordering
+        }
+        Node::IndexOnlyScan { table, index, ranges, .. } if ranges.len() == 1 => {
+            let table = catalog.must_read_table(table)?;
+            let index = table.get_index(index)?;
+            let mut columns = vec![index.columns[0].clone()];
+            // A single key has the same value for every key column, and its rows are sorted by
+            // primary key.
+            if let (Bound::Included(a), Bound::Included(b)) = &ranges[0] {
+                if a == b && a.len() == index.columns.len() {
+                    columns = index.columns.clone();
+                    columns.push(table.get_primary_key()?.name.clone());
+                }
+            }
+            let mut ordering = Vec::new();
+            for name in columns {
+                let column = table.get_column(&name)?;
+                if is_index_ordered(column) {
+                    ordering.push((table.get_column_index(&name)?, column.datatype.clone()));
+                }
+            }
+            ordering
+        }
+        Node::IndexRangeScan { table, index, .. } => {
+            let table = catalog.must_read_table(table)?;
+            let column = table.get_column(&table.get_index(index)?.columns[0])?;
+            match is_index_ordered(column) {
+                true => vec![(table.get_column_index(&column.name)?, column.datatype.clone())],
+                false => Vec::new(),
+            }
+        }
+        Node::Distinct { source, .. }
+        | Node::Filter { source, .. }
+        | Node::Limit { source, .. }
+        | Node::Offset { source, .. } => ordering(catalog, source)?,
+        // Semi joins emit left rows in order, and lateral joins emit each left row's joined
+        // rows in turn.
+        Node::HashSemiJoin { left, .. } | Node::LateralJoin { left, .. } => {
+            ordering(catalog, left)?
+        }
+        Node::Projection { source, expressions } => {
+            let ordering = ordering(catalog, source)?;
+            let mut projected = Vec::new();
+            for (i, (expr, _)) in expressions.iter().enumerate() {
+                if let Expression::Field(f, _) = expr {
+                    if let Some((_, datatype)) = ordering.iter().find(|(o, _)| o == f) {
+                        projected.push((i, datatype.clone()));
                     }
-                    (a, b) => Ok(Node::NestedLoopJoin {
-                        left,
-                        left_size,
-                        right,
-                        predicate: Some(Expression::Equal(a.into(), b.into())),
-                        outer,
-                    }),
+                }
+            }
+            projected
+        }
+        // Merge joins emit rows in left order. For inner joins, the right join field is equal
+        // to the left one, so it's sorted too.
+        Node::MergeJoin { left, left_field, right_field, outer, .. } => {
+            let mut ordering = ordering(catalog, left)?;
+            let width = cost::Estimator::new(catalog).width(left)?;
+            let datatype = ordering.iter().find(|(i, _)| *i == left_field.0).map(|(_, t)| t);
+            if let (false, Some(width), Some(datatype)) = (outer, width, datatype) {
+                ordering.push((width + right_field.0, datatype.clone()));
+            }
+            ordering
+        }
+        _ => Vec::new(),
+    })
+}
+
+/// An order elimination optimizer, which removes sorts when their input is already sorted by the
+/// order keys, e.g. a table scan ordered by primary key or an index range scan ordered by the
+/// index column. Descending orders are eliminated by scanning the table in reverse.
+pub struct OrderElimination<'a, C: Catalog> {
+    catalog: &'a C,
+}
+
+impl<'a, C: Catalog> OrderElimination<'a, C> {
+    pub fn new(catalog: &'a C) -> Self {
+        Self { catalog }
+    }
+
+    /// Returns true if the node is a table scan, possibly filtered or projected, such that its
+    /// order can be reversed by scanning the table in reverse.
+    fn reversible(node: &Node) -> bool {
+        match node {
+            Node::Filter { source, .. } | Node::Projection { source, .. } => {
+                Self::reversible(source)
+            }
+            Node::Scan { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Reverses the order of a reversible node, see reversible().
+    fn reverse(node: Node) -> Node {
+        match node {
+            Node::Filter { source, predicate } => {
+                Node::Filter { source: Box::new(Self::reverse(*source)), predicate }
+            }
+            Node::Projection { source, expressions } => {
+                Node::Projection { source: Box::new(Self::reverse(*source)), expressions }
+            }
+            Node::Scan { table, alias, filter, columns } => {
+                Node::ReverseScan { table, alias, filter, columns }
+            }
+            node => node,
+        }
+    }
+}
+
+impl<'a, C: Catalog> Optimizer for OrderElimination<'a, C> {
+    fn optimize(&self, node: Node) -> Result<Node> {
+        node.transform(&Ok, &|n| match n {
+            Node::Order { source, orders, memory } => {
+                // The input is sorted by all order keys if each key is individually sorted, with
+                // NULLs sorting first like in keys.
+                let ordering = ordering(self.catalog, &source)?;
+                let sorted = |direction: Direction| {
+                    orders.iter().all(|(expr, d, nulls)| {
+                        *d == direction
+                            && *nulls == NullOrder::default_for(d)
+                            && matches!(expr, Expression::Field(i, _)
+                                if ordering.iter().any(|(o, _)| o == i))
+                    })
+                };
+                if sorted(Direction::Ascending) {
+                    return Ok(*source);
+                }
+                if sorted(Direction::Descending) && Self::reversible(&source) {
+                    return Ok(Self::reverse(*source));
+                }
+                Ok(Node::Order { source, orders, memory })
+            }
+            n => Ok(n),
+        })
+    }
+}
+
+/// A common subexpression eliminator, which evaluates expensive expressions that are used more than
+/// once in a projection (and a filter below it) only once per row, by computing them in an
+/// intermediate projection and referencing its columns instead. Sort and filter expressions above a
+/// projection also reuse any expensive expressions computed by it.
+pub struct SubexpressionEliminator<'a, C: Catalog> {
+    catalog: &'a C,
+}
+
+impl<'a, C: Catalog> SubexpressionEliminator<'a, C> {
+    pub fn new(catalog: &'a C) -> Self {
+        Self { catalog }
+    }
+
+    /// Returns true if the expression is expensive enough to be worth evaluating only once, i.e.
+    /// a function call, LIKE match, or JSON extraction. Volatile functions must be evaluated for
+    /// every occurrence.
+    fn is_expensive(expr: &Expression) -> bool {
+        matches!(
+            expr,
+            Expression::Function(_, _)
+                | Expression::Like(_, _)
+                | Expression::Match(_, _)
+                | Expression::Relevance(_, _)
+                | Expression::JsonExtract(_, _)
+                | Expression::JsonExtractText(_, _)
+        ) && !expr.contains(&|e| matches!(e, Expression::Function(f, _) if f.is_volatile()))
+    }
+
+    /// Extracts expensive subexpressions that occur more than once in the given expressions, and at
+    /// least once in the first required expressions, replacing them with field references to the
+    /// columns following the source's columns. Returns the extracted expressions, where larger
+    /// subexpressions are extracted first.
+    fn extract(
+        exprs: &mut [&mut Expression],
+        required: usize,
+        width: usize,
+    ) -> Result<Vec<Expression>> {
+        let mut extracted = Vec::new();
+        loop {
+            let subexprs = std::cell::RefCell::new(Vec::new());
+            let mut candidates = 0;
+            for (i, expr) in exprs.iter().enumerate() {
+                expr.walk(&|e| {
+                    subexprs.borrow_mut().push(e.clone());
+                    true
+                });
+                if i + 1 == required {
+                    candidates = subexprs.borrow().len();
+                }
+            }
+            let subexprs = subexprs.into_inner();
+            let Some(common) = subexprs[..candidates]
+                .iter()
+                .find(|e| Self::is_expensive(e) && subexprs.iter().filter(|s| s == e).count() > 1)
+            else {
+                return Ok(extracted);
+            };
+            let field = Expression::Field(width + extracted.len(), None);
+            for expr in exprs.iter_mut() {
+                let e = replace(*expr, Expression::Constant(Value::Null));
+                **expr = e.transform(
+                    &|e| match e == *common {
+                        true => Ok(field.clone()),
+                        false => Ok(e),
+                    },
+                    &Ok,
+                )?;
+            }
+            extracted.push(common.clone());
+        }
+    }
+
+    /// Builds an intermediate projection of the source columns referenced by the given expressions,
+    /// followed by the extracted expressions, and remaps the expressions' field references to it.
+    fn project(
+        source: Node,
+        width: usize,
+        extracted: Vec<Expression>,
+        exprs: &mut [&mut Expression],
+    ) -> Result<Node> {
+        let referenced = std::cell::RefCell::new(vec![None; width]);
+        for expr in exprs.iter() {
+            expr.walk(&|e| {
+                if let Expression::Field(i, label) = e {
+                    if let Some(r) = referenced.borrow_mut().get_mut(*i) {
+                        *r = Some(label.clone());
+                    }
+                }
+                true
+            });
+        }
+        let mut expressions = Vec::new();
+        let mut map = Vec::new();
+        for (i, label) in referenced.into_inner().into_iter().enumerate() {
+            map.push(expressions.len());
+            if let Some(label) = label {
+                expressions.push((Expression::Field(i, label), None));
+            }
+        }
+        map.extend((0..extracted.len()).map(|i| expressions.len() + i));
+        expressions.extend(extracted.into_iter().map(|e| (e, None)));
+
+        for expr in exprs.iter_mut() {
+            let e = replace(*expr, Expression::Constant(Value::Null));
+            **expr = e.transform(&Ok, &|e| match e {
+                Expression::Field(i, label) => Ok(Expression::Field(map[i], label)),
+                e => Ok(e),
+            })?;
+        }
+        Ok(Node::Projection { source: Box::new(source), expressions })
+    }
+
+    /// Extracts common subexpressions in a projection of the given source node. If the source is a
+    /// filter, subexpressions shared with its predicate are computed below the filter. Others are
+    /// computed above it, to avoid evaluating them for rows that are filtered out.
+    fn eliminate(
+        &self,
+        source: Node,
+        mut expressions: Vec<(Expression, Option<String>)>,
+    ) -> Result<Node> {
+        let estimator = cost::Estimator::new(self.catalog);
+        let source = match source {
+            Node::Filter { source, mut predicate } => {
+                if let Some(width) = estimator.width(&source)? {
+                    let mut exprs = vec![&mut predicate];
+                    exprs.extend(expressions.iter_mut().map(|(e, _)| e));
+                    let extracted = Self::extract(&mut exprs, 1, width)?;
+                    if !extracted.is_empty() {
+                        let source = Self::project(*source, width, extracted, &mut exprs)?;
+                        return self.eliminate(
+                            Node::Filter { source: Box::new(source), predicate },
+                            expressions,
+                        );
+                    }
+                }
+                Node::Filter { source, predicate }
+            }
+            source => source,
+        };
+        let Some(width) = estimator.width(&source)? else {
+            return Ok(Node::Projection { source: Box::new(source), expressions });
+        };
+        let mut exprs: Vec<_> = expressions.iter_mut().map(|(e, _)| e).collect();
+        let required = exprs.len();
+        let extracted = Self::extract(&mut exprs, required, width)?;
+        let source = match extracted.is_empty() {
+            true => source,
+            false => Self::project(source, width, extracted, &mut exprs)?,
+        };
+        Ok(Node::Projection { source: Box::new(source), expressions })
+    }
+
+    /// Replaces subexpressions of an expression evaluated on a projection's output with references
+    /// to the projection's columns, when they compute the same expensive expression.
+    fn reuse(expr: Expression, projection: &[(Expression, Option<String>)]) -> Result<Expression> {
+        expr.transform(
+            &|e| {
+                // Inline the projected expressions, to compare with the projection's expressions.
+                let inlined = e.clone().transform(&Ok, &|e| match e {
+                    Expression::Field(i, _) if i < projection.len() => Ok(projection[i].0.clone()),
+                    e => Ok(e),
+                })?;
+                for (i, (expr, label)) in projection.iter().enumerate() {
+                    if *expr == inlined && Self::is_expensive(expr) {
+                        return Ok(Expression::Field(i, label.clone().map(|l| (None, l))));
+                    }
+                }
+                Ok(e)
+            },
+            &Ok,
+        )
+    }
+}
+
+impl<'a, C: Catalog> Optimizer for SubexpressionEliminator<'a, C> {
+    fn optimize(&self, node: Node) -> Result<Node> {
+        node.transform(
+            // While descending the node tree, reuse projected expressions in sorts and filters.
+            &|n| match n {
+                Node::Order { source, orders, memory } => match *source {
+                    Node::Projection { source, expressions } => {
+                        let orders = orders
+                            .into_iter()
+                            .map(|(e, d, n)| Ok((Self::reuse(e, &expressions)?, d, n)))
+                            .collect::<Result<_>>()?;
+                        let source = Box::new(Node::Projection { source, expressions });
+                        Ok(Node::Order { source, orders, memory })
+                    }
+                    source => Ok(Node::Order { source: Box::new(source), orders, memory }),
                 },
+                Node::Filter { source, predicate } => match *source {
+                    Node::Projection { source, expressions } => {
+                        let predicate = Self::reuse(predicate, &expressions)?;
+                        let source = Box::new(Node::Projection { source, expressions });
+                        Ok(Node::Filter { source, predicate })
+                    }
+                    source => Ok(Node::Filter { source: Box::new(source), predicate }),
+                },
+                n => Ok(n),
+            },
+            // While ascending the node tree, extract common subexpressions in projections.
+            &|n| match n {
+                Node::Projection { source, expressions } => self.eliminate(*source, expressions),
+                n => Ok(n),
+            },
+        )
+    }
+}
+
+/// A limit pushdown optimizer, which moves limits below projections and offsets, such that they
+/// end up directly above the scan or sort producing the rows. The executors of scans and sorts
+/// below a limit stop after producing enough rows, and sorts only keep that many rows in memory.
+pub struct LimitPushdown;
+
+impl LimitPushdown {
+    /// Pushes a limit down into the given node, returning the limited node.
+    fn push(node: Node, limit: u64) -> Node {
+        match node {
+            Node::Projection { source, expressions } => {
+                Node::Projection { source: Box::new(Self::push(*source, limit)), expressions }
+            }
+            // An offset needs the first limit+offset rows of its source.
+            Node::Offset { source, offset } => Node::Offset {
+                source: Box::new(Self::push(*source, limit.saturating_add(offset))),
+                offset,
+            },
+            node => Node::Limit { source: Box::new(node), limit, ties: Vec::new() },
+        }
+    }
+}
+
+impl Optimizer for LimitPushdown {
+    fn optimize(&self, node: Node) -> Result<Node> {
+        node.transform(&Ok, &|n| match n {
+            // WITH TIES limits evaluate the tie expressions against their source rows, so they
+            // can't be moved below projections.
+            Node::Limit { source, limit, ties } if ties.is_empty() => {
+                Ok(Self::push(*source, limit))
+            }
+            n => Ok(n),
+        })
+    }
+}
+
+/// A column pruning optimizer, which passes the columns referenced by a projection down to the
+/// table scans below it, such that the scans only decode these columns from the stored rows. As
+/// with IndexOnly, only the nodes between a projection and the scan are considered. Joins split
+/// the referenced columns between their inputs.
+pub struct ColumnPruning<'a, C: Catalog> {
+    catalog: &'a C,
+}
+
+impl<'a, C: Catalog> ColumnPruning<'a, C> {
+    pub fn new(catalog: &'a C) -> Self {
+        Self { catalog }
+    }
+
+    /// Prunes the columns of table scans below a projection, given the columns referenced by the
+    /// nodes above.
+    fn prune(&self, node: Node, mut columns: Vec<usize>) -> Result<Node> {
+        Ok(match node {
+            Node::Filter { source, predicate } => {
+                columns.extend(predicate.fields());
+                Node::Filter { source: Box::new(self.prune(*source, columns)?), predicate }
+            }
+            Node::Order { source, orders, memory } => {
+                columns.extend(orders.iter().flat_map(|(e, _, _)| e.fields()));
+                Node::Order { source: Box::new(self.prune(*source, columns)?), orders, memory }
+            }
+            Node::Limit { source, limit, ties } => {
+                columns.extend(ties.iter().flat_map(|e| e.fields()));
+                Node::Limit { source: Box::new(self.prune(*source, columns)?), limit, ties }
+            }
+            Node::Offset { source, offset } => {
+                Node::Offset { source: Box::new(self.prune(*source, columns)?), offset }
+            }
+            Node::HashSemiJoin { left, keys, right, anti, null_aware } => {
+                columns.extend(keys.iter().flat_map(|e| e.fields()));
+                let left = Box::new(self.prune(*left, columns)?);
+                Node::HashSemiJoin { left, keys, right, anti, null_aware }
+            }
+            // Plain DISTINCT compares entire rows, including unreferenced columns.
+            Node::Distinct { source, on } if !on.is_empty() => {
+                columns.extend(on.iter().flat_map(|e| e.fields()));
+                Node::Distinct { source: Box::new(self.prune(*source, columns)?), on }
+            }
+            Node::NestedLoopJoin { left, left_size, right, predicate, outer } => {
+                columns.extend(predicate.iter().flat_map(|e| e.fields()));
+                let (l, r) = Self::split(columns, left_size);
+                let (left, right) = (self.prune(*left, l)?, self.prune(*right, r)?);
+                let (left, right) = (Box::new(left), Box::new(right));
+                Node::NestedLoopJoin { left, left_size, right, predicate, outer }
+            }
+            Node::HashJoin { left, left_field, right, right_field, outer } => {
+                let Some(left_size) = cost::Estimator::new(self.catalog).width(&left)? else {
+                    return Ok(Node::HashJoin { left, left_field, right, right_field, outer });
+                };
+                columns.extend([left_field.0, left_size + right_field.0]);
+                let (l, r) = Self::split(columns, left_size);
+                let (left, right) = (self.prune(*left, l)?, self.prune(*right, r)?);
+                let (left, right) = (Box::new(left), Box::new(right));
+                Node::HashJoin { left, left_field, right, right_field, outer }
+            }
+            Node::MergeJoin { left, left_field, right, right_field, outer } => {
+                let Some(left_size) = cost::Estimator::new(self.catalog).width(&left)? else {
+                    return Ok(Node::MergeJoin { left, left_field, right, right_field, outer });
+                };
+                columns.extend([left_field.0, left_size + right_field.0]);
+                let (l, r) = Self::split(columns, left_size);
+                let (left, right) = (self.prune(*left, l)?, self.prune(*right, r)?);
+                let (left, right) = (Box::new(left), Box::new(right));
+                Node::MergeJoin { left, left_field, right, right_field, outer }
+            }
+            Node::Scan { table, alias, filter, columns: _ } => {
+                let columns = self.columns(&table, columns)?;
+                Node::Scan { table, alias, filter, columns }
+            }
+            Node::ReverseScan { table, alias, filter, columns: _ } => {
+                let columns = self.columns(&table, columns)?;
+                Node::ReverseScan { table, alias, filter, columns }
+            }
+            node => node,
+        })
+    }
+
+    /// Splits referenced join columns into the left and right input columns.
+    fn split(columns: Vec<usize>, left_size: usize) -> (Vec<usize>, Vec<usize>) {
+        let (left, right): (Vec<_>, Vec<_>) = columns.into_iter().partition(|c| *c < left_size);
+        (left, right.into_iter().map(|c| c - left_size).collect())
+    }
+
+    /// Returns the sorted columns to decode for a table scan, or None if all columns are
+    /// referenced.
+    fn columns(&self, table: &str, mut columns: Vec<usize>) -> Result<Option<Vec<usize>>> {
+        columns.sort();
+        columns.dedup();
+        let width = self.catalog.must_read_table(table)?.columns.len();
+        Ok(Some(columns).filter(|c| c.len() < width))
+    }
+}
+
+impl<'a, C: Catalog> Optimizer for ColumnPruning<'a, C> {
+    fn optimize(&self, node: Node) -> Result<Node> {
+        node.transform(
+            &|n| match n {
+                Node::Projection { source, expressions } => {
+                    let columns = expressions.iter().flat_map(|(e, _)| e.fields()).collect();
+                    let source = Box::new(self.prune(*source, columns)?);
+                    Ok(Node::Projection { source, expressions })
+                }
                 n => Ok(n),
             },
             &Ok,
         )
     }
 }
+
+/// A parallel scan optimizer, which converts table scans into parallel scans with the given number
+/// of workers. Scans below a limit are kept, since they stop reading after enough rows. Only used
+/// for read-only transactions, see Plan::parallelize().
+pub struct ParallelScan {
+    workers: usize,
+}
+
+impl ParallelScan {
+    pub fn new(workers: usize) -> Self {
+        Self { workers }
+    }
+}
+
+impl Optimizer for ParallelScan {
+    fn optimize(&self, node: Node) -> Result<Node> {
+        node.transform(&Ok, &|n| match n {
+            Node::Scan { table, alias, filter, columns } => {
+                Ok(Node::ParallelScan { table, alias, filter, columns, workers: self.workers })
+            }
+            // The limit is only passed to the scan without ties, see Executor::build_node().
+            Node::Limit { source, limit, ties } if ties.is_empty() => {
+                let source = match *source {
+                    Node::ParallelScan { table, alias, filter, columns, .. } => {
+                        Node::Scan { table, alias, filter, columns }
+                    }
+                    source => source,
+                };
+                Ok(Node::Limit { source: Box::new(source), limit, ties })
+            }
+            n => Ok(n),
+        })
+    }
+}
+
+/// Returns true if the keycode encoding of a datatype's values, as used for primary and index
+/// keys, sorts in the same order as the values themselves. Dates and times are encoded as i64
+/// days or microseconds, which sort chronologically.
+fn is_key_ordered(datatype: &DataType) -> bool {
+    matches!(
+        datatype,
+        DataType::Boolean
+            | DataType::Integer
+            | DataType::String
+            | DataType::Date
+            | DataType::Time
+            | DataType::Timestamp
+    )
+}
+
+/// Returns true if a column's index entries sort in the same order as its values, i.e. its
+/// datatype is key-ordered and it isn't collated. Collated columns sort by collation key.
+fn is_index_ordered(column: &Column) -> bool {
+    is_key_ordered(&column.datatype) && column.collation == Collation::Binary
+}
+
+/// Returns true if a range can be used to scan keys of the given datatype, i.e. the datatype is
+/// key-ordered and the range bounds are values of that datatype.
+fn is_key_range(range: &(Bound<Value>, Bound<Value>), datatype: &DataType) -> bool {
+    let typed = |bound: &Bound<Value>| match bound {
+        Bound::Included(v) | Bound::Excluded(v) => v.datatype().as_ref() == Some(datatype),
+        Bound::Unbounded => true,
+    };
+    is_key_ordered(datatype) && typed(&range.0) && typed(&range.1)
+}
+
+/// The distinct key expressions of a table's indexes, which are matched against equivalent filter
+/// expressions regardless of field labels. Each is assigned a key field following the table's
+/// columns, which equivalent expressions are replaced by, such that they can be looked up like
+/// columns.
+struct KeyExpressions {
+    width: usize,
+    keys: Vec<Expression>,
+}
+
+impl KeyExpressions {
+    fn new(table: &Table, indexes: &[Index]) -> Result<Self> {
+        let mut keys = Vec::new();
+        for expr in indexes.iter().flat_map(|i| i.expressions.iter().flatten()) {
+            let expr = unlabeled(expr.clone())?;
+            if !keys.contains(&expr) {
+                keys.push(expr);
+            }
+        }
+        Ok(Self { width: table.columns.len(), keys })
+    }
+
+    /// Returns the field of an index key, i.e. the column index or key field.
+    fn field(&self, table: &Table, index: &Index, i: usize) -> Result<usize> {
+        let Some(expr) = index.get_expression(i) else {
+            return table.get_column_index(&index.columns[i]);
+        };
+        let expr = unlabeled(expr.clone())?;
+        Ok(self.width + self.keys.iter().position(|k| *k == expr).expect("key expression"))
+    }
+
+    /// Replaces key expressions in an expression with their key fields.
+    fn replace(&self, expr: Expression) -> Result<Expression> {
+        if self.keys.is_empty() {
+            return Ok(expr);
+        }
+        expr.transform(
+            &|e| {
+                let key = unlabeled(e.clone())?;
+                Ok(match self.keys.iter().position(|k| *k == key) {
+                    Some(k) => Expression::Field(self.width + k, None),
+                    None => e,
+                })
+            },
+            &Ok,
+        )
+    }
+}
+
+/// Removes the labels of an expression's fields.
+fn unlabeled(expr: Expression) -> Result<Expression> {
+    expr.transform(&Ok, &|e| match e {
+        Expression::Field(i, _) => Ok(Expression::Field(i, None)),
+        e => Ok(e),
+    })
+}
+
+/// A hash join field, as a field index and label.
+type JoinField = (usize, Option<(Option<String>, String)>);
+
+/// Returns the left and right join fields of an equality between a field of the left source and
+/// a field of the right source, given the number of left columns, with the right field index
+/// relative to the right source.
+fn equijoin(expr: &Expression, left_size: usize) -> Option<(JoinField, JoinField)> {
+    let Expression::Equal(lhs, rhs) = expr else {
+        return None;
+    };
+    let (Expression::Field(a, a_label), Expression::Field(b, b_label)) = (&**lhs, &**rhs) else {
+        return None;
+    };
+    match (*a < left_size, *b < left_size) {
+        (true, false) => Some(((*a, a_label.clone()), (*b - left_size, b_label.clone()))),
+        (false, true) => Some(((*b, b_label.clone()), (*a - left_size, a_label.clone()))),
+        _ => None,
+    }
+}
@@ -1,265 +1,1372 @@
-use super::super::parser::ast;
-use super::super::schema::{Catalog, Column, Table};
-use super::super::types::{Expression, Value};
-use super::{Aggregate, Direction, Node, Plan};
+use super::super::parser::{ast, Parser};
+use super::super::schema::{
+    qualify_name, split_name, Catalog, Column, ColumnChange, EnumType, Index, InformationSchema,
+    Sequence, Table, Trigger, TriggerEvent, TriggerTiming, User, View, DEFAULT_DATABASE,
+};
+use super::super::types::{Collation, DataType, Expression, Value};
+use super::{
+    Aggregate, ConflictKey, Direction, Node, NullOrder, OnConflict, Plan, Returning, SetOperator,
+    TriggerAction,
+};
 use crate::error::{Error, Result};
 
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
-use std::mem::replace;
+use std::mem::{replace, take};
 
 /// A query plan builder.
 pub struct Planner<'a, C: Catalog> {
     catalog: &'a mut C,
+    // Common table expressions visible to the query currently being built, in definition order.
+    ctes: Vec<ast::CommonTableExpression>,
+    // Parameter values, for statements parsed by Parser::parameterized().
+    parameters: &'a [Value],
+    // Parameters whose values were used during planning, e.g. as LIMIT counts.
+    pinned: RefCell<HashSet<usize>>,
+    // Optimizer hints given in any SELECT of the statement.
+    hints: Vec<ast::Hint>,
+    // If true, string literals aren't implicitly converted, see coerce().
+    strict_types: bool,
+    // The current database, in which unqualified names are resolved.
+    database: String,
+    // The scope of the FROM items preceding a LATERAL item being built, whose
+    // columns the item can reference as outer fields.
+    lateral: Option<Scope>,
 }
 
 impl<'a, C: Catalog> Planner<'a, C> {
     /// Creates a new planner.
     pub fn new(catalog: &'a mut C) -> Self {
-        Self { catalog }
+        Self {
+            catalog,
+            ctes: Vec::new(),
+            parameters: &[],
+            pinned: RefCell::default(),
+            hints: Vec::new(),
+            strict_types: false,
+            database: DEFAULT_DATABASE.to_string(),
+            lateral: None,
+        }
+    }
+
+    /// Sets the current database, in which unqualified table, view and index
+    /// names are resolved, see Catalog::resolve_table().
+    pub fn with_database(mut self, database: &str) -> Self {
+        self.database = database.to_string();
+        self
+    }
+
+    /// Sets the parameter values for a parameterized statement. Parameters
+    /// are built as placeholders, except where their values are needed
+    /// during planning.
+    pub fn with_parameters(mut self, parameters: &'a [Value]) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Disables implicit conversion of string literals, which must be cast
+    /// explicitly instead, see coerce().
+    pub fn with_strict_types(mut self, strict_types: bool) -> Self {
+        self.strict_types = strict_types;
+        self
     }
 
     /// Builds a plan for an AST statement.
     pub fn build(&mut self, statement: ast::Statement) -> Result<Plan> {
-        Ok(Plan(self.build_statement(statement)?))
+        let node = self.build_statement(statement)?;
+        Ok(Plan(node, take(&mut self.hints)))
+    }
+
+    /// Returns the parameters whose values were used while building plans,
+    /// in order. The plans are only valid for these parameter values.
+    pub fn pinned(&self) -> Vec<usize> {
+        let mut pinned: Vec<usize> = self.pinned.borrow().iter().copied().collect();
+        pinned.sort();
+        pinned
     }
 
     /// Builds a plan node for a statement.
-    fn build_statement(&self, statement: ast::Statement) -> Result<Node> {
+    fn build_statement(&mut self, statement: ast::Statement) -> Result<Node> {
         Ok(match statement {
-            // Transaction control and explain statements should have been handled by session.
-            ast::Statement::Begin { .. } | ast::Statement::Commit | ast::Statement::Rollback => {
+            // Transaction control, settings, COPY and explain statements are handled by the
+            // session.
+            ast::Statement::Begin { .. }
+            | ast::Statement::Commit
+            | ast::Statement::Rollback
+            | ast::Statement::Savepoint { .. }
+            | ast::Statement::RollbackToSavepoint { .. }
+            | ast::Statement::ReleaseSavepoint { .. }
+            | ast::Statement::Set { .. }
+            | ast::Statement::ShowSetting { .. }
+            | ast::Statement::ShowAll
+            | ast::Statement::ShowQueries
+            | ast::Statement::Kill { .. }
+            | ast::Statement::ShowDatabases
+            | ast::Statement::Use { .. }
+            | ast::Statement::CopyFrom { .. }
+            | ast::Statement::CopyTo { .. } => {
                 return Err(Error::Internal(format!(
-                    "Unexpected transaction statement {:?}",
+                    "Unexpected session statement {:?}",
                     statement
                 )))
             }
 
-            ast::Statement::Explain(_) => {
+            ast::Statement::Explain { .. } => {
                 return Err(Error::Internal("Unexpected explain statement".into()))
             }
 
+            ast::Statement::AsOf { .. } => {
+                return Err(Error::Value("AS OF SYSTEM TIME can't be used here".into()))
+            }
+
             // DDL statements (schema changes).
-            ast::Statement::CreateTable { name, columns } => Node::CreateTable {
-                schema: Table::new(
-                    name,
+            ast::Statement::CreateDatabase { name } => Node::CreateDatabase { database: name },
+
+            ast::Statement::CreateTable { name, mut columns, unique, temporary } => {
+                let name = self.resolve_new_table(name, temporary)?;
+                for column in columns.iter_mut() {
+                    column.datatype = self.resolve_datatype(column.datatype.clone())?;
+                }
+                // Generated columns can refer to the table's other columns.
+                let mut scope = Scope::new();
+                scope.add_relation(
+                    split_name(&name).1.to_string(),
                     columns
-                        .into_iter()
-                        .map(|c| {
-                            let nullable = c.nullable.unwrap_or(!c.primary_key);
-                            let default = match c.default {
-                                Some(expr) => Some(self.evaluate_constant(expr)?),
-                                None if nullable => Some(Value::Null),
-                                None => None,
-                            };
-                            Ok(Column {
-                                name: c.name,
-                                datatype: c.datatype,
-                                primary_key: c.primary_key,
-                                nullable,
-                                default,
-                                index: c.index && !c.primary_key,
-                                unique: c.unique || c.primary_key,
-                                references: c.references,
-                            })
-                        })
-                        .collect::<Result<_>>()?,
-                )?,
-            },
+                        .iter()
+                        .map(|c| (Some(c.name.clone()), Some(c.datatype.clone())))
+                        .collect(),
+                )?;
+                let columns = columns
+                    .into_iter()
+                    .map(|c| self.build_column(c, &mut scope))
+                    .collect::<Result<_>>()?;
+                let mut schema = Table::new(name, columns)?;
+                // UNIQUE constraints are backed by unique indexes, named like in Postgres.
+                for columns in unique {
+                    schema.indexes.push(Index {
+                        name: format!("{}_{}_key", schema.name, columns.join("_")),
+                        columns,
+                        expressions: Vec::new(),
+                        unique: true,
+                        include: Vec::new(),
+                        building: false,
+                        deferred: false,
+                        fulltext: false,
+                    });
+                }
+                schema.temporary = temporary;
+                Node::CreateTable { schema }
+            }
+
+            ast::Statement::CreateTableAs { name, query, temporary } => {
+                let name = self.resolve_new_table(name, temporary)?;
+                let (source, scope) = self.build_query(*query)?;
+                let mut columns: Vec<String> = Vec::new();
+                for i in 0..scope.len() {
+                    let Some((_, column)) = scope.get_label(i)? else {
+                        return Err(Error::Value(format!(
+                            "Column {} of table {} has no name, use AS to name it",
+                            i + 1,
+                            name
+                        )));
+                    };
+                    if columns.contains(&column) {
+                        return Err(Error::Value(format!(
+                            "Duplicate column {} in table {}",
+                            column, name
+                        )));
+                    }
+                    columns.push(column);
+                }
+                Node::CreateTableAs { table: name, columns, temporary, source: Box::new(source) }
+            }
 
             ast::Statement::DropTable { name, if_exists } => {
-                Node::DropTable { table: name, if_exists }
+                Node::DropTable { table: self.resolve_table(&name)?, if_exists }
+            }
+
+            ast::Statement::AddColumn { table, mut column } => {
+                column.datatype = self.resolve_datatype(column.datatype.clone())?;
+                let table = self.catalog.must_read_table(&self.resolve_table(&table)?)?;
+                let column = self.build_column(column, &mut Scope::from_table(table.clone())?)?;
+                Node::AddColumn { table: table.name, column }
+            }
+
+            ast::Statement::DropColumn { table, column } => {
+                Node::DropColumn { table: self.resolve_table(&table)?, column }
+            }
+
+            ast::Statement::AlterColumn { table, column, mut change } => {
+                if let ColumnChange::Type(datatype) = &mut change {
+                    *datatype = self.resolve_datatype(datatype.clone())?;
+                }
+                Node::AlterColumn { table: self.resolve_table(&table)?, column, change }
+            }
+
+            // Indexes belong to the database of their table.
+            ast::Statement::CreateIndex { name, table, columns, unique, fulltext, include } => {
+                let table = self.resolve_table(&table)?;
+                let mut index = self.build_index(&table, name, columns, unique, include)?;
+                index.fulltext = fulltext;
+                Node::CreateIndex { table, index }
+            }
+
+            ast::Statement::DropIndex { name, if_exists } => Node::DropIndex {
+                index: self.catalog.resolve_index(&name, &self.database)?,
+                if_exists,
+            },
+
+            // The view's query is resolved in the view's database, as when it's expanded.
+            ast::Statement::CreateView { name, columns, query, sql } => {
+                let name = qualify_name(&name, &self.database);
+                let database = replace(&mut self.database, split_name(&name).0.to_string());
+                let result = self.build_query(*query);
+                self.database = database;
+                // Build the query to check that it's valid, but only store its SQL text.
+                let (node, scope) = result?;
+                Self::label_columns(node, &scope, &format!("View {}", name), columns.clone())?;
+                Node::CreateView { view: View { name, columns, query: sql, owner: None } }
+            }
+
+            ast::Statement::DropView { name, if_exists } => {
+                Node::DropView { view: qualify_name(&name, &self.database), if_exists }
+            }
+
+            // Like Postgres, descending sequences start at -1 by default.
+            ast::Statement::CreateSequence { name, start, increment } => {
+                let increment = increment.unwrap_or(1);
+                let start = start.unwrap_or(if increment < 0 { -1 } else { 1 });
+                let name = qualify_name(&name, &self.database);
+                Node::CreateSequence { sequence: Sequence::new(name, start, increment)? }
+            }
+
+            ast::Statement::DropSequence { name, if_exists } => {
+                Node::DropSequence { sequence: qualify_name(&name, &self.database), if_exists }
+            }
+
+            ast::Statement::CreateType { name, labels } => {
+                let name = qualify_name(&name, &self.database);
+                Node::CreateType { enum_type: EnumType::new(name, labels)? }
+            }
+
+            ast::Statement::DropType { name, if_exists } => {
+                Node::DropType { name: qualify_name(&name, &self.database), if_exists }
+            }
+
+            ast::Statement::CreateUser { name, password } => {
+                Node::CreateUser { user: User::new(name, password.as_deref())? }
+            }
+
+            ast::Statement::AlterUser { name, password } => {
+                Node::AlterUser { user: User::new(name, password.as_deref())? }
+            }
+
+            ast::Statement::DropUser { name, if_exists } => Node::DropUser { name, if_exists },
+
+            ast::Statement::CreateRole { name } => Node::CreateRole { role: User::new_role(name)? },
+
+            ast::Statement::Grant { privileges, table, grantee } => Node::Grant {
+                table: self.catalog.must_read_table(&self.resolve_table(&table)?)?.name,
+                grantee,
+                privileges: privileges.into_iter().collect(),
+            },
+
+            ast::Statement::Revoke { privileges, table, grantee } => Node::Revoke {
+                table: self.catalog.must_read_table(&self.resolve_table(&table)?)?.name,
+                grantee,
+                privileges: privileges.into_iter().collect(),
+            },
+
+            ast::Statement::GrantRole { role, user } => Node::GrantRole { role, user },
+
+            ast::Statement::RevokeRole { role, user } => Node::RevokeRole { role, user },
+
+            ast::Statement::CreateTrigger { name, table, timing, event, action, sql } => {
+                let table = self.catalog.must_read_table(&self.resolve_table(&table)?)?;
+                let trigger = Trigger { name, timing, event, action: sql, owner: None };
+                // Build the action to check that it's valid, but only store its SQL text.
+                self.build_trigger(&table, &trigger, action)?;
+                Node::CreateTrigger { table: table.name, trigger }
+            }
+
+            ast::Statement::DropTrigger { name, table, if_exists } => {
+                Node::DropTrigger { table: self.resolve_table(&table)?, trigger: name, if_exists }
+            }
+
+            ast::Statement::Comment { table, column, comment } => {
+                Node::Comment { table: self.resolve_table(&table)?, column, comment }
             }
 
             // DML statements (mutations).
-            ast::Statement::Delete { table, r#where } => {
+            ast::Statement::Delete { table: name, using, r#where, returning } => {
+                let table = self.resolve_table(&name)?;
+                let returning = self.build_returning(&table, returning)?;
                 let scope = &mut Scope::from_table(self.catalog.must_read_table(&table)?)?;
                 Node::Delete {
-                    table: table.clone(),
-                    source: Box::new(Node::Scan {
-                        table,
-                        alias: None,
-                        filter: r#where.map(|e| self.build_expression(scope, e)).transpose()?,
-                    }),
+                    table,
+                    source: Box::new(self.build_mutation_source(scope, name, using, r#where)?),
+                    returning,
                 }
             }
 
-            ast::Statement::Insert { table, columns, values } => Node::Insert {
-                table,
-                columns: columns.unwrap_or_default(),
-                expressions: values
-                    .into_iter()
-                    .map(|exprs| {
-                        exprs
-                            .into_iter()
-                            .map(|expr| self.build_expression(&mut Scope::constant(), expr))
-                            .collect::<Result<_>>()
-                    })
-                    .collect::<Result<_>>()?,
-            },
+            ast::Statement::Insert { table, columns, values, on_conflict, returning } => {
+                let table = self.resolve_table(&table)?;
+                Node::Insert {
+                    on_conflict: on_conflict
+                        .map(|c| self.build_on_conflict(&table, c))
+                        .transpose()?,
+                    returning: self.build_returning(&table, returning)?,
+                    table,
+                    columns: columns.unwrap_or_default(),
+                    expressions: values
+                        .into_iter()
+                        .map(|exprs| {
+                            exprs
+                                .into_iter()
+                                .map(|expr| self.build_expression(&mut Scope::constant(), expr))
+                                .collect::<Result<_>>()
+                        })
+                        .collect::<Result<_>>()?,
+                }
+            }
 
-            ast::Statement::Update { table, set, r#where } => {
-                let scope = &mut Scope::from_table(self.catalog.must_read_table(&table)?)?;
+            ast::Statement::Update { table: name, set, from, r#where, returning } => {
+                let table = self.resolve_table(&name)?;
+                let returning = self.build_returning(&table, returning)?;
+                let schema = self.catalog.must_read_table(&table)?;
+                let scope = &mut Scope::from_table(schema.clone())?;
+                // Updated columns are resolved before joining FROM tables, since they always refer
+                // to the updated table.
+                let columns =
+                    set.keys().map(|c| scope.resolve(None, c)).collect::<Result<Vec<_>>>()?;
+                for index in &columns {
+                    Self::check_updatable(&schema, *index)?;
+                }
+                let source = self.build_mutation_source(scope, name, from, r#where)?;
                 Node::Update {
-                    table: table.clone(),
-                    source: Box::new(Node::Scan {
-                        table,
-                        alias: None,
-                        filter: r#where.map(|e| self.build_expression(scope, e)).transpose()?,
-                    }),
-                    expressions: set
+                    table,
+                    source: Box::new(source),
+                    expressions: columns
                         .into_iter()
-                        .map(|(c, e)| {
-                            Ok((
-                                scope.resolve(None, &c)?,
-                                Some(c),
-                                self.build_expression(scope, e)?,
-                            ))
-                        })
+                        .zip(set)
+                        .map(|(i, (c, e))| Ok((i, Some(c), self.build_expression(scope, e)?)))
                         .collect::<Result<_>>()?,
+                    returning,
                 }
             }
 
             // Queries.
+            statement @ (ast::Statement::Select { .. } | ast::Statement::SetOperation { .. }) => {
+                self.build_query(statement)?.0
+            }
+
+            // Schema inspection.
+            ast::Statement::ShowTables => Node::ShowTables { database: self.database.clone() },
+
+            ast::Statement::ShowCreateTable { name } => {
+                let table = self.resolve_table(&name)?;
+                Node::ShowCreateTable { table: self.catalog.must_read_table(&table)?.name }
+            }
+
+            ast::Statement::Describe { name } => {
+                let table = self.resolve_table(&name)?;
+                Node::Describe { table: self.catalog.must_read_table(&table)?.name }
+            }
+
+            // Statistics collection.
+            ast::Statement::Analyze { table: Some(table) } => {
+                let table = self.resolve_table(&table)?;
+                Node::Analyze { tables: vec![self.catalog.must_read_table(&table)?.name] }
+            }
+
+            ast::Statement::Analyze { table: None } => {
+                Node::Analyze { tables: self.catalog.scan_tables()?.map(|t| t.name).collect() }
+            }
+        })
+    }
+
+    /// Resolves a table name in the current database, see Catalog::resolve_table().
+    fn resolve_table(&self, table: &str) -> Result<String> {
+        self.catalog.resolve_table(table, &self.database)
+    }
+
+    /// Resolves the sequence name given as the first argument of a sequence
+    /// function in the current database, checking that the sequence exists.
+    /// The name must be a string literal.
+    fn resolve_sequence(&self, function: &str, name: &Expression) -> Result<String> {
+        let Expression::Constant(Value::String(name)) = name else {
+            return Err(Error::Value(format!("{} requires a sequence name", function)));
+        };
+        let name = qualify_name(name, &self.database);
+        if self.catalog.read_sequence(&name)?.is_none() {
+            return Err(Error::Value(format!("Sequence {} does not exist", name)));
+        }
+        Ok(name)
+    }
+
+    /// Resolves a user-defined type name to its enum datatype, with its labels,
+    /// including as an array element type.
+    fn resolve_datatype(&self, datatype: DataType) -> Result<DataType> {
+        if let DataType::Array(element) = datatype {
+            return Ok(DataType::Array(Box::new(self.resolve_datatype(*element)?)));
+        }
+        let DataType::Enum(name, _) = datatype else { return Ok(datatype) };
+        let name = qualify_name(&name, &self.database);
+        match self.catalog.read_type(&name)? {
+            Some(enum_type) => Ok(enum_type.datatype()),
+            None => Err(Error::Value(format!("Type {} does not exist", name))),
+        }
+    }
+
+    /// Resolves the name of a new table. Temporary tables don't belong to a database.
+    fn resolve_new_table(&self, table: String, temporary: bool) -> Result<String> {
+        match temporary {
+            true if table.contains('.') => Err(Error::Value(format!(
+                "Temporary table {} can't be qualified by a database",
+                table
+            ))),
+            true => Ok(table),
+            false => Ok(qualify_name(&table, &self.database)),
+        }
+    }
+
+    /// Builds the source rows for an UPDATE or DELETE, given the table scope. If any tables are
+    /// given via UPDATE ... FROM or DELETE ... USING, the table is joined with them and the scope is
+    /// extended with their columns. The mutated table's columns always come first in the rows.
+    fn build_mutation_source(
+        &mut self,
+        scope: &mut Scope,
+        table: String,
+        from: Vec<ast::FromItem>,
+        r#where: Option<ast::Expression>,
+    ) -> Result<Node> {
+        if from.is_empty() {
+            let filter = r#where.map(|e| self.build_predicate(scope, e)).transpose()?;
+            let table = self.resolve_table(&table)?;
+            return Ok(Node::Scan { table, alias: None, filter, columns: None });
+        }
+        let mut items = vec![ast::FromItem::Table { name: table, alias: None }];
+        items.extend(from);
+        *scope = Scope::new();
+        let mut node = self.build_from_clause(scope, items)?;
+        if let Some(expr) = r#where {
+            let predicate = self.build_predicate(scope, expr)?;
+            node = Node::Filter { source: Box::new(node), predicate };
+        }
+        Ok(node)
+    }
+
+    /// Builds the RETURNING expressions of an INSERT, UPDATE or DELETE, which are evaluated
+    /// against each affected row: the new row for INSERT and UPDATE, and the deleted row for
+    /// DELETE. RETURNING * returns all columns, and field references are labeled by column name.
+    fn build_returning(
+        &self,
+        table: &str,
+        returning: Option<ast::Returning>,
+    ) -> Result<Option<Returning>> {
+        let Some(returning) = returning else {
+            return Ok(None);
+        };
+        let table = self.catalog.must_read_table(table)?;
+        if returning.is_empty() {
+            return Ok(Some(
+                table
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        let field = (Some(split_name(&table.name).1.to_string()), c.name.clone());
+                        (Expression::Field(i, Some(field)), Some(c.name.clone()))
+                    })
+                    .collect(),
+            ));
+        }
+        let mut scope = Scope::from_table(table)?;
+        returning
+            .into_iter()
+            .map(|(expr, label)| {
+                let expr = self.build_expression(&mut scope, expr)?;
+                let label = match (label, &expr) {
+                    (Some(label), _) => Some(label),
+                    (None, Expression::Field(_, Some((_, name)))) => Some(name.clone()),
+                    (None, _) => None,
+                };
+                Ok((expr, label))
+            })
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+
+    /// Checks that a column can be set by an UPDATE, i.e. that it isn't generated.
+    fn check_updatable(table: &Table, index: usize) -> Result<()> {
+        match &table.columns[index] {
+            column if column.generated.is_some() => {
+                Err(Error::Value(format!("Can't update generated column {}", column.name)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Builds a trigger action, resolving names in the table's database. The action refers to the
+    /// written row via the old and new tables, which SET expressions see as fields and statements
+    /// as outer fields, like the columns preceding a lateral item.
+    pub fn build_trigger(
+        &mut self,
+        table: &Table,
+        trigger: &Trigger,
+        action: ast::TriggerAction,
+    ) -> Result<TriggerAction> {
+        let mut scope = Scope::new();
+        if trigger.event != TriggerEvent::Insert {
+            scope.add_table("old".into(), table.clone())?;
+        }
+        if trigger.event != TriggerEvent::Delete {
+            scope.add_table("new".into(), table.clone())?;
+        }
+        if matches!(action, ast::TriggerAction::Set(_))
+            && (trigger.timing != TriggerTiming::Before || trigger.event == TriggerEvent::Delete)
+        {
+            return Err(Error::Value(
+                "SET can only be used by BEFORE INSERT or UPDATE triggers".into(),
+            ));
+        }
+        let database = replace(&mut self.database, split_name(&table.name).0.to_string());
+        let result = match action {
+            ast::TriggerAction::Set(set) => set
+                .into_iter()
+                .map(|(column, expr)| {
+                    let index = table.get_column_index(&column)?;
+                    Self::check_updatable(table, index)?;
+                    Ok((index, self.build_expression(&mut scope, expr)?))
+                })
+                .collect::<Result<_>>()
+                .map(TriggerAction::Set),
+            ast::TriggerAction::Statement(statement) => {
+                let lateral = self.lateral.replace(scope);
+                let result = self.build_statement(*statement);
+                self.lateral = lateral;
+                result.map(|node| TriggerAction::Execute(Box::new(node)))
+            }
+        };
+        self.database = database;
+        result
+    }
+
+    /// Builds an INSERT ... ON CONFLICT action. The conflict target must match the primary key or
+    /// a unique index (in any column order). DO UPDATE expressions can refer to both the existing
+    /// row, via the table name, and the inserted row, via the excluded table.
+    fn build_on_conflict(&self, table: &str, on_conflict: ast::OnConflict) -> Result<OnConflict> {
+        let table = self.catalog.must_read_table(table)?;
+        let mut keys = vec![(ConflictKey::PrimaryKey, vec![table.get_primary_key()?.name.clone()])];
+        for index in table.get_indexes().into_iter().filter(|i| i.unique && !i.building) {
+            keys.push((ConflictKey::Index(index.name), index.columns));
+        }
+        if !on_conflict.columns.is_empty() {
+            let mut target = on_conflict.columns.clone();
+            target.sort();
+            keys.retain(|(_, columns)| {
+                let mut columns = columns.clone();
+                columns.sort();
+                columns == target
+            });
+            if keys.is_empty() {
+                return Err(Error::Value(format!(
+                    "No primary key or unique index on table {} for columns ({})",
+                    table.name,
+                    on_conflict.columns.join(", ")
+                )));
+            }
+        } else if on_conflict.update.is_some() {
+            return Err(Error::Value("ON CONFLICT DO UPDATE requires a conflict target".into()));
+        }
+
+        let mut scope = Scope::from_table(table.clone())?;
+        scope.add_table("excluded".into(), table.clone())?;
+        let update = on_conflict
+            .update
+            .map(|set| {
+                set.into_iter()
+                    .map(|(c, e)| {
+                        let index = table.get_column_index(&c)?;
+                        Self::check_updatable(&table, index)?;
+                        Ok((index, Some(c), self.build_expression(&mut scope, e)?))
+                    })
+                    .collect::<Result<_>>()
+            })
+            .transpose()?;
+        let predicate =
+            on_conflict.r#where.map(|e| self.build_predicate(&mut scope, e)).transpose()?;
+        Ok(OnConflict { keys: keys.into_iter().map(|(k, _)| k).collect(), update, predicate })
+    }
+
+    /// Builds a column schema from an AST column specification.
+    /// Builds a column schema. Generated column expressions are built in the given scope of the
+    /// table's columns.
+    fn build_column(&mut self, column: ast::Column, scope: &mut Scope) -> Result<Column> {
+        let nullable = column.nullable.unwrap_or(!column.primary_key && !column.auto_increment);
+        // Defaults that call functions (e.g. NOW()) are evaluated for each row, others are
+        // evaluated once here.
+        let default = match column.default {
+            Some(expr) => {
+                let expr = self.build_expression(&mut Scope::constant(), expr)?;
+                if expr.contains(&|e| matches!(e, Expression::Function(..))) {
+                    Some(expr)
+                } else {
+                    // Array text literals are parsed, e.g. DEFAULT '{}'.
+                    match (expr.evaluate(None)?, &column.datatype) {
+                        (value @ Value::String(_), datatype @ DataType::Array(_)) => {
+                            Some(Expression::Constant(value.cast(datatype)?))
+                        }
+                        (value, _) => Some(Expression::Constant(value)),
+                    }
+                }
+            }
+            None if nullable && !column.auto_increment && column.generated.is_none() => {
+                Some(Expression::Constant(Value::Null))
+            }
+            None => None,
+        };
+        let generated = column.generated.map(|e| self.build_expression(scope, e)).transpose()?;
+        Ok(Column {
+            name: column.name,
+            datatype: column.datatype,
+            collation: column.collation,
+            primary_key: column.primary_key,
+            nullable,
+            default,
+            generated,
+            index: column.index && !column.primary_key,
+            auto_increment: column.auto_increment,
+            unique: column.unique || column.primary_key,
+            references: column.references.map(|r| self.resolve_table(&r)).transpose()?,
+            on_delete: column.on_delete,
+            on_update: column.on_update,
+            deferred: column.deferred,
+            comment: None,
+        })
+    }
+
+    /// Builds an index schema. Key expressions are built in the scope of the table, and unnamed
+    /// indexes are named after the table and keys like in Postgres, e.g. users_email_idx or
+    /// users_expr_idx.
+    fn build_index(
+        &mut self,
+        table: &str,
+        name: Option<String>,
+        keys: Vec<ast::Expression>,
+        unique: bool,
+        include: Vec<String>,
+    ) -> Result<Index> {
+        let mut scope = Scope::from_table(self.catalog.must_read_table(table)?)?;
+        let (mut columns, mut expressions, mut parts) = (Vec::new(), Vec::new(), Vec::new());
+        for key in keys {
+            match key {
+                ast::Expression::Field(None, column) => {
+                    parts.push(column.clone());
+                    columns.push(column);
+                    expressions.push(None);
+                }
+                expr => {
+                    let expr = self.build_expression(&mut scope, expr)?;
+                    parts.push("expr".to_string());
+                    columns.push(expr.to_string());
+                    expressions.push(Some(expr));
+                }
+            }
+        }
+        if expressions.iter().all(Option::is_none) {
+            expressions.clear();
+        }
+        let (database, table) = split_name(table);
+        let name = name.unwrap_or_else(|| format!("{}_{}_idx", table, parts.join("_")));
+        let name = qualify_name(&name, database);
+        Ok(Index {
+            name,
+            columns,
+            expressions,
+            unique,
+            include,
+            building: false,
+            deferred: false,
+            fulltext: false,
+        })
+    }
+
+    /// Builds a plan node for a SELECT query or set operation, returning it along with the scope
+    /// of its output columns. Any common table expressions in a WITH clause are visible while
+    /// building the query, and are inlined at each reference.
+    fn build_query(&mut self, mut statement: ast::Statement) -> Result<(Node, Scope)> {
+        let with = match &mut statement {
+            ast::Statement::Select { with, .. } | ast::Statement::SetOperation { with, .. } => {
+                std::mem::take(with)
+            }
+            statement => {
+                return Err(Error::Internal(format!("Unexpected query statement {:?}", statement)))
+            }
+        };
+
+        let depth = self.ctes.len();
+        for cte in with {
+            if self.ctes[depth..].iter().any(|c| c.name == cte.name) {
+                self.ctes.truncate(depth);
+                return Err(Error::Value(format!(
+                    "Duplicate common table expression {}",
+                    cte.name
+                )));
+            }
+            self.ctes.push(cte);
+        }
+        let result = match statement {
             ast::Statement::Select {
-                mut select,
+                with: _,
+                hints,
+                select,
+                distinct,
+                distinct_on,
                 from,
                 r#where,
                 group_by,
-                mut having,
-                mut order,
+                grouping_sets,
+                having,
+                order,
                 offset,
                 limit,
             } => {
-                let scope = &mut Scope::new();
+                self.hints.extend(hints);
+                self.build_select(
+                    select,
+                    distinct,
+                    distinct_on,
+                    from,
+                    r#where,
+                    group_by,
+                    grouping_sets,
+                    having,
+                    order,
+                    offset,
+                    limit,
+                )
+            }
+            ast::Statement::SetOperation {
+                with: _,
+                operator,
+                all,
+                left,
+                right,
+                order,
+                offset,
+                limit,
+            } => self.build_set_operation(operator, all, *left, *right, order, offset, limit),
+            _ => unreachable!("checked above"),
+        };
+        self.ctes.truncate(depth);
+        result
+    }
 
-                // Build FROM clause.
-                let mut node = if !from.is_empty() {
-                    self.build_from_clause(scope, from)?
-                } else if select.is_empty() {
-                    return Err(Error::Value("Can't select * without a table".into()));
-                } else {
-                    Node::Nothing
-                };
+    /// Builds a set operation, combining the rows of two queries. The output columns are named
+    /// after the left query's columns, and ORDER BY can only refer to these names.
+    #[allow(clippy::too_many_arguments)]
+    fn build_set_operation(
+        &mut self,
+        operator: ast::SetOperator,
+        all: bool,
+        left: ast::Statement,
+        right: ast::Statement,
+        order: Vec<(ast::Expression, ast::Order, Option<ast::NullOrder>)>,
+        offset: Option<ast::Expression>,
+        limit: Option<ast::Limit>,
+    ) -> Result<(Node, Scope)> {
+        let operator = match operator {
+            ast::SetOperator::Except => SetOperator::Except,
+            ast::SetOperator::Intersect => SetOperator::Intersect,
+            ast::SetOperator::Union => SetOperator::Union,
+        };
+        let (left, left_scope) = self.build_query(left)?;
+        let (right, right_scope) = self.build_query(right)?;
+        if left_scope.len() != right_scope.len() {
+            return Err(Error::Value(format!(
+                "Each {} query must have the same number of columns",
+                operator.to_string().to_uppercase()
+            )));
+        }
+        let mut scope = Scope::new();
+        for i in 0..left_scope.len() {
+            scope.add_column(None, left_scope.get_column(i)?.1, None);
+        }
+        let node =
+            Node::SetOperation { left: Box::new(left), right: Box::new(right), operator, all };
+        let (node, order_exprs) = self.build_order(&mut scope, node, order)?;
+        let node = self.build_offset_limit(node, offset, limit, order_exprs)?;
+        Ok((node, scope))
+    }
 
-                // Build WHERE clause.
-                if let Some(expr) = r#where {
-                    node = Node::Filter {
-                        source: Box::new(node),
-                        predicate: self.build_expression(scope, expr)?,
-                    };
-                };
+    /// Builds the clauses of a SELECT query. Helper for build_query().
+    #[allow(clippy::too_many_arguments)]
+    fn build_select(
+        &mut self,
+        mut select: Vec<(ast::Expression, Option<String>)>,
+        distinct_all: bool,
+        distinct_on: Vec<ast::Expression>,
+        from: Vec<ast::FromItem>,
+        r#where: Option<ast::Expression>,
+        group_by: Vec<ast::Expression>,
+        grouping_sets: Option<ast::GroupingSets>,
+        mut having: Option<ast::Expression>,
+        mut order: Vec<(ast::Expression, ast::Order, Option<ast::NullOrder>)>,
+        offset: Option<ast::Expression>,
+        limit: Option<ast::Limit>,
+    ) -> Result<(Node, Scope)> {
+        let mut scope = Scope::new();
+
+        // Build FROM clause.
+        let mut node = if !from.is_empty() {
+            self.build_from_clause(&mut scope, from)?
+        } else if select.is_empty() {
+            return Err(Error::Value("Can't select * without a table".into()));
+        } else {
+            Node::Nothing
+        };
+
+        // Build WHERE clause.
+        if let Some(expr) = r#where {
+            node = self.build_where(&mut scope, node, expr)?;
+        };
+
+        // DISTINCT ON keeps the first row for each distinct value of its expressions. These must
+        // match the leading ORDER BY expressions, such that equal values are adjacent, and
+        // default to ordering by the DISTINCT ON expressions.
+        let distinct = distinct_on.len();
+        if order.is_empty() {
+            order = distinct_on.into_iter().map(|e| (e, ast::Order::Ascending, None)).collect();
+        } else if distinct > 0
+            && (order.len() < distinct
+                || !order[..distinct].iter().all(|(e, _, _)| distinct_on.contains(e))
+                || !distinct_on.iter().all(|d| order[..distinct].iter().any(|(e, _, _)| e == d)))
+        {
+            return Err(Error::Value(
+                "DISTINCT ON expressions must match initial ORDER BY expressions".into(),
+            ));
+        }
+
+        // Build SELECT clause.
+        let mut hidden = 0;
+        if !select.is_empty() {
+            let group_by = self.resolve_group_by(group_by, &select)?;
+
+            // Inject hidden SELECT columns for fields and aggregates used in ORDER BY and
+            // HAVING expressions but not present in existing SELECT output. These will be
+            // removed again by a later projection.
+            if let Some(ref mut expr) = having {
+                hidden += self.inject_hidden(expr, &mut select, &group_by)?;
+            }
+            let mut order_hidden = 0;
+            for (expr, _, _) in order.iter_mut() {
+                order_hidden += self.inject_hidden(expr, &mut select, &group_by)?;
+            }
+            if distinct_all && order_hidden > 0 {
+                return Err(Error::Value(
+                    "For SELECT DISTINCT, ORDER BY expressions must appear in select list".into(),
+                ));
+            }
+            hidden += order_hidden;
 
-                // Build SELECT clause.
-                let mut hidden = 0;
-                if !select.is_empty() {
-                    // Inject hidden SELECT columns for fields and aggregates used in ORDER BY and
-                    // HAVING expressions but not present in existing SELECT output. These will be
-                    // removed again by a later projection.
-                    if let Some(ref mut expr) = having {
-                        hidden += self.inject_hidden(expr, &mut select)?;
+            // Extract any aggregate functions and GROUP BY expressions, replacing them with
+            // Column placeholders. Aggregations are handled by evaluating group expressions
+            // and aggregate function arguments in a pre-projection, passing the results
+            // to an aggregation node, and then evaluating the final SELECT expressions
+            // in the post-projection. For example:
+            //
+            // SELECT (MAX(rating * 100) - MIN(rating * 100)) / 100
+            // FROM movies
+            // GROUP BY released - 2000
+            //
+            // Results in the following nodes:
+            //
+            // - Projection: rating * 100, rating * 100, released - 2000
+            // - Aggregation: max(#0), min(#1) group by #2
+            // - Projection: (#0 - #1) / 100
+            let aggregates = self.extract_aggregates(&mut select)?;
+            let groups = self.extract_groups(&mut select, group_by, aggregates.len())?;
+            let grouping_sets = Self::build_grouping_sets(grouping_sets, groups.len())?;
+            if !aggregates.is_empty() || !groups.is_empty() {
+                Self::check_grouped(&select, &groups)?;
+                node =
+                    self.build_aggregation(&mut scope, node, groups, aggregates, grouping_sets)?;
+            }
+
+            // Build the remaining non-aggregate projection.
+            let expressions: Vec<(Expression, Option<String>)> = select
+                .into_iter()
+                .map(|(e, l)| Ok((self.build_expression(&mut scope, e)?, l)))
+                .collect::<Result<_>>()?;
+            scope.project(&expressions, self.parameters)?;
+            node = Node::Projection { source: Box::new(node), expressions };
+        };
+
+        // Build HAVING clause.
+        if let Some(expr) = having {
+            node = Node::Filter {
+                source: Box::new(node),
+                predicate: self.build_predicate(&mut scope, expr)?,
+            };
+        };
+
+        // Build ORDER clause.
+        let order_exprs;
+        (node, order_exprs) = self.build_order(&mut scope, node, order)?;
+
+        // Build DISTINCT ON clause, on the leading order expressions.
+        if distinct > 0 {
+            node = Node::Distinct { source: Box::new(node), on: order_exprs[..distinct].to_vec() };
+        }
+
+        // Build plain DISTINCT clause, i.e. DISTINCT ON all output columns. The rows are also
+        // ordered by these after any ORDER BY expressions, such that duplicates are adjacent. Like
+        // GROUP BY, this compares exact values regardless of collation.
+        if distinct_all {
+            let on = (0..scope.len() - hidden)
+                .map(|i| Ok(Expression::Field(i, scope.get_label(i)?)))
+                .collect::<Result<Vec<_>>>()?;
+            let (source, mut orders) = match node {
+                Node::Order { source, orders, .. } => (source, orders),
+                node => (Box::new(node), Vec::new()),
+            };
+            for expr in &on {
+                if !orders.iter().any(|(e, _, _)| e == expr) {
+                    let nulls = NullOrder::default_for(&Direction::Ascending);
+                    orders.push((expr.clone(), Direction::Ascending, nulls));
+                }
+            }
+            let source = Box::new(Node::Order { source, orders, memory: None });
+            node = Node::Distinct { source, on };
+        }
+
+        // Build OFFSET and LIMIT clauses.
+        node = self.build_offset_limit(node, offset, limit, order_exprs)?;
+
+        // Remove any hidden columns.
+        if hidden > 0 {
+            let expressions: Vec<_> =
+                (0..(scope.len() - hidden)).map(|i| (Expression::Field(i, None), None)).collect();
+            scope.project(&expressions, self.parameters)?;
+            node = Node::Projection { source: Box::new(node), expressions };
+        }
+
+        Ok((node, scope))
+    }
+
+    /// Builds a WHERE clause. Subqueries are decorrelated by executing them once and joining their
+    /// result with the source rows, rather than executing them once per row. EXISTS and IN
+    /// conditions are built as semi joins, NOT EXISTS and NOT IN as anti joins, and scalar
+    /// subqueries are left joined. Any subquery columns are removed again by a projection.
+    fn build_where(
+        &mut self,
+        scope: &mut Scope,
+        mut node: Node,
+        expr: ast::Expression,
+    ) -> Result<Node> {
+        if !expr.contains(&Self::is_subquery) {
+            let predicate = self.build_predicate(scope, expr)?;
+            return Ok(Node::Filter { source: Box::new(node), predicate });
+        }
+
+        let width = scope.len();
+        let mut predicates = Vec::new();
+        for conjunct in Self::split_conjuncts(expr) {
+            use ast::Operation::{InSubquery, Not};
+            match conjunct {
+                ast::Expression::Exists(query) => {
+                    node = self.build_semi_join(scope, node, *query, None, false)?;
+                }
+                ast::Expression::Operation(InSubquery(expr, query)) => {
+                    node = self.build_semi_join(scope, node, *query, Some(*expr), false)?;
+                }
+                ast::Expression::Operation(Not(expr)) if Self::is_subquery(&expr) => match *expr {
+                    ast::Expression::Exists(query) => {
+                        node = self.build_semi_join(scope, node, *query, None, true)?;
                     }
-                    for (expr, _) in order.iter_mut() {
-                        hidden += self.inject_hidden(expr, &mut select)?;
+                    ast::Expression::Operation(InSubquery(expr, query)) => {
+                        node = self.build_semi_join(scope, node, *query, Some(*expr), true)?;
                     }
+                    expr => predicates.push(ast::Operation::Not(Box::new(expr)).into()),
+                },
+                // Scalar subqueries can be used anywhere in the condition, and are replaced by
+                // their joined value column.
+                mut conjunct => {
+                    conjunct.transform_mut(
+                        &mut |expr| match expr {
+                            ast::Expression::Subquery(query) => {
+                                let source = replace(&mut node, Node::Nothing);
+                                let value;
+                                (node, value) =
+                                    self.build_scalar_subquery(scope, source, *query)?;
+                                Ok(ast::Expression::Column(value))
+                            }
+                            expr => Ok(expr),
+                        },
+                        &mut Ok,
+                    )?;
+                    predicates.push(conjunct);
+                }
+            }
+        }
 
-                    // Extract any aggregate functions and GROUP BY expressions, replacing them with
-                    // Column placeholders. Aggregations are handled by evaluating group expressions
-                    // and aggregate function arguments in a pre-projection, passing the results
-                    // to an aggregation node, and then evaluating the final SELECT expressions
-                    // in the post-projection. For example:
-                    //
-                    // SELECT (MAX(rating * 100) - MIN(rating * 100)) / 100
-                    // FROM movies
-                    // GROUP BY released - 2000
-                    //
-                    // Results in the following nodes:
-                    //
-                    // - Projection: rating * 100, rating * 100, released - 2000
-                    // - Aggregation: max(#0), min(#1) group by #2
-                    // - Projection: (#0 - #1) / 100
-                    let aggregates = self.extract_aggregates(&mut select)?;
-                    let groups = self.extract_groups(&mut select, group_by, aggregates.len())?;
-                    if !aggregates.is_empty() || !groups.is_empty() {
-                        node = self.build_aggregation(scope, node, groups, aggregates)?;
-                    }
+        if let Some(predicate) = Self::join_conjuncts(predicates) {
+            let predicate = self.build_predicate(scope, predicate)?;
+            node = Node::Filter { source: Box::new(node), predicate };
+        }
 
-                    // Build the remaining non-aggregate projection.
-                    let expressions: Vec<(Expression, Option<String>)> = select
-                        .into_iter()
-                        .map(|(e, l)| Ok((self.build_expression(scope, e)?, l)))
-                        .collect::<Result<_>>()?;
-                    scope.project(&expressions)?;
-                    node = Node::Projection { source: Box::new(node), expressions };
-                };
+        // Remove any scalar subquery columns.
+        if scope.len() > width {
+            let expressions = (0..width)
+                .map(|i| Ok((Expression::Field(i, scope.get_label(i)?), None)))
+                .collect::<Result<Vec<_>>>()?;
+            scope.project(&expressions, self.parameters)?;
+            node = Node::Projection { source: Box::new(node), expressions };
+        }
+        Ok(node)
+    }
 
-                // Build HAVING clause.
-                if let Some(expr) = having {
-                    node = Node::Filter {
-                        source: Box::new(node),
-                        predicate: self.build_expression(scope, expr)?,
-                    };
-                };
+    /// Builds an EXISTS subquery, or an IN subquery with the given value, as a semi join (or
+    /// anti join for NOT) of the source node. NOT IN uses a null-aware anti join.
+    fn build_semi_join(
+        &mut self,
+        scope: &mut Scope,
+        node: Node,
+        query: ast::Statement,
+        value: Option<ast::Expression>,
+        anti: bool,
+    ) -> Result<Node> {
+        let kind = if value.is_some() { Subquery::In } else { Subquery::Exists };
+        let null_aware = anti && value.is_some();
+        let (mut right, outer) = self.build_subquery(scope, query, kind)?;
+        // An uncorrelated EXISTS only needs a single row, without any columns.
+        if outer.is_empty() && value.is_none() {
+            right = Node::Projection {
+                source: Box::new(Node::Limit { source: Box::new(right), limit: 1, ties: vec![] }),
+                expressions: Vec::new(),
+            };
+        }
+        let keys = outer
+            .into_iter()
+            .chain(value)
+            .map(|expr| self.build_expression(scope, expr))
+            .collect::<Result<_>>()?;
+        Ok(Node::HashSemiJoin {
+            left: Box::new(node),
+            keys,
+            right: Box::new(right),
+            anti,
+            null_aware,
+        })
+    }
 
-                // Build ORDER clause.
-                if !order.is_empty() {
-                    node = Node::Order {
-                        source: Box::new(node),
-                        orders: order
-                            .into_iter()
-                            .map(|(e, o)| {
-                                Ok((
-                                    self.build_expression(scope, e)?,
-                                    match o {
-                                        ast::Order::Ascending => Direction::Ascending,
-                                        ast::Order::Descending => Direction::Descending,
-                                    },
-                                ))
-                            })
-                            .collect::<Result<_>>()?,
-                    };
+    /// Builds a scalar subquery as a left join of the source node, returning the joined node and
+    /// the index of the subquery value column.
+    fn build_scalar_subquery(
+        &mut self,
+        scope: &mut Scope,
+        node: Node,
+        query: ast::Statement,
+    ) -> Result<(Node, usize)> {
+        let (right, outer) = self.build_subquery(scope, query, Subquery::Scalar)?;
+        let width = scope.len();
+        let keys = outer.len();
+        let predicates = outer
+            .into_iter()
+            .enumerate()
+            .map(|(i, expr)| {
+                Ok(Expression::Equal(
+                    self.build_expression(scope, expr)?.into(),
+                    Expression::Field(width + i, None).into(),
+                ))
+            })
+            .collect::<Result<_>>()?;
+        for _ in 0..=keys {
+            scope.add_column(None, None, None);
+        }
+        let node = Node::NestedLoopJoin {
+            left: Box::new(node),
+            left_size: width,
+            right: Box::new(right),
+            predicate: Self::join_predicates(predicates),
+            outer: true,
+        };
+        Ok((node, width + keys))
+    }
+
+    /// Builds a subquery, returning its node and the outer expressions of any correlated
+    /// equalities, i.e. conditions that refer to fields of the outer query. These are removed
+    /// from the subquery, which instead selects their inner expressions as key columns
+    /// (followed by the IN or scalar value) to be joined with the outer expressions. Correlated
+    /// scalar subqueries are grouped by the key columns.
+    fn build_subquery(
+        &mut self,
+        scope: &Scope,
+        mut query: ast::Statement,
+        kind: Subquery,
+    ) -> Result<(Node, Vec<ast::Expression>)> {
+        if let Subquery::Scalar = kind {
+            match &query {
+                ast::Statement::Select { select, group_by, grouping_sets: None, .. }
+                    if select.len() == 1
+                        && group_by.is_empty()
+                        && self.is_aggregate(&select[0].0) => {}
+                _ => {
+                    return Err(Error::Value(
+                        "Scalar subqueries must compute a single aggregate".into(),
+                    ))
+                }
+            }
+        }
+
+        let (outer, inner): (Vec<_>, Vec<_>) =
+            self.extract_correlated(scope, &mut query)?.into_iter().unzip();
+        if !outer.is_empty() {
+            let ast::Statement::Select {
+                select,
+                distinct,
+                distinct_on,
+                group_by,
+                having,
+                order,
+                offset,
+                limit,
+                ..
+            } = &mut query
+            else {
+                return Err(Error::Internal("Expected correlated SELECT query".into()));
+            };
+            if !group_by.is_empty()
+                || having.is_some()
+                || *distinct
+                || !distinct_on.is_empty()
+                || offset.is_some()
+                || limit.is_some()
+            {
+                return Err(Error::Value(
+                    "Correlated subqueries can't use GROUP BY, HAVING, DISTINCT, LIMIT or OFFSET"
+                        .into(),
+                ));
+            }
+            if let Subquery::In = kind {
+                if select.len() != 1 {
+                    return Err(Error::Value("Subquery must return a single column".into()));
+                }
+            }
+            if let Subquery::Scalar = kind {
+                if select[0].0.contains(&|e| match e {
+                    ast::Expression::Function(f, _)
+                    | ast::Expression::DistinctFunction(f, _, _) => f == "count",
+                    _ => false,
+                }) {
+                    return Err(Error::Value(
+                        "COUNT is not supported in correlated scalar subqueries".into(),
+                    ));
                 }
+                *group_by = inner.clone();
+            }
+            let value = match kind {
+                Subquery::Exists => None,
+                Subquery::In | Subquery::Scalar => Some(select.remove(0)),
+            };
+            *select = inner.into_iter().map(|expr| (expr, None)).chain(value).collect();
+            order.clear();
+        }
+
+        let keys = outer.len();
+        let (node, scope) = self.build_query(query)?;
+        if let Subquery::In = kind {
+            if scope.len() != keys + 1 {
+                return Err(Error::Value("Subquery must return a single column".into()));
+            }
+        }
+        Ok((node, outer))
+    }
+
+    /// Extracts correlated WHERE conditions from a subquery, i.e. ones that refer to fields of
+    /// the outer query scope, returning their outer and inner expressions. These must be
+    /// equalities between an expression of outer fields and an expression of inner fields.
+    /// Fields that exist in both scopes refer to the inner query.
+    fn extract_correlated(
+        &mut self,
+        scope: &Scope,
+        query: &mut ast::Statement,
+    ) -> Result<Vec<(ast::Expression, ast::Expression)>> {
+        let ast::Statement::Select { with, from, r#where, .. } = query else {
+            return Ok(Vec::new());
+        };
+        if !with.is_empty() || from.is_empty() || r#where.is_none() {
+            return Ok(Vec::new());
+        }
+        let mut inner = Scope::new();
+        self.build_from_clause(&mut inner, from.clone())?;
+        let is_outer = |e: &ast::Expression| match e {
+            ast::Expression::Field(table, name) => {
+                inner.resolve(table.as_deref(), name).is_err()
+                    && scope.resolve(table.as_deref(), name).is_ok()
+            }
+            _ => false,
+        };
+        let is_inner =
+            |e: &ast::Expression| matches!(e, ast::Expression::Field(_, _)) && !is_outer(e);
+
+        let mut correlated = Vec::new();
+        let mut conjuncts = Vec::new();
+        for conjunct in Self::split_conjuncts(r#where.take().expect("checked above")) {
+            if !conjunct.contains(&is_outer) {
+                conjuncts.push(conjunct);
+                continue;
+            }
+            match conjunct {
+                ast::Expression::Operation(ast::Operation::Equal(lhs, rhs))
+                    if !lhs.contains(&is_inner) && !rhs.contains(&is_outer) =>
+                {
+                    correlated.push((*lhs, *rhs))
+                }
+                ast::Expression::Operation(ast::Operation::Equal(lhs, rhs))
+                    if !rhs.contains(&is_inner) && !lhs.contains(&is_outer) =>
+                {
+                    correlated.push((*rhs, *lhs))
+                }
+                _ => {
+                    return Err(Error::Value(
+                        "Correlated subquery conditions must be equalities between outer and inner expressions"
+                            .into(),
+                    ))
+                }
+            }
+        }
+        *r#where = Self::join_conjuncts(conjuncts);
+        Ok(correlated)
+    }
+
+    /// Returns true if the AST expression is a subquery.
+    fn is_subquery(expr: &ast::Expression) -> bool {
+        matches!(
+            expr,
+            ast::Expression::Subquery(_)
+                | ast::Expression::Exists(_)
+                | ast::Expression::Operation(ast::Operation::InSubquery(_, _))
+        )
+    }
+
+    /// Builds a predicate, e.g. a WHERE clause. Like in MySQL, MATCH ... AGAINST can be used as a
+    /// condition, which matches texts with a positive relevance, i.e. like @@.
+    fn build_predicate(&self, scope: &mut Scope, expr: ast::Expression) -> Result<Expression> {
+        self.build_expression(scope, Self::predicate(expr))
+    }
+
+    /// Rewrites MATCH ... AGAINST conditions of an AST predicate as @@, see build_predicate().
+    fn predicate(expr: ast::Expression) -> ast::Expression {
+        use ast::Operation::{And, Match, Not, Or, Relevance};
+        match expr {
+            ast::Expression::Operation(Relevance(lhs, rhs)) => Match(lhs, rhs).into(),
+            ast::Expression::Operation(And(lhs, rhs)) => {
+                And(Self::predicate(*lhs).into(), Self::predicate(*rhs).into()).into()
+            }
+            ast::Expression::Operation(Or(lhs, rhs)) => {
+                Or(Self::predicate(*lhs).into(), Self::predicate(*rhs).into()).into()
+            }
+            ast::Expression::Operation(Not(expr)) => Not(Self::predicate(*expr).into()).into(),
+            expr => expr,
+        }
+    }
+
+    /// Splits an AST expression into its AND conjuncts.
+    fn split_conjuncts(expr: ast::Expression) -> Vec<ast::Expression> {
+        match expr {
+            ast::Expression::Operation(ast::Operation::And(lhs, rhs)) => {
+                let mut conjuncts = Self::split_conjuncts(*lhs);
+                conjuncts.extend(Self::split_conjuncts(*rhs));
+                conjuncts
+            }
+            expr => vec![expr],
+        }
+    }
 
-                // Build OFFSET clause.
-                if let Some(expr) = offset {
-                    node = Node::Offset {
-                        source: Box::new(node),
-                        offset: match self.evaluate_constant(expr)? {
-                            Value::Integer(i) if i >= 0 => Ok(i as u64),
-                            v => Err(Error::Value(format!("Invalid offset {}", v))),
-                        }?,
-                    }
-                }
+    /// Joins AST expressions with AND, if any.
+    fn join_conjuncts(exprs: Vec<ast::Expression>) -> Option<ast::Expression> {
+        exprs.into_iter().reduce(|lhs, rhs| ast::Operation::And(lhs.into(), rhs.into()).into())
+    }
 
-                // Build LIMIT clause.
-                if let Some(expr) = limit {
-                    node = Node::Limit {
-                        source: Box::new(node),
-                        limit: match self.evaluate_constant(expr)? {
-                            Value::Integer(i) if i >= 0 => Ok(i as u64),
-                            v => Err(Error::Value(format!("Invalid limit {}", v))),
-                        }?,
-                    }
-                }
+    /// Joins expressions with AND, if any.
+    fn join_predicates(exprs: Vec<Expression>) -> Option<Expression> {
+        exprs.into_iter().reduce(|lhs, rhs| Expression::And(lhs.into(), rhs.into()))
+    }
 
-                // Remove any hidden columns.
-                if hidden > 0 {
-                    node = Node::Projection {
-                        source: Box::new(node),
-                        expressions: (0..(scope.len() - hidden))
-                            .map(|i| (Expression::Field(i, None), None))
-                            .collect(),
-                    }
+    /// Builds an ORDER BY clause, if any, returning the node along with the order expressions.
+    /// These are retained for DISTINCT ON and LIMIT WITH TIES.
+    fn build_order(
+        &self,
+        scope: &mut Scope,
+        node: Node,
+        order: Vec<(ast::Expression, ast::Order, Option<ast::NullOrder>)>,
+    ) -> Result<(Node, Vec<Expression>)> {
+        if order.is_empty() {
+            return Ok((node, Vec::new()));
+        }
+        let orders: Vec<(Expression, Direction, NullOrder)> = order
+            .into_iter()
+            .map(|(e, o, n)| {
+                let direction = match o {
+                    ast::Order::Ascending => Direction::Ascending,
+                    ast::Order::Descending => Direction::Descending,
+                };
+                let nulls = match n {
+                    Some(ast::NullOrder::First) => NullOrder::First,
+                    Some(ast::NullOrder::Last) => NullOrder::Last,
+                    None => NullOrder::default_for(&direction),
+                };
+                // Collated columns are sorted by their collation keys.
+                let mut expr = self.build_expression(scope, e)?;
+                if let Some(collation) = scope.collation(&expr) {
+                    expr = Expression::Collate(expr.into(), collation);
                 }
+                Ok((expr, direction, nulls))
+            })
+            .collect::<Result<_>>()?;
+        let order_exprs = orders.iter().map(|(e, _, _)| e.clone()).collect();
+        Ok((Node::Order { source: Box::new(node), orders, memory: None }, order_exprs))
+    }
 
-                node
+    /// Builds OFFSET and LIMIT clauses, if any. LIMIT WITH TIES requires the order expressions.
+    fn build_offset_limit(
+        &self,
+        mut node: Node,
+        offset: Option<ast::Expression>,
+        limit: Option<ast::Limit>,
+        order_exprs: Vec<Expression>,
+    ) -> Result<Node> {
+        if let Some(expr) = offset {
+            node = Node::Offset {
+                source: Box::new(node),
+                offset: match self.evaluate_constant(expr)? {
+                    Value::Integer(i) if i >= 0 => Ok(i as u64),
+                    v => Err(Error::Value(format!("Invalid offset {}", v))),
+                }?,
             }
-        })
+        }
+        if let Some(ast::Limit { count, with_ties }) = limit {
+            if with_ties && order_exprs.is_empty() {
+                return Err(Error::Value("WITH TIES requires ORDER BY".into()));
+            }
+            node = Node::Limit {
+                source: Box::new(node),
+                limit: match self.evaluate_constant(count)? {
+                    Value::Integer(i) if i >= 0 => Ok(i as u64),
+                    v => Err(Error::Value(format!("Invalid limit {}", v))),
+                }?,
+                ties: if with_ties { order_exprs } else { Vec::new() },
+            }
+        }
+        Ok(node)
     }
 
     /// Builds a FROM clause consisting of several items. Each item is either a single table or a
     /// join of an arbitrary number of tables. All of the items are joined, since e.g. 'SELECT * FROM
     /// a, b' is an implicit join of a and b.
-    fn build_from_clause(&self, scope: &mut Scope, from: Vec<ast::FromItem>) -> Result<Node> {
+    fn build_from_clause(&mut self, scope: &mut Scope, from: Vec<ast::FromItem>) -> Result<Node> {
         let base_scope = scope.clone();
         let mut items = from.into_iter();
         let mut node = match items.next() {
@@ -267,6 +1374,10 @@ impl<'a, C: Catalog> Planner<'a, C> {
             None => return Err(Error::Value("No from items given".into())),
         };
         for item in items {
+            if let ast::FromItem::Lateral(item) = item {
+                node = self.build_lateral(scope, node, *item, None, false)?;
+                continue;
+            }
             let mut right_scope = base_scope.clone();
             let right = self.build_from_item(&mut right_scope, item)?;
             node = Node::NestedLoopJoin {
@@ -285,14 +1396,45 @@ impl<'a, C: Catalog> Planner<'a, C> {
     /// e.g. 'SELECT * FROM a LEFT JOIN b ON b.a_id = a.id'. Any tables will be stored in
     /// self.tables keyed by their query name (i.e. alias if given, otherwise name). The table can
     /// only be referenced by the query name (so if alias is given, cannot reference by name).
-    fn build_from_item(&self, scope: &mut Scope, item: ast::FromItem) -> Result<Node> {
+    fn build_from_item(&mut self, scope: &mut Scope, item: ast::FromItem) -> Result<Node> {
         Ok(match item {
             ast::FromItem::Table { name, alias } => {
+                if let Some(index) = self.ctes.iter().rposition(|cte| cte.name == name) {
+                    return self.build_cte(scope, index, alias);
+                }
+                let resolved = self.resolve_table(&name)?;
+                if let Some(view) = self.catalog.read_view(&resolved)? {
+                    return self.build_view(scope, view, alias);
+                }
+                if let Some(table) = InformationSchema::lookup(&name) {
+                    let labels =
+                        table.columns().iter().map(|(c, t)| (Some(c.to_string()), Some(t.clone())));
+                    scope.add_relation(
+                        alias.clone().unwrap_or_else(|| table.name().to_string()),
+                        labels.collect(),
+                    )?;
+                    return Ok(Node::InformationSchema { table, alias });
+                }
                 scope.add_table(
-                    alias.clone().unwrap_or_else(|| name.clone()),
-                    self.catalog.must_read_table(&name)?,
+                    alias.clone().unwrap_or_else(|| split_name(&name).1.to_string()),
+                    self.catalog.must_read_table(&resolved)?,
                 )?;
-                Node::Scan { table: name, alias, filter: None }
+                Node::Scan { table: resolved, alias, filter: None, columns: None }
+            }
+
+            ast::FromItem::Join { left, right, r#type, predicate }
+                if matches!(*right, ast::FromItem::Lateral(_)) =>
+            {
+                let ast::FromItem::Lateral(right) = *right else { unreachable!("checked above") };
+                let outer = match r#type {
+                    ast::JoinType::Cross | ast::JoinType::Inner => false,
+                    ast::JoinType::Left => true,
+                    ast::JoinType::Right => {
+                        return Err(Error::Value("LATERAL can't be used with RIGHT JOIN".into()))
+                    }
+                };
+                let left = self.build_from_item(scope, *left)?;
+                self.build_lateral(scope, left, *right, predicate, outer)?
             }
 
             ast::FromItem::Join { left, right, r#type, predicate } => {
@@ -305,7 +1447,7 @@ impl<'a, C: Catalog> Planner<'a, C> {
                 let left = Box::new(self.build_from_item(scope, *left)?);
                 let left_size = scope.len();
                 let right = Box::new(self.build_from_item(scope, *right)?);
-                let predicate = predicate.map(|e| self.build_expression(scope, e)).transpose()?;
+                let predicate = predicate.map(|e| self.build_predicate(scope, e)).transpose()?;
                 let outer = match r#type {
                     ast::JoinType::Cross | ast::JoinType::Inner => false,
                     ast::JoinType::Left | ast::JoinType::Right => true,
@@ -316,14 +1458,190 @@ impl<'a, C: Catalog> Planner<'a, C> {
                         .chain(0..left_size)
                         .map(|i| Ok((Expression::Field(i, scope.get_label(i)?), None)))
                         .collect::<Result<Vec<_>>>()?;
-                    scope.project(&expressions)?;
+                    scope.project(&expressions, self.parameters)?;
                     node = Node::Projection { source: Box::new(node), expressions }
                 }
                 node
             }
+
+            ast::FromItem::Values { rows, alias, columns } => {
+                let width = rows.first().map(|row| row.len()).unwrap_or(0);
+                if rows.iter().any(|row| row.len() != width) {
+                    return Err(Error::Value("VALUES lists must all be the same length".into()));
+                }
+                if columns.len() > width {
+                    return Err(Error::Value(format!(
+                        "VALUES {} has {} columns, but {} were given",
+                        alias,
+                        width,
+                        columns.len()
+                    )));
+                }
+                let mut labels: Vec<String> = (1..=width).map(|i| format!("column{}", i)).collect();
+                for (i, column) in columns.into_iter().enumerate() {
+                    labels[i] = column;
+                }
+                let rows = rows
+                    .into_iter()
+                    .map(|row| {
+                        row.into_iter()
+                            .map(|expr| self.build_expression(&mut Scope::constant(), expr))
+                            .collect::<Result<_>>()
+                    })
+                    .collect::<Result<_>>()?;
+                scope.add_relation(
+                    alias,
+                    labels.iter().map(|l| (Some(l.clone()), None)).collect(),
+                )?;
+                Node::Values { columns: labels, rows }
+            }
+
+            ast::FromItem::Unnest { expression, alias, column } => {
+                // A lateral unnest() call can reference the preceding columns, as outer fields
+                // with their datatypes.
+                let (mut expr_scope, outer_scope) = match &self.lateral {
+                    Some(lateral) => (Scope::new(), lateral.clone()),
+                    None => (Scope::constant(), Scope::constant()),
+                };
+                let expression = self.build_expression(&mut expr_scope, expression)?;
+                let fields = expression.clone().transform(
+                    &|e| match e {
+                        Expression::Outer(i, label) => Ok(Expression::Field(i, label)),
+                        e => Ok(e),
+                    },
+                    &Ok,
+                )?;
+                let datatype = match outer_scope.datatype(&fields, self.parameters) {
+                    Some(DataType::Array(element)) => Some(*element),
+                    _ => None,
+                };
+                scope.add_relation(alias, vec![(Some(column.clone()), datatype)])?;
+                Node::Unnest { expression, column }
+            }
+
+            ast::FromItem::Subquery { query, alias, columns } => {
+                let (node, query_scope) = self.build_query(*query)?;
+                let what = format!("Subquery {}", alias);
+                let (node, columns) = Self::label_columns(node, &query_scope, &what, columns)?;
+                let columns = columns.into_iter().zip(query_scope.datatypes).collect();
+                let offset = scope.len();
+                scope.add_relation(alias, columns)?;
+                scope.add_collations(
+                    query_scope.collations.into_iter().map(|(i, c)| (offset + i, c)),
+                );
+                node
+            }
+
+            // A lateral item without preceding items is built as is.
+            ast::FromItem::Lateral(item) => self.build_from_item(scope, *item)?,
         })
     }
 
+    /// Builds a lateral join of a left node with a LATERAL subquery or unnest() call, which can
+    /// reference the left columns as outer fields. The right node is executed for each left row.
+    fn build_lateral(
+        &mut self,
+        scope: &mut Scope,
+        left: Node,
+        item: ast::FromItem,
+        predicate: Option<ast::Expression>,
+        outer: bool,
+    ) -> Result<Node> {
+        let left_size = scope.len();
+        let lateral = self.lateral.replace(scope.clone());
+        let right = self.build_from_item(scope, item);
+        self.lateral = lateral;
+        let right = Box::new(right?);
+        let predicate = predicate.map(|e| self.build_predicate(scope, e)).transpose()?;
+        Ok(Node::LateralJoin { left: Box::new(left), left_size, right, predicate, outer })
+    }
+
+    /// Builds a reference to a common table expression by inlining its query. The query can only
+    /// see the common table expressions defined before it, so these are temporarily hidden, as
+    /// are the columns of any enclosing lateral join.
+    fn build_cte(
+        &mut self,
+        scope: &mut Scope,
+        index: usize,
+        alias: Option<String>,
+    ) -> Result<Node> {
+        let hidden = self.ctes.split_off(index);
+        let cte = hidden[0].clone();
+        let lateral = self.lateral.take();
+        let result = self.build_query(*cte.query);
+        self.lateral = lateral;
+        self.ctes.extend(hidden);
+        let (node, cte_scope) = result?;
+
+        let what = format!("Common table expression {}", cte.name);
+        let (node, columns) = Self::label_columns(node, &cte_scope, &what, cte.columns)?;
+        let columns = columns.into_iter().zip(cte_scope.datatypes).collect();
+        let offset = scope.len();
+        scope.add_relation(alias.unwrap_or(cte.name), columns)?;
+        scope.add_collations(cte_scope.collations.into_iter().map(|(i, c)| (offset + i, c)));
+        Ok(node)
+    }
+
+    /// Builds a reference to a view by parsing and inlining its query. The query can't see any
+    /// common table expressions or lateral columns from the referencing query, so these are
+    /// temporarily hidden.
+    fn build_view(&mut self, scope: &mut Scope, view: View, alias: Option<String>) -> Result<Node> {
+        let query = Parser::new(&view.query).parse()?;
+        let ctes = std::mem::take(&mut self.ctes);
+        let lateral = self.lateral.take();
+        let database = replace(&mut self.database, split_name(&view.name).0.to_string());
+        let result = self.build_query(query);
+        self.ctes = ctes;
+        self.lateral = lateral;
+        self.database = database;
+        let (node, view_scope) = result?;
+
+        let what = format!("View {}", view.name);
+        let (node, columns) = Self::label_columns(node, &view_scope, &what, view.columns)?;
+        let columns = columns.into_iter().zip(view_scope.datatypes).collect();
+        let offset = scope.len();
+        scope
+            .add_relation(alias.unwrap_or_else(|| split_name(&view.name).1.to_string()), columns)?;
+        scope.add_collations(view_scope.collations.into_iter().map(|(i, c)| (offset + i, c)));
+        Ok(node)
+    }
+
+    /// Relabels the output columns of a named query (e.g. a common table expression or view)
+    /// with the given column names, if any, by adding a projection. Returns the node along with
+    /// the resulting column labels.
+    fn label_columns(
+        mut node: Node,
+        query_scope: &Scope,
+        what: &str,
+        labels: Vec<String>,
+    ) -> Result<(Node, Vec<Option<String>>)> {
+        let mut columns = (0..query_scope.len())
+            .map(|i| Ok(query_scope.get_column(i)?.1))
+            .collect::<Result<Vec<_>>>()?;
+        if !labels.is_empty() {
+            if labels.len() > columns.len() {
+                return Err(Error::Value(format!(
+                    "{} has {} columns, but {} were given",
+                    what,
+                    columns.len(),
+                    labels.len()
+                )));
+            }
+            for (i, label) in labels.into_iter().enumerate() {
+                columns[i] = Some(label);
+            }
+            node = Node::Projection {
+                source: Box::new(node),
+                expressions: columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, label)| (Expression::Field(i, None), label.clone()))
+                    .collect(),
+            };
+        }
+        Ok((node, columns))
+    }
+
     /// Builds an aggregation node. All aggregate parameters and GROUP BY expressions are evaluated
     /// in a pre-projection, whose results are fed into an Aggregate node. This node computes the
     /// aggregates for the given groups, passing the group values through directly.
@@ -332,79 +1650,232 @@ impl<'a, C: Catalog> Planner<'a, C> {
         scope: &mut Scope,
         source: Node,
         groups: Vec<(ast::Expression, Option<String>)>,
-        aggregations: Vec<(Aggregate, ast::Expression)>,
+        aggregations: Vec<(Aggregate, Vec<ast::Expression>)>,
+        grouping_sets: Vec<Vec<usize>>,
     ) -> Result<Node> {
         let mut aggregates = Vec::new();
         let mut expressions = Vec::new();
-        for (aggregate, expr) in aggregations {
+        for (aggregate, args) in aggregations {
             aggregates.push(aggregate);
-            expressions.push((self.build_expression(scope, expr)?, None));
+            for expr in args {
+                expressions.push((self.build_expression(scope, expr)?, None));
+            }
         }
+        let inputs = expressions.len();
         for (expr, label) in groups {
             expressions.push((self.build_expression(scope, expr)?, label));
         }
+        // The aggregation node emits one column per aggregate, followed by the groups. We pass
+        // null values for the aggregates since we don't want field references to hit the fields
+        // in scope before the aggregation.
         scope.project(
-            &expressions
-                .iter()
-                .cloned()
-                .enumerate()
-                .map(|(i, (e, l))| {
-                    if i < aggregates.len() {
-                        // We pass null values here since we don't want field references to hit
-                        // the fields in scope before the aggregation.
-                        (Expression::Constant(Value::Null), None)
-                    } else {
-                        (e, l)
-                    }
-                })
+            &std::iter::repeat_n((Expression::Constant(Value::Null), None), aggregates.len())
+                .chain(expressions[inputs..].iter().cloned())
                 .collect::<Vec<_>>(),
+            self.parameters,
         )?;
         let node = Node::Aggregation {
             source: Box::new(Node::Projection { source: Box::new(source), expressions }),
             aggregates,
+            grouping_sets,
+            memory: None,
         };
         Ok(node)
     }
 
+    /// Builds the grouping sets for ROLLUP or CUBE over the given number of group expressions,
+    /// as the indexes of the groups in each set. Returns an empty vector for a plain GROUP BY.
+    fn build_grouping_sets(
+        grouping_sets: Option<ast::GroupingSets>,
+        groups: usize,
+    ) -> Result<Vec<Vec<usize>>> {
+        Ok(match grouping_sets {
+            None => Vec::new(),
+            Some(ast::GroupingSets::Rollup) => {
+                (0..=groups).rev().map(|n| (0..n).collect()).collect()
+            }
+            Some(ast::GroupingSets::Cube) => {
+                if groups > 12 {
+                    return Err(Error::Value("CUBE is limited to 12 elements".into()));
+                }
+                (0..1usize << groups)
+                    .rev()
+                    .map(|mask| {
+                        (0..groups).filter(|i| mask & (1 << (groups - 1 - i)) != 0).collect()
+                    })
+                    .collect()
+            }
+        })
+    }
+
     /// Extracts aggregate functions from an AST expression tree. This finds the aggregate
     /// function calls, replaces them with ast::Expression::Column(i), maps the aggregate functions
-    /// to aggregates, and returns them along with their argument expressions.
+    /// to aggregates, and returns them along with their input expressions.
     fn extract_aggregates(
         &self,
         exprs: &mut [(ast::Expression, Option<String>)],
-    ) -> Result<Vec<(Aggregate, ast::Expression)>> {
+    ) -> Result<Vec<(Aggregate, Vec<ast::Expression>)>> {
         let mut aggregates = Vec::new();
         for (expr, _) in exprs {
             expr.transform_mut(
-                &mut |mut e| match &mut e {
-                    ast::Expression::Function(f, args) if args.len() == 1 => {
-                        if let Some(aggregate) = self.aggregate_from_name(f) {
-                            aggregates.push((aggregate, args.remove(0)));
-                            Ok(ast::Expression::Column(aggregates.len() - 1))
-                        } else {
-                            Ok(e)
-                        }
+                &mut |e| match e {
+                    ast::Expression::Function(f, args) if self.is_aggregate_function(&f) => {
+                        aggregates.push(self.build_aggregate(&f, args, Vec::new())?);
+                        Ok(ast::Expression::Column(aggregates.len() - 1))
+                    }
+                    ast::Expression::OrderedFunction(f, args, order)
+                        if self.is_aggregate_function(&f) =>
+                    {
+                        aggregates.push(self.build_aggregate(&f, args, order)?);
+                        Ok(ast::Expression::Column(aggregates.len() - 1))
+                    }
+                    ast::Expression::DistinctFunction(f, args, order)
+                        if self.is_aggregate_function(&f) =>
+                    {
+                        let (aggregate, args) = self.build_aggregate(&f, args, order)?;
+                        aggregates.push((Aggregate::Distinct(Box::new(aggregate)), args));
+                        Ok(ast::Expression::Column(aggregates.len() - 1))
                     }
-                    _ => Ok(e),
+                    e => Ok(e),
                 },
                 &mut Ok,
             )?;
         }
-        for (_, expr) in &aggregates {
-            if self.is_aggregate(expr) {
+        for (_, exprs) in &aggregates {
+            if exprs.iter().any(|expr| self.is_aggregate(expr)) {
                 return Err(Error::Value("Aggregate functions can't be nested".into()));
             }
         }
         Ok(aggregates)
     }
 
-    /// Extracts group by expressions, and replaces them with column references with the given
-    /// offset. These can be either an arbitray expression, a reference to a SELECT column, or the
-    /// same expression as a SELECT column. The following are all valid:
+    /// Builds an aggregate from an aggregate function call, returning it along with its input
+    /// expressions: the aggregated value followed by any sort keys.
+    fn build_aggregate(
+        &self,
+        name: &str,
+        mut args: Vec<ast::Expression>,
+        order: Vec<(ast::Expression, ast::Order, Option<ast::NullOrder>)>,
+    ) -> Result<(Aggregate, Vec<ast::Expression>)> {
+        let arity = if name == "string_agg" { 2 } else { 1 };
+        if args.len() != arity {
+            return Err(Error::Value(format!(
+                "{} takes {} arguments, got {}",
+                name.to_uppercase(),
+                arity,
+                args.len()
+            )));
+        }
+        if !order.is_empty() && name != "string_agg" && name != "array_agg" {
+            return Err(Error::Value(format!("{} does not take ORDER BY", name.to_uppercase())));
+        }
+        // The sort keys of ordered aggregates follow the aggregated value.
+        let mut directions = Vec::new();
+        for (expr, order, nulls) in order {
+            let direction = match order {
+                ast::Order::Ascending => Direction::Ascending,
+                ast::Order::Descending => Direction::Descending,
+            };
+            let nulls = match nulls {
+                Some(ast::NullOrder::First) => NullOrder::First,
+                Some(ast::NullOrder::Last) => NullOrder::Last,
+                None => NullOrder::default_for(&direction),
+            };
+            directions.push((direction, nulls));
+            args.push(expr);
+        }
+        let aggregate = match name {
+            "array_agg" => Aggregate::ArrayAgg { order: directions },
+            "avg" => Aggregate::Average,
+            "bool_and" => Aggregate::BoolAnd,
+            "bool_or" => Aggregate::BoolOr,
+            "count" => Aggregate::Count,
+            "max" => Aggregate::Max,
+            "min" => Aggregate::Min,
+            "stddev" => Aggregate::StdDev,
+            "string_agg" => {
+                // A NULL separator concatenates the strings without one.
+                let separator = match args.remove(1) {
+                    ast::Expression::Literal(ast::Literal::String(s)) => Some(s),
+                    ast::Expression::Literal(ast::Literal::Null) => Some(String::new()),
+                    // The parameter value is pinned, see evaluate_constant().
+                    expr @ ast::Expression::Parameter(_) => match self.evaluate_constant(expr)? {
+                        Value::String(s) => Some(s),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                let Some(separator) = separator else {
+                    return Err(Error::Value(
+                        "STRING_AGG separator must be a constant string".into(),
+                    ));
+                };
+                Aggregate::StringAgg { separator, order: directions }
+            }
+            "sum" => Aggregate::Sum,
+            "variance" => Aggregate::Variance,
+            name => return Err(Error::Internal(format!("Unknown aggregate {}", name))),
+        };
+        Ok((aggregate, args))
+    }
+
+    /// Resolves GROUP BY references to SELECT columns, either by AS label or by 1-based ordinal
+    /// position, to the referenced SELECT expressions. Other group expressions are returned as is.
+    /// The following are all equivalent:
     ///
     /// SELECT released / 100 AS century, COUNT(*) FROM movies GROUP BY century
+    /// SELECT released / 100 AS century, COUNT(*) FROM movies GROUP BY 1
+    /// SELECT released / 100, COUNT(*) FROM movies GROUP BY released / 100
+    fn resolve_group_by(
+        &self,
+        group_by: Vec<ast::Expression>,
+        select: &[(ast::Expression, Option<String>)],
+    ) -> Result<Vec<ast::Expression>> {
+        let mut resolved = Vec::new();
+        for g in group_by {
+            let position = match &g {
+                ast::Expression::Field(None, label) => {
+                    match select.iter().find(|(_, l)| l.as_deref() == Some(label)) {
+                        Some((expr, _)) => resolved.push(expr.clone()),
+                        None => resolved.push(g),
+                    }
+                    continue;
+                }
+                ast::Expression::Literal(ast::Literal::Integer(i)) => *i,
+                // The parameter value is pinned, see evaluate_constant().
+                ast::Expression::Parameter(_) => match self.evaluate_constant(g.clone())? {
+                    Value::Integer(i) => i,
+                    _ => {
+                        resolved.push(g);
+                        continue;
+                    }
+                },
+                _ => {
+                    resolved.push(g);
+                    continue;
+                }
+            };
+            let Some((expr, _)) =
+                usize::try_from(position).ok().and_then(|p| select.get(p.checked_sub(1)?))
+            else {
+                return Err(Error::Value(format!(
+                    "GROUP BY position {} is not in select list",
+                    position
+                )));
+            };
+            resolved.push(expr.clone());
+        }
+        Ok(resolved)
+    }
+
+    /// Extracts group by expressions, and replaces them with column references with the given
+    /// offset, wherever they occur in the SELECT expressions, e.g. the following are valid:
+    ///
     /// SELECT released / 100, COUNT(*) FROM movies GROUP BY released / 100
+    /// SELECT (released / 100) * 100 + 1, COUNT(*) FROM movies GROUP BY released / 100
     /// SELECT COUNT(*) FROM movies GROUP BY released / 100
+    ///
+    /// A group takes the label of an identical SELECT expression, if any.
     fn extract_groups(
         &self,
         exprs: &mut [(ast::Expression, Option<String>)],
@@ -413,46 +1884,67 @@ impl<'a, C: Catalog> Planner<'a, C> {
     ) -> Result<Vec<(ast::Expression, Option<String>)>> {
         let mut groups = Vec::new();
         for g in group_by {
-            // Look for references to SELECT columns with AS labels
-            if let ast::Expression::Field(None, label) = &g {
-                if let Some(i) = exprs.iter().position(|(_, l)| l.as_deref() == Some(label)) {
-                    groups.push((
-                        replace(&mut exprs[i].0, ast::Expression::Column(offset + groups.len())),
-                        exprs[i].1.clone(),
-                    ));
-                    continue;
-                }
+            if self.is_aggregate(&g) {
+                return Err(Error::Value("Group expression cannot contain aggregates".into()));
             }
-            // Look for expressions exactly equal to the group expression
-            if let Some(i) = exprs.iter().position(|(e, _)| e == &g) {
-                groups.push((
-                    replace(&mut exprs[i].0, ast::Expression::Column(offset + groups.len())),
-                    exprs[i].1.clone(),
-                ));
-                continue;
+            let label = exprs.iter().find(|(e, _)| e == &g).and_then(|(_, l)| l.clone());
+            let column = ast::Expression::Column(offset + groups.len());
+            for (expr, _) in exprs.iter_mut() {
+                expr.transform_mut(
+                    &mut |e| if e == g { Ok(column.clone()) } else { Ok(e) },
+                    &mut Ok,
+                )?;
             }
-            // Otherwise, just use the group expression directly
-            groups.push((g, None))
+            groups.push((g, label));
         }
-        // Make sure no group expressions contain Column references, which would be placed here
-        // during extract_aggregates().
-        for (expr, _) in &groups {
-            if self.is_aggregate(expr) {
-                return Err(Error::Value("Group expression cannot contain aggregates".into()));
+        Ok(groups)
+    }
+
+    /// Checks that SELECT expressions in an aggregate query only refer to fields that are grouped
+    /// by, once group expressions and aggregates have been extracted. Qualified and unqualified
+    /// references to the same column name match, since the scope resolves them after grouping.
+    fn check_grouped(
+        select: &[(ast::Expression, Option<String>)],
+        groups: &[(ast::Expression, Option<String>)],
+    ) -> Result<()> {
+        let ungrouped = RefCell::new(None);
+        for (expr, _) in select {
+            expr.walk(&|e| {
+                let ast::Expression::Field(table, name) = e else { return true };
+                let grouped = groups.iter().any(|(g, _)| match g {
+                    ast::Expression::Field(t, n) => {
+                        n == name && (t.is_none() || table.is_none() || t == table)
+                    }
+                    _ => false,
+                });
+                if !grouped {
+                    *ungrouped.borrow_mut() = Some(match table {
+                        Some(table) => format!("{}.{}", table, name),
+                        None => name.clone(),
+                    });
+                }
+                grouped
+            });
+            if let Some(field) = ungrouped.take() {
+                return Err(Error::Value(format!(
+                    "Column {} must appear in GROUP BY or be used in an aggregate function",
+                    field
+                )));
             }
         }
-        Ok(groups)
+        Ok(())
     }
 
     /// Injects hidden expressions into SELECT expressions. This is used for ORDER BY and HAVING, in
-    /// order to apply these to fields or aggregates that are not present in the SELECT output, e.g.
-    /// to order on a column that is not selected. This is done by replacing the relevant parts of
+    /// order to apply these to fields, group expressions or aggregates that are not present in the
+    /// SELECT output, e.g. to order on a column that is not selected. This is done by replacing the relevant parts of
     /// the given expression with Column references to either existing columns or new, hidden
     /// columns in the select expressions. Returns the number of hidden columns added.
     fn inject_hidden(
         &self,
         expr: &mut ast::Expression,
         select: &mut Vec<(ast::Expression, Option<String>)>,
+        group_by: &[ast::Expression],
     ) -> Result<usize> {
         // Replace any identical expressions or label references with column references.
         for (i, (sexpr, label)) in select.iter().enumerate() {
@@ -474,12 +1966,26 @@ impl<'a, C: Catalog> Planner<'a, C> {
         }
         // Any remaining aggregate functions and field references must be extracted as hidden
         // columns.
+        // Group expressions outside of aggregates are extracted as a whole, such that they're
+        // matched with the GROUP BY expressions rather than extracting their fields.
         let mut hidden = 0;
         expr.transform_mut(
             &mut |e| match &e {
-                ast::Expression::Function(f, a) if self.aggregate_from_name(f).is_some() => {
-                    if let ast::Expression::Column(c) = a[0] {
-                        if self.is_aggregate(&select[c].0) {
+                e if group_by.contains(e) => match select.iter().position(|(s, _)| s == e) {
+                    Some(i) => Ok(ast::Expression::Column(i)),
+                    None => {
+                        select.push((e.clone(), None));
+                        hidden += 1;
+                        Ok(ast::Expression::Column(select.len() - 1))
+                    }
+                },
+                ast::Expression::Function(f, a)
+                | ast::Expression::OrderedFunction(f, a, _)
+                | ast::Expression::DistinctFunction(f, a, _)
+                    if self.is_aggregate_function(f) =>
+                {
+                    if let Some(ast::Expression::Column(c)) = a.first() {
+                        if self.is_aggregate(&select[*c].0) {
                             return Err(Error::Value(
                                 "Aggregate function cannot reference aggregate".into(),
                             ));
@@ -501,22 +2007,30 @@ impl<'a, C: Catalog> Planner<'a, C> {
         Ok(hidden)
     }
 
-    /// Returns the aggregate corresponding to the given aggregate function name.
-    fn aggregate_from_name(&self, name: &str) -> Option<Aggregate> {
-        match name {
-            "avg" => Some(Aggregate::Average),
-            "count" => Some(Aggregate::Count),
-            "max" => Some(Aggregate::Max),
-            "min" => Some(Aggregate::Min),
-            "sum" => Some(Aggregate::Sum),
-            _ => None,
-        }
+    /// Checks whether the given function name is an aggregate function.
+    fn is_aggregate_function(&self, name: &str) -> bool {
+        matches!(
+            name,
+            "array_agg"
+                | "avg"
+                | "bool_and"
+                | "bool_or"
+                | "count"
+                | "max"
+                | "min"
+                | "stddev"
+                | "string_agg"
+                | "sum"
+                | "variance"
+        )
     }
 
     /// Checks whether a given expression is an aggregate expression.
     fn is_aggregate(&self, expr: &ast::Expression) -> bool {
         expr.contains(&|e| match e {
-            ast::Expression::Function(f, _) => self.aggregate_from_name(f).is_some(),
+            ast::Expression::Function(f, _)
+            | ast::Expression::OrderedFunction(f, _, _)
+            | ast::Expression::DistinctFunction(f, _, _) => self.is_aggregate_function(f),
             _ => false,
         })
     }
@@ -532,13 +2046,49 @@ impl<'a, C: Catalog> Planner<'a, C> {
                 ast::Literal::Integer(i) => Value::Integer(i),
                 ast::Literal::Float(f) => Value::Float(f),
                 ast::Literal::String(s) => Value::String(s),
+                ast::Literal::Bytes(b) => Value::Bytes(b),
             }),
+            ast::Expression::Parameter(i) => Parameter(i),
             ast::Expression::Column(i) => Field(i, scope.get_label(i)?),
-            ast::Expression::Field(table, name) => {
-                Field(scope.resolve(table.as_deref(), &name)?, Some((table, name)))
+            ast::Expression::Subquery(_) | ast::Expression::Exists(_) => {
+                return Err(Error::Value(
+                    "Subqueries are only supported in WHERE clauses of SELECT queries".into(),
+                ))
+            }
+            ast::Expression::Field(table, name) => match scope.resolve(table.as_deref(), &name) {
+                Ok(index) => Field(index, Some((table, name))),
+                // Lateral items can reference the columns of the preceding FROM items, and
+                // trigger actions the old and new rows. These are bound before execution, even
+                // in constant expressions such as VALUES rows, but can't be used while planning,
+                // see evaluate_constant().
+                Err(err) => match &self.lateral {
+                    Some(lateral) => match lateral.resolve(table.as_deref(), &name) {
+                        Ok(index) => Outer(index, Some((table, name))),
+                        Err(_) => return Err(err),
+                    },
+                    None => return Err(err),
+                },
+            },
+            ast::Expression::Function(name, args) => {
+                let Some(function) = super::super::types::Function::lookup(&name) else {
+                    return Err(Error::Value(format!("Unknown function {}", name,)));
+                };
+                function.check_arity(args.len())?;
+                let mut args = args
+                    .into_iter()
+                    .map(|e| self.build_expression(scope, e))
+                    .collect::<Result<Vec<_>>>()?;
+                if function.is_sequence() {
+                    let name = self.resolve_sequence(&function.to_string(), &args[0])?;
+                    args[0] = Constant(Value::String(name));
+                }
+                Function(function, args)
+            }
+            ast::Expression::OrderedFunction(name, _, _) => {
+                return Err(Error::Value(format!("{} does not take ORDER BY", name.to_uppercase())))
             }
-            ast::Expression::Function(name, _) => {
-                return Err(Error::Value(format!("Unknown function {}", name,)))
+            ast::Expression::DistinctFunction(name, _, _) => {
+                return Err(Error::Value(format!("{} does not take DISTINCT", name.to_uppercase())))
             }
             ast::Expression::Operation(op) => match op {
                 // Logical operators
@@ -553,102 +2103,283 @@ impl<'a, C: Catalog> Planner<'a, C> {
                 ),
 
                 // Comparison operators
-                ast::Operation::Equal(lhs, rhs) => Equal(
-                    self.build_expression(scope, *lhs)?.into(),
-                    self.build_expression(scope, *rhs)?.into(),
-                ),
-                ast::Operation::GreaterThan(lhs, rhs) => GreaterThan(
-                    self.build_expression(scope, *lhs)?.into(),
-                    self.build_expression(scope, *rhs)?.into(),
-                ),
-                ast::Operation::GreaterThanOrEqual(lhs, rhs) => Or(
-                    GreaterThan(
-                        self.build_expression(scope, *lhs.clone())?.into(),
-                        self.build_expression(scope, *rhs.clone())?.into(),
-                    )
-                    .into(),
-                    Equal(
-                        self.build_expression(scope, *lhs)?.into(),
-                        self.build_expression(scope, *rhs)?.into(),
-                    )
-                    .into(),
-                ),
+                ast::Operation::Any(lhs, rhs) => {
+                    let mut lhs = self.build_expression(scope, *lhs)?;
+                    let rhs = self.build_expression(scope, *rhs)?;
+                    // A string literal is coerced to the array's element type, as for =. The
+                    // element type is given as a typed NULL.
+                    if let Some(DataType::Array(element)) = scope.datatype(&rhs, self.parameters) {
+                        let null = Cast(Constant(Value::Null).into(), *element);
+                        let mut operands = [lhs, null];
+                        self.coerce(scope, &mut operands)?;
+                        [lhs, _] = operands;
+                    }
+                    Any(lhs.into(), rhs.into())
+                }
+                ast::Operation::Between(expr, low, high) => {
+                    let mut operands = [
+                        self.build_expression(scope, *expr)?,
+                        self.build_expression(scope, *low)?,
+                        self.build_expression(scope, *high)?,
+                    ];
+                    self.coerce(scope, &mut operands)?;
+                    self.collate(scope, &mut operands)?;
+                    let [expr, low, high] = operands;
+                    Between(expr.into(), low.into(), high.into())
+                }
+                ast::Operation::Equal(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_comparison(scope, *lhs, *rhs)?;
+                    Equal(lhs, rhs)
+                }
+                ast::Operation::GreaterThan(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_comparison(scope, *lhs, *rhs)?;
+                    GreaterThan(lhs, rhs)
+                }
+                ast::Operation::GreaterThanOrEqual(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_comparison(scope, *lhs, *rhs)?;
+                    Or(GreaterThan(lhs.clone(), rhs.clone()).into(), Equal(lhs, rhs).into())
+                }
+                ast::Operation::InSubquery(_, _) => {
+                    return Err(Error::Value(
+                        "Subqueries are only supported in WHERE clauses of SELECT queries".into(),
+                    ))
+                }
+                ast::Operation::In(expr, list) => {
+                    let mut operands = std::iter::once(*expr)
+                        .chain(list)
+                        .map(|e| self.build_expression(scope, e))
+                        .collect::<Result<Vec<_>>>()?;
+                    self.coerce(scope, &mut operands)?;
+                    self.collate(scope, &mut operands)?;
+                    let expr = operands.remove(0);
+                    In(expr.into(), operands)
+                }
                 ast::Operation::IsNull(expr) => IsNull(self.build_expression(scope, *expr)?.into()),
-                ast::Operation::LessThan(lhs, rhs) => LessThan(
+                ast::Operation::LessThan(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_comparison(scope, *lhs, *rhs)?;
+                    LessThan(lhs, rhs)
+                }
+                ast::Operation::LessThanOrEqual(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_comparison(scope, *lhs, *rhs)?;
+                    Or(LessThan(lhs.clone(), rhs.clone()).into(), Equal(lhs, rhs).into())
+                }
+                ast::Operation::Concat(lhs, rhs) => Concat(
                     self.build_expression(scope, *lhs)?.into(),
                     self.build_expression(scope, *rhs)?.into(),
                 ),
-                ast::Operation::LessThanOrEqual(lhs, rhs) => Or(
-                    LessThan(
-                        self.build_expression(scope, *lhs.clone())?.into(),
-                        self.build_expression(scope, *rhs.clone())?.into(),
-                    )
-                    .into(),
-                    Equal(
-                        self.build_expression(scope, *lhs)?.into(),
-                        self.build_expression(scope, *rhs)?.into(),
-                    )
-                    .into(),
-                ),
-                ast::Operation::Like(lhs, rhs) => Like(
+                ast::Operation::Like(lhs, rhs) => {
+                    let mut operands =
+                        [self.build_expression(scope, *lhs)?, self.build_expression(scope, *rhs)?];
+                    self.collate(scope, &mut operands)?;
+                    let [lhs, rhs] = operands;
+                    Like(lhs.into(), rhs.into())
+                }
+                ast::Operation::Match(lhs, rhs) => Match(
                     self.build_expression(scope, *lhs)?.into(),
                     self.build_expression(scope, *rhs)?.into(),
                 ),
-                ast::Operation::NotEqual(lhs, rhs) => Not(Equal(
+                ast::Operation::Relevance(lhs, rhs) => Relevance(
                     self.build_expression(scope, *lhs)?.into(),
                     self.build_expression(scope, *rhs)?.into(),
-                )
-                .into()),
-
-                // Mathematical operators
-                ast::Operation::Assert(expr) => Assert(self.build_expression(scope, *expr)?.into()),
-                ast::Operation::Add(lhs, rhs) => Add(
+                ),
+                ast::Operation::ArrayElement(lhs, rhs) => ArrayElement(
                     self.build_expression(scope, *lhs)?.into(),
                     self.build_expression(scope, *rhs)?.into(),
                 ),
-                ast::Operation::Divide(lhs, rhs) => Divide(
+                ast::Operation::JsonExtract(lhs, rhs) => JsonExtract(
                     self.build_expression(scope, *lhs)?.into(),
                     self.build_expression(scope, *rhs)?.into(),
                 ),
-                ast::Operation::Exponentiate(lhs, rhs) => Exponentiate(
+                ast::Operation::JsonExtractText(lhs, rhs) => JsonExtractText(
                     self.build_expression(scope, *lhs)?.into(),
                     self.build_expression(scope, *rhs)?.into(),
                 ),
+                ast::Operation::NotEqual(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_comparison(scope, *lhs, *rhs)?;
+                    Not(Equal(lhs, rhs).into())
+                }
+
+                // Mathematical operators
+                ast::Operation::Assert(expr) => Assert(self.build_expression(scope, *expr)?.into()),
+                ast::Operation::Add(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_operands(scope, *lhs, *rhs)?;
+                    Add(lhs, rhs)
+                }
+                ast::Operation::Divide(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_operands(scope, *lhs, *rhs)?;
+                    Divide(lhs, rhs)
+                }
+                ast::Operation::Exponentiate(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_operands(scope, *lhs, *rhs)?;
+                    Exponentiate(lhs, rhs)
+                }
                 ast::Operation::Factorial(expr) => {
                     Factorial(self.build_expression(scope, *expr)?.into())
                 }
-                ast::Operation::Modulo(lhs, rhs) => Modulo(
-                    self.build_expression(scope, *lhs)?.into(),
-                    self.build_expression(scope, *rhs)?.into(),
-                ),
-                ast::Operation::Multiply(lhs, rhs) => Multiply(
-                    self.build_expression(scope, *lhs)?.into(),
-                    self.build_expression(scope, *rhs)?.into(),
-                ),
+                ast::Operation::Modulo(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_operands(scope, *lhs, *rhs)?;
+                    Modulo(lhs, rhs)
+                }
+                ast::Operation::Multiply(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_operands(scope, *lhs, *rhs)?;
+                    Multiply(lhs, rhs)
+                }
                 ast::Operation::Negate(expr) => Negate(self.build_expression(scope, *expr)?.into()),
-                ast::Operation::Subtract(lhs, rhs) => Subtract(
-                    self.build_expression(scope, *lhs)?.into(),
-                    self.build_expression(scope, *rhs)?.into(),
-                ),
+                ast::Operation::Subtract(lhs, rhs) => {
+                    let (lhs, rhs) = self.build_operands(scope, *lhs, *rhs)?;
+                    Subtract(lhs, rhs)
+                }
+
+                // Type operators
+                ast::Operation::Cast(expr, datatype) => {
+                    let datatype = self.resolve_datatype(datatype)?;
+                    Cast(self.build_expression(scope, *expr)?.into(), datatype)
+                }
             },
         })
     }
 
-    /// Builds and evaluates a constant AST expression.
-    fn evaluate_constant(&self, expr: ast::Expression) -> Result<Value> {
-        self.build_expression(&mut Scope::constant(), expr)?.evaluate(None)
+    /// Builds the operands of a binary comparison operator, coercing string
+    /// literals via coerce() and comparing collated columns via collate().
+    fn build_comparison(
+        &self,
+        scope: &mut Scope,
+        lhs: ast::Expression,
+        rhs: ast::Expression,
+    ) -> Result<(Box<Expression>, Box<Expression>)> {
+        let mut operands = [self.build_expression(scope, lhs)?, self.build_expression(scope, rhs)?];
+        self.coerce(scope, &mut operands)?;
+        self.collate(scope, &mut operands)?;
+        let [lhs, rhs] = operands;
+        Ok((lhs.into(), rhs.into()))
+    }
+
+    /// Builds the operands of a binary arithmetic operator, coercing string
+    /// literals via coerce().
+    fn build_operands(
+        &self,
+        scope: &mut Scope,
+        lhs: ast::Expression,
+        rhs: ast::Expression,
+    ) -> Result<(Box<Expression>, Box<Expression>)> {
+        let mut operands = [self.build_expression(scope, lhs)?, self.build_expression(scope, rhs)?];
+        self.coerce(scope, &mut operands)?;
+        let [lhs, rhs] = operands;
+        Ok((lhs.into(), rhs.into()))
+    }
+
+    /// Implicitly converts string literal operands of a comparison or
+    /// arithmetic operator to the datatype of the other operands, if known,
+    /// e.g. int_col = '5' compares with the integer 5. Otherwise, numeric
+    /// operands are converted to their common type when evaluated, and other
+    /// mismatched types error. With strict types, string literals must be
+    /// cast explicitly instead.
+    fn coerce(&self, scope: &Scope, operands: &mut [Expression]) -> Result<()> {
+        let literal = |expr: &Expression| match expr {
+            Expression::Constant(value @ Value::String(_)) => Some(value.clone()),
+            Expression::Parameter(i) => {
+                self.parameters.get(*i).filter(|v| matches!(v, Value::String(_))).cloned()
+            }
+            _ => None,
+        };
+        let Some(datatype) = operands
+            .iter()
+            .filter(|expr| literal(expr).is_none())
+            .find_map(|expr| scope.datatype(expr, self.parameters))
+            .filter(|datatype| *datatype != DataType::String)
+        else {
+            return Ok(());
+        };
+        for operand in operands.iter_mut() {
+            let Some(value) = literal(operand) else { continue };
+            if self.strict_types {
+                return Err(Error::Value(format!(
+                    "String literal '{}' must be explicitly cast to {}",
+                    value, datatype
+                )));
+            }
+            // Parameters are converted when the plan is bound. Cached plans
+            // are keyed by the parameter types, so they're always strings.
+            *operand = match operand {
+                Expression::Parameter(i) => {
+                    Expression::Cast(Expression::Parameter(*i).into(), datatype.clone())
+                }
+                _ => Expression::Constant(value.cast(&datatype)?),
+            };
+        }
+        Ok(())
+    }
+
+    /// Wraps the operands of a comparison in the collation of any collated
+    /// column among them, such that strings are compared by their collation
+    /// keys, e.g. name = 'abc' matches 'ABC' if name has the NOCASE collation.
+    /// Errors if the operands are columns with different collations.
+    fn collate(&self, scope: &Scope, operands: &mut [Expression]) -> Result<()> {
+        let mut collations = operands.iter().filter_map(|expr| scope.collation(expr));
+        let Some(collation) = collations.next() else {
+            return Ok(());
+        };
+        if let Some(other) = collations.find(|c| *c != collation) {
+            return Err(Error::Value(format!(
+                "Can't compare values with collations {} and {}",
+                collation, other
+            )));
+        }
+        for operand in operands.iter_mut() {
+            let expr = replace(operand, Expression::Constant(Value::Null));
+            *operand = Expression::Collate(expr.into(), collation);
+        }
+        Ok(())
+    }
+
+    /// Builds and evaluates a constant AST expression. Any parameters are
+    /// substituted with their values, and pinned.
+    pub fn evaluate_constant(&self, expr: ast::Expression) -> Result<Value> {
+        let expr = self.build_expression(&mut Scope::constant(), expr)?.transform(&Ok, &|e| {
+            if let Expression::Outer(i, label) = &e {
+                return Err(Error::Value(format!(
+                    "Expression must be constant, found field {}",
+                    match label {
+                        Some((Some(table), name)) => format!("{}.{}", table, name),
+                        Some((None, name)) => name.clone(),
+                        None => format!("#{}", i),
+                    }
+                )));
+            }
+            let Expression::Parameter(i) = e else { return Ok(e) };
+            let value = self.parameters.get(i).cloned();
+            let value =
+                value.ok_or_else(|| Error::Internal(format!("Unbound parameter ${}", i)))?;
+            self.pinned.borrow_mut().insert(i);
+            Ok(Expression::Constant(value))
+        })?;
+        expr.evaluate(None)
     }
 }
 
+/// A kind of subquery in a WHERE clause, see build_subquery().
+enum Subquery {
+    /// EXISTS (SELECT ...)
+    Exists,
+    /// expr IN (SELECT ...)
+    In,
+    /// (SELECT aggregate ...)
+    Scalar,
+}
+
 /// Manages names available to expressions and executors, and maps them onto columns/fields.
 #[derive(Clone, Debug)]
 pub struct Scope {
     // If true, the scope is constant and cannot contain any variables.
     constant: bool,
-    // Currently visible tables, by query name (i.e. alias or actual name).
-    tables: HashMap<String, Table>,
+    // Currently visible tables and other relations, by query name (i.e. alias or actual name).
+    tables: HashSet<String>,
     // Column labels, if any (qualified by table name when available)
     columns: Vec<(Option<String>, Option<String>)>,
+    // Column datatypes, if known, e.g. for table columns.
+    datatypes: Vec<Option<DataType>>,
+    // Column collations by column index, for columns with a non-binary collation.
+    collations: HashMap<usize, Collation>,
     // Qualified names to column indexes.
     qualified: HashMap<(String, String), usize>,
     // Unqualified names to column indexes, if unique.
@@ -662,8 +2393,10 @@ impl Scope {
     fn new() -> Self {
         Self {
             constant: false,
-            tables: HashMap::new(),
+            tables: HashSet::new(),
             columns: Vec::new(),
+            datatypes: Vec::new(),
+            collations: HashMap::new(),
             qualified: HashMap::new(),
             unqualified: HashMap::new(),
             ambiguous: HashSet::new(),
@@ -680,13 +2413,18 @@ impl Scope {
     /// Creates a scope from a table.
     fn from_table(table: Table) -> Result<Self> {
         let mut scope = Self::new();
-        scope.add_table(table.name.clone(), table)?;
+        scope.add_table(split_name(&table.name).1.to_string(), table)?;
         Ok(scope)
     }
 
     /// Adds a column to the scope.
     #[allow(clippy::map_entry)]
-    fn add_column(&mut self, table: Option<String>, label: Option<String>) {
+    fn add_column(
+        &mut self,
+        table: Option<String>,
+        label: Option<String>,
+        datatype: Option<DataType>,
+    ) {
         if let Some(l) = label.clone() {
             if let Some(t) = table.clone() {
                 self.qualified.insert((t, l.clone()), self.columns.len());
@@ -701,20 +2439,45 @@ impl Scope {
             }
         }
         self.columns.push((table, label));
+        self.datatypes.push(datatype);
     }
 
     /// Adds a table to the scope.
     fn add_table(&mut self, label: String, table: Table) -> Result<()> {
+        let offset = self.len();
+        let collations: HashMap<usize, Collation> =
+            table.columns.iter().enumerate().map(|(i, c)| (offset + i, c.collation)).collect();
+        self.add_relation(
+            label,
+            table.columns.into_iter().map(|c| (Some(c.name), Some(c.datatype))).collect(),
+        )?;
+        self.add_collations(collations);
+        Ok(())
+    }
+
+    /// Adds column collations to the scope, ignoring binary collations.
+    fn add_collations(&mut self, collations: impl IntoIterator<Item = (usize, Collation)>) {
+        let collations = collations.into_iter().filter(|(_, c)| *c != Collation::Binary);
+        self.collations.extend(collations);
+    }
+
+    /// Adds a relation with the given column labels and datatypes to the scope, e.g. a table or a
+    /// common table expression.
+    fn add_relation(
+        &mut self,
+        label: String,
+        columns: Vec<(Option<String>, Option<DataType>)>,
+    ) -> Result<()> {
         if self.constant {
             return Err(Error::Internal("Can't modify constant scope".into()));
         }
-        if self.tables.contains_key(&label) {
+        if self.tables.contains(&label) {
             return Err(Error::Value(format!("Duplicate table name {}", label)));
         }
-        for column in &table.columns {
-            self.add_column(Some(label.clone()), Some(column.name.clone()));
+        for (column, datatype) in columns {
+            self.add_column(Some(label.clone()), column, datatype);
         }
-        self.tables.insert(label, table);
+        self.tables.insert(label);
         Ok(())
     }
 
@@ -745,14 +2508,16 @@ impl Scope {
         if self.constant {
             return Err(Error::Internal("Can't modify constant scope".into()));
         }
-        for (label, table) in scope.tables {
-            if self.tables.contains_key(&label) {
+        for label in scope.tables {
+            if self.tables.contains(&label) {
                 return Err(Error::Value(format!("Duplicate table name {}", label)));
             }
-            self.tables.insert(label, table);
+            self.tables.insert(label);
         }
-        for (table, label) in scope.columns {
-            self.add_column(table, label);
+        let offset = self.len();
+        self.add_collations(scope.collations.into_iter().map(|(i, c)| (offset + i, c)));
+        for ((table, label), datatype) in scope.columns.into_iter().zip(scope.datatypes) {
+            self.add_column(table, label, datatype);
         }
         Ok(())
     }
@@ -766,7 +2531,7 @@ impl Scope {
             )));
         }
         if let Some(table) = table {
-            if !self.tables.contains_key(table) {
+            if !self.tables.contains(table) {
                 return Err(Error::Value(format!("Unknown table {}", table)));
             }
             self.qualified
@@ -788,31 +2553,117 @@ impl Scope {
         self.columns.len()
     }
 
+    /// Infers the datatype of an expression in the scope, given any parameter
+    /// values, if known. This only handles simple cases like fields, constants,
+    /// casts and operators, as needed to coerce string literals, see
+    /// Planner::coerce().
+    fn datatype(&self, expr: &Expression, parameters: &[Value]) -> Option<DataType> {
+        use Expression::*;
+        match expr {
+            Constant(value) => value.datatype(),
+            Parameter(i) => parameters.get(*i)?.datatype(),
+            Field(i, _) => self.datatypes.get(*i).cloned().flatten(),
+            Cast(_, datatype) => Some(datatype.clone()),
+            Add(lhs, rhs)
+            | Subtract(lhs, rhs)
+            | Multiply(lhs, rhs)
+            | Divide(lhs, rhs)
+            | Modulo(lhs, rhs) => {
+                use DataType::{Date, Decimal, Float, Integer, Interval, Time, Timestamp};
+                let (l, r) = (self.datatype(lhs, parameters)?, self.datatype(rhs, parameters)?);
+                match (expr, l, r) {
+                    // Date and time arithmetic, see add() and subtract().
+                    (Subtract(..), Date, Date) => Some(Integer),
+                    (Multiply(..), Interval, Integer | Float)
+                    | (Multiply(..), Integer | Float, Interval)
+                    | (Divide(..), Interval, Integer | Float)
+                    | (Add(..) | Subtract(..), Interval, Interval)
+                    | (Subtract(..), Date | Timestamp, Date | Timestamp)
+                    | (Subtract(..), Time, Time) => Some(Interval),
+                    (Add(..) | Subtract(..), Date | Timestamp, Interval)
+                    | (Add(..), Interval, Date | Timestamp) => Some(Timestamp),
+                    (Add(..) | Subtract(..), Time, Interval) | (Add(..), Interval, Time) => {
+                        Some(Time)
+                    }
+                    (Add(..) | Subtract(..), Date, Integer) | (Add(..), Integer, Date) => {
+                        Some(Date)
+                    }
+                    (_, l @ (Integer | Float | Decimal), r) => l.common(&r),
+                    _ => None,
+                }
+            }
+            Negate(expr) => self.datatype(expr, parameters),
+            And(..) | Or(..) | Not(..) | Any(..) | Between(..) | Equal(..) | GreaterThan(..)
+            | LessThan(..) | In(..) | IsNull(..) | Like(..) | Match(..) => Some(DataType::Boolean),
+            Relevance(..) => Some(DataType::Float),
+            // Concatenation with an array yields an array.
+            Concat(lhs, rhs) => {
+                match (self.datatype(lhs, parameters), self.datatype(rhs, parameters)) {
+                    (Some(array @ DataType::Array(_)), _)
+                    | (_, Some(array @ DataType::Array(_))) => Some(array),
+                    _ => Some(DataType::String),
+                }
+            }
+            JsonExtractText(..) => Some(DataType::String),
+            ArrayElement(array, _) => match self.datatype(array, parameters)? {
+                DataType::Array(element) => Some(*element),
+                _ => None,
+            },
+            Function(super::super::types::Function::Array, args) => {
+                Some(DataType::Array(Box::new(
+                    args.iter()
+                        .find_map(|arg| self.datatype(arg, parameters))
+                        .unwrap_or(DataType::String),
+                )))
+            }
+            JsonExtract(..) => Some(DataType::Json),
+            _ => None,
+        }
+    }
+
+    /// Returns the collation of an expression in the scope, if it's a column with a non-binary
+    /// collation.
+    fn collation(&self, expr: &Expression) -> Option<Collation> {
+        match expr {
+            Expression::Field(i, _) => self.collations.get(i).copied(),
+            _ => None,
+        }
+    }
+
     /// Projects the scope. This takes a set of expressions and labels in the current scope,
     /// and returns a new scope for the projection.
-    fn project(&mut self, projection: &[(Expression, Option<String>)]) -> Result<()> {
+    fn project(
+        &mut self,
+        projection: &[(Expression, Option<String>)],
+        parameters: &[Value],
+    ) -> Result<()> {
         if self.constant {
             return Err(Error::Internal("Can't modify constant scope".into()));
         }
         let mut new = Self::new();
         new.tables = self.tables.clone();
         for (expr, label) in projection {
+            let datatype = self.datatype(expr, parameters);
+            let len = new.len();
             match (expr, label) {
-                (_, Some(label)) => new.add_column(None, Some(label.clone())),
+                (_, Some(label)) => new.add_column(None, Some(label.clone()), datatype),
                 (Expression::Field(_, Some((Some(table), name))), _) => {
-                    new.add_column(Some(table.clone()), Some(name.clone()))
+                    new.add_column(Some(table.clone()), Some(name.clone()), datatype)
                 }
                 (Expression::Field(_, Some((None, name))), _) => {
                     if let Some(i) = self.unqualified.get(name) {
                         let (table, name) = self.columns[*i].clone();
-                        new.add_column(table, name);
+                        new.add_column(table, name, datatype);
                     }
                 }
                 (Expression::Field(i, None), _) => {
                     let (table, label) = self.columns.get(*i).cloned().unwrap_or((None, None));
-                    new.add_column(table, label)
+                    new.add_column(table, label, datatype)
                 }
-                _ => new.add_column(None, None),
+                _ => new.add_column(None, None, datatype),
+            }
+            if let Some(collation) = self.collation(expr).filter(|_| new.len() > len) {
+                new.collations.insert(len, collation);
             }
         }
         *self = new;
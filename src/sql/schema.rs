@@ -1,21 +1,255 @@
 use super::engine::Transaction;
-use super::parser::format_ident;
-use super::types::{DataType, Value};
+use super::parser::{format_ident, format_name, format_string};
+use super::types::{fulltext, Collation, DataType, Expression, Row, Value};
 use crate::error::{Error, Result};
 
 use serde_derive::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt::{self, Display};
 
+/// The default database. Its tables, views and indexes are stored under their
+/// unqualified names, while those of other databases are stored under names
+/// qualified by the database name, e.g. db.table, such that their rows and
+/// other keys are namespaced by database.
+pub const DEFAULT_DATABASE: &str = "main";
+
+/// Qualifies a table, view or index name with the given database, unless it
+/// is already qualified. Names in the default database are unqualified.
+pub fn qualify_name(name: &str, database: &str) -> String {
+    match name.split_once('.').unwrap_or((database, name)) {
+        (DEFAULT_DATABASE, name) => name.to_string(),
+        (database, name) => format!("{}.{}", database, name),
+    }
+}
+
+/// Splits a catalog name into its database and unqualified name.
+pub fn split_name(name: &str) -> (&str, &str) {
+    name.split_once('.').unwrap_or((DEFAULT_DATABASE, name))
+}
+
 /// The catalog stores schema information
 pub trait Catalog {
     /// Creates a new table
     fn create_table(&mut self, table: Table) -> Result<()>;
     /// Deletes an existing table, or errors if it does not exist
     fn delete_table(&mut self, table: &str) -> Result<()>;
+    /// Adds a column to an existing table
+    fn add_column(&mut self, table: &str, column: Column) -> Result<()>;
+    /// Drops a column from an existing table
+    fn drop_column(&mut self, table: &str, column: &str) -> Result<()>;
+    /// Changes the datatype or nullability of a column in an existing table
+    fn alter_column(&mut self, table: &str, column: &str, change: ColumnChange) -> Result<()>;
+    /// Creates a new index on an existing table. A building index is only
+    /// added to the table schema, and its entries are built via build_index().
+    fn create_index(&mut self, table: &str, index: Index) -> Result<()>;
+    /// Builds the entries of a building index for up to limit rows following
+    /// the given primary key, or from the first row. Returns the primary key
+    /// of the last row built, or None once all rows are built, in which case
+    /// the index is marked as built.
+    fn build_index(
+        &mut self,
+        index: &str,
+        after: Option<Value>,
+        limit: usize,
+    ) -> Result<Option<Value>>;
+    /// Drops an existing index, or errors if it does not exist
+    fn drop_index(&mut self, index: &str) -> Result<()>;
+    /// Creates a new trigger on an existing table
+    fn create_trigger(&mut self, table: &str, trigger: Trigger) -> Result<()>;
+    /// Drops a trigger from a table, or errors if it does not exist
+    fn drop_trigger(&mut self, table: &str, trigger: &str) -> Result<()>;
+    /// Sets or removes the comment on a table, or on one of its columns
+    fn set_comment(
+        &mut self,
+        table: &str,
+        column: Option<&str>,
+        comment: Option<String>,
+    ) -> Result<()>;
     /// Reads a table, if it exists
     fn read_table(&self, table: &str) -> Result<Option<Table>>;
     /// Iterates over all tables
     fn scan_tables(&self) -> Result<Tables>;
+    /// Creates a new view
+    fn create_view(&mut self, view: View) -> Result<()>;
+    /// Deletes an existing view, or errors if it does not exist
+    fn delete_view(&mut self, view: &str) -> Result<()>;
+    /// Reads a view, if it exists
+    fn read_view(&self, view: &str) -> Result<Option<View>>;
+    /// Iterates over all views
+    fn scan_views(&self) -> Result<Views>;
+    /// Creates a new sequence
+    fn create_sequence(&mut self, sequence: Sequence) -> Result<()>;
+    /// Deletes an existing sequence, or errors if it does not exist
+    fn delete_sequence(&mut self, sequence: &str) -> Result<()>;
+    /// Reads a sequence, if it exists
+    fn read_sequence(&self, sequence: &str) -> Result<Option<Sequence>>;
+    /// Lists all sequences, in name order
+    fn scan_sequences(&self) -> Result<Vec<Sequence>>;
+    /// Creates a new enum type
+    fn create_type(&mut self, enum_type: EnumType) -> Result<()>;
+    /// Deletes an existing enum type, or errors if it does not exist or is
+    /// used by a column
+    fn delete_type(&mut self, name: &str) -> Result<()>;
+    /// Reads an enum type, if it exists
+    fn read_type(&self, name: &str) -> Result<Option<EnumType>>;
+    /// Lists all enum types, in name order
+    fn scan_types(&self) -> Result<Vec<EnumType>>;
+    /// Stores a table's statistics, replacing any existing statistics
+    fn write_statistics(&mut self, table: &str, statistics: Statistics) -> Result<()>;
+    /// Reads a table's statistics, if the table has been analyzed
+    fn read_statistics(&self, table: &str) -> Result<Option<Statistics>>;
+    /// Returns the schema version, which is incremented by every change to
+    /// tables, indexes or views
+    fn schema_version(&self) -> Result<u64>;
+    /// Creates a new database
+    fn create_database(&mut self, database: &str) -> Result<()>;
+    /// Lists all databases created via create_database(), in name order
+    fn scan_databases(&self) -> Result<Vec<String>>;
+    /// Creates a new user
+    fn create_user(&mut self, user: User) -> Result<()>;
+    /// Replaces an existing user, or errors if it does not exist
+    fn alter_user(&mut self, user: User) -> Result<()>;
+    /// Deletes an existing user, or errors if it does not exist
+    fn delete_user(&mut self, name: &str) -> Result<()>;
+    /// Reads a user, if it exists
+    fn read_user(&self, name: &str) -> Result<Option<User>>;
+    /// Lists all users and roles, in name order
+    fn scan_users(&self) -> Result<Vec<User>>;
+    /// Replaces the privileges granted to a user or role on a table, removing
+    /// the grant if empty. Errors if the table does not exist.
+    fn set_privileges(
+        &mut self,
+        table: &str,
+        grantee: &str,
+        privileges: BTreeSet<Privilege>,
+    ) -> Result<()>;
+
+    /// Returns true if the database exists
+    fn has_database(&self, database: &str) -> Result<bool> {
+        Ok(database == DEFAULT_DATABASE || self.scan_databases()?.iter().any(|d| d == database))
+    }
+
+    /// Resolves a table name given in the current database to its name in the
+    /// catalog, see qualify_name(). Unqualified names of temporary tables,
+    /// which don't belong to a database, take precedence.
+    fn resolve_table(&self, table: &str, database: &str) -> Result<String> {
+        if database != DEFAULT_DATABASE
+            && !table.contains('.')
+            && self.read_table(table)?.is_some_and(|t| t.temporary)
+        {
+            return Ok(table.to_string());
+        }
+        Ok(qualify_name(table, database))
+    }
+
+    /// Resolves an index name given in the current database to its name in
+    /// the catalog, like resolve_table().
+    fn resolve_index(&self, index: &str, database: &str) -> Result<String> {
+        if database != DEFAULT_DATABASE
+            && !index.contains('.')
+            && self.read_index_table(index)?.is_some_and(|t| t.temporary)
+        {
+            return Ok(index.to_string());
+        }
+        Ok(qualify_name(index, database))
+    }
+
+    /// Returns true if a user has a privilege on a table, or any privilege if
+    /// None, either as the table's owner, via a grant to the user, or via a
+    /// grant to one of the user's roles. The root user and temporary tables
+    /// are exempt.
+    fn has_privilege(
+        &self,
+        user: &str,
+        table: &Table,
+        privilege: Option<Privilege>,
+    ) -> Result<bool> {
+        if user == User::ROOT || table.temporary || table.owner.as_deref() == Some(user) {
+            return Ok(true);
+        }
+        let roles = self.read_user(user)?.map(|u| u.roles).unwrap_or_default();
+        Ok(table.privileges.iter().any(|(grantee, privileges)| {
+            privilege.is_none_or(|p| privileges.contains(&p))
+                && (grantee == user || roles.contains(grantee))
+        }))
+    }
+
+    /// Checks that a user has a privilege on a table, see has_privilege().
+    /// Errors with Error::PermissionDenied otherwise, or if the table does
+    /// not exist.
+    fn authorize(&self, user: &str, table: &str, privilege: Privilege) -> Result<()> {
+        let table = self.must_read_table(table)?;
+        if self.has_privilege(user, &table, Some(privilege))? {
+            return Ok(());
+        }
+        Err(Error::PermissionDenied(format!(
+            "{} on table {} for user {}",
+            privilege, table.name, user
+        )))
+    }
+
+    /// Checks that a user has any privilege on a table, as required to see
+    /// its schema, e.g. via DESCRIBE.
+    fn authorize_any(&self, user: &str, table: &str) -> Result<()> {
+        let table = self.must_read_table(table)?;
+        if self.has_privilege(user, &table, None)? {
+            return Ok(());
+        }
+        Err(Error::PermissionDenied(format!(
+            "no privileges on table {} for user {}",
+            table.name, user
+        )))
+    }
+
+    /// Checks that a user owns a table, or is the root user, as required to
+    /// grant or revoke privileges on it.
+    fn authorize_owner(&self, user: &str, table: &str) -> Result<()> {
+        let table = self.must_read_table(table)?;
+        if user == User::ROOT || table.temporary || table.owner.as_deref() == Some(user) {
+            return Ok(());
+        }
+        Err(Error::PermissionDenied(format!("user {} does not own table {}", user, table.name)))
+    }
+
+    /// Checks that a user owns a view, or is the root user, as required to
+    /// drop it.
+    fn authorize_view_owner(&self, user: &str, view: &str) -> Result<()> {
+        let view = self
+            .read_view(view)?
+            .ok_or_else(|| Error::Value(format!("View {} does not exist", view)))?;
+        if view.owned_by(user) {
+            return Ok(());
+        }
+        Err(Error::PermissionDenied(format!("user {} does not own view {}", user, view.name)))
+    }
+
+    /// Checks that a user owns a sequence, or is the root user, as required to
+    /// use it via NEXTVAL() or SETVAL(), or drop it.
+    fn authorize_sequence_owner(&self, user: &str, sequence: &str) -> Result<()> {
+        let sequence = self
+            .read_sequence(sequence)?
+            .ok_or_else(|| Error::Value(format!("Sequence {} does not exist", sequence)))?;
+        if user == User::ROOT || sequence.owner.as_deref() == Some(user) {
+            return Ok(());
+        }
+        Err(Error::PermissionDenied(format!(
+            "user {} does not own sequence {}",
+            user, sequence.name
+        )))
+    }
+
+    /// Checks that a user owns an enum type, or is the root user, as required
+    /// to drop it.
+    fn authorize_type_owner(&self, user: &str, name: &str) -> Result<()> {
+        let enum_type = self
+            .read_type(name)?
+            .ok_or_else(|| Error::Value(format!("Type {} does not exist", name)))?;
+        if user == User::ROOT || enum_type.owner.as_deref() == Some(user) {
+            return Ok(());
+        }
+        Err(Error::PermissionDenied(format!("user {} does not own type {}", user, enum_type.name)))
+    }
 
     /// Reads a table, and errors if it does not exist
     fn must_read_table(&self, table: &str) -> Result<Table> {
@@ -23,6 +257,12 @@ pub trait Catalog {
             .ok_or_else(|| Error::Value(format!("Table {} does not exist", table)))
     }
 
+    /// Returns the table containing the given index (created via CREATE
+    /// INDEX), if it exists. Index names are unique across all tables.
+    fn read_index_table(&self, index: &str) -> Result<Option<Table>> {
+        Ok(self.scan_tables()?.find(|t| t.indexes.iter().any(|i| i.name == index)))
+    }
+
     /// Returns all references to a table, as table,column pairs.
     fn table_references(&self, table: &str, with_self: bool) -> Result<Vec<(String, Vec<String>)>> {
         Ok(self
@@ -46,17 +286,52 @@ pub trait Catalog {
 /// A table scan iterator
 pub type Tables = Box<dyn DoubleEndedIterator<Item = Table> + Send>;
 
+/// A view scan iterator
+pub type Views = Box<dyn DoubleEndedIterator<Item = View> + Send>;
+
 /// A table schema
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Table {
     pub name: String,
     pub columns: Vec<Column>,
+    /// Indexes created via CREATE INDEX or table UNIQUE constraints. Columns
+    /// declared with INDEX or UNIQUE have a separate single-column index named
+    /// after the column.
+    pub indexes: Vec<Index>,
+    /// The schema version, incremented whenever the table is altered
+    pub version: u64,
+    /// Whether rows written with the previous schema version are valid in
+    /// this one, see Compatibility
+    pub compatibility: Compatibility,
+    /// Whether the table is a temporary table, local to the session that
+    /// created it
+    pub temporary: bool,
+    /// A comment describing the table, set via COMMENT ON TABLE
+    pub comment: Option<String>,
+    /// Row-level triggers created via CREATE TRIGGER, in creation order.
+    pub triggers: Vec<Trigger>,
+    /// The user that created the table, if the session was authenticated.
+    /// The owner has all privileges on the table, and can grant them.
+    pub owner: Option<String>,
+    /// Privileges granted on the table via GRANT, by user or role name.
+    pub privileges: BTreeMap<String, BTreeSet<Privilege>>,
 }
 
 impl Table {
     /// Creates a new table schema
     pub fn new(name: String, columns: Vec<Column>) -> Result<Self> {
-        let table = Self { name, columns };
+        let table = Self {
+            name,
+            columns,
+            indexes: Vec::new(),
+            version: 0,
+            compatibility: Compatibility::Compatible,
+            temporary: false,
+            comment: None,
+            triggers: Vec::new(),
+            owner: None,
+            privileges: BTreeMap::new(),
+        };
         Ok(table)
     }
 
@@ -74,6 +349,42 @@ impl Table {
         })
     }
 
+    /// Returns all indexes of the table, including single-column indexes for
+    /// columns declared with INDEX or UNIQUE.
+    pub fn get_indexes(&self) -> Vec<Index> {
+        self.columns
+            .iter()
+            .filter(|c| c.index || c.unique && !c.primary_key)
+            .map(|c| Index {
+                name: c.name.clone(),
+                columns: vec![c.name.clone()],
+                expressions: Vec::new(),
+                unique: c.unique && !c.primary_key,
+                include: Vec::new(),
+                building: false,
+                deferred: c.deferred,
+                fulltext: false,
+            })
+            .chain(self.indexes.iter().cloned())
+            .collect()
+    }
+
+    /// Fetches an index by name, including column indexes
+    pub fn get_index(&self, name: &str) -> Result<Index> {
+        self.get_indexes()
+            .into_iter()
+            .find(|i| i.name == name)
+            .ok_or_else(|| Error::Value(format!("No index {} on table {}", name, self.name)))
+    }
+
+    /// Fetches a trigger by name
+    pub fn get_trigger(&self, name: &str) -> Result<&Trigger> {
+        self.triggers
+            .iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| Error::Value(format!("No trigger {} on table {}", name, self.name)))
+    }
+
     /// Returns the primary key column of the table
     pub fn get_primary_key(&self) -> Result<&Column> {
         self.columns
@@ -94,6 +405,63 @@ impl Table {
         .ok_or_else(|| Error::Value("Primary key value not found for row".into()))
     }
 
+    /// Pads a stored row with default values for any columns that were added
+    /// after it was written. Added columns with constant defaults are not
+    /// back-filled in storage, so older rows may be shorter than the current
+    /// schema. Rows written with the previous schema version may also still
+    /// contain a dropped column, which is removed. Enum values are stored as
+    /// their ordinals, see compact_row(), and are converted to their labels.
+    pub fn pad_row(&self, mut row: Row) -> Result<Row> {
+        if let Compatibility::Dropped(position) = self.compatibility {
+            if row.len() == self.columns.len() + 1 {
+                row.remove(position);
+            }
+        }
+        if row.len() > self.columns.len() {
+            return Err(Error::Internal(format!("Invalid row size for table {}", self.name)));
+        }
+        for (value, column) in row.iter_mut().zip(&self.columns) {
+            if let (DataType::Enum(name, labels), Value::Integer(ordinal)) =
+                (&column.datatype, &value)
+            {
+                let label = usize::try_from(*ordinal).ok().and_then(|i| labels.get(i));
+                *value = Value::String(label.cloned().ok_or_else(|| {
+                    Error::Internal(format!("Invalid ordinal {} for enum {}", ordinal, name))
+                })?);
+            }
+        }
+        for column in self.columns.iter().skip(row.len()) {
+            match &column.default {
+                Some(Expression::Constant(default)) => row.push(default.clone()),
+                Some(_) | None => {
+                    return Err(Error::Internal(format!(
+                        "No default value for column {}",
+                        column.name
+                    )))
+                }
+            }
+        }
+        Ok(row)
+    }
+
+    /// Converts a row's enum values to their ordinals for storage, which is
+    /// more compact than their labels. Rows are converted back by pad_row().
+    pub fn compact_row<'a>(&self, row: &'a [Value]) -> Result<Cow<'a, [Value]>> {
+        let mut row = Cow::Borrowed(row);
+        for (i, column) in self.columns.iter().enumerate() {
+            let (DataType::Enum(name, labels), Some(Value::String(label))) =
+                (&column.datatype, row.get(i))
+            else {
+                continue;
+            };
+            let ordinal = labels.iter().position(|l| l == label).ok_or_else(|| {
+                Error::Value(format!("Invalid value {} for enum {}", label, name))
+            })?;
+            row.to_mut()[i] = Value::Integer(ordinal as i64);
+        }
+        Ok(row)
+    }
+
     /// Validates the table schema
     pub fn validate(&self, txn: &mut dyn Transaction) -> Result<()> {
         if self.columns.is_empty() {
@@ -107,9 +475,59 @@ impl Table {
         for column in &self.columns {
             column.validate(self, txn)?;
         }
+        let indexes = self.get_indexes();
+        for (i, index) in indexes.iter().enumerate() {
+            if indexes[..i].iter().any(|other| other.name == index.name) {
+                return Err(Error::Value(format!(
+                    "Index {} already exists on table {}",
+                    index.name, self.name
+                )));
+            }
+            index.validate(self)?;
+        }
         Ok(())
     }
 
+    /// Computes the values of a row's generated columns from its other values,
+    /// casting them to the columns' datatypes. Integer and float values of
+    /// decimal columns are also converted to decimals, since numeric literals
+    /// like 1.5 are floats.
+    pub fn generate_row(&self, row: &mut Row) -> Result<()> {
+        for (i, column) in self.columns.iter().enumerate() {
+            if let Some(generated) = &column.generated {
+                row[i] = generated.evaluate(Some(row))?.cast(&column.datatype)?;
+            } else if column.datatype == DataType::Decimal
+                && matches!(row[i], Value::Integer(_) | Value::Float(_))
+            {
+                row[i] = row[i].clone().cast(&DataType::Decimal)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the first column of the given enum type, or an array of it, if
+    /// any.
+    pub fn get_type_column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| {
+            let datatype = match &c.datatype {
+                DataType::Array(element) => element,
+                datatype => datatype,
+            };
+            matches!(datatype, DataType::Enum(n, _) if n == name)
+        })
+    }
+
+    /// Returns the generated columns whose expressions refer to the given
+    /// column.
+    pub fn get_generated_by(&self, column: &str) -> Result<Vec<&Column>> {
+        let position = self.get_column_index(column)?;
+        Ok(self
+            .columns
+            .iter()
+            .filter(|c| c.generated.as_ref().is_some_and(|g| g.fields().contains(&position)))
+            .collect())
+    }
+
     /// Validates a row
     pub fn validate_row(&self, row: &[Value], txn: &mut dyn Transaction) -> Result<()> {
         if row.len() != self.columns.len() {
@@ -121,16 +539,319 @@ impl Table {
         }
         Ok(())
     }
+
+    /// Returns true if the table has constraints deferred until commit
+    pub fn has_deferred(&self) -> bool {
+        self.columns.iter().any(|c| c.deferred)
+    }
+
+    /// Validates a row's deferred constraints, once the transaction commits
+    pub fn validate_deferred(&self, row: &[Value], txn: &mut dyn Transaction) -> Result<()> {
+        let pk = self.get_row_key(row)?;
+        for (column, value) in self.columns.iter().zip(row.iter()) {
+            if column.deferred {
+                column.validate_reference(self, &pk, value, txn)?;
+            }
+        }
+        for index in self.get_indexes().into_iter().filter(|i| i.deferred && i.unique) {
+            let values = index.get_row_key(self, row)?;
+            let ids = txn.read_index(&self.name, &index.name, &values)?;
+            index.check_unique(&values, &ids, &pk)?;
+        }
+        Ok(())
+    }
+}
+
+/// A change to an existing column, via ALTER TABLE ... ALTER COLUMN.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum ColumnChange {
+    /// Changes the column's datatype, casting existing values to it.
+    Type(DataType),
+    /// Sets whether the column allows NULL values.
+    Nullable(bool),
+}
+
+impl Display for ColumnChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Type(datatype) => write!(f, "TYPE {}", datatype),
+            Self::Nullable(true) => write!(f, "DROP NOT NULL"),
+            Self::Nullable(false) => write!(f, "SET NOT NULL"),
+        }
+    }
+}
+
+/// Whether rows written with a table's previous schema version are valid in
+/// the current one. Transactions that began before a schema change still use
+/// the previous version, and can only commit writes to the table after the
+/// change if it's compatible. Otherwise, they fail with a serialization error.
+/// Transactions that are more than one version behind always fail.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub enum Compatibility {
+    /// Rows are valid, possibly lacking added columns with constant defaults.
+    #[default]
+    Compatible,
+    /// Rows contain a value for a column that was dropped at the given
+    /// position, which is removed when read.
+    Dropped(usize),
+    /// Rows may be invalid, e.g. because they aren't indexed by a new index.
+    Incompatible,
 }
 
 impl Display for Table {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "CREATE TABLE {} (\n{}\n)",
-            format_ident(&self.name),
+            "CREATE {}TABLE {} (\n{}\n)",
+            if self.temporary { "TEMPORARY " } else { "" },
+            format_name(&self.name),
             self.columns.iter().map(|c| format!("  {}", c)).collect::<Vec<String>>().join(",\n")
-        )
+        )?;
+        for index in &self.indexes {
+            write!(
+                f,
+                ";\nCREATE {}INDEX {} ON {} ({})",
+                match (index.unique, index.fulltext) {
+                    (true, _) => "UNIQUE ",
+                    (false, true) => "FULLTEXT ",
+                    (false, false) => "",
+                },
+                format_name(&index.name),
+                format_name(&self.name),
+                index
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| match index.get_expression(i) {
+                        Some(expr) => format!("({})", expr),
+                        None => format_ident(c),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+            if !index.include.is_empty() {
+                write!(
+                    f,
+                    " INCLUDE ({})",
+                    index.include.iter().map(|c| format_ident(c)).collect::<Vec<_>>().join(", ")
+                )?;
+            }
+            if index.building {
+                write!(f, " /* building */")?;
+            }
+        }
+        for trigger in &self.triggers {
+            write!(
+                f,
+                ";\nCREATE TRIGGER {} {} {} ON {} FOR EACH ROW EXECUTE {}",
+                format_ident(&trigger.name),
+                trigger.timing,
+                trigger.event,
+                format_name(&self.name),
+                trigger.action
+            )?;
+        }
+        if let Some(comment) = &self.comment {
+            write!(
+                f,
+                ";\nCOMMENT ON TABLE {} IS {}",
+                format_name(&self.name),
+                format_string(comment)
+            )?;
+        }
+        for column in &self.columns {
+            if let Some(comment) = &column.comment {
+                write!(
+                    f,
+                    ";\nCOMMENT ON COLUMN {}.{} IS {}",
+                    format_name(&self.name),
+                    format_ident(&column.name),
+                    format_string(comment)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A secondary index, mapping the values of one or more columns or
+/// expressions to the primary keys of the rows containing them.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Index {
+    /// Index name
+    pub name: String,
+    /// The indexed columns, in key order. For key expressions, this is the
+    /// expression text.
+    pub columns: Vec<String>,
+    /// The key expressions by key position, with None for column keys, e.g.
+    /// for CREATE INDEX ON t ((LOWER(email))). Empty if there are none.
+    pub expressions: Vec<Option<Expression>>,
+    /// Whether the indexed values must be unique. Keys containing NULL are
+    /// exempt, so any number of rows can have NULL values.
+    pub unique: bool,
+    /// Non-key columns stored in the index entries via INCLUDE, such that
+    /// queries can be answered from the index alone.
+    pub include: Vec<String>,
+    /// Whether the index is still being built by CREATE INDEX. Writes
+    /// maintain building indexes, but queries don't use them. If the build is
+    /// interrupted, e.g. by a crash, the index must be dropped.
+    pub building: bool,
+    /// Whether uniqueness is checked when the transaction commits rather than
+    /// on each write, i.e. for a column declared UNIQUE INITIALLY DEFERRED.
+    pub deferred: bool,
+    /// Whether this is a FULLTEXT index on a STRING column, whose entries
+    /// map the terms of the column's values to the rows containing them, see
+    /// fulltext::tokenize(). They're stored under their own key prefix.
+    pub fulltext: bool,
+}
+
+impl Index {
+    /// Validates the index schema
+    pub fn validate(&self, table: &Table) -> Result<()> {
+        if self.columns.is_empty() {
+            return Err(Error::Value(format!("Index {} has no columns", self.name)));
+        }
+        if self.fulltext {
+            return self.validate_fulltext(table);
+        }
+        let columns: Vec<&String> = self.columns.iter().chain(&self.include).collect();
+        for (i, column) in columns.iter().enumerate() {
+            match self.get_expression(i) {
+                Some(expr) => self.validate_expression(table, expr)?,
+                None => {
+                    let datatype = &table.get_column(column)?.datatype;
+                    if let Some(kind) = unindexable(datatype).filter(|_| i < self.columns.len()) {
+                        return Err(Error::Value(format!(
+                            "{} column {} can't be indexed",
+                            kind, column
+                        )));
+                    }
+                }
+            }
+            if columns[..i].contains(column) {
+                return Err(Error::Value(format!(
+                    "Column {} given multiple times for index {}",
+                    column, self.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates a FULLTEXT index, which must index a single STRING column.
+    fn validate_fulltext(&self, table: &Table) -> Result<()> {
+        if self.columns.len() != 1 || !self.expressions.is_empty() || !self.include.is_empty() {
+            return Err(Error::Value(format!(
+                "FULLTEXT index {} must have a single column",
+                self.name
+            )));
+        }
+        let column = table.get_column(&self.columns[0])?;
+        if column.datatype != DataType::String {
+            return Err(Error::Value(format!(
+                "FULLTEXT index {} can't index {} column {}",
+                self.name, column.datatype, column.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Validates a key expression, which must be deterministic and refer to
+    /// columns of the table.
+    fn validate_expression(&self, table: &Table, expr: &Expression) -> Result<()> {
+        if expr.contains(&|e| matches!(e, Expression::Function(f, _) if !f.is_deterministic())) {
+            return Err(Error::Value(format!(
+                "Index {} can't use non-deterministic functions",
+                self.name
+            )));
+        }
+        if let Some(field) = expr.fields().into_iter().find(|f| *f >= table.columns.len()) {
+            return Err(Error::Internal(format!("Invalid field {} in index {}", field, self.name)));
+        }
+        Ok(())
+    }
+
+    /// Returns the key expression at the given key position, if any.
+    pub fn get_expression(&self, i: usize) -> Option<&Expression> {
+        self.expressions.get(i).and_then(|e| e.as_ref())
+    }
+
+    /// Returns true if the index contains the given column, either as a key
+    /// column, an included column, or in a key expression.
+    pub fn has_column(&self, table: &Table, column: &str) -> bool {
+        let position = table.get_column_index(column).ok();
+        self.columns.iter().chain(&self.include).any(|c| c == column)
+            || self
+                .expressions
+                .iter()
+                .flatten()
+                .any(|e| e.fields().into_iter().any(|f| Some(f) == position))
+    }
+
+    /// Returns the values of the included columns of a row.
+    pub fn get_row_include(&self, table: &Table, row: &[Value]) -> Result<Vec<Value>> {
+        self.include
+            .iter()
+            .map(|c| {
+                row.get(table.get_column_index(c)?).cloned().ok_or_else(|| {
+                    Error::Value(format!("Index value for column {} not found for row", c))
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the index key of a row, i.e. the values of the indexed columns
+    /// and key expressions.
+    pub fn get_row_key(&self, table: &Table, row: &[Value]) -> Result<Vec<Value>> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                if let Some(expr) = self.get_expression(i) {
+                    return expr.evaluate(Some(&row.to_vec()));
+                }
+                row.get(table.get_column_index(c)?).cloned().ok_or_else(|| {
+                    Error::Value(format!("Index value for column {} not found for row", c))
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the distinct terms of a row's value for a FULLTEXT index. NULL
+    /// values have no terms.
+    pub fn get_row_terms(&self, table: &Table, row: &[Value]) -> Result<BTreeSet<String>> {
+        match self.get_row_key(table, row)?.as_slice() {
+            [Value::String(text)] => Ok(fulltext::terms(text)),
+            [Value::Null] => Ok(BTreeSet::new()),
+            values => Err(Error::Internal(format!(
+                "Invalid FULLTEXT index {} value {:?}",
+                self.name, values
+            ))),
+        }
+    }
+
+    /// Checks whether a key conflicts with a unique index entry, given the
+    /// primary keys of the rows with that key and the primary key of the row
+    /// being written.
+    pub fn check_unique(&self, values: &[Value], ids: &HashSet<Value>, id: &Value) -> Result<()> {
+        if !self.unique || values.contains(&Value::Null) || ids.iter().all(|i| i == id) {
+            return Ok(());
+        }
+        match values {
+            [value] if self.name == self.columns[0] => Err(Error::Value(format!(
+                "Unique value {} already exists for column {}",
+                value, self.name
+            ))),
+            [value] => Err(Error::Value(format!(
+                "Unique value {} already exists for index {}",
+                value, self.name
+            ))),
+            values => Err(Error::Value(format!(
+                "Unique value ({}) already exists for index {}",
+                values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "),
+                self.name
+            ))),
+        }
     }
 }
 
@@ -141,18 +862,38 @@ pub struct Column {
     pub name: String,
     /// Column datatype
     pub datatype: DataType,
+    /// The collation of a STRING column, which determines how its values
+    /// compare and sort, including in its indexes.
+    pub collation: Collation,
     /// Whether the column is a primary key
     pub primary_key: bool,
     /// Whether the column allows null values
     pub nullable: bool,
-    /// The default value of the column
-    pub default: Option<Value>,
+    /// The default value of the column. This is a constant, unless it calls
+    /// functions (e.g. NOW()), in which case it is evaluated for each row.
+    pub default: Option<Expression>,
+    /// The expression computing a generated column's value from the other
+    /// columns of its row, via GENERATED ALWAYS AS. It is evaluated and stored
+    /// whenever the row is written.
+    pub generated: Option<Expression>,
     /// Whether the column should only take unique values
     pub unique: bool,
     /// The table which is referenced by this foreign key
     pub references: Option<String>,
+    /// The action to take when a referenced row is deleted
+    pub on_delete: ReferenceAction,
+    /// The action to take when a referenced row's primary key is updated
+    pub on_update: ReferenceAction,
+    /// Whether the column's unique and foreign key constraints are checked
+    /// when the transaction commits rather than by each statement
+    pub deferred: bool,
     /// Whether the column should be indexed
     pub index: bool,
+    /// Whether the column takes the next value of an auto-incrementing
+    /// sequence when not given a value
+    pub auto_increment: bool,
+    /// A comment describing the column, set via COMMENT ON COLUMN
+    pub comment: Option<String>,
 }
 
 impl Column {
@@ -166,10 +907,62 @@ impl Column {
             return Err(Error::Value(format!("Primary key {} must be unique", self.name)));
         }
 
-        // Validate default value
+        // Validate collation. Primary keys are stored and referenced by their exact value, so
+        // they can't be collated.
+        if self.collation != Collation::Binary {
+            if self.datatype != DataType::String {
+                return Err(Error::Value(format!(
+                    "Collation {} can't be used for {} column {}",
+                    self.collation, self.datatype, self.name
+                )));
+            }
+            if self.primary_key {
+                return Err(Error::Value(format!(
+                    "Primary key {} can't have a collation",
+                    self.name
+                )));
+            }
+        }
+
+        // Validate arrays and intervals, which index keys can't contain.
+        if let Some(kind) = unindexable(&self.datatype) {
+            if self.primary_key || self.unique || self.index {
+                return Err(Error::Value(format!(
+                    "{} column {} can't be indexed",
+                    kind, self.name
+                )));
+            }
+        }
+
+        // Validate auto-increment
+        if self.auto_increment {
+            if self.datatype != DataType::Integer {
+                return Err(Error::Value(format!(
+                    "Auto-increment column {} must be INTEGER, got {}",
+                    self.name, self.datatype
+                )));
+            }
+            if self.default.is_some() {
+                return Err(Error::Value(format!(
+                    "Auto-increment column {} can't have a default value",
+                    self.name
+                )));
+            }
+        }
+
+        // Validate default value. Non-constant defaults are evaluated once to check their type,
+        // but sequence functions would consume sequence values, so they're replaced by an
+        // integer.
         if let Some(default) = &self.default {
-            if let Some(datatype) = default.datatype() {
-                if datatype != self.datatype {
+            let default = default.clone().transform(&Ok, &|e| match e {
+                Expression::Function(f, _) if f.is_sequence() => {
+                    Ok(Expression::Constant(Value::Integer(1)))
+                }
+                e => Ok(e),
+            })?;
+            let value = default.evaluate(None)?;
+            if let Some(datatype) = value.datatype() {
+                if !has_datatype(&value, &self.datatype)? {
                     return Err(Error::Value(format!(
                         "Default value for column {} has datatype {}, must be {}",
                         self.name, datatype, self.datatype
@@ -181,13 +974,57 @@ impl Column {
                     self.name
                 )));
             }
-        } else if self.nullable {
+        } else if self.nullable && !self.auto_increment && self.generated.is_none() {
             return Err(Error::Value(format!(
                 "Nullable column {} must have a default value",
                 self.name
             )));
         }
 
+        // Validate generated column. Its value is computed from the rest of the row whenever
+        // it's written, so it can only depend on other columns that aren't generated.
+        if let Some(generated) = &self.generated {
+            if self.primary_key {
+                return Err(Error::Value(format!(
+                    "Primary key {} can't be a generated column",
+                    self.name
+                )));
+            }
+            if self.default.is_some() || self.auto_increment {
+                return Err(Error::Value(format!(
+                    "Generated column {} can't have a default value",
+                    self.name
+                )));
+            }
+            if generated
+                .contains(&|e| matches!(e, Expression::Function(f, _) if !f.is_deterministic()))
+            {
+                return Err(Error::Value(format!(
+                    "Generated column {} can't use non-deterministic functions",
+                    self.name
+                )));
+            }
+            for field in generated.fields() {
+                let column = table.columns.get(field).ok_or_else(|| {
+                    Error::Internal(format!("Invalid field {} in generated column", field))
+                })?;
+                if column.generated.is_some() {
+                    return Err(Error::Value(format!(
+                        "Generated column {} can't refer to generated column {}",
+                        self.name, column.name
+                    )));
+                }
+            }
+        }
+
+        // Validate deferral
+        if self.deferred && self.references.is_none() && (!self.unique || self.primary_key) {
+            return Err(Error::Value(format!(
+                "Column {} has no unique or foreign key constraint to defer",
+                self.name
+            )));
+        }
+
         // Validate references
         if let Some(reference) = &self.references {
             let target = if reference == &table.name {
@@ -200,6 +1037,14 @@ impl Column {
                     reference, self.name
                 )));
             };
+            for (event, action) in [("DELETE", self.on_delete), ("UPDATE", self.on_update)] {
+                if action == ReferenceAction::SetNull && !self.nullable {
+                    return Err(Error::Value(format!(
+                        "Can't use ON {} SET NULL for non-nullable column {}",
+                        event, self.name
+                    )));
+                }
+            }
             if self.datatype != target.get_primary_key()?.datatype {
                 return Err(Error::Value(format!(
                     "Can't reference {} primary key of table {} from {} column {}",
@@ -226,10 +1071,9 @@ impl Column {
         match value.datatype() {
             None if self.nullable => Ok(()),
             None => Err(Error::Value(format!("NULL value not allowed for column {}", self.name))),
-            Some(ref datatype) if datatype != &self.datatype => Err(Error::Value(format!(
-                "Invalid datatype {} for {} column {}",
-                datatype, self.datatype, self.name
-            ))),
+            Some(ref datatype) if !has_datatype(value, &self.datatype)? => Err(Error::Value(
+                format!("Invalid datatype {} for {} column {}", datatype, self.datatype, self.name),
+            )),
             _ => Ok(()),
         }?;
 
@@ -241,58 +1085,75 @@ impl Column {
             _ => Ok(()),
         }?;
 
-        // Validate outgoing references
-        if let Some(target) = &self.references {
-            match value {
-                Value::Null => Ok(()),
-                Value::Float(f) if f.is_nan() => Ok(()),
-                v if target == &table.name && v == pk => Ok(()),
-                v if txn.read(target, v)?.is_none() => Err(Error::Value(format!(
-                    "Referenced primary key {} in table {} does not exist",
-                    v, target,
-                ))),
-                _ => Ok(()),
-            }?;
-        }
-
-        // Validate uniqueness constraints
-        if self.unique && !self.primary_key && value != &Value::Null {
-            let index = table.get_column_index(&self.name)?;
-            let mut scan = txn.scan(&table.name, None)?;
-            while let Some(row) = scan.next().transpose()? {
-                if row.get(index).unwrap_or(&Value::Null) == value
-                    && &table.get_row_key(&row)? != pk
-                {
-                    return Err(Error::Value(format!(
-                        "Unique value {} already exists for column {}",
-                        value, self.name
-                    )));
-                }
-            }
+        // Validate outgoing references, unless deferred until commit
+        if !self.deferred {
+            self.validate_reference(table, pk, value, txn)?;
         }
 
         Ok(())
     }
+
+    /// Validates that a column value references an existing row, if the
+    /// column is a foreign key
+    pub fn validate_reference(
+        &self,
+        table: &Table,
+        pk: &Value,
+        value: &Value,
+        txn: &mut dyn Transaction,
+    ) -> Result<()> {
+        let Some(target) = &self.references else { return Ok(()) };
+        match value {
+            Value::Null => Ok(()),
+            Value::Float(f) if f.is_nan() => Ok(()),
+            v if target == &table.name && v == pk => Ok(()),
+            v if txn.read(target, v)?.is_none() => Err(Error::Value(format!(
+                "Referenced primary key {} in table {} does not exist",
+                v, target,
+            ))),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl Display for Column {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut sql = format_ident(&self.name);
         sql += &format!(" {}", self.datatype);
+        if self.collation != Collation::Binary {
+            sql += &format!(" COLLATE {}", self.collation);
+        }
         if self.primary_key {
             sql += " PRIMARY KEY";
         }
+        if self.auto_increment {
+            sql += " AUTO_INCREMENT";
+        }
         if !self.nullable && !self.primary_key {
             sql += " NOT NULL";
+        } else if self.nullable && self.auto_increment {
+            sql += " NULL";
         }
         if let Some(default) = &self.default {
             sql += &format!(" DEFAULT {}", default);
         }
+        if let Some(generated) = &self.generated {
+            sql += &format!(" GENERATED ALWAYS AS ({}) STORED", generated);
+        }
         if self.unique && !self.primary_key {
             sql += " UNIQUE";
         }
         if let Some(reference) = &self.references {
             sql += &format!(" REFERENCES {}", reference);
+            if self.on_delete != ReferenceAction::Restrict {
+                sql += &format!(" ON DELETE {}", self.on_delete);
+            }
+            if self.on_update != ReferenceAction::Restrict {
+                sql += &format!(" ON UPDATE {}", self.on_update);
+            }
+        }
+        if self.deferred {
+            sql += " DEFERRABLE INITIALLY DEFERRED";
         }
         if self.index {
             sql += " INDEX";
@@ -300,3 +1161,605 @@ impl Display for Column {
         write!(f, "{}", sql)
     }
 }
+
+/// A foreign key referential action, taken on referencing rows when the
+/// referenced row is deleted or its primary key is updated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub enum ReferenceAction {
+    /// Error if the row is referenced.
+    #[default]
+    Restrict,
+    /// Delete referencing rows, or update their references to the new key.
+    Cascade,
+    /// Set the referencing column to NULL.
+    SetNull,
+}
+
+impl Display for ReferenceAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Restrict => "RESTRICT",
+            Self::Cascade => "CASCADE",
+            Self::SetNull => "SET NULL",
+        })
+    }
+}
+
+/// A row-level trigger, which executes an action for each row written by an
+/// INSERT, UPDATE or DELETE on its table, in the same transaction. The action
+/// can refer to the row's values before and after the write as OLD and NEW.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Trigger {
+    /// Trigger name, unique within its table
+    pub name: String,
+    /// Whether the action executes before or after the row is written
+    pub timing: TriggerTiming,
+    /// The kind of write the trigger fires for
+    pub event: TriggerEvent,
+    /// The action, as SQL text: either SET assignments to the NEW row, or a
+    /// parenthesized INSERT, UPDATE or DELETE statement. Like a view's query,
+    /// it is parsed and planned when used.
+    pub action: String,
+    /// The user that created the trigger, if the session was authenticated.
+    /// The action is authorized as the owner when the trigger is created and
+    /// when it fires, rather than as the user writing the row.
+    pub owner: Option<String>,
+}
+
+/// When a trigger's action executes, relative to the row write.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum TriggerTiming {
+    /// Before the row is written, such that the action can modify it.
+    Before,
+    /// After the row is written.
+    After,
+}
+
+impl Display for TriggerTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Before => "BEFORE",
+            Self::After => "AFTER",
+        })
+    }
+}
+
+/// The kind of row write that fires a trigger.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum TriggerEvent {
+    /// A row is inserted. The action can refer to NEW.
+    Insert,
+    /// A row is updated. The action can refer to OLD and NEW.
+    Update,
+    /// A row is deleted. The action can refer to OLD.
+    Delete,
+}
+
+impl Display for TriggerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Insert => "INSERT",
+            Self::Update => "UPDATE",
+            Self::Delete => "DELETE",
+        })
+    }
+}
+
+/// A table privilege, granted to users and roles via GRANT.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum Privilege {
+    /// Reading rows, including via ANALYZE.
+    Select,
+    /// Inserting rows, including via COPY FROM.
+    Insert,
+    /// Updating rows.
+    Update,
+    /// Deleting rows.
+    Delete,
+    /// Altering or dropping the table, and creating or dropping its indexes
+    /// and triggers.
+    Ddl,
+    /// Creating foreign keys that reference the table.
+    References,
+}
+
+impl Privilege {
+    /// All privileges, as granted by GRANT ALL.
+    pub const ALL: [Privilege; 6] =
+        [Self::Select, Self::Insert, Self::Update, Self::Delete, Self::Ddl, Self::References];
+}
+
+impl Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Select => "SELECT",
+            Self::Insert => "INSERT",
+            Self::Update => "UPDATE",
+            Self::Delete => "DELETE",
+            Self::Ddl => "DDL",
+            Self::References => "REFERENCES",
+        })
+    }
+}
+
+/// A view, i.e. a named query. Views are not materialized, but stored as SQL
+/// text and expanded into the queries that use them during planning.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct View {
+    pub name: String,
+    /// Column names for the query's output columns. May be fewer than the
+    /// query's columns, or empty to use the query's own column names.
+    pub columns: Vec<String>,
+    /// The SELECT query, as SQL text
+    pub query: String,
+    /// The user that created the view, if the session was authenticated.
+    /// Only the owner can drop it.
+    pub owner: Option<String>,
+}
+
+impl Display for View {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CREATE VIEW {}", format_name(&self.name))?;
+        if !self.columns.is_empty() {
+            write!(
+                f,
+                " ({})",
+                self.columns.iter().map(|c| format_ident(c)).collect::<Vec<_>>().join(", ")
+            )?;
+        }
+        write!(f, " AS {}", self.query)
+    }
+}
+
+impl View {
+    /// Returns whether a user owns the view, or is the root user. Only these
+    /// users can see its definition or drop it.
+    pub fn owned_by(&self, user: &str) -> bool {
+        user == User::ROOT || self.owner.as_deref() == Some(user)
+    }
+}
+
+/// A sequence, which generates integers via NEXTVAL(). Only its definition is
+/// stored in the catalog, while its values are allocated outside of
+/// transactions, see Sequences.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Sequence {
+    pub name: String,
+    /// The first value.
+    pub start: i64,
+    /// The difference between consecutive values. Can be negative.
+    pub increment: i64,
+    /// The version of the transaction that created the sequence, set by the
+    /// catalog. Distinguishes the sequence's values from those of a dropped
+    /// sequence with the same name.
+    pub version: u64,
+    /// The user that created the sequence, if the session was authenticated.
+    /// Only the owner can use and drop it.
+    pub owner: Option<String>,
+}
+
+impl Sequence {
+    /// Creates a new sequence, validating its definition.
+    pub fn new(name: String, start: i64, increment: i64) -> Result<Self> {
+        if increment == 0 {
+            return Err(Error::Value(format!("Sequence {} can't have an increment of 0", name)));
+        }
+        Ok(Self { name, start, increment, version: 0, owner: None })
+    }
+}
+
+impl Display for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CREATE SEQUENCE {} START {} INCREMENT {}",
+            format_name(&self.name),
+            self.start,
+            self.increment
+        )
+    }
+}
+
+/// Returns the kind of a datatype whose values can't be used in index keys:
+/// arrays, and intervals since equal intervals can have different parts (e.g.
+/// 1 month and 30 days) and thus different keys.
+fn unindexable(datatype: &DataType) -> Option<&'static str> {
+    match datatype {
+        DataType::Array(_) => Some("Array"),
+        DataType::Interval => Some("Interval"),
+        _ => None,
+    }
+}
+
+/// Checks whether a value has the given column datatype. Enum values are
+/// strings with one of the enum's labels, erroring otherwise, and array
+/// elements are checked individually. NULL has any datatype.
+fn has_datatype(value: &Value, datatype: &DataType) -> Result<bool> {
+    match (value, datatype) {
+        (Value::Null, _) => Ok(true),
+        (Value::String(_), DataType::Enum(..)) => value.clone().cast(datatype).map(|_| true),
+        (Value::Array(elements), DataType::Array(element)) => {
+            for value in elements {
+                if !has_datatype(value, element)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (value, datatype) => Ok(value.datatype().as_ref() == Some(datatype)),
+    }
+}
+
+/// An enum type, created via CREATE TYPE ... AS ENUM. Columns of the type
+/// take one of its labels, and store them as their ordinals. The labels are
+/// copied into the columns' datatypes, so the type can't be dropped while
+/// it's used by a column.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct EnumType {
+    pub name: String,
+    /// The labels, in declaration order.
+    pub labels: Vec<String>,
+    /// The user that created the type, if the session was authenticated.
+    /// Only the owner can drop it.
+    pub owner: Option<String>,
+}
+
+impl EnumType {
+    /// Creates a new enum type, validating its labels.
+    pub fn new(name: String, labels: Vec<String>) -> Result<Self> {
+        if labels.is_empty() {
+            return Err(Error::Value(format!("Enum {} has no labels", name)));
+        }
+        for (i, label) in labels.iter().enumerate() {
+            if labels[..i].contains(label) {
+                return Err(Error::Value(format!(
+                    "Label {} given multiple times for enum {}",
+                    label, name
+                )));
+            }
+        }
+        Ok(Self { name, labels, owner: None })
+    }
+
+    /// Returns the enum's datatype.
+    pub fn datatype(&self) -> DataType {
+        DataType::Enum(self.name.clone(), self.labels.clone())
+    }
+}
+
+impl Display for EnumType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CREATE TYPE {} AS ENUM ({})",
+            format_name(&self.name),
+            self.labels.iter().map(|l| format_string(l)).collect::<Vec<_>>().join(", ")
+        )
+    }
+}
+
+/// A user, created via CREATE USER, which clients authenticate as when the
+/// server has authentication enabled. Roles, created via CREATE ROLE, are
+/// users that can't authenticate, and are used to grant privileges to several
+/// users at once. Users and roles share a namespace, and aren't scoped to a
+/// database.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct User {
+    pub name: String,
+    /// The password hash. Users without a password can't authenticate.
+    pub password: Option<Password>,
+    /// Whether the user can authenticate, i.e. false for roles.
+    pub login: bool,
+    /// The roles granted to the user via GRANT. Roles are not inherited
+    /// transitively, i.e. a role's own roles don't apply to its members.
+    pub roles: BTreeSet<String>,
+}
+
+impl User {
+    /// The name of the built-in superuser, whose password is given in the
+    /// server configuration rather than the catalog.
+    pub const ROOT: &'static str = "root";
+
+    /// Creates a new user, hashing the password if any.
+    pub fn new(name: String, password: Option<&str>) -> Result<Self> {
+        if name.is_empty() {
+            return Err(Error::Value("User name can't be empty".into()));
+        }
+        if name == Self::ROOT {
+            return Err(Error::Value(format!("User name {} is reserved", name)));
+        }
+        let password = password.map(Password::new).transpose()?;
+        Ok(Self { name, password, login: true, roles: BTreeSet::new() })
+    }
+
+    /// Creates a new role, which can't authenticate.
+    pub fn new_role(name: String) -> Result<Self> {
+        Ok(Self { login: false, ..Self::new(name, None)? })
+    }
+
+    /// Returns true if the given password matches the user's password.
+    pub fn verify_password(&self, password: &str) -> bool {
+        self.login && self.password.as_ref().is_some_and(|p| p.verify(password))
+    }
+}
+
+/// A salted PBKDF2-HMAC-SHA256 password hash.
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+pub struct Password {
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+impl Password {
+    /// The number of PBKDF2 iterations for new password hashes.
+    const ITERATIONS: u32 = 100_000;
+
+    /// Hashes a password with a random salt.
+    pub fn new(password: &str) -> Result<Self> {
+        use ring::rand::SecureRandom as _;
+        let mut salt = vec![0; 16];
+        ring::rand::SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| Error::Internal("Failed to generate password salt".into()))?;
+        let mut hash = vec![0; ring::digest::SHA256_OUTPUT_LEN];
+        ring::pbkdf2::derive(
+            ring::pbkdf2::PBKDF2_HMAC_SHA256,
+            Self::ITERATIONS.try_into()?,
+            &salt,
+            password.as_bytes(),
+            &mut hash,
+        );
+        Ok(Self { iterations: Self::ITERATIONS, salt, hash })
+    }
+
+    /// Returns true if the given password matches the hash, in constant time.
+    pub fn verify(&self, password: &str) -> bool {
+        let Ok(iterations) = self.iterations.try_into() else {
+            return false;
+        };
+        ring::pbkdf2::verify(
+            ring::pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &self.salt,
+            password.as_bytes(),
+            &self.hash,
+        )
+        .is_ok()
+    }
+}
+
+/// Password hashes are omitted from debug output.
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Password")
+    }
+}
+
+/// Table statistics, as collected by ANALYZE and used by the optimizer to
+/// estimate the cost of alternative plans. Statistics are a snapshot, and are
+/// not updated as rows are written.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Statistics {
+    /// The number of rows in the table.
+    pub rows: u64,
+    /// Column statistics, by column name.
+    pub columns: BTreeMap<String, ColumnStatistics>,
+}
+
+/// Column statistics.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ColumnStatistics {
+    /// The number of distinct non-NULL values.
+    pub distinct: u64,
+    /// The number of NULL values.
+    pub nulls: u64,
+    /// An equi-depth histogram of the non-NULL values, as the ordered bucket
+    /// bounds, starting with the minimum value and ending with the maximum.
+    /// Each bucket holds roughly the same number of values.
+    pub histogram: Vec<Value>,
+}
+
+/// A virtual table in the information_schema namespace, which describes the
+/// schema via rows generated from the catalog when queried.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum InformationSchema {
+    /// Tables and views, one row each.
+    Tables,
+    /// Table columns, one row each.
+    Columns,
+    /// Secondary indexes, one row per key or included column.
+    Indexes,
+    /// Primary key, unique and foreign key constraints, one row per column.
+    Constraints,
+}
+
+impl InformationSchema {
+    /// The namespace of the virtual tables.
+    pub const NAMESPACE: &'static str = "information_schema";
+
+    /// Looks up a virtual table by its qualified name, e.g.
+    /// information_schema.tables. Names are case-insensitive.
+    pub fn lookup(name: &str) -> Option<Self> {
+        let (namespace, name) = name.split_once('.')?;
+        if !namespace.eq_ignore_ascii_case(Self::NAMESPACE) {
+            return None;
+        }
+        match name.to_lowercase().as_str() {
+            "tables" => Some(Self::Tables),
+            "columns" => Some(Self::Columns),
+            "indexes" => Some(Self::Indexes),
+            "constraints" => Some(Self::Constraints),
+            _ => None,
+        }
+    }
+
+    /// Returns the unqualified table name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Tables => "tables",
+            Self::Columns => "columns",
+            Self::Indexes => "indexes",
+            Self::Constraints => "constraints",
+        }
+    }
+
+    /// Returns the table's column names and datatypes.
+    pub fn columns(&self) -> &'static [(&'static str, DataType)] {
+        match self {
+            Self::Tables => &[
+                ("table_name", DataType::String),
+                ("table_type", DataType::String),
+                ("comment", DataType::String),
+            ],
+            Self::Columns => &[
+                ("table_name", DataType::String),
+                ("column_name", DataType::String),
+                ("ordinal_position", DataType::Integer),
+                ("data_type", DataType::String),
+                ("is_nullable", DataType::Boolean),
+                ("column_default", DataType::String),
+                ("is_auto_increment", DataType::Boolean),
+                ("comment", DataType::String),
+            ],
+            Self::Indexes => &[
+                ("table_name", DataType::String),
+                ("index_name", DataType::String),
+                ("column_name", DataType::String),
+                ("ordinal_position", DataType::Integer),
+                ("is_unique", DataType::Boolean),
+                ("is_included", DataType::Boolean),
+            ],
+            Self::Constraints => &[
+                ("table_name", DataType::String),
+                ("constraint_name", DataType::String),
+                ("constraint_type", DataType::String),
+                ("column_name", DataType::String),
+                ("ordinal_position", DataType::Integer),
+                ("referenced_table", DataType::String),
+                ("on_delete", DataType::String),
+                ("on_update", DataType::String),
+                ("is_deferred", DataType::Boolean),
+            ],
+        }
+    }
+
+    /// Generates the virtual table's rows from the catalog, ordered by table
+    /// name. If a user is given, only tables that the user has any privilege
+    /// on and views that the user owns are included.
+    pub fn rows(&self, catalog: &(impl Catalog + ?Sized), user: Option<&str>) -> Result<Vec<Row>> {
+        let mut rows = Vec::new();
+        for table in catalog.scan_tables()? {
+            if let Some(user) = user {
+                if !catalog.has_privilege(user, &table, None)? {
+                    continue;
+                }
+            }
+            rows.extend(self.table_rows(&table)?);
+        }
+        if *self == Self::Tables {
+            for view in catalog.scan_views()? {
+                if user.is_some_and(|user| !view.owned_by(user)) {
+                    continue;
+                }
+                rows.push(vec![
+                    Value::String(view.name),
+                    Value::String("VIEW".into()),
+                    Value::Null,
+                ]);
+            }
+            rows.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap_or(std::cmp::Ordering::Equal));
+        }
+        Ok(rows)
+    }
+
+    /// Generates the virtual table's rows describing a table.
+    fn table_rows(&self, table: &Table) -> Result<Vec<Row>> {
+        let string = |s: &str| Value::String(s.to_string());
+        let position = |i: usize| Value::Integer(i as i64 + 1);
+        let comment = |c: &Option<String>| c.as_deref().map_or(Value::Null, string);
+        let mut rows = Vec::new();
+        match self {
+            Self::Tables => {
+                let r#type = if table.temporary { "LOCAL TEMPORARY" } else { "BASE TABLE" };
+                rows.push(vec![string(&table.name), string(r#type), comment(&table.comment)]);
+            }
+            Self::Columns => {
+                for (i, column) in table.columns.iter().enumerate() {
+                    rows.push(vec![
+                        string(&table.name),
+                        string(&column.name),
+                        position(i),
+                        string(&column.datatype.to_string()),
+                        Value::Boolean(column.nullable),
+                        column.default.as_ref().map_or(Value::Null, |d| string(&d.to_string())),
+                        Value::Boolean(column.auto_increment),
+                        comment(&column.comment),
+                    ]);
+                }
+            }
+            Self::Indexes => {
+                for index in table.get_indexes() {
+                    let columns = index.columns.iter().map(|c| (c, false));
+                    let include = index.include.iter().map(|c| (c, true));
+                    for (i, (column, included)) in columns.chain(include).enumerate() {
+                        rows.push(vec![
+                            string(&table.name),
+                            string(&index.name),
+                            string(column),
+                            position(i),
+                            Value::Boolean(index.unique),
+                            Value::Boolean(included),
+                        ]);
+                    }
+                }
+            }
+            Self::Constraints => {
+                // Constraints are named like in Postgres, except UNIQUE constraints which are
+                // named after their index.
+                let pk = table.get_primary_key()?;
+                rows.push(vec![
+                    string(&table.name),
+                    Value::String(format!("{}_pkey", table.name)),
+                    string("PRIMARY KEY"),
+                    string(&pk.name),
+                    position(0),
+                    Value::Null,
+                    Value::Null,
+                    Value::Null,
+                    Value::Boolean(false),
+                ]);
+                for index in table.get_indexes().into_iter().filter(|i| i.unique) {
+                    for (i, column) in index.columns.iter().enumerate() {
+                        rows.push(vec![
+                            string(&table.name),
+                            string(&index.name),
+                            string("UNIQUE"),
+                            string(column),
+                            position(i),
+                            Value::Null,
+                            Value::Null,
+                            Value::Null,
+                            Value::Boolean(index.deferred),
+                        ]);
+                    }
+                }
+                for column in &table.columns {
+                    let Some(reference) = &column.references else { continue };
+                    rows.push(vec![
+                        string(&table.name),
+                        Value::String(format!("{}_{}_fkey", table.name, column.name)),
+                        string("FOREIGN KEY"),
+                        string(&column.name),
+                        position(0),
+                        string(reference),
+                        string(&column.on_delete.to_string()),
+                        string(&column.on_update.to_string()),
+                        Value::Boolean(column.deferred),
+                    ]);
+                }
+            }
+        }
+        Ok(rows)
+    }
+}
@@ -0,0 +1,111 @@
+//! Support for one-dimensional arrays of the scalar types. Arrays are built
+//! with the ARRAY[...] constructor, or cast from text literals in the Postgres
+//! format, e.g. '{1,2,NULL}', where elements containing special characters
+//! are double-quoted with backslash escapes. Elements are indexed from 1.
+
+use super::Value;
+
+/// Parses an array text literal into its elements, as strings or None for
+/// NULL. Returns None if invalid.
+pub fn parse(s: &str) -> Option<Vec<Option<String>>> {
+    let inner = s.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let mut elements = Vec::new();
+    if inner.trim().is_empty() {
+        return Some(elements);
+    }
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut element = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => element.push(chars.next()?),
+                    c => element.push(c),
+                }
+            }
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            elements.push(Some(element));
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                match c {
+                    '{' | '}' | '"' => return None,
+                    '\\' => element.push(chars.next()?),
+                    c => element.push(c),
+                }
+            }
+            let element = element.trim_end();
+            if element.is_empty() {
+                return None;
+            }
+            elements.push((!element.eq_ignore_ascii_case("null")).then(|| element.to_string()));
+        }
+        match chars.next() {
+            None => return Some(elements),
+            Some(',') => {}
+            Some(_) => return None,
+        }
+    }
+}
+
+/// Formats array elements as an array text literal, quoting elements as
+/// needed such that parse() yields their string forms.
+pub fn format(values: &[Value]) -> String {
+    let elements: Vec<String> = values
+        .iter()
+        .map(|value| {
+            let s = match value {
+                Value::Null => return "NULL".to_string(),
+                value => value.to_string(),
+            };
+            let special = |c: char| matches!(c, '{' | '}' | ',' | '"' | '\\') || c.is_whitespace();
+            if s.is_empty() || s.eq_ignore_ascii_case("null") || s.contains(special) {
+                format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+            } else {
+                s
+            }
+        })
+        .collect();
+    format!("{{{}}}", elements.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(super::parse("{}"), Some(vec![]));
+        assert_eq!(super::parse(" { } "), Some(vec![]));
+        assert_eq!(super::parse("{1,2, 3 }"), Some(vec![some("1"), some("2"), some("3")]));
+        assert_eq!(super::parse("{a,NULL,null}"), Some(vec![some("a"), None, None]));
+        assert_eq!(
+            super::parse(r#"{"a, b","NULL","",x\,y,"\"q\\"}"#),
+            Some(vec![some("a, b"), some("NULL"), some(""), some("x,y"), some("\"q\\")])
+        );
+        for invalid in ["", "1,2", "{1,2", "{1,,2}", "{,}", "{{1}}", r#"{"a"b}"#, r#"{"a}"#] {
+            assert_eq!(super::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn format_roundtrip() {
+        let values = vec![
+            Value::String("a".into()),
+            Value::Null,
+            Value::String("NULL".into()),
+            Value::String("".into()),
+            Value::String("a, \"b\" {c}\\".into()),
+        ];
+        let s = format(&values);
+        assert_eq!(s, r#"{a,NULL,"NULL","","a, \"b\" {c}\\"}"#);
+        let parsed: Vec<Value> = super::parse(&s)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.map(Value::String).unwrap_or(Value::Null))
+            .collect();
+        assert_eq!(parsed, values);
+    }
+}
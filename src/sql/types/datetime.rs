@@ -0,0 +1,475 @@
+//! Date and time support for the DATE, TIME, TIMESTAMP and INTERVAL types.
+//! Values are naïve (without time zone), with microsecond precision, and
+//! timestamps are taken to be in UTC.
+//!
+//! Values are serialized as i64 days or microseconds since the Unix epoch (or
+//! midnight for times), such that the order-preserving keycode encoding sorts
+//! them chronologically and they can be used in primary keys and indexes.
+//! Intervals are serialized as their i64 months, days and microseconds.
+
+use super::Value;
+use crate::error::{Error, Result};
+
+use chrono::{Datelike as _, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike as _};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+/// The Unix epoch.
+const EPOCH: NaiveDateTime = chrono::DateTime::UNIX_EPOCH.naive_utc();
+
+/// Parses a date string, e.g. 2024-01-31.
+pub fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok()
+}
+
+/// Parses a time string, e.g. 13:45, 13:45:30, or 13:45:30.123456.
+pub fn parse_time(s: &str) -> Option<NaiveTime> {
+    let s = s.trim();
+    NaiveTime::parse_from_str(s, "%H:%M:%S%.f")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+        .ok()
+        .map(truncate_time)
+}
+
+/// Parses a timestamp string, e.g. 2024-01-31 13:45:30.123456. The time can
+/// be separated by a space or T, and defaults to midnight if omitted.
+pub fn parse_timestamp(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    if let Some(date) = parse_date(s) {
+        return Some(date.into());
+    }
+    let (date, time) = s.split_once([' ', 'T'])?;
+    Some(NaiveDateTime::new(parse_date(date)?, parse_time(time)?))
+}
+
+/// The number of microseconds in a second, minute, hour and day.
+const SECOND: i64 = 1_000_000;
+const MINUTE: i64 = 60 * SECOND;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+/// Truncates a time to microsecond precision.
+fn truncate_time(time: NaiveTime) -> NaiveTime {
+    time.with_nanosecond(time.nanosecond() / 1000 * 1000).expect("valid nanosecond")
+}
+
+/// Returns the current UTC timestamp, with microsecond precision.
+pub fn now() -> NaiveDateTime {
+    let now = chrono::Utc::now().naive_utc();
+    NaiveDateTime::new(now.date(), truncate_time(now.time()))
+}
+
+/// Extracts a date or time field from a value, as for EXTRACT(field FROM value).
+/// Dates are taken to be at midnight, and times don't have date fields.
+pub fn extract(field: &str, value: Value) -> Result<Value> {
+    let (date, time) = match value {
+        Value::Interval(interval) => return interval.extract(field),
+        Value::Date(date) => (Some(date), NaiveTime::MIN),
+        Value::Time(time) => (None, time),
+        Value::Timestamp(ts) => (Some(ts.date()), ts.time()),
+        value => return Err(Error::Value(format!("Can't extract {} from {}", field, value))),
+    };
+    let micros = time.nanosecond() as i64 / 1000;
+    Ok(match (field, date) {
+        ("year", Some(date)) => Value::Integer(date.year() as i64),
+        ("quarter", Some(date)) => Value::Integer(date.month0() as i64 / 3 + 1),
+        ("month", Some(date)) => Value::Integer(date.month() as i64),
+        ("week", Some(date)) => Value::Integer(date.iso_week().week() as i64),
+        ("day", Some(date)) => Value::Integer(date.day() as i64),
+        ("dow", Some(date)) => Value::Integer(date.weekday().num_days_from_sunday() as i64),
+        ("doy", Some(date)) => Value::Integer(date.ordinal() as i64),
+        ("hour", _) => Value::Integer(time.hour() as i64),
+        ("minute", _) => Value::Integer(time.minute() as i64),
+        ("second", _) => Value::Integer(time.second() as i64),
+        ("microsecond", _) => Value::Integer(time.second() as i64 * 1_000_000 + micros),
+        ("epoch", Some(date)) => {
+            let micros = micros_since_epoch(NaiveDateTime::new(date, time));
+            Value::Float(micros as f64 / 1_000_000.0)
+        }
+        ("epoch", None) => {
+            Value::Float(time.num_seconds_from_midnight() as f64 + micros as f64 / 1_000_000.0)
+        }
+        ("year" | "quarter" | "month" | "week" | "day" | "dow" | "doy", None) => {
+            return Err(Error::Value(format!("Can't extract {} from TIME", field)))
+        }
+        (field, _) => return Err(Error::Value(format!("Unknown date/time field {}", field))),
+    })
+}
+
+/// Truncates a value to the given precision, as for DATE_TRUNC(field, value).
+/// The result has the same type as the value, and times can't be truncated to
+/// date fields.
+pub fn truncate(field: &str, value: Value) -> Result<Value> {
+    let date_field = match field {
+        "year" | "quarter" | "month" | "week" | "day" => true,
+        "hour" | "minute" | "second" => false,
+        field => return Err(Error::Value(format!("Unknown date/time field {}", field))),
+    };
+    let truncate_date = |date: NaiveDate| -> NaiveDate {
+        match field {
+            "year" => date.with_ordinal(1),
+            "quarter" => date.with_day(1).and_then(|d| d.with_month0(date.month0() / 3 * 3)),
+            "month" => date.with_day(1),
+            "week" => Some(date - Duration::days(date.weekday().num_days_from_monday() as i64)),
+            _ => Some(date),
+        }
+        .expect("valid date")
+    };
+    let truncate_time = |time: NaiveTime| -> NaiveTime {
+        match field {
+            "hour" => NaiveTime::from_hms_opt(time.hour(), 0, 0),
+            "minute" => NaiveTime::from_hms_opt(time.hour(), time.minute(), 0),
+            "second" => NaiveTime::from_hms_opt(time.hour(), time.minute(), time.second()),
+            _ => Some(NaiveTime::MIN),
+        }
+        .expect("valid time")
+    };
+    Ok(match value {
+        Value::Date(date) => Value::Date(truncate_date(date)),
+        Value::Time(_) if date_field => {
+            return Err(Error::Value(format!("Can't truncate TIME to {}", field)))
+        }
+        Value::Time(time) => Value::Time(truncate_time(time)),
+        Value::Timestamp(ts) => {
+            Value::Timestamp(NaiveDateTime::new(truncate_date(ts.date()), truncate_time(ts.time())))
+        }
+        value => return Err(Error::Value(format!("Can't truncate {}", value))),
+    })
+}
+
+/// Returns the number of microseconds since the Unix epoch.
+fn micros_since_epoch(ts: NaiveDateTime) -> i64 {
+    (ts - EPOCH).num_microseconds().expect("timestamp out of range")
+}
+
+/// A time interval, as for the INTERVAL type. Like Postgres, months, days and
+/// microseconds are kept separately, since months vary in length: one month
+/// after January 31 is the last day of February. Intervals are compared and
+/// hashed by their total duration, taking months to be 30 days, such that
+/// 1 month equals 30 days.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub micros: i64,
+}
+
+impl Interval {
+    /// Parses an interval string, as a sequence of quantities with units, e.g.
+    /// '1 year 2 months', '1.5 hours' or '-3 days', optionally followed by a
+    /// time of the form [-]HH:MM[:SS[.ffffff]] and/or 'ago' to negate it.
+    /// Fractional months and days spill over into days and microseconds. The
+    /// Display form is accepted too, e.g. '1 mon 2 days 03:00:00'.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut interval = Self::default();
+        let mut tokens = s.split_whitespace().peekable();
+        tokens.peek()?;
+        while let Some(token) = tokens.next() {
+            if token.eq_ignore_ascii_case("ago") && tokens.peek().is_none() {
+                return interval.checked_neg();
+            }
+            if token.contains(':') {
+                interval = interval
+                    .checked_add(Self { micros: parse_interval_time(token)?, ..Self::default() })?;
+                continue;
+            }
+            // The unit can be attached to the number, e.g. 1day.
+            let split = token.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(token.len());
+            let (number, unit) = match token.split_at(split) {
+                (number, "") => (number, tokens.next()?),
+                (number, unit) => (number, unit),
+            };
+            let unit = match unit.to_lowercase().as_str() {
+                "microsecond" | "microseconds" | "us" => Self { micros: 1, ..Self::default() },
+                "millisecond" | "milliseconds" | "ms" => Self { micros: 1000, ..Self::default() },
+                "second" | "seconds" | "sec" | "secs" | "s" => {
+                    Self { micros: SECOND, ..Self::default() }
+                }
+                "minute" | "minutes" | "min" | "mins" | "m" => {
+                    Self { micros: MINUTE, ..Self::default() }
+                }
+                "hour" | "hours" | "hr" | "hrs" | "h" => Self { micros: HOUR, ..Self::default() },
+                "day" | "days" | "d" => Self { days: 1, ..Self::default() },
+                "week" | "weeks" | "w" => Self { days: 7, ..Self::default() },
+                "month" | "months" | "mon" | "mons" => Self { months: 1, ..Self::default() },
+                "year" | "years" | "yr" | "yrs" | "y" => Self { months: 12, ..Self::default() },
+                _ => return None,
+            };
+            let quantity = match number.parse::<i64>() {
+                Ok(n) => unit.checked_mul(n)?,
+                Err(_) if number.contains(|c: char| c.is_ascii_digit()) => {
+                    unit.checked_mul_float(number.parse().ok()?)?
+                }
+                Err(_) => return None,
+            };
+            interval = interval.checked_add(quantity)?;
+        }
+        Some(interval)
+    }
+
+    /// Returns the total duration in microseconds, taking months to be 30
+    /// days.
+    fn duration(&self) -> i128 {
+        (self.months as i128 * 30 + self.days as i128) * DAY as i128 + self.micros as i128
+    }
+
+    /// Adds two intervals, or returns None on overflow.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        Some(Self {
+            months: self.months.checked_add(other.months)?,
+            days: self.days.checked_add(other.days)?,
+            micros: self.micros.checked_add(other.micros)?,
+        })
+    }
+
+    /// Negates the interval, or returns None on overflow.
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self {
+            months: self.months.checked_neg()?,
+            days: self.days.checked_neg()?,
+            micros: self.micros.checked_neg()?,
+        })
+    }
+
+    /// Multiplies the interval by an integer, or returns None on overflow.
+    pub fn checked_mul(self, factor: i64) -> Option<Self> {
+        Some(Self {
+            months: i32::try_from(self.months as i64 * factor).ok()?,
+            days: i32::try_from(self.days as i64 * factor).ok()?,
+            micros: self.micros.checked_mul(factor)?,
+        })
+    }
+
+    /// Multiplies the interval by a float, or returns None on overflow. As in
+    /// Postgres, fractional months spill over into days (taking months to be
+    /// 30 days), and fractional days into microseconds, rounded.
+    pub fn checked_mul_float(self, factor: f64) -> Option<Self> {
+        let months = self.months as f64 * factor;
+        let days = self.days as f64 * factor + months.fract() * 30.0;
+        let micros = (self.micros as f64 * factor + days.fract() * DAY as f64).round();
+        let fits = |f: f64, min: f64, max: f64| f.is_finite() && f >= min && f <= max;
+        if !fits(months, i32::MIN as f64, i32::MAX as f64)
+            || !fits(days, i32::MIN as f64, i32::MAX as f64)
+            || !fits(micros, i64::MIN as f64, i64::MAX as f64)
+        {
+            return None;
+        }
+        Some(Self { months: months as i32, days: days as i32, micros: micros as i64 })
+    }
+
+    /// Extracts a field from the interval, as for EXTRACT(field FROM value).
+    /// Time fields are taken from the microseconds, and the epoch is the total
+    /// number of seconds, taking months to be 30 days.
+    fn extract(&self, field: &str) -> Result<Value> {
+        Ok(Value::Integer(match field {
+            "year" => self.months as i64 / 12,
+            "month" => self.months as i64 % 12,
+            "day" => self.days as i64,
+            "hour" => self.micros / HOUR,
+            "minute" => self.micros % HOUR / MINUTE,
+            "second" => self.micros % MINUTE / SECOND,
+            "microsecond" => self.micros % MINUTE,
+            "epoch" => return Ok(Value::Float(self.duration() as f64 / SECOND as f64)),
+            "quarter" | "week" | "dow" | "doy" => {
+                return Err(Error::Value(format!("Can't extract {} from INTERVAL", field)))
+            }
+            field => return Err(Error::Value(format!("Unknown date/time field {}", field))),
+        }))
+    }
+}
+
+impl std::fmt::Display for Interval {
+    /// Formats the interval like Postgres, e.g. 1 year 2 mons 3 days 04:05:06.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        let mut part = |n: i64, unit: &str| {
+            if n != 0 {
+                parts.push(format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" }));
+            }
+        };
+        part(self.months as i64 / 12, "year");
+        part(self.months as i64 % 12, "mon");
+        part(self.days as i64, "day");
+        if self.micros != 0 || parts.is_empty() {
+            let sign = if self.micros < 0 { "-" } else { "" };
+            let micros = self.micros.unsigned_abs();
+            let (hours, minutes, seconds) = (
+                micros / HOUR as u64,
+                micros % HOUR as u64 / MINUTE as u64,
+                micros % MINUTE as u64 / SECOND as u64,
+            );
+            let mut time = format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, seconds);
+            if !micros.is_multiple_of(SECOND as u64) {
+                let fraction = format!("{:06}", micros % SECOND as u64);
+                time = format!("{}.{}", time, fraction.trim_end_matches('0'));
+            }
+            parts.push(time);
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl PartialEq for Interval {
+    fn eq(&self, other: &Self) -> bool {
+        self.duration() == other.duration()
+    }
+}
+
+impl Eq for Interval {}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Interval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.duration().cmp(&other.duration())
+    }
+}
+
+impl Hash for Interval {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.duration().hash(state)
+    }
+}
+
+/// Parses the time part of an interval, [-]HH:MM[:SS[.ffffff]], as
+/// microseconds. Hours can exceed 24.
+fn parse_interval_time(s: &str) -> Option<i64> {
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(s) => (-1, s),
+        None => (1, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let digits = |s: &str| -> Option<i64> {
+        (!s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())).then(|| s.parse().ok())?
+    };
+    let mut parts = s.split(':');
+    let hours = digits(parts.next()?)?;
+    let minutes = digits(parts.next()?).filter(|m| *m < 60)?;
+    let (seconds, fraction) = match parts.next() {
+        Some(seconds) => seconds.split_once('.').unwrap_or((seconds, "")),
+        None => ("0", ""),
+    };
+    let seconds = digits(seconds).filter(|s| *s < 60)?;
+    let fraction = match fraction {
+        "" => 0,
+        fraction => digits(&format!("{:0<6}", &fraction[..fraction.len().min(6)]))?,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    let micros =
+        hours.checked_mul(HOUR)?.checked_add(minutes * MINUTE + seconds * SECOND + fraction)?;
+    Some(sign * micros)
+}
+
+/// Adds an interval to a timestamp, or returns None if out of range. Months
+/// are added first, clamping the day to the end of the month, then days and
+/// microseconds.
+pub fn add_interval(ts: NaiveDateTime, interval: Interval) -> Option<NaiveDateTime> {
+    let months = Months::new(interval.months.unsigned_abs());
+    let ts = match interval.months {
+        0.. => ts.checked_add_months(months)?,
+        _ => ts.checked_sub_months(months)?,
+    };
+    ts.checked_add_signed(Duration::try_days(interval.days as i64)?)?
+        .checked_add_signed(Duration::microseconds(interval.micros))
+}
+
+/// Adds an interval to a time, wrapping around midnight. Months and days are
+/// ignored, like Postgres.
+pub fn add_time_interval(time: NaiveTime, interval: Interval) -> NaiveTime {
+    let micros = interval.micros.rem_euclid(DAY);
+    time.overflowing_add_signed(Duration::microseconds(micros)).0
+}
+
+/// Subtracts two timestamps, returning the difference as an interval of days
+/// and microseconds, e.g. 1 day 02:00:00. Returns None if out of range.
+pub fn subtract_timestamps(lhs: NaiveDateTime, rhs: NaiveDateTime) -> Option<Interval> {
+    let micros = lhs.signed_duration_since(rhs).num_microseconds()?;
+    Some(Interval { months: 0, days: i32::try_from(micros / DAY).ok()?, micros: micros % DAY })
+}
+
+/// Serializes dates as the number of days since the Unix epoch.
+pub mod serde_date {
+    use super::EPOCH;
+    use chrono::{Duration, NaiveDate};
+    use serde::{de::Error as _, Deserialize as _, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64((*date - EPOCH.date()).num_days())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+        let days = i64::deserialize(deserializer)?;
+        Duration::try_days(days)
+            .and_then(|days| EPOCH.date().checked_add_signed(days))
+            .ok_or_else(|| D::Error::custom(format!("invalid date {}", days)))
+    }
+}
+
+/// Serializes times as the number of microseconds since midnight.
+pub mod serde_time {
+    use chrono::{NaiveTime, Timelike as _};
+    use serde::{de::Error as _, Deserialize as _, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error> {
+        let micros =
+            time.num_seconds_from_midnight() as i64 * 1_000_000 + time.nanosecond() as i64 / 1000;
+        serializer.serialize_i64(micros)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+        let micros = i64::deserialize(deserializer)?;
+        u32::try_from(micros.div_euclid(1_000_000))
+            .ok()
+            .and_then(|secs| {
+                NaiveTime::from_num_seconds_from_midnight_opt(
+                    secs,
+                    micros.rem_euclid(1_000_000) as u32 * 1000,
+                )
+            })
+            .ok_or_else(|| D::Error::custom(format!("invalid time {}", micros)))
+    }
+}
+
+/// Serializes timestamps as the number of microseconds since the Unix epoch.
+pub mod serde_timestamp {
+    use super::{micros_since_epoch, EPOCH};
+    use chrono::{Duration, NaiveDateTime};
+    use serde::{de::Error as _, Deserialize as _, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(ts: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(micros_since_epoch(*ts))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<NaiveDateTime, D::Error> {
+        let micros = i64::deserialize(deserializer)?;
+        EPOCH
+            .checked_add_signed(Duration::microseconds(micros))
+            .ok_or_else(|| D::Error::custom(format!("invalid timestamp {}", micros)))
+    }
+}
+
+/// Serializes intervals as their i64 months, days and microseconds, since the
+/// keycode encoding doesn't support i32.
+pub mod serde_interval {
+    use super::Interval;
+    use serde::{de::Error as _, Deserialize as _, Deserializer, Serialize as _, Serializer};
+
+    pub fn serialize<S: Serializer>(interval: &Interval, serializer: S) -> Result<S::Ok, S::Error> {
+        (interval.months as i64, interval.days as i64, interval.micros).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Interval, D::Error> {
+        let (months, days, micros) = <(i64, i64, i64)>::deserialize(deserializer)?;
+        match (i32::try_from(months), i32::try_from(days)) {
+            (Ok(months), Ok(days)) => Ok(Interval { months, days, micros }),
+            _ => Err(D::Error::custom(format!("invalid interval {} {}", months, days))),
+        }
+    }
+}
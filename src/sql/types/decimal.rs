@@ -0,0 +1,169 @@
+//! Support for the exact-precision DECIMAL type, backed by rust_decimal. This
+//! has up to 28 significant digits, and keeps the scale (number of fractional
+//! digits) of values, e.g. 1.50 is distinct from 1.5 when displayed but they
+//! compare as equal.
+//!
+//! Decimals are serialized as an order-preserving byte string, such that the
+//! keycode encoding sorts them numerically and they can be used in primary
+//! keys and indexes. Since equal values must have equal keys, values must be
+//! normalized via Decimal::normalize() before being used in keys.
+
+use rust_decimal::prelude::ToPrimitive as _;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{de::Error as _, Deserialize as _, Deserializer, Serializer};
+use std::str::FromStr as _;
+
+/// Parses a decimal string, e.g. 3.14 or 1.5e3. Returns None if the value
+/// can't be represented exactly.
+pub fn parse(s: &str) -> Option<Decimal> {
+    let s = s.trim();
+    Decimal::from_str_exact(s).or_else(|_| Decimal::from_scientific(s)).ok()
+}
+
+/// Converts a float to the nearest decimal, using its shortest string
+/// representation such that e.g. 0.1 yields 0.1 rather than the float's
+/// exact binary value. Returns None for infinity, NaN and out-of-range values.
+pub fn from_float(f: f64) -> Option<Decimal> {
+    if !f.is_finite() {
+        return None;
+    }
+    Decimal::from_str(&f.to_string()).ok()
+}
+
+/// Converts a decimal to the nearest float.
+pub fn to_float(d: Decimal) -> f64 {
+    d.to_f64().expect("decimal is always representable as float")
+}
+
+/// Converts a decimal to an integer, rounding half away from zero like floats.
+/// Returns None if out of range.
+pub fn to_integer(d: Decimal) -> Option<i64> {
+    d.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero).to_i64()
+}
+
+/// Serializes a decimal as an order-preserving byte string. The normalized
+/// value 0.DDD × 10^E is encoded as a sign byte (0 negative, 1 zero, 2
+/// positive), the exponent E, the significant digits D, and a terminator. For
+/// negative numbers, the exponent, digits, and terminator are inverted such
+/// that larger magnitudes sort first. Finally, the original scale is appended,
+/// which doesn't affect the ordering since it's only reached for equal values.
+pub fn serialize<S: Serializer>(d: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    let normalized = d.normalize();
+    let mut bytes = Vec::with_capacity(32);
+    if normalized.is_zero() {
+        bytes.push(0x01);
+    } else {
+        let negative = normalized.is_sign_negative();
+        let mantissa = normalized.mantissa().unsigned_abs().to_string();
+        let exponent = mantissa.len() as i64 - normalized.scale() as i64;
+        let digits = mantissa.trim_end_matches('0').bytes().map(|b| b - b'0' + 1);
+        // The exponent is in the range -27 to 29, and digits are encoded as 1-10.
+        let exponent = (exponent + 64) as u8;
+        if negative {
+            bytes.push(0x00);
+            bytes.push(!exponent);
+            bytes.extend(digits.map(|d| 11 - d));
+            bytes.push(0xff);
+        } else {
+            bytes.push(0x02);
+            bytes.push(exponent);
+            bytes.extend(digits);
+            bytes.push(0x00);
+        }
+    }
+    bytes.push(d.scale() as u8);
+    serializer.serialize_bytes(&bytes)
+}
+
+/// Deserializes a decimal serialized by serialize().
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+    let bytes = serde_bytes::ByteBuf::deserialize(deserializer)?.into_vec();
+    let invalid = || D::Error::custom(format!("invalid decimal {:x?}", bytes));
+    let (scale, value) = bytes.split_last().ok_or_else(invalid)?;
+    let mut decimal = match value {
+        [0x01] => Decimal::ZERO,
+        [sign @ (0x00 | 0x02), exponent, digits @ .., terminator] => {
+            let negative = *sign == 0x00;
+            if *terminator != if negative { 0xff } else { 0x00 } {
+                return Err(invalid());
+            }
+            let exponent = if negative { !exponent } else { *exponent } as i64 - 64;
+            let mut mantissa: i128 = 0;
+            for digit in digits {
+                let digit = if negative { 11u8.wrapping_sub(*digit) } else { *digit } as i128 - 1;
+                if !(0..=9).contains(&digit) {
+                    return Err(invalid());
+                }
+                mantissa = mantissa.checked_mul(10).ok_or_else(invalid)? + digit;
+            }
+            // The value is 0.DDD × 10^E, i.e. DDD × 10^(E - len(DDD)).
+            let mut shift = exponent - digits.len() as i64;
+            while shift > 0 {
+                mantissa = mantissa.checked_mul(10).ok_or_else(invalid)?;
+                shift -= 1;
+            }
+            if negative {
+                mantissa = -mantissa;
+            }
+            Decimal::try_from_i128_with_scale(mantissa, (-shift) as u32).map_err(|_| invalid())?
+        }
+        _ => return Err(invalid()),
+    };
+    decimal.rescale(*scale as u32);
+    Ok(decimal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Value;
+    use crate::encoding::keycode;
+    use rust_decimal::Decimal;
+
+    /// Tests that the keycode encoding of decimals preserves their order, and
+    /// that they roundtrip with their scale intact.
+    #[test]
+    fn keycode_order() -> crate::error::Result<()> {
+        let decimals = [
+            "-79228162514264337593543950335",
+            "-1000",
+            "-999.99",
+            "-10",
+            "-1.23",
+            "-1.2",
+            "-1",
+            "-0.1",
+            "-0.0000000000000000000000000001",
+            "0",
+            "0.0000000000000000000000000001",
+            "0.001",
+            "0.1",
+            "0.12",
+            "0.123",
+            "1",
+            "1.2",
+            "1.23",
+            "9",
+            "10",
+            "100",
+            "101",
+            "79228162514264337593543950335",
+        ];
+        let mut prev: Option<Vec<u8>> = None;
+        for s in decimals {
+            let value = Value::Decimal(s.parse::<Decimal>().unwrap().normalize());
+            let key = keycode::serialize(&value)?;
+            assert_eq!(keycode::deserialize::<Value>(&key)?, value);
+            if let Some(prev) = prev {
+                assert!(prev < key, "{s} not ordered after previous value");
+            }
+            prev = Some(key);
+        }
+
+        let value = Value::Decimal("-1.2300".parse().unwrap());
+        let Value::Decimal(d) = keycode::deserialize(&keycode::serialize(&value)?)? else {
+            panic!("expected decimal")
+        };
+        assert_eq!(d.to_string(), "-1.2300");
+        Ok(())
+    }
+}
@@ -1,10 +1,14 @@
-use super::{Row, Value};
+use super::datetime::{self, Interval};
+use super::{decimal, fulltext, json, Collation, DataType, Function, Row, Sequences, Value};
 use crate::error::{Error, Result};
 
+use chrono::{Duration, NaiveDate, NaiveDateTime};
 use regex::Regex;
 use serde_derive::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::fmt::{self, Display};
 use std::mem::replace;
+use std::ops::Bound;
 
 /// An expression, made up of constants and operations
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -12,6 +16,14 @@ pub enum Expression {
     // Values
     Constant(Value),
     Field(usize, Option<(Option<String>, String)>),
+    Function(Function, Vec<Expression>),
+    /// A field of the current left row of a lateral join, referenced by its
+    /// right node. It must be bound to a constant before the right node is
+    /// executed, see Node::LateralJoin.
+    Outer(usize, Option<(Option<String>, String)>),
+    /// A numbered parameter in a cached plan, which must be bound to a
+    /// constant before the plan is executed.
+    Parameter(usize),
 
     // Logical operations
     And(Box<Expression>, Box<Expression>),
@@ -19,8 +31,12 @@ pub enum Expression {
     Or(Box<Expression>, Box<Expression>),
 
     // Comparisons operations (GTE, LTE, and NEQ are composite operations)
+    /// Whether the value equals any element of an array, i.e. = ANY.
+    Any(Box<Expression>, Box<Expression>),
+    Between(Box<Expression>, Box<Expression>, Box<Expression>),
     Equal(Box<Expression>, Box<Expression>),
     GreaterThan(Box<Expression>, Box<Expression>),
+    In(Box<Expression>, Vec<Expression>),
     IsNull(Box<Expression>),
     LessThan(Box<Expression>, Box<Expression>),
 
@@ -36,7 +52,27 @@ pub enum Expression {
     Subtract(Box<Expression>, Box<Expression>),
 
     // String operations
+    Concat(Box<Expression>, Box<Expression>),
     Like(Box<Expression>, Box<Expression>),
+    /// Whether a text matches a full-text query, i.e. @@, see fulltext.
+    Match(Box<Expression>, Box<Expression>),
+    /// The relevance of a text for a full-text query, i.e. MATCH ... AGAINST.
+    Relevance(Box<Expression>, Box<Expression>),
+
+    // JSON operations
+    JsonExtract(Box<Expression>, Box<Expression>),
+    JsonExtractText(Box<Expression>, Box<Expression>),
+
+    // Array operations
+    /// An array element, by 1-based index.
+    ArrayElement(Box<Expression>, Box<Expression>),
+
+    // Type operations
+    Cast(Box<Expression>, DataType),
+    /// The collation key of a string, see Collation::key(). The planner
+    /// wraps the operands of comparisons and sorts of collated columns in
+    /// this, such that strings compare by their keys.
+    Collate(Box<Expression>, Collation),
 }
 
 impl Expression {
@@ -47,224 +83,302 @@ impl Expression {
             // Constant values
             Self::Constant(c) => c.clone(),
             Self::Field(i, _) => row.and_then(|row| row.get(*i).cloned()).unwrap_or(Null),
-
-            // Logical operations
-            Self::And(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                (Boolean(lhs), Boolean(rhs)) => Boolean(lhs && rhs),
-                (Boolean(lhs), Null) if !lhs => Boolean(false),
-                (Boolean(_), Null) => Null,
-                (Null, Boolean(rhs)) if !rhs => Boolean(false),
-                (Null, Boolean(_)) => Null,
-                (Null, Null) => Null,
-                (lhs, rhs) => return Err(Error::Value(format!("Can't and {} and {}", lhs, rhs))),
-            },
-            Self::Not(expr) => match expr.evaluate(row)? {
-                Boolean(b) => Boolean(!b),
-                Null => Null,
-                value => return Err(Error::Value(format!("Can't negate {}", value))),
-            },
-            Self::Or(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                (Boolean(lhs), Boolean(rhs)) => Boolean(lhs || rhs),
-                (Boolean(lhs), Null) if lhs => Boolean(true),
-                (Boolean(_), Null) => Null,
-                (Null, Boolean(rhs)) if rhs => Boolean(true),
-                (Null, Boolean(_)) => Null,
-                (Null, Null) => Null,
-                (lhs, rhs) => return Err(Error::Value(format!("Can't or {} and {}", lhs, rhs))),
-            },
+            Self::Outer(i, _) => {
+                return Err(Error::Internal(format!("Unbound outer field #{}", i)));
+            }
+            Self::Parameter(i) => {
+                return Err(Error::Internal(format!("Unbound parameter ${}", i)));
+            }
+            Self::Function(function, args) => {
+                function.evaluate(args.iter().map(|e| e.evaluate(row)).collect::<Result<_>>()?)?
+            }
 
             // Comparison operations
-            #[allow(clippy::float_cmp)] // Up to the user if they want to compare or not
-            Self::Equal(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                (Boolean(lhs), Boolean(rhs)) => Boolean(lhs == rhs),
-                (Integer(lhs), Integer(rhs)) => Boolean(lhs == rhs),
-                (Integer(lhs), Float(rhs)) => Boolean(lhs as f64 == rhs),
-                (Float(lhs), Integer(rhs)) => Boolean(lhs == rhs as f64),
-                (Float(lhs), Float(rhs)) => Boolean(lhs == rhs),
-                (String(lhs), String(rhs)) => Boolean(lhs == rhs),
-                (Null, _) | (_, Null) => Null,
-                (lhs, rhs) => {
-                    return Err(Error::Value(format!("Can't compare {} and {}", lhs, rhs)))
-                }
-            },
-            Self::GreaterThan(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                #[allow(clippy::bool_comparison)]
-                (Boolean(lhs), Boolean(rhs)) => Boolean(lhs > rhs),
-                (Integer(lhs), Integer(rhs)) => Boolean(lhs > rhs),
-                (Integer(lhs), Float(rhs)) => Boolean(lhs as f64 > rhs),
-                (Float(lhs), Integer(rhs)) => Boolean(lhs > rhs as f64),
-                (Float(lhs), Float(rhs)) => Boolean(lhs > rhs),
-                (String(lhs), String(rhs)) => Boolean(lhs > rhs),
-                (Null, _) | (_, Null) => Null,
-                (lhs, rhs) => {
-                    return Err(Error::Value(format!("Can't compare {} and {}", lhs, rhs)))
-                }
-            },
-            Self::LessThan(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                #[allow(clippy::bool_comparison)]
-                (Boolean(lhs), Boolean(rhs)) => Boolean(lhs < rhs),
-                (Integer(lhs), Integer(rhs)) => Boolean(lhs < rhs),
-                (Integer(lhs), Float(rhs)) => Boolean((lhs as f64) < rhs),
-                (Float(lhs), Integer(rhs)) => Boolean(lhs < rhs as f64),
-                (Float(lhs), Float(rhs)) => Boolean(lhs < rhs),
-                (String(lhs), String(rhs)) => Boolean(lhs < rhs),
-                (Null, _) | (_, Null) => Null,
-                (lhs, rhs) => {
-                    return Err(Error::Value(format!("Can't compare {} and {}", lhs, rhs)))
+            Self::Between(expr, low, high) => {
+                // Evaluate each operand once, and compare them as low <= expr AND expr <= high.
+                let value = Self::Constant(expr.evaluate(row)?);
+                let low = Self::Constant(low.evaluate(row)?);
+                let high = Self::Constant(high.evaluate(row)?);
+                Self::And(
+                    Self::Or(
+                        Self::GreaterThan(value.clone().into(), low.clone().into()).into(),
+                        Self::Equal(value.clone().into(), low.into()).into(),
+                    )
+                    .into(),
+                    Self::Or(
+                        Self::LessThan(value.clone().into(), high.clone().into()).into(),
+                        Self::Equal(value.into(), high.into()).into(),
+                    )
+                    .into(),
+                )
+                .evaluate(None)?
+            }
+            Self::In(expr, list) => {
+                // Like Postgres, yields NULL rather than FALSE if there is no match but either
+                // side contains a NULL.
+                let value = Self::Constant(expr.evaluate(row)?);
+                let mut result = Boolean(false);
+                for item in list {
+                    let item = Self::Constant(item.evaluate(row)?);
+                    match Self::Equal(value.clone().into(), item.into()).evaluate(None)? {
+                        Boolean(true) => return Ok(Boolean(true)),
+                        Boolean(false) => {}
+                        _ => result = Null,
+                    }
                 }
-            },
-            Self::IsNull(expr) => match expr.evaluate(row)? {
-                Null => Boolean(true),
-                _ => Boolean(false),
-            },
+                result
+            }
 
-            // Mathematical operations
-            Self::Add(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                (Integer(lhs), Integer(rhs)) => Integer(
-                    lhs.checked_add(rhs).ok_or_else(|| Error::Value("Integer overflow".into()))?,
-                ),
-                (Integer(lhs), Float(rhs)) => Float(lhs as f64 + rhs),
-                (Integer(_), Null) => Null,
-                (Float(lhs), Float(rhs)) => Float(lhs + rhs),
-                (Float(lhs), Integer(rhs)) => Float(lhs + rhs as f64),
-                (Float(_), Null) => Null,
-                (Null, Float(_)) => Null,
-                (Null, Integer(_)) => Null,
-                (Null, Null) => Null,
-                (lhs, rhs) => return Err(Error::Value(format!("Can't add {} and {}", lhs, rhs))),
-            },
-            Self::Assert(expr) => match expr.evaluate(row)? {
-                Float(f) => Float(f),
-                Integer(i) => Integer(i),
-                Null => Null,
-                expr => return Err(Error::Value(format!("Can't take the positive of {}", expr))),
-            },
-            Self::Divide(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                (Integer(_), Integer(0)) => {
-                    return Err(Error::Value("Can't divide by zero".into()))
-                }
-                (Integer(lhs), Integer(rhs)) => Integer(lhs / rhs),
-                (Integer(lhs), Float(rhs)) => Float(lhs as f64 / rhs),
-                (Integer(_), Null) => Null,
-                (Float(lhs), Integer(rhs)) => Float(lhs / rhs as f64),
-                (Float(lhs), Float(rhs)) => Float(lhs / rhs),
-                (Float(_), Null) => Null,
-                (Null, Float(_)) => Null,
-                (Null, Integer(_)) => Null,
-                (Null, Null) => Null,
-                (lhs, rhs) => {
-                    return Err(Error::Value(format!("Can't divide {} and {}", lhs, rhs)))
-                }
-            },
-            Self::Exponentiate(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                (Integer(lhs), Integer(rhs)) if rhs >= 0 => Integer(
-                    lhs.checked_pow(rhs as u32)
-                        .ok_or_else(|| Error::Value("Integer overflow".into()))?,
-                ),
-                (Integer(lhs), Integer(rhs)) => Float((lhs as f64).powf(rhs as f64)),
-                (Integer(lhs), Float(rhs)) => Float((lhs as f64).powf(rhs)),
-                (Integer(_), Null) => Null,
-                (Float(lhs), Integer(rhs)) => Float((lhs).powi(rhs as i32)),
-                (Float(lhs), Float(rhs)) => Float((lhs).powf(rhs)),
-                (Float(_), Null) => Null,
-                (Null, Float(_)) => Null,
-                (Null, Integer(_)) => Null,
-                (Null, Null) => Null,
-                (lhs, rhs) => {
-                    return Err(Error::Value(format!("Can't exponentiate {} and {}", lhs, rhs)))
-                }
-            },
-            Self::Factorial(expr) => match expr.evaluate(row)? {
-                Integer(i) if i < 0 => {
-                    return Err(Error::Value("Can't take factorial of negative number".into()))
-                }
-                Integer(i) => Integer((1..=i).product()),
-                Null => Null,
-                value => return Err(Error::Value(format!("Can't take factorial of {}", value))),
-            },
-            Self::Modulo(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                // This uses remainder semantics, like Postgres.
-                (Integer(_), Integer(0)) => {
-                    return Err(Error::Value("Can't divide by zero".into()))
-                }
-                (Integer(lhs), Integer(rhs)) => Integer(lhs % rhs),
-                (Integer(lhs), Float(rhs)) => Float(lhs as f64 % rhs),
-                (Integer(_), Null) => Null,
-                (Float(lhs), Integer(rhs)) => Float(lhs % rhs as f64),
-                (Float(lhs), Float(rhs)) => Float(lhs % rhs),
-                (Float(_), Null) => Null,
-                (Null, Float(_)) => Null,
-                (Null, Integer(_)) => Null,
-                (Null, Null) => Null,
-                (lhs, rhs) => {
-                    return Err(Error::Value(format!("Can't take modulo of {} and {}", lhs, rhs)))
-                }
-            },
-            Self::Multiply(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                (Integer(lhs), Integer(rhs)) => Integer(
-                    lhs.checked_mul(rhs).ok_or_else(|| Error::Value("Integer overflow".into()))?,
-                ),
-                (Integer(lhs), Float(rhs)) => Float(lhs as f64 * rhs),
-                (Integer(_), Null) => Null,
-                (Float(lhs), Integer(rhs)) => Float(lhs * rhs as f64),
-                (Float(lhs), Float(rhs)) => Float(lhs * rhs),
-                (Float(_), Null) => Null,
-                (Null, Float(_)) => Null,
-                (Null, Integer(_)) => Null,
-                (Null, Null) => Null,
-                (lhs, rhs) => {
-                    return Err(Error::Value(format!("Can't multiply {} and {}", lhs, rhs)))
-                }
-            },
-            Self::Negate(expr) => match expr.evaluate(row)? {
-                Integer(i) => Integer(-i),
-                Float(f) => Float(-f),
-                Null => Null,
-                value => return Err(Error::Value(format!("Can't negate {}", value))),
-            },
-            Self::Subtract(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                (Integer(lhs), Integer(rhs)) => Integer(
-                    lhs.checked_sub(rhs).ok_or_else(|| Error::Value("Integer overflow".into()))?,
-                ),
-                (Integer(lhs), Float(rhs)) => Float(lhs as f64 - rhs),
-                (Integer(_), Null) => Null,
-                (Float(lhs), Integer(rhs)) => Float(lhs - rhs as f64),
-                (Float(lhs), Float(rhs)) => Float(lhs - rhs),
-                (Float(_), Null) => Null,
-                (Null, Float(_)) => Null,
-                (Null, Integer(_)) => Null,
-                (Null, Null) => Null,
-                (lhs, rhs) => {
-                    return Err(Error::Value(format!("Can't subtract {} and {}", lhs, rhs)))
-                }
-            },
+            // Operators, applied to the evaluated operands
+            Self::Add(lhs, rhs)
+            | Self::And(lhs, rhs)
+            | Self::Any(lhs, rhs)
+            | Self::ArrayElement(lhs, rhs)
+            | Self::Concat(lhs, rhs)
+            | Self::Divide(lhs, rhs)
+            | Self::Equal(lhs, rhs)
+            | Self::Exponentiate(lhs, rhs)
+            | Self::GreaterThan(lhs, rhs)
+            | Self::JsonExtract(lhs, rhs)
+            | Self::JsonExtractText(lhs, rhs)
+            | Self::LessThan(lhs, rhs)
+            | Self::Like(lhs, rhs)
+            | Self::Match(lhs, rhs)
+            | Self::Modulo(lhs, rhs)
+            | Self::Multiply(lhs, rhs)
+            | Self::Or(lhs, rhs)
+            | Self::Relevance(lhs, rhs)
+            | Self::Subtract(lhs, rhs) => self.binary(lhs.evaluate(row)?, rhs.evaluate(row)?)?,
+            Self::Assert(expr)
+            | Self::Cast(expr, _)
+            | Self::Collate(expr, _)
+            | Self::Factorial(expr)
+            | Self::IsNull(expr)
+            | Self::Negate(expr)
+            | Self::Not(expr) => self.unary(expr.evaluate(row)?)?,
+        })
+    }
 
-            // String operations
-            Self::Like(lhs, rhs) => match (lhs.evaluate(row)?, rhs.evaluate(row)?) {
-                (String(lhs), String(rhs)) => Boolean(
-                    Regex::new(&format!(
-                        "^{}$",
-                        regex::escape(&rhs)
-                            .replace('%', ".*")
-                            .replace(".*.*", "%")
-                            .replace('_', ".")
-                            .replace("..", "_")
-                    ))?
-                    .is_match(&lhs),
-                ),
-                (String(_), Null) => Null,
-                (Null, String(_)) => Null,
-                (lhs, rhs) => return Err(Error::Value(format!("Can't LIKE {} and {}", lhs, rhs))),
-            },
+    /// Compiles the expression into nested closures, which evaluate it for
+    /// a row without traversing the expression tree. Operators are resolved
+    /// to their functions once here, rather than being matched for every row.
+    /// Errors if the expression contains unbound parameters.
+    pub fn compile(&self) -> Result<Compiled> {
+        use Value::*;
+        Ok(Compiled(match self {
+            Self::Constant(c) => {
+                let c = c.clone();
+                Box::new(move |_| Ok(c.clone()))
+            }
+            Self::Field(i, _) => {
+                let i = *i;
+                Box::new(move |row| Ok(row.get(i).cloned().unwrap_or(Null)))
+            }
+            Self::Outer(i, _) => {
+                return Err(Error::Internal(format!("Unbound outer field #{}", i)));
+            }
+            Self::Parameter(i) => {
+                return Err(Error::Internal(format!("Unbound parameter ${}", i)));
+            }
+            Self::Function(function, args) => {
+                let function = *function;
+                let args = args.iter().map(|arg| arg.compile()).collect::<Result<Vec<_>>>()?;
+                Box::new(move |row| {
+                    function
+                        .evaluate(args.iter().map(|arg| arg.evaluate(row)).collect::<Result<_>>()?)
+                })
+            }
+
+            // Comparison operations, as for evaluate().
+            Self::Between(expr, low, high) => {
+                let (expr, low, high) = (expr.compile()?, low.compile()?, high.compile()?);
+                Box::new(move |row| {
+                    let value = expr.evaluate(row)?;
+                    let (low, high) = (low.evaluate(row)?, high.evaluate(row)?);
+                    let above =
+                        or(greater_than(value.clone(), low.clone())?, equal(value.clone(), low)?)?;
+                    let below = or(less_than(value.clone(), high.clone())?, equal(value, high)?)?;
+                    and(above, below)
+                })
+            }
+            Self::In(expr, list) => {
+                let expr = expr.compile()?;
+                let list = list.iter().map(|item| item.compile()).collect::<Result<Vec<_>>>()?;
+                Box::new(move |row| {
+                    let value = expr.evaluate(row)?;
+                    let mut result = Boolean(false);
+                    for item in &list {
+                        match equal(value.clone(), item.evaluate(row)?)? {
+                            Boolean(true) => return Ok(Boolean(true)),
+                            Boolean(false) => {}
+                            _ => result = Null,
+                        }
+                    }
+                    Ok(result)
+                })
+            }
+
+            // LIKE with a constant pattern builds its regular expression once.
+            Self::Like(lhs, rhs) if matches!(**rhs, Self::Constant(String(_))) => {
+                let Self::Constant(pattern) = (**rhs).clone() else { unreachable!() };
+                let (lhs, regex) = (lhs.compile()?, like_regex(&pattern.to_string())?);
+                Box::new(move |row| match lhs.evaluate(row)? {
+                    String(lhs) => Ok(Boolean(regex.is_match(&lhs))),
+                    lhs => like(lhs, pattern.clone()),
+                })
+            }
+
+            // Operators, applied to the evaluated operands
+            Self::Add(lhs, rhs)
+            | Self::And(lhs, rhs)
+            | Self::Any(lhs, rhs)
+            | Self::ArrayElement(lhs, rhs)
+            | Self::Concat(lhs, rhs)
+            | Self::Divide(lhs, rhs)
+            | Self::Equal(lhs, rhs)
+            | Self::Exponentiate(lhs, rhs)
+            | Self::GreaterThan(lhs, rhs)
+            | Self::JsonExtract(lhs, rhs)
+            | Self::JsonExtractText(lhs, rhs)
+            | Self::LessThan(lhs, rhs)
+            | Self::Like(lhs, rhs)
+            | Self::Match(lhs, rhs)
+            | Self::Modulo(lhs, rhs)
+            | Self::Multiply(lhs, rhs)
+            | Self::Or(lhs, rhs)
+            | Self::Relevance(lhs, rhs)
+            | Self::Subtract(lhs, rhs) => {
+                let (op, lhs, rhs) = (self.binary_fn()?, lhs.compile()?, rhs.compile()?);
+                Box::new(move |row| op(lhs.evaluate(row)?, rhs.evaluate(row)?))
+            }
+            Self::Cast(expr, datatype) => {
+                let (expr, datatype) = (expr.compile()?, datatype.clone());
+                Box::new(move |row| expr.evaluate(row)?.cast(&datatype))
+            }
+            Self::Collate(expr, collation) => {
+                let (expr, collation) = (expr.compile()?, *collation);
+                Box::new(move |row| Ok(collation.key(expr.evaluate(row)?)))
+            }
+            Self::Assert(expr)
+            | Self::Factorial(expr)
+            | Self::IsNull(expr)
+            | Self::Negate(expr)
+            | Self::Not(expr) => {
+                let (op, expr) = (self.unary_fn()?, expr.compile()?);
+                Box::new(move |row| op(expr.evaluate(row)?))
+            }
+        }))
+    }
+
+    /// Returns the function applying a binary operator to its evaluated
+    /// operands, such that it can be resolved once when compiling.
+    fn binary_fn(&self) -> Result<fn(Value, Value) -> Result<Value>> {
+        Ok(match self {
+            Self::Add(..) => add,
+            Self::And(..) => and,
+            Self::Any(..) => any,
+            Self::ArrayElement(..) => array_element,
+            Self::Concat(..) => concat,
+            Self::Divide(..) => divide,
+            Self::Equal(..) => equal,
+            Self::Exponentiate(..) => exponentiate,
+            Self::GreaterThan(..) => greater_than,
+            Self::JsonExtract(..) => json_extract,
+            Self::JsonExtractText(..) => json_extract_text,
+            Self::LessThan(..) => less_than,
+            Self::Like(..) => like,
+            Self::Match(..) => matches,
+            Self::Modulo(..) => modulo,
+            Self::Multiply(..) => multiply,
+            Self::Or(..) => or,
+            Self::Relevance(..) => relevance,
+            Self::Subtract(..) => subtract,
+            expr => return Err(Error::Internal(format!("Not a binary operator: {:?}", expr))),
+        })
+    }
+
+    /// Applies a binary operator to its evaluated operands.
+    fn binary(&self, lhs: Value, rhs: Value) -> Result<Value> {
+        self.binary_fn()?(lhs, rhs)
+    }
+
+    /// Returns the function applying a unary operator to its evaluated
+    /// operand, such that it can be resolved once when compiling. Casts and
+    /// collations depend on their target, and are compiled separately.
+    fn unary_fn(&self) -> Result<fn(Value) -> Result<Value>> {
+        Ok(match self {
+            Self::Assert(_) => assert,
+            Self::Factorial(_) => factorial,
+            Self::IsNull(_) => is_null,
+            Self::Negate(_) => negate,
+            Self::Not(_) => not,
+            expr => return Err(Error::Internal(format!("Not a unary operator: {:?}", expr))),
         })
     }
 
+    /// Applies a unary operator to its evaluated operand.
+    fn unary(&self, value: Value) -> Result<Value> {
+        match self {
+            Self::Cast(_, datatype) => value.cast(datatype),
+            Self::Collate(_, collation) => Ok(collation.key(value)),
+            _ => self.unary_fn()?(value),
+        }
+    }
+
     /// Walks the expression tree while calling a closure. Returns true as soon as the closure
     /// returns true. This is the inverse of walk().
     pub fn contains<F: Fn(&Expression) -> bool>(&self, visitor: &F) -> bool {
         !self.walk(&|e| !visitor(e))
     }
 
+    /// Returns the row fields referenced by the expression, in walk order and
+    /// possibly repeated.
+    pub fn fields(&self) -> Vec<usize> {
+        let fields = std::cell::RefCell::new(Vec::new());
+        self.walk(&|e| {
+            if let Self::Field(i, _) = e {
+                fields.borrow_mut().push(*i);
+            }
+            true
+        });
+        fields.into_inner()
+    }
+
+    /// Returns true if the expression calls a sequence function, see
+    /// evaluate_sequences().
+    pub fn calls_sequences(&self) -> bool {
+        self.contains(&|e| matches!(e, Self::Function(f, _) if f.is_sequence()))
+    }
+
+    /// Evaluates an expression like evaluate(), including any sequence
+    /// function calls. These can't be evaluated by evaluate() or compile(),
+    /// since they need access to the sequences, so executors that support them
+    /// use this instead.
+    pub fn evaluate_sequences(
+        &self,
+        row: Option<&Row>,
+        sequences: &mut dyn Sequences,
+    ) -> Result<Value> {
+        if !self.calls_sequences() {
+            return self.evaluate(row);
+        }
+        self.clone().bind_sequences(row, sequences)?.evaluate(row)
+    }
+
+    /// Replaces the sequence function calls in the expression with their
+    /// values for the given row.
+    fn bind_sequences(self, row: Option<&Row>, sequences: &mut dyn Sequences) -> Result<Self> {
+        let sequences = std::cell::RefCell::new(sequences);
+        self.transform(&Ok, &|e| match e {
+            Self::Function(function, args) if function.is_sequence() => {
+                let args = args.iter().map(|arg| arg.evaluate(row)).collect::<Result<_>>()?;
+                let value = function.evaluate_sequence(args, *sequences.borrow_mut())?;
+                Ok(Self::Constant(value))
+            }
+            e => Ok(e),
+        })
+    }
+
     /// Replaces the expression with result of the closure. Helper function for transform().
     fn replace_with<F: Fn(Self) -> Result<Self>>(&mut self, f: F) -> Result<()> {
         // Temporarily replace expression with a null value, in case closure panics. May consider
@@ -284,27 +398,55 @@ impl Expression {
         match &mut self {
             Self::Add(lhs, rhs)
             | Self::And(lhs, rhs)
+            | Self::Any(lhs, rhs)
+            | Self::ArrayElement(lhs, rhs)
+            | Self::Concat(lhs, rhs)
             | Self::Divide(lhs, rhs)
             | Self::Equal(lhs, rhs)
             | Self::Exponentiate(lhs, rhs)
             | Self::GreaterThan(lhs, rhs)
+            | Self::JsonExtract(lhs, rhs)
+            | Self::JsonExtractText(lhs, rhs)
             | Self::LessThan(lhs, rhs)
             | Self::Like(lhs, rhs)
+            | Self::Match(lhs, rhs)
             | Self::Modulo(lhs, rhs)
             | Self::Multiply(lhs, rhs)
             | Self::Or(lhs, rhs)
+            | Self::Relevance(lhs, rhs)
             | Self::Subtract(lhs, rhs) => {
                 Self::replace_with(lhs, |e| e.transform(before, after))?;
                 Self::replace_with(rhs, |e| e.transform(before, after))?;
             }
 
             Self::Assert(expr)
+            | Self::Cast(expr, _)
+            | Self::Collate(expr, _)
             | Self::Factorial(expr)
             | Self::IsNull(expr)
             | Self::Negate(expr)
             | Self::Not(expr) => Self::replace_with(expr, |e| e.transform(before, after))?,
 
-            Self::Constant(_) | Self::Field(_, _) => {}
+            Self::Between(expr, low, high) => {
+                Self::replace_with(expr, |e| e.transform(before, after))?;
+                Self::replace_with(low, |e| e.transform(before, after))?;
+                Self::replace_with(high, |e| e.transform(before, after))?;
+            }
+
+            Self::In(expr, list) => {
+                Self::replace_with(expr, |e| e.transform(before, after))?;
+                for item in list {
+                    Self::replace_with(item, |e| e.transform(before, after))?;
+                }
+            }
+
+            Self::Function(_, args) => {
+                for arg in args {
+                    Self::replace_with(arg, |e| e.transform(before, after))?;
+                }
+            }
+
+            Self::Constant(_) | Self::Field(_, _) | Self::Outer(_, _) | Self::Parameter(_) => {}
         };
         after(self)
     }
@@ -315,24 +457,43 @@ impl Expression {
             && match self {
                 Self::Add(lhs, rhs)
                 | Self::And(lhs, rhs)
+                | Self::Any(lhs, rhs)
+                | Self::ArrayElement(lhs, rhs)
+                | Self::Concat(lhs, rhs)
                 | Self::Divide(lhs, rhs)
                 | Self::Equal(lhs, rhs)
                 | Self::Exponentiate(lhs, rhs)
                 | Self::GreaterThan(lhs, rhs)
+                | Self::JsonExtract(lhs, rhs)
+                | Self::JsonExtractText(lhs, rhs)
                 | Self::LessThan(lhs, rhs)
                 | Self::Like(lhs, rhs)
+                | Self::Match(lhs, rhs)
                 | Self::Modulo(lhs, rhs)
                 | Self::Multiply(lhs, rhs)
                 | Self::Or(lhs, rhs)
+                | Self::Relevance(lhs, rhs)
                 | Self::Subtract(lhs, rhs) => lhs.walk(visitor) && rhs.walk(visitor),
 
                 Self::Assert(expr)
+                | Self::Cast(expr, _)
+                | Self::Collate(expr, _)
                 | Self::Factorial(expr)
                 | Self::IsNull(expr)
                 | Self::Negate(expr)
                 | Self::Not(expr) => expr.walk(visitor),
 
-                Self::Constant(_) | Self::Field(_, _) => true,
+                Self::Between(expr, low, high) => {
+                    expr.walk(visitor) && low.walk(visitor) && high.walk(visitor)
+                }
+
+                Self::In(expr, list) => expr.walk(visitor) && list.iter().all(|e| e.walk(visitor)),
+
+                Self::Function(_, args) => args.iter().all(|e| e.walk(visitor)),
+
+                Self::Constant(_) | Self::Field(_, _) | Self::Outer(_, _) | Self::Parameter(_) => {
+                    true
+                }
             }
     }
 
@@ -467,18 +628,31 @@ impl Expression {
     // Expressions must be a combination of =, IS NULL, OR to be converted.
     pub fn as_lookup(&self, field: usize) -> Option<Vec<Value>> {
         use Expression::*;
+        let is_field = |expr: &Expression| expr.is_field(field);
         // FIXME This should use a single match level, but since the child expressions are boxed
         // that would require box patterns, which are unstable.
         match &self {
             Equal(lhs, rhs) => match (&**lhs, &**rhs) {
-                (Field(i, _), Constant(v)) if i == &field => Some(vec![v.clone()]),
-                (Constant(v), Field(i, _)) if i == &field => Some(vec![v.clone()]),
+                (lhs, Constant(v)) if is_field(lhs) => Some(vec![v.clone()]),
+                (Constant(v), rhs) if is_field(rhs) => Some(vec![v.clone()]),
                 (_, _) => None,
             },
             IsNull(e) => match &**e {
                 Field(i, _) if i == &field => Some(vec![Value::Null]),
                 _ => None,
             },
+            // NULL list items can never match, so they're omitted from the lookup.
+            In(e, list) => match &**e {
+                e if is_field(e) => list
+                    .iter()
+                    .filter(|item| !matches!(item, Constant(Value::Null)))
+                    .map(|item| match item {
+                        Constant(v) => Some(v.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => None,
+            },
             Or(lhs, rhs) => match (lhs.as_lookup(field), rhs.as_lookup(field)) {
                 (Some(mut lvalues), Some(mut rvalues)) => {
                     lvalues.append(&mut rvalues);
@@ -490,6 +664,81 @@ impl Expression {
         }
     }
 
+    // Checks if the expression is a range comparison of a field against constants, and returns
+    // the range of values it can match. Expressions must be a combination of =, <, >, BETWEEN,
+    // prefix LIKE, AND, OR to be converted. The range may contain values that don't match, e.g.
+    // for OR it spans both ranges along with any gap between them.
+    pub fn as_range(&self, field: usize) -> Option<(Bound<Value>, Bound<Value>)> {
+        use Bound::*;
+        use Expression::*;
+        // Comparisons with NULL never match, so they're not converted.
+        let constant = |expr: &Expression| match expr {
+            Constant(Value::Null) => None,
+            Constant(v) => Some(v.clone()),
+            _ => None,
+        };
+        let is_field = |expr: &Expression| expr.is_field(field);
+        match self {
+            Equal(lhs, rhs) if is_field(lhs) => {
+                constant(rhs).map(|v| (Included(v.clone()), Included(v)))
+            }
+            Equal(lhs, rhs) if is_field(rhs) => {
+                constant(lhs).map(|v| (Included(v.clone()), Included(v)))
+            }
+            GreaterThan(lhs, rhs) | LessThan(rhs, lhs) if is_field(lhs) => {
+                constant(rhs).map(|v| (Excluded(v), Unbounded))
+            }
+            GreaterThan(lhs, rhs) | LessThan(rhs, lhs) if is_field(rhs) => {
+                constant(lhs).map(|v| (Unbounded, Excluded(v)))
+            }
+            Between(expr, low, high) if is_field(expr) => {
+                Some((Included(constant(low)?), Included(constant(high)?)))
+            }
+            // A LIKE pattern with a literal prefix only matches strings with that prefix, i.e.
+            // strings from the prefix up to the prefix with its last character incremented.
+            Like(lhs, rhs) if is_field(lhs) => {
+                let Constant(Value::String(pattern)) = &**rhs else { return None };
+                let mut prefix: String =
+                    pattern.chars().take_while(|c| !"%_".contains(*c)).collect();
+                if prefix.is_empty() {
+                    return None;
+                }
+                let low = Included(Value::String(prefix.clone()));
+                while let Some(c) = prefix.pop() {
+                    if let Some(next) = (c as u32 + 1..=char::MAX as u32).find_map(char::from_u32) {
+                        prefix.push(next);
+                        return Some((low, Excluded(Value::String(prefix))));
+                    }
+                }
+                Some((low, Unbounded))
+            }
+            And(lhs, rhs) => match (lhs.as_range(field), rhs.as_range(field)) {
+                (Some((llow, lhigh)), Some((rlow, rhigh))) => {
+                    Some((max_bound(llow, rlow, false)?, min_bound(lhigh, rhigh, true)?))
+                }
+                (Some(range), None) | (None, Some(range)) => Some(range),
+                (None, None) => None,
+            },
+            Or(lhs, rhs) => {
+                let (llow, lhigh) = lhs.as_range(field)?;
+                let (rlow, rhigh) = rhs.as_range(field)?;
+                Some((min_bound(llow, rlow, false)?, max_bound(lhigh, rhigh, true)?))
+            }
+            _ => None,
+        }
+    }
+
+    // Checks if the expression is the given field, or its collation key. Collated fields are
+    // compared by their keys, and the keys of their lookups and ranges are encoded with the
+    // collation too, so they're looked up like the field itself.
+    fn is_field(&self, field: usize) -> bool {
+        match self {
+            Self::Field(i, _) => *i == field,
+            Self::Collate(expr, _) => matches!(**expr, Self::Field(i, _) if i == field),
+            _ => false,
+        }
+    }
+
     // Creates an expression from a list of field lookup values.
     pub fn from_lookup(
         field: usize,
@@ -517,6 +766,628 @@ impl Expression {
     }
 }
 
+// Logical operations
+/// Logical AND, where NULL is unknown (i.e. three-valued logic).
+fn and(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (Boolean(lhs), Boolean(rhs)) => Boolean(lhs && rhs),
+        (Boolean(lhs), Null) if !lhs => Boolean(false),
+        (Boolean(_), Null) => Null,
+        (Null, Boolean(rhs)) if !rhs => Boolean(false),
+        (Null, Boolean(_)) => Null,
+        (Null, Null) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't and {} and {}", lhs, rhs))),
+    })
+}
+
+/// Logical OR, where NULL is unknown (i.e. three-valued logic).
+fn or(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (Boolean(lhs), Boolean(rhs)) => Boolean(lhs || rhs),
+        (Boolean(lhs), Null) if lhs => Boolean(true),
+        (Boolean(_), Null) => Null,
+        (Null, Boolean(rhs)) if rhs => Boolean(true),
+        (Null, Boolean(_)) => Null,
+        (Null, Null) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't or {} and {}", lhs, rhs))),
+    })
+}
+
+/// Compares two values for equality, converting numeric types.
+#[allow(clippy::float_cmp)] // Up to the user if they want to compare or not
+fn equal(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (Boolean(lhs), Boolean(rhs)) => Boolean(lhs == rhs),
+        (Integer(lhs), Integer(rhs)) => Boolean(lhs == rhs),
+        (Integer(lhs), Float(rhs)) => Boolean(lhs as f64 == rhs),
+        (Float(lhs), Integer(rhs)) => Boolean(lhs == rhs as f64),
+        (Float(lhs), Float(rhs)) => Boolean(lhs == rhs),
+        (String(lhs), String(rhs)) => Boolean(lhs == rhs),
+        (Bytes(lhs), Bytes(rhs)) => Boolean(lhs == rhs),
+        (Uuid(lhs), Uuid(rhs)) => Boolean(lhs == rhs),
+        (Decimal(lhs), Decimal(rhs)) => Boolean(lhs == rhs),
+        (Decimal(lhs), Integer(rhs)) => Boolean(lhs == rhs.into()),
+        (Integer(lhs), Decimal(rhs)) => Boolean(rust_decimal::Decimal::from(lhs) == rhs),
+        (Decimal(lhs), Float(rhs)) => Boolean(decimal::to_float(lhs) == rhs),
+        (Float(lhs), Decimal(rhs)) => Boolean(lhs == decimal::to_float(rhs)),
+        (Date(lhs), Date(rhs)) => Boolean(lhs == rhs),
+        (Date(lhs), Timestamp(rhs)) => Boolean(NaiveDateTime::from(lhs) == rhs),
+        (Time(lhs), Time(rhs)) => Boolean(lhs == rhs),
+        (Timestamp(lhs), Date(rhs)) => Boolean(lhs == NaiveDateTime::from(rhs)),
+        (Timestamp(lhs), Timestamp(rhs)) => Boolean(lhs == rhs),
+        (Interval(lhs), Interval(rhs)) => Boolean(lhs == rhs),
+        (Json(lhs), Json(rhs)) => Boolean(lhs == rhs),
+        (Array(lhs), Array(rhs)) => compare_arrays(lhs, rhs, Ordering::is_eq)?,
+        (Null, _) | (_, Null) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't compare {} and {}", lhs, rhs))),
+    })
+}
+
+/// Compares whether the left value is greater than the right value.
+fn greater_than(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        #[allow(clippy::bool_comparison)]
+        (Boolean(lhs), Boolean(rhs)) => Boolean(lhs > rhs),
+        (Integer(lhs), Integer(rhs)) => Boolean(lhs > rhs),
+        (Integer(lhs), Float(rhs)) => Boolean(lhs as f64 > rhs),
+        (Float(lhs), Integer(rhs)) => Boolean(lhs > rhs as f64),
+        (Float(lhs), Float(rhs)) => Boolean(lhs > rhs),
+        (String(lhs), String(rhs)) => Boolean(lhs > rhs),
+        (Bytes(lhs), Bytes(rhs)) => Boolean(lhs > rhs),
+        (Uuid(lhs), Uuid(rhs)) => Boolean(lhs > rhs),
+        (Decimal(lhs), Decimal(rhs)) => Boolean(lhs > rhs),
+        (Decimal(lhs), Integer(rhs)) => Boolean(lhs > rhs.into()),
+        (Integer(lhs), Decimal(rhs)) => Boolean(rust_decimal::Decimal::from(lhs) > rhs),
+        (Decimal(lhs), Float(rhs)) => Boolean(decimal::to_float(lhs) > rhs),
+        (Float(lhs), Decimal(rhs)) => Boolean(lhs > decimal::to_float(rhs)),
+        (Date(lhs), Date(rhs)) => Boolean(lhs > rhs),
+        (Date(lhs), Timestamp(rhs)) => Boolean(NaiveDateTime::from(lhs) > rhs),
+        (Time(lhs), Time(rhs)) => Boolean(lhs > rhs),
+        (Timestamp(lhs), Date(rhs)) => Boolean(lhs > NaiveDateTime::from(rhs)),
+        (Timestamp(lhs), Timestamp(rhs)) => Boolean(lhs > rhs),
+        (Interval(lhs), Interval(rhs)) => Boolean(lhs > rhs),
+        (Array(lhs), Array(rhs)) => compare_arrays(lhs, rhs, Ordering::is_gt)?,
+        (Null, _) | (_, Null) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't compare {} and {}", lhs, rhs))),
+    })
+}
+
+/// Compares whether the left value is less than the right value.
+fn less_than(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        #[allow(clippy::bool_comparison)]
+        (Boolean(lhs), Boolean(rhs)) => Boolean(lhs < rhs),
+        (Integer(lhs), Integer(rhs)) => Boolean(lhs < rhs),
+        (Integer(lhs), Float(rhs)) => Boolean((lhs as f64) < rhs),
+        (Float(lhs), Integer(rhs)) => Boolean(lhs < rhs as f64),
+        (Float(lhs), Float(rhs)) => Boolean(lhs < rhs),
+        (String(lhs), String(rhs)) => Boolean(lhs < rhs),
+        (Bytes(lhs), Bytes(rhs)) => Boolean(lhs < rhs),
+        (Uuid(lhs), Uuid(rhs)) => Boolean(lhs < rhs),
+        (Decimal(lhs), Decimal(rhs)) => Boolean(lhs < rhs),
+        (Decimal(lhs), Integer(rhs)) => Boolean(lhs < rhs.into()),
+        (Integer(lhs), Decimal(rhs)) => Boolean(rust_decimal::Decimal::from(lhs) < rhs),
+        (Decimal(lhs), Float(rhs)) => Boolean(decimal::to_float(lhs) < rhs),
+        (Float(lhs), Decimal(rhs)) => Boolean(lhs < decimal::to_float(rhs)),
+        (Date(lhs), Date(rhs)) => Boolean(lhs < rhs),
+        (Date(lhs), Timestamp(rhs)) => Boolean(NaiveDateTime::from(lhs) < rhs),
+        (Time(lhs), Time(rhs)) => Boolean(lhs < rhs),
+        (Timestamp(lhs), Date(rhs)) => Boolean(lhs < NaiveDateTime::from(rhs)),
+        (Timestamp(lhs), Timestamp(rhs)) => Boolean(lhs < rhs),
+        (Interval(lhs), Interval(rhs)) => Boolean(lhs < rhs),
+        (Array(lhs), Array(rhs)) => compare_arrays(lhs, rhs, Ordering::is_lt)?,
+        (Null, _) | (_, Null) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't compare {} and {}", lhs, rhs))),
+    })
+}
+
+/// Compares two arrays element-wise, where NULL elements are equal to each
+/// other and sort first, like Postgres.
+fn compare_arrays(lhs: Vec<Value>, rhs: Vec<Value>, f: fn(Ordering) -> bool) -> Result<Value> {
+    match lhs.partial_cmp(&rhs) {
+        Some(ordering) => Ok(Value::Boolean(f(ordering))),
+        None => Err(Error::Value(format!(
+            "Can't compare {} and {}",
+            Value::Array(lhs),
+            Value::Array(rhs)
+        ))),
+    }
+}
+
+/// Checks whether a value equals any element of an array. Like IN, yields NULL
+/// rather than FALSE if there is no match but either side contains a NULL.
+fn any(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    let elements = match rhs {
+        Array(elements) => elements,
+        Null => return Ok(Null),
+        rhs => return Err(Error::Value(format!("Can't compare {} with ANY of {}", lhs, rhs))),
+    };
+    let mut result = Boolean(false);
+    for element in elements {
+        match equal(lhs.clone(), element)? {
+            Boolean(true) => return Ok(Boolean(true)),
+            Boolean(false) => {}
+            _ => result = Null,
+        }
+    }
+    Ok(result)
+}
+
+// Mathematical operations
+/// Adds two numbers, or an interval or number of days to a date or time.
+fn add(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (Decimal(lhs), Decimal(rhs)) => Decimal(decimal_overflow(lhs.checked_add(rhs))?),
+        (Decimal(lhs), Integer(rhs)) => Decimal(decimal_overflow(lhs.checked_add(rhs.into()))?),
+        (Integer(lhs), Decimal(rhs)) => {
+            Decimal(decimal_overflow(rust_decimal::Decimal::from(lhs).checked_add(rhs))?)
+        }
+        (Decimal(lhs), Float(rhs)) => Float(decimal::to_float(lhs) + rhs),
+        (Float(lhs), Decimal(rhs)) => Float(lhs + decimal::to_float(rhs)),
+        (Decimal(_), Null) | (Null, Decimal(_)) => Null,
+        (Integer(lhs), Integer(rhs)) => {
+            Integer(lhs.checked_add(rhs).ok_or_else(|| Error::Value("Integer overflow".into()))?)
+        }
+        (Integer(lhs), Float(rhs)) => Float(lhs as f64 + rhs),
+        (Integer(_), Null) => Null,
+        (Float(lhs), Float(rhs)) => Float(lhs + rhs),
+        (Float(lhs), Integer(rhs)) => Float(lhs + rhs as f64),
+        (Float(_), Null) => Null,
+        (Null, Float(_)) => Null,
+        (Null, Integer(_)) => Null,
+        (Null, Null) => Null,
+        (Timestamp(ts), Interval(i)) | (Interval(i), Timestamp(ts)) => {
+            Timestamp(timestamp_overflow(datetime::add_interval(ts, i))?)
+        }
+        // Like Postgres, adding an interval to a date yields a timestamp.
+        (Date(date), Interval(i)) | (Interval(i), Date(date)) => {
+            Timestamp(timestamp_overflow(datetime::add_interval(date.into(), i))?)
+        }
+        (Time(time), Interval(i)) | (Interval(i), Time(time)) => {
+            Time(datetime::add_time_interval(time, i))
+        }
+        (Interval(lhs), Interval(rhs)) => Interval(interval_overflow(lhs.checked_add(rhs))?),
+        (Date(date), Integer(days)) | (Integer(days), Date(date)) => Date(add_days(date, days)?),
+        (Date(_) | Time(_) | Timestamp(_) | Interval(_), Null)
+        | (Null, Date(_) | Time(_) | Timestamp(_) | Interval(_)) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't add {} and {}", lhs, rhs))),
+    })
+}
+
+/// Divides two numbers, or an interval by a number.
+fn divide(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (Decimal(_) | Integer(_), Decimal(rhs)) if rhs.is_zero() => {
+            return Err(Error::Value("Can't divide by zero".into()))
+        }
+        (Decimal(_), Integer(0)) => return Err(Error::Value("Can't divide by zero".into())),
+        (Decimal(lhs), Decimal(rhs)) => Decimal(decimal_overflow(lhs.checked_div(rhs))?),
+        (Decimal(lhs), Integer(rhs)) => Decimal(decimal_overflow(lhs.checked_div(rhs.into()))?),
+        (Integer(lhs), Decimal(rhs)) => {
+            Decimal(decimal_overflow(rust_decimal::Decimal::from(lhs).checked_div(rhs))?)
+        }
+        (Decimal(lhs), Float(rhs)) => Float(decimal::to_float(lhs) / rhs),
+        (Float(lhs), Decimal(rhs)) => Float(lhs / decimal::to_float(rhs)),
+        (Decimal(_), Null) | (Null, Decimal(_)) => Null,
+        (Integer(_), Integer(0)) => return Err(Error::Value("Can't divide by zero".into())),
+        (Integer(lhs), Integer(rhs)) => Integer(lhs / rhs),
+        (Integer(lhs), Float(rhs)) => Float(lhs as f64 / rhs),
+        (Integer(_), Null) => Null,
+        (Float(lhs), Integer(rhs)) => Float(lhs / rhs as f64),
+        (Float(lhs), Float(rhs)) => Float(lhs / rhs),
+        (Float(_), Null) => Null,
+        (Null, Float(_)) => Null,
+        (Null, Integer(_)) => Null,
+        (Null, Null) => Null,
+        (Interval(_), Integer(0) | Float(0.0)) => {
+            return Err(Error::Value("Can't divide by zero".into()))
+        }
+        (Interval(lhs), Integer(rhs)) => {
+            Interval(interval_overflow(lhs.checked_mul_float(1.0 / rhs as f64))?)
+        }
+        (Interval(lhs), Float(rhs)) => {
+            Interval(interval_overflow(lhs.checked_mul_float(1.0 / rhs))?)
+        }
+        (Interval(_), Null) | (Null, Interval(_)) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't divide {} and {}", lhs, rhs))),
+    })
+}
+
+/// Raises a number to the power of another.
+pub(super) fn exponentiate(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        // Decimal exponentiation isn't exact in general, so it yields a float.
+        (Decimal(lhs), Decimal(rhs)) => Float(decimal::to_float(lhs).powf(decimal::to_float(rhs))),
+        (Decimal(lhs), Integer(rhs)) => Float(decimal::to_float(lhs).powf(rhs as f64)),
+        (Decimal(lhs), Float(rhs)) => Float(decimal::to_float(lhs).powf(rhs)),
+        (Integer(lhs), Decimal(rhs)) => Float((lhs as f64).powf(decimal::to_float(rhs))),
+        (Float(lhs), Decimal(rhs)) => Float(lhs.powf(decimal::to_float(rhs))),
+        (Decimal(_), Null) | (Null, Decimal(_)) => Null,
+        (Integer(lhs), Integer(rhs)) if rhs >= 0 => Integer(
+            lhs.checked_pow(rhs as u32).ok_or_else(|| Error::Value("Integer overflow".into()))?,
+        ),
+        (Integer(lhs), Integer(rhs)) => Float((lhs as f64).powf(rhs as f64)),
+        (Integer(lhs), Float(rhs)) => Float((lhs as f64).powf(rhs)),
+        (Integer(_), Null) => Null,
+        (Float(lhs), Integer(rhs)) => Float((lhs).powi(rhs as i32)),
+        (Float(lhs), Float(rhs)) => Float((lhs).powf(rhs)),
+        (Float(_), Null) => Null,
+        (Null, Float(_)) => Null,
+        (Null, Integer(_)) => Null,
+        (Null, Null) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't exponentiate {} and {}", lhs, rhs))),
+    })
+}
+
+/// Takes the remainder of dividing two numbers.
+pub(super) fn modulo(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        // This uses remainder semantics, like Postgres.
+        (Decimal(_) | Integer(_), Decimal(rhs)) if rhs.is_zero() => {
+            return Err(Error::Value("Can't divide by zero".into()))
+        }
+        (Decimal(_), Integer(0)) => return Err(Error::Value("Can't divide by zero".into())),
+        (Decimal(lhs), Decimal(rhs)) => Decimal(decimal_overflow(lhs.checked_rem(rhs))?),
+        (Decimal(lhs), Integer(rhs)) => Decimal(decimal_overflow(lhs.checked_rem(rhs.into()))?),
+        (Integer(lhs), Decimal(rhs)) => {
+            Decimal(decimal_overflow(rust_decimal::Decimal::from(lhs).checked_rem(rhs))?)
+        }
+        (Decimal(lhs), Float(rhs)) => Float(decimal::to_float(lhs) % rhs),
+        (Float(lhs), Decimal(rhs)) => Float(lhs % decimal::to_float(rhs)),
+        (Decimal(_), Null) | (Null, Decimal(_)) => Null,
+        // This uses remainder semantics, like Postgres.
+        (Integer(_), Integer(0)) => return Err(Error::Value("Can't divide by zero".into())),
+        (Integer(lhs), Integer(rhs)) => {
+            Integer(lhs.checked_rem(rhs).ok_or_else(|| Error::Value("Integer overflow".into()))?)
+        }
+        (Integer(lhs), Float(rhs)) => Float(lhs as f64 % rhs),
+        (Integer(_), Null) => Null,
+        (Float(lhs), Integer(rhs)) => Float(lhs % rhs as f64),
+        (Float(lhs), Float(rhs)) => Float(lhs % rhs),
+        (Float(_), Null) => Null,
+        (Null, Float(_)) => Null,
+        (Null, Integer(_)) => Null,
+        (Null, Null) => Null,
+        (lhs, rhs) => {
+            return Err(Error::Value(format!("Can't take modulo of {} and {}", lhs, rhs)))
+        }
+    })
+}
+
+/// Multiplies two numbers, or an interval by a number.
+fn multiply(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (Decimal(lhs), Decimal(rhs)) => Decimal(decimal_overflow(lhs.checked_mul(rhs))?),
+        (Decimal(lhs), Integer(rhs)) => Decimal(decimal_overflow(lhs.checked_mul(rhs.into()))?),
+        (Integer(lhs), Decimal(rhs)) => {
+            Decimal(decimal_overflow(rust_decimal::Decimal::from(lhs).checked_mul(rhs))?)
+        }
+        (Decimal(lhs), Float(rhs)) => Float(decimal::to_float(lhs) * rhs),
+        (Float(lhs), Decimal(rhs)) => Float(lhs * decimal::to_float(rhs)),
+        (Decimal(_), Null) | (Null, Decimal(_)) => Null,
+        (Integer(lhs), Integer(rhs)) => {
+            Integer(lhs.checked_mul(rhs).ok_or_else(|| Error::Value("Integer overflow".into()))?)
+        }
+        (Integer(lhs), Float(rhs)) => Float(lhs as f64 * rhs),
+        (Integer(_), Null) => Null,
+        (Float(lhs), Integer(rhs)) => Float(lhs * rhs as f64),
+        (Float(lhs), Float(rhs)) => Float(lhs * rhs),
+        (Float(_), Null) => Null,
+        (Null, Float(_)) => Null,
+        (Null, Integer(_)) => Null,
+        (Null, Null) => Null,
+        (Interval(i), Integer(n)) | (Integer(n), Interval(i)) => {
+            Interval(interval_overflow(i.checked_mul(n))?)
+        }
+        (Interval(i), Float(f)) | (Float(f), Interval(i)) => {
+            Interval(interval_overflow(i.checked_mul_float(f))?)
+        }
+        (Interval(_), Null) | (Null, Interval(_)) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't multiply {} and {}", lhs, rhs))),
+    })
+}
+
+/// Subtracts two numbers, dates or times, or an interval or number of days
+/// from a date or time.
+fn subtract(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (Decimal(lhs), Decimal(rhs)) => Decimal(decimal_overflow(lhs.checked_sub(rhs))?),
+        (Decimal(lhs), Integer(rhs)) => Decimal(decimal_overflow(lhs.checked_sub(rhs.into()))?),
+        (Integer(lhs), Decimal(rhs)) => {
+            Decimal(decimal_overflow(rust_decimal::Decimal::from(lhs).checked_sub(rhs))?)
+        }
+        (Decimal(lhs), Float(rhs)) => Float(decimal::to_float(lhs) - rhs),
+        (Float(lhs), Decimal(rhs)) => Float(lhs - decimal::to_float(rhs)),
+        (Decimal(_), Null) | (Null, Decimal(_)) => Null,
+        (Integer(lhs), Integer(rhs)) => {
+            Integer(lhs.checked_sub(rhs).ok_or_else(|| Error::Value("Integer overflow".into()))?)
+        }
+        (Integer(lhs), Float(rhs)) => Float(lhs as f64 - rhs),
+        (Integer(_), Null) => Null,
+        (Float(lhs), Integer(rhs)) => Float(lhs - rhs as f64),
+        (Float(lhs), Float(rhs)) => Float(lhs - rhs),
+        (Float(_), Null) => Null,
+        (Null, Float(_)) => Null,
+        (Null, Integer(_)) => Null,
+        (Null, Null) => Null,
+        (Timestamp(ts), Interval(i)) => {
+            let i = interval_overflow(i.checked_neg())?;
+            Timestamp(timestamp_overflow(datetime::add_interval(ts, i))?)
+        }
+        (Date(date), Interval(i)) => {
+            let i = interval_overflow(i.checked_neg())?;
+            Timestamp(timestamp_overflow(datetime::add_interval(date.into(), i))?)
+        }
+        (Time(time), Interval(i)) => {
+            Time(datetime::add_time_interval(time, interval_overflow(i.checked_neg())?))
+        }
+        (Interval(lhs), Interval(rhs)) => {
+            Interval(interval_overflow(rhs.checked_neg().and_then(|rhs| lhs.checked_add(rhs)))?)
+        }
+        // The difference between timestamps is an interval of days and
+        // microseconds, while the difference between dates is a number of days.
+        (Timestamp(lhs), Timestamp(rhs)) => {
+            Interval(interval_overflow(datetime::subtract_timestamps(lhs, rhs))?)
+        }
+        (Timestamp(lhs), Date(rhs)) => {
+            Interval(interval_overflow(datetime::subtract_timestamps(lhs, rhs.into()))?)
+        }
+        (Date(lhs), Timestamp(rhs)) => {
+            Interval(interval_overflow(datetime::subtract_timestamps(lhs.into(), rhs))?)
+        }
+        (Date(lhs), Date(rhs)) => Integer((lhs - rhs).num_days()),
+        (Time(lhs), Time(rhs)) => {
+            let micros = (lhs - rhs).num_microseconds().expect("time difference fits in i64");
+            Interval(datetime::Interval { months: 0, days: 0, micros })
+        }
+        (Date(date), Integer(days)) => {
+            let days = days.checked_neg().ok_or_else(|| Error::Value("Integer overflow".into()))?;
+            Date(add_days(date, days)?)
+        }
+        (Date(_) | Time(_) | Timestamp(_) | Interval(_), Null)
+        | (Null, Date(_) | Time(_) | Timestamp(_) | Interval(_)) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't subtract {} and {}", lhs, rhs))),
+    })
+}
+
+// String operations
+/// Concatenates two strings, byte strings or arrays, or an array and an element.
+fn concat(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (Null, _) | (_, Null) => Null,
+        (Array(lhs), Array(rhs)) => Array([lhs, rhs].concat()),
+        (Array(mut lhs), rhs) => {
+            lhs.push(rhs);
+            Array(lhs)
+        }
+        (lhs, Array(mut rhs)) => {
+            rhs.insert(0, lhs);
+            Array(rhs)
+        }
+        (String(lhs), String(rhs)) => String(lhs + &rhs),
+        (Bytes(lhs), Bytes(rhs)) => Bytes([lhs, rhs].concat()),
+        // Non-string values are converted to strings, as long as one side is a string.
+        (String(lhs), rhs) => String(lhs + &rhs.to_string()),
+        (lhs, String(rhs)) => String(lhs.to_string() + &rhs),
+        (lhs, rhs) => return Err(Error::Value(format!("Can't concatenate {} and {}", lhs, rhs))),
+    })
+}
+
+/// Matches a string against a LIKE pattern.
+fn like(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (String(lhs), String(rhs)) => Boolean(like_regex(&rhs)?.is_match(&lhs)),
+        (String(_), Null) => Null,
+        (Null, String(_)) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't LIKE {} and {}", lhs, rhs))),
+    })
+}
+
+/// Converts a LIKE pattern into an anchored regular expression.
+fn like_regex(pattern: &str) -> Result<Regex> {
+    Ok(Regex::new(&format!(
+        "^{}$",
+        regex::escape(pattern)
+            .replace('%', ".*")
+            .replace(".*.*", "%")
+            .replace('_', ".")
+            .replace("..", "_")
+    ))?)
+}
+
+// Full-text search operations
+/// Checks whether a text matches a full-text query.
+fn matches(lhs: Value, rhs: Value) -> Result<Value> {
+    Ok(match relevance(lhs, rhs)? {
+        Value::Float(relevance) => Value::Boolean(relevance > 0.0),
+        value => value,
+    })
+}
+
+/// Computes the relevance of a text for a full-text query.
+fn relevance(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (String(text), String(query)) => Float(fulltext::relevance(&text, &query)),
+        (String(_) | Null, Null) | (Null, String(_)) => Null,
+        (lhs, rhs) => return Err(Error::Value(format!("Can't match {} against {}", lhs, rhs))),
+    })
+}
+
+// JSON operations
+/// Extracts a JSON object field or array element.
+fn json_extract(lhs: Value, rhs: Value) -> Result<Value> {
+    match (lhs, rhs) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (lhs, rhs) => json::extract(lhs, rhs, false),
+    }
+}
+
+/// Extracts a JSON object field or array element as text.
+fn json_extract_text(lhs: Value, rhs: Value) -> Result<Value> {
+    match (lhs, rhs) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (lhs, rhs) => json::extract(lhs, rhs, true),
+    }
+}
+
+// Array operations
+/// Looks up an array element by 1-based index, or NULL if out of range.
+fn array_element(lhs: Value, rhs: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match (lhs, rhs) {
+        (Null, _) | (_, Null) => Null,
+        (Array(mut elements), Integer(index)) => match usize::try_from(index) {
+            Ok(index) if (1..=elements.len()).contains(&index) => elements.swap_remove(index - 1),
+            _ => Null,
+        },
+        (lhs, rhs) => return Err(Error::Value(format!("Can't index {} by {}", lhs, rhs))),
+    })
+}
+
+// Unary operations
+/// Logical NOT.
+fn not(value: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match value {
+        Boolean(b) => Boolean(!b),
+        Null => Null,
+        value => return Err(Error::Value(format!("Can't negate {}", value))),
+    })
+}
+
+/// Checks whether a value is NULL.
+fn is_null(value: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match value {
+        Null => Boolean(true),
+        _ => Boolean(false),
+    })
+}
+
+/// Asserts that a value is a number, i.e. unary +.
+fn assert(value: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match value {
+        Decimal(d) => Decimal(d),
+        Float(f) => Float(f),
+        Integer(i) => Integer(i),
+        Null => Null,
+        expr => return Err(Error::Value(format!("Can't take the positive of {}", expr))),
+    })
+}
+
+/// Takes the factorial of an integer.
+fn factorial(value: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match value {
+        Integer(i) if i < 0 => {
+            return Err(Error::Value("Can't take factorial of negative number".into()))
+        }
+        Integer(i) => Integer((1..=i).product()),
+        Null => Null,
+        value => return Err(Error::Value(format!("Can't take factorial of {}", value))),
+    })
+}
+
+/// Negates a number or interval.
+fn negate(value: Value) -> Result<Value> {
+    use Value::*;
+    Ok(match value {
+        Integer(i) => Integer(-i),
+        Float(f) => Float(-f),
+        Decimal(d) => Decimal(-d),
+        Interval(i) => Interval(interval_overflow(i.checked_neg())?),
+        Null => Null,
+        value => return Err(Error::Value(format!("Can't negate {}", value))),
+    })
+}
+
+/// A compiled expression, see Expression::compile().
+pub struct Compiled(Box<CompiledFn>);
+
+/// A compiled expression closure, evaluating the expression for a row.
+type CompiledFn = dyn Fn(&Row) -> Result<Value> + Send + Sync;
+
+impl Compiled {
+    /// Evaluates the compiled expression for a row.
+    pub fn evaluate(&self, row: &Row) -> Result<Value> {
+        (self.0)(row)
+    }
+}
+
+/// Returns the result of a checked decimal operation, or an overflow error.
+fn decimal_overflow(result: Option<rust_decimal::Decimal>) -> Result<rust_decimal::Decimal> {
+    result.ok_or_else(|| Error::Value("Decimal overflow".into()))
+}
+
+/// Returns the result of a checked interval operation, or an overflow error.
+fn interval_overflow(result: Option<Interval>) -> Result<Interval> {
+    result.ok_or_else(|| Error::Value("Interval overflow".into()))
+}
+
+/// Returns the result of a checked timestamp operation, or an out of range
+/// error.
+fn timestamp_overflow(result: Option<NaiveDateTime>) -> Result<NaiveDateTime> {
+    result.ok_or_else(|| Error::Value("Timestamp out of range".into()))
+}
+
+/// Adds a number of days to a date, or errors if out of range.
+fn add_days(date: NaiveDate, days: i64) -> Result<NaiveDate> {
+    Duration::try_days(days)
+        .and_then(|days| date.checked_add_signed(days))
+        .ok_or_else(|| Error::Value("Date out of range".into()))
+}
+
+/// Compares two lower or upper range bounds, where an unbounded lower bound is
+/// the lowest and an unbounded upper bound is the highest. For equal values, an
+/// inclusive bound is below an exclusive lower bound and above an exclusive upper
+/// bound. Returns None if the values can't be compared.
+fn compare_bounds(a: &Bound<Value>, b: &Bound<Value>, upper: bool) -> Option<Ordering> {
+    use Bound::*;
+    Some(match (a, b) {
+        (Unbounded, Unbounded) => Ordering::Equal,
+        (Unbounded, _) if upper => Ordering::Greater,
+        (Unbounded, _) => Ordering::Less,
+        (_, Unbounded) if upper => Ordering::Less,
+        (_, Unbounded) => Ordering::Greater,
+        (Included(x) | Excluded(x), Included(y) | Excluded(y)) => match x.partial_cmp(y)? {
+            Ordering::Equal => match (a, b) {
+                (Included(_), Excluded(_)) if upper => Ordering::Greater,
+                (Included(_), Excluded(_)) => Ordering::Less,
+                (Excluded(_), Included(_)) if upper => Ordering::Less,
+                (Excluded(_), Included(_)) => Ordering::Greater,
+                _ => Ordering::Equal,
+            },
+            ordering => ordering,
+        },
+    })
+}
+
+/// Returns the lowest of two lower or upper range bounds.
+fn min_bound(a: Bound<Value>, b: Bound<Value>, upper: bool) -> Option<Bound<Value>> {
+    Some(if compare_bounds(&a, &b, upper)? == Ordering::Greater { b } else { a })
+}
+
+/// Returns the highest of two lower or upper range bounds.
+fn max_bound(a: Bound<Value>, b: Bound<Value>, upper: bool) -> Option<Bound<Value>> {
+    Some(if compare_bounds(&a, &b, upper)? == Ordering::Less { b } else { a })
+}
+
 impl Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
@@ -524,6 +1395,19 @@ impl Display for Expression {
             Self::Field(i, None) => format!("#{}", i),
             Self::Field(_, Some((None, name))) => name.to_string(),
             Self::Field(_, Some((Some(table), name))) => format!("{}.{}", table, name),
+            Self::Outer(i, None) => format!("outer #{}", i),
+            Self::Outer(_, Some((None, name))) => format!("outer {}", name),
+            Self::Outer(_, Some((Some(table), name))) => format!("outer {}.{}", table, name),
+            Self::Parameter(i) => format!("${}", i),
+            Self::Function(Function::Array, args) => format!(
+                "ARRAY[{}]",
+                args.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", ")
+            ),
+            Self::Function(function, args) => format!(
+                "{}({})",
+                function,
+                args.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", ")
+            ),
 
             Self::And(lhs, rhs) => format!("{} AND {}", lhs, rhs),
             Self::Or(lhs, rhs) => format!("{} OR {}", lhs, rhs),
@@ -533,6 +1417,13 @@ impl Display for Expression {
             Self::GreaterThan(lhs, rhs) => format!("{} > {}", lhs, rhs),
             Self::LessThan(lhs, rhs) => format!("{} < {}", lhs, rhs),
             Self::IsNull(expr) => format!("{} IS NULL", expr),
+            Self::Any(lhs, rhs) => format!("{} = ANY ({})", lhs, rhs),
+            Self::Between(expr, low, high) => format!("{} BETWEEN {} AND {}", expr, low, high),
+            Self::In(expr, list) => format!(
+                "{} IN ({})",
+                expr,
+                list.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", ")
+            ),
 
             Self::Add(lhs, rhs) => format!("{} + {}", lhs, rhs),
             Self::Assert(expr) => expr.to_string(),
@@ -544,7 +1435,18 @@ impl Display for Expression {
             Self::Negate(expr) => format!("-{}", expr),
             Self::Subtract(lhs, rhs) => format!("{} - {}", lhs, rhs),
 
+            Self::Concat(lhs, rhs) => format!("{} || {}", lhs, rhs),
             Self::Like(lhs, rhs) => format!("{} LIKE {}", lhs, rhs),
+            Self::Match(lhs, rhs) => format!("{} @@ {}", lhs, rhs),
+            Self::Relevance(lhs, rhs) => format!("MATCH ({}) AGAINST ({})", lhs, rhs),
+
+            Self::JsonExtract(lhs, rhs) => format!("{} -> {}", lhs, rhs),
+            Self::JsonExtractText(lhs, rhs) => format!("{} ->> {}", lhs, rhs),
+
+            Self::ArrayElement(lhs, rhs) => format!("{}[{}]", lhs, rhs),
+
+            Self::Cast(expr, datatype) => format!("CAST({} AS {})", expr, datatype),
+            Self::Collate(expr, collation) => format!("{} COLLATE {}", expr, collation),
         };
         write!(f, "{}", s)
     }
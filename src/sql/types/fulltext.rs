@@ -0,0 +1,58 @@
+//! Support for full-text search. Text is split into terms, which are its runs
+//! of alphanumeric characters in lowercase, e.g. "Star Wars: A New Hope" has
+//! the terms star, wars, a, new and hope. FULLTEXT indexes map the terms of a
+//! column's values to the rows containing them.
+//!
+//! A text matches a query if it contains any of the query's terms, and its
+//! relevance sums 1 + ln(n) for each distinct query term occurring n times in
+//! the text, such that texts containing more of the terms rank higher than
+//! texts repeating one of them.
+
+use std::collections::{BTreeSet, HashMap};
+
+/// Splits a text into its terms, in order and possibly repeated.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()).map(|t| t.to_lowercase())
+}
+
+/// Returns the distinct terms of a text.
+pub fn terms(text: &str) -> BTreeSet<String> {
+    tokenize(text).collect()
+}
+
+/// Returns the relevance of a text for a query, or 0 if it doesn't match.
+pub fn relevance(text: &str, query: &str) -> f64 {
+    let query = terms(query);
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for term in tokenize(text).filter(|t| query.contains(t)) {
+        *counts.entry(term).or_default() += 1;
+    }
+    counts.values().map(|&n| 1.0 + f64::from(n).ln()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize() {
+        let tokens = |s| super::tokenize(s).collect::<Vec<_>>();
+        assert_eq!(tokens("Star Wars: A New Hope"), vec!["star", "wars", "a", "new", "hope"]);
+        assert_eq!(tokens("  don't-stop, ÉTÉ 2049 "), vec!["don", "t", "stop", "été", "2049"]);
+        assert_eq!(tokens(" .,;! "), Vec::<String>::new());
+        assert_eq!(
+            terms("the cat and the hat"),
+            ["and", "cat", "hat", "the"].map(String::from).into()
+        );
+    }
+
+    #[test]
+    fn relevance() {
+        assert_eq!(super::relevance("The Empire Strikes Back", "star wars"), 0.0);
+        assert_eq!(super::relevance("Star Trek", "star wars"), 1.0);
+        assert_eq!(super::relevance("Star Wars", "STAR wars star"), 2.0);
+        assert_eq!(super::relevance("star star", "star"), 1.0 + 2f64.ln());
+        assert_eq!(super::relevance("", "star"), 0.0);
+        assert_eq!(super::relevance("star", ""), 0.0);
+    }
+}
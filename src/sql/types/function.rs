@@ -0,0 +1,589 @@
+use super::expression::{exponentiate, modulo};
+use super::{datetime, decimal, json, DataType, Value};
+use crate::error::{Error, Result};
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde_derive::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+use std::ops::RangeInclusive;
+
+/// A built-in scalar function. Functions are resolved by name during planning,
+/// and evaluated on argument values by evaluate().
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Function {
+    Abs,
+    Array,
+    Cardinality,
+    Ceil,
+    Coalesce,
+    Concat,
+    CurrVal,
+    DateTrunc,
+    Extract,
+    Floor,
+    GenRandomUuid,
+    Greatest,
+    JsonExtract,
+    JsonSet,
+    Left,
+    Length,
+    Least,
+    Lower,
+    LTrim,
+    Mod,
+    NextVal,
+    Now,
+    NullIf,
+    OctetLength,
+    Position,
+    Power,
+    Random,
+    Replace,
+    Right,
+    Round,
+    RTrim,
+    SetVal,
+    Sign,
+    Sqrt,
+    Substring,
+    Trim,
+    Upper,
+}
+
+impl Function {
+    /// All functions, used for name lookups.
+    const ALL: [Function; 37] = [
+        Self::Abs,
+        Self::Array,
+        Self::Cardinality,
+        Self::Ceil,
+        Self::Coalesce,
+        Self::Concat,
+        Self::CurrVal,
+        Self::DateTrunc,
+        Self::Extract,
+        Self::Floor,
+        Self::GenRandomUuid,
+        Self::Greatest,
+        Self::JsonExtract,
+        Self::JsonSet,
+        Self::Left,
+        Self::Least,
+        Self::Length,
+        Self::Lower,
+        Self::LTrim,
+        Self::Mod,
+        Self::NextVal,
+        Self::Now,
+        Self::NullIf,
+        Self::OctetLength,
+        Self::Position,
+        Self::Power,
+        Self::Random,
+        Self::Replace,
+        Self::Right,
+        Self::Round,
+        Self::RTrim,
+        Self::SetVal,
+        Self::Sign,
+        Self::Sqrt,
+        Self::Substring,
+        Self::Trim,
+        Self::Upper,
+    ];
+
+    /// Looks up a function by its (lowercase) name.
+    pub fn lookup(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// Returns the function name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Abs => "abs",
+            Self::Array => "array",
+            Self::Cardinality => "cardinality",
+            Self::Ceil => "ceil",
+            Self::Coalesce => "coalesce",
+            Self::Concat => "concat",
+            Self::CurrVal => "currval",
+            Self::DateTrunc => "date_trunc",
+            Self::Extract => "extract",
+            Self::Floor => "floor",
+            Self::GenRandomUuid => "gen_random_uuid",
+            Self::Greatest => "greatest",
+            Self::JsonExtract => "json_extract",
+            Self::JsonSet => "json_set",
+            Self::Least => "least",
+            Self::Left => "left",
+            Self::Length => "length",
+            Self::Lower => "lower",
+            Self::LTrim => "ltrim",
+            Self::Mod => "mod",
+            Self::NextVal => "nextval",
+            Self::Now => "now",
+            Self::NullIf => "nullif",
+            Self::OctetLength => "octet_length",
+            Self::Position => "position",
+            Self::Power => "power",
+            Self::Random => "random",
+            Self::Replace => "replace",
+            Self::Right => "right",
+            Self::Round => "round",
+            Self::RTrim => "rtrim",
+            Self::SetVal => "setval",
+            Self::Sign => "sign",
+            Self::Sqrt => "sqrt",
+            Self::Substring => "substring",
+            Self::Trim => "trim",
+            Self::Upper => "upper",
+        }
+    }
+
+    /// Returns the number of arguments the function accepts.
+    fn arity(&self) -> RangeInclusive<usize> {
+        match self {
+            Self::Array => 0..=usize::MAX,
+            Self::Coalesce | Self::Concat | Self::Greatest | Self::Least => 1..=usize::MAX,
+            Self::GenRandomUuid | Self::Now | Self::Random => 0..=0,
+            Self::Abs
+            | Self::Cardinality
+            | Self::Ceil
+            | Self::CurrVal
+            | Self::Floor
+            | Self::Length
+            | Self::Lower
+            | Self::NextVal
+            | Self::OctetLength
+            | Self::Sign
+            | Self::Sqrt
+            | Self::Upper => 1..=1,
+            Self::LTrim | Self::Round | Self::RTrim | Self::Trim => 1..=2,
+            Self::DateTrunc
+            | Self::Extract
+            | Self::JsonExtract
+            | Self::Left
+            | Self::Mod
+            | Self::NullIf
+            | Self::Position
+            | Self::Power
+            | Self::Right
+            | Self::SetVal => 2..=2,
+            Self::Substring => 2..=3,
+            Self::JsonSet | Self::Replace => 3..=3,
+        }
+    }
+
+    /// Returns true if the function can return a different result every time
+    /// it's called with the same arguments, and must be evaluated for every
+    /// row. Other functions, including NOW(), are evaluated once per statement
+    /// when their arguments are constant.
+    pub fn is_volatile(&self) -> bool {
+        matches!(self, Self::GenRandomUuid | Self::Random) || self.is_sequence()
+    }
+
+    /// Returns true if the function always returns the same result for the
+    /// same arguments, unlike NOW() and volatile functions, such that it can
+    /// compute generated columns.
+    pub fn is_deterministic(&self) -> bool {
+        !self.is_volatile() && *self != Self::Now
+    }
+
+    /// Returns true if the function reads or modifies a sequence. These are
+    /// evaluated via evaluate_sequence(), see Expression::bind_sequences().
+    pub fn is_sequence(&self) -> bool {
+        matches!(self, Self::CurrVal | Self::NextVal | Self::SetVal)
+    }
+
+    /// Checks that the function accepts the given number of arguments.
+    pub fn check_arity(&self, args: usize) -> Result<()> {
+        let arity = self.arity();
+        if arity.contains(&args) {
+            return Ok(());
+        }
+        Err(Error::Value(match (arity.start(), arity.end()) {
+            (min, &usize::MAX) => {
+                format!("{} takes at least {} arguments, got {}", self, min, args)
+            }
+            (min, max) if min == max => format!("{} takes {} arguments, got {}", self, min, args),
+            (min, max) => format!("{} takes {} to {} arguments, got {}", self, min, max, args),
+        }))
+    }
+
+    /// Evaluates the function for the given argument values. Except for the
+    /// ARRAY constructor, CONCAT and the conditional functions COALESCE,
+    /// GREATEST, LEAST and NULLIF, functions return NULL if any argument is
+    /// NULL (for JSON_SET, only the document and path; a NULL value is stored
+    /// as JSON null).
+    pub fn evaluate(&self, args: Vec<Value>) -> Result<Value> {
+        self.check_arity(args.len())?;
+        match self {
+            Self::CurrVal | Self::NextVal | Self::SetVal => {
+                return Err(Error::Value(format!("{} can't be used here", self)));
+            }
+            Self::Array => {
+                if args.iter().any(|v| matches!(v, Value::Array(_))) {
+                    return Err(Error::Value("Nested arrays are not supported".into()));
+                }
+                return Ok(Value::Array(unify(args, self)?));
+            }
+            Self::Coalesce => {
+                let args = unify(args, self)?;
+                return Ok(args.into_iter().find(|v| *v != Value::Null).unwrap_or(Value::Null));
+            }
+            // NULL arguments are ignored, like Postgres. Like MAX and MIN,
+            // values that can't be compared (e.g. NaN) yield NULL.
+            Self::Greatest | Self::Least => {
+                let mut result = Value::Null;
+                for arg in unify(args, self)?.into_iter().filter(|v| *v != Value::Null) {
+                    match arg.partial_cmp(&result) {
+                        _ if result == Value::Null => result = arg,
+                        None => return Ok(Value::Null),
+                        Some(Ordering::Greater) if *self == Self::Greatest => result = arg,
+                        Some(Ordering::Less) if *self == Self::Least => result = arg,
+                        Some(_) => {}
+                    }
+                }
+                return Ok(result);
+            }
+            // Returns the first argument unchanged, but compares the unified values.
+            Self::NullIf => {
+                let value = args[0].clone();
+                let args = unify(args, self)?;
+                if args[0] != Value::Null && args[0] == args[1] {
+                    return Ok(Value::Null);
+                }
+                return Ok(value);
+            }
+            _ => {}
+        }
+        if *self == Self::Concat {
+            return Ok(Value::String(
+                args.into_iter().filter(|v| *v != Value::Null).map(|v| v.to_string()).collect(),
+            ));
+        }
+        if *self == Self::JsonSet {
+            let mut args = args.into_iter();
+            let document = args.next().expect("arity checked");
+            let path = args.next().expect("arity checked");
+            let value = args.next().expect("arity checked");
+            if document == Value::Null || path == Value::Null {
+                return Ok(Value::Null);
+            }
+            return json::set_path(document, &string_arg(Some(path), self)?, value);
+        }
+        if args.contains(&Value::Null) {
+            return Ok(Value::Null);
+        }
+        let mut args = args.into_iter();
+        Ok(match self {
+            Self::Array
+            | Self::Coalesce
+            | Self::Concat
+            | Self::CurrVal
+            | Self::Greatest
+            | Self::JsonSet
+            | Self::Least
+            | Self::NextVal
+            | Self::NullIf
+            | Self::SetVal => unreachable!("handled above"),
+            Self::DateTrunc => {
+                let field = string_arg(args.next(), self)?.to_lowercase();
+                datetime::truncate(&field, args.next().expect("arity checked"))?
+            }
+            Self::Extract => {
+                let field = string_arg(args.next(), self)?.to_lowercase();
+                datetime::extract(&field, args.next().expect("arity checked"))?
+            }
+            Self::JsonExtract => {
+                let document = args.next().expect("arity checked");
+                json::extract_path(document, &string_arg(args.next(), self)?)?
+            }
+            Self::GenRandomUuid => Value::Uuid(uuid::Uuid::new_v4()),
+            Self::Now => Value::Timestamp(datetime::now()),
+            Self::Random => Value::Float(rand::random()),
+            Self::Abs => match args.next().expect("arity checked") {
+                Value::Integer(i) => Value::Integer(
+                    i.checked_abs().ok_or_else(|| Error::Value("Integer overflow".into()))?,
+                ),
+                Value::Float(f) => Value::Float(f.abs()),
+                Value::Decimal(d) => Value::Decimal(d.abs()),
+                arg => return Err(numeric_error(arg, self)),
+            },
+            Self::Cardinality => match args.next().expect("arity checked") {
+                Value::Array(elements) => Value::Integer(elements.len() as i64),
+                arg => {
+                    return Err(Error::Value(format!(
+                        "{} expected array argument, got {}",
+                        self, arg
+                    )))
+                }
+            },
+            Self::Ceil | Self::Floor => match args.next().expect("arity checked") {
+                Value::Integer(i) => Value::Integer(i),
+                Value::Float(f) if *self == Self::Ceil => Value::Float(f.ceil()),
+                Value::Float(f) => Value::Float(f.floor()),
+                Value::Decimal(d) if *self == Self::Ceil => Value::Decimal(d.ceil()),
+                Value::Decimal(d) => Value::Decimal(d.floor()),
+                arg => return Err(numeric_error(arg, self)),
+            },
+            // MOD and POWER are equivalent to the % and ^ operators.
+            Self::Mod => {
+                let lhs = args.next().expect("arity checked");
+                modulo(lhs, args.next().expect("arity checked"))?
+            }
+            Self::Power => {
+                let lhs = args.next().expect("arity checked");
+                exponentiate(lhs, args.next().expect("arity checked"))?
+            }
+            Self::Round => {
+                let value = args.next().expect("arity checked");
+                let digits = args.next().map(|d| integer_arg(Some(d), self)).transpose()?;
+                round(value, digits.unwrap_or(0), self)?
+            }
+            Self::Sign => match args.next().expect("arity checked") {
+                Value::Integer(i) => Value::Integer(i.signum()),
+                // f64::signum() returns 1 for 0.0.
+                Value::Float(0.0) => Value::Float(0.0),
+                Value::Float(f) => Value::Float(f.signum()),
+                Value::Decimal(d) => Value::Decimal(match d.cmp(&Decimal::ZERO) {
+                    Ordering::Less => Decimal::NEGATIVE_ONE,
+                    Ordering::Equal => Decimal::ZERO,
+                    Ordering::Greater => Decimal::ONE,
+                }),
+                arg => return Err(numeric_error(arg, self)),
+            },
+            // Square roots aren't exact in general, so they always yield a float.
+            Self::Sqrt => {
+                let f = match args.next().expect("arity checked") {
+                    Value::Integer(i) => i as f64,
+                    Value::Float(f) => f,
+                    Value::Decimal(d) => decimal::to_float(d),
+                    arg => return Err(numeric_error(arg, self)),
+                };
+                if f < 0.0 {
+                    return Err(Error::Value("Can't take square root of negative number".into()));
+                }
+                Value::Float(f.sqrt())
+            }
+            Self::Length => match args.next() {
+                Some(Value::Bytes(b)) => Value::Integer(b.len() as i64),
+                arg => Value::Integer(string_arg(arg, self)?.chars().count() as i64),
+            },
+            Self::OctetLength => match args.next() {
+                Some(Value::Bytes(b)) => Value::Integer(b.len() as i64),
+                arg => Value::Integer(string_arg(arg, self)?.len() as i64),
+            },
+            Self::Lower => Value::String(string_arg(args.next(), self)?.to_lowercase()),
+            Self::Upper => Value::String(string_arg(args.next(), self)?.to_uppercase()),
+            Self::Position => {
+                let substring = string_arg(args.next(), self)?;
+                let string = string_arg(args.next(), self)?;
+                Value::Integer(match string.find(&substring) {
+                    Some(i) => string[..i].chars().count() as i64 + 1,
+                    None => 0,
+                })
+            }
+            Self::Replace => {
+                let string = string_arg(args.next(), self)?;
+                let from = string_arg(args.next(), self)?;
+                let to = string_arg(args.next(), self)?;
+                if from.is_empty() {
+                    Value::String(string)
+                } else {
+                    Value::String(string.replace(&from, &to))
+                }
+            }
+            Self::Trim | Self::LTrim | Self::RTrim => {
+                let string = string_arg(args.next(), self)?;
+                let chars: Vec<char> = match string_arg_opt(args.next(), self)? {
+                    Some(chars) => chars.chars().collect(),
+                    None => vec![' '],
+                };
+                Value::String(
+                    match self {
+                        Self::LTrim => string.trim_start_matches(chars.as_slice()),
+                        Self::RTrim => string.trim_end_matches(chars.as_slice()),
+                        _ => string.trim_matches(chars.as_slice()),
+                    }
+                    .to_string(),
+                )
+            }
+            Self::Left | Self::Right => {
+                let string = string_arg(args.next(), self)?;
+                let n = integer_arg(args.next(), self)?;
+                let len = string.chars().count() as i64;
+                // A negative count means all but the last (or first) -n characters.
+                let n = if n < 0 { (len + n).max(0) } else { n.min(len) };
+                Value::String(match self {
+                    Self::Left => string.chars().take(n as usize).collect(),
+                    _ => string.chars().skip((len - n) as usize).collect(),
+                })
+            }
+            Self::Substring => {
+                let string = string_arg(args.next(), self)?;
+                let start = integer_arg(args.next(), self)?;
+                // Like Postgres, the range [start, start + length) is clamped to the string, and
+                // positions before 1 count towards the length.
+                let end = match args.next() {
+                    Some(length) => {
+                        let length = integer_arg(Some(length), self)?;
+                        if length < 0 {
+                            return Err(Error::Value("substring length can't be negative".into()));
+                        }
+                        start.saturating_add(length)
+                    }
+                    None => i64::MAX,
+                };
+                let start = start.max(1);
+                Value::String(if end <= start {
+                    String::new()
+                } else {
+                    string.chars().skip((start - 1) as usize).take((end - start) as usize).collect()
+                })
+            }
+        })
+    }
+
+    /// Evaluates a sequence function for the given argument values, the
+    /// sequence name and SETVAL's value. Returns NULL if any argument is NULL.
+    pub fn evaluate_sequence(
+        &self,
+        args: Vec<Value>,
+        sequences: &mut dyn Sequences,
+    ) -> Result<Value> {
+        self.check_arity(args.len())?;
+        if args.contains(&Value::Null) {
+            return Ok(Value::Null);
+        }
+        let mut args = args.into_iter();
+        let sequence = string_arg(args.next(), self)?;
+        Ok(Value::Integer(match self {
+            Self::CurrVal => sequences.current_value(&sequence)?,
+            Self::NextVal => sequences.next_value(&sequence)?,
+            Self::SetVal => {
+                let value = integer_arg(args.next(), self)?;
+                sequences.set_value(&sequence, value)?;
+                value
+            }
+            _ => return Err(Error::Internal(format!("{} is not a sequence function", self))),
+        }))
+    }
+}
+
+/// Converts the non-NULL arguments to a common type, as given by
+/// DataType::common(), or errors if their types can't be matched.
+fn unify(args: Vec<Value>, f: &Function) -> Result<Vec<Value>> {
+    let mut datatype: Option<DataType> = None;
+    for next in args.iter().filter_map(|v| v.datatype()) {
+        datatype = Some(match datatype {
+            None => next,
+            Some(current) => current.common(&next).ok_or_else(|| {
+                Error::Value(format!("{} types {} and {} cannot be matched", f, current, next))
+            })?,
+        });
+    }
+    match datatype {
+        Some(datatype) => args.into_iter().map(|v| v.cast(&datatype)).collect(),
+        None => Ok(args),
+    }
+}
+
+/// Rounds a number to the given number of fractional digits, or to a power of
+/// ten if negative, with halves rounded away from zero. Integers and decimals
+/// are rounded exactly, and floats to the nearest representable value.
+fn round(value: Value, digits: i64, f: &Function) -> Result<Value> {
+    Ok(match value {
+        Value::Integer(i) if digits >= 0 => Value::Integer(i),
+        Value::Integer(i) => {
+            // Rounding to more digits than an integer has yields 0.
+            let Some(scale) = u32::try_from(-digits).ok().and_then(|d| 10_i64.checked_pow(d))
+            else {
+                return Ok(Value::Integer(0));
+            };
+            let (quotient, remainder) = (i / scale, i % scale);
+            let quotient = match remainder.unsigned_abs() * 2 >= scale as u64 {
+                true => quotient + i.signum(),
+                false => quotient,
+            };
+            Value::Integer(
+                quotient
+                    .checked_mul(scale)
+                    .ok_or_else(|| Error::Value("Integer overflow".into()))?,
+            )
+        }
+        Value::Float(float) => {
+            let scale = 10_f64.powi(digits.clamp(-308, 308) as i32);
+            let rounded = (float * scale).round() / scale;
+            // The scaled value overflows if the float has no digits to round.
+            Value::Float(if rounded.is_finite() { rounded } else { float })
+        }
+        Value::Decimal(d) if digits >= 0 => Value::Decimal(
+            d.round_dp_with_strategy(digits.min(28) as u32, RoundingStrategy::MidpointAwayFromZero),
+        ),
+        Value::Decimal(d) => {
+            let Some(scale) =
+                (digits..0).try_fold(Decimal::ONE, |scale, _| scale.checked_mul(Decimal::TEN))
+            else {
+                return Ok(Value::Decimal(Decimal::ZERO));
+            };
+            let rounded =
+                (d / scale).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero);
+            Value::Decimal(
+                rounded
+                    .checked_mul(scale)
+                    .ok_or_else(|| Error::Value("Decimal overflow".into()))?,
+            )
+        }
+        value => return Err(numeric_error(value, f)),
+    })
+}
+
+/// Returns an error for a non-numeric function argument.
+fn numeric_error(arg: Value, f: &Function) -> Error {
+    Error::Value(format!("{} expected numeric argument, got {}", f, arg))
+}
+
+/// Fetches a string argument.
+fn string_arg(arg: Option<Value>, f: &Function) -> Result<String> {
+    string_arg_opt(arg, f)?.ok_or_else(|| Error::Internal("Missing function argument".into()))
+}
+
+/// Fetches an optional string argument.
+fn string_arg_opt(arg: Option<Value>, f: &Function) -> Result<Option<String>> {
+    match arg {
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(v) => Err(Error::Value(format!("{} expected string argument, got {}", f, v))),
+        None => Ok(None),
+    }
+}
+
+/// Fetches an integer argument.
+fn integer_arg(arg: Option<Value>, f: &Function) -> Result<i64> {
+    match arg {
+        Some(Value::Integer(i)) => Ok(i),
+        Some(v) => Err(Error::Value(format!("{} expected integer argument, got {}", f, v))),
+        None => Err(Error::Internal("Missing function argument".into())),
+    }
+}
+
+/// Sequence operations, used to evaluate the sequence functions NEXTVAL(),
+/// CURRVAL() and SETVAL() by name. Sequences are not transactional, so values
+/// are not reused after a rollback.
+pub trait Sequences {
+    /// Returns the next value of a sequence.
+    fn next_value(&mut self, sequence: &str) -> Result<i64>;
+    /// Returns the value most recently returned by next_value() for a
+    /// sequence in the current session.
+    fn current_value(&self, sequence: &str) -> Result<i64>;
+    /// Sets the current value of a sequence, such that the next value follows
+    /// it.
+    fn set_value(&mut self, sequence: &str, value: i64) -> Result<()>;
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name().to_uppercase())
+    }
+}
@@ -0,0 +1,220 @@
+//! Support for the JSON type, backed by serde_json. Documents are validated
+//! when parsed, and stored in a canonical compact form with object keys in
+//! sorted order, such that equal documents have equal encodings regardless of
+//! the whitespace and key order they were given with.
+//!
+//! Documents are navigated via JSON paths, which are given as $ for the root
+//! followed by .key for object keys and [n] for array elements, e.g.
+//! $.tags[0].name. Negative array indexes count from the end.
+
+use super::Value;
+use crate::error::{Error, Result};
+
+use serde::{de::Error as _, Deserialize as _, Deserializer, Serializer};
+use serde_json::Value as Json;
+
+/// Parses a JSON document. Returns None if invalid.
+pub fn parse(s: &str) -> Option<Json> {
+    serde_json::from_str(s).ok()
+}
+
+/// A JSON path element.
+#[derive(Clone, Debug, PartialEq)]
+enum PathElement {
+    Key(String),
+    Index(i64),
+}
+
+/// Parses a JSON path, e.g. $.a.b[0].
+fn parse_path(path: &str) -> Result<Vec<PathElement>> {
+    let invalid = || Error::Value(format!("Invalid JSON path {}", path));
+    let mut rest = path.trim().strip_prefix('$').ok_or_else(invalid)?;
+    let mut elements = Vec::new();
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('.') {
+            let end = r.find(['.', '[']).unwrap_or(r.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            elements.push(PathElement::Key(r[..end].to_string()));
+            rest = &r[end..];
+        } else if let Some(r) = rest.strip_prefix('[') {
+            let (index, r) = r.split_once(']').ok_or_else(invalid)?;
+            elements.push(PathElement::Index(index.trim().parse().map_err(|_| invalid())?));
+            rest = r;
+        } else {
+            return Err(invalid());
+        }
+    }
+    Ok(elements)
+}
+
+/// Converts a possibly negative array index into an offset, if in bounds.
+fn array_offset(array: &[Json], index: i64) -> Option<usize> {
+    let offset = if index < 0 { array.len() as i64 + index } else { index };
+    (0..array.len() as i64).contains(&offset).then_some(offset as usize)
+}
+
+/// Looks up a path element in a JSON value, if present.
+fn get<'a>(json: &'a Json, element: &PathElement) -> Option<&'a Json> {
+    match (json, element) {
+        (Json::Object(object), PathElement::Key(key)) => object.get(key),
+        (Json::Array(array), PathElement::Index(index)) => array.get(array_offset(array, *index)?),
+        _ => None,
+    }
+}
+
+/// Converts a document argument to JSON. Strings are parsed as JSON documents.
+pub fn from_value(value: Value) -> Result<Json> {
+    match value {
+        Value::Json(json) => Ok(json),
+        Value::String(s) => parse(&s).ok_or_else(|| Error::Value(format!("Invalid JSON {}", s))),
+        value => Err(Error::Value(format!("Expected JSON, got {}", value))),
+    }
+}
+
+/// Converts an SQL value to a JSON value, for storing in a document. Strings
+/// are stored as JSON strings, and values without an exact JSON equivalent
+/// (e.g. dates) are stored as their string representation. This includes
+/// decimals, since JSON numbers are commonly parsed as floats.
+pub fn to_json(value: Value) -> Result<Json> {
+    Ok(match value {
+        Value::Null => Json::Null,
+        Value::Boolean(b) => Json::Bool(b),
+        Value::Integer(i) => Json::from(i),
+        Value::Float(f) => match serde_json::Number::from_f64(f) {
+            Some(n) => Json::Number(n),
+            None => return Err(Error::Value(format!("Can't convert {} to JSON", f))),
+        },
+        Value::Json(json) => json,
+        Value::Array(values) => {
+            Json::Array(values.into_iter().map(to_json).collect::<Result<_>>()?)
+        }
+        value => Json::String(value.to_string()),
+    })
+}
+
+/// Extracts an object field (given a string key) or array element (given an
+/// integer index) from a document, as for the -> operator. Returns NULL if
+/// not found, or the element as text if as_text is true (as for ->>).
+pub fn extract(document: Value, element: Value, as_text: bool) -> Result<Value> {
+    let element = match element {
+        Value::String(key) => PathElement::Key(key),
+        Value::Integer(index) => PathElement::Index(index),
+        element => {
+            return Err(Error::Value(format!("Can't extract {} from JSON document", element)))
+        }
+    };
+    let json = from_value(document)?;
+    Ok(match get(&json, &element) {
+        None => Value::Null,
+        Some(json) if as_text => text(json.clone()),
+        Some(json) => Value::Json(json.clone()),
+    })
+}
+
+/// Extracts the value at the given path, as for json_extract(). Returns NULL
+/// if not found.
+pub fn extract_path(document: Value, path: &str) -> Result<Value> {
+    let path = parse_path(path)?;
+    let json = from_value(document)?;
+    let mut current = &json;
+    for element in &path {
+        match get(current, element) {
+            Some(json) => current = json,
+            None => return Ok(Value::Null),
+        }
+    }
+    Ok(Value::Json(current.clone()))
+}
+
+/// Sets the value at the given path, as for json_set(). Existing values are
+/// replaced, and missing object keys are inserted, as are array elements
+/// one past the end (appended). The document is returned unchanged if the
+/// path's parent does not exist.
+pub fn set_path(document: Value, path: &str, value: Value) -> Result<Value> {
+    let path = parse_path(path)?;
+    let mut json = from_value(document)?;
+    let value = to_json(value)?;
+    let Some((last, parents)) = path.split_last() else {
+        return Ok(Value::Json(value));
+    };
+    let mut current = &mut json;
+    for element in parents {
+        let next = match (current, element) {
+            (Json::Object(object), PathElement::Key(key)) => object.get_mut(key),
+            (Json::Array(array), PathElement::Index(index)) => match array_offset(array, *index) {
+                Some(offset) => array.get_mut(offset),
+                None => None,
+            },
+            _ => None,
+        };
+        match next {
+            Some(next) => current = next,
+            None => return Ok(Value::Json(json)),
+        }
+    }
+    match (current, last) {
+        (Json::Object(object), PathElement::Key(key)) => {
+            object.insert(key.clone(), value);
+        }
+        (Json::Array(array), PathElement::Index(index)) if *index == array.len() as i64 => {
+            array.push(value)
+        }
+        (Json::Array(array), PathElement::Index(index)) => {
+            if let Some(offset) = array_offset(array, *index) {
+                array[offset] = value
+            }
+        }
+        _ => {}
+    }
+    Ok(Value::Json(json))
+}
+
+/// Converts a JSON value to text, as for the ->> operator. Strings are
+/// unquoted, and JSON null yields NULL.
+fn text(json: Json) -> Value {
+    match json {
+        Json::Null => Value::Null,
+        Json::String(s) => Value::String(s),
+        json => Value::String(json.to_string()),
+    }
+}
+
+/// Serializes a JSON document as its canonical compact string.
+pub fn serialize<S: Serializer>(
+    json: &Json,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&json.to_string())
+}
+
+/// Deserializes a JSON document serialized by serialize().
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Json, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse(&s).ok_or_else(|| D::Error::custom(format!("invalid JSON {}", s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_path() -> Result<()> {
+        use PathElement::*;
+        assert_eq!(super::parse_path("$")?, vec![]);
+        assert_eq!(
+            super::parse_path("$.a.bc[0][-1].d")?,
+            vec![Key("a".into()), Key("bc".into()), Index(0), Index(-1), Key("d".into())]
+        );
+        for invalid in ["", "a", "$a", "$.", "$..a", "$[", "$[a]", "$[0"] {
+            assert_eq!(
+                super::parse_path(invalid),
+                Err(Error::Value(format!("Invalid JSON path {}", invalid)))
+            );
+        }
+        Ok(())
+    }
+}
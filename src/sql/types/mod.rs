@@ -1,12 +1,23 @@
+pub mod array;
+pub mod datetime;
+pub mod decimal;
 mod expression;
-pub use expression::Expression;
+pub mod fulltext;
+mod function;
+pub mod json;
+pub use expression::{Compiled, Expression};
+pub use function::{Function, Sequences};
 
 use crate::error::{Error, Result};
 
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use datetime::Interval;
+use rust_decimal::Decimal;
 use serde_derive::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
+use uuid::Uuid;
 
 /// A datatype
 #[derive(Clone, Debug, Hash, PartialEq, Serialize, Deserialize)]
@@ -15,19 +26,106 @@ pub enum DataType {
     Integer,
     Float,
     String,
+    Date,
+    Time,
+    Timestamp,
+    Decimal,
+    Bytes,
+    Json,
+    Uuid,
+    /// An enum type created via CREATE TYPE, by name and labels. Its values
+    /// are strings with one of the labels. The parser leaves the labels empty,
+    /// and the planner looks them up in the catalog.
+    Enum(String, Vec<String>),
+    /// A one-dimensional array of the given element type.
+    Array(Box<DataType>),
+    Interval,
 }
 
 impl std::fmt::Display for DataType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.write_str(match self {
+            Self::Array(element) => return write!(f, "{}[]", element),
+            Self::Enum(name, _) => name,
             Self::Boolean => "BOOLEAN",
             Self::Integer => "INTEGER",
             Self::Float => "FLOAT",
             Self::String => "STRING",
+            Self::Date => "DATE",
+            Self::Time => "TIME",
+            Self::Timestamp => "TIMESTAMP",
+            Self::Interval => "INTERVAL",
+            Self::Decimal => "DECIMAL",
+            Self::Bytes => "BYTEA",
+            Self::Json => "JSON",
+            Self::Uuid => "UUID",
         })
     }
 }
 
+impl DataType {
+    /// Returns the common type that values of both types can be converted to
+    /// such that equal values compare equal, if any. Numeric types widen in
+    /// the order INTEGER, DECIMAL, FLOAT.
+    pub fn common(&self, other: &DataType) -> Option<DataType> {
+        match (self, other) {
+            (l, r) if l == r => Some(l.clone()),
+            (Self::Array(l), Self::Array(r)) => Some(Self::Array(Box::new(l.common(r)?))),
+            (Self::Float, Self::Integer | Self::Decimal)
+            | (Self::Integer | Self::Decimal, Self::Float) => Some(Self::Float),
+            (Self::Decimal, Self::Integer) | (Self::Integer, Self::Decimal) => Some(Self::Decimal),
+            (_, _) => None,
+        }
+    }
+}
+
+/// A string collation, which determines how a column's strings compare and
+/// sort. Values of other datatypes are unaffected.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Collation {
+    /// Compares strings by their UTF-8 bytes, i.e. by Unicode code point.
+    #[default]
+    Binary,
+    /// Compares strings ignoring the case of ASCII letters.
+    NoCase,
+    /// Compares strings ignoring the case of all Unicode letters, using their
+    /// Unicode lowercase mappings.
+    UnicodeNoCase,
+}
+
+impl std::fmt::Display for Collation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Binary => "BINARY",
+            Self::NoCase => "NOCASE",
+            Self::UnicodeNoCase => "UNICODE_NOCASE",
+        })
+    }
+}
+
+impl Collation {
+    /// Looks up a collation by name, case-insensitively.
+    pub fn lookup(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "BINARY" => Some(Self::Binary),
+            "NOCASE" => Some(Self::NoCase),
+            "UNICODE_NOCASE" => Some(Self::UnicodeNoCase),
+            _ => None,
+        }
+    }
+
+    /// Returns the collation key of a value. Strings that are equal under the
+    /// collation have equal keys, and keys compare in collation order, so
+    /// collated values are compared, sorted and indexed by their keys.
+    pub fn key(&self, value: Value) -> Value {
+        match (self, value) {
+            (Self::NoCase, Value::String(s)) => Value::String(s.to_ascii_lowercase()),
+            (Self::UnicodeNoCase, Value::String(s)) => Value::String(s.to_lowercase()),
+            (_, value) => value,
+        }
+    }
+}
+
 /// A specific value of a data type
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Value {
@@ -36,6 +134,23 @@ pub enum Value {
     Integer(i64),
     Float(f64),
     String(String),
+    #[serde(with = "datetime::serde_date")]
+    Date(NaiveDate),
+    #[serde(with = "datetime::serde_time")]
+    Time(NaiveTime),
+    #[serde(with = "datetime::serde_timestamp")]
+    Timestamp(NaiveDateTime),
+    #[serde(with = "decimal")]
+    Decimal(Decimal),
+    #[serde(with = "serde_bytes")]
+    Bytes(Vec<u8>),
+    #[serde(with = "json")]
+    Json(serde_json::Value),
+    #[serde(with = "serde_uuid")]
+    Uuid(Uuid),
+    Array(Vec<Value>),
+    #[serde(with = "datetime::serde_interval")]
+    Interval(Interval),
 }
 
 impl std::cmp::Eq for Value {}
@@ -45,11 +160,21 @@ impl Hash for Value {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.datatype().hash(state);
         match self {
-            Value::Null => self.hash(state),
+            Value::Null => {}
             Value::Boolean(v) => v.hash(state),
             Value::Integer(v) => v.hash(state),
             Value::Float(v) => v.to_be_bytes().hash(state),
             Value::String(v) => v.hash(state),
+            Value::Date(v) => v.hash(state),
+            Value::Time(v) => v.hash(state),
+            Value::Timestamp(v) => v.hash(state),
+            Value::Decimal(v) => v.hash(state),
+            Value::Bytes(v) => v.hash(state),
+            // The canonical string form is equal for equal documents.
+            Value::Json(v) => v.to_string().hash(state),
+            Value::Uuid(v) => v.hash(state),
+            Value::Array(v) => v.hash(state),
+            Value::Interval(v) => v.hash(state),
         }
     }
 }
@@ -67,7 +192,9 @@ impl<'a> From<&'a Value> for Cow<'a, Value> {
 }
 
 impl Value {
-    /// Returns the value's datatype, or None for null values
+    /// Returns the value's datatype, or None for null values. Arrays take
+    /// their element type from the first non-null element, or are STRING[] if
+    /// they have none.
     pub fn datatype(&self) -> Option<DataType> {
         match self {
             Self::Null => None,
@@ -75,9 +202,178 @@ impl Value {
             Self::Integer(_) => Some(DataType::Integer),
             Self::Float(_) => Some(DataType::Float),
             Self::String(_) => Some(DataType::String),
+            Self::Date(_) => Some(DataType::Date),
+            Self::Time(_) => Some(DataType::Time),
+            Self::Timestamp(_) => Some(DataType::Timestamp),
+            Self::Interval(_) => Some(DataType::Interval),
+            Self::Decimal(_) => Some(DataType::Decimal),
+            Self::Bytes(_) => Some(DataType::Bytes),
+            Self::Json(_) => Some(DataType::Json),
+            Self::Uuid(_) => Some(DataType::Uuid),
+            Self::Array(values) => Some(DataType::Array(Box::new(
+                values.iter().find_map(|v| v.datatype()).unwrap_or(DataType::String),
+            ))),
         }
     }
 
+    /// Casts the value to the given datatype, or errors if the value can't be represented as
+    /// that type. NULL can be cast to any type, and remains NULL.
+    pub fn cast(self, datatype: &DataType) -> Result<Self> {
+        let err = |v: &Value| Error::Value(format!("Can't cast {} to {}", v, datatype));
+        Ok(match (self, datatype) {
+            (Self::Null, _) => Self::Null,
+
+            (v @ Self::Boolean(_), DataType::Boolean) => v,
+            (Self::Boolean(b), DataType::Integer) => Self::Integer(b as i64),
+            (Self::Boolean(b), DataType::Float) => Self::Float(if b { 1.0 } else { 0.0 }),
+            (Self::Boolean(b), DataType::Decimal) => Self::Decimal(Decimal::from(b as i64)),
+            (v @ Self::Boolean(_), DataType::String) => Self::String(v.to_string()),
+
+            (Self::Integer(i), DataType::Boolean) => Self::Boolean(i != 0),
+            (v @ Self::Integer(_), DataType::Integer) => v,
+            (Self::Integer(i), DataType::Float) => Self::Float(i as f64),
+            (Self::Integer(i), DataType::Decimal) => Self::Decimal(Decimal::from(i)),
+            (Self::Integer(i), DataType::String) => Self::String(i.to_string()),
+
+            // Floats are rounded to the nearest integer, like Postgres.
+            (v @ Self::Float(_), DataType::Boolean) => return Err(err(&v)),
+            (Self::Float(f), DataType::Integer)
+                if f.is_finite() && f.round() >= i64::MIN as f64 && f.round() < i64::MAX as f64 =>
+            {
+                Self::Integer(f.round() as i64)
+            }
+            (v @ Self::Float(_), DataType::Integer) => return Err(err(&v)),
+            (v @ Self::Float(_), DataType::Float) => v,
+            (Self::Float(f), DataType::String) => Self::String(f.to_string()),
+            (Self::Float(f), DataType::Decimal) => match decimal::from_float(f) {
+                Some(d) => Self::Decimal(d),
+                None => return Err(err(&Self::Float(f))),
+            },
+
+            (Self::String(s), DataType::Boolean) => match s.trim().to_lowercase().as_str() {
+                "t" | "true" | "y" | "yes" | "on" | "1" => Self::Boolean(true),
+                "f" | "false" | "n" | "no" | "off" | "0" => Self::Boolean(false),
+                _ => return Err(err(&Self::String(s))),
+            },
+            (Self::String(s), DataType::Integer) => match s.trim().parse() {
+                Ok(i) => Self::Integer(i),
+                Err(_) => return Err(err(&Self::String(s))),
+            },
+            (Self::String(s), DataType::Float) => match s.trim().parse() {
+                Ok(f) => Self::Float(f),
+                Err(_) => return Err(err(&Self::String(s))),
+            },
+            (Self::String(s), DataType::Date) => match datetime::parse_date(&s) {
+                Some(date) => Self::Date(date),
+                None => return Err(err(&Self::String(s))),
+            },
+            (Self::String(s), DataType::Time) => match datetime::parse_time(&s) {
+                Some(time) => Self::Time(time),
+                None => return Err(err(&Self::String(s))),
+            },
+            (Self::String(s), DataType::Timestamp) => match datetime::parse_timestamp(&s) {
+                Some(ts) => Self::Timestamp(ts),
+                None => return Err(err(&Self::String(s))),
+            },
+            (Self::String(s), DataType::Interval) => match datetime::Interval::parse(&s) {
+                Some(interval) => Self::Interval(interval),
+                None => return Err(err(&Self::String(s))),
+            },
+            (Self::String(s), DataType::Decimal) => match decimal::parse(&s) {
+                Some(d) => Self::Decimal(d),
+                None => return Err(err(&Self::String(s))),
+            },
+            // Like Postgres, strings in the hex format \xDEADBEEF are decoded, while other
+            // strings are taken as their raw UTF-8 bytes.
+            (Self::String(s), DataType::Bytes) => match s.strip_prefix("\\x") {
+                Some(h) => match hex::decode(h) {
+                    Ok(bytes) => Self::Bytes(bytes),
+                    Err(_) => return Err(err(&Self::String(s))),
+                },
+                None => Self::Bytes(s.into_bytes()),
+            },
+            (Self::String(s), DataType::Json) => match json::parse(&s) {
+                Some(json) => Self::Json(json),
+                None => return Err(err(&Self::String(s))),
+            },
+            (Self::String(s), DataType::Uuid) => match Uuid::try_parse(s.trim()) {
+                Ok(uuid) => Self::Uuid(uuid),
+                Err(_) => return Err(err(&Self::String(s))),
+            },
+            (v @ Self::String(_), DataType::String) => v,
+            (Self::String(s), DataType::Enum(name, labels)) => match labels.contains(&s) {
+                true => Self::String(s),
+                false => {
+                    return Err(Error::Value(format!("Invalid value {} for enum {}", s, name)))
+                }
+            },
+
+            (v @ Self::Bytes(_), DataType::Bytes) => v,
+            (v @ Self::Bytes(_), DataType::String) => Self::String(v.to_string()),
+            (Self::Bytes(b), DataType::Uuid) => match Uuid::from_slice(&b) {
+                Ok(uuid) => Self::Uuid(uuid),
+                Err(_) => return Err(err(&Self::Bytes(b))),
+            },
+
+            (v @ Self::Uuid(_), DataType::Uuid) => v,
+            (Self::Uuid(uuid), DataType::String) => Self::String(uuid.to_string()),
+            (Self::Uuid(uuid), DataType::Bytes) => Self::Bytes(uuid.as_bytes().to_vec()),
+
+            (v @ Self::Json(_), DataType::Json) => v,
+            (Self::Json(json), DataType::String) => Self::String(json.to_string()),
+
+            (Self::Array(values), DataType::Array(element)) => {
+                Self::Array(values.into_iter().map(|v| v.cast(element)).collect::<Result<_>>()?)
+            }
+            (v @ Self::Array(_), DataType::String) => Self::String(v.to_string()),
+            (v @ Self::Array(_), DataType::Json) => Self::Json(json::to_json(v)?),
+            (Self::String(s), DataType::Array(element)) => match array::parse(&s) {
+                Some(elements) => Self::Array(
+                    elements
+                        .into_iter()
+                        .map(|e| e.map_or(Ok(Self::Null), |e| Self::String(e).cast(element)))
+                        .collect::<Result<_>>()?,
+                ),
+                None => return Err(err(&Self::String(s))),
+            },
+
+            (v @ Self::Decimal(_), DataType::Boolean) => return Err(err(&v)),
+            (Self::Decimal(d), DataType::Integer) => match decimal::to_integer(d) {
+                Some(i) => Self::Integer(i),
+                None => return Err(err(&Self::Decimal(d))),
+            },
+            (Self::Decimal(d), DataType::Float) => Self::Float(decimal::to_float(d)),
+            (Self::Decimal(d), DataType::String) => Self::String(d.to_string()),
+            (v @ Self::Decimal(_), DataType::Decimal) => v,
+
+            (v @ Self::Date(_), DataType::Date) => v,
+            (Self::Date(date), DataType::Timestamp) => Self::Timestamp(date.into()),
+            (v @ Self::Time(_), DataType::Time) => v,
+            (Self::Timestamp(ts), DataType::Date) => Self::Date(ts.date()),
+            (Self::Timestamp(ts), DataType::Time) => Self::Time(ts.time()),
+            (v @ Self::Timestamp(_), DataType::Timestamp) => v,
+            (v @ Self::Interval(_), DataType::Interval) => v,
+            (
+                v @ (Self::Date(_) | Self::Time(_) | Self::Timestamp(_) | Self::Interval(_)),
+                DataType::String,
+            ) => Self::String(v.to_string()),
+            (
+                v @ (Self::Date(_)
+                | Self::Time(_)
+                | Self::Timestamp(_)
+                | Self::Interval(_)
+                | Self::Bytes(_)
+                | Self::Json(_)
+                | Self::Uuid(_)
+                | Self::Array(_)),
+                _,
+            ) => return Err(err(&v)),
+            (v @ (Self::Boolean(_) | Self::Integer(_) | Self::Float(_) | Self::Decimal(_)), _) => {
+                return Err(err(&v))
+            }
+        })
+    }
+
     /// Returns the inner boolean, or an error if not a boolean
     pub fn boolean(self) -> Result<bool> {
         match self {
@@ -121,6 +417,15 @@ impl std::fmt::Display for Value {
                 Self::Integer(i) => i.to_string(),
                 Self::Float(f) => f.to_string(),
                 Self::String(s) => s.clone(),
+                Self::Date(d) => d.to_string(),
+                Self::Time(t) => t.to_string(),
+                Self::Timestamp(ts) => ts.to_string(),
+                Self::Interval(interval) => interval.to_string(),
+                Self::Decimal(d) => d.to_string(),
+                Self::Bytes(b) => format!("\\x{}", hex::encode(b)),
+                Self::Json(json) => json.to_string(),
+                Self::Uuid(uuid) => uuid.to_string(),
+                Self::Array(values) => array::format(values),
             }
             .as_ref(),
         )
@@ -139,6 +444,16 @@ impl PartialOrd for Value {
             (Self::Integer(a), Self::Float(b)) => (*a as f64).partial_cmp(b),
             (Self::Integer(a), Self::Integer(b)) => a.partial_cmp(b),
             (Self::String(a), Self::String(b)) => a.partial_cmp(b),
+            (Self::Date(a), Self::Date(b)) => a.partial_cmp(b),
+            (Self::Time(a), Self::Time(b)) => a.partial_cmp(b),
+            (Self::Timestamp(a), Self::Timestamp(b)) => a.partial_cmp(b),
+            (Self::Interval(a), Self::Interval(b)) => a.partial_cmp(b),
+            (Self::Decimal(a), Self::Decimal(b)) => a.partial_cmp(b),
+            (Self::Bytes(a), Self::Bytes(b)) => a.partial_cmp(b),
+            (Self::Uuid(a), Self::Uuid(b)) => a.partial_cmp(b),
+            (Self::Array(a), Self::Array(b)) => a.partial_cmp(b),
+            (Self::Decimal(a), Self::Integer(b)) => a.partial_cmp(&Decimal::from(*b)),
+            (Self::Integer(a), Self::Decimal(b)) => Decimal::from(*a).partial_cmp(b),
             (_, _) => None,
         }
     }
@@ -156,6 +471,12 @@ impl From<f64> for Value {
     }
 }
 
+impl From<Decimal> for Value {
+    fn from(v: Decimal) -> Self {
+        Value::Decimal(v)
+    }
+}
+
 impl From<i64> for Value {
     fn from(v: i64) -> Self {
         Value::Integer(v)
@@ -188,3 +509,18 @@ pub struct Column {
 
 /// A set of columns
 pub type Columns = Vec<Column>;
+
+/// Serializes UUIDs as their 16 raw bytes, which sort in UUID order.
+mod serde_uuid {
+    use serde::{de::Error as _, Deserialize as _, Deserializer, Serializer};
+    use uuid::Uuid;
+
+    pub fn serialize<S: Serializer>(uuid: &Uuid, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(uuid.as_bytes())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Uuid, D::Error> {
+        let bytes = serde_bytes::ByteBuf::deserialize(deserializer)?;
+        Uuid::from_slice(&bytes).map_err(|_| D::Error::custom(format!("invalid UUID {:x?}", bytes)))
+    }
+}
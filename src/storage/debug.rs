@@ -61,7 +61,7 @@ pub fn format_key_value(key: &[u8], value: &Option<Vec<u8>>) -> (String, Option<
                     }
                 }
             }
-            mvcc::Key::TxnActive(_) => {}
+            mvcc::Key::TxnActive(_) | mvcc::Key::TxnBeginTime(..) => {}
             mvcc::Key::TxnActiveSnapshot(_) => {
                 if let Some(ref v) = value {
                     if let Ok(active) = bincode::deserialize::<HashSet<u64>>(v) {
@@ -85,6 +85,16 @@ pub fn format_key_value(key: &[u8], value: &Option<Vec<u8>>) -> (String, Option<
             mvcc::Key::Unversioned(userkey) => {
                 fkey = format!("Unversioned({})", format_raw(&userkey));
             }
+            mvcc::Key::TxnSavepoint(version, id, userkey) => {
+                fkey = format!("TxnSavepoint({}, {}, {})", version, id, format_raw(&userkey));
+                if let Some(ref v) = value {
+                    match bincode::deserialize(v) {
+                        Ok(Some(v)) => fvalue = Some(format_raw(v)),
+                        Ok(None) => fvalue = Some(String::from("None")),
+                        Err(_) => {}
+                    }
+                }
+            }
         }
     }
 
@@ -119,7 +129,10 @@ impl<E: engine::Engine> Engine<E> {
 }
 
 impl<E: engine::Engine> engine::Engine for Engine<E> {
-    type ScanIterator<'a> = E::ScanIterator<'a> where E: 'a;
+    type ScanIterator<'a>
+        = E::ScanIterator<'a>
+    where
+        E: 'a;
 
     fn flush(&mut self) -> Result<()> {
         self.inner.flush()
@@ -88,6 +88,12 @@
 //! not see any of t2's writes, because it's still in its local snapshot of the
 //! active set at the time it began.
 //!
+//! A transaction can also take savepoints, and later roll back to one of them
+//! while keeping its earlier writes. A savepoint records the current values of
+//! all keys the transaction has written as Key::TxnSavepoint(version, id, key).
+//! Rolling back to it restores those values, and removes any versions (and
+//! TxnWrite records) for keys that were first written after the savepoint.
+//!
 //! READ-ONLY AND TIME TRAVEL QUERIES
 //! =================================
 //!
@@ -125,6 +131,13 @@
 //! 1  a1      c1  d1
 //!    a   b   c   d   Keys
 //!
+//! Time-travel queries can also be given a wall-clock time rather than a
+//! version. Read-write transactions can record the time they began at as
+//! Key::TxnBeginTime(time, version), and a time is resolved to the first version
+//! that began after it. A time-travel query at that version sees the writes of
+//! all transactions that committed before it began, which is the state as of the
+//! given time (modulo transactions that committed in between).
+//!
 //! Read-only queries work similarly to time-travel queries, with one exception:
 //! they read at the next (current) version, i.e. Key::NextVersion, and use the
 //! current active set, storing the snapshot in memory only. Read-only queries
@@ -145,7 +158,7 @@ use crate::error::{Error, Result};
 
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ops::{Bound, RangeBounds};
 use std::sync::{Arc, Mutex, MutexGuard};
 
@@ -189,6 +202,20 @@ pub enum Key<'a> {
         #[serde(borrow)]
         Cow<'a, [u8]>,
     ),
+    /// The value a key had (written by an active transaction) when the
+    /// transaction took a savepoint, identified by the transaction version and
+    /// savepoint ID, in case it rolls back to the savepoint.
+    TxnSavepoint(
+        Version,
+        u64,
+        #[serde(with = "serde_bytes")]
+        #[serde(borrow)]
+        Cow<'a, [u8]>,
+    ),
+    /// The wall-clock time a read-write transaction began at, in microseconds
+    /// since the Unix epoch, keyed by time for time-travel queries. Only
+    /// written when the time is given.
+    TxnBeginTime(i64, Version),
 }
 
 impl<'a> Key<'a> {
@@ -215,6 +242,8 @@ enum KeyPrefix<'a> {
         Cow<'a, [u8]>,
     ),
     Unversioned,
+    TxnSavepoint(Version),
+    TxnBeginTime,
 }
 
 impl<'a> KeyPrefix<'a> {
@@ -250,7 +279,13 @@ impl<E: Engine> MVCC<E> {
 
     /// Begins a new read-write transaction.
     pub fn begin(&self) -> Result<Transaction<E>> {
-        Transaction::begin(self.engine.clone())
+        Transaction::begin(self.engine.clone(), None)
+    }
+
+    /// Begins a new read-write transaction, recording the given wall-clock
+    /// time (in microseconds since the Unix epoch) as its begin time.
+    pub fn begin_at(&self, time: i64) -> Result<Transaction<E>> {
+        Transaction::begin(self.engine.clone(), Some(time))
     }
 
     /// Begins a new read-only transaction at the latest version.
@@ -263,6 +298,27 @@ impl<E: Engine> MVCC<E> {
         Transaction::begin_read_only(self.engine.clone(), Some(version))
     }
 
+    /// Returns the version of the first read-write transaction that began
+    /// after the given wall-clock time (in microseconds since the Unix epoch),
+    /// or None if none has. A time-travel query as of this version sees the
+    /// state as of the given time.
+    pub fn version_at(&self, time: i64) -> Result<Option<Version>> {
+        let Some(from) = time.checked_add(1) else {
+            return Ok(None);
+        };
+        let mut engine = self.engine.lock()?;
+        let from = Key::TxnBeginTime(from, 0).encode()?;
+        let to = Key::TxnBeginTime(i64::MAX, Version::MAX).encode()?;
+        let mut scan = engine.scan(from..=to);
+        match scan.next().transpose()? {
+            Some((key, _)) => match Key::decode(&key)? {
+                Key::TxnBeginTime(_, version) => Ok(Some(version)),
+                key => Err(Error::Internal(format!("Expected TxnBeginTime key, got {:?}", key))),
+            },
+            None => Ok(None),
+        }
+    }
+
     /// Resumes a transaction from the given transaction state.
     pub fn resume(&self, state: TransactionState) -> Result<Transaction<E>> {
         Transaction::resume(self.engine.clone(), state)
@@ -350,7 +406,7 @@ impl TransactionState {
     /// that version commits its writes. See the module documentation for
     /// details.
     fn is_visible(&self, version: Version) -> bool {
-        if self.active.get(&version).is_some() {
+        if self.active.contains(&version) {
             false
         } else if self.read_only {
             version < self.version
@@ -363,8 +419,9 @@ impl TransactionState {
 impl<E: Engine> Transaction<E> {
     /// Begins a new transaction in read-write mode. This will allocate a new
     /// version that the transaction can write at, add it to the active set, and
-    /// record its active snapshot for time-travel queries.
-    fn begin(engine: Arc<Mutex<E>>) -> Result<Self> {
+    /// record its active snapshot (and begin time, if given) for time-travel
+    /// queries.
+    fn begin(engine: Arc<Mutex<E>>, time: Option<i64>) -> Result<Self> {
         let mut session = engine.lock()?;
 
         // Allocate a new version to write at.
@@ -380,6 +437,9 @@ impl<E: Engine> Transaction<E> {
         if !active.is_empty() {
             session.set(&Key::TxnActiveSnapshot(version).encode()?, bincode::serialize(&active)?)?
         }
+        if let Some(time) = time {
+            session.set(&Key::TxnBeginTime(time, version).encode()?, vec![])?;
+        }
         session.set(&Key::TxnActive(version).encode()?, vec![])?;
         drop(session);
 
@@ -459,6 +519,24 @@ impl<E: Engine> Transaction<E> {
         &self.st
     }
 
+    /// Returns the keys written by the transaction, in key order. Keys that
+    /// were restored by a savepoint rollback may be included.
+    pub fn writes(&self) -> Result<Vec<Vec<u8>>> {
+        if self.st.read_only {
+            return Ok(Vec::new());
+        }
+        let mut session = self.engine.lock()?;
+        let mut keys = Vec::new();
+        let mut scan = session.scan_prefix(&KeyPrefix::TxnWrite(self.st.version).encode()?);
+        while let Some((key, _)) = scan.next().transpose()? {
+            match Key::decode(&key)? {
+                Key::TxnWrite(_, key) => keys.push(key.into_owned()),
+                key => return Err(Error::Internal(format!("Expected TxnWrite, got {:?}", key))),
+            }
+        }
+        Ok(keys)
+    }
+
     /// Commits the transaction, by removing it from the active set. This will
     /// immediately make its writes visible to subsequent transactions. Also
     /// removes its TxnWrite records, which are no longer needed.
@@ -474,6 +552,7 @@ impl<E: Engine> Transaction<E> {
         for key in remove {
             session.delete(&key)?
         }
+        Self::remove_savepoints(&mut session, self.st.version, 0)?;
         session.delete(&Key::TxnActive(self.st.version).encode()?)
     }
 
@@ -500,9 +579,115 @@ impl<E: Engine> Transaction<E> {
         for key in rollback.into_iter() {
             session.delete(&key)?;
         }
+        Self::remove_savepoints(&mut session, self.st.version, 0)?;
         session.delete(&Key::TxnActive(self.st.version).encode()?) // remove from active set
     }
 
+    /// Creates a savepoint with the given ID, which the transaction can later
+    /// roll back to, undoing any writes made since then. This records the
+    /// current value of every key the transaction has written. Any existing
+    /// savepoints with the same or a higher ID are replaced, such that IDs
+    /// form a stack. A no-op for read-only transactions.
+    pub fn savepoint(&self, id: u64) -> Result<()> {
+        if self.st.read_only {
+            return Ok(());
+        }
+        let mut session = self.engine.lock()?;
+        Self::remove_savepoints(&mut session, self.st.version, id)?;
+        let mut keys = Vec::new();
+        let mut scan = session.scan_prefix(&KeyPrefix::TxnWrite(self.st.version).encode()?);
+        while let Some((key, _)) = scan.next().transpose()? {
+            match Key::decode(&key)? {
+                Key::TxnWrite(_, key) => keys.push(key.into_owned()),
+                key => return Err(Error::Internal(format!("Expected TxnWrite, got {:?}", key))),
+            }
+        }
+        drop(scan);
+        for key in keys {
+            let value = session
+                .get(&Key::Version(key.as_slice().into(), self.st.version).encode()?)?
+                .ok_or_else(|| Error::Internal("Missing version for TxnWrite".into()))?;
+            session.set(&Key::TxnSavepoint(self.st.version, id, key.into()).encode()?, value)?;
+        }
+        Ok(())
+    }
+
+    /// Rolls back to the savepoint with the given ID, undoing all writes made
+    /// since it was created: keys written before the savepoint are restored to
+    /// their value at that time, and keys first written after it are removed
+    /// along with their TxnWrite records. The savepoint itself is retained,
+    /// but any later savepoints are removed.
+    pub fn rollback_to_savepoint(&self, id: u64) -> Result<()> {
+        if self.st.read_only {
+            return Ok(());
+        }
+        let mut session = self.engine.lock()?;
+        let mut saved = HashMap::new();
+        let mut scan = session.scan_prefix(&KeyPrefix::TxnSavepoint(self.st.version).encode()?);
+        while let Some((key, value)) = scan.next().transpose()? {
+            match Key::decode(&key)? {
+                Key::TxnSavepoint(_, i, key) if i == id => {
+                    saved.insert(key.into_owned(), value);
+                }
+                Key::TxnSavepoint(..) => {}
+                key => {
+                    return Err(Error::Internal(format!("Expected TxnSavepoint, got {:?}", key)))
+                }
+            }
+        }
+        drop(scan);
+        let mut writes = Vec::new();
+        let mut scan = session.scan_prefix(&KeyPrefix::TxnWrite(self.st.version).encode()?);
+        while let Some((key, _)) = scan.next().transpose()? {
+            match Key::decode(&key)? {
+                Key::TxnWrite(_, userkey) => writes.push((key.clone(), userkey.into_owned())),
+                key => return Err(Error::Internal(format!("Expected TxnWrite, got {:?}", key))),
+            }
+        }
+        drop(scan);
+        for (write, key) in writes {
+            let version = Key::Version(key.as_slice().into(), self.st.version).encode()?;
+            match saved.remove(&key) {
+                Some(value) => session.set(&version, value)?,
+                None => {
+                    session.delete(&version)?;
+                    session.delete(&write)?;
+                }
+            }
+        }
+        Self::remove_savepoints(&mut session, self.st.version, id + 1)
+    }
+
+    /// Releases the savepoint with the given ID, along with any later
+    /// savepoints. Writes made since the savepoint are kept.
+    pub fn release_savepoint(&self, id: u64) -> Result<()> {
+        if self.st.read_only {
+            return Ok(());
+        }
+        Self::remove_savepoints(&mut self.engine.lock()?, self.st.version, id)
+    }
+
+    /// Removes the transaction's savepoint records with IDs at or above the
+    /// given ID.
+    fn remove_savepoints(session: &mut MutexGuard<E>, version: Version, from: u64) -> Result<()> {
+        let mut remove = Vec::new();
+        let mut scan = session.scan_prefix(&KeyPrefix::TxnSavepoint(version).encode()?);
+        while let Some((key, _)) = scan.next().transpose()? {
+            match Key::decode(&key)? {
+                Key::TxnSavepoint(_, id, _) if id < from => {}
+                Key::TxnSavepoint(..) => remove.push(key),
+                key => {
+                    return Err(Error::Internal(format!("Expected TxnSavepoint, got {:?}", key)))
+                }
+            }
+        }
+        drop(scan);
+        for key in remove {
+            session.delete(&key)?;
+        }
+        Ok(())
+    }
+
     /// Deletes a key.
     pub fn delete(&self, key: &[u8]) -> Result<()> {
         self.write_version(key, None)
@@ -574,7 +759,7 @@ impl<E: Engine> Transaction<E> {
 
     /// Returns an iterator over the latest visible key/value pairs at the
     /// transaction's version.
-    pub fn scan<R: RangeBounds<Vec<u8>>>(&self, range: R) -> Result<Scan<E>> {
+    pub fn scan<R: RangeBounds<Vec<u8>>>(&self, range: R) -> Result<Scan<'_, E>> {
         let start = match range.start_bound() {
             Bound::Excluded(k) => Bound::Excluded(Key::Version(k.into(), u64::MAX).encode()?),
             Bound::Included(k) => Bound::Included(Key::Version(k.into(), 0).encode()?),
@@ -589,7 +774,7 @@ impl<E: Engine> Transaction<E> {
     }
 
     /// Scans keys under a given prefix.
-    pub fn scan_prefix(&self, prefix: &[u8]) -> Result<Scan<E>> {
+    pub fn scan_prefix(&self, prefix: &[u8]) -> Result<Scan<'_, E>> {
         // Normally, KeyPrefix::Version will only match all versions of the
         // exact given key. We want all keys maching the prefix, so we chop off
         // the KeyCode byte slice terminator 0x0000 at the end.
@@ -848,6 +1033,10 @@ pub mod tests {
             self.new_txn("begin", self.mvcc.begin())
         }
 
+        fn begin_at(&mut self, time: i64) -> Result<ScheduleTransaction> {
+            self.new_txn(&format!("begin at {}", time), self.mvcc.begin_at(time))
+        }
+
         fn begin_read_only(&mut self) -> Result<ScheduleTransaction> {
             self.new_txn("begin read-only", self.mvcc.begin_read_only())
         }
@@ -995,6 +1184,24 @@ pub mod tests {
             result
         }
 
+        fn savepoint(&self, id: u64) -> Result<()> {
+            let result = self.txn.savepoint(id);
+            self.print_mutation(&format!("savepoint {}", id), &result)?;
+            result
+        }
+
+        fn rollback_to_savepoint(&self, id: u64) -> Result<()> {
+            let result = self.txn.rollback_to_savepoint(id);
+            self.print_mutation(&format!("rollback to savepoint {}", id), &result)?;
+            result
+        }
+
+        fn release_savepoint(&self, id: u64) -> Result<()> {
+            let result = self.txn.release_savepoint(id);
+            self.print_mutation(&format!("release savepoint {}", id), &result)?;
+            result
+        }
+
         fn delete(&self, key: &[u8]) -> Result<()> {
             let result = self.txn.delete(key);
             self.print_mutation(&format!("del {}", debug::format_raw(key)), &result)?;
@@ -1026,7 +1233,7 @@ pub mod tests {
             Ok(value)
         }
 
-        fn scan<R: RangeBounds<Vec<u8>>>(&self, range: R) -> Result<Scan<Debug<Memory>>> {
+        fn scan<R: RangeBounds<Vec<u8>>>(&self, range: R) -> Result<Scan<'_, Debug<Memory>>> {
             let name = format!(
                 "scan {}..{}",
                 match range.start_bound() {
@@ -1045,7 +1252,7 @@ pub mod tests {
             Ok(scan)
         }
 
-        fn scan_prefix(&self, prefix: &[u8]) -> Result<Scan<Debug<Memory>>> {
+        fn scan_prefix(&self, prefix: &[u8]) -> Result<Scan<'_, Debug<Memory>>> {
             let mut scan = self.txn.scan_prefix(prefix)?;
             self.print_scan(&format!("scan prefix {}", debug::format_raw(prefix)), scan.to_vec()?)?;
             Ok(scan)
@@ -1130,6 +1337,7 @@ pub mod tests {
                 Key::Version(b"foo".as_slice().into(), 1),
             ),
             (KeyPrefix::Unversioned, Key::Unversioned(b"foo".as_slice().into())),
+            (KeyPrefix::TxnSavepoint(1), Key::TxnSavepoint(1, 2, b"foo".as_slice().into())),
         ];
 
         for (prefix, key) in cases {
@@ -1208,6 +1416,41 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    /// Versions should be resolvable by the time their transaction began at,
+    /// for time-travel queries by time.
+    fn version_at() -> Result<()> {
+        let mut mvcc = Schedule::new("version_at")?;
+        assert_eq!(mvcc.mvcc.version_at(100)?, None);
+
+        let t1 = mvcc.begin_at(100)?;
+        t1.set(b"key", vec![1])?;
+        t1.commit()?;
+
+        // Transactions without a begin time aren't recorded.
+        let t2 = mvcc.begin()?;
+        t2.set(b"key", vec![2])?;
+        t2.commit()?;
+
+        let t3 = mvcc.begin_at(200)?;
+        t3.set(b"key", vec![3])?;
+        t3.commit()?;
+
+        // A time resolves to the first version that began after it.
+        assert_eq!(mvcc.mvcc.version_at(i64::MIN)?, Some(1));
+        assert_eq!(mvcc.mvcc.version_at(99)?, Some(1));
+        assert_eq!(mvcc.mvcc.version_at(100)?, Some(3));
+        assert_eq!(mvcc.mvcc.version_at(199)?, Some(3));
+        assert_eq!(mvcc.mvcc.version_at(200)?, None);
+        assert_eq!(mvcc.mvcc.version_at(i64::MAX)?, None);
+
+        // Reading as of the resolved version sees the state at that time.
+        let t4 = mvcc.begin_as_of(3)?;
+        assert_scan!(t4.scan(..)? => {b"key" => [2]});
+
+        Ok(())
+    }
+
     #[test]
     /// Begin as of should provide a read-only view of a historical version.
     fn begin_as_of() -> Result<()> {
@@ -1685,6 +1928,60 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    /// Tests that rolling back to a savepoint undoes writes made since the
+    /// savepoint, restoring earlier writes and releasing the keys for other
+    /// transactions, while retaining the rest of the transaction's writes.
+    fn savepoint() -> Result<()> {
+        let mut mvcc = Schedule::new("savepoint")?;
+        mvcc.setup(vec![(b"a", 1, Some(&[0])), (b"b", 1, Some(&[0])), (b"c", 1, Some(&[0]))])?;
+
+        let t1 = mvcc.begin()?;
+        let t2 = mvcc.begin()?;
+
+        // Write a before savepoint 1, then modify it and write b and c, taking
+        // another savepoint in between.
+        t1.set(b"a", vec![1])?;
+        t1.savepoint(1)?;
+        t1.set(b"a", vec![2])?;
+        t1.delete(b"b")?;
+        t1.savepoint(2)?;
+        t1.set(b"c", vec![2])?;
+        assert_eq!(t2.set(b"b", vec![3]), Err(Error::Serialization));
+
+        // Rolling back to savepoint 1 restores a and removes b and c, also
+        // removing savepoint 2. t2 can now write b.
+        t1.rollback_to_savepoint(1)?;
+        assert_scan!(t1.scan(..)? => {
+            b"a" => [1],
+            b"b" => [0],
+            b"c" => [0],
+        });
+        t2.set(b"b", vec![3])?;
+
+        // Savepoint 1 remains, and can be rolled back to again.
+        t1.set(b"c", vec![1])?;
+        t1.rollback_to_savepoint(1)?;
+        assert_eq!(t1.get(b"c")?, Some(vec![0]));
+
+        // Releasing the savepoint keeps later writes, and commit removes any
+        // remaining savepoints.
+        t1.set(b"c", vec![1])?;
+        t1.release_savepoint(1)?;
+        t1.savepoint(1)?;
+        t1.commit()?;
+        t2.commit()?;
+
+        let t3 = mvcc.begin_read_only()?;
+        assert_scan!(t3.scan(..)? => {
+            b"a" => [1],
+            b"b" => [3],
+            b"c" => [1],
+        });
+
+        Ok(())
+    }
+
     #[test]
     /// Tests that transaction rollback properly rolls back uncommitted writes,
     /// allowing other concurrent transactions to write the keys.
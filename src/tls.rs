@@ -0,0 +1,202 @@
+//! TLS for SQL client and Raft peer connections, using rustls. Certificates
+//! and keys are loaded from PEM files, and peers are verified against a CA
+//! certificate. Servers can optionally require client certificates signed by
+//! the CA (mutual TLS), in which case Raft peers authenticate with the node's
+//! own certificate.
+
+use crate::error::{Error, Result};
+
+use rustls::pki_types::pem::PemObject as _;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// A node's TLS configuration, for both inbound and outbound connections.
+#[derive(Clone)]
+pub struct Config {
+    /// The configuration for inbound connections.
+    server: Arc<ServerConfig>,
+    /// The configuration for outbound Raft peer connections.
+    client: Arc<ClientConfig>,
+}
+
+impl Config {
+    /// Creates a TLS configuration from the node's PEM certificate (chain) and
+    /// private key, and the CA certificate(s) used to verify peers. If
+    /// client_auth is true, clients must present a certificate signed by the
+    /// CA.
+    pub fn new(cert: &Path, key: &Path, ca: &Path, client_auth: bool) -> Result<Self> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(to_config_err)?;
+        let builder = if client_auth {
+            let verifier = WebPkiClientVerifier::builder_with_provider(load_roots(ca)?, provider)
+                .build()
+                .map_err(|err| Error::Config(format!("Invalid TLS CA {}: {err}", ca.display())))?;
+            builder.with_client_cert_verifier(verifier)
+        } else {
+            builder.with_no_client_auth()
+        };
+        let server =
+            builder.with_single_cert(load_certs(cert)?, load_key(key)?).map_err(to_config_err)?;
+        let client = client_config(ca, Some((cert, key)))?;
+        Ok(Self { server: Arc::new(server), client })
+    }
+
+    /// Returns the client configuration, which authenticates with the node's
+    /// certificate.
+    pub fn client(&self) -> Arc<ClientConfig> {
+        self.client.clone()
+    }
+
+    /// Performs a server handshake on an accepted connection.
+    pub fn accept(&self, socket: TcpStream) -> Result<Stream> {
+        let conn = ServerConnection::new(self.server.clone())?;
+        Stream::new_server(conn, socket)
+    }
+
+    /// Performs a client handshake on an outbound connection to the given
+    /// address, verifying the peer certificate against its host name.
+    pub fn connect(&self, socket: TcpStream, addr: &str) -> Result<Stream> {
+        connect(&self.client, socket, addr)
+    }
+}
+
+/// Creates a client configuration that verifies servers against the given CA
+/// certificate(s), and optionally authenticates with a certificate and key.
+pub fn client_config(ca: &Path, identity: Option<(&Path, &Path)>) -> Result<Arc<ClientConfig>> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(to_config_err)?
+        .with_root_certificates(load_roots(ca)?);
+    let config = match identity {
+        Some((cert, key)) => builder
+            .with_client_auth_cert(load_certs(cert)?, load_key(key)?)
+            .map_err(to_config_err)?,
+        None => builder.with_no_client_auth(),
+    };
+    Ok(Arc::new(config))
+}
+
+/// Performs a client handshake on an outbound connection to the given
+/// host:port address, verifying the server certificate against the host.
+pub fn connect(config: &Arc<ClientConfig>, socket: TcpStream, addr: &str) -> Result<Stream> {
+    let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let name = ServerName::try_from(host.to_string())
+        .map_err(|err| Error::Value(format!("Invalid TLS server name {host}: {err}")))?;
+    let conn = ClientConnection::new(config.clone(), name)?;
+    Stream::new_client(conn, socket)
+}
+
+/// A connection stream, either plaintext TCP or TLS. A TLS stream is shared
+/// between clones via a mutex, such that a connection can be split into a
+/// reader and a writer. These must be used sequentially, since a read blocks
+/// writes for its duration.
+pub enum Stream {
+    Tcp(TcpStream),
+    Server(Arc<Mutex<rustls::StreamOwned<ServerConnection, TcpStream>>>),
+    Client(Arc<Mutex<rustls::StreamOwned<ClientConnection, TcpStream>>>),
+}
+
+impl Stream {
+    /// Creates a server TLS stream, completing the handshake.
+    fn new_server(mut conn: ServerConnection, mut socket: TcpStream) -> Result<Self> {
+        while conn.is_handshaking() {
+            conn.complete_io(&mut socket)?;
+        }
+        Ok(Self::Server(Arc::new(Mutex::new(rustls::StreamOwned::new(conn, socket)))))
+    }
+
+    /// Creates a client TLS stream, completing the handshake.
+    fn new_client(mut conn: ClientConnection, mut socket: TcpStream) -> Result<Self> {
+        while conn.is_handshaking() {
+            conn.complete_io(&mut socket)?;
+        }
+        Ok(Self::Client(Arc::new(Mutex::new(rustls::StreamOwned::new(conn, socket)))))
+    }
+
+    /// Returns another handle to the same connection.
+    pub fn try_clone(&self) -> Result<Self> {
+        Ok(match self {
+            Self::Tcp(socket) => Self::Tcp(socket.try_clone()?),
+            Self::Server(stream) => Self::Server(stream.clone()),
+            Self::Client(stream) => Self::Client(stream.clone()),
+        })
+    }
+}
+
+impl From<TcpStream> for Stream {
+    fn from(socket: TcpStream) -> Self {
+        Self::Tcp(socket)
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Tcp(socket) => socket.read(buf),
+            Self::Server(stream) => stream.lock().expect("TLS stream poisoned").read(buf),
+            Self::Client(stream) => stream.lock().expect("TLS stream poisoned").read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Tcp(socket) => socket.write(buf),
+            Self::Server(stream) => stream.lock().expect("TLS stream poisoned").write(buf),
+            Self::Client(stream) => stream.lock().expect("TLS stream poisoned").write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Tcp(socket) => socket.flush(),
+            Self::Server(stream) => stream.lock().expect("TLS stream poisoned").flush(),
+            Self::Client(stream) => stream.lock().expect("TLS stream poisoned").flush(),
+        }
+    }
+}
+
+/// Loads PEM certificates from a file.
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+        .map_err(|err| {
+            Error::Config(format!("Can't load TLS certificates {}: {err}", path.display()))
+        })?;
+    if certs.is_empty() {
+        return Err(Error::Config(format!("No TLS certificates in {}", path.display())));
+    }
+    Ok(certs)
+}
+
+/// Loads a PEM private key from a file.
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_file(path)
+        .map_err(|err| Error::Config(format!("Can't load TLS key {}: {err}", path.display())))
+}
+
+/// Loads PEM CA certificates from a file into a root store.
+fn load_roots(path: &Path) -> Result<Arc<RootCertStore>> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(cert).map_err(|err| {
+            Error::Config(format!("Invalid TLS CA certificate {}: {err}", path.display()))
+        })?;
+    }
+    Ok(Arc::new(roots))
+}
+
+/// Converts a TLS configuration error.
+fn to_config_err(err: rustls::Error) -> Error {
+    Error::Config(format!("Invalid TLS configuration: {err}"))
+}
@@ -0,0 +1,363 @@
+use super::testcluster::basic_auth;
+use super::{assert_row, assert_rows, TestCluster};
+
+use ::postgres::error::SqlState;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use serial_test::serial;
+use std::io::Write as _;
+use toydb::error::Error;
+use toydb::server::proto;
+use toydb::sql::types::Value;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[test]
+#[serial]
+fn auth() -> Result<()> {
+    let tc = TestCluster::run_with_auth(3, "rootpw")?;
+
+    // Unauthenticated clients can't execute statements.
+    let mut c = tc.connect_unauthenticated(1)?;
+    assert_eq!(c.execute("SELECT 1").err(), Some(Error::Unauthenticated));
+
+    // Root can't authenticate with the wrong password.
+    let mut c = tc.connect_unauthenticated(1)?;
+    assert_eq!(c.authenticate("root", "wrong").err(), Some(Error::Unauthenticated));
+
+    // Root can create users, which can authenticate on any node.
+    let mut c = tc.connect_unauthenticated(1)?;
+    c.authenticate("root", "rootpw")?;
+    c.execute("CREATE USER alice WITH PASSWORD 'secret'")?;
+    for id in 1..=3 {
+        let mut c = tc.connect_unauthenticated(id)?;
+        c.authenticate("alice", "secret")?;
+        assert_row(c.execute("SELECT 1")?, vec![Value::Integer(1)]);
+    }
+
+    // Unknown users and wrong passwords are rejected.
+    let mut c = tc.connect_unauthenticated(2)?;
+    assert_eq!(c.authenticate("bob", "secret").err(), Some(Error::Unauthenticated));
+    let mut c = tc.connect_unauthenticated(2)?;
+    assert_eq!(c.authenticate("alice", "wrong").err(), Some(Error::Unauthenticated));
+
+    // Altering the password invalidates the old one.
+    let mut c = tc.connect(1)?;
+    c.execute("ALTER USER alice PASSWORD 'other'")?;
+    let mut c = tc.connect_unauthenticated(2)?;
+    assert_eq!(c.authenticate("alice", "secret").err(), Some(Error::Unauthenticated));
+    let mut c = tc.connect_unauthenticated(2)?;
+    c.authenticate("alice", "other")?;
+
+    // Dropped users can't authenticate.
+    let mut c = tc.connect(1)?;
+    c.execute("DROP USER alice")?;
+    let mut c = tc.connect_unauthenticated(3)?;
+    assert_eq!(c.authenticate("alice", "other").err(), Some(Error::Unauthenticated));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn auth_postgres() -> Result<()> {
+    let tc = TestCluster::run_with_auth(1, "rootpw")?;
+    tc.connect(1)?.execute("CREATE USER alice PASSWORD 'secret'")?;
+
+    // Clients authenticate with a cleartext password.
+    let mut c = tc.connect_pg_as(1, "alice", "secret")?;
+    assert!(c.simple_query("SELECT 1").is_ok());
+
+    let err = tc.connect_pg_as(1, "alice", "wrong").err().expect("expected error");
+    assert_eq!(err.code(), Some(&SqlState::INVALID_PASSWORD));
+    let err = tc.connect_pg_as(1, "bob", "secret").err().expect("expected error");
+    assert_eq!(err.code(), Some(&SqlState::INVALID_PASSWORD));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn auth_http() -> Result<()> {
+    let tc = TestCluster::run_with_auth(1, "rootpw")?;
+    tc.connect(1)?.execute("CREATE USER alice PASSWORD 'secret'")?;
+    let query = Some(("text/plain", "SELECT 1"));
+
+    // Requests require Basic credentials, except health checks.
+    let unauthorized = (401, json!({"error": "Authentication failed"}));
+    assert_eq!(tc.http_as(1, None, "POST", "/query", query)?, unauthorized);
+    assert_eq!(tc.http_as(1, None, "GET", "/status", None)?, unauthorized);
+    assert_eq!(tc.http_as(1, Some(("alice", "wrong")), "POST", "/query", query)?, unauthorized);
+    assert_eq!(tc.http_as(1, None, "GET", "/healthz", None)?, (200, json!("ok")));
+
+    assert_eq!(
+        tc.http_as(1, Some(("alice", "secret")), "POST", "/query", query)?,
+        (200, json!({"columns": [null], "rows": [[1]]}))
+    );
+
+    // Statements are authorized as the request's user.
+    tc.connect(1)?.execute("CREATE TABLE test (id INTEGER PRIMARY KEY)")?;
+    let query = Some(("text/plain", "SELECT * FROM test"));
+    assert_eq!(
+        tc.http_as(1, Some(("alice", "secret")), "POST", "/query", query)?,
+        (403, json!({"error": "Permission denied: SELECT on table test for user alice"}))
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn auth_grpc() -> Result<()> {
+    let tc = TestCluster::run_with_auth(1, "rootpw")?;
+    tc.connect(1)?.execute("CREATE USER alice PASSWORD 'secret'")?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let mut c = tc.connect_grpc(1).await?;
+        let request = || proto::ExecuteRequest { query: "SELECT 1".into(), params: vec![] };
+
+        // Calls without valid authorization metadata are rejected.
+        let status = c.execute(request()).await.expect_err("expected error");
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+        let mut wrong = tonic::Request::new(request());
+        wrong.metadata_mut().insert("authorization", basic_auth("alice", "wrong").parse()?);
+        let status = c.execute(wrong).await.expect_err("expected error");
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+        let mut valid = tonic::Request::new(request());
+        valid.metadata_mut().insert("authorization", basic_auth("alice", "secret").parse()?);
+        assert_eq!(c.execute(valid).await?.into_inner().rows.len(), 1);
+
+        Ok(())
+    })
+}
+
+#[test]
+#[serial]
+fn authorization() -> Result<()> {
+    let tc = TestCluster::run_with_auth(1, "rootpw")?;
+    let mut root = tc.connect(1)?;
+    root.execute("CREATE USER alice PASSWORD 'a'")?;
+    root.execute("CREATE USER bob PASSWORD 'b'")?;
+    root.execute("CREATE ROLE readers")?;
+    let connect = |user: &str, password: &str| -> Result<_> {
+        let mut c = tc.connect_unauthenticated(1)?;
+        c.authenticate(user, password)?;
+        Ok(c)
+    };
+    let denied = |err: Option<Error>| matches!(err, Some(Error::PermissionDenied(_)));
+
+    // Alice owns the tables she creates, and has all privileges on them.
+    let mut alice = connect("alice", "a")?;
+    alice.execute("CREATE TABLE test (id INTEGER PRIMARY KEY, value STRING)")?;
+    alice.execute("INSERT INTO test VALUES (1, 'a')")?;
+    alice.execute("CREATE INDEX test_value ON test (value)")?;
+    assert_row(alice.execute("SELECT id FROM test")?, vec![Value::Integer(1)]);
+
+    // Only root can COPY to or from server-side files, but others can use STDIN.
+    let mut file = tempfile::NamedTempFile::new()?;
+    writeln!(file, "2,b")?;
+    let path = file.path().to_str().unwrap();
+    assert!(denied(alice.execute(&format!("COPY test FROM '{path}'")).err()));
+    assert!(denied(alice.execute(&format!("COPY test TO '{path}'")).err()));
+    alice.copy_from("COPY test FROM STDIN", "2,b\n".as_bytes())?;
+    root.execute("DELETE FROM test WHERE id = 2")?;
+    root.execute(&format!("COPY test FROM '{path}'"))?;
+    alice.execute("DELETE FROM test WHERE id = 2")?;
+
+    // Bob has no privileges on the table, including via views and EXPLAIN.
+    let mut bob = connect("bob", "b")?;
+    assert!(denied(bob.execute("SELECT * FROM test").err()));
+    assert!(denied(bob.execute("EXPLAIN SELECT * FROM test").err()));
+    assert!(denied(bob.execute("INSERT INTO test VALUES (2, 'b')").err()));
+    assert!(denied(bob.execute("UPDATE test SET value = 'b'").err()));
+    assert!(denied(bob.execute("DELETE FROM test").err()));
+    assert!(denied(bob.execute("ALTER TABLE test ADD COLUMN other STRING").err()));
+    assert!(denied(bob.execute("DROP INDEX test_value").err()));
+    assert!(denied(bob.execute("DROP TABLE test").err()));
+    bob.execute("CREATE VIEW bobview AS SELECT * FROM test")?;
+    assert!(denied(bob.execute("SELECT * FROM bobview").err()));
+
+    // Nor can he see the table's schema, or reference it via foreign keys.
+    let fk = "CREATE TABLE ref (id INTEGER PRIMARY KEY, test_id INTEGER REFERENCES test)";
+    assert!(denied(bob.execute("DESCRIBE test").err()));
+    assert!(denied(bob.execute("SHOW CREATE TABLE test").err()));
+    assert_rows(bob.execute("SHOW TABLES")?, Vec::new());
+    assert_rows(
+        bob.execute("SELECT table_name FROM information_schema.tables")?,
+        vec![vec![Value::String("bobview".into())]],
+    );
+    assert!(denied(bob.execute(fk).err()));
+    bob.execute("DROP TABLE IF EXISTS missing")?;
+
+    // Views and sequences can only be used and dropped by their owner.
+    alice.execute("CREATE VIEW aliceview AS SELECT 1")?;
+    alice.execute("CREATE SEQUENCE seq")?;
+    assert_row(alice.execute("SELECT NEXTVAL('seq')")?, vec![Value::Integer(1)]);
+    assert!(denied(bob.execute("SELECT NEXTVAL('seq')").err()));
+    assert!(denied(bob.execute("SELECT SETVAL('seq', 10)").err()));
+    assert!(denied(bob.execute("DROP SEQUENCE seq").err()));
+    assert!(denied(bob.execute("DROP VIEW aliceview").err()));
+    bob.execute("DROP VIEW IF EXISTS missing")?;
+
+    // Likewise, types can only be dropped by their owner.
+    alice.execute("CREATE TYPE mood AS ENUM ('happy', 'sad')")?;
+    assert!(denied(bob.execute("DROP TYPE mood").err()));
+    bob.execute("DROP TYPE IF EXISTS missing")?;
+
+    // Nor can he grant himself privileges, or manage users.
+    assert!(denied(bob.execute("GRANT SELECT ON test TO bob").err()));
+    assert!(denied(bob.execute("GRANT readers TO bob").err()));
+    assert!(denied(bob.execute("CREATE USER carol").err()));
+    assert!(denied(bob.execute("CREATE DATABASE bobdb").err()));
+    assert!(denied(bob.execute("ALTER USER alice PASSWORD 'x'").err()));
+    bob.execute("ALTER USER bob PASSWORD 'b'")?;
+
+    // Privileges granted by the owner apply to subsequent statements,
+    // including cached plans.
+    alice.execute("GRANT SELECT, UPDATE ON test TO bob")?;
+    assert_row(bob.execute("SELECT id FROM test")?, vec![Value::Integer(1)]);
+    assert_row(bob.execute("SELECT id FROM bobview")?, vec![Value::Integer(1)]);
+    bob.execute("UPDATE test SET value = 'b' WHERE id = 1")?;
+    assert!(denied(bob.execute("DELETE FROM test").err()));
+    alice.execute("REVOKE SELECT ON test FROM bob")?;
+    assert!(denied(bob.execute("SELECT id FROM test").err()));
+
+    // Without SELECT, he can only update rows without reading them.
+    bob.execute("UPDATE test SET value = 'c'")?;
+    assert!(denied(bob.execute("UPDATE test SET value = 'c' WHERE id = 1").err()));
+    assert!(denied(bob.execute("UPDATE test SET value = value || 'c'").err()));
+    assert!(denied(bob.execute("UPDATE test SET value = 'c' RETURNING id").err()));
+
+    // Any privilege makes the table's schema visible.
+    assert_rows(bob.execute("SHOW TABLES")?, vec![vec![Value::String("test".into())]]);
+    bob.execute("DESCRIBE test")?;
+    assert!(denied(bob.execute(fk).err()));
+    alice.execute("GRANT REFERENCES ON test TO bob")?;
+    bob.execute(fk)?;
+    bob.execute("DROP TABLE ref")?;
+
+    // Privileges granted to a role apply to its members.
+    alice.execute("GRANT SELECT ON test TO readers")?;
+    assert!(denied(bob.execute("SELECT id FROM test").err()));
+    root.execute("GRANT readers TO bob")?;
+    assert_row(bob.execute("SELECT id FROM test")?, vec![Value::Integer(1)]);
+    root.execute("REVOKE readers FROM bob")?;
+    assert!(denied(bob.execute("SELECT id FROM test").err()));
+
+    // Trigger actions are authorized as the trigger's creator, both when it's
+    // created and when it fires, rather than as the user writing the row.
+    root.execute("CREATE TABLE audit (id INTEGER PRIMARY KEY, value STRING)")?;
+    let trigger = "CREATE TRIGGER test_audit AFTER UPDATE ON test FOR EACH ROW \
+        EXECUTE (INSERT INTO audit VALUES (new.id, new.value))";
+    assert!(denied(alice.execute(trigger).err()));
+    root.execute("GRANT INSERT ON audit TO alice")?;
+    alice.execute(trigger)?;
+    bob.execute("UPDATE test SET value = 'd'")?;
+    assert_row(
+        root.execute("SELECT * FROM audit")?,
+        vec![Value::Integer(1), Value::String("d".into())],
+    );
+    root.execute("REVOKE INSERT ON audit FROM alice")?;
+    assert!(denied(bob.execute("UPDATE test SET value = 'e'").err()));
+    alice.execute("DROP TRIGGER test_audit ON test")?;
+
+    // Roles can't authenticate.
+    let mut c = tc.connect_unauthenticated(1)?;
+    assert_eq!(c.authenticate("readers", "").err(), Some(Error::Unauthenticated));
+
+    // Users owning tables, views, sequences or types can't be dropped, but the
+    // owner can drop them.
+    assert!(root.execute("DROP USER alice").is_err());
+    alice.execute("DROP TABLE test")?;
+    assert!(root.execute("DROP USER alice").is_err());
+    alice.execute("DROP VIEW aliceview")?;
+    assert!(root.execute("DROP USER alice").is_err());
+    alice.execute("DROP SEQUENCE seq")?;
+    assert!(root.execute("DROP USER alice").is_err());
+    alice.execute("DROP TYPE mood")?;
+    root.execute("DROP USER alice")?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn authorization_admin() -> Result<()> {
+    let tc = TestCluster::run_with_auth(1, "rootpw")?;
+    tc.connect(1)?.execute("CREATE USER alice PASSWORD 'secret'")?;
+    let mut alice = tc.connect_unauthenticated(1)?;
+    alice.authenticate("alice", "secret")?;
+
+    // Only root can add cluster nodes.
+    assert!(matches!(alice.join(2, "localhost:9999"), Err(Error::PermissionDenied(_))));
+
+    // Users can only see and cancel their own queries, while root can see and
+    // cancel all of them.
+    let mut root = tc.connect(1)?;
+    root.execute("CREATE TABLE test (id INTEGER PRIMARY KEY)")?;
+    root.execute("INSERT INTO test VALUES (1), (2)")?;
+    let (Some(cursor), _) = root.open("SELECT * FROM test")? else { panic!("expected cursor") };
+    let (Some(_), _) = alice.open("SHOW QUERIES")? else { panic!("expected cursor") };
+    let show_queries = |c: &mut toydb::Client| -> Result<Vec<(i64, String)>> {
+        c.execute("SHOW QUERIES")?
+            .into_rows()?
+            .map(|r| match r?.as_slice() {
+                [Value::Integer(id), Value::String(query)] => Ok((*id, query.clone())),
+                row => panic!("unexpected row {row:?}"),
+            })
+            .collect()
+    };
+    let queries = |c: &mut toydb::Client| -> Result<Vec<String>> {
+        Ok(show_queries(c)?.into_iter().map(|(_, query)| query).collect())
+    };
+    assert_eq!(queries(&mut alice)?, vec!["SHOW QUERIES", "SHOW QUERIES"]);
+    assert_eq!(queries(&mut root)?, vec!["SELECT * FROM test", "SHOW QUERIES", "SHOW QUERIES"]);
+    let id = show_queries(&mut root)?[0].0;
+    assert!(matches!(alice.execute(&format!("KILL {id}")), Err(Error::PermissionDenied(_))));
+    assert_eq!(root.fetch(cursor, 2)?.0.len(), 2);
+
+    // Users can only see the schemas of tables they have privileges on, and
+    // views they own. Others are reported as missing.
+    root.execute("CREATE VIEW rootview AS SELECT * FROM test")?;
+    alice.execute("CREATE VIEW aliceview AS SELECT 1")?;
+    let missing =
+        |err: Option<Error>| matches!(err, Some(Error::Value(e)) if e.ends_with("does not exist"));
+    assert!(missing(alice.get_table("test").err()));
+    assert_eq!(alice.list_tables()?, Vec::<String>::new());
+    assert!(missing(alice.get_view("rootview").err()));
+    assert_eq!(alice.get_view("aliceview")?.name, "aliceview");
+    assert_eq!(alice.list_views()?, vec!["aliceview"]);
+    assert_eq!(root.list_views()?, vec!["aliceview", "rootview"]);
+    root.execute("GRANT SELECT ON test TO alice")?;
+    assert_eq!(alice.get_table("test")?.name, "test");
+    assert_eq!(alice.list_tables()?, vec!["test"]);
+    root.execute("REVOKE SELECT ON test FROM alice")?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        use proto::admin_request::{Join, ListTables, ListViews, Request};
+        let mut c = tc.connect_grpc(1).await?;
+        let admin = |request| {
+            let mut request = tonic::Request::new(proto::AdminRequest { request: Some(request) });
+            request.metadata_mut().insert("authorization", basic_auth("alice", "secret").parse()?);
+            Ok::<_, Box<dyn std::error::Error>>(request)
+        };
+
+        let join = Request::Join(Join { id: 2, address: "localhost:9999".into() });
+        let status = c.admin(admin(join)?).await.expect_err("expected error");
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+        let status = c.admin(admin(Request::GetTable("test".into()))?).await.unwrap_err();
+        assert_eq!(status.message(), "Table test does not exist");
+        let response = c.admin(admin(Request::ListTables(ListTables {}))?).await?.into_inner();
+        assert_eq!(response.names, Vec::<String>::new());
+        let status = c.admin(admin(Request::GetView("rootview".into()))?).await.unwrap_err();
+        assert_eq!(status.message(), "View rootview does not exist");
+        let response = c.admin(admin(Request::ListViews(ListViews {}))?).await?.into_inner();
+        assert_eq!(response.names, vec!["aliceview"]);
+
+        Ok(())
+    })
+}
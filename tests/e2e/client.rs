@@ -5,12 +5,13 @@ use toydb::raft;
 use toydb::server::Status;
 use toydb::sql::execution::ResultSet;
 use toydb::sql::schema;
-use toydb::sql::types::{Column, DataType, Value};
+use toydb::sql::types::{Collation, Column, DataType, Expression, Row, Value};
 use toydb::storage;
 use toydb::storage::{engine, mvcc};
 
 use pretty_assertions::assert_eq;
 use serial_test::serial;
+use std::io::Write as _;
 
 #[test]
 #[serial]
@@ -27,74 +28,131 @@ fn get_table() -> Result<()> {
                 schema::Column {
                     name: "id".into(),
                     datatype: DataType::Integer,
+                    collation: Collation::Binary,
                     primary_key: true,
                     nullable: false,
                     default: None,
+                    generated: None,
                     unique: true,
                     index: false,
+                    auto_increment: false,
                     references: None,
+                    on_delete: schema::ReferenceAction::Restrict,
+                    on_update: schema::ReferenceAction::Restrict,
+                    deferred: false,
+                    comment: None,
                 },
                 schema::Column {
                     name: "title".into(),
                     datatype: DataType::String,
+                    collation: Collation::Binary,
                     primary_key: false,
                     nullable: false,
                     default: None,
+                    generated: None,
                     unique: false,
                     index: false,
+                    auto_increment: false,
                     references: None,
+                    on_delete: schema::ReferenceAction::Restrict,
+                    on_update: schema::ReferenceAction::Restrict,
+                    deferred: false,
+                    comment: None,
                 },
                 schema::Column {
                     name: "studio_id".into(),
                     datatype: DataType::Integer,
+                    collation: Collation::Binary,
                     primary_key: false,
                     nullable: false,
                     default: None,
+                    generated: None,
                     unique: false,
                     index: false,
+                    auto_increment: false,
                     references: Some("studios".into()),
+                    on_delete: schema::ReferenceAction::Restrict,
+                    on_update: schema::ReferenceAction::Restrict,
+                    deferred: false,
+                    comment: None,
                 },
                 schema::Column {
                     name: "genre_id".into(),
                     datatype: DataType::Integer,
+                    collation: Collation::Binary,
                     primary_key: false,
                     nullable: false,
                     default: None,
+                    generated: None,
                     unique: false,
                     index: false,
+                    auto_increment: false,
                     references: Some("genres".into()),
+                    on_delete: schema::ReferenceAction::Restrict,
+                    on_update: schema::ReferenceAction::Restrict,
+                    deferred: false,
+                    comment: None,
                 },
                 schema::Column {
                     name: "released".into(),
                     datatype: DataType::Integer,
+                    collation: Collation::Binary,
                     primary_key: false,
                     nullable: false,
                     default: None,
+                    generated: None,
                     unique: false,
                     index: false,
+                    auto_increment: false,
                     references: None,
+                    on_delete: schema::ReferenceAction::Restrict,
+                    on_update: schema::ReferenceAction::Restrict,
+                    deferred: false,
+                    comment: None,
                 },
                 schema::Column {
                     name: "rating".into(),
                     datatype: DataType::Float,
+                    collation: Collation::Binary,
                     primary_key: false,
                     nullable: true,
-                    default: Some(Value::Null),
+                    default: Some(Expression::Constant(Value::Null)),
+                    generated: None,
                     unique: false,
                     index: false,
+                    auto_increment: false,
                     references: None,
+                    on_delete: schema::ReferenceAction::Restrict,
+                    on_update: schema::ReferenceAction::Restrict,
+                    deferred: false,
+                    comment: None,
                 },
                 schema::Column {
                     name: "ultrahd".into(),
                     datatype: DataType::Boolean,
+                    collation: Collation::Binary,
                     primary_key: false,
                     nullable: true,
-                    default: Some(Value::Null),
+                    default: Some(Expression::Constant(Value::Null)),
+                    generated: None,
                     unique: false,
                     index: false,
+                    auto_increment: false,
                     references: None,
+                    on_delete: schema::ReferenceAction::Restrict,
+                    on_update: schema::ReferenceAction::Restrict,
+                    deferred: false,
+                    comment: None,
                 },
-            ]
+            ],
+            indexes: vec![],
+            version: 0,
+            compatibility: schema::Compatibility::Compatible,
+            temporary: false,
+            comment: None,
+            triggers: vec![],
+            owner: None,
+            privileges: Default::default(),
         }
     );
     Ok(())
@@ -110,6 +168,30 @@ fn list_tables() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn get_view() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect_any()?;
+
+    assert_eq!(c.list_views()?, Vec::<String>::new());
+    assert_eq!(c.get_view("good"), Err(Error::Value("View good does not exist".into())));
+
+    c.execute("CREATE VIEW good (name) AS SELECT title FROM movies WHERE rating >= 8")?;
+    c.execute("CREATE VIEW bad AS SELECT title FROM movies WHERE rating < 5")?;
+    assert_eq!(c.list_views()?, vec!["bad", "good"]);
+    assert_eq!(
+        c.get_view("good")?,
+        schema::View {
+            name: "good".into(),
+            columns: vec!["name".into()],
+            query: "SELECT title FROM movies WHERE rating >= 8".into(),
+            owner: None,
+        }
+    );
+    Ok(())
+}
+
 #[test]
 #[serial]
 fn status() -> Result<()> {
@@ -123,16 +205,16 @@ fn status() -> Result<()> {
             raft: raft::Status {
                 leader: 1,
                 term: 1,
-                last_index: [(1, 27)].into(),
-                commit_index: 27,
-                apply_index: 27,
+                last_index: [(1, 11)].into(),
+                commit_index: 11,
+                apply_index: 11,
                 storage: storage::engine::Status {
                     name: "bitcask".to_string(),
-                    keys: 29,
-                    size: 1322,
-                    total_disk_size: 1829,
-                    live_disk_size: 1554,
-                    garbage_disk_size: 275
+                    keys: 13,
+                    size: 1093,
+                    total_disk_size: 1296,
+                    live_disk_size: 1197,
+                    garbage_disk_size: 99
                 },
             },
             mvcc: mvcc::Status {
@@ -140,11 +222,11 @@ fn status() -> Result<()> {
                 active_txns: 0,
                 storage: engine::Status {
                     name: "bitcask".to_string(),
-                    keys: 26,
-                    size: 1630,
-                    total_disk_size: 4556,
-                    live_disk_size: 1838,
-                    garbage_disk_size: 2718
+                    keys: 28,
+                    size: 1835,
+                    total_disk_size: 4546,
+                    live_disk_size: 2059,
+                    garbage_disk_size: 2487
                 },
             }
         },
@@ -152,6 +234,62 @@ fn status() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn redirect_to_leader() -> Result<()> {
+    let tc = TestCluster::run_without_forwarding(3)?;
+
+    // Clients connected to any node are redirected to the leader.
+    let leader = tc.connect_any()?.status()?.raft.leader;
+    for id in 1..=3 {
+        let mut c = tc.connect(id)?;
+        assert_eq!(c.status()?.server, leader);
+    }
+
+    // Writes and transactions submitted via followers are served by the
+    // leader.
+    let follower = if leader == 1 { 2 } else { 1 };
+    let mut c = tc.connect(follower)?;
+    c.execute("CREATE TABLE test (id INTEGER PRIMARY KEY)")?;
+    c.execute("BEGIN")?;
+    c.execute("INSERT INTO test VALUES (1)")?;
+    c.execute("COMMIT")?;
+    assert_row(c.execute("SELECT * FROM test")?, vec![Value::Integer(1)]);
+    assert_eq!(c.status()?.server, leader);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn join_cluster() -> Result<()> {
+    let tc = TestCluster::run_joined(3)?;
+
+    // Node 1 is the leader, and writes are replicated to the joined nodes.
+    let mut c = tc.connect(1)?;
+    c.execute("CREATE TABLE test (id INTEGER PRIMARY KEY)")?;
+    c.execute("INSERT INTO test VALUES (1)")?;
+    assert_eq!(c.status()?.raft.leader, 1);
+
+    // Wait for all nodes to join and catch up, then read the value from them.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    for id in 1..=3 {
+        let mut c = tc.connect(id)?;
+        loop {
+            match c.execute("SELECT * FROM test") {
+                Ok(rs) => {
+                    assert_row(rs, vec![Value::Integer(1)]);
+                    break;
+                }
+                Err(err) if std::time::Instant::now() >= deadline => return Err(err),
+                Err(_) => std::thread::sleep(std::time::Duration::from_millis(100)),
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[test]
 #[serial]
 fn execute() -> Result<()> {
@@ -227,6 +365,1074 @@ fn execute() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn execute_bytes() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect_any()?;
+
+    // Binary values, including invalid UTF-8 and NUL bytes, roundtrip as raw
+    // bytes rather than strings.
+    let payload: Vec<u8> = (0..=255).collect();
+    c.execute("CREATE TABLE blobs (id INTEGER PRIMARY KEY, data BYTEA)")?;
+    assert_eq!(
+        c.execute(&format!("INSERT INTO blobs VALUES (1, x'{}')", hex::encode(&payload))),
+        Ok(ResultSet::Create { count: 1 }),
+    );
+    assert_eq!(
+        c.execute(r"INSERT INTO blobs VALUES (2, BYTEA '\x00ff'), (3, x''), (4, NULL)"),
+        Ok(ResultSet::Create { count: 3 }),
+    );
+    assert_rows(
+        c.execute("SELECT * FROM blobs")?,
+        vec![
+            vec![Value::Integer(1), Value::Bytes(payload)],
+            vec![Value::Integer(2), Value::Bytes(vec![0x00, 0xff])],
+            vec![Value::Integer(3), Value::Bytes(vec![])],
+            vec![Value::Integer(4), Value::Null],
+        ],
+    );
+    assert_rows(
+        c.execute("SELECT LENGTH(data) FROM blobs WHERE data = x'00FF'")?,
+        vec![vec![Value::Integer(2)]],
+    );
+    assert_eq!(
+        c.execute("INSERT INTO blobs VALUES (5, 'text')"),
+        Err(Error::Value("Invalid datatype STRING for BYTEA column data".into())),
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_auto_increment() -> Result<()> {
+    let tc = TestCluster::run(5)?;
+    let mut a = tc.connect(1)?;
+    let mut b = tc.connect(2)?;
+    a.execute("CREATE TABLE counter (id SERIAL PRIMARY KEY, value STRING)")?;
+
+    // Omitted values are taken from the sequence.
+    a.execute("INSERT INTO counter (value) VALUES ('a'), ('b')")?;
+
+    // Values taken by a rolled back txn are not reused.
+    a.execute("BEGIN")?;
+    a.execute("INSERT INTO counter (value) VALUES ('c')")?;
+    a.execute("ROLLBACK")?;
+    a.execute("INSERT INTO counter (value) VALUES ('d')")?;
+
+    // Each node allocates a separate batch of values.
+    b.execute("INSERT INTO counter (value) VALUES ('e')")?;
+
+    assert_rows(
+        a.execute("SELECT * FROM counter")?,
+        vec![
+            vec![Value::Integer(1), Value::String("a".into())],
+            vec![Value::Integer(2), Value::String("b".into())],
+            vec![Value::Integer(4), Value::String("d".into())],
+            vec![Value::Integer(101), Value::String("e".into())],
+        ],
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_temporary() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut a = tc.connect(1)?;
+    let mut b = tc.connect(1)?;
+
+    // Temporary tables are only visible to the session that created them,
+    // but can be joined with other tables.
+    a.execute("CREATE TEMPORARY TABLE picks (id INTEGER PRIMARY KEY, note STRING)")?;
+    a.execute("INSERT INTO picks VALUES (1, 'classic'), (3, 'rewatch')")?;
+    assert_rows(
+        a.execute(
+            "SELECT m.title, p.note FROM picks p JOIN movies m ON m.id = p.id ORDER BY p.id",
+        )?,
+        vec![
+            vec![Value::String("Stalker".into()), Value::String("classic".into())],
+            vec![Value::String("Primer".into()), Value::String("rewatch".into())],
+        ],
+    );
+    assert!(a.list_tables()?.contains(&"picks".to_string()));
+    assert!(!b.list_tables()?.contains(&"picks".to_string()));
+    let show_tables = |c: &mut toydb::Client| -> Result<Vec<Value>> {
+        c.execute("SHOW TABLES")?.into_rows()?.map(|r| Ok(r?.remove(0))).collect()
+    };
+    assert!(show_tables(&mut a)?.contains(&Value::String("picks".into())));
+    assert!(!show_tables(&mut b)?.contains(&Value::String("picks".into())));
+    assert_eq!(
+        b.execute("SELECT * FROM picks"),
+        Err(Error::Value("Table picks does not exist".into()))
+    );
+
+    // Temporary tables are transactional.
+    a.execute("BEGIN")?;
+    a.execute("DELETE FROM picks")?;
+    a.execute("INSERT INTO movies (id, title, genre_id, studio_id, released, rating, ultrahd) VALUES (99, 'Temp', 1, 1, 2024, 5.0, FALSE)")?;
+    a.execute("ROLLBACK")?;
+    assert_row(a.execute("SELECT COUNT(*) FROM picks")?, vec![Value::Integer(2)]);
+    assert_row(a.execute("SELECT COUNT(*) FROM movies")?, vec![Value::Integer(10)]);
+
+    // Names can't conflict with other tables, and temporary tables can't be
+    // referenced by other tables.
+    assert_eq!(
+        a.execute("CREATE TEMPORARY TABLE movies (id INTEGER PRIMARY KEY)"),
+        Err(Error::Value("Table movies already exists".into()))
+    );
+    assert_eq!(
+        a.execute("CREATE TABLE picks (id INTEGER PRIMARY KEY)"),
+        Err(Error::Value("Table picks already exists".into()))
+    );
+    assert_eq!(
+        a.execute(
+            "CREATE TABLE reviews (id INTEGER PRIMARY KEY, pick_id INTEGER REFERENCES picks)"
+        ),
+        Err(Error::Value("Table picks referenced by column pick_id does not exist".into()))
+    );
+
+    // Temporary tables are dropped when the session disconnects.
+    a.execute("CREATE TEMP TABLE top AS SELECT id, title FROM movies WHERE rating >= 8.5")?;
+    assert_row(a.execute("SELECT COUNT(*) FROM top")?, vec![Value::Integer(1)]);
+    drop(a);
+    let mut a = tc.connect(1)?;
+    assert_eq!(
+        a.execute("SELECT * FROM top"),
+        Err(Error::Value("Table top does not exist".into()))
+    );
+    b.execute("CREATE TABLE picks (id INTEGER PRIMARY KEY)")?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_explain_analyze() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect(1)?;
+
+    // EXPLAIN ANALYZE returns the same plan as EXPLAIN, along with the rows
+    // produced by each node in pre-order.
+    let query = "SELECT m.title, g.name FROM movies m JOIN genres g ON m.genre_id = g.id \
+                 WHERE m.rating >= 8 ORDER BY m.id";
+    let ResultSet::Explain(explain) = c.execute(&format!("EXPLAIN {}", query))? else {
+        panic!("expected explain result")
+    };
+    let ResultSet::ExplainAnalyze { plan, stats } =
+        c.execute(&format!("EXPLAIN ANALYZE {}", query))?
+    else {
+        panic!("expected explain analyze result")
+    };
+    assert_eq!(plan, explain);
+    assert_eq!(
+        plan.format_analyzed(&stats)
+            .lines()
+            .map(|l| l.split(" (rows=").next().unwrap())
+            .collect::<Vec<_>>(),
+        explain.to_string().lines().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        stats.iter().map(|s| (s.rows, s.loops)).collect::<Vec<_>>(),
+        vec![(4, 1), (4, 1), (4, 1), (4, 1), (4, 1), (3, 1)]
+    );
+
+    // The right node of a lateral join is executed once per left row, and
+    // its statistics are summed across executions.
+    let ResultSet::ExplainAnalyze { stats, .. } = c.execute(
+        "EXPLAIN ANALYZE SELECT g.name, m.title FROM genres g CROSS JOIN LATERAL \
+         (SELECT title FROM movies WHERE genre_id = g.id ORDER BY rating DESC LIMIT 1) m",
+    )?
+    else {
+        panic!("expected explain analyze result")
+    };
+    assert_eq!(
+        stats.iter().map(|s| (s.rows, s.loops)).collect::<Vec<_>>(),
+        vec![(3, 1), (3, 1), (3, 1), (3, 3), (3, 3), (3, 3), (10, 3), (10, 3)]
+    );
+
+    // Mutations are executed, and report the number of affected rows.
+    let ResultSet::ExplainAnalyze { stats, .. } =
+        c.execute("EXPLAIN ANALYZE UPDATE movies SET rating = 9.0 WHERE rating >= 8")?
+    else {
+        panic!("expected explain analyze result")
+    };
+    assert_eq!(stats.iter().map(|s| (s.rows, s.loops)).collect::<Vec<_>>(), vec![(4, 1), (4, 1)]);
+    assert_row(
+        c.execute("SELECT COUNT(*) FROM movies WHERE rating = 9.0")?,
+        vec![Value::Integer(4)],
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_explain_verbose() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect(1)?;
+    c.execute("ANALYZE")?;
+
+    let query = "SELECT m.title, g.name FROM movies m JOIN genres g ON m.genre_id = g.id \
+                 WHERE m.rating >= 8 ORDER BY m.id";
+    let ResultSet::ExplainVerbose { plan, estimates, stats } =
+        c.execute(&format!("EXPLAIN VERBOSE {}", query))?
+    else {
+        panic!("expected explain verbose result")
+    };
+    assert!(stats.is_empty());
+    assert_eq!(
+        plan.format_verbose(&estimates, &stats),
+        "Projection: #0, #1 (estimated rows=5 cost=43.3)
+└─ Order: m.id asc (estimated rows=5 cost=38.6)
+   └─ Projection: m.title, g.name, m.id (estimated rows=5 cost=28.3)
+      └─ HashJoin: inner on m.genre_id = g.id (estimated rows=5 cost=23.7)
+         ├─ Scan: movies as m (m.rating > 8 OR m.rating = 8) (estimated rows=5 cost=10.0)
+         └─ Scan: genres as g (estimated rows=3 cost=3.0)"
+    );
+
+    // EXPLAIN (FORMAT JSON) returns the plan tree as a single JSON value.
+    let Value::Json(json) =
+        c.execute(&format!("EXPLAIN (FORMAT JSON, ANALYZE) {}", query))?.into_value()?
+    else {
+        panic!("expected json value")
+    };
+    let mut nodes = Vec::new();
+    let mut stack = vec![&json];
+    while let Some(node) = stack.pop() {
+        nodes.push((node["node"].as_str().unwrap(), node["rows"].as_u64().unwrap()));
+        assert!(node["estimated_rows"].is_f64() && node["estimated_cost"].is_f64());
+        stack.extend(node["children"].as_array().unwrap().iter().rev());
+    }
+    assert_eq!(
+        nodes,
+        vec![
+            ("Projection", 4),
+            ("Order", 4),
+            ("Projection", 4),
+            ("HashJoin", 4),
+            ("Scan", 4),
+            ("Scan", 3)
+        ]
+    );
+    assert_eq!(json["children"][0]["details"], "m.id asc");
+
+    // Unknown options and formats are rejected.
+    assert!(matches!(
+        c.execute(&format!("EXPLAIN (FORMAT XML) {}", query)),
+        Err(Error::Syntax { message, .. }) if message == "Unknown EXPLAIN format xml"
+    ));
+    assert!(matches!(
+        c.execute(&format!("EXPLAIN (COSTS) {}", query)),
+        Err(Error::Syntax { message, .. }) if message == "Unknown EXPLAIN option costs"
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_settings() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut a = tc.connect_any()?;
+    let mut b = tc.connect_any()?;
+
+    // Settings can be set and shown, and are local to the session.
+    assert_row(a.execute("SHOW statement_timeout")?, vec![Value::Integer(0)]);
+    assert_eq!(
+        a.execute("SET statement_timeout = 5000")?,
+        ResultSet::Set { name: "statement_timeout".into() }
+    );
+    a.execute("SET serialization_retries TO 3")?;
+    assert_row(a.execute("SHOW statement_timeout")?, vec![Value::Integer(5000)]);
+    assert_row(b.execute("SHOW statement_timeout")?, vec![Value::Integer(0)]);
+    assert_rows(
+        a.execute("SHOW ALL")?,
+        vec![
+            vec![Value::String("default_transaction_read_only".into()), Value::Boolean(false)],
+            vec![Value::String("parallel_workers".into()), Value::Integer(1)],
+            vec![Value::String("plan_cache_size".into()), Value::Integer(100)],
+            vec![Value::String("query_memory".into()), Value::Integer(1048576)],
+            vec![Value::String("serialization_retries".into()), Value::Integer(3)],
+            vec![Value::String("statement_timeout".into()), Value::Integer(5000)],
+            vec![Value::String("strict_types".into()), Value::Boolean(false)],
+            vec![Value::String("work_memory".into()), Value::Integer(65536)],
+        ],
+    );
+    a.execute("SET statement_timeout = DEFAULT")?;
+    assert_row(a.execute("SHOW statement_timeout")?, vec![Value::Integer(0)]);
+
+    // Unknown settings and invalid values error.
+    assert_eq!(a.execute("SHOW foo"), Err(Error::Value("Unknown setting foo".into())));
+    assert_eq!(a.execute("SET foo = 1"), Err(Error::Value("Unknown setting foo".into())));
+    assert_eq!(
+        a.execute("SET statement_timeout = 'soon'"),
+        Err(Error::Value("Invalid value soon for setting statement_timeout".into()))
+    );
+    assert_eq!(
+        a.execute("SET statement_timeout = -5"),
+        Err(Error::Value("Invalid value -5 for setting statement_timeout".into()))
+    );
+    assert_eq!(
+        a.execute("SET serialization_retries = id"),
+        Err(Error::Value("Expression must be constant, found field id".into()))
+    );
+
+    // Constant expressions are evaluated.
+    a.execute("SET serialization_retries = 1 + 1")?;
+    assert_row(a.execute("SHOW serialization_retries")?, vec![Value::Integer(2)]);
+    a.execute("SET serialization_retries TO 3")?;
+
+    // Timeouts can also be given as strings with a unit.
+    a.execute("SET statement_timeout = '2min'")?;
+    assert_row(a.execute("SHOW statement_timeout")?, vec![Value::Integer(120_000)]);
+    a.execute("SET statement_timeout = '0'")?;
+    assert_row(a.execute("SHOW statement_timeout")?, vec![Value::Integer(0)]);
+    assert_eq!(
+        a.execute("SET statement_timeout = '5 days'"),
+        Err(Error::Value("Invalid value 5 days for setting statement_timeout".into()))
+    );
+
+    // Queries run normally within the statement timeout.
+    a.execute("SET statement_timeout = 60000")?;
+    assert_row(a.execute("SELECT COUNT(*) FROM genres")?, vec![Value::Integer(3)]);
+
+    // When default_transaction_read_only is set, transactions and standalone
+    // statements are read-only unless BEGIN READ WRITE is given.
+    a.execute("SET default_transaction_read_only = TRUE")?;
+    assert_eq!(a.execute("INSERT INTO genres VALUES (4, 'Drama')"), Err(Error::ReadOnly));
+    assert_eq!(a.execute("BEGIN")?, ResultSet::Begin { version: 2, read_only: true });
+    assert_eq!(a.execute("INSERT INTO genres VALUES (4, 'Drama')"), Err(Error::ReadOnly));
+    a.execute("COMMIT")?;
+    assert_eq!(a.execute("BEGIN READ WRITE")?, ResultSet::Begin { version: 2, read_only: false });
+    a.execute("INSERT INTO genres VALUES (4, 'Drama')")?;
+    a.execute("COMMIT")?;
+    b.execute("INSERT INTO genres VALUES (5, 'Horror')")?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_strict_types() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect_any()?;
+
+    // String literals are converted to the types they're compared with, also
+    // when the plan is cached for the same query with an integer.
+    let stalker = vec![Value::String("Stalker".into())];
+    assert_row(c.execute("SELECT title FROM movies WHERE id = 1")?, stalker.clone());
+    assert_row(c.execute("SELECT title FROM movies WHERE id = '1'")?, stalker.clone());
+    assert_row(c.execute("SELECT title FROM movies WHERE id = '1'")?, stalker.clone());
+    assert_row(c.execute("SELECT title FROM movies WHERE id = 1")?, stalker.clone());
+
+    // With strict types, they must be cast explicitly.
+    c.execute("SET strict_types = TRUE")?;
+    assert_eq!(
+        c.execute("SELECT title FROM movies WHERE id = '1'"),
+        Err(Error::Value("String literal '1' must be explicitly cast to INTEGER".into()))
+    );
+    assert_row(
+        c.execute("SELECT title FROM movies WHERE id = CAST('1' AS INTEGER)")?,
+        stalker.clone(),
+    );
+    assert_row(
+        c.execute("SELECT id FROM movies WHERE title = 'Stalker'")?,
+        vec![Value::Integer(1)],
+    );
+
+    c.execute("SET strict_types = FALSE")?;
+    assert_row(c.execute("SELECT title FROM movies WHERE id = '1'")?, stalker);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_statement_timeout() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect_any()?;
+    c.execute("CREATE TABLE digits (d INTEGER PRIMARY KEY)")?;
+    c.execute("INSERT INTO digits VALUES (0), (1), (2), (3), (4), (5), (6), (7), (8), (9)")?;
+    c.execute(
+        "CREATE TABLE numbers AS SELECT a.d * 100 + b.d * 10 + e.d AS id, 0 AS n \
+         FROM digits a, digits b, digits e",
+    )?;
+
+    // A long-running query errors once the timeout passes.
+    c.execute("SET statement_timeout = '100ms'")?;
+    assert_eq!(
+        c.execute("SELECT COUNT(*) FROM movies m1, movies m2, movies m3, movies m4, movies m5"),
+        Err(Error::Timeout)
+    );
+
+    // A statement that times out in a transaction has its writes rolled back,
+    // and the transaction can continue.
+    c.execute("SET statement_timeout = 0")?;
+    c.execute("BEGIN")?;
+    c.execute("INSERT INTO genres VALUES (4, 'Drama')")?;
+    c.execute("SET statement_timeout = 50")?;
+    assert_eq!(c.execute("UPDATE numbers SET n = 1"), Err(Error::Timeout));
+    c.execute("SET statement_timeout = 0")?;
+    assert_row(c.execute("SELECT SUM(n) FROM numbers")?, vec![Value::Integer(0)]);
+    c.execute("COMMIT")?;
+    assert_row(c.execute("SELECT name FROM genres WHERE id = 4")?, vec!["Drama".into()]);
+    assert_row(c.execute("SELECT SUM(n) FROM numbers")?, vec![Value::Integer(0)]);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_plan_cache() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut a = tc.connect(1)?;
+    let mut b = tc.connect(1)?;
+
+    // Cached plans are reused for statements which only differ in their
+    // literals, including ones whose values are used during planning.
+    assert_row(a.execute("SELECT title FROM movies WHERE id = 1")?, vec!["Stalker".into()]);
+    assert_row(a.execute("SELECT title FROM movies WHERE id = 3")?, vec!["Primer".into()]);
+    a.execute("UPDATE genres SET name = 'Sci-fi' WHERE id = 1")?;
+    a.execute("UPDATE genres SET name = 'Thriller' WHERE id = 2")?;
+    assert_rows(
+        a.execute("SELECT STRING_AGG(name, ', ' ORDER BY id) FROM genres LIMIT 1")?,
+        vec![vec!["Sci-fi, Thriller, Comedy".into()]],
+    );
+    assert_rows(a.execute("SELECT STRING_AGG(name, '/' ORDER BY id) FROM genres LIMIT 0")?, vec![]);
+    assert_rows(
+        a.execute("SELECT id FROM movies ORDER BY id LIMIT 2")?,
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)]],
+    );
+    assert_rows(
+        a.execute("SELECT id FROM movies ORDER BY id LIMIT 3")?,
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)], vec![Value::Integer(3)]],
+    );
+
+    // Schema changes in other sessions invalidate cached plans, here moving
+    // the name column.
+    assert_row(a.execute("SELECT name FROM genres WHERE id = 1")?, vec!["Sci-fi".into()]);
+    b.execute("ALTER TABLE genres ADD COLUMN rank INTEGER DEFAULT 0")?;
+    b.execute("ALTER TABLE genres DROP COLUMN name")?;
+    b.execute("ALTER TABLE genres ADD COLUMN name STRING DEFAULT 'none'")?;
+    assert_row(a.execute("SELECT name FROM genres WHERE id = 2")?, vec!["none".into()]);
+
+    // Rolled back schema changes invalidate them too, even if another
+    // session then reaches the same schema version.
+    a.execute("BEGIN")?;
+    a.execute("ALTER TABLE genres DROP COLUMN rank")?;
+    assert_row(a.execute("SELECT name FROM genres WHERE id = 1")?, vec!["none".into()]);
+    a.execute("ROLLBACK")?;
+    b.execute("CREATE TABLE other (id INTEGER PRIMARY KEY)")?;
+    assert_row(a.execute("SELECT name FROM genres WHERE id = 1")?, vec!["none".into()]);
+
+    // As do changes to temporary tables.
+    a.execute("CREATE TEMPORARY TABLE picks (id INTEGER PRIMARY KEY)")?;
+    a.execute("INSERT INTO picks VALUES (1), (2)")?;
+    assert_row(a.execute("SELECT * FROM picks WHERE id = 1")?, vec![Value::Integer(1)]);
+    a.execute("ALTER TABLE picks ADD COLUMN note STRING DEFAULT 'none'")?;
+    assert_row(
+        a.execute("SELECT * FROM picks WHERE id = 2")?,
+        vec![Value::Integer(2), "none".into()],
+    );
+
+    // The cache can be disabled.
+    a.execute("SET plan_cache_size = 0")?;
+    assert_row(a.execute("SELECT title FROM movies WHERE id = 2")?, vec!["Sicario".into()]);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_catalog_cache() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut a = tc.connect(1)?;
+    let mut b = tc.connect(1)?;
+    let mut c = tc.connect(2)?;
+    a.execute("SET plan_cache_size = 0")?;
+    let genre = |id: i64, name: &str| vec![Value::Integer(id), name.into()];
+
+    // Schema changes in other sessions, including on other nodes, are seen by
+    // subsequent statements.
+    assert_row(a.execute("SELECT * FROM genres WHERE id = 1")?, genre(1, "Science Fiction"));
+    b.execute("ALTER TABLE genres ADD COLUMN rank INTEGER DEFAULT 0")?;
+    let mut row = genre(1, "Science Fiction");
+    row.push(Value::Integer(0));
+    assert_row(a.execute("SELECT * FROM genres WHERE id = 1")?, row.clone());
+    c.execute("ALTER TABLE genres DROP COLUMN rank")?;
+    assert_row(a.execute("SELECT * FROM genres WHERE id = 1")?, genre(1, "Science Fiction"));
+
+    // Transactions keep using the schema of their snapshot.
+    a.execute("BEGIN")?;
+    assert_row(a.execute("SELECT * FROM genres WHERE id = 1")?, genre(1, "Science Fiction"));
+    b.execute("ALTER TABLE genres ADD COLUMN rank INTEGER DEFAULT 0")?;
+    assert_row(a.execute("SELECT * FROM genres WHERE id = 1")?, genre(1, "Science Fiction"));
+    a.execute("COMMIT")?;
+    assert_row(a.execute("SELECT * FROM genres WHERE id = 1")?, row.clone());
+
+    // Rolled back schema changes aren't cached, even if another session then
+    // reaches the same schema version.
+    a.execute("BEGIN")?;
+    a.execute("ALTER TABLE genres DROP COLUMN rank")?;
+    assert_row(a.execute("SELECT * FROM genres WHERE id = 1")?, genre(1, "Science Fiction"));
+    a.execute("ROLLBACK")?;
+    b.execute("CREATE TABLE other (id INTEGER PRIMARY KEY)")?;
+    assert_row(a.execute("SELECT * FROM genres WHERE id = 1")?, row);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_databases() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut a = tc.connect(1)?;
+    let mut b = tc.connect(2)?;
+
+    assert_eq!(a.execute("USE app"), Err(Error::Value("Database app does not exist".into())));
+    a.execute("CREATE DATABASE app")?;
+    assert_rows(a.execute("SHOW DATABASES")?, vec![vec!["app".into()], vec!["main".into()]]);
+
+    // Tables in different databases can have the same name.
+    assert_eq!(a.execute("USE app")?, ResultSet::Use { database: "app".into() });
+    a.execute("CREATE TABLE genres (id INTEGER PRIMARY KEY, name STRING)")?;
+    a.execute("INSERT INTO genres VALUES (1, 'Horror')")?;
+    assert_rows(a.execute("SHOW TABLES")?, vec![vec!["genres".into()]]);
+    assert_row(a.execute("SELECT * FROM genres")?, vec![Value::Integer(1), "Horror".into()]);
+    assert_row(
+        b.execute("SELECT * FROM genres WHERE id = 1")?,
+        vec![Value::Integer(1), "Science Fiction".into()],
+    );
+    assert_row(b.execute("SELECT name FROM app.genres")?, vec!["Horror".into()]);
+
+    // Qualified names can refer to other databases, including the default one.
+    assert_row(
+        a.execute("SELECT genres.name, g.name FROM genres JOIN main.genres g ON g.id = genres.id")?,
+        vec!["Horror".into(), "Science Fiction".into()],
+    );
+    a.execute("UPDATE main.genres SET name = 'Sci-Fi' WHERE id = 1")?;
+    assert_row(b.execute("SELECT name FROM genres WHERE id = 1")?, vec!["Sci-Fi".into()]);
+
+    // Views resolve names in their own database.
+    a.execute("CREATE VIEW names AS SELECT name FROM genres")?;
+    assert_row(b.execute("SELECT * FROM app.names")?, vec!["Horror".into()]);
+    assert_eq!(
+        b.execute("SELECT * FROM names"),
+        Err(Error::Value("Table names does not exist".into()))
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_sequences() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut a = tc.connect(1)?;
+    let mut b = tc.connect(2)?;
+
+    a.execute("CREATE SEQUENCE seq START 10")?;
+    assert_eq!(
+        a.execute("SELECT CURRVAL('seq')"),
+        Err(Error::Value("Sequence seq has no current value in this session".into()))
+    );
+    assert_row(
+        a.execute("SELECT NEXTVAL('seq'), CURRVAL('seq')")?,
+        vec![Value::Integer(10), Value::Integer(10)],
+    );
+
+    // Each node allocates a block of values at a time, and current values are
+    // local to the session.
+    assert_row(b.execute("SELECT NEXTVAL('seq')")?, vec![Value::Integer(110)]);
+    assert_row(a.execute("SELECT NEXTVAL('seq')")?, vec![Value::Integer(11)]);
+    assert_row(b.execute("SELECT CURRVAL('seq')")?, vec![Value::Integer(110)]);
+
+    // Sequences aren't transactional.
+    a.execute("BEGIN")?;
+    assert_row(a.execute("SELECT NEXTVAL('seq')")?, vec![Value::Integer(12)]);
+    a.execute("ROLLBACK")?;
+    assert_row(a.execute("SELECT NEXTVAL('seq')")?, vec![Value::Integer(13)]);
+
+    // Column defaults can use sequences.
+    a.execute("CREATE TABLE tickets (id INTEGER PRIMARY KEY DEFAULT NEXTVAL('seq'), name STRING)")?;
+    b.execute("INSERT INTO tickets (name) VALUES ('b')")?;
+    a.execute("INSERT INTO tickets (name) VALUES ('a')")?;
+    assert_rows(
+        a.execute("SELECT * FROM tickets ORDER BY id")?,
+        vec![vec![Value::Integer(14), "a".into()], vec![Value::Integer(111), "b".into()]],
+    );
+
+    // SETVAL discards the values allocated to the node, but not to other nodes.
+    a.execute("SELECT SETVAL('seq', 1000)")?;
+    assert_row(a.execute("SELECT NEXTVAL('seq')")?, vec![Value::Integer(1001)]);
+    assert_row(b.execute("SELECT NEXTVAL('seq')")?, vec![Value::Integer(112)]);
+
+    a.execute("DROP SEQUENCE seq")?;
+    assert_eq!(
+        b.execute("SELECT NEXTVAL('seq')"),
+        Err(Error::Value("Sequence seq does not exist".into()))
+    );
+
+    // A node's block is cut short at the integer bounds, and the last value is
+    // handed out before the sequence is exhausted.
+    a.execute("CREATE SEQUENCE max START 9223372036854775806")?;
+    assert_row(a.execute("SELECT NEXTVAL('max')")?, vec![Value::Integer(i64::MAX - 1)]);
+    assert_row(a.execute("SELECT NEXTVAL('max')")?, vec![Value::Integer(i64::MAX)]);
+    assert_eq!(
+        a.execute("SELECT NEXTVAL('max')"),
+        Err(Error::Value("Sequence max is exhausted".into()))
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_parallel_scan() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect_any()?;
+    c.execute("CREATE TABLE digits (d INTEGER PRIMARY KEY)")?;
+    c.execute("INSERT INTO digits VALUES (0), (1), (2), (3), (4), (5), (6), (7), (8), (9)")?;
+    c.execute(
+        "CREATE TABLE numbers AS SELECT a.d * 1000 + b.d * 100 + c.d * 10 + e.d AS id, \
+         a.d + b.d + c.d + e.d AS sum FROM digits a, digits b, digits c, digits e",
+    )?;
+    let query = "SELECT id FROM numbers WHERE sum % 7 = 0";
+    let explain = |c: &mut toydb::Client, query: &str| -> Result<String> {
+        match c.execute(&format!("EXPLAIN {}", query))? {
+            ResultSet::Explain(plan) => Ok(plan.to_string()),
+            result => panic!("unexpected result {:?}", result),
+        }
+    };
+    let serial: Vec<Row> = c.execute(query)?.into_rows()?.collect::<Result<_>>()?;
+    assert_eq!(serial.len(), 1422);
+    assert!(!explain(&mut c, query)?.contains("ParallelScan"));
+
+    // With parallel workers, read-only statements scan in parallel, and return
+    // the same rows in primary key order.
+    c.execute("SET parallel_workers = 4")?;
+    assert_eq!(
+        explain(&mut c, query)?,
+        "Projection: id\n└─ ParallelScan: numbers (sum % 7 = 0) workers 4"
+    );
+    let parallel: Vec<Row> = c.execute(query)?.into_rows()?.collect::<Result<_>>()?;
+    assert_eq!(parallel, serial);
+    assert_row(
+        c.execute("SELECT COUNT(*), SUM(id), MAX(sum) FROM numbers")?,
+        vec![Value::Integer(10000), Value::Integer(49995000), Value::Integer(36)],
+    );
+
+    // Workers beyond the server's max_parallel_workers share its worker pool.
+    c.execute("SET parallel_workers = 64")?;
+    let parallel: Vec<Row> = c.execute(query)?.into_rows()?.collect::<Result<_>>()?;
+    assert_eq!(parallel, serial);
+
+    // Scans below a limit stop early, so they're kept serial.
+    assert!(!explain(&mut c, "SELECT id FROM numbers LIMIT 3")?.contains("ParallelScan"));
+
+    // Read-write transactions scan serially, read-only ones in parallel.
+    c.execute("BEGIN")?;
+    assert!(!explain(&mut c, query)?.contains("ParallelScan"));
+    c.execute("COMMIT")?;
+    c.execute("BEGIN READ ONLY")?;
+    assert!(explain(&mut c, query)?.contains("ParallelScan"));
+    c.execute("COMMIT")?;
+
+    // At least one worker is required.
+    assert_eq!(
+        c.execute("SET parallel_workers = 0"),
+        Err(Error::Value("Invalid value 0 for setting parallel_workers".into()))
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_work_memory() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect_any()?;
+    c.execute("CREATE TABLE digits (d INTEGER PRIMARY KEY)")?;
+    c.execute("INSERT INTO digits VALUES (0), (1), (2), (3), (4), (5), (6), (7), (8), (9)")?;
+    c.execute(
+        "CREATE TABLE numbers AS SELECT a.d * 100 + b.d * 10 + e.d AS id, \
+         a.d + b.d + e.d AS sum, 'number ' || (a.d * 10 + b.d) AS name \
+         FROM digits a, digits b, digits e",
+    )?;
+    let queries = [
+        "SELECT id, sum FROM numbers ORDER BY sum",
+        "SELECT id, name FROM numbers ORDER BY name DESC, id",
+        "SELECT n.id, d.d FROM numbers n, digits d ORDER BY n.sum, d.d DESC",
+        "SELECT id FROM numbers ORDER BY sum DESC, id LIMIT 200",
+        "SELECT sum, COUNT(*) FROM numbers GROUP BY sum ORDER BY COUNT(*), sum",
+        "SELECT id % 500 AS g, COUNT(*), SUM(sum), STRING_AGG(name, ',' ORDER BY id DESC) \
+         FROM numbers GROUP BY id % 500 ORDER BY g",
+        "SELECT sum, id % 7, MIN(id), MAX(name) FROM numbers \
+         GROUP BY ROLLUP (sum, id % 7) ORDER BY sum, id % 7",
+        "SELECT id % 300 AS g, COUNT(DISTINCT sum), SUM(DISTINCT id % 10) FROM numbers \
+         GROUP BY id % 300 ORDER BY g",
+    ];
+    let mut sorted = Vec::new();
+    for query in queries {
+        sorted.push(c.execute(query)?.into_rows()?.collect::<Result<Vec<Row>>>()?);
+    }
+
+    // With a small memory budget, sorts spill sorted runs to temporary files
+    // and merge them, returning the same rows in the same order. Sorts with
+    // many runs merge them in several passes. Ties are
+    // resolved in input order, as for an in-memory sort. Aggregations spill
+    // rows of new groups to partitions and aggregate them one at a time.
+    c.execute("SET work_memory = 16")?;
+    for (query, expect) in queries.into_iter().zip(sorted) {
+        let rows = c.execute(query)?.into_rows()?.collect::<Result<Vec<Row>>>()?;
+        assert_eq!(rows, expect, "{}", query);
+    }
+
+    // The budget must be at least 1 kilobyte.
+    assert_eq!(
+        c.execute("SET work_memory = 0"),
+        Err(Error::Value("Invalid value 0 for setting work_memory".into()))
+    );
+
+    // Statements whose sorts, hash joins or aggregations buffer more rows
+    // than the query memory limit error, rather than exhausting memory.
+    c.execute("SET work_memory = DEFAULT")?;
+    c.execute("SET query_memory = 32")?;
+    for query in [
+        "SELECT id, name FROM numbers ORDER BY name",
+        "SELECT a.id, b.id FROM numbers a JOIN numbers b ON a.name = b.name",
+        "SELECT id, COUNT(*) FROM numbers GROUP BY id",
+        "SELECT id FROM numbers WHERE name IN (SELECT name || id FROM numbers)",
+    ] {
+        assert_eq!(c.execute(query).map(|_| ()), Err(Error::OutOfMemory), "{}", query);
+    }
+
+    // Array elements count towards the limit, even when there are few rows.
+    let array = format!("ARRAY[{}]", vec!["id"; 500].join(", "));
+    let query = format!("SELECT id, {array} FROM numbers WHERE id < 20 ORDER BY sum");
+    assert_eq!(c.execute(&query).map(|_| ()), Err(Error::OutOfMemory));
+
+    // Sorts and aggregations spilling within the limit succeed.
+    c.execute("SET work_memory = 16")?;
+    let rows = c.execute(queries[1])?.into_rows()?.collect::<Result<Vec<Row>>>()?;
+    assert_eq!(rows.len(), 1000);
+
+    // 0 disables the limit.
+    c.execute("SET work_memory = DEFAULT")?;
+    c.execute("SET query_memory = 0")?;
+    let rows = c.execute(queries[1])?.into_rows()?.collect::<Result<Vec<Row>>>()?;
+    assert_eq!(rows.len(), 1000);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_cursor() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect_any()?;
+    let query = "SELECT id, title FROM movies ORDER BY id";
+    let expect = c.execute(query)?.into_rows()?.collect::<Result<Vec<Row>>>()?;
+
+    // Queries return a cursor and the columns, and rows are fetched in pages.
+    // Once exhausted, the cursor is closed.
+    let (Some(cursor), ResultSet::Query { columns, mut rows }) = c.open(query)? else {
+        panic!("expected cursor")
+    };
+    assert_eq!(
+        columns,
+        vec![Column { name: Some("id".into()) }, Column { name: Some("title".into()) }]
+    );
+    assert!(rows.next().is_none());
+    let (page, done) = c.fetch(cursor, 4)?;
+    assert_eq!((page.as_slice(), done), (&expect[..4], false));
+    let (page, done) = c.fetch(cursor, 4)?;
+    assert_eq!((page.as_slice(), done), (&expect[4..8], false));
+    let (page, done) = c.fetch(cursor, 4)?;
+    assert_eq!((page.as_slice(), done), (&expect[8..], true));
+    assert_eq!(c.fetch(cursor, 4), Err(Error::Value(format!("Cursor {cursor} does not exist"))));
+
+    // Statements without rows don't return a cursor.
+    assert_eq!(
+        c.open("INSERT INTO genres VALUES (9, 'Western')")?,
+        (None, ResultSet::Create { count: 1 })
+    );
+
+    // Cursors can be interleaved with each other and with other statements,
+    // and closed before they're exhausted.
+    let (Some(a), _) = c.open(query)? else { panic!("expected cursor") };
+    let (Some(b), _) = c.open("SELECT name FROM genres WHERE id = 9")? else {
+        panic!("expected cursor")
+    };
+    assert_ne!(a, b);
+    assert_eq!(c.fetch(a, 1)?, (expect[..1].to_vec(), false));
+    assert_eq!(c.execute("DELETE FROM genres WHERE id = 9")?, ResultSet::Delete { count: 1 });
+    assert_eq!(c.fetch(b, 10)?, (vec![vec![Value::String("Western".into())]], true));
+    assert_eq!(c.fetch(a, 1)?, (expect[1..2].to_vec(), false));
+    c.close(a)?;
+    assert_eq!(c.fetch(a, 1), Err(Error::Value(format!("Cursor {a} does not exist"))));
+    assert_eq!(c.close(a), Err(Error::Value(format!("Cursor {a} does not exist"))));
+
+    // Errors while fetching rows close the cursor.
+    let (Some(cursor), _) = c.open("SELECT 10 / (id - 3) FROM movies")? else {
+        panic!("expected cursor")
+    };
+    assert_eq!(c.fetch(cursor, 10), Err(Error::Value("Can't divide by zero".into())));
+    assert_eq!(c.fetch(cursor, 10), Err(Error::Value(format!("Cursor {cursor} does not exist"))));
+
+    // Transaction state is tracked as for execute().
+    assert!(matches!(c.open("BEGIN")?, (None, ResultSet::Begin { .. })));
+    assert!(c.txn().is_some());
+    assert!(matches!(c.open("COMMIT")?, (None, ResultSet::Commit { .. })));
+    assert_eq!(c.txn(), None);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_kill() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut a = tc.connect(1)?;
+    let mut b = tc.connect(1)?;
+    let show_queries = |c: &mut toydb::Client| -> Result<Vec<(i64, String)>> {
+        c.execute("SHOW QUERIES")?
+            .into_rows()?
+            .map(|r| match r?.as_slice() {
+                [Value::Integer(id), Value::String(query)] => Ok((*id, query.clone())),
+                row => panic!("unexpected row {row:?}"),
+            })
+            .collect()
+    };
+
+    // Queries are registered until their rows have been fetched, and can be
+    // cancelled from another session. The cursor then errors and is closed.
+    let query = "SELECT id, title FROM movies";
+    let (Some(cursor), _) = a.open(query)? else { panic!("expected cursor") };
+    assert_eq!(a.fetch(cursor, 2)?.0.len(), 2);
+    let queries = show_queries(&mut b)?;
+    assert_eq!(queries.len(), 2);
+    assert_eq!(queries[0].1, query);
+    assert_eq!(queries[1].1, "SHOW QUERIES");
+    let id = queries[0].0 as u64;
+    assert_eq!(b.execute(&format!("KILL {id}"))?, ResultSet::Kill { id });
+    assert_eq!(a.fetch(cursor, 2), Err(Error::Value("Query cancelled".into())));
+    assert_eq!(show_queries(&mut b)?.len(), 1);
+    assert_eq!(
+        b.execute(&format!("KILL {id}")),
+        Err(Error::Value(format!("Query {id} does not exist")))
+    );
+
+    // A long-running query is cancelled while executing.
+    let query =
+        "SELECT COUNT(*) FROM movies m1, movies m2, movies m3, movies m4, movies m5, movies m6";
+    let handle = std::thread::spawn(move || a.execute(query));
+    let id = loop {
+        if let Some((id, _)) = show_queries(&mut b)?.into_iter().find(|(_, q)| q == query) {
+            break id;
+        }
+        assert!(!handle.is_finished(), "query finished early");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    };
+    b.execute(&format!("KILL {id}"))?;
+    assert_eq!(handle.join().unwrap(), Err(Error::Value("Query cancelled".into())));
+
+    // The query ID must be a number.
+    assert!(b.execute("KILL 'query'").is_err());
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_create_index_online() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut a = tc.connect(1)?;
+    let mut b = tc.connect(1)?;
+    let mut c = tc.connect(1)?;
+    let explain = |c: &mut toydb::Client, query: &str| -> Result<String> {
+        match c.execute(&format!("EXPLAIN {}", query))? {
+            ResultSet::Explain(plan) => Ok(plan.to_string()),
+            result => panic!("unexpected result {:?}", result),
+        }
+    };
+
+    // Existing rows are indexed in several batches.
+    a.execute("CREATE TABLE digits (d INTEGER PRIMARY KEY)")?;
+    a.execute("INSERT INTO digits VALUES (0), (1), (2), (3), (4), (5), (6), (7), (8), (9)")?;
+    a.execute(
+        "CREATE TABLE numbers AS SELECT a.d * 1000 + b.d * 100 + c.d * 10 + e.d AS id, \
+         9999 - a.d * 1000 - b.d * 100 - c.d * 10 - e.d AS rev \
+         FROM digits a, digits b, digits c, digits e WHERE a.d < 2",
+    )?;
+    assert_eq!(
+        a.execute("CREATE INDEX numbers_rev ON numbers (rev)")?,
+        ResultSet::CreateIndex { name: "numbers_rev".into() }
+    );
+    let query = "SELECT id FROM numbers WHERE rev = 8765";
+    assert!(explain(&mut a, query)?.contains("numbers index numbers_rev"));
+    assert_row(a.execute(query)?, vec![Value::Integer(1234)]);
+
+    // The build waits for transactions that began before the index was added,
+    // since they may write rows without indexing them.
+    b.execute("BEGIN")?;
+    b.execute("INSERT INTO movies VALUES (11, 'Andrei Rublev', 1, 1, 1966, 8.1, FALSE)")?;
+    let handle =
+        std::thread::spawn(move || a.execute("CREATE INDEX movies_title ON movies (title)"));
+    while !c.get_table("movies")?.indexes.iter().any(|i| i.name == "movies_title") {
+        assert!(!handle.is_finished(), "index build finished early");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert!(c.get_table("movies")?.indexes[0].building);
+
+    // Meanwhile, queries don't use the index, but writes maintain it.
+    let query = "SELECT id FROM movies WHERE title = 'Stalker (1979)'";
+    assert!(!explain(&mut c, query)?.contains("movies_title"));
+    c.execute("UPDATE movies SET title = 'Stalker (1979)' WHERE id = 1")?;
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    assert!(!handle.is_finished(), "index build finished early");
+
+    // Once the transaction commits, its rows are indexed too.
+    b.execute("COMMIT")?;
+    assert_eq!(handle.join().unwrap()?, ResultSet::CreateIndex { name: "movies_title".into() });
+    assert!(!c.get_table("movies")?.indexes[0].building);
+    assert!(explain(&mut c, query)?.contains("movies index movies_title"));
+    assert_row(c.execute(query)?, vec![Value::Integer(1)]);
+    assert_row(
+        c.execute("SELECT id FROM movies WHERE title = 'Andrei Rublev'")?,
+        vec![Value::Integer(11)],
+    );
+    assert_rows(c.execute("SELECT id FROM movies WHERE title = 'Stalker'")?, Vec::new());
+
+    // A failed build drops the index again.
+    assert_eq!(
+        c.execute("CREATE UNIQUE INDEX movies_studio ON movies (studio_id)"),
+        Err(Error::Value("Unique value 4 already exists for index movies_studio".into()))
+    );
+    assert_eq!(c.get_table("movies")?.indexes.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_copy_from() -> Result<()> {
+    let tc = TestCluster::run(3)?;
+    let mut c = tc.connect(1)?;
+    c.execute(
+        "CREATE TABLE people (id INTEGER PRIMARY KEY, name STRING, score FLOAT, active BOOLEAN DEFAULT TRUE)",
+    )?;
+
+    // Load from a file on the server, with a header and NULL marker.
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "id,name,score,active\n1,a,1.5,false\n2,\"b, c\",NULL,true\n")?;
+    let path = file.path().to_str().unwrap();
+    assert_eq!(
+        c.execute(&format!("COPY people FROM '{path}' WITH (FORMAT CSV, HEADER, NULL 'NULL')"))?,
+        ResultSet::Copy { count: 2 }
+    );
+
+    // Stream input from the client, with a column list and delimiter. Omitted
+    // columns take their default values.
+    let input = (3..=2000).map(|i| format!("{i}|{i}.5\n")).collect::<String>();
+    assert_eq!(
+        c.copy_from("COPY people (id, score) FROM STDIN (DELIMITER '|')", input.as_bytes())?,
+        ResultSet::Copy { count: 1998 }
+    );
+    assert_rows(
+        c.execute("SELECT * FROM people WHERE id <= 3 OR id = 2000")?,
+        vec![
+            vec![Value::Integer(1), "a".into(), Value::Float(1.5), Value::Boolean(false)],
+            vec![Value::Integer(2), "b, c".into(), Value::Null, Value::Boolean(true)],
+            vec![Value::Integer(3), Value::Null, Value::Float(3.5), Value::Boolean(true)],
+            vec![Value::Integer(2000), Value::Null, Value::Float(2000.5), Value::Boolean(true)],
+        ],
+    );
+
+    // Invalid input errors and rolls back the entire load, and the session
+    // remains usable.
+    assert_eq!(
+        c.copy_from("COPY people FROM STDIN", "3000,x,1,true\n3001,y,z,true\n".as_bytes()),
+        Err(Error::Value("Line 2: Can't cast z to FLOAT".into()))
+    );
+    assert_eq!(
+        c.copy_from("COPY people FROM STDIN", "3000,x,1\n".as_bytes()),
+        Err(Error::Value("Line 1: expected 4 fields, got 3".into()))
+    );
+    assert_eq!(
+        c.execute("COPY people FROM STDIN"),
+        Err(Error::Value("COPY FROM STDIN requires client input".into()))
+    );
+    assert_row(c.execute("SELECT COUNT(*) FROM people")?, vec![Value::Integer(2000)]);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_copy_to() -> Result<()> {
+    let tc = TestCluster::run(1)?;
+    let mut c = tc.connect(1)?;
+    c.execute("CREATE TABLE people (id INTEGER PRIMARY KEY, name STRING, score FLOAT)")?;
+    c.execute("INSERT INTO people VALUES (1, 'a', 1.5), (2, 'b, \"c\"', NULL), (3, 'd', 3.0)")?;
+    let dir = tempdir::TempDir::new("toydb")?;
+
+    // Export a table as CSV, and load it into another table.
+    let path = dir.path().join("people.csv");
+    let path = path.to_str().unwrap();
+    assert_eq!(
+        c.execute(&format!("COPY people TO '{path}' WITH (HEADER, NULL 'NULL')"))?,
+        ResultSet::Copy { count: 3 }
+    );
+    assert_eq!(
+        std::fs::read_to_string(path)?,
+        "id,name,score\n1,a,1.5\n2,\"b, \"\"c\"\"\",NULL\n3,d,3\n"
+    );
+    c.execute("CREATE TABLE copied (id INTEGER PRIMARY KEY, name STRING, score FLOAT)")?;
+    assert_eq!(
+        c.execute(&format!("COPY copied FROM '{path}' WITH (HEADER, NULL 'NULL')"))?,
+        ResultSet::Copy { count: 3 }
+    );
+    assert_rows(
+        c.execute("SELECT * FROM copied")?,
+        vec![
+            vec![Value::Integer(1), "a".into(), Value::Float(1.5)],
+            vec![Value::Integer(2), "b, \"c\"".into(), Value::Null],
+            vec![Value::Integer(3), "d".into(), Value::Float(3.0)],
+        ],
+    );
+
+    // Export a query as JSON lines, and a column subset as CSV.
+    let path = dir.path().join("people.json");
+    let path = path.to_str().unwrap();
+    assert_eq!(
+        c.execute(&format!(
+            "COPY (SELECT id, name, score * 2 FROM people WHERE id > 1) TO '{path}' (FORMAT JSON)"
+        ))?,
+        ResultSet::Copy { count: 2 }
+    );
+    assert_eq!(
+        std::fs::read_to_string(path)?,
+        "{\"id\":2,\"name\":\"b, \\\"c\\\"\",\"?\":null}\n{\"id\":3,\"name\":\"d\",\"?\":6.0}\n"
+    );
+    let path = dir.path().join("names.csv");
+    let path = path.to_str().unwrap();
+    c.execute(&format!("COPY people (name, id) TO '{path}' (DELIMITER '|')"))?;
+    assert_eq!(std::fs::read_to_string(path)?, "a|1\n\"b, \"\"c\"\"\"|2\nd|3\n");
+
+    // Errors.
+    assert!(matches!(
+        c.execute(&format!("COPY people FROM '{path}' (FORMAT JSON)")),
+        Err(Error::Syntax { message, .. }) if message == "COPY FROM only supports the CSV format"
+    ));
+    assert!(c.execute("COPY missing TO '/tmp/missing.csv'").is_err());
+
+    Ok(())
+}
+
 #[test]
 #[serial]
 fn execute_txn() -> Result<()> {
@@ -316,6 +1522,213 @@ fn execute_txn() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn execute_txn_as_of_time() -> Result<()> {
+    let tc = TestCluster::run_with(1, dataset::MOVIES)?;
+    let mut c = tc.connect_any()?;
+
+    // Note the time after the dataset was loaded at version 1, then make a
+    // change at version 2.
+    let ResultSet::Query { mut rows, .. } = c.execute("SELECT NOW()")? else {
+        panic!("expected query result");
+    };
+    let Some(Ok(row)) = rows.next() else { panic!("expected row") };
+    let [Value::Timestamp(time)] = row.as_slice() else { panic!("expected timestamp") };
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    c.execute("INSERT INTO genres VALUES (4, 'Drama')")?;
+
+    // A time-travel transaction at that time shouldn't see the change.
+    assert_eq!(
+        c.execute(&format!("BEGIN READ ONLY AS OF SYSTEM TIME TIMESTAMP '{}'", time))?,
+        ResultSet::Begin { version: 2, read_only: true },
+    );
+    assert_row(c.execute("SELECT COUNT(*) FROM genres")?, vec![Value::Integer(3)]);
+    assert_eq!(c.execute("COMMIT")?, ResultSet::Commit { version: 2 });
+
+    // Nor should a single query, which can also take the time as a string.
+    let query = format!("SELECT COUNT(*) FROM genres AS OF SYSTEM TIME '{}'", time);
+    assert_row(c.execute(&query)?, vec![Value::Integer(3)]);
+    assert_eq!(c.txn(), None);
+
+    // Relative intervals are offsets from the current time. The dataset
+    // didn't exist an hour ago, and the change exists now.
+    assert_eq!(
+        c.execute("SELECT COUNT(*) FROM genres AS OF SYSTEM TIME '-1h'"),
+        Err(Error::Value("Table genres does not exist".into())),
+    );
+    assert_row(
+        c.execute("SELECT COUNT(*) FROM genres g AS OF SYSTEM TIME INTERVAL '-0 seconds'")?,
+        vec![Value::Integer(4)],
+    );
+
+    // Versions can still be given as integers.
+    assert_row(
+        c.execute("SELECT COUNT(*) FROM genres AS OF SYSTEM TIME 2")?,
+        vec![Value::Integer(3)],
+    );
+
+    // Future and invalid times error.
+    assert!(matches!(
+        c.execute("SELECT COUNT(*) FROM genres AS OF SYSTEM TIME '1h'"),
+        Err(Error::Value(message)) if message.ends_with("is in the future"),
+    ));
+    assert_eq!(
+        c.execute("SELECT COUNT(*) FROM genres AS OF SYSTEM TIME 'foo'"),
+        Err(Error::Value("Invalid AS OF SYSTEM TIME foo".into())),
+    );
+
+    // Single queries can't be time-travel queries in a transaction.
+    c.execute("BEGIN")?;
+    assert_eq!(
+        c.execute("SELECT COUNT(*) FROM genres AS OF SYSTEM TIME '-1m'"),
+        Err(Error::Value("Can't use AS OF SYSTEM TIME in a transaction".into())),
+    );
+    c.execute("ROLLBACK")?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_txn_savepoint() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect_any()?;
+
+    // Savepoints require a transaction.
+    assert_eq!(c.execute("SAVEPOINT a"), Err(Error::Value("Not in a transaction".into())));
+    assert_eq!(
+        c.execute("ROLLBACK TO SAVEPOINT a"),
+        Err(Error::Value("Not in a transaction".into()))
+    );
+
+    // Rolling back to a savepoint undoes later writes, including those of
+    // nested savepoints, which are removed.
+    assert_eq!(c.execute("BEGIN")?, ResultSet::Begin { version: 2, read_only: false });
+    c.execute("INSERT INTO genres VALUES (4, 'Drama')")?;
+    assert_eq!(c.execute("SAVEPOINT a")?, ResultSet::Savepoint { name: "a".into() });
+    c.execute("UPDATE genres SET name = 'Drama!' WHERE id = 4")?;
+    c.execute("UPDATE genres SET name = 'Sci-Fi' WHERE id = 1")?;
+    assert_eq!(c.execute("SAVEPOINT b")?, ResultSet::Savepoint { name: "b".into() });
+    c.execute("INSERT INTO genres VALUES (5, 'Horror')")?;
+    assert_eq!(
+        c.execute("ROLLBACK TO SAVEPOINT a")?,
+        ResultSet::RollbackToSavepoint { name: "a".into() }
+    );
+    assert_eq!(c.txn(), Some((2, false)));
+    assert_rows(
+        c.execute("SELECT * FROM genres")?,
+        vec![
+            vec![Value::Integer(1), Value::String("Science Fiction".into())],
+            vec![Value::Integer(2), Value::String("Action".into())],
+            vec![Value::Integer(3), Value::String("Comedy".into())],
+            vec![Value::Integer(4), Value::String("Drama".into())],
+        ],
+    );
+    assert_eq!(c.execute("ROLLBACK TO b"), Err(Error::Value("Savepoint b does not exist".into())));
+
+    // The savepoint is kept after rolling back to it. Releasing it keeps the
+    // writes made since.
+    c.execute("INSERT INTO genres VALUES (5, 'Musical')")?;
+    c.execute("ROLLBACK TO a")?;
+    c.execute("INSERT INTO genres VALUES (5, 'Western')")?;
+    assert_eq!(c.execute("RELEASE SAVEPOINT a")?, ResultSet::ReleaseSavepoint { name: "a".into() });
+    assert_eq!(c.execute("RELEASE a"), Err(Error::Value("Savepoint a does not exist".into())));
+    assert_eq!(c.execute("COMMIT")?, ResultSet::Commit { version: 2 });
+    assert_rows(
+        c.execute("SELECT * FROM genres WHERE id > 3")?,
+        vec![
+            vec![Value::Integer(4), Value::String("Drama".into())],
+            vec![Value::Integer(5), Value::String("Western".into())],
+        ],
+    );
+
+    // Savepoints don't outlive their transaction.
+    c.execute("BEGIN")?;
+    assert_eq!(c.execute("ROLLBACK TO a"), Err(Error::Value("Savepoint a does not exist".into())));
+    c.execute("ROLLBACK")?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn execute_txn_deferred() -> Result<()> {
+    let tc = TestCluster::run(3)?;
+    let mut c = tc.connect_any()?;
+
+    // Set up mutually referencing tables with deferred constraints.
+    c.execute(
+        "CREATE TABLE people (id INTEGER PRIMARY KEY, name STRING UNIQUE INITIALLY DEFERRED)",
+    )?;
+    c.execute(
+        "CREATE TABLE teams (
+            id INTEGER PRIMARY KEY,
+            lead_id INTEGER NOT NULL REFERENCES people DEFERRABLE INITIALLY DEFERRED
+        )",
+    )?;
+    c.execute(
+        "ALTER TABLE people ADD COLUMN team_id INTEGER DEFAULT NULL \
+         REFERENCES teams DEFERRABLE INITIALLY DEFERRED",
+    )?;
+
+    // Mutually referencing rows can be inserted in a single transaction.
+    c.execute("BEGIN")?;
+    c.execute("INSERT INTO teams VALUES (1, 1)")?;
+    c.execute("INSERT INTO people VALUES (1, 'Alice', 1), (2, 'Bob', 1)")?;
+    c.execute("COMMIT")?;
+    assert_rows(
+        c.execute("SELECT * FROM people")?,
+        vec![
+            vec![Value::Integer(1), Value::String("Alice".into()), Value::Integer(1)],
+            vec![Value::Integer(2), Value::String("Bob".into()), Value::Integer(1)],
+        ],
+    );
+
+    // Implicit transactions check constraints at the end of the statement.
+    assert_eq!(
+        c.execute("INSERT INTO teams VALUES (2, 3)"),
+        Err(Error::Value("Referenced primary key 3 in table people does not exist".into()))
+    );
+
+    // Violations are reported at commit, which rolls back the transaction.
+    c.execute("BEGIN")?;
+    c.execute("INSERT INTO teams VALUES (2, 3)")?;
+    c.execute("INSERT INTO people VALUES (4, 'Carol', 2)")?;
+    assert_eq!(
+        c.execute("COMMIT"),
+        Err(Error::Value("Referenced primary key 3 in table people does not exist".into()))
+    );
+    assert_rows(c.execute("SELECT * FROM teams WHERE id = 2")?, Vec::new());
+    assert_rows(c.execute("SELECT * FROM people WHERE id = 4")?, Vec::new());
+
+    // Unique values can be swapped within a transaction, but not duplicated.
+    c.execute("BEGIN")?;
+    c.execute("UPDATE people SET name = 'Bob' WHERE id = 1")?;
+    c.execute("UPDATE people SET name = 'Alice' WHERE id = 2")?;
+    c.execute("COMMIT")?;
+    assert_rows(
+        c.execute("SELECT id, name FROM people")?,
+        vec![
+            vec![Value::Integer(1), Value::String("Bob".into())],
+            vec![Value::Integer(2), Value::String("Alice".into())],
+        ],
+    );
+
+    c.execute("BEGIN")?;
+    c.execute("UPDATE people SET name = 'Bob' WHERE id = 2")?;
+    assert_eq!(
+        c.execute("COMMIT"),
+        Err(Error::Value("Unique value Bob already exists for column name".into()))
+    );
+    assert_row(
+        c.execute("SELECT name FROM people WHERE id = 2")?,
+        vec![Value::String("Alice".into())],
+    );
+
+    Ok(())
+}
+
 #[test]
 #[serial]
 fn execute_txn_concurrent() -> Result<()> {
@@ -352,3 +1765,78 @@ fn execute_txn_concurrent() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[serial]
+fn execute_txn_schema_change() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut a = tc.connect_any()?;
+    let mut b = tc.connect_any()?;
+    let mut c = tc.connect_any()?;
+
+    // Transactions that began before a compatible schema change can still
+    // write with the previous schema version. Their rows are read with the
+    // current one.
+    a.execute("BEGIN")?;
+    a.execute("INSERT INTO genres VALUES (4, 'Drama')")?;
+    c.execute("ALTER TABLE genres ADD COLUMN popular BOOLEAN DEFAULT FALSE")?;
+    a.execute("INSERT INTO genres VALUES (5, 'Comedy')")?;
+    a.execute("COMMIT")?;
+    assert_rows(
+        c.execute("SELECT * FROM genres WHERE id >= 4")?,
+        vec![
+            vec![Value::Integer(4), Value::String("Drama".into()), Value::Boolean(false)],
+            vec![Value::Integer(5), Value::String("Comedy".into()), Value::Boolean(false)],
+        ],
+    );
+
+    // This includes dropping a column, which is removed from such rows when
+    // they're read, and from storage by the next schema change.
+    a.execute("BEGIN")?;
+    c.execute("ALTER TABLE genres DROP COLUMN popular")?;
+    a.execute("INSERT INTO genres VALUES (6, 'Horror', TRUE)")?;
+    a.execute("COMMIT")?;
+    assert_row(
+        c.execute("SELECT * FROM genres WHERE id = 6")?,
+        vec![Value::Integer(6), Value::String("Horror".into())],
+    );
+    c.execute("ALTER TABLE genres ADD COLUMN rating INTEGER DEFAULT 0")?;
+    assert_row(
+        c.execute("SELECT * FROM genres WHERE id = 6")?,
+        vec![Value::Integer(6), Value::String("Horror".into()), Value::Integer(0)],
+    );
+
+    // A schema change fails if such rows are committed concurrently with it,
+    // since it can't see them.
+    a.execute("BEGIN")?;
+    c.execute("ALTER TABLE genres ADD COLUMN color STRING DEFAULT 'red'")?;
+    b.execute("BEGIN")?;
+    b.execute("ALTER TABLE genres DROP COLUMN rating")?;
+    a.execute("INSERT INTO genres VALUES (7, 'Western', 1)")?;
+    a.execute("COMMIT")?;
+    assert_eq!(b.execute("COMMIT"), Err(Error::Serialization));
+    c.execute("ALTER TABLE genres DROP COLUMN rating")?;
+    assert_row(
+        c.execute("SELECT * FROM genres WHERE id = 7")?,
+        vec![Value::Integer(7), Value::String("Western".into()), Value::String("red".into())],
+    );
+
+    // Writes with the previous version fail after an incompatible change, e.g.
+    // a new index which they don't maintain.
+    a.execute("BEGIN")?;
+    b.execute("BEGIN")?;
+    b.execute("CREATE INDEX genres_color ON genres (color)")?;
+    b.execute("COMMIT")?;
+    a.execute("INSERT INTO genres VALUES (8, 'Thriller', 'blue')")?;
+    assert_eq!(a.execute("COMMIT"), Err(Error::Serialization));
+
+    // As do writes more than one version behind.
+    a.execute("BEGIN")?;
+    a.execute("INSERT INTO genres VALUES (8, 'Thriller', 'blue')")?;
+    c.execute("ALTER TABLE genres ADD COLUMN x INTEGER DEFAULT 1")?;
+    c.execute("ALTER TABLE genres ADD COLUMN y INTEGER DEFAULT 2")?;
+    assert_eq!(a.execute("COMMIT"), Err(Error::Serialization));
+    assert_rows(c.execute("SELECT * FROM genres WHERE id = 8")?, Vec::new());
+
+    Ok(())
+}
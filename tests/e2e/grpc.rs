@@ -0,0 +1,187 @@
+use super::{dataset, TestCluster};
+
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use toydb::server::proto;
+use toydb::server::proto::admin_request::{ListTables, Request};
+use toydb::server::proto::value::Value as V;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Builds an ExecuteRequest with the given parameters.
+fn request(query: &str, params: Vec<Option<V>>) -> proto::ExecuteRequest {
+    let params = params.into_iter().map(|value| proto::Value { value }).collect();
+    proto::ExecuteRequest { query: query.to_string(), params }
+}
+
+/// Builds a protobuf row.
+fn row(values: Vec<Option<V>>) -> proto::Row {
+    proto::Row { values: values.into_iter().map(|value| proto::Value { value }).collect() }
+}
+
+#[test]
+#[serial]
+fn grpc_execute() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let mut c = tc.connect_grpc(1).await?;
+
+        // Queries return columns and rows.
+        let response = c
+            .execute(request("SELECT id, title, rating, ultrahd FROM movies WHERE id < 3", vec![]))
+            .await?
+            .into_inner();
+        assert_eq!(response.columns, vec!["id", "title", "rating", "ultrahd"]);
+        assert_eq!(
+            response.rows,
+            vec![
+                row(vec![
+                    Some(V::Integer(1)),
+                    Some(V::String("Stalker".into())),
+                    Some(V::Float(8.2)),
+                    None
+                ]),
+                row(vec![
+                    Some(V::Integer(2)),
+                    Some(V::String("Sicario".into())),
+                    Some(V::Float(7.6)),
+                    Some(V::Boolean(true))
+                ]),
+            ]
+        );
+
+        // Parameters are substituted as literals, and other statements
+        // return their result as JSON.
+        let response = c
+            .execute(request(
+                "INSERT INTO genres VALUES ($1, $2), ($3, $4)",
+                vec![
+                    Some(V::Integer(4)),
+                    Some(V::String("It's $1".into())),
+                    Some(V::Integer(-5)),
+                    Some(V::String("Drama".into())),
+                ],
+            ))
+            .await?
+            .into_inner();
+        assert_eq!(response.result, r#"{"Create":{"count":2}}"#);
+        assert_eq!(response.rows, vec![]);
+
+        let response = c
+            .execute(request(
+                "SELECT id, name, $2 FROM genres WHERE id = $1 OR id < 0 ORDER BY id",
+                vec![Some(V::Integer(4)), None],
+            ))
+            .await?
+            .into_inner();
+        assert_eq!(response.columns, vec!["id", "name", ""]);
+        assert_eq!(
+            response.rows,
+            vec![
+                row(vec![Some(V::Integer(-5)), Some(V::String("Drama".into())), None]),
+                row(vec![Some(V::Integer(4)), Some(V::String("It's $1".into())), None]),
+            ]
+        );
+
+        // Other types are returned as text.
+        let response =
+            c.execute(request("SELECT DATE '2024-01-01', ARRAY[1, 2]", vec![])).await?.into_inner();
+        assert_eq!(
+            response.rows,
+            vec![row(vec![
+                Some(V::Text("2024-01-01".into())),
+                Some(V::Array(proto::Array {
+                    values: vec![
+                        proto::Value { value: Some(V::Integer(1)) },
+                        proto::Value { value: Some(V::Integer(2)) },
+                    ]
+                })),
+            ])]
+        );
+
+        // Errors return a status code and message.
+        let err = c.execute(request("SELECT * FROM missing", vec![])).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert_eq!(err.message(), "Table missing does not exist");
+
+        Ok(())
+    })
+}
+
+#[test]
+#[serial]
+fn grpc_stream() -> Result<()> {
+    let tc = TestCluster::run_with(1, dataset::TEST_TABLE)?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let mut c = tc.connect_grpc(1).await?;
+
+        let values: Vec<_> = (1..=250).map(|i| format!("({i}, 'value')")).collect();
+        c.execute(request(&format!("INSERT INTO test VALUES {}", values.join(", ")), vec![]))
+            .await?;
+
+        // Rows are streamed in batches of 100, with the columns first.
+        let mut stream = c.stream(request("SELECT id FROM test", vec![])).await?.into_inner();
+        let mut batches = Vec::new();
+        while let Some(response) = stream.message().await? {
+            if batches.is_empty() {
+                assert_eq!(response.columns, vec!["id"]);
+            } else {
+                assert_eq!(response.columns, Vec::<String>::new());
+            }
+            batches.push(response.rows);
+        }
+        assert_eq!(batches.iter().map(|b| b.len()).collect::<Vec<_>>(), vec![100, 100, 50]);
+        assert_eq!(batches[2][49], row(vec![Some(V::Integer(250))]));
+
+        // Empty results and other statements return a single response.
+        let mut stream =
+            c.stream(request("SELECT id FROM test WHERE id < 0", vec![])).await?.into_inner();
+        let response = stream.message().await?.unwrap();
+        assert_eq!((response.columns, response.rows), (vec!["id".to_string()], vec![]));
+        assert_eq!(stream.message().await?, None);
+
+        let mut stream = c.stream(request("DELETE FROM test", vec![])).await?.into_inner();
+        assert_eq!(stream.message().await?.unwrap().result, r#"{"Delete":{"count":250}}"#);
+        assert_eq!(stream.message().await?, None);
+
+        // Errors are returned in the stream.
+        let mut stream = c.stream(request("SELECT 1 / 0", vec![])).await?.into_inner();
+        let err = stream.message().await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        Ok(())
+    })
+}
+
+#[test]
+#[serial]
+fn grpc_status_admin() -> Result<()> {
+    let tc = TestCluster::run_with(1, dataset::TEST_TABLE)?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let mut c = tc.connect_grpc(1).await?;
+
+        let status = c.status(proto::StatusRequest {}).await?.into_inner();
+        assert_eq!(status.server, 1);
+        let raft = status.raft.unwrap();
+        assert_eq!((raft.leader, raft.term), (1, 1));
+        assert_eq!(raft.last_index, [(1, raft.commit_index)].into());
+        assert_eq!(status.mvcc.unwrap().active_txns, 0);
+
+        let admin = |request| proto::AdminRequest { request: Some(request) };
+        let response = c.admin(admin(Request::ListTables(ListTables {}))).await?.into_inner();
+        assert_eq!(response.names, vec!["test"]);
+        let response = c.admin(admin(Request::GetTable("test".into()))).await?.into_inner();
+        assert_eq!(
+            response.definition,
+            "CREATE TABLE test (\n  id INTEGER PRIMARY KEY,\n  value STRING DEFAULT NULL\n)"
+        );
+        let err = c.admin(admin(Request::GetView("missing".into()))).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert_eq!(err.message(), "View missing does not exist");
+
+        Ok(())
+    })
+}
@@ -0,0 +1,98 @@
+use super::{dataset, TestCluster};
+
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use serial_test::serial;
+use toydb::error::Result;
+
+const JSON: &str = "application/json";
+const TEXT: &str = "text/plain";
+
+#[test]
+#[serial]
+fn http_query() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+
+    // Queries return columns and rows.
+    assert_eq!(
+        tc.http(1, "POST", "/query", Some((TEXT, "SELECT * FROM genres ORDER BY id")))?,
+        (
+            200,
+            json!({
+                "columns": ["id", "name"],
+                "rows": [[1, "Science Fiction"], [2, "Action"], [3, "Comedy"]],
+            })
+        )
+    );
+    assert_eq!(
+        tc.http(2, "POST", "/query", Some((TEXT, "SELECT TRUE, 1.5, NULL, 'a' || 'b' AS s")))?,
+        (200, json!({"columns": [null, null, null, "s"], "rows": [[true, 1.5, null, "ab"]]}))
+    );
+
+    // Parameters are substituted as literals.
+    let body = json!({
+        "query": "INSERT INTO genres VALUES ($1, $2), ($3, $4)",
+        "params": [4, "It's $1", -5, "Drama"],
+    });
+    assert_eq!(
+        tc.http(3, "POST", "/query", Some((JSON, &body.to_string())))?,
+        (200, json!({"Create": {"count": 2}}))
+    );
+    let body = json!({
+        "query": "SELECT * FROM genres WHERE id = $1 OR id < $2 OR name = $3",
+        "params": [4, 0, null],
+    });
+    assert_eq!(
+        tc.http(4, "POST", "/query", Some((JSON, &body.to_string())))?,
+        (200, json!({"columns": ["id", "name"], "rows": [[-5, "Drama"], [4, "It's $1"]]}))
+    );
+
+    // Decimals are returned as strings, to preserve their precision, and can
+    // be given back as DECIMAL parameters.
+    let decimal = "1234567890123456789.123456789";
+    let body = json!({"query": "SELECT DECIMAL $1", "params": [decimal]});
+    assert_eq!(
+        tc.http(1, "POST", "/query", Some((JSON, &body.to_string())))?,
+        (200, json!({"columns": [null], "rows": [[decimal]]}))
+    );
+
+    // Errors return a status code and message.
+    assert_eq!(
+        tc.http(5, "POST", "/query", Some((TEXT, "SELECT * FROM missing")))?,
+        (400, json!({"error": "Table missing does not exist"}))
+    );
+    let body = json!({"query": "SELECT $1, $2", "params": [1]});
+    assert_eq!(
+        tc.http(5, "POST", "/query", Some((JSON, &body.to_string())))?,
+        (400, json!({"error": "Missing value for parameter $2"}))
+    );
+    assert_eq!(tc.http(5, "POST", "/query", Some((JSON, "{")))?.0, 400);
+    assert_eq!(
+        tc.http(5, "GET", "/query", None)?,
+        (405, json!({"error": "Method not allowed, use POST"}))
+    );
+    assert_eq!(
+        tc.http(5, "GET", "/missing", None)?,
+        (404, json!({"error": "Path /missing not found"}))
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn http_status() -> Result<()> {
+    let tc = TestCluster::run(1)?;
+
+    assert_eq!(tc.http(1, "GET", "/healthz", None)?, (200, json!("ok")));
+
+    let (status, body) = tc.http(1, "GET", "/status", None)?;
+    assert_eq!(status, 200);
+    assert_eq!(body["server"], json!(1));
+    assert_eq!(body["raft"]["leader"], json!(1));
+    assert_eq!(body["raft"]["term"], json!(1));
+    assert_eq!(body["raft"]["last_index"], json!({"1": 1}));
+    assert_eq!(body["mvcc"]["active_txns"], json!(0));
+
+    Ok(())
+}
@@ -3,11 +3,16 @@
 //!
 //! TODO: these tests should be rewritten as data-driven golden master tests.
 
+mod auth;
 mod client;
 pub mod dataset;
+mod grpc;
+mod http;
 mod isolation;
+mod postgres;
 mod recovery;
 mod testcluster;
+mod tls;
 
 use testcluster::TestCluster;
 
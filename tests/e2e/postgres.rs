@@ -0,0 +1,119 @@
+use super::{dataset, TestCluster};
+
+use ::postgres::error::SqlState;
+use ::postgres::types::Type;
+use ::postgres::SimpleQueryMessage;
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Returns the column names and text rows of a simple query's result.
+fn result(messages: Vec<SimpleQueryMessage>) -> (Vec<String>, Vec<Vec<Option<String>>>) {
+    let (mut columns, mut rows) = (Vec::new(), Vec::new());
+    for message in messages {
+        if let SimpleQueryMessage::Row(row) = message {
+            columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+            rows.push((0..row.len()).map(|i| row.get(i).map(String::from)).collect());
+        }
+    }
+    (columns, rows)
+}
+
+/// Converts string slices into text rows.
+fn text(rows: &[&[Option<&str>]]) -> Vec<Vec<Option<String>>> {
+    rows.iter().map(|row| row.iter().map(|v| v.map(String::from)).collect()).collect()
+}
+
+#[test]
+#[serial]
+fn postgres_simple_query() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect_pg(1)?;
+
+    // Queries return text rows.
+    let (columns, rows) = result(c.simple_query("SELECT * FROM genres ORDER BY id")?);
+    assert_eq!(columns, vec!["id", "name"]);
+    assert_eq!(
+        rows,
+        text(&[
+            &[Some("1"), Some("Science Fiction")],
+            &[Some("2"), Some("Action")],
+            &[Some("3"), Some("Comedy")]
+        ])
+    );
+
+    let (_, rows) = result(c.simple_query("SELECT TRUE, 1.5, NULL, 'a' || 'b'")?);
+    assert_eq!(rows, text(&[&[Some("t"), Some("1.5"), None, Some("ab")]]));
+
+    // Statements return command tags, and can be combined.
+    let messages = c.simple_query(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, value STRING); \
+         INSERT INTO test VALUES (1, 'a'), (2, 'b'); \
+         DELETE FROM test",
+    )?;
+    let counts: Vec<_> = messages
+        .iter()
+        .filter_map(|m| match m {
+            SimpleQueryMessage::CommandComplete(count) => Some(*count),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(counts, vec![0, 2, 2]);
+
+    // Errors return an SQLSTATE and message.
+    let err = c.simple_query("SELECT * FROM missing").unwrap_err();
+    let err = err.as_db_error().unwrap();
+    assert_eq!(err.code(), &SqlState::DATA_EXCEPTION);
+    assert_eq!(err.message(), "Table missing does not exist");
+
+    let err = c.simple_query("SELEC 1").unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::SYNTAX_ERROR));
+
+    // Transactions span statements, and are visible to other connections once
+    // committed.
+    let mut other = tc.connect_pg(2)?;
+    c.batch_execute("BEGIN; INSERT INTO test VALUES (1, 'Western')")?;
+    assert_eq!(result(other.simple_query("SELECT * FROM test")?).1, text(&[]));
+    c.batch_execute("COMMIT")?;
+    assert_eq!(
+        result(other.simple_query("SELECT * FROM test")?).1,
+        text(&[&[Some("1"), Some("Western")]])
+    );
+
+    // Empty queries are allowed.
+    assert_eq!(result(c.simple_query("")?).1, text(&[]));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn postgres_extended_query() -> Result<()> {
+    let tc = TestCluster::run_with(5, dataset::MOVIES)?;
+    let mut c = tc.connect_pg(1)?;
+
+    // Parameters are bound as literals of their given type.
+    let insert =
+        c.prepare_typed("INSERT INTO genres VALUES ($1, $2)", &[Type::INT8, Type::TEXT])?;
+    assert_eq!(c.execute(&insert, &[&4i64, &"Drama"])?, 1);
+    assert_eq!(c.execute(&insert, &[&5i64, &"It's a 'Western'"])?, 1);
+    assert_eq!(c.execute(&insert, &[&6i64, &Option::<&str>::None]).is_err(), true);
+
+    let update =
+        c.prepare_typed("UPDATE genres SET name = $2 WHERE id >= $1", &[Type::INT4, Type::TEXT])?;
+    assert_eq!(c.execute(&update, &[&5i32, &"Western"])?, 1);
+
+    let (_, rows) = result(c.simple_query("SELECT * FROM genres WHERE id > 3 ORDER BY id")?);
+    assert_eq!(rows, text(&[&[Some("4"), Some("Drama")], &[Some("5"), Some("Western")]]));
+
+    // Errors are returned for the failed statement.
+    let err = c.execute("INSERT INTO genres VALUES (1, 'Western')", &[]).unwrap_err();
+    assert_eq!(
+        err.as_db_error().unwrap().message(),
+        "Primary key 1 already exists for table genres"
+    );
+    assert_eq!(c.execute("DELETE FROM genres WHERE id > 3", &[])?, 2);
+
+    Ok(())
+}
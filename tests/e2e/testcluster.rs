@@ -1,6 +1,9 @@
 use rand::Rng;
+use std::sync::Arc;
 use toydb::error::Result;
 use toydb::raft::NodeID;
+use toydb::server::proto::toy_db_client::ToyDbClient;
+use toydb::tls;
 use toydb::Client;
 
 /// Runs a toyDB cluster using the built binary in a temporary directory. The
@@ -13,6 +16,13 @@ use toydb::Client;
 /// server (and eventually the toySQL client) end-to-end.
 pub struct TestCluster {
     nodes: u8,
+    forward_requests: bool,
+    join: bool,
+    /// If Some, SQL and Raft connections use TLS, with mutual authentication
+    /// if true.
+    tls: Option<bool>,
+    /// If Some, clients must authenticate, with this root password.
+    root_password: Option<String>,
     dir: tempdir::TempDir,
     children: std::collections::HashMap<NodeID, std::process::Child>,
 }
@@ -20,11 +30,18 @@ pub struct TestCluster {
 impl TestCluster {
     const SQL_BASE_PORT: u16 = 19600;
     const RAFT_BASE_PORT: u16 = 19700;
+    const PG_BASE_PORT: u16 = 19800;
+    const HTTP_BASE_PORT: u16 = 19900;
+    const GRPC_BASE_PORT: u16 = 20000;
 
     /// Creates a new test cluster.
     pub fn new(nodes: u8) -> Result<Self> {
         Ok(Self {
             nodes,
+            forward_requests: true,
+            join: false,
+            tls: None,
+            root_password: None,
             dir: tempdir::TempDir::new("toydb")?,
             children: std::collections::HashMap::new(),
         })
@@ -37,6 +54,43 @@ impl TestCluster {
         Ok(tc)
     }
 
+    /// Creates a new test cluster where followers don't forward requests to the
+    /// leader, and starts it.
+    pub fn run_without_forwarding(nodes: u8) -> Result<Self> {
+        let mut tc = Self::new(nodes)?;
+        tc.forward_requests = false;
+        tc.start()?;
+        Ok(tc)
+    }
+
+    /// Creates a new test cluster without static peers, where node 1 is
+    /// initialized as a single-node cluster and the others join it, and starts
+    /// it.
+    pub fn run_joined(nodes: u8) -> Result<Self> {
+        let mut tc = Self::new(nodes)?;
+        tc.join = true;
+        tc.start()?;
+        Ok(tc)
+    }
+
+    /// Creates a new test cluster using TLS, optionally requiring client
+    /// certificates, and starts it.
+    pub fn run_with_tls(nodes: u8, client_auth: bool) -> Result<Self> {
+        let mut tc = Self::new(nodes)?;
+        tc.tls = Some(client_auth);
+        tc.start()?;
+        Ok(tc)
+    }
+
+    /// Creates a new test cluster requiring authentication, with the given
+    /// root password, and starts it.
+    pub fn run_with_auth(nodes: u8, root_password: &str) -> Result<Self> {
+        let mut tc = Self::new(nodes)?;
+        tc.root_password = Some(root_password.to_string());
+        tc.start()?;
+        Ok(tc)
+    }
+
     /// Creates a new test cluster, starts it, and imports an initial dataset.
     pub fn run_with(nodes: u8, init: &str) -> Result<Self> {
         let tc = Self::run(nodes)?;
@@ -84,14 +138,59 @@ impl TestCluster {
         cfg.push_str(&format!("data_dir: {}\n", self.node_path(id).to_string_lossy()));
         cfg.push_str(&format!("listen_sql: {}\n", self.node_address_sql(id)));
         cfg.push_str(&format!("listen_raft: {}\n", self.node_address_raft(id)));
+        cfg.push_str(&format!("listen_pg: {}\n", self.node_address_pg(id)));
+        cfg.push_str(&format!("listen_http: {}\n", self.node_address_http(id)));
+        cfg.push_str(&format!("listen_grpc: {}\n", self.node_address_grpc(id)));
         cfg.push_str("peers: {\n");
-        for peer in self.ids().filter(|p| p != &id) {
+        for peer in self.ids().filter(|p| p != &id && !self.join) {
             cfg.push_str(&format!("  '{}': {},\n", peer, self.node_address_raft(peer)))
         }
         cfg.push_str("}\n");
+        cfg.push_str("peers_sql: {\n");
+        for peer in self.ids().filter(|p| p != &id) {
+            cfg.push_str(&format!("  '{}': {},\n", peer, self.node_address_sql(peer)))
+        }
+        cfg.push_str("}\n");
+        cfg.push_str(&format!("forward_requests: {}\n", self.forward_requests));
+        if let Some(client_auth) = self.tls {
+            cfg.push_str(&format!("tls_cert: {}\n", self.tls_path("node.crt").display()));
+            cfg.push_str(&format!("tls_key: {}\n", self.tls_path("node.key").display()));
+            cfg.push_str(&format!("tls_ca: {}\n", self.tls_path("ca.crt").display()));
+            cfg.push_str(&format!("tls_client_auth: {client_auth}\n"));
+        }
+        if let Some(root_password) = &self.root_password {
+            cfg.push_str("auth: true\n");
+            cfg.push_str(&format!("root_password: '{root_password}'\n"));
+        }
         cfg
     }
 
+    /// Returns the path to the given TLS certificate or key file.
+    pub fn tls_path(&self, name: &str) -> std::path::PathBuf {
+        self.dir.path().join("tls").join(name)
+    }
+
+    /// Generates a CA certificate and key, and a node certificate and key for
+    /// localhost signed by it, which is used by all nodes and clients.
+    fn generate_tls(&self) -> Result<()> {
+        let ca_key = rcgen::KeyPair::generate().expect("Failed to generate CA key");
+        let mut ca_params = rcgen::CertificateParams::new(Vec::new()).unwrap();
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).expect("Failed to generate CA certificate");
+
+        let key = rcgen::KeyPair::generate().expect("Failed to generate node key");
+        let cert = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .signed_by(&key, &ca, &ca_key)
+            .expect("Failed to generate node certificate");
+
+        std::fs::create_dir_all(self.tls_path(""))?;
+        std::fs::write(self.tls_path("ca.crt"), ca.pem())?;
+        std::fs::write(self.tls_path("node.crt"), cert.pem())?;
+        std::fs::write(self.tls_path("node.key"), key.serialize_pem())?;
+        Ok(())
+    }
+
     /// Returns the given node's Raft TCP address.
     fn node_address_raft(&self, id: NodeID) -> String {
         self.assert_id(id);
@@ -99,21 +198,43 @@ impl TestCluster {
     }
 
     /// Returns the given node's SQL TCP address.
-    fn node_address_sql(&self, id: NodeID) -> String {
+    pub fn node_address_sql(&self, id: NodeID) -> String {
         self.assert_id(id);
         format!("localhost:{}", Self::SQL_BASE_PORT + id as u16)
     }
 
+    /// Returns the given node's PostgreSQL TCP address.
+    fn node_address_pg(&self, id: NodeID) -> String {
+        self.assert_id(id);
+        format!("localhost:{}", Self::PG_BASE_PORT + id as u16)
+    }
+
+    /// Returns the given node's HTTP TCP address.
+    fn node_address_http(&self, id: NodeID) -> String {
+        self.assert_id(id);
+        format!("localhost:{}", Self::HTTP_BASE_PORT + id as u16)
+    }
+
+    /// Returns the given node's gRPC TCP address.
+    fn node_address_grpc(&self, id: NodeID) -> String {
+        self.assert_id(id);
+        format!("localhost:{}", Self::GRPC_BASE_PORT + id as u16)
+    }
+
     /// Starts the test cluster. It keeps running until the cluster is dropped.
     pub fn start(&mut self) -> Result<()> {
         // Build the binary.
         let build = escargot::CargoBuild::new().bin("toydb").run().expect("Failed to build binary");
 
+        if self.tls.is_some() {
+            self.generate_tls()?;
+        }
+
         // Spawn nodes.
         for id in self.ids() {
             // Create node directory and config file.
-            std::fs::create_dir_all(&self.node_path(id))?;
-            std::fs::write(&self.node_path(id).join("toydb.yaml"), self.node_config(id))?;
+            std::fs::create_dir_all(self.node_path(id))?;
+            std::fs::write(self.node_path(id).join("toydb.yaml"), self.node_config(id))?;
 
             // Spawn node. Silence output by default, since there doesn't appear
             // to be a way to pass the output to the "cargo test" output capture
@@ -121,9 +242,18 @@ impl TestCluster {
             //
             // TODO: see if there's a way to send this to "cargo test" and have
             // it capture it like println!.
+            let mut args = vec![
+                "-c".to_string(),
+                self.node_path(id).join("toydb.yaml").to_string_lossy().to_string(),
+            ];
+            match id {
+                1 if self.join => args.push("init".into()),
+                _ if self.join => args.extend(["join".into(), self.node_address_sql(1)]),
+                _ => {}
+            }
             let child = build
                 .command()
-                .args(vec!["-c", &self.node_path(id).join("toydb.yaml").to_string_lossy()])
+                .args(args)
                 .stdout(std::process::Stdio::null())
                 .stderr(std::process::Stdio::null())
                 .spawn()?;
@@ -150,18 +280,134 @@ impl TestCluster {
         Ok(())
     }
 
-    /// Connects to the given cluster node.
+    /// Connects to the given cluster node, via TLS if enabled. The client
+    /// authenticates with the node certificate, and as root if authentication
+    /// is enabled.
     pub fn connect(&self, id: NodeID) -> Result<Client> {
         self.assert_id(id);
+        let mut client = match self.tls {
+            Some(_) => self.connect_tls(id, self.tls_client(true)?)?,
+            None => Client::new(self.node_address_sql(id))?,
+        };
+        if let Some(root_password) = &self.root_password {
+            client.authenticate("root", root_password)?;
+        }
+        Ok(client)
+    }
+
+    /// Connects to the given cluster node without authenticating.
+    pub fn connect_unauthenticated(&self, id: NodeID) -> Result<Client> {
         Client::new(self.node_address_sql(id))
     }
 
+    /// Connects to the given cluster node via TLS using the given client
+    /// configuration.
+    pub fn connect_tls(&self, id: NodeID, config: Arc<rustls::ClientConfig>) -> Result<Client> {
+        Client::new_tls(&self.node_address_sql(id), config)
+    }
+
+    /// Returns a TLS client configuration trusting the cluster CA, which
+    /// authenticates with the node certificate if auth is true.
+    pub fn tls_client(&self, auth: bool) -> Result<Arc<rustls::ClientConfig>> {
+        let (cert, key) = (self.tls_path("node.crt"), self.tls_path("node.key"));
+        tls::client_config(&self.tls_path("ca.crt"), auth.then_some((&*cert, &*key)))
+    }
+
+    /// Connects to the given cluster node via the PostgreSQL wire protocol,
+    /// as root if authentication is enabled.
+    pub fn connect_pg(&self, id: NodeID) -> std::result::Result<postgres::Client, postgres::Error> {
+        match &self.root_password {
+            Some(root_password) => self.connect_pg_as(id, "root", root_password),
+            None => self.connect_pg_as(id, "toydb", ""),
+        }
+    }
+
+    /// Connects to the given cluster node via the PostgreSQL wire protocol,
+    /// as the given user.
+    pub fn connect_pg_as(
+        &self,
+        id: NodeID,
+        user: &str,
+        password: &str,
+    ) -> std::result::Result<postgres::Client, postgres::Error> {
+        let address = self.node_address_pg(id);
+        let (host, port) = address.split_once(':').unwrap();
+        postgres::Config::new()
+            .host(host)
+            .port(port.parse().unwrap())
+            .user(user)
+            .password(password)
+            .dbname("main")
+            .connect(postgres::NoTls)
+    }
+
+    /// Sends an HTTP request to the given cluster node, with a body of the
+    /// given content type if any. Returns the status code and JSON body. Uses
+    /// root credentials if authentication is enabled.
+    pub fn http(
+        &self,
+        id: NodeID,
+        method: &str,
+        path: &str,
+        body: Option<(&str, &str)>,
+    ) -> Result<(u16, serde_json::Value)> {
+        let credentials = self.root_password.as_deref().map(|password| ("root", password));
+        self.http_as(id, credentials, method, path, body)
+    }
+
+    /// Sends an HTTP request like http(), with the given Basic credentials
+    /// (user and password) if any.
+    pub fn http_as(
+        &self,
+        id: NodeID,
+        credentials: Option<(&str, &str)>,
+        method: &str,
+        path: &str,
+        body: Option<(&str, &str)>,
+    ) -> Result<(u16, serde_json::Value)> {
+        use std::io::{Read as _, Write as _};
+        let address = self.node_address_http(id);
+        let mut socket = std::net::TcpStream::connect(&address)?;
+        let mut request = format!("{method} {path} HTTP/1.1\r\nHost: {address}\r\n");
+        request.push_str("Connection: close\r\n");
+        if let Some((user, password)) = credentials {
+            request.push_str(&format!("Authorization: {}\r\n", basic_auth(user, password)));
+        }
+        if let Some((content_type, body)) = body {
+            request.push_str(&format!("Content-Type: {content_type}\r\n"));
+            request.push_str(&format!("Content-Length: {}\r\n\r\n{body}", body.len()));
+        } else {
+            request.push_str("\r\n");
+        }
+        socket.write_all(request.as_bytes())?;
+        let mut response = String::new();
+        socket.read_to_string(&mut response)?;
+        let (head, body) = response.split_once("\r\n\r\n").expect("invalid HTTP response");
+        let status = head.split(' ').nth(1).expect("missing HTTP status").parse()?;
+        let body = serde_json::from_str(body).expect("invalid JSON body");
+        Ok((status, body))
+    }
+
+    /// Connects to the given cluster node via gRPC.
+    pub async fn connect_grpc(
+        &self,
+        id: NodeID,
+    ) -> std::result::Result<ToyDbClient<tonic::transport::Channel>, tonic::transport::Error> {
+        ToyDbClient::connect(format!("http://{}", self.node_address_grpc(id))).await
+    }
+
     /// Connects to a random cluster node.
     pub fn connect_any(&self) -> Result<Client> {
         self.connect(rand::thread_rng().gen_range(1..=self.nodes))
     }
 }
 
+/// Returns an HTTP Basic Authorization header value for the given credentials.
+pub fn basic_auth(user: &str, password: &str) -> String {
+    use base64::Engine as _;
+    format!("Basic {}", base64::prelude::BASE64_STANDARD.encode(format!("{user}:{password}")))
+}
+
 impl Drop for TestCluster {
     /// Kills the child processes when the cluster is dropped. The temp dir is
     /// removed by TempDir::drop().
@@ -0,0 +1,51 @@
+use super::{assert_row, TestCluster};
+
+use serial_test::serial;
+use toydb::error::Result;
+use toydb::sql::types::Value;
+use toydb::Client;
+
+#[test]
+#[serial]
+fn tls() -> Result<()> {
+    let tc = TestCluster::run_with_tls(3, false)?;
+
+    // Writes are replicated to peers via TLS, and visible on all nodes.
+    let mut c = tc.connect_tls(1, tc.tls_client(false)?)?;
+    c.execute("CREATE TABLE test (id INTEGER PRIMARY KEY, value STRING)")?;
+    c.execute("INSERT INTO test VALUES (1, 'a')")?;
+    for id in 1..=3 {
+        let mut c = tc.connect_tls(id, tc.tls_client(false)?)?;
+        assert_row(
+            c.execute("SELECT * FROM test")?,
+            vec![Value::Integer(1), Value::String("a".into())],
+        );
+    }
+
+    // Plaintext clients can't connect.
+    let mut c = Client::new(tc.node_address_sql(1))?;
+    assert!(c.execute("SELECT 1").is_err());
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn tls_client_auth() -> Result<()> {
+    let tc = TestCluster::run_with_tls(3, true)?;
+
+    // Clients with a certificate signed by the CA can connect.
+    let mut c = tc.connect_tls(1, tc.tls_client(true)?)?;
+    c.execute("CREATE TABLE test (id INTEGER PRIMARY KEY)")?;
+    c.execute("INSERT INTO test VALUES (1)")?;
+    let mut c = tc.connect_tls(2, tc.tls_client(true)?)?;
+    assert_row(c.execute("SELECT * FROM test")?, vec![Value::Integer(1)]);
+
+    // Clients without a certificate are rejected. With TLS 1.3, the server
+    // verifies the client certificate after the client handshake completes,
+    // so the error may be returned by the first request.
+    let result = tc.connect_tls(1, tc.tls_client(false)?).and_then(|mut c| c.execute("SELECT 1"));
+    assert!(result.is_err());
+
+    Ok(())
+}
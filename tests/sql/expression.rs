@@ -1,13 +1,24 @@
 //! Evaluates SQL expressions and compares with expectations.
 use toydb::error::{Error, Result};
 use toydb::sql::engine::Engine;
+use toydb::sql::parser::Span;
 use toydb::sql::types::Value;
 
+use serde_json::json;
+
 fn eval_expr(expr: &str) -> Result<Value> {
     let engine = super::setup(Vec::new())?;
     engine.session().execute(&format!("SELECT {}", expr))?.into_value()
 }
 
+/// Returns a syntax error at the given 1-based column of the expression (on
+/// a single line), near the given text.
+fn syntax(message: &str, column: usize, near: &str) -> Result<Value> {
+    let start = "SELECT ".len() + column - 1;
+    let span = Span { start, end: start + near.len(), line: 1, column: start + 1 };
+    Err(Error::Syntax { message: message.into(), span, near: near.into() })
+}
+
 macro_rules! test_expr {
     ( $( $name:ident: $expr:expr => $expect:expr, )* ) => {
     $(
@@ -34,8 +45,8 @@ test_expr! {
     // Constants and literals
     const_case: "TrUe" => Ok(Boolean(true)),
     const_false: "FALSE" => Ok(Boolean(false)),
-    const_infinity: "INFINITY" => Ok(Float(std::f64::INFINITY)),
-    const_nan: "NAN" => Ok(Float(std::f64::NAN)),
+    const_infinity: "INFINITY" => Ok(Float(f64::INFINITY)),
+    const_nan: "NAN" => Ok(Float(f64::NAN)),
     const_null: "NULL" => Ok(Null),
     const_true: "TRUE" => Ok(Boolean(true)),
 
@@ -50,7 +61,7 @@ test_expr! {
     lit_float_min_neg: "-1.23456789012345e-307" => Ok(Float(-1.234_567_890_123_45e-307)),
     lit_float_min_round: "1.23456789012345e-323" => Ok(Float(1e-323)),
     lit_float_round_53bit: "0.12345678901234567890" => Ok(Float(0.123_456_789_012_345_68)),
-    lit_float_overflow: "1e309" => Ok(Float(std::f64::INFINITY)),
+    lit_float_overflow: "1e309" => Ok(Float(f64::INFINITY)),
     lit_float_underflow: "1e-325" => Ok(Float(0.0)),
 
     lit_integer: "3" => Ok(Integer(3)),
@@ -58,8 +69,8 @@ test_expr! {
     lit_integer_zeroprefix: "03" => Ok(Integer(3)),
     lit_integer_max: "9223372036854775807" => Ok(Integer(9_223_372_036_854_775_807)),
     lit_integer_min: "-9223372036854775807" => Ok(Integer(-9_223_372_036_854_775_807)),
-    lit_integer_overflow: "9223372036854775808" => Err(Error::Parse("number too large to fit in target type".into())),
-    lit_integer_underflow: "-9223372036854775808" => Err(Error::Parse("number too large to fit in target type".into())),
+    lit_integer_overflow: "9223372036854775808" => syntax("number too large to fit in target type", 1, "9223372036854775808"),
+    lit_integer_underflow: "-9223372036854775808" => syntax("number too large to fit in target type", 2, "9223372036854775808"),
 
     lit_string: "'Hi! 👋'" => Ok(String("Hi! 👋".into())),
     lit_string_escape: r#"'Try \n newlines and \t tabs'"# => Ok(String(r#"Try \n newlines and \t tabs"#.into())),
@@ -67,13 +78,269 @@ test_expr! {
     lit_string_whitespace: "' Has \n newlines and \t tabs  '" => Ok(String(" Has \n newlines and \t tabs  ".into())),
     lit_string_long: &format!("'{}'", "a".repeat(4096)) => Ok("a".repeat(4096).into()),
 
+    lit_decimal: "DECIMAL '3.14'" => Ok(Decimal("3.14".parse().unwrap())),
+    lit_decimal_scale: "DECIMAL '1.50'::STRING" => Ok(String("1.50".into())),
+    lit_decimal_exp: "NUMERIC '1.5e3'" => Ok(Decimal(1500.into())),
+    lit_decimal_max: "DECIMAL '79228162514264337593543950335'" => Ok(Decimal(rust_decimal::Decimal::MAX)),
+    lit_decimal_overflow: "DECIMAL '79228162514264337593543950336'" => Err(Error::Value("Can't cast 79228162514264337593543950336 to DECIMAL".into())),
+    lit_decimal_precision: "DECIMAL '0.12345678901234567890123456789'" => Err(Error::Value("Can't cast 0.12345678901234567890123456789 to DECIMAL".into())),
+    lit_decimal_invalid: "DECIMAL 'abc'" => Err(Error::Value("Can't cast abc to DECIMAL".into())),
+    lit_date: "DATE '2024-02-29'" => Ok(Date("2024-02-29".parse().unwrap())),
+    lit_date_invalid: "DATE '2023-02-29'" => Err(Error::Value("Can't cast 2023-02-29 to DATE".into())),
+    lit_date_number: "DATE 20240229" => syntax("Expected string literal, found 20240229", 6, "20240229"),
+    lit_time: "TIME '13:45:30.123456'" => Ok(Time("13:45:30.123456".parse().unwrap())),
+    lit_time_minutes: "TIME '13:45'" => Ok(Time("13:45:00".parse().unwrap())),
+    lit_time_nanos: "TIME '13:45:30.123456789'" => Ok(Time("13:45:30.123456".parse().unwrap())),
+    lit_time_invalid: "TIME '25:00'" => Err(Error::Value("Can't cast 25:00 to TIME".into())),
+    lit_timestamp: "TIMESTAMP '2024-02-29 13:45:30'" => Ok(Timestamp("2024-02-29T13:45:30".parse().unwrap())),
+    lit_timestamp_t: "TIMESTAMP '2024-02-29T13:45:30'" => Ok(Timestamp("2024-02-29T13:45:30".parse().unwrap())),
+    lit_timestamp_date: "TIMESTAMP '2024-02-29'" => Ok(Timestamp("2024-02-29T00:00:00".parse().unwrap())),
+    lit_timestamp_invalid: "TIMESTAMP '2024-02-29 13'" => Err(Error::Value("Can't cast 2024-02-29 13 to TIMESTAMP".into())),
+    lit_interval: "INTERVAL '1 year 2 months 3 days 4 hours 5 minutes 6.5 seconds'::STRING" => Ok(String("1 year 2 mons 3 days 04:05:06.5".into())),
+    lit_interval_abbrev: "INTERVAL '2 h 30 m 1 us'::STRING" => Ok(String("02:30:00.000001".into())),
+    lit_interval_attached: "INTERVAL '1day 2weeks'::STRING" => Ok(String("15 days".into())),
+    lit_interval_time: "INTERVAL '1 day -01:30'::STRING" => Ok(String("1 day -01:30:00".into())),
+    lit_interval_time_hours: "INTERVAL '36:00:00.25'::STRING" => Ok(String("36:00:00.25".into())),
+    lit_interval_fraction: "INTERVAL '1.5 months 0.5 days'::STRING" => Ok(String("1 mon 15 days 12:00:00".into())),
+    lit_interval_negative: "INTERVAL '-2 years'::STRING" => Ok(String("-2 years".into())),
+    lit_interval_ago: "INTERVAL '3 days 2 hours ago'::STRING" => Ok(String("-3 days -02:00:00".into())),
+    lit_interval_zero: "INTERVAL '0 days'::STRING" => Ok(String("00:00:00".into())),
+    lit_interval_display: "INTERVAL '1 year 2 mons -3 days 04:05:06.000007'::STRING" => Ok(String("1 year 2 mons -3 days 04:05:06.000007".into())),
+    lit_interval_cast: "'1 hour'::INTERVAL = INTERVAL '60 minutes'" => Ok(Boolean(true)),
+    lit_interval_invalid: "INTERVAL '1 fortnight'" => Err(Error::Value("Can't cast 1 fortnight to INTERVAL".into())),
+    lit_interval_invalid_time: "INTERVAL '01:60'" => Err(Error::Value("Can't cast 01:60 to INTERVAL".into())),
+    lit_interval_no_unit: "INTERVAL '1'" => Err(Error::Value("Can't cast 1 to INTERVAL".into())),
+    lit_interval_overflow: "INTERVAL '3000000000 days'" => Err(Error::Value("Can't cast 3000000000 days to INTERVAL".into())),
+    lit_interval_number: "INTERVAL 1" => syntax("Expected string literal, found 1", 10, "1"),
+    lit_bytes: "x'DEADbeef'" => Ok(Bytes(vec![0xde, 0xad, 0xbe, 0xef])),
+    lit_bytes_upper: "X'00ff'" => Ok(Bytes(vec![0x00, 0xff])),
+    lit_bytes_empty: "x''" => Ok(Bytes(vec![])),
+    lit_bytes_odd: "x'abc'" => syntax("Invalid hex string abc", 1, "x'abc'"),
+    lit_bytes_invalid: "x'zz'" => syntax("Invalid hex string zz", 1, "x'zz'"),
+    lit_bytes_ident: "x" => Err(Error::Value("Unknown field x".into())),
+    lit_bytea: r"BYTEA '\xDEADbeef'" => Ok(Bytes(vec![0xde, 0xad, 0xbe, 0xef])),
+    lit_bytea_raw: "BYTEA 'abc'" => Ok(Bytes(b"abc".to_vec())),
+    lit_bytea_invalid: r"BYTEA '\xzz'" => Err(Error::Value(r"Can't cast \xzz to BYTEA".into())),
+    lit_json: r#"JSON '{"b": [1, 2.5, null], "a": "x"}'"# => Ok(Json(json!({"a": "x", "b": [1, 2.5, null]}))),
+    lit_json_scalar: "JSON '3'" => Ok(Json(json!(3))),
+    lit_json_canonical: r#"JSON ' { "b": 1, "a": true } '::STRING"# => Ok(String(r#"{"a":true,"b":1}"#.into())),
+    lit_json_invalid: "JSON '{a: 1}'" => Err(Error::Value("Can't cast {a: 1} to JSON".into())),
+    lit_uuid: "UUID 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11'" => Ok(Uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".parse().unwrap())),
+    lit_uuid_simple: "UUID 'a0eebc999c0b4ef8bb6d6bb9bd380a11'::STRING" => Ok(String("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".into())),
+    lit_uuid_braced: "UUID '{a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11}'::STRING" => Ok(String("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".into())),
+    lit_uuid_invalid: "UUID 'a0eebc99-9c0b'" => Err(Error::Value("Can't cast a0eebc99-9c0b to UUID".into())),
+
     // Functions
     func_unknown: "unknown()" => Err(Error::Value("Unknown function unknown".into())),
     func_unknown_case: "UnKnown ( )" => Err(Error::Value("Unknown function unknown".into())),
     func_unknown_space: "unknown ( )" => Err(Error::Value("Unknown function unknown".into())),
     func_unknown_args: "unknown(a, b, c)" => Err(Error::Value("Unknown function unknown".into())),
-    func_unknown_open: "unknown(a, b, c" => Err(Error::Parse("Unexpected end of input".into())),
-    func_unknown_trailing_comma: "unknown(a, b, c,)" => Err(Error::Parse("Expected expression atom, found )".into())),
+    func_unknown_open: "unknown(a, b, c" => syntax("Unexpected end of input", 16, ""),
+    func_unknown_trailing_comma: "unknown(a, b, c,)" => syntax("Expected expression atom, found )", 17, ")"),
+    func_arity: "upper('a', 'b')" => Err(Error::Value("UPPER takes 1 arguments, got 2".into())),
+    func_arity_range: "substring('a')" => Err(Error::Value("SUBSTRING takes 2 to 3 arguments, got 1".into())),
+    func_arity_min: "concat()" => Err(Error::Value("CONCAT takes at least 1 arguments, got 0".into())),
+    func_type: "upper(1)" => Err(Error::Value("UPPER expected string argument, got 1".into())),
+    func_null: "upper(NULL)" => Ok(Null),
+
+    // Array functions
+    func_cardinality: "cardinality(ARRAY[1, NULL])" => Ok(Integer(2)),
+    func_cardinality_empty: "cardinality(ARRAY[])" => Ok(Integer(0)),
+    func_cardinality_null: "cardinality(NULL)" => Ok(Null),
+    func_cardinality_string: "cardinality('a')" => Err(Error::Value("CARDINALITY expected array argument, got a".into())),
+
+    // String functions
+    func_concat: "concat('a', 1, NULL, TRUE)" => Ok(String("a1TRUE".into())),
+    func_concat_null: "concat(NULL)" => Ok(String("".into())),
+    func_left: "left('abcde', 2)" => Ok(String("ab".into())),
+    func_left_negative: "left('abcde', -2)" => Ok(String("abc".into())),
+    func_left_overflow: "left('abc', 5)" => Ok(String("abc".into())),
+    func_length: "length('abc')" => Ok(Integer(3)),
+    func_length_unicode: "length('åäö')" => Ok(Integer(3)),
+    func_length_empty: "length('')" => Ok(Integer(0)),
+    func_length_bytes: "length(x'00ff00')" => Ok(Integer(3)),
+    func_lower: "lower('AbC')" => Ok(String("abc".into())),
+    func_ltrim: "ltrim('  abc  ')" => Ok(String("abc  ".into())),
+    func_ltrim_chars: "ltrim('xyabc', 'yx')" => Ok(String("abc".into())),
+    func_octet_length: "octet_length('åäö')" => Ok(Integer(6)),
+    func_octet_length_bytes: "octet_length(x'00ff')" => Ok(Integer(2)),
+    func_octet_length_integer: "octet_length(1)" => Err(Error::Value("OCTET_LENGTH expected string argument, got 1".into())),
+    func_position: "position('c' IN 'abcd')" => Ok(Integer(3)),
+    func_position_args: "position('c', 'abcd')" => Ok(Integer(3)),
+    func_position_missing: "position('x' IN 'abcd')" => Ok(Integer(0)),
+    func_position_null: "position(NULL IN 'abcd')" => Ok(Null),
+    func_replace: "replace('abcabc', 'b', 'xy')" => Ok(String("axycaxyc".into())),
+    func_replace_empty: "replace('abc', '', 'x')" => Ok(String("abc".into())),
+    func_right: "right('abcde', 2)" => Ok(String("de".into())),
+    func_right_negative: "right('abcde', -2)" => Ok(String("cde".into())),
+    func_rtrim: "rtrim('  abc  ')" => Ok(String("  abc".into())),
+    func_substring: "substring('abcde', 2, 3)" => Ok(String("bcd".into())),
+    func_substring_from: "substring('abcde' FROM 2)" => Ok(String("bcde".into())),
+    func_substring_from_for: "substring('abcde' FROM 2 FOR 3)" => Ok(String("bcd".into())),
+    func_substring_before: "substring('abcde', 0, 3)" => Ok(String("ab".into())),
+    func_substring_after: "substring('abcde', 10)" => Ok(String("".into())),
+    func_substring_negative: "substring('abcde', 1, -1)" => Err(Error::Value("substring length can't be negative".into())),
+    func_substring_unicode: "substring('åäö', 2, 1)" => Ok(String("ä".into())),
+    func_trim: "trim('  abc  ')" => Ok(String("abc".into())),
+    func_trim_chars: "trim('xxabcxx', 'x')" => Ok(String("abc".into())),
+    func_upper: "UPPER('aBc')" => Ok(String("ABC".into())),
+
+    // Date and time functions
+    func_now: "NOW() > TIMESTAMP '2024-01-01'" => Ok(Boolean(true)),
+    func_current_timestamp: "CURRENT_TIMESTAMP > TIMESTAMP '2024-01-01'" => Ok(Boolean(true)),
+    func_extract_year: "EXTRACT(year FROM TIMESTAMP '2024-02-29 13:45:30.5')" => Ok(Integer(2024)),
+    func_extract_quarter: "EXTRACT(quarter FROM DATE '2024-05-01')" => Ok(Integer(2)),
+    func_extract_month: "EXTRACT(MONTH FROM DATE '2024-02-29')" => Ok(Integer(2)),
+    func_extract_week: "EXTRACT(week FROM DATE '2024-01-01')" => Ok(Integer(1)),
+    func_extract_day: "EXTRACT(day FROM DATE '2024-02-29')" => Ok(Integer(29)),
+    func_extract_dow: "EXTRACT(dow FROM DATE '2024-02-25')" => Ok(Integer(0)),
+    func_extract_doy: "EXTRACT(doy FROM DATE '2024-02-29')" => Ok(Integer(60)),
+    func_extract_hour: "EXTRACT(hour FROM TIMESTAMP '2024-02-29 13:45:30.5')" => Ok(Integer(13)),
+    func_extract_hour_date: "EXTRACT(hour FROM DATE '2024-02-29')" => Ok(Integer(0)),
+    func_extract_minute: "EXTRACT(minute FROM TIME '13:45:30.5')" => Ok(Integer(45)),
+    func_extract_second: "EXTRACT(second FROM TIME '13:45:30.5')" => Ok(Integer(30)),
+    func_extract_microsecond: "EXTRACT(microsecond FROM TIME '13:45:30.5')" => Ok(Integer(30500000)),
+    func_extract_epoch: "EXTRACT(epoch FROM TIMESTAMP '1970-01-02 00:00:01.5')" => Ok(Float(86401.5)),
+    func_extract_epoch_time: "EXTRACT(epoch FROM TIME '00:01:01')" => Ok(Float(61.0)),
+    func_extract_args: "EXTRACT('year', DATE '2024-02-29')" => Ok(Integer(2024)),
+    func_extract_time_year: "EXTRACT(year FROM TIME '13:45')" => Err(Error::Value("Can't extract year from TIME".into())),
+    func_extract_unknown: "EXTRACT(foo FROM DATE '2024-02-29')" => Err(Error::Value("Unknown date/time field foo".into())),
+    func_extract_string: "EXTRACT(year FROM '2024-02-29')" => Err(Error::Value("Can't extract year from 2024-02-29".into())),
+    func_extract_null: "EXTRACT(year FROM NULL)" => Ok(Null),
+    func_extract_interval_year: "EXTRACT(year FROM INTERVAL '14 months')" => Ok(Integer(1)),
+    func_extract_interval_month: "EXTRACT(month FROM INTERVAL '14 months')" => Ok(Integer(2)),
+    func_extract_interval_day: "EXTRACT(day FROM INTERVAL '1 month 40 days')" => Ok(Integer(40)),
+    func_extract_interval_hour: "EXTRACT(hour FROM INTERVAL '1 day 25:30')" => Ok(Integer(25)),
+    func_extract_interval_minute: "EXTRACT(minute FROM INTERVAL '25:30')" => Ok(Integer(30)),
+    func_extract_interval_second: "EXTRACT(second FROM INTERVAL '90.5 seconds')" => Ok(Integer(30)),
+    func_extract_interval_microsecond: "EXTRACT(microsecond FROM INTERVAL '90.5 seconds')" => Ok(Integer(30500000)),
+    func_extract_interval_epoch: "EXTRACT(epoch FROM INTERVAL '1 month 1.5 seconds')" => Ok(Float(2592001.5)),
+    func_extract_interval_week: "EXTRACT(week FROM INTERVAL '1 day')" => Err(Error::Value("Can't extract week from INTERVAL".into())),
+    func_date_trunc_year: "DATE_TRUNC('year', TIMESTAMP '2024-05-17 13:45:30')" => Ok(Timestamp("2024-01-01T00:00:00".parse().unwrap())),
+    func_date_trunc_quarter: "DATE_TRUNC('quarter', DATE '2024-05-17')" => Ok(Date("2024-04-01".parse().unwrap())),
+    func_date_trunc_month: "DATE_TRUNC('month', DATE '2024-05-17')" => Ok(Date("2024-05-01".parse().unwrap())),
+    func_date_trunc_week: "DATE_TRUNC('week', DATE '2024-05-17')" => Ok(Date("2024-05-13".parse().unwrap())),
+    func_date_trunc_day: "DATE_TRUNC('day', TIMESTAMP '2024-05-17 13:45:30')" => Ok(Timestamp("2024-05-17T00:00:00".parse().unwrap())),
+    func_date_trunc_hour: "DATE_TRUNC('hour', TIMESTAMP '2024-05-17 13:45:30')" => Ok(Timestamp("2024-05-17T13:00:00".parse().unwrap())),
+    func_date_trunc_minute: "DATE_TRUNC('minute', TIME '13:45:30')" => Ok(Time("13:45:00".parse().unwrap())),
+    func_date_trunc_second: "DATE_TRUNC('second', TIME '13:45:30.123')" => Ok(Time("13:45:30".parse().unwrap())),
+    func_date_trunc_time_day: "DATE_TRUNC('day', TIME '13:45:30')" => Err(Error::Value("Can't truncate TIME to day".into())),
+    func_date_trunc_unknown: "DATE_TRUNC('foo', DATE '2024-05-17')" => Err(Error::Value("Unknown date/time field foo".into())),
+    func_gen_random_uuid_version: "SUBSTRING(gen_random_uuid()::STRING, 15, 1)" => Ok(String("4".into())),
+    func_gen_random_uuid_unique: "gen_random_uuid() = gen_random_uuid()" => Ok(Boolean(false)),
+    func_gen_random_uuid_args: "gen_random_uuid(1)" => Err(Error::Value("GEN_RANDOM_UUID takes 0 arguments, got 1".into())),
+    func_json_extract: r#"json_extract(JSON '{"a": {"b": [1, {"c": "x"}]}}', '$.a.b[1].c')"# => Ok(Json(json!("x"))),
+    func_json_extract_root: r#"json_extract(JSON '{"a": 1}', '$')"# => Ok(Json(json!({"a": 1}))),
+    func_json_extract_negative: "json_extract(JSON '[1, 2, 3]', '$[-1]')" => Ok(Json(json!(3))),
+    func_json_extract_missing: r#"json_extract(JSON '{"a": 1}', '$.b')"# => Ok(Null),
+    func_json_extract_null: r#"json_extract(JSON '{"a": null}', '$.a')"# => Ok(Json(json!(null))),
+    func_json_extract_string: r#"json_extract('{"a": 1}', '$.a')"# => Ok(Json(json!(1))),
+    func_json_extract_string_invalid: "json_extract('{', '$.a')" => Err(Error::Value("Invalid JSON {".into())),
+    func_json_extract_path_invalid: r#"json_extract(JSON '{"a": 1}', 'a')"# => Err(Error::Value("Invalid JSON path a".into())),
+    func_json_extract_integer: "json_extract(1, '$')" => Err(Error::Value("Expected JSON, got 1".into())),
+    func_json_set: r#"json_set(JSON '{"a": 1}', '$.a', 2)"# => Ok(Json(json!({"a": 2}))),
+    func_json_set_insert: r#"json_set(JSON '{"a": 1}', '$.b', 'x')"# => Ok(Json(json!({"a": 1, "b": "x"}))),
+    func_json_set_nested: r#"json_set(JSON '{"a": [1, 2]}', '$.a[0]', JSON '{"b": true}')"# => Ok(Json(json!({"a": [{"b": true}, 2]}))),
+    func_json_set_append: "json_set(JSON '[1, 2]', '$[2]', 3.5)" => Ok(Json(json!([1, 2, 3.5]))),
+    func_json_set_out_of_bounds: "json_set(JSON '[1, 2]', '$[5]', 3)" => Ok(Json(json!([1, 2]))),
+    func_json_set_missing_parent: r#"json_set(JSON '{"a": 1}', '$.b.c', 2)"# => Ok(Json(json!({"a": 1}))),
+    func_json_set_null_value: r#"json_set(JSON '{"a": 1}', '$.a', NULL)"# => Ok(Json(json!({"a": null}))),
+    func_json_set_null_document: "json_set(NULL, '$.a', 1)" => Ok(Null),
+    func_json_set_decimal: "json_set(JSON '{}', '$.a', DECIMAL '1.50')" => Ok(Json(json!({"a": "1.50"}))),
+    func_json_set_date: "json_set(JSON '{}', '$.a', DATE '2024-02-29')" => Ok(Json(json!({"a": "2024-02-29"}))),
+    func_json_set_root: "json_set(JSON '{}', '$', 1)" => Ok(Json(json!(1))),
+    func_json_set_nan: "json_set(JSON '{}', '$.a', NAN)" => Err(Error::Value("Can't convert NaN to JSON".into())),
+
+    // Conditional functions
+    func_coalesce: "coalesce(NULL, 2, 3)" => Ok(Integer(2)),
+    func_coalesce_single: "coalesce(1)" => Ok(Integer(1)),
+    func_coalesce_null: "coalesce(NULL, NULL)" => Ok(Null),
+    func_coalesce_unify: "coalesce(1, 2.5)" => Ok(Float(1.0)),
+    func_coalesce_unify_decimal: "coalesce(NULL, 1, DECIMAL '2.5')" => Ok(Decimal(1.into())),
+    func_coalesce_string: "coalesce(NULL, 'a', 'b')" => Ok(String("a".into())),
+    func_coalesce_mismatch: "coalesce(1, 'a')" => Err(Error::Value("COALESCE types INTEGER and STRING cannot be matched".into())),
+    func_coalesce_arity: "coalesce()" => Err(Error::Value("COALESCE takes at least 1 arguments, got 0".into())),
+    func_nullif: "nullif(1, 1)" => Ok(Null),
+    func_nullif_unequal: "nullif(1, 2)" => Ok(Integer(1)),
+    func_nullif_unify: "nullif(1, 1.0)" => Ok(Null),
+    func_nullif_unify_unequal: "nullif(1, 1.5)" => Ok(Integer(1)),
+    func_nullif_null: "nullif(NULL, 1)" => Ok(Null),
+    func_nullif_null_rhs: "nullif(1, NULL)" => Ok(Integer(1)),
+    func_nullif_mismatch: "nullif('a', TRUE)" => Err(Error::Value("NULLIF types STRING and BOOLEAN cannot be matched".into())),
+    func_nullif_arity: "nullif(1)" => Err(Error::Value("NULLIF takes 2 arguments, got 1".into())),
+    func_greatest: "greatest(1, 3, 2)" => Ok(Integer(3)),
+    func_greatest_null: "greatest(NULL, 1, NULL)" => Ok(Integer(1)),
+    func_greatest_all_null: "greatest(NULL, NULL)" => Ok(Null),
+    func_greatest_unify: "greatest(3, 2.5)" => Ok(Float(3.0)),
+    func_greatest_string: "greatest('a', 'c', 'b')" => Ok(String("c".into())),
+    func_greatest_date: "greatest(DATE '2024-01-01', DATE '2023-01-01')" => Ok(Date("2024-01-01".parse().unwrap())),
+    func_greatest_mismatch: "greatest(1, DATE '2024-01-01')" => Err(Error::Value("GREATEST types INTEGER and DATE cannot be matched".into())),
+    func_greatest_json: "greatest(JSON '1', JSON '2')" => Ok(Null),
+    func_least: "least(2, 1, 3)" => Ok(Integer(1)),
+    func_least_null: "least(NULL, 2, 1)" => Ok(Integer(1)),
+    func_least_unify: "least(DECIMAL '1.5', 2)" => Ok(Decimal("1.5".parse().unwrap())),
+    func_least_nan: "least(1.0, NAN)" => Ok(Null),
+
+    // Mathematical functions
+    func_abs: "abs(-3)" => Ok(Integer(3)),
+    func_abs_float: "abs(-3.5)" => Ok(Float(3.5)),
+    func_abs_decimal: "abs(DECIMAL '-1.50')" => Ok(Decimal("1.50".parse().unwrap())),
+    func_abs_null: "abs(NULL)" => Ok(Null),
+    func_abs_overflow: "abs(-9223372036854775807 - 1)" => Err(Error::Value("Integer overflow".into())),
+    func_abs_string: "abs('a')" => Err(Error::Value("ABS expected numeric argument, got a".into())),
+    func_ceil: "ceil(2.1)" => Ok(Float(3.0)),
+    func_ceil_negative: "ceil(-2.9)" => Ok(Float(-2.0)),
+    func_ceil_int: "ceil(2)" => Ok(Integer(2)),
+    func_ceil_decimal: "ceil(DECIMAL '2.1')" => Ok(Decimal(3.into())),
+    func_ceil_infinity: "ceil(INFINITY)" => Ok(Float(f64::INFINITY)),
+    func_floor: "floor(2.9)" => Ok(Float(2.0)),
+    func_floor_negative: "floor(-2.1)" => Ok(Float(-3.0)),
+    func_floor_int: "floor(-2)" => Ok(Integer(-2)),
+    func_floor_decimal: "floor(DECIMAL '-2.1')" => Ok(Decimal((-3).into())),
+    func_floor_bool: "floor(TRUE)" => Err(Error::Value("FLOOR expected numeric argument, got TRUE".into())),
+    func_mod: "mod(7, 3)" => Ok(Integer(1)),
+    func_mod_negative: "mod(-7, 3)" => Ok(Integer(-1)),
+    func_mod_float: "mod(7.5, 2)" => Ok(Float(1.5)),
+    func_mod_decimal: "mod(DECIMAL '7.5', 2)" => Ok(Decimal("1.5".parse().unwrap())),
+    func_mod_zero: "mod(7, 0)" => Err(Error::Value("Can't divide by zero".into())),
+    func_mod_null: "mod(NULL, 2)" => Ok(Null),
+    func_mod_overflow: "mod(-9223372036854775807 - 1, -1)" => Err(Error::Value("Integer overflow".into())),
+    func_power: "power(2, 10)" => Ok(Integer(1024)),
+    func_power_negative: "power(2, -1)" => Ok(Float(0.5)),
+    func_power_float: "power(2.0, 0.5)" => Ok(Float(2_f64.sqrt())),
+    func_power_overflow: "power(2, 64)" => Err(Error::Value("Integer overflow".into())),
+    func_power_string: "power('a', 2)" => Err(Error::Value("Can't exponentiate a and 2".into())),
+    func_power_arity: "power(2)" => Err(Error::Value("POWER takes 2 arguments, got 1".into())),
+    func_random_range: "random() >= 0.0 AND random() < 1.0" => Ok(Boolean(true)),
+    func_random_unique: "random() = random()" => Ok(Boolean(false)),
+    func_random_args: "random(1)" => Err(Error::Value("RANDOM takes 0 arguments, got 1".into())),
+    func_round: "round(2.5)" => Ok(Float(3.0)),
+    func_round_negative: "round(-2.5)" => Ok(Float(-3.0)),
+    func_round_digits: "round(1.23456, 2)" => Ok(Float(1.23)),
+    func_round_digits_negative: "round(1234.5, -2)" => Ok(Float(1200.0)),
+    func_round_int: "round(17)" => Ok(Integer(17)),
+    func_round_int_digits: "round(17, -1)" => Ok(Integer(20)),
+    func_round_int_half: "round(-15, -1)" => Ok(Integer(-20)),
+    func_round_int_large: "round(17, -30)" => Ok(Integer(0)),
+    func_round_int_overflow: "round(9223372036854775807, -1)" => Err(Error::Value("Integer overflow".into())),
+    func_round_decimal: "round(DECIMAL '2.345', 2)" => Ok(Decimal("2.35".parse().unwrap())),
+    func_round_decimal_negative: "round(DECIMAL '-1250', -2)" => Ok(Decimal((-1300).into())),
+    func_round_decimal_large: "round(DECIMAL '1250', -40)" => Ok(Decimal(0.into())),
+    func_round_float_precise: "round(1e300, 20)" => Ok(Float(1e300)),
+    func_round_nan: "round(NAN)" => Ok(Float(f64::NAN)),
+    func_round_digits_float: "round(2.5, 1.0)" => Err(Error::Value("ROUND expected integer argument, got 1".into())),
+    func_round_null_digits: "round(2.5, NULL)" => Ok(Null),
+    func_sign: "sign(-5)" => Ok(Integer(-1)),
+    func_sign_zero: "sign(0)" => Ok(Integer(0)),
+    func_sign_float: "sign(2.5)" => Ok(Float(1.0)),
+    func_sign_float_zero: "sign(0.0)" => Ok(Float(0.0)),
+    func_sign_decimal: "sign(DECIMAL '-0.5')" => Ok(Decimal((-1).into())),
+    func_sign_decimal_zero: "sign(DECIMAL '0')" => Ok(Decimal(0.into())),
+    func_sqrt: "sqrt(16)" => Ok(Float(4.0)),
+    func_sqrt_float: "sqrt(2.25)" => Ok(Float(1.5)),
+    func_sqrt_decimal: "sqrt(DECIMAL '6.25')" => Ok(Float(2.5)),
+    func_sqrt_negative: "sqrt(-1)" => Err(Error::Value("Can't take square root of negative number".into())),
+    func_sqrt_null: "sqrt(NULL)" => Ok(Null),
 
     // Logical operators
     op_and_true_true: "TRUE AND TRUE" => Ok(Boolean(true)),
@@ -130,7 +397,34 @@ test_expr! {
     op_eq_string_case: "'abc' = 'ABC'" => Ok(Boolean(false)),
     op_eq_string_unicode: "'😀' = '😀'" => Ok(Boolean(true)),
     op_eq_string_unicode_not: "'😀' = '🙁'" => Ok(Boolean(false)),
-    op_eq_conflict: "1 = 'a'" => Err(Error::Value("Can't compare 1 and a".into())),
+    op_eq_decimal: "DECIMAL '1.50' = DECIMAL '1.5'" => Ok(Boolean(true)),
+    op_eq_decimal_not: "DECIMAL '1.5' = DECIMAL '1.51'" => Ok(Boolean(false)),
+    op_eq_decimal_int: "DECIMAL '2.0' = 2" => Ok(Boolean(true)),
+    op_eq_int_decimal: "2 = DECIMAL '2.01'" => Ok(Boolean(false)),
+    op_eq_decimal_float: "DECIMAL '0.5' = 0.5" => Ok(Boolean(true)),
+    op_eq_date: "DATE '2024-02-29' = DATE '2024-02-29'" => Ok(Boolean(true)),
+    op_eq_date_not: "DATE '2024-02-29' = DATE '2024-03-01'" => Ok(Boolean(false)),
+    op_eq_date_timestamp: "DATE '2024-02-29' = TIMESTAMP '2024-02-29 00:00:00'" => Ok(Boolean(true)),
+    op_eq_date_string: "DATE '2024-02-29' = '2024-02-29'" => Ok(Boolean(true)),
+    op_eq_time: "TIME '13:45' = TIME '13:45:00'" => Ok(Boolean(true)),
+    op_eq_timestamp: "TIMESTAMP '2024-02-29 13:45' = TIMESTAMP '2024-02-29 13:45:00.000001'" => Ok(Boolean(false)),
+    op_eq_bytes: "x'00ff' = x'00FF'" => Ok(Boolean(true)),
+    op_eq_bytes_not: "x'00ff' = x'00fe'" => Ok(Boolean(false)),
+    op_eq_bytes_string: "x'61' = 'a'" => Ok(Boolean(true)),
+    op_eq_json: r#"JSON '{"a": 1, "b": [true]}' = JSON '{ "b": [true], "a": 1 }'"# => Ok(Boolean(true)),
+    op_eq_json_not: r#"JSON '{"a": 1}' = JSON '{"a": 2}'"# => Ok(Boolean(false)),
+    op_eq_json_string: r#"JSON '"a"' = 'a'"# => Err(Error::Value("Can't cast a to JSON".into())),
+    op_eq_uuid: "UUID 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11' = UUID 'A0EEBC999C0B4EF8BB6D6BB9BD380A11'" => Ok(Boolean(true)),
+    op_eq_uuid_not: "UUID 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11' = UUID 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a12'" => Ok(Boolean(false)),
+    op_eq_uuid_string: "UUID 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11' = 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'" => Ok(Boolean(true)),
+    op_eq_conflict: "1 = 'a'" => Err(Error::Value("Can't cast a to INTEGER".into())),
+    op_eq_int_string: "1 = '1'" => Ok(Boolean(true)),
+    op_eq_string_int: "' 1 ' = 1" => Ok(Boolean(true)),
+    op_eq_float_string: "1.5 = '1.50'" => Ok(Boolean(true)),
+    op_eq_decimal_string: "DECIMAL '1.5' = '1.50'" => Ok(Boolean(true)),
+    op_eq_bool_string: "TRUE = 'yes'" => Ok(Boolean(true)),
+    op_eq_int_string_float: "1 = '1.5'" => Err(Error::Value("Can't cast 1.5 to INTEGER".into())),
+    op_eq_null_string: "NULL = '1'" => Ok(Null),
 
     op_neq_bool: "TRUE != FALSE" => Ok(Boolean(true)),
     op_neq_bool_not: "TRUE != TRUE" => Ok(Boolean(false)),
@@ -152,9 +446,21 @@ test_expr! {
     op_neq_string_case: "'abc' != 'ABC'" => Ok(Boolean(true)),
     op_neq_string_unicode: "'😀' != '🙁'" => Ok(Boolean(true)),
     op_neq_string_unicode_not: "'😀' != '😀'" => Ok(Boolean(false)),
-    op_neq_conflict: "1 != 'a'" => Err(Error::Value("Can't compare 1 and a".into())),
+    op_neq_conflict: "1 != 'a'" => Err(Error::Value("Can't cast a to INTEGER".into())),
 
     op_gt_bool: "TRUE > FALSE" => Ok(Boolean(true)),
+    op_gt_decimal: "DECIMAL '0.3' > DECIMAL '0.29'" => Ok(Boolean(true)),
+    op_gt_decimal_negative: "DECIMAL '-0.3' > DECIMAL '-0.29'" => Ok(Boolean(false)),
+    op_gt_decimal_int: "DECIMAL '2.01' > 2" => Ok(Boolean(true)),
+    op_gt_date: "DATE '2024-03-01' > DATE '2024-02-29'" => Ok(Boolean(true)),
+    op_gt_date_timestamp: "TIMESTAMP '2024-02-29 00:00:01' > DATE '2024-02-29'" => Ok(Boolean(true)),
+    op_gt_time: "TIME '13:45' > TIME '09:30'" => Ok(Boolean(true)),
+    op_gt_time_date: "TIME '13:45' > DATE '2024-02-29'" => Err(Error::Value("Can't compare 13:45:00 and 2024-02-29".into())),
+    op_gt_bytes: "x'0100' > x'00ff'" => Ok(Boolean(true)),
+    op_gt_bytes_prefix: "x'00ff' > x'00'" => Ok(Boolean(true)),
+    op_gt_json: "JSON '2' > JSON '1'" => Err(Error::Value("Can't compare 2 and 1".into())),
+    op_gt_uuid: "UUID 'b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11' > UUID 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a12'" => Ok(Boolean(true)),
+    op_gt_timestamp: "TIMESTAMP '1969-07-20 20:17' > TIMESTAMP '1970-01-01'" => Ok(Boolean(false)),
     op_gt_bool_eq: "TRUE > TRUE" => Ok(Boolean(false)),
     op_gt_bool_not: "FALSE > TRUE" => Ok(Boolean(false)),
     op_gt_float: "3.14 > 3.13" => Ok(Boolean(true)),
@@ -185,7 +491,7 @@ test_expr! {
     op_gt_string_unicode: "'🙁' > '😀'" => Ok(Boolean(true)),
     op_gt_string_unicode_eq: "'😀' > '😀'" => Ok(Boolean(false)),
     op_gt_string_unicode_not: "'😀' > '🙁'" => Ok(Boolean(false)),
-    op_gt_conflict: "1 > 'a'" => Err(Error::Value("Can't compare 1 and a".into())),
+    op_gt_conflict: "1 > 'a'" => Err(Error::Value("Can't cast a to INTEGER".into())),
 
     op_gte_bool: "TRUE >= TRUE" => Ok(Boolean(true)),
     op_gte_bool_gt: "TRUE >= FALSE" => Ok(Boolean(true)),
@@ -217,7 +523,7 @@ test_expr! {
     op_gte_string_unicode: "'😀' >= '😀'" => Ok(Boolean(true)),
     op_gte_string_unicode_gt: "'🙁' >= '😀'" => Ok(Boolean(true)),
     op_gte_string_unicode_not: "'😀' >= '🙁'" => Ok(Boolean(false)),
-    op_gte_conflict: "1 >= 'a'" => Err(Error::Value("Can't compare 1 and a".into())),
+    op_gte_conflict: "1 >= 'a'" => Err(Error::Value("Can't cast a to INTEGER".into())),
 
     op_lt_bool: "FALSE < TRUE" => Ok(Boolean(true)),
     op_lt_bool_eq: "TRUE < TRUE" => Ok(Boolean(false)),
@@ -250,7 +556,10 @@ test_expr! {
     op_lt_string_unicode: "'😀' < '🙁'" => Ok(Boolean(true)),
     op_lt_string_unicode_eq: "'😀' < '😀'" => Ok(Boolean(false)),
     op_lt_string_unicode_not: "'🙁' < '😀'" => Ok(Boolean(false)),
-    op_lt_conflict: "1 < 'a'" => Err(Error::Value("Can't compare 1 and a".into())),
+    op_lt_bytes: "x'00ff' < x'0100'" => Ok(Boolean(true)),
+    op_lt_bytes_eq: "x'00ff' < x'00ff'" => Ok(Boolean(false)),
+    op_lt_uuid: "UUID '00000000-0000-0000-0000-000000000001' < UUID 'ffffffff-0000-0000-0000-000000000000'" => Ok(Boolean(true)),
+    op_lt_conflict: "1 < 'a'" => Err(Error::Value("Can't cast a to INTEGER".into())),
 
     op_lte_bool: "TRUE <= TRUE" => Ok(Boolean(true)),
     op_lte_bool_lt: "FALSE <= TRUE" => Ok(Boolean(true)),
@@ -282,15 +591,21 @@ test_expr! {
     op_lte_string_unicode: "'😀' <= '😀'" => Ok(Boolean(true)),
     op_lte_string_unicode_lt: "'😀' <= '🙁'" => Ok(Boolean(true)),
     op_lte_string_unicode_not: "'🙁' <= '😀'" => Ok(Boolean(false)),
-    op_lte_conflict: "1 <= 'a'" => Err(Error::Value("Can't compare 1 and a".into())),
+    op_lte_conflict: "1 <= 'a'" => Err(Error::Value("Can't cast a to INTEGER".into())),
 
     op_null: "NULL IS NULL" => Ok(Boolean(true)),
     op_null_not: "NULL IS NOT NULL" => Ok(Boolean(false)),
     op_null_bool: "TRUE IS NULL" => Ok(Boolean(false)),
     op_null_bool_not: "TRUE IS NOT NULL" => Ok(Boolean(true)),
-    op_null_rhs_bool: "NULL IS TRUE" => Err(Error::Parse("Expected token NULL, found TRUE".into())),
+    op_null_rhs_bool: "NULL IS TRUE" => syntax("Expected token NULL, found TRUE", 9, "TRUE"),
 
     // Math operators
+    op_add_decimal_decimal: "DECIMAL '0.1' + DECIMAL '0.2'" => Ok(Decimal("0.3".parse().unwrap())),
+    op_add_decimal_int: "DECIMAL '0.10' + 1" => Ok(Decimal("1.10".parse().unwrap())),
+    op_add_int_decimal: "1 + DECIMAL '0.1'" => Ok(Decimal("1.1".parse().unwrap())),
+    op_add_decimal_float: "DECIMAL '0.5' + 0.25" => Ok(Float(0.75)),
+    op_add_decimal_null: "DECIMAL '0.1' + NULL" => Ok(Null),
+    op_add_overflow_decimal: "DECIMAL '79228162514264337593543950335' + 1" => Err(Error::Value("Decimal overflow".into())),
     op_add_float_float: "3.1 + 2.71" => Ok(Float(3.1 + 2.71)),
     op_add_float_int: "3.72 + 1" => Ok(Float(3.72 + 1.0)),
     op_add_float_null: "3.14 + NULL" => Ok(Null),
@@ -301,46 +616,57 @@ test_expr! {
     op_add_null_int: "NULL + 1" => Ok(Null),
     op_add_null_null: "NULL + NULL" => Ok(Null),
     op_add_negative: "1 + -3" => Ok(Integer(-2)),
-    op_add_infinity: "1 + INFINITY" => Ok(Float(std::f64::INFINITY)),
-    op_add_nan: "1 + NAN" => Ok(Float(std::f64::NAN)),
+    op_add_infinity: "1 + INFINITY" => Ok(Float(f64::INFINITY)),
+    op_add_nan: "1 + NAN" => Ok(Float(f64::NAN)),
     op_add_overflow_int: "9223372036854775807 + 1" => Err(Error::Value("Integer overflow".into())),
     op_add_underflow_int: "-9223372036854775807 + -2" => Err(Error::Value("Integer overflow".into())),
-    op_add_overflow_float: "2e308 + 2e308" => Ok(Float(std::f64::INFINITY)),
+    op_add_overflow_float: "2e308 + 2e308" => Ok(Float(f64::INFINITY)),
     op_add_round_int_float: "9223372036854775807 + 10.0" => Ok(Float(9_223_372_036_854_776_000.0)),
     op_add_error_bool: "TRUE + FALSE" => Err(Error::Value("Can't add TRUE and FALSE".into())),
     op_add_error_strings: "'a' + 'b'" => Err(Error::Value("Can't add a and b".into())),
+    op_add_int_string: "1 + '2'" => Ok(Integer(3)),
+    op_add_string_float: "'2' + 0.5" => Ok(Float(2.5)),
+    op_add_error_int_string: "1 + 'a'" => Err(Error::Value("Can't cast a to INTEGER".into())),
 
+    op_assert_decimal: "+DECIMAL '1.5'" => Ok(Decimal("1.5".parse().unwrap())),
     op_assert_float: "+3.72" => Ok(Float(3.72)),
     op_assert_int: "+1" => Ok(Integer(1)),
     op_assert_null: "+NULL" => Ok(Null),
-    op_assert_infinity: "+INFINITY" => Ok(Float(std::f64::INFINITY)),
-    op_assert_nan: "+NAN" => Ok(Float(std::f64::NAN)),
+    op_assert_infinity: "+INFINITY" => Ok(Float(f64::INFINITY)),
+    op_assert_nan: "+NAN" => Ok(Float(f64::NAN)),
     op_assert_multi: "+++1" => Ok(Integer(1)),
     op_assert_error_bool: "+TRUE" => Err(Error::Value("Can't take the positive of TRUE".into())),
     op_assert_error_string: "+'abc'" => Err(Error::Value("Can't take the positive of abc".into())),
 
+    op_divide_decimal_decimal: "DECIMAL '1' / DECIMAL '3'" => Ok(Decimal("0.3333333333333333333333333333".parse().unwrap())),
+    op_divide_decimal_int: "DECIMAL '7.5' / 2" => Ok(Decimal("3.75".parse().unwrap())),
+    op_divide_int_decimal: "1 / DECIMAL '4'" => Ok(Decimal("0.25".parse().unwrap())),
+    op_divide_decimal_zero: "DECIMAL '1' / DECIMAL '0.00'" => Err(Error::Value("Can't divide by zero".into())),
+    op_divide_decimal_int_zero: "DECIMAL '1' / 0" => Err(Error::Value("Can't divide by zero".into())),
+    op_divide_int_decimal_zero: "1 / DECIMAL '0'" => Err(Error::Value("Can't divide by zero".into())),
     op_divide_float_float: "4.16 / 3.2" => Ok(Float(1.3)),
-    op_divide_float_float_zero: "4.16 / 0.0" => Ok(Float(std::f64::INFINITY)),
-    op_divide_float_float_zero_zero: "0.0 / 0.0" => Ok(Float(std::f64::NAN)),
+    op_divide_float_float_zero: "4.16 / 0.0" => Ok(Float(f64::INFINITY)),
+    op_divide_float_float_zero_zero: "0.0 / 0.0" => Ok(Float(f64::NAN)),
     op_divide_float_integer: "1.5 / 3" => Ok(Float(0.5)),
-    op_divide_float_integer_zero: "4.16 / 0" => Ok(Float(std::f64::INFINITY)),
+    op_divide_float_integer_zero: "4.16 / 0" => Ok(Float(f64::INFINITY)),
     op_divide_float_null: "4.16 / NULL" => Ok(Null),
     op_divide_integer_float: "3 / 1.2" => Ok(Float(2.5)),
-    op_divide_integer_float_zero: "3 / 0.0" => Ok(Float(std::f64::INFINITY)),
+    op_divide_integer_float_zero: "3 / 0.0" => Ok(Float(f64::INFINITY)),
     op_divide_integer_integer: "8 / 3" => Ok(Integer(2)),
     op_divide_integer_integer_negative: "8 / -3" => Ok(Integer(-2)),
     op_divide_integer_integer_zero: "1 / 0" => Err(Error::Value("Can't divide by zero".into())),
     op_divide_integer_null: "1 / NULL" => Ok(Null),
     op_divide_infinity: "1 / INFINITY" => Ok(Float(0.0)),
-    op_divide_infinity_divisor: "INFINITY / 10" => Ok(Float(std::f64::INFINITY)),
-    op_divide_infinity_infinity: "INFINITY / INFINITY" => Ok(Float(std::f64::NAN)),
-    op_divide_nan: "1 / NAN" => Ok(Float(std::f64::NAN)),
+    op_divide_infinity_divisor: "INFINITY / 10" => Ok(Float(f64::INFINITY)),
+    op_divide_infinity_infinity: "INFINITY / INFINITY" => Ok(Float(f64::NAN)),
+    op_divide_nan: "1 / NAN" => Ok(Float(f64::NAN)),
     op_divide_null_float: "NULL / 3.14" => Ok(Null),
     op_divide_null_integer: "NULL / 1" => Ok(Null),
     op_divide_null_null: "NULL / NULL" => Ok(Null),
     op_divide_error_bool: "TRUE / FALSE" => Err(Error::Value("Can't divide TRUE and FALSE".into())),
     op_divide_error_strings: "'a' / 'b'" => Err(Error::Value("Can't divide a and b".into())),
 
+    op_exp_decimal_int: "DECIMAL '1.5' ^ 2" => Ok(Float(2.25)),
     op_exp_float_float: "6.25 ^ 0.5" => Ok(Float(2.5)),
     op_exp_float_int: "6.25 ^ 2" => Ok(Float(39.0625)),
     op_exp_float_null: "3.14 ^ NULL" => Ok(Null),
@@ -351,12 +677,12 @@ test_expr! {
     op_exp_null_float: "NULL ^ 3.14" => Ok(Null),
     op_exp_null_int: "NULL ^ 1" => Ok(Null),
     op_exp_null_null: "NULL ^ NULL" => Ok(Null),
-    op_exp_infinity: "INFINITY ^ 2" => Ok(Float(std::f64::INFINITY)),
-    op_exp_infinity_exp: "2 ^ INFINITY" => Ok(Float(std::f64::INFINITY)),
-    op_exp_infinity_infinity: "INFINITY ^ INFINITY" => Ok(Float(std::f64::INFINITY)),
-    op_exp_nan: "NAN ^ 2" => Ok(Float(std::f64::NAN)),
-    op_exp_nan_exp: "2 ^ NAN" => Ok(Float(std::f64::NAN)),
-    op_exp_overflow_float: "10e200 ^ 2" => Ok(Float(std::f64::INFINITY)),
+    op_exp_infinity: "INFINITY ^ 2" => Ok(Float(f64::INFINITY)),
+    op_exp_infinity_exp: "2 ^ INFINITY" => Ok(Float(f64::INFINITY)),
+    op_exp_infinity_infinity: "INFINITY ^ INFINITY" => Ok(Float(f64::INFINITY)),
+    op_exp_nan: "NAN ^ 2" => Ok(Float(f64::NAN)),
+    op_exp_nan_exp: "2 ^ NAN" => Ok(Float(f64::NAN)),
+    op_exp_overflow_float: "10e200 ^ 2" => Ok(Float(f64::INFINITY)),
     op_exp_overflow_int: "9223372036854775807 ^ 2" => Err(Error::Value("Integer overflow".into())),
     op_exp_negative: "2 ^ -3" => Ok(Float(0.125)),
     op_exp_error_bool: "TRUE ^ FALSE" => Err(Error::Value("Can't exponentiate TRUE and FALSE".into())),
@@ -370,25 +696,31 @@ test_expr! {
     op_factorial_error_negative: "-3!" => Err(Error::Value("Can't take factorial of negative number".into())),
     op_factorial_error_string: "'abc'!" => Err(Error::Value("Can't take factorial of abc".into())),
 
+    op_modulo_decimal_decimal: "DECIMAL '6.28' % DECIMAL '2.2'" => Ok(Decimal("1.88".parse().unwrap())),
+    op_modulo_decimal_int: "DECIMAL '-7.5' % 2" => Ok(Decimal("-1.5".parse().unwrap())),
+    op_modulo_decimal_zero: "DECIMAL '1' % 0" => Err(Error::Value("Can't divide by zero".into())),
     op_modulo_float_float: "6.28 % 2.2" => Ok(Float(1.88)),
-    op_modulo_float_float_zero: "6.28 % 0.0" => Ok(Float(std::f64::NAN)),
+    op_modulo_float_float_zero: "6.28 % 0.0" => Ok(Float(f64::NAN)),
     op_modulo_float_int: "3.15 % 2" => Ok(Float(1.15)),
     op_modulo_float_null: "3.14 % NULL" => Ok(Null),
     op_modulo_int_float: "6 % 3.15" => Ok(Float(2.85)),
     op_modulo_int_int: "5 % 3" => Ok(Integer(2)),
     op_modulo_int_int_zero: "7 % 0" => Err(Error::Value("Can't divide by zero".into())),
+    op_modulo_int_int_overflow: "(-9223372036854775807 - 1) % -1" => Err(Error::Value("Integer overflow".into())),
     op_modulo_int_null: "1 % NULL" => Ok(Null),
     op_modulo_null_float: "NULL % 3.14" => Ok(Null),
     op_modulo_null_int: "NULL % 1" => Ok(Null),
     op_modulo_null_null: "NULL % NULL" => Ok(Null),
     op_modulo_negative: "-5 % 3" => Ok(Integer(-2)),
     op_modulo_negative_rhs: "5 % -3" => Ok(Integer(2)),
-    op_modulo_infinity: "INFINITY % 7" => Ok(Float(std::f64::NAN)),
+    op_modulo_infinity: "INFINITY % 7" => Ok(Float(f64::NAN)),
     op_modulo_infinity_divisor: "7 % INFINITY" => Ok(Float(7.0)),
-    op_modulo_nan: "7 % NAN" => Ok(Float(std::f64::NAN)),
+    op_modulo_nan: "7 % NAN" => Ok(Float(f64::NAN)),
     op_modulo_error_bool: "TRUE % FALSE" => Err(Error::Value("Can't take modulo of TRUE and FALSE".into())),
     op_modulo_error_strings: "'a' % 'b'" => Err(Error::Value("Can't take modulo of a and b".into())),
 
+    op_multiply_decimal_decimal: "DECIMAL '1.10' * DECIMAL '3'" => Ok(Decimal("3.30".parse().unwrap())),
+    op_multiply_decimal_int: "DECIMAL '19.99' * 3" => Ok(Decimal("59.97".parse().unwrap())),
     op_multiply_float_float: "3.1 * 2.71" => Ok(Float(3.1 * 2.71)),
     op_multiply_float_int: "3.72 * 1" => Ok(Float(3.72 * 1.0)),
     op_multiply_float_null: "3.14 * NULL" => Ok(Null),
@@ -399,26 +731,29 @@ test_expr! {
     op_multiply_null_int: "NULL * 1" => Ok(Null),
     op_multiply_null_null: "NULL * NULL" => Ok(Null),
     op_multiply_negative: "2 * -3" => Ok(Integer(-6)),
-    op_multiply_infinity: "2 * INFINITY" => Ok(Float(std::f64::INFINITY)),
-    op_multiply_nan: "2 * NAN" => Ok(Float(std::f64::NAN)),
+    op_multiply_infinity: "2 * INFINITY" => Ok(Float(f64::INFINITY)),
+    op_multiply_nan: "2 * NAN" => Ok(Float(f64::NAN)),
     op_multiply_overflow_int: "9223372036854775807 * 2" => Err(Error::Value("Integer overflow".into())),
     op_multiply_underflow_int: "9223372036854775807 * -2" => Err(Error::Value("Integer overflow".into())),
-    op_multiply_overflow_float: "2e308 * 2" => Ok(Float(std::f64::INFINITY)),
+    op_multiply_overflow_float: "2e308 * 2" => Ok(Float(f64::INFINITY)),
     op_multiply_round_int_float: "9223372036854775807 * 2.0" => Ok(Float(18_446_744_073_709_552_000.0)),
     op_multiply_error_bool: "TRUE * FALSE" => Err(Error::Value("Can't multiply TRUE and FALSE".into())),
     op_multiply_error_strings: "'a' * 'b'" => Err(Error::Value("Can't multiply a and b".into())),
 
+    op_negate_decimal: "-DECIMAL '1.5'" => Ok(Decimal("-1.5".parse().unwrap())),
     op_negate: "-1" => Ok(Integer(-1)),
     op_negate_double: "--1" => Ok(Integer(1)),
     op_negate_float: "-3.72" => Ok(Float(-3.72)),
     op_negate_mixed: "-+-+-1" => Ok(Integer(-1)),
     op_negate_multi: "---1" => Ok(Integer(-1)),
     op_negate_null: "-NULL" => Ok(Null),
-    op_negate_infinity: "-INFINITY" => Ok(Float(-std::f64::INFINITY)),
-    op_negate_nan: "-NAN" => Ok(Float(std::f64::NAN)),
+    op_negate_infinity: "-INFINITY" => Ok(Float(-f64::INFINITY)),
+    op_negate_nan: "-NAN" => Ok(Float(f64::NAN)),
     op_negate_error_bool: "-TRUE" => Err(Error::Value("Can't negate TRUE".into())),
     op_negate_error_string: "-'abc'" => Err(Error::Value("Can't negate abc".into())),
 
+    op_subtract_decimal_decimal: "DECIMAL '0.3' - DECIMAL '0.1'" => Ok(Decimal("0.2".parse().unwrap())),
+    op_subtract_int_decimal: "1 - DECIMAL '0.01'" => Ok(Decimal("0.99".parse().unwrap())),
     op_subtract_float_float: "3.1 - 2.71" => Ok(Float(3.1 - 2.71)),
     op_subtract_float_int: "3.72 - 1" => Ok(Float(3.72 - 1.0)),
     op_subtract_float_null: "3.14 - NULL" => Ok(Null),
@@ -429,15 +764,99 @@ test_expr! {
     op_subtract_null_int: "NULL - 1" => Ok(Null),
     op_subtract_null_null: "NULL - NULL" => Ok(Null),
     op_subtract_negative: "1 - -3" => Ok(Integer(4)),
-    op_subtract_infinity: "1 - INFINITY" => Ok(Float(-std::f64::INFINITY)),
-    op_subtract_nan: "1 - NAN" => Ok(Float(std::f64::NAN)),
+    op_subtract_infinity: "1 - INFINITY" => Ok(Float(-f64::INFINITY)),
+    op_subtract_nan: "1 - NAN" => Ok(Float(f64::NAN)),
     op_subtract_overflow_int: "9223372036854775807 - -1" => Err(Error::Value("Integer overflow".into())),
     op_subtract_underflow_int: "-9223372036854775807 - 2" => Err(Error::Value("Integer overflow".into())),
-    op_subtract_overflow_float: "2e308 - -2e308" => Ok(Float(std::f64::INFINITY)),
+    op_subtract_overflow_float: "2e308 - -2e308" => Ok(Float(f64::INFINITY)),
     op_subtract_round_int_float: "9223372036854775807 - -10.0" => Ok(Float(9_223_372_036_854_776_000.0)),
     op_subtract_error_bool: "TRUE - FALSE" => Err(Error::Value("Can't subtract TRUE and FALSE".into())),
     op_subtract_error_strings: "'a' - 'b'" => Err(Error::Value("Can't subtract a and b".into())),
 
+    op_between: "2 BETWEEN 1 AND 3" => Ok(Boolean(true)),
+    op_between_low: "1 BETWEEN 1 AND 3" => Ok(Boolean(true)),
+    op_between_high: "3 BETWEEN 1 AND 3" => Ok(Boolean(true)),
+    op_between_below: "0 BETWEEN 1 AND 3" => Ok(Boolean(false)),
+    op_between_above: "4 BETWEEN 1 AND 3" => Ok(Boolean(false)),
+    op_between_reversed: "2 BETWEEN 3 AND 1" => Ok(Boolean(false)),
+    op_between_mixed: "2 BETWEEN 1.5 AND 2.5" => Ok(Boolean(true)),
+    op_between_string: "'b' BETWEEN 'a' AND 'c'" => Ok(Boolean(true)),
+    op_between_expr: "1 + 1 BETWEEN 3 - 2 AND 1 * 3" => Ok(Boolean(true)),
+    op_between_null: "NULL BETWEEN 1 AND 3" => Ok(Null),
+    op_between_null_low: "2 BETWEEN NULL AND 3" => Ok(Null),
+    op_between_null_low_false: "4 BETWEEN NULL AND 3" => Ok(Boolean(false)),
+    op_between_not: "2 NOT BETWEEN 1 AND 3" => Ok(Boolean(false)),
+    op_between_not_outside: "4 NOT BETWEEN 1 AND 3" => Ok(Boolean(true)),
+    op_between_missing_and: "2 BETWEEN 1 OR 3" => syntax("Expected token AND, found OR", 13, "OR"),
+    op_between_error: "2 BETWEEN 'a' AND 3" => Err(Error::Value("Can't cast a to INTEGER".into())),
+    op_between_int_string: "2 BETWEEN '1' AND '3'" => Ok(Boolean(true)),
+    op_between_string_date: "'2024-02-29' BETWEEN DATE '2024-01-01' AND DATE '2024-12-31'" => Ok(Boolean(true)),
+    op_in: "2 IN (1, 2, 3)" => Ok(Boolean(true)),
+    op_in_single: "2 IN (2)" => Ok(Boolean(true)),
+    op_in_missing: "4 IN (1, 2, 3)" => Ok(Boolean(false)),
+    op_in_mixed: "2 IN (1.0, 2.0)" => Ok(Boolean(true)),
+    op_in_string: "'b' IN ('a', 'b')" => Ok(Boolean(true)),
+    op_in_expr: "1 + 1 IN (3 - 1)" => Ok(Boolean(true)),
+    op_in_null: "NULL IN (1, 2)" => Ok(Null),
+    op_in_null_item: "3 IN (1, NULL)" => Ok(Null),
+    op_in_null_item_match: "1 IN (1, NULL)" => Ok(Boolean(true)),
+    op_in_not: "2 NOT IN (1, 2, 3)" => Ok(Boolean(false)),
+    op_in_not_missing: "4 NOT IN (1, 2, 3)" => Ok(Boolean(true)),
+    op_in_not_null_item: "4 NOT IN (1, NULL)" => Ok(Null),
+    op_in_empty: "1 IN ()" => syntax("Expected expression atom, found )", 7, ")"),
+    op_in_noparen: "1 IN 1" => syntax("Expected token (, found 1", 6, "1"),
+    op_in_error: "1 IN ('a')" => Err(Error::Value("Can't cast a to INTEGER".into())),
+    op_in_int_string: "2 IN ('1', '2')" => Ok(Boolean(true)),
+    op_in_string_int: "'2' IN (1, 2)" => Ok(Boolean(true)),
+    op_not_unexpected: "1 NOT 2" => syntax("Unexpected token 2", 7, "2"),
+
+    // Date and time operators
+    op_add_timestamp_interval: "TIMESTAMP '2024-01-31 12:00' + INTERVAL '1 month 1 day 1 hour'" => Ok(Timestamp("2024-03-01T13:00:00".parse().unwrap())),
+    op_add_interval_timestamp: "INTERVAL '90 minutes' + TIMESTAMP '2024-02-29 23:00'" => Ok(Timestamp("2024-03-01T00:30:00".parse().unwrap())),
+    op_add_timestamp_interval_month_end: "TIMESTAMP '2024-01-31' + INTERVAL '1 month'" => Ok(Timestamp("2024-02-29T00:00:00".parse().unwrap())),
+    op_add_timestamp_interval_negative: "TIMESTAMP '2024-03-31' + INTERVAL '-1 month'" => Ok(Timestamp("2024-02-29T00:00:00".parse().unwrap())),
+    op_add_timestamp_interval_overflow: "TIMESTAMP '2024-01-01' + INTERVAL '2000000000 months'" => Err(Error::Value("Timestamp out of range".into())),
+    op_add_date_interval: "DATE '2024-02-28' + INTERVAL '1 day 12 hours'" => Ok(Timestamp("2024-02-29T12:00:00".parse().unwrap())),
+    op_add_date_int: "DATE '2024-02-28' + 2" => Ok(Date("2024-03-01".parse().unwrap())),
+    op_add_int_date: "1 + DATE '2024-02-28'" => Ok(Date("2024-02-29".parse().unwrap())),
+    op_add_date_int_overflow: "DATE '2024-02-28' + 9223372036854775807" => Err(Error::Value("Date out of range".into())),
+    op_add_time_interval: "TIME '23:30' + INTERVAL '1 day 1 hour'" => Ok(Time("00:30:00".parse().unwrap())),
+    op_add_interval_interval: "(INTERVAL '1 month 2 hours' + INTERVAL '3 days -1 hour')::STRING" => Ok(String("1 mon 3 days 01:00:00".into())),
+    op_add_interval_null: "INTERVAL '1 day' + NULL" => Ok(Null),
+    op_add_null_timestamp: "NULL + TIMESTAMP '2024-01-01'" => Ok(Null),
+    op_add_timestamp_timestamp: "TIMESTAMP '2024-01-01' + TIMESTAMP '2024-01-01'" => Err(Error::Value("Can't add 2024-01-01 00:00:00 and 2024-01-01 00:00:00".into())),
+    op_add_interval_int: "INTERVAL '1 day' + 1" => Err(Error::Value("Can't add 1 day and 1".into())),
+    op_subtract_timestamp_interval: "TIMESTAMP '2024-03-31 01:00' - INTERVAL '1 month 2 hours'" => Ok(Timestamp("2024-02-28T23:00:00".parse().unwrap())),
+    op_subtract_date_interval: "DATE '2024-03-01' - INTERVAL '1 day'" => Ok(Timestamp("2024-02-29T00:00:00".parse().unwrap())),
+    op_subtract_date_int: "DATE '2024-03-01' - 1" => Ok(Date("2024-02-29".parse().unwrap())),
+    op_subtract_date_date: "DATE '2024-03-01' - DATE '2024-01-01'" => Ok(Integer(60)),
+    op_subtract_time_interval: "TIME '00:30' - INTERVAL '1 hour'" => Ok(Time("23:30:00".parse().unwrap())),
+    op_subtract_time_time: "(TIME '13:45' - TIME '12:00:30')::STRING" => Ok(String("01:44:30".into())),
+    op_subtract_timestamp_timestamp: "(TIMESTAMP '2024-03-01 02:00' - TIMESTAMP '2024-02-28')::STRING" => Ok(String("2 days 02:00:00".into())),
+    op_subtract_timestamp_timestamp_negative: "(TIMESTAMP '2024-02-28' - TIMESTAMP '2024-03-01 02:00')::STRING" => Ok(String("-2 days -02:00:00".into())),
+    op_subtract_timestamp_date: "(TIMESTAMP '2024-03-01 02:00' - DATE '2024-03-01')::STRING" => Ok(String("02:00:00".into())),
+    op_subtract_interval_interval: "(INTERVAL '1 day' - INTERVAL '2 hours')::STRING" => Ok(String("1 day -02:00:00".into())),
+    op_subtract_interval_timestamp: "INTERVAL '1 day' - TIMESTAMP '2024-01-01'" => Err(Error::Value("Can't subtract 1 day and 2024-01-01 00:00:00".into())),
+    op_subtract_timestamp_null: "TIMESTAMP '2024-01-01' - NULL" => Ok(Null),
+    op_multiply_interval_int: "(INTERVAL '1 month 1 day 01:00' * 3)::STRING" => Ok(String("3 mons 3 days 03:00:00".into())),
+    op_multiply_int_interval: "(2 * INTERVAL '-1 hour')::STRING" => Ok(String("-02:00:00".into())),
+    op_multiply_interval_float: "(INTERVAL '1 month' * 1.5)::STRING" => Ok(String("1 mon 15 days".into())),
+    op_multiply_interval_overflow: "INTERVAL '1 month' * 3000000000" => Err(Error::Value("Interval overflow".into())),
+    op_multiply_interval_null: "INTERVAL '1 day' * NULL" => Ok(Null),
+    op_multiply_interval_interval: "INTERVAL '1 day' * INTERVAL '1 day'" => Err(Error::Value("Can't multiply 1 day and 1 day".into())),
+    op_divide_interval_int: "(INTERVAL '1 day' / 3)::STRING" => Ok(String("08:00:00".into())),
+    op_divide_interval_float: "(INTERVAL '3 months' / 2.0)::STRING" => Ok(String("1 mon 15 days".into())),
+    op_divide_interval_zero: "INTERVAL '1 day' / 0" => Err(Error::Value("Can't divide by zero".into())),
+    op_divide_interval_float_zero: "INTERVAL '1 day' / 0.0" => Err(Error::Value("Can't divide by zero".into())),
+    op_negate_interval: "(-INTERVAL '1 day 02:00')::STRING" => Ok(String("-1 days -02:00:00".into())),
+    op_eq_interval: "INTERVAL '1 month' = INTERVAL '30 days'" => Ok(Boolean(true)),
+    op_eq_interval_not: "INTERVAL '1 day' = INTERVAL '23 hours'" => Ok(Boolean(false)),
+    op_eq_interval_string: "INTERVAL '1 day' = '24 hours'" => Ok(Boolean(true)),
+    op_gt_interval: "INTERVAL '1 month' > INTERVAL '29 days 23:59'" => Ok(Boolean(true)),
+    op_lt_interval: "INTERVAL '-1 day' < INTERVAL '1 second'" => Ok(Boolean(true)),
+    op_gt_interval_timestamp: "INTERVAL '1 day' > TIMESTAMP '2024-01-01'" => Err(Error::Value("Can't compare 1 day and 2024-01-01 00:00:00".into())),
+    op_range_timestamp_interval: "TIMESTAMP '2024-01-01 12:00' BETWEEN TIMESTAMP '2024-01-01' AND TIMESTAMP '2024-01-01' + INTERVAL '1 day'" => Ok(Boolean(true)),
+
     // String operators
     op_like_percent: "'abcde' LIKE 'a%e'" => Ok(Boolean(true)),
     op_like_percent_escape: "'ab%de' LIKE 'ab%%de'" => Ok(Boolean(true)),
@@ -457,7 +876,152 @@ test_expr! {
     op_like_eq: "'abc' LIKE 'abc'" => Ok(Boolean(true)),
     op_like_neq: "'xyz' LIKE 'abc'" => Ok(Boolean(false)),
     op_like_null: "'abc' LIKE NULL" => Ok(Null),
+    op_concat: "'ab' || 'cd'" => Ok(String("abcd".into())),
+    op_concat_integer: "'a' || 1" => Ok(String("a1".into())),
+    op_concat_integer_lhs: "1 || 'a'" => Ok(String("1a".into())),
+    op_concat_integers: "1 || 2" => Err(Error::Value("Can't concatenate 1 and 2".into())),
+    op_concat_null: "'a' || NULL" => Ok(Null),
+    op_concat_multi: "'a' || 'b' || 'c'" => Ok(String("abc".into())),
+    op_concat_bytes: "x'dead' || x'beef'" => Ok(Bytes(vec![0xde, 0xad, 0xbe, 0xef])),
+    op_concat_bytes_string: "x'dead' || 'a'" => Ok(String("\\xdeada".into())),
     op_like_null_lhs: "NULL LIKE 'abc'" => Ok(Null),
+    op_match: "'Star Wars: A New Hope' @@ 'star trek'" => Ok(Boolean(true)),
+    op_match_case: "'STAR WARS' @@ 'wars'" => Ok(Boolean(true)),
+    op_match_not: "'Star Wars' @@ 'startrek'" => Ok(Boolean(false)),
+    op_match_empty: "'Star Wars' @@ ''" => Ok(Boolean(false)),
+    op_match_null: "'Star Wars' @@ NULL" => Ok(Null),
+    op_match_null_lhs: "NULL @@ 'star'" => Ok(Null),
+    op_match_integer: "1 @@ 'star'" => Err(Error::Value("Can't match 1 against star".into())),
+    op_match_precedence: "'a' || 'b c' @@ 'ab'" => Ok(Boolean(true)),
+    op_relevance: "MATCH ('Star Wars') AGAINST ('star trek')" => Ok(Float(1.0)),
+    op_relevance_repeated: "MATCH ('star star wars') AGAINST ('star wars')" => Ok(Float(2.0 + 2f64.ln())),
+    op_relevance_none: "MATCH ('Star Wars') AGAINST ('trek')" => Ok(Float(0.0)),
+    op_relevance_null: "MATCH (NULL) AGAINST ('trek')" => Ok(Null),
+    op_relevance_against_missing: "MATCH ('Star Wars') ('trek')" => syntax("Expected token against, found (", 21, "("),
+
+    // JSON operators
+    op_json_extract: r#"JSON '{"a": {"b": 1}}' -> 'a'"# => Ok(Json(json!({"b": 1}))),
+    op_json_extract_chain: r#"JSON '{"a": {"b": [1, 2]}}' -> 'a' -> 'b' -> 1"# => Ok(Json(json!(2))),
+    op_json_extract_negative: "JSON '[1, 2, 3]' -> -1" => Ok(Json(json!(3))),
+    op_json_extract_missing: r#"JSON '{"a": 1}' -> 'b'"# => Ok(Null),
+    op_json_extract_mismatch: r#"JSON '{"a": 1}' -> 0"# => Ok(Null),
+    op_json_extract_scalar: "JSON '1' -> 'a'" => Ok(Null),
+    op_json_extract_null: "NULL -> 'a'" => Ok(Null),
+    op_json_extract_string: r#"'{"a": 1}' -> 'a'"# => Ok(Json(json!(1))),
+    op_json_extract_float: "JSON '[1]' -> 0.0" => Err(Error::Value("Can't extract 0 from JSON document".into())),
+    op_json_extract_text: r#"JSON '{"a": "x"}' ->> 'a'"# => Ok(String("x".into())),
+    op_json_extract_text_number: r#"JSON '{"a": 1.5}' ->> 'a'"# => Ok(String("1.5".into())),
+    op_json_extract_text_object: r#"JSON '{"a": {"b": 1}}' ->> 'a'"# => Ok(String(r#"{"b":1}"#.into())),
+    op_json_extract_text_null: r#"JSON '{"a": null}' ->> 'a'"# => Ok(Null),
+    op_json_extract_eq: r#"JSON '{"a": 1}' -> 'a' = JSON '1'"# => Ok(Boolean(true)),
+    op_json_extract_text_concat: r#"JSON '{"a": "x"}' ->> 'a' || 'y'"# => Ok(String("xy".into())),
+    op_json_extract_text_cast: r#"(JSON '{"a": "2"}' ->> 'a')::INTEGER + 1"# => Ok(Integer(3)),
+
+    // Array operators
+    op_array: "ARRAY[1, 2]" => Ok(Array(vec![Integer(1), Integer(2)])),
+    op_array_empty: "ARRAY[]" => Ok(Array(vec![])),
+    op_array_unify: "ARRAY[1, 2.5, NULL]" => Ok(Array(vec![Float(1.0), Float(2.5), Null])),
+    op_array_mismatch: "ARRAY[1, 'a']" => Err(Error::Value("ARRAY types INTEGER and STRING cannot be matched".into())),
+    op_array_nested: "ARRAY[ARRAY[1]]" => Err(Error::Value("Nested arrays are not supported".into())),
+    op_array_noclose: "ARRAY[1" => syntax("Unexpected end of input", 8, ""),
+    op_array_element: "ARRAY['a', 'b'][2]" => Ok(String("b".into())),
+    op_array_element_zero: "ARRAY['a', 'b'][0]" => Ok(Null),
+    op_array_element_out_of_range: "ARRAY['a', 'b'][3]" => Ok(Null),
+    op_array_element_null: "ARRAY['a'][NULL]" => Ok(Null),
+    op_array_element_string: "'a'[1]" => Err(Error::Value("Can't index a by 1".into())),
+    op_array_element_prec: "-ARRAY[1][1]" => Ok(Integer(-1)),
+    op_array_any: "2 = ANY (ARRAY[1, 2])" => Ok(Boolean(true)),
+    op_array_any_missing: "3 = ANY (ARRAY[1, 2])" => Ok(Boolean(false)),
+    op_array_any_null_element: "3 = ANY (ARRAY[1, NULL])" => Ok(Null),
+    op_array_any_null_element_match: "1 = ANY (ARRAY[1, NULL])" => Ok(Boolean(true)),
+    op_array_any_null_array: "1 = ANY (NULL)" => Ok(Null),
+    op_array_any_empty: "1 = ANY (ARRAY[])" => Ok(Boolean(false)),
+    op_array_any_coerce: "'2' = ANY (ARRAY[1, 2])" => Ok(Boolean(true)),
+    op_array_any_prec: "1 = ANY (ARRAY[1]) AND FALSE" => Ok(Boolean(false)),
+    op_array_eq: "ARRAY[1, NULL] = ARRAY[1.0, NULL]" => Ok(Boolean(true)),
+    op_array_lt: "ARRAY[1, 2] < ARRAY[1, 3]" => Ok(Boolean(true)),
+    op_array_lt_prefix: "ARRAY[1] < ARRAY[1, 0]" => Ok(Boolean(true)),
+    op_array_compare_mismatch: "ARRAY[1] = ARRAY['a']" => Err(Error::Value("Can't compare {1} and {a}".into())),
+    op_array_concat: "ARRAY[1] || ARRAY[2, 3]" => Ok(Array(vec![Integer(1), Integer(2), Integer(3)])),
+    op_array_concat_element: "ARRAY['a'] || 'b'" => Ok(Array(vec![String("a".into()), String("b".into())])),
+    op_array_concat_element_lhs: "0 || ARRAY[1]" => Ok(Array(vec![Integer(0), Integer(1)])),
+    op_array_cast_string: "'{1,NULL, 3}'::INTEGER[]" => Ok(Array(vec![Integer(1), Null, Integer(3)])),
+    op_array_cast_quoted: r#"'{"a,b","NULL",c}'::STRING[]"# => Ok(Array(vec![String("a,b".into()), String("NULL".into()), String("c".into())])),
+    op_array_cast_invalid: "'{1'::INTEGER[]" => Err(Error::Value("Can't cast {1 to INTEGER[]".into())),
+    op_array_cast_element_invalid: "'{a}'::INTEGER[]" => Err(Error::Value("Can't cast a to INTEGER".into())),
+    op_array_cast_elements: "ARRAY[1, 2]::STRING[]" => Ok(Array(vec![String("1".into()), String("2".into())])),
+    op_array_cast_to_string: r#"ARRAY['a b', NULL, '"']::STRING"# => Ok(String(r#"{"a b",NULL,"\""}"#.into())),
+    op_array_cast_to_json: "ARRAY[1, NULL]::JSON" => Ok(Json(json!([1, null]))),
+    op_array_cast_scalar: "1::INTEGER[]" => Err(Error::Value("Can't cast 1 to INTEGER[]".into())),
+    op_array_multidimensional: "'{}'::INTEGER[][]" => syntax("Multidimensional arrays are not supported", 16, "["),
+
+    // Type operators
+    op_cast_bool_bool: "CAST(TRUE AS BOOLEAN)" => Ok(Boolean(true)),
+    op_cast_bool_int: "CAST(TRUE AS INTEGER)" => Ok(Integer(1)),
+    op_cast_bool_float: "CAST(FALSE AS FLOAT)" => Ok(Float(0.0)),
+    op_cast_bool_string: "CAST(TRUE AS STRING)" => Ok(String("TRUE".into())),
+    op_cast_int_bool: "CAST(3 AS BOOLEAN)" => Ok(Boolean(true)),
+    op_cast_int_bool_zero: "CAST(0 AS BOOL)" => Ok(Boolean(false)),
+    op_cast_int_float: "CAST(3 AS FLOAT)" => Ok(Float(3.0)),
+    op_cast_int_string: "CAST(-3 AS TEXT)" => Ok(String("-3".into())),
+    op_cast_float_bool: "CAST(3.14 AS BOOLEAN)" => Err(Error::Value("Can't cast 3.14 to BOOLEAN".into())),
+    op_cast_float_int: "CAST(3.5 AS INTEGER)" => Ok(Integer(4)),
+    op_cast_float_int_neg: "CAST(-3.4 AS INT)" => Ok(Integer(-3)),
+    op_cast_float_int_infinity: "CAST(INFINITY AS INTEGER)" => Err(Error::Value("Can't cast inf to INTEGER".into())),
+    op_cast_float_int_nan: "CAST(NAN AS INTEGER)" => Err(Error::Value("Can't cast NaN to INTEGER".into())),
+    op_cast_float_int_overflow: "CAST(1e19 AS INTEGER)" => Err(Error::Value("Can't cast 10000000000000000000 to INTEGER".into())),
+    op_cast_float_string: "CAST(3.14 AS VARCHAR)" => Ok(String("3.14".into())),
+    op_cast_string_bool: "CAST(' Yes ' AS BOOLEAN)" => Ok(Boolean(true)),
+    op_cast_string_bool_f: "CAST('f' AS BOOLEAN)" => Ok(Boolean(false)),
+    op_cast_string_bool_invalid: "CAST('maybe' AS BOOLEAN)" => Err(Error::Value("Can't cast maybe to BOOLEAN".into())),
+    op_cast_string_int: "CAST(' 42 ' AS INTEGER)" => Ok(Integer(42)),
+    op_cast_string_int_invalid: "CAST('4.2' AS INTEGER)" => Err(Error::Value("Can't cast 4.2 to INTEGER".into())),
+    op_cast_string_float: "CAST('4.2e1' AS FLOAT)" => Ok(Float(42.0)),
+    op_cast_string_float_invalid: "CAST('abc' AS FLOAT)" => Err(Error::Value("Can't cast abc to FLOAT".into())),
+    op_cast_string_string: "CAST('abc' AS STRING)" => Ok(String("abc".into())),
+    op_cast_null: "CAST(NULL AS INTEGER)" => Ok(Null),
+    op_cast_expr: "CAST(1 + 2 AS STRING)" => Ok(String("3".into())),
+    op_cast_invalid_type: "CAST(1 AS FOO)" => Err(Error::Value("Type foo does not exist".into())),
+    op_cast_missing_as: "CAST(1 INTEGER)" => syntax("Expected token AS, found INTEGER", 8, "INTEGER"),
+    op_cast_bool_decimal: "CAST(TRUE AS DECIMAL)" => Ok(Decimal(1.into())),
+    op_cast_int_decimal: "CAST(42 AS DECIMAL)" => Ok(Decimal(42.into())),
+    op_cast_float_decimal: "CAST(0.1 AS DECIMAL)" => Ok(Decimal("0.1".parse().unwrap())),
+    op_cast_float_decimal_inf: "CAST(INFINITY AS DECIMAL)" => Err(Error::Value("Can't cast inf to DECIMAL".into())),
+    op_cast_float_decimal_large: "CAST(1e30 AS DECIMAL)" => Err(Error::Value("Can't cast 1000000000000000000000000000000 to DECIMAL".into())),
+    op_cast_decimal_bool: "CAST(DECIMAL '1' AS BOOLEAN)" => Err(Error::Value("Can't cast 1 to BOOLEAN".into())),
+    op_cast_decimal_int: "CAST(DECIMAL '2.5' AS INTEGER)" => Ok(Integer(3)),
+    op_cast_decimal_int_negative: "CAST(DECIMAL '-2.5' AS INTEGER)" => Ok(Integer(-3)),
+    op_cast_decimal_int_overflow: "CAST(DECIMAL '9223372036854775808' AS INTEGER)" => Err(Error::Value("Can't cast 9223372036854775808 to INTEGER".into())),
+    op_cast_decimal_float: "CAST(DECIMAL '0.1' AS FLOAT)" => Ok(Float(0.1)),
+    op_cast_decimal_string: "CAST(DECIMAL '-0.10' AS STRING)" => Ok(String("-0.10".into())),
+    op_cast_string_date: "CAST(' 2024-02-29 ' AS DATE)" => Ok(Date("2024-02-29".parse().unwrap())),
+    op_cast_date_string: "CAST(DATE '2024-02-29' AS STRING)" => Ok(String("2024-02-29".into())),
+    op_cast_date_timestamp: "CAST(DATE '2024-02-29' AS TIMESTAMP)" => Ok(Timestamp("2024-02-29T00:00:00".parse().unwrap())),
+    op_cast_date_time: "CAST(DATE '2024-02-29' AS TIME)" => Err(Error::Value("Can't cast 2024-02-29 to TIME".into())),
+    op_cast_date_integer: "CAST(DATE '2024-02-29' AS INTEGER)" => Err(Error::Value("Can't cast 2024-02-29 to INTEGER".into())),
+    op_cast_integer_date: "CAST(1 AS DATE)" => Err(Error::Value("Can't cast 1 to DATE".into())),
+    op_cast_string_bytes: r"CAST('\x0aFF' AS BYTEA)" => Ok(Bytes(vec![0x0a, 0xff])),
+    op_cast_string_bytes_utf8: "'å'::BYTEA" => Ok(Bytes(vec![0xc3, 0xa5])),
+    op_cast_bytes_string: "CAST(x'0aff' AS STRING)" => Ok(String(r"\x0aff".into())),
+    op_cast_bytes_integer: "CAST(x'01' AS INTEGER)" => Err(Error::Value(r"Can't cast \x01 to INTEGER".into())),
+    op_cast_integer_bytes: "CAST(1 AS BYTEA)" => Err(Error::Value("Can't cast 1 to BYTEA".into())),
+    op_cast_string_json: r#"CAST('[1, "a"]' AS JSON)"# => Ok(Json(json!([1, "a"]))),
+    op_cast_json_string: r#"CAST(JSON '{"a": [1, "b"]}' AS STRING)"# => Ok(String(r#"{"a":[1,"b"]}"#.into())),
+    op_cast_json_integer: "CAST(JSON '1' AS INTEGER)" => Err(Error::Value("Can't cast 1 to INTEGER".into())),
+    op_cast_string_uuid: "CAST(' a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11 ' AS UUID)" => Ok(Uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".parse().unwrap())),
+    op_cast_uuid_string: "CAST(UUID 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11' AS STRING)" => Ok(String("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".into())),
+    op_cast_uuid_bytes: "CAST(UUID '00010203-0405-0607-0809-0a0b0c0d0e0f' AS BYTEA)" => Ok(Bytes((0..16).collect())),
+    op_cast_bytes_uuid: "CAST(x'000102030405060708090a0b0c0d0e0f' AS UUID)" => Ok(Uuid("00010203-0405-0607-0809-0a0b0c0d0e0f".parse().unwrap())),
+    op_cast_bytes_uuid_short: "CAST(x'0001' AS UUID)" => Err(Error::Value(r"Can't cast \x0001 to UUID".into())),
+    op_cast_uuid_integer: "CAST(UUID '00000000-0000-0000-0000-000000000001' AS INTEGER)" => Err(Error::Value("Can't cast 00000000-0000-0000-0000-000000000001 to INTEGER".into())),
+    op_cast_time_string: "CAST(TIME '13:45:30.5' AS STRING)" => Ok(String("13:45:30.500".into())),
+    op_cast_timestamp_date: "CAST(TIMESTAMP '2024-02-29 13:45' AS DATE)" => Ok(Date("2024-02-29".parse().unwrap())),
+    op_cast_timestamp_time: "CAST(TIMESTAMP '2024-02-29 13:45' AS TIME)" => Ok(Time("13:45:00".parse().unwrap())),
+    op_cast_timestamp_string: "TIMESTAMP '2024-02-29 13:45'::STRING" => Ok(String("2024-02-29 13:45:00".into())),
+    op_cast_shorthand: "'42'::INTEGER" => Ok(Integer(42)),
+    op_cast_shorthand_chain: "3.7::INTEGER::STRING" => Ok(String("4".into())),
+    op_cast_shorthand_null: "NULL::BOOLEAN" => Ok(Null),
+    op_cast_shorthand_colon: "1:INTEGER" => syntax("Unexpected token :", 2, ":"),
 
     // Operator precedence, testing each operator against the ones at the same level and immediately
     // below it in order.
@@ -465,6 +1029,10 @@ test_expr! {
     op_prec_negate_factorial_paren: "-(3!)" => Ok(Integer(-6)),
     op_prec_negate_is: "-NULL IS NULL" => Ok(Boolean(true)),
     op_prec_negate_is_paren: "-(NULL IS NULL)" => Err(Error::Value("Can't negate TRUE".into())),
+    op_prec_negate_cast: "-'1'::INTEGER" => Ok(Integer(-1)),
+    op_prec_negate_cast_paren: "(-'1')::INTEGER" => Err(Error::Value("Can't negate 1".into())),
+    op_prec_cast_factorial: "'3'::INTEGER!" => Ok(Integer(6)),
+    op_prec_cast_exponentiate: "2 ^ '3'::INTEGER" => Ok(Integer(8)),
 
     op_prec_not_factorial: "NOT NULL IS NULL" => Ok(Boolean(true)),
     op_prec_not_factorial_paren: "NOT (NULL IS NULL)" => Ok(Boolean(false)),
@@ -508,6 +1076,10 @@ test_expr! {
     op_prec_modulo_subtract_paren: "(8 - 5) % 3" => Ok(Integer(0)),
 
     op_prec_add_subtract: "1 + 2 - 3" => Ok(Integer(0)),
+    op_prec_add_concat: "'a' || 1 + 2" => Err(Error::Value("Can't add a1 and 2".into())),
+    op_prec_add_concat_paren: "'a' || (1 + 2)" => Ok(String("a3".into())),
+    op_prec_concat_multiply: "'a' || 2 * 3" => Ok(String("a6".into())),
+    op_prec_concat_eq: "'a' || 'b' = 'ab'" => Ok(Boolean(true)),
     op_prec_add_gt: "1 + 2 > 2" => Ok(Boolean(true)),
     op_prec_add_gt_paren: "1 + (2 > 2)" => Err(Error::Value("Can't add 1 and FALSE".into())),
     op_prec_add_gte: "1 + 2 >= 2" => Ok(Boolean(true)),
@@ -567,6 +1139,13 @@ test_expr! {
     op_prec_lte_like: "3 <= 5 LIKE 'abc'" => Err(Error::Value("Can't LIKE TRUE and abc".into())),
     op_prec_lte_like_paren: "3 <= (5 LIKE 'abc')" => Err(Error::Value("Can't LIKE 5 and abc".into())),
 
+    op_prec_between_eq: "2 BETWEEN 1 AND 3 = TRUE" => Ok(Boolean(true)),
+    op_prec_between_and: "2 BETWEEN 1 AND 3 AND FALSE" => Ok(Boolean(false)),
+    op_prec_in_add: "1 + 1 IN (2)" => Ok(Boolean(true)),
+    op_prec_in_eq: "2 IN (2) = TRUE" => Ok(Boolean(true)),
+    op_prec_not_in: "NOT 2 IN (2)" => Err(Error::Value("Can't negate 2".into())),
+    op_prec_not_in_paren: "NOT (2 IN (2))" => Ok(Boolean(false)),
+
     op_prec_eq_neq: "1 = 1 != FALSE" => Ok(Boolean(true)),
     op_prec_eq_like: "1 = 1 LIKE 'abc'" => Err(Error::Value("Can't LIKE TRUE and abc".into())),
     op_prec_eq_and: "1 = 1 AND TRUE" => Ok(Boolean(true)),
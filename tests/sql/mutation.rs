@@ -1,6 +1,7 @@
 //! Mutation tests, using an in-memory database against golden files in tests/sql/mutation/
 use toydb::error::Result;
 use toydb::sql::engine::{Engine as _, Transaction as _};
+use toydb::sql::execution::ResultSet;
 use toydb::sql::schema::Catalog as _;
 
 use goldenfile::Mint;
@@ -23,6 +24,13 @@ macro_rules! test_mutation {
 
                 write!(f, "Query: {}\n", $query.trim())?;
                 match engine.session().execute($query) {
+                    Ok(ResultSet::Query { columns, rows }) => {
+                        write!(f, "Result: {:?}\n", columns)?;
+                        for row in rows {
+                            write!(f, "{:?}\n", row?)?;
+                        }
+                        write!(f, "\n")?;
+                    },
                     Ok(resultset) => {
                         write!(f, "Result: {:?}\n\n", resultset)?;
                     },
@@ -33,17 +41,18 @@ macro_rules! test_mutation {
                 let txn = engine.begin()?;
                 for table in txn.scan_tables()? {
                     write!(f, "\n{}\n", table)?;
-                    for row in txn.scan(&table.name, None)? {
+                    for row in txn.scan(&table.name, None, false, None, None)? {
                         write!(f, "{:?}\n", row?)?;
                     }
 
-                    for column in table.columns.iter().filter(|c| c.index) {
-                        write!(f, "\nIndex {}.{}\n", table.name, column.name)?;
-                        let mut scan = txn.scan_index(&table.name, &column.name)?;
-                        while let Some((value, pks)) = scan.next().transpose()? {
+                    for index in table.get_indexes() {
+                        write!(f, "\nIndex {}.{}\n", table.name, index.name)?;
+                        let mut scan = txn.scan_index(&table.name, &index.name)?;
+                        while let Some((values, pks)) = scan.next().transpose()? {
                             let mut pks = pks.into_iter().collect::<Vec<_>>();
                             pks.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-                            write!(f, "{:?} => {:?}\n", value, pks)?;
+                            let values = values.iter().map(|v| format!("{:?}", v)).collect::<Vec<_>>();
+                            write!(f, "{} => {:?}\n", values.join(", "), pks)?;
                         }
                     }
                 }
@@ -118,6 +127,39 @@ test_mutation! { with [
     insert_bare_values: "INSERT INTO test VALUES",
 }
 
+test_mutation! { with [
+        "CREATE TABLE test (
+            id INTEGER PRIMARY KEY,
+            email STRING UNIQUE,
+            first STRING,
+            last STRING,
+            hits INTEGER NOT NULL DEFAULT 1,
+            UNIQUE (first, last)
+        )",
+        "INSERT INTO test VALUES (1, 'a@x', 'Ann', 'Ames', 1), (2, 'b@x', 'Bob', 'Bell', 5)",
+    ];
+
+    insert_on_conflict_nothing: "INSERT INTO test VALUES (1, 'x@x', 'X', 'X', 1), (3, 'c@x', 'Cat', 'Cox', 1) ON CONFLICT DO NOTHING",
+    insert_on_conflict_nothing_unique: "INSERT INTO test VALUES (3, 'a@x', 'X', 'X', 1) ON CONFLICT DO NOTHING",
+    insert_on_conflict_nothing_target: "INSERT INTO test VALUES (3, 'c@x', 'Bob', 'Bell', 1) ON CONFLICT (last, first) DO NOTHING",
+    insert_on_conflict_nothing_other: "INSERT INTO test VALUES (3, 'a@x', 'X', 'X', 1) ON CONFLICT (id) DO NOTHING",
+    insert_on_conflict_nothing_null: "INSERT INTO test VALUES (3, NULL, 'X', 'X', 1), (4, NULL, 'Y', 'Y', 1) ON CONFLICT (email) DO NOTHING",
+    insert_on_conflict_update: "INSERT INTO test (id, email, first, last) VALUES (1, 'new@x', 'Ann', 'Ames'), (3, 'c@x', 'Cat', 'Cox') ON CONFLICT (id) DO UPDATE SET email = excluded.email, hits = test.hits + 1",
+    insert_on_conflict_update_unique: "INSERT INTO test (id, email) VALUES (9, 'b@x') ON CONFLICT (email) DO UPDATE SET hits = test.hits + excluded.hits",
+    insert_on_conflict_update_where: "INSERT INTO test VALUES (1, 'a@x', 'Ann', 'Ames', 9), (2, 'b@x', 'Bob', 'Bell', 9) ON CONFLICT (id) DO UPDATE SET hits = excluded.hits WHERE test.hits < excluded.hits - 5",
+    insert_on_conflict_update_pk: "INSERT INTO test (id, email) VALUES (3, 'a@x') ON CONFLICT (email) DO UPDATE SET id = excluded.id",
+    insert_on_conflict_update_twice: "INSERT INTO test (id, email) VALUES (1, 'a@x'), (1, 'a@x') ON CONFLICT (id) DO UPDATE SET hits = test.hits + 1",
+    insert_on_conflict_update_inserted: "INSERT INTO test (id, email) VALUES (3, 'c@x'), (3, 'c@x') ON CONFLICT (id) DO UPDATE SET hits = test.hits + 1",
+    insert_on_conflict_update_ambiguous: "INSERT INTO test (id) VALUES (1) ON CONFLICT (id) DO UPDATE SET hits = hits + 1",
+    insert_on_conflict_update_violation: "INSERT INTO test (id, email) VALUES (1, 'x@x') ON CONFLICT (id) DO UPDATE SET email = 'b@x'",
+    insert_on_conflict_update_no_target: "INSERT INTO test (id) VALUES (1) ON CONFLICT DO UPDATE SET hits = 0",
+    insert_on_conflict_no_key: "INSERT INTO test (id) VALUES (1) ON CONFLICT (hits) DO NOTHING",
+    insert_on_conflict_partial_key: "INSERT INTO test (id) VALUES (1) ON CONFLICT (first) DO NOTHING",
+    insert_on_conflict_missing_column: "INSERT INTO test (id) VALUES (1) ON CONFLICT (id) DO UPDATE SET missing = 1",
+    insert_on_conflict_bare: "INSERT INTO test (id) VALUES (1) ON CONFLICT",
+    insert_on_conflict_bare_do: "INSERT INTO test (id) VALUES (1) ON CONFLICT (id) DO",
+}
+
 test_mutation! { with [
         "CREATE TABLE test (
             id INTEGER PRIMARY KEY DEFAULT 0,
@@ -150,3 +192,136 @@ test_mutation! { with [
     update_bare_where: "UPDATE test SET name = 'x' WHERE",
     update_bare_no_table: "UPDATE",
 }
+
+test_mutation! { with [
+        "CREATE TABLE test (
+            id INTEGER PRIMARY KEY,
+            name STRING INDEX,
+            value INTEGER
+        )",
+        "INSERT INTO test VALUES (1, 'a', 100), (2, 'b', 102), (3, 'c', 103)",
+        "CREATE TABLE other (id INTEGER PRIMARY KEY, test_id INTEGER, name STRING, value INTEGER)",
+        "INSERT INTO other VALUES (1, 1, 'x', 10), (2, 1, 'y', 20), (3, 3, 'z', 30)",
+    ];
+
+    update_from: "UPDATE test SET value = other.value FROM other WHERE other.test_id = test.id",
+    update_from_alias: "UPDATE test SET name = o.name, value = test.value + o.value FROM other o WHERE o.id = test.id AND o.name > 'x'",
+    update_from_multiple: "UPDATE test SET value = a.value + b.value FROM other a, other b WHERE a.id = test.id AND b.id = 3",
+    update_from_join: "UPDATE test SET name = b.name FROM other a JOIN other b ON a.id = b.test_id WHERE a.test_id = test.id AND b.id = 2",
+    update_from_self: "UPDATE test SET value = t.value FROM test t WHERE t.id = test.id + 1",
+    update_from_no_where: "UPDATE test SET name = other.name FROM other",
+    update_from_no_match: "UPDATE test SET name = other.name FROM other WHERE other.test_id = 2",
+    update_from_set_unqualified: "UPDATE test SET name = 'q' FROM other WHERE other.id = 2 AND test.id = 2",
+    update_from_ambiguous: "UPDATE test SET value = value FROM other",
+    update_from_duplicate: "UPDATE test SET value = 0 FROM test",
+    update_from_missing_table: "UPDATE test SET value = 0 FROM missing",
+    update_from_bare: "UPDATE test SET value = 0 FROM",
+
+    delete_using: "DELETE FROM test USING other WHERE other.test_id = test.id",
+    delete_using_alias: "DELETE FROM test USING other o WHERE o.id = test.id AND o.name > 'x'",
+    delete_using_multiple: "DELETE FROM test USING other a, other b WHERE a.test_id = test.id AND b.id = a.id + 1",
+    delete_using_join: "DELETE FROM test USING other a JOIN other b ON a.id = b.test_id WHERE a.test_id = test.id",
+    delete_using_self: "DELETE FROM test USING test t WHERE t.id = test.id + 1",
+    delete_using_no_where: "DELETE FROM test USING other",
+    delete_using_no_match: "DELETE FROM test USING other WHERE other.test_id = 2",
+    delete_using_ambiguous: "DELETE FROM test USING other WHERE value > 0",
+    delete_using_duplicate: "DELETE FROM test USING test",
+    delete_using_missing_table: "DELETE FROM test USING missing",
+    delete_using_bare: "DELETE FROM test USING",
+}
+
+test_mutation! { with [
+        "CREATE TABLE test (
+            id INTEGER PRIMARY KEY AUTO_INCREMENT,
+            name STRING UNIQUE,
+            value INTEGER DEFAULT 7
+        )",
+        "INSERT INTO test (name, value) VALUES ('a', 1), ('b', 2), ('c', 0)",
+        "CREATE TABLE other (id INTEGER PRIMARY KEY, test_id INTEGER)",
+        "INSERT INTO other VALUES (1, 1), (2, 3)",
+    ];
+
+    returning_insert: "INSERT INTO test (name) VALUES ('x'), ('y') RETURNING id, name, value",
+    returning_insert_star: "INSERT INTO test (name) VALUES ('x') RETURNING *",
+    returning_insert_expr: "INSERT INTO test (name) VALUES ('x') RETURNING id * 10 AS ten, UPPER(name), test.value",
+    returning_insert_on_conflict_update: "INSERT INTO test (name, value) VALUES ('a', 9), ('x', 9) ON CONFLICT (name) DO UPDATE SET value = excluded.value RETURNING id, value",
+    returning_insert_on_conflict_nothing: "INSERT INTO test (name) VALUES ('a'), ('x') ON CONFLICT DO NOTHING RETURNING id",
+    returning_update: "UPDATE test SET value = value + 1 WHERE id > 1 RETURNING id, value",
+    returning_update_from: "UPDATE test SET value = 0 FROM other WHERE other.test_id = test.id RETURNING *",
+    returning_update_from_other: "UPDATE test SET value = 0 FROM other WHERE other.test_id = test.id RETURNING other.id",
+    returning_update_error: "UPDATE test SET value = value + 1 RETURNING 10 / (value - 1)",
+    returning_delete: "DELETE FROM test WHERE value < 2 RETURNING name",
+    returning_delete_using: "DELETE FROM test USING other WHERE other.test_id = test.id RETURNING *",
+    returning_delete_none: "DELETE FROM test WHERE FALSE RETURNING id",
+    returning_aggregate: "DELETE FROM test RETURNING COUNT(*)",
+    returning_unknown: "DELETE FROM test RETURNING missing",
+    returning_bare: "DELETE FROM test RETURNING",
+}
+
+test_mutation! { with [
+        "CREATE SEQUENCE seq",
+        "CREATE SEQUENCE down START WITH 10 INCREMENT BY -5",
+        "CREATE TABLE test (
+            id INTEGER PRIMARY KEY DEFAULT NEXTVAL('seq'),
+            name STRING,
+            value INTEGER DEFAULT NULL
+        )",
+        "INSERT INTO test (name) VALUES ('a'), ('b')",
+    ];
+
+    sequence_default: "INSERT INTO test (name) VALUES ('c'), ('d')",
+    sequence_insert: "INSERT INTO test VALUES (NEXTVAL('down'), 'x', NEXTVAL('down'))",
+    sequence_update: "UPDATE test SET value = NEXTVAL('down') RETURNING id, value",
+    sequence_select: "SELECT NEXTVAL('seq'), NEXTVAL('seq') * 10",
+    sequence_select_rows: "SELECT id, NEXTVAL('down') FROM test",
+    sequence_values: "VALUES (NEXTVAL('seq')), (NEXTVAL('seq'))",
+    sequence_currval: "SELECT NEXTVAL('seq'), CURRVAL('seq'), NEXTVAL('down'), CURRVAL('seq')",
+    sequence_currval_unset: "SELECT CURRVAL('seq')",
+    sequence_setval: "SELECT SETVAL('seq', 100), NEXTVAL('seq'), NEXTVAL('seq')",
+    sequence_setval_null: "SELECT SETVAL('seq', NULL)",
+    sequence_setval_max: "SELECT SETVAL('seq', 9223372036854775806), NEXTVAL('seq')",
+    sequence_exhausted: "SELECT SETVAL('seq', 9223372036854775807), NEXTVAL('seq')",
+    sequence_missing: "SELECT NEXTVAL('missing')",
+    sequence_name_expr: "SELECT NEXTVAL(name) FROM test",
+}
+
+test_mutation! { with [
+        "CREATE SEQUENCE audit_id",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, value INTEGER, version INTEGER DEFAULT 0)",
+        "CREATE TABLE audit (
+            id INTEGER PRIMARY KEY DEFAULT NEXTVAL('audit_id'),
+            op STRING,
+            test_id INTEGER,
+            old_value INTEGER DEFAULT NULL,
+            new_value INTEGER DEFAULT NULL
+        )",
+        "CREATE TABLE counts (name STRING PRIMARY KEY, count INTEGER)",
+        "INSERT INTO test VALUES (1, 'a', 10, 0), (2, 'b', 20, 0)",
+        "INSERT INTO counts VALUES ('a', 1), ('b', 1)",
+        "CREATE TRIGGER test_version BEFORE UPDATE ON test FOR EACH ROW EXECUTE SET version = old.version + 1",
+        "CREATE TRIGGER test_insert AFTER INSERT ON test FOR EACH ROW EXECUTE (INSERT INTO audit (op, test_id, new_value) VALUES ('insert', new.id, new.value))",
+        "CREATE TRIGGER test_update AFTER UPDATE ON test FOR EACH ROW EXECUTE (INSERT INTO audit (op, test_id, old_value, new_value) VALUES ('update', new.id, old.value, new.value))",
+        "CREATE TRIGGER test_delete AFTER DELETE ON test FOR EACH ROW EXECUTE (INSERT INTO audit (op, test_id, old_value) VALUES ('delete', old.id, old.value))",
+        "CREATE TRIGGER count_insert AFTER INSERT ON test FOR EACH ROW EXECUTE (INSERT INTO counts VALUES (new.name, 1) ON CONFLICT (name) DO UPDATE SET count = counts.count + 1)",
+        "CREATE TRIGGER count_delete AFTER DELETE ON test FOR EACH ROW EXECUTE (UPDATE counts SET count = count - 1 WHERE name = old.name)",
+    ];
+
+    trigger_insert: "INSERT INTO test (id, name, value) VALUES (3, 'c', 30), (4, 'a', 40)",
+    trigger_insert_error: "INSERT INTO test (id, name, value) VALUES (3, 'c', 30), (4, NULL, 40)",
+    trigger_insert_on_conflict: "INSERT INTO test VALUES (1, 'a', 11, 0), (3, 'c', 30, 0) ON CONFLICT (id) DO UPDATE SET value = excluded.value",
+    trigger_update: "UPDATE test SET value = value + 1 WHERE id = 1",
+    trigger_update_returning: "UPDATE test SET value = 0 RETURNING id, value, version",
+    trigger_update_set: "UPDATE test SET version = 7 WHERE id = 2",
+    trigger_delete: "DELETE FROM test WHERE name = 'a'",
+}
+
+test_mutation! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, created STRING DEFAULT NULL, upper STRING GENERATED ALWAYS AS (UPPER(name)) STORED)",
+        "CREATE TRIGGER test_created BEFORE INSERT ON test FOR EACH ROW EXECUTE SET created = 'now', name = COALESCE(new.name, 'anonymous')",
+        "CREATE TABLE loop (id INTEGER PRIMARY KEY)",
+        "CREATE TRIGGER loop_insert AFTER INSERT ON loop FOR EACH ROW EXECUTE (INSERT INTO loop VALUES (new.id + 1))",
+    ];
+
+    trigger_insert_set: "INSERT INTO test (id, name) VALUES (1, 'a'), (2, NULL) RETURNING *",
+    trigger_recursion: "INSERT INTO loop VALUES (1)",
+}
@@ -5,6 +5,7 @@ use toydb::sql::engine::{Engine, Transaction};
 use toydb::sql::execution::ResultSet;
 use toydb::sql::parser::Parser;
 use toydb::sql::plan::Plan;
+use toydb::sql::schema::DEFAULT_DATABASE;
 use toydb::sql::types::Row;
 
 use goldenfile::Mint;
@@ -80,7 +81,7 @@ macro_rules! test_query {
 
             // First, just try to generate a plan and execute it
             let result = Parser::new($query).parse()
-                .and_then(|ast| Plan::build(ast, &mut txn))
+                .and_then(|ast| Plan::build(ast, &mut txn, DEFAULT_DATABASE, false))
                 .and_then(|plan| plan.optimize(&mut txn))
                 .and_then(|plan| {
                     write!(f, "Explain:\n{}\n\n", plan)?;
@@ -128,7 +129,7 @@ macro_rules! test_query {
             write!(f, "{:#?}\n\n", ast)?;
 
             write!(f, "Plan: ")?;
-            let plan = match Plan::build(ast, &mut txn) {
+            let plan = match Plan::build(ast, &mut txn, DEFAULT_DATABASE, false) {
                 Ok(plan) => plan,
                 Err(err) => {
                     write!(f, "{:?}", err)?;
@@ -159,6 +160,10 @@ test_query! {
     bare: "SELECT",
     trailing_comma: "SELECT 1,",
     lowercase: "select 1",
+    syntax_multiline: "SELECT id, title\n    FROM movies\n    WHERE id = = 1",
+    syntax_unicode: "SELECT '👋', id FORM movies",
+    syntax_unexpected_char: "SELECT 1 # 2",
+    syntax_unterminated: "SELECT 'abc",
 
     field_single: "SELECT id FROM movies",
     field_multi: "SELECT id, title FROM movies",
@@ -175,6 +180,43 @@ test_query! {
     expr_dynamic: "SELECT 2020 - year AS age FROM movies",
     expr_static: "SELECT 1 + 2 * 3, 'abc' LIKE 'x%' AS nope",
     expr_mixed: "SELECT 1 + 2 * 3, 2020 - released AS age FROM movies",
+    expr_common: "SELECT UPPER(title), LENGTH(UPPER(title)) FROM movies WHERE id < 4",
+    expr_common_nested: "SELECT LEFT(UPPER(title), 3), LENGTH(UPPER(title)), LEFT(UPPER(title), 3) || '!' FROM movies WHERE id < 4",
+    expr_common_order: "SELECT UPPER(title) AS name FROM movies ORDER BY LENGTH(UPPER(title)), id LIMIT 3",
+    expr_common_having: "SELECT id, UPPER(title) AS name FROM movies HAVING UPPER(title) LIKE 'S%'",
+    expr_common_filter: "SELECT m.id, LOWER(g.name) FROM movies m JOIN genres g ON m.genre_id = g.id WHERE LOWER(g.name) LIKE 'a%' OR m.id = 1",
+    expr_common_volatile: "SELECT gen_random_uuid() = gen_random_uuid() FROM movies WHERE id = 1",
+    expr_conditional: "SELECT id, COALESCE(ultrahd, FALSE), NULLIF(studio_id, 4), GREATEST(rating, released - 2000), LEAST(rating, 8) FROM movies WHERE id < 6",
+    expr_math: "SELECT id, ABS(rating - 8), ROUND(rating), CEIL(rating), FLOOR(rating), MOD(released, 10), POWER(2, id), SIGN(rating - 8), SQRT(id) FROM movies WHERE id < 6",
+    expr_math_random: "SELECT COUNT(DISTINCT RANDOM()) FROM movies",
+    expr_coerce_key: "SELECT id, title FROM movies WHERE id = '3'",
+    expr_coerce_index: "SELECT id, title FROM movies WHERE genre_id IN ('2', '3') AND released > '2014' ORDER BY id",
+    expr_coerce_float: "SELECT id, rating * '10' FROM movies WHERE rating BETWEEN '8' AND '8.5'",
+    expr_coerce_boolean: "SELECT id FROM movies WHERE ultrahd = 'no'",
+    expr_coerce_expr: "SELECT id FROM movies WHERE released - 2000 = '4'",
+    expr_coerce_invalid: "SELECT id FROM movies WHERE id = 'three'",
+    expr_coerce_string: "SELECT id FROM movies WHERE title = '1'",
+    expr_coerce_cte: "WITH m AS (SELECT id, released / 10 * 10 AS decade FROM movies) SELECT id FROM m WHERE decade = '1970'",
+    expr_coerce_join: "SELECT m.id, s.name FROM movies m JOIN studios s ON m.studio_id = s.id WHERE s.id = '3'",
+    expr_coerce_group: "SELECT studio_id, COUNT(*) FROM movies GROUP BY studio_id HAVING studio_id < '3' ORDER BY studio_id",
+
+    subquery_exists: "SELECT title FROM movies m WHERE EXISTS (SELECT * FROM genres g WHERE g.id = m.genre_id AND g.name = 'Action')",
+    subquery_exists_uncorrelated: "SELECT name FROM genres WHERE EXISTS (SELECT * FROM movies WHERE rating > 8.5)",
+    subquery_not_exists: "SELECT name FROM studios s WHERE NOT EXISTS (SELECT * FROM movies m WHERE m.studio_id = s.id AND m.rating > 8)",
+    subquery_in: "SELECT * FROM genres WHERE id IN (SELECT genre_id FROM movies WHERE released > 2010)",
+    subquery_in_correlated: "SELECT name FROM studios s WHERE 2004 IN (SELECT released FROM movies m WHERE m.studio_id = s.id)",
+    subquery_not_exists_uncorrelated: "SELECT name FROM genres WHERE NOT EXISTS (SELECT * FROM movies WHERE rating > 9)",
+    subquery_not_in: "SELECT name FROM genres WHERE id NOT IN (SELECT genre_id FROM movies WHERE released < 2000)",
+    subquery_not_in_null: "SELECT title FROM movies WHERE ultrahd NOT IN (SELECT ultrahd FROM movies WHERE id = 1)",
+    subquery_not_in_null_value: "SELECT title FROM movies WHERE ultrahd NOT IN (SELECT ultrahd FROM movies WHERE id = 2)",
+    subquery_not_in_correlated: "SELECT name FROM studios s WHERE 1 NOT IN (SELECT genre_id FROM movies m WHERE m.studio_id = s.id)",
+    subquery_scalar: "SELECT title, rating FROM movies WHERE rating > (SELECT AVG(rating) FROM movies)",
+    subquery_scalar_correlated: "SELECT title, rating FROM movies m WHERE rating = (SELECT MAX(rating) FROM movies i WHERE i.genre_id = m.genre_id)",
+    subquery_scalar_count: "SELECT name FROM studios s WHERE (SELECT COUNT(*) FROM movies m WHERE m.studio_id = s.id) = 0",
+    subquery_scalar_column: "SELECT title FROM movies WHERE rating > (SELECT rating FROM movies)",
+    subquery_correlated_inequality: "SELECT name FROM studios s WHERE EXISTS (SELECT * FROM movies m WHERE m.studio_id > s.id)",
+    subquery_or: "SELECT name FROM genres WHERE id = 1 OR EXISTS (SELECT * FROM movies)",
+    subquery_select: "SELECT (SELECT MAX(rating) FROM movies)",
 
     as_: r#"SELECT 1, 2 b, 3 AS c, 4 AS "👋", id AS "some id" FROM movies"#,
     as_bare: "SELECT 1 AS",
@@ -203,7 +245,25 @@ test_query! {
     where_pk_or_partial: "SELECT * FROM movies WHERE (id = 2 OR id = 3 OR id = 4 OR id = 5) AND genre_id = 1",
     where_index: "SELECT * FROM movies WHERE genre_id = 2 ORDER BY id",
     where_index_or: "SELECT * FROM movies WHERE genre_id = 2 OR genre_id = 3 OR genre_id = 4 OR genre_id = 5 ORDER BY id",
+    where_index_intersection: "SELECT * FROM movies WHERE genre_id = 1 AND studio_id = 3",
     where_index_or_partial: "SELECT * FROM movies WHERE (genre_id = 2 OR genre_id = 3) AND studio_id = 2 ORDER BY id",
+    where_pk_in: "SELECT * FROM movies WHERE id IN (3, 5, NULL, 7)",
+    where_pk_in_null: "SELECT * FROM movies WHERE id IN (NULL)",
+    where_pk_not_in: "SELECT * FROM movies WHERE id NOT IN (3, 5, 7)",
+    where_pk_range: "SELECT id, title FROM movies WHERE id > 3 AND id <= 6",
+    where_pk_range_between: "SELECT id, title FROM movies WHERE id BETWEEN 8 AND 20",
+    where_pk_range_empty: "SELECT id, title FROM movies WHERE id > 5 AND id < 5",
+    where_pk_range_float: "SELECT id, title FROM movies WHERE id > 7.5",
+    where_pk_range_null: "SELECT id, title FROM movies WHERE id < NULL OR id > 8",
+    where_pk_range_or: "SELECT id, title FROM movies WHERE id < 3 OR id >= 9",
+    where_pk_range_reversed: "SELECT id, title FROM movies WHERE 3 >= id",
+    where_pk_range_string: "SELECT * FROM countries WHERE id >= 'fr' AND id < 'us'",
+    where_index_in: "SELECT * FROM movies WHERE genre_id IN (2, 3) ORDER BY id",
+    where_between: "SELECT * FROM movies WHERE released BETWEEN 2000 AND 2010 ORDER BY id",
+    where_not_between: "SELECT * FROM movies WHERE released NOT BETWEEN 2000 AND 2010 ORDER BY id",
+    where_like: "SELECT id, title FROM movies WHERE title LIKE '%s%' ORDER BY id",
+    where_like_field: "SELECT id, title FROM movies WHERE 'Heat' LIKE title",
+    where_like_integer: "SELECT id, title FROM movies WHERE id LIKE '1%'",
     where_field_unknown: "SELECT * FROM movies WHERE unknown",
     where_field_qualified: "SELECT movies.id, genres.id FROM movies, genres WHERE movies.id >= 3 AND genres.id = 1",
     where_field_ambiguous: "SELECT movies.id, genres.id FROM movies, genres WHERE id >= 3",
@@ -228,6 +288,11 @@ test_query! {
     order_aggregate: "SELECT studio_id, MAX(rating) FROM movies GROUP BY studio_id ORDER BY MAX(rating)",
     order_aggregate_noselect: "SELECT studio_id, MAX(rating) FROM movies GROUP BY studio_id ORDER BY MIN(rating)",
     order_group_by_noselect: "SELECT MAX(rating) FROM movies GROUP BY studio_id ORDER BY studio_id",
+    order_expr_noselect: "SELECT id, title FROM movies ORDER BY released % 4, id DESC",
+    order_expr_alias: "SELECT id, rating * 10 AS score FROM movies ORDER BY score % 10 DESC, score",
+    order_pk: "SELECT id, title FROM movies WHERE rating > 7 ORDER BY id",
+    order_pk_desc: "SELECT id, title FROM movies WHERE rating > 7 ORDER BY id DESC LIMIT 3",
+    order_pk_desc_nulls_first: "SELECT id, title FROM movies ORDER BY id DESC NULLS FIRST",
 }
 test_query! { with [
         "CREATE TABLE booleans (id INTEGER PRIMARY KEY, value BOOLEAN)",
@@ -249,6 +314,10 @@ test_query! { with [
     ];
     order_integer_asc: "SELECT * FROM integers ORDER BY value ASC",
     order_integer_desc: "SELECT * FROM integers ORDER BY value DESC",
+    order_integer_asc_nulls_last: "SELECT * FROM integers ORDER BY value ASC NULLS LAST",
+    order_integer_desc_nulls_first: "SELECT * FROM integers ORDER BY value DESC NULLS FIRST",
+    order_integer_nulls_default: "SELECT * FROM integers ORDER BY value NULLS FIRST",
+    order_integer_nulls_invalid: "SELECT * FROM integers ORDER BY value NULLS MIDDLE",
 }
 test_query! { with [
         "CREATE TABLE strings (id INTEGER PRIMARY KEY, value STRING)",
@@ -266,6 +335,179 @@ test_query! { with [
     order_string_asc: "SELECT * FROM strings ORDER BY value ASC",
     order_string_desc: "SELECT * FROM strings ORDER BY value DESC",
 }
+test_query! { with [
+        "CREATE TABLE prices (price DECIMAL PRIMARY KEY, discount NUMERIC INDEX, name STRING)",
+        "INSERT INTO prices VALUES
+            (DECIMAL '19.99', DECIMAL '0.10', 'Book'),
+            (DECIMAL '-5.00', NULL, 'Coupon'),
+            (DECIMAL '0.10', DECIMAL '0.1', 'Candy'),
+            (DECIMAL '1000', DECIMAL '0.25', 'Bike'),
+            (DECIMAL '0.2', NULL, 'Gum')
+        ",
+    ];
+    decimal_scan: "SELECT * FROM prices",
+    decimal_pk: "SELECT * FROM prices WHERE price = DECIMAL '0.1'",
+    decimal_index: "SELECT * FROM prices WHERE discount = DECIMAL '0.100' ORDER BY price",
+    decimal_aggregate: "SELECT SUM(price), AVG(price), MIN(price), MAX(price) FROM prices",
+    decimal_expr: "SELECT name, price * (1 - discount) AS total FROM prices WHERE discount IS NOT NULL",
+}
+test_query! { with [
+        "CREATE TABLE blobs (id BYTEA PRIMARY KEY, tag BYTEA INDEX, name STRING)",
+        r"INSERT INTO blobs VALUES
+            (x'cafe', x'01', 'Coffee'),
+            (x'', NULL, 'Empty'),
+            (x'00ff', x'02', 'Short'),
+            (BYTEA '\xdeadbeef', x'01', 'Beef'),
+            (BYTEA 'abc', NULL, 'Text')
+        ",
+    ];
+    bytes_scan: "SELECT * FROM blobs",
+    bytes_pk: "SELECT * FROM blobs WHERE id = x'DEADBEEF'",
+    bytes_index: "SELECT * FROM blobs WHERE tag = x'01' ORDER BY id",
+    bytes_order: "SELECT id, name FROM blobs ORDER BY id DESC",
+    bytes_functions: "SELECT name, LENGTH(id), id || x'00', CAST(id AS STRING) FROM blobs",
+}
+test_query! { with [
+        "CREATE TABLE events (id INTEGER PRIMARY KEY, tags JSON INDEX, payload JSON)",
+        r#"INSERT INTO events VALUES
+            (1, JSON '["a", "b"]', JSON '{"type": "click", "x": 10, "y": 20}'),
+            (2, JSON '[]', JSON '{"type": "view", "page": {"path": "/", "ms": 31}}'),
+            (3, JSON '["b", "a"]', JSON '{"type": "click", "x": 5, "y": null}'),
+            (4, NULL, JSON '{"y": null, "x": 5, "type": "click"}'),
+            (5, JSON '[ "a", "b" ]', NULL)
+        "#,
+    ];
+    json_scan: "SELECT * FROM events",
+    json_extract: "SELECT id, payload->'type', payload->>'type', payload->'page'->>'path' FROM events",
+    json_filter: "SELECT id FROM events WHERE payload->>'type' = 'click' AND (payload->>'x')::INTEGER > 5",
+    json_index: r#"SELECT id FROM events WHERE tags = JSON '["a","b"]' ORDER BY id"#,
+    json_group: "SELECT payload->>'type' AS type, COUNT(*) FROM events GROUP BY type ORDER BY type",
+    json_distinct: "SELECT payload, COUNT(*) FROM events WHERE payload->>'x' = '5' GROUP BY payload",
+    json_functions: "SELECT id, json_extract(payload, '$.page.ms'), json_set(payload, '$.seen', TRUE) FROM events WHERE id <= 2",
+}
+test_query! { with [
+        "CREATE TABLE cities (id INTEGER PRIMARY KEY, country STRING, name STRING, population INTEGER)",
+        "INSERT INTO cities VALUES
+            (1, 'fr', 'Paris', 2100000),
+            (2, 'fr', 'Lyon', 520000),
+            (3, 'us', 'Paris', 25000),
+            (4, 'us', 'Austin', 960000)
+        ",
+        "CREATE INDEX cities_name ON cities (name)",
+        "CREATE INDEX cities_country_name ON cities (country, name)",
+    ];
+    index_explicit: "SELECT * FROM cities WHERE name = 'Paris' ORDER BY id",
+    index_composite: "SELECT * FROM cities WHERE country = 'us' AND name = 'Paris'",
+    index_composite_in: "SELECT * FROM cities WHERE country IN ('fr', 'us') AND name = 'Paris' AND population > 100000",
+    index_composite_partial: "SELECT * FROM cities WHERE country = 'us' ORDER BY id",
+    index_range_between: "SELECT * FROM cities WHERE name BETWEEN 'Austin' AND 'Paris'",
+    index_range_greater: "SELECT * FROM cities WHERE name > 'M'",
+    index_range_like: "SELECT * FROM cities WHERE name LIKE 'Pa%'",
+    index_range_like_wildcard: "SELECT * FROM cities WHERE name LIKE '%s'",
+}
+test_query! { with [
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email STRING, score INTEGER)",
+        "INSERT INTO users VALUES
+            (1, 'Alice@example.com', 10),
+            (2, 'bob@example.com', 20),
+            (3, 'ALICE@EXAMPLE.COM', 30),
+            (4, NULL, 40)
+        ",
+        "CREATE INDEX ON users ((LOWER(email)))",
+        "CREATE INDEX users_score_email ON users (score, (LOWER(email)))",
+    ];
+    index_expression: "SELECT * FROM users WHERE LOWER(email) = 'alice@example.com' ORDER BY id",
+    index_expression_alias: "SELECT u.id FROM users u WHERE LOWER(u.email) = 'bob@example.com'",
+    index_expression_composite: "SELECT id FROM users WHERE score = 30 AND LOWER(email) = 'alice@example.com'",
+    index_expression_filter: "SELECT id FROM users WHERE LOWER(email) IN ('alice@example.com', 'x') AND score > 10",
+    index_expression_is_null: "SELECT id FROM users WHERE LOWER(email) IS NULL",
+    index_expression_mismatch: "SELECT id FROM users WHERE UPPER(email) = 'BOB@EXAMPLE.COM'",
+    index_expression_range: "SELECT id FROM users WHERE LOWER(email) > 'b'",
+}
+test_query! { with [
+        "CREATE TABLE staff (id INTEGER PRIMARY KEY, dept STRING, name STRING, salary INTEGER)",
+        "INSERT INTO staff VALUES
+            (1, 'eng', 'Alice', 100),
+            (2, 'eng', 'Bob', 90),
+            (3, 'ops', 'Carol', 80),
+            (4, 'sales', 'Dave', 70),
+            (5, 'eng', 'Erin', 95)
+        ",
+        "CREATE INDEX staff_dept ON staff (dept) INCLUDE (name)",
+        "UPDATE staff SET name = 'Robert' WHERE id = 2",
+    ];
+    index_only_count: "SELECT COUNT(*) FROM staff WHERE dept = 'eng'",
+    index_only_include: "SELECT name FROM staff WHERE dept = 'eng' ORDER BY name",
+    index_only_key: "SELECT id, dept FROM staff WHERE dept IN ('eng', 'ops') ORDER BY id",
+    index_only_range: "SELECT id, name FROM staff WHERE dept LIKE 'o%'",
+    index_only_uncovered: "SELECT name, salary FROM staff WHERE dept = 'eng' ORDER BY name",
+    index_only_uncovered_filter: "SELECT name FROM staff WHERE dept = 'eng' AND salary > 90 ORDER BY name",
+    index_only_order: "SELECT id, name FROM staff WHERE dept = 'eng' ORDER BY id",
+    index_only_limit: "SELECT id, name FROM staff WHERE dept IN ('eng', 'ops') LIMIT 2",
+    index_only_order_multi: "SELECT dept, name FROM staff WHERE dept IN ('ops', 'eng') ORDER BY dept",
+}
+test_query! { with [
+        "CREATE TABLE accounts (id UUID PRIMARY KEY, parent UUID INDEX, name STRING)",
+        "INSERT INTO accounts VALUES
+            (UUID 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', NULL, 'Root'),
+            (UUID '123e4567-e89b-12d3-a456-426614174000', UUID 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'Alice'),
+            (UUID 'f47ac10b-58cc-4372-a567-0e02b2c3d479', UUID 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'Bob'),
+            (UUID '00000000-0000-0000-0000-000000000000', NULL, 'Nil')
+        ",
+    ];
+    uuid_scan: "SELECT * FROM accounts",
+    uuid_pk: "SELECT * FROM accounts WHERE id = UUID 'F47AC10B-58CC-4372-A567-0E02B2C3D479'",
+    uuid_index: "SELECT name FROM accounts WHERE parent = UUID 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11' ORDER BY name",
+    uuid_join: "SELECT c.name, p.name FROM accounts c JOIN accounts p ON c.parent = p.id ORDER BY c.name",
+    uuid_random_per_row: "SELECT COUNT(*) FROM accounts GROUP BY gen_random_uuid()",
+}
+test_query! { with [
+        "CREATE TABLE events (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            name STRING,
+            created TIMESTAMP DEFAULT NOW()
+        )",
+        "INSERT INTO events (name) VALUES ('a'), ('b'), ('c')",
+        "ALTER TABLE events ADD COLUMN token UUID DEFAULT gen_random_uuid() UNIQUE",
+    ];
+    default_expr_per_row: "SELECT COUNT(*) FROM events GROUP BY id",
+    default_expr_now: "SELECT name, created > TIMESTAMP '2024-01-01' FROM events ORDER BY name",
+    default_expr_add_column: "SELECT COUNT(*) FROM events GROUP BY token",
+}
+test_query! { with [
+        "CREATE TABLE people (id INTEGER PRIMARY KEY, name STRING, age INTEGER)",
+        "INSERT INTO people VALUES (1, 'Alice', 34), (2, 'Bob', 12), (3, 'Carol', 51)",
+        "CREATE VIEW adults AS SELECT id, name FROM people WHERE age >= 18",
+        "CREATE VIEW named (person_id, person) AS SELECT id, name FROM people",
+        "CREATE VIEW adult_names AS SELECT name FROM adults",
+        "CREATE TABLE scratch (id INTEGER PRIMARY KEY)",
+        "CREATE VIEW dangling AS SELECT * FROM scratch",
+        "DROP TABLE scratch",
+    ];
+    view: "SELECT * FROM adults ORDER BY id",
+    view_columns: "SELECT person FROM named WHERE person_id > 1 ORDER BY person_id",
+    view_nested: "SELECT * FROM adult_names ORDER BY name",
+    view_join: "SELECT a.name, p.age FROM adults a JOIN people p ON a.id = p.id ORDER BY a.id",
+    view_cte_shadow: "WITH adults AS (SELECT 1 AS id) SELECT * FROM adults",
+    view_cte_hidden: "WITH people AS (SELECT 1 AS id) SELECT * FROM adults ORDER BY id",
+    view_dangling: "SELECT * FROM dangling",
+}
+test_query! { with [
+        "CREATE TABLE launches (at TIMESTAMP PRIMARY KEY, day DATE INDEX, name STRING)",
+        "INSERT INTO launches VALUES
+            (TIMESTAMP '1969-07-16 13:32:00', DATE '1969-07-16', 'Apollo 11'),
+            (TIMESTAMP '1957-10-04 19:28:34', DATE '1957-10-04', 'Sputnik 1'),
+            (TIMESTAMP '2021-12-25 12:20:00', DATE '2021-12-25', 'JWST'),
+            (TIMESTAMP '1990-04-24 12:33:51', DATE '1990-04-24', 'Hubble')
+        ",
+    ];
+    datetime_scan: "SELECT * FROM launches",
+    datetime_pk: "SELECT * FROM launches WHERE at = TIMESTAMP '1990-04-24 12:33:51'",
+    datetime_index: "SELECT * FROM launches WHERE day = DATE '1969-07-16'",
+    datetime_compare: "SELECT name FROM launches WHERE at > DATE '1970-01-01' ORDER BY day DESC",
+    datetime_extract: "SELECT name, EXTRACT(year FROM at), EXTRACT(dow FROM day) FROM launches",
+    datetime_trunc: "SELECT name, DATE_TRUNC('month', at) FROM launches",
+}
 test_query! {
     limit: "SELECT * FROM movies LIMIT 3",
     limit_zero: "SELECT * FROM movies LIMIT 0",
@@ -279,6 +521,57 @@ test_query! {
     limit_boolean: "SELECT * FROM movies LIMIT TRUE",
     limit_float: "SELECT * FROM movies LIMIT 3.14",
     limit_string: "SELECT * FROM movies LIMIT 'abc'",
+    limit_order: "SELECT id, title, released FROM movies ORDER BY released DESC LIMIT 3",
+    limit_order_offset: "SELECT id, title, released FROM movies ORDER BY released LIMIT 2 OFFSET 3",
+    limit_order_ties: "SELECT id, title, ultrahd FROM movies ORDER BY ultrahd DESC LIMIT 4",
+    limit_order_pk_desc: "SELECT id, title FROM movies ORDER BY id DESC LIMIT 2 OFFSET 1",
+    limit_filter: "SELECT id, title FROM movies WHERE rating > 8 LIMIT 2",
+    limit_fetch: "SELECT * FROM movies ORDER BY id FETCH FIRST 3 ROWS ONLY",
+    limit_fetch_next_row: "SELECT id, title FROM movies ORDER BY id OFFSET 2 ROWS FETCH NEXT ROW ONLY",
+    limit_fetch_and_limit: "SELECT * FROM movies LIMIT 2 FETCH FIRST 2 ROWS ONLY",
+    limit_fetch_no_rows: "SELECT * FROM movies FETCH FIRST 2 ONLY",
+    limit_with_ties: "SELECT id, title, rating FROM movies ORDER BY rating DESC FETCH FIRST 2 ROWS WITH TIES",
+    limit_with_ties_multi: "SELECT id, title, rating FROM movies ORDER BY rating DESC, id FETCH FIRST 2 ROWS WITH TIES",
+    limit_with_ties_noselect: "SELECT title FROM movies ORDER BY rating DESC OFFSET 3 ROWS FETCH NEXT 2 ROWS WITH TIES",
+    limit_with_ties_zero: "SELECT * FROM movies ORDER BY rating FETCH FIRST 0 ROWS WITH TIES",
+    limit_with_ties_no_order: "SELECT * FROM movies FETCH FIRST 2 ROWS WITH TIES",
+
+    distinct_on: "SELECT DISTINCT ON (studio_id) studio_id, title, rating FROM movies ORDER BY studio_id, rating DESC",
+    distinct_on_noorder: "SELECT DISTINCT ON (studio_id) studio_id, title FROM movies",
+    distinct_on_multiple: "SELECT DISTINCT ON (genre_id, studio_id) genre_id, studio_id, title FROM movies ORDER BY studio_id, genre_id, released",
+    distinct_on_noselect: "SELECT DISTINCT ON (released / 10) title FROM movies ORDER BY released / 10 DESC, title",
+    distinct_on_star: "SELECT DISTINCT ON (genre_id) * FROM movies ORDER BY genre_id, id DESC",
+    distinct_on_nulls: "SELECT DISTINCT ON (ultrahd) ultrahd, title FROM movies ORDER BY ultrahd NULLS FIRST, title",
+    distinct_on_aggregate: "SELECT DISTINCT ON (genre_id) genre_id, studio_id, COUNT(*) FROM movies GROUP BY genre_id, studio_id ORDER BY genre_id, COUNT(*) DESC, studio_id",
+    distinct_on_limit: "SELECT DISTINCT ON (studio_id) studio_id, title FROM movies ORDER BY studio_id, title LIMIT 2 OFFSET 1",
+    distinct_on_order_mismatch: "SELECT DISTINCT ON (studio_id) title FROM movies ORDER BY rating, studio_id",
+    distinct: "SELECT DISTINCT studio_id FROM movies",
+    distinct_multiple: "SELECT DISTINCT genre_id, studio_id FROM movies",
+    distinct_star: "SELECT DISTINCT * FROM (SELECT genre_id, ultrahd FROM movies) AS m",
+    distinct_order: "SELECT DISTINCT genre_id, studio_id FROM movies ORDER BY studio_id DESC",
+    distinct_order_hidden: "SELECT DISTINCT genre_id FROM movies ORDER BY studio_id",
+    distinct_aggregate: "SELECT DISTINCT COUNT(*) FROM movies GROUP BY genre_id",
+    distinct_limit: "SELECT DISTINCT studio_id FROM movies ORDER BY studio_id LIMIT 2 OFFSET 1",
+
+    set_union: "SELECT id, name FROM genres UNION SELECT id, name FROM studios",
+    set_union_all: "SELECT genre_id FROM movies UNION ALL SELECT id FROM genres",
+    set_union_distinct: "SELECT genre_id FROM movies UNION DISTINCT SELECT id FROM genres",
+    set_intersect: "SELECT genre_id FROM movies INTERSECT SELECT id FROM genres WHERE id > 1",
+    set_intersect_all: "SELECT genre_id FROM movies INTERSECT ALL SELECT genre_id FROM movies WHERE studio_id = 4",
+    set_except: "SELECT studio_id FROM movies EXCEPT SELECT id FROM studios WHERE country_id = 'us'",
+    set_except_all: "SELECT genre_id FROM movies EXCEPT ALL SELECT id FROM genres",
+    set_precedence: "SELECT id FROM genres UNION SELECT id FROM studios INTERSECT SELECT 4",
+    set_left_assoc: "SELECT id FROM studios EXCEPT SELECT 1 EXCEPT SELECT 2",
+    set_order_limit: "SELECT title AS name FROM movies WHERE rating > 8 UNION SELECT name FROM genres ORDER BY name DESC LIMIT 3",
+    set_order_qualified: "SELECT id FROM genres UNION SELECT id FROM studios ORDER BY genres.id",
+    set_order_in_operand: "SELECT id FROM genres ORDER BY id UNION SELECT id FROM studios",
+    set_column_mismatch: "SELECT id, name FROM genres UNION SELECT id FROM studios",
+    set_type_float: "SELECT rating FROM movies WHERE id <= 3 UNION SELECT 8 UNION SELECT 7.6",
+    set_type_decimal: "SELECT 1 UNION SELECT DECIMAL '1.0' UNION SELECT 2",
+    set_type_mismatch: "SELECT id FROM genres UNION SELECT name FROM genres",
+    set_nulls: "SELECT NULL UNION SELECT NULL INTERSECT SELECT NULL",
+    set_with: "WITH g AS (SELECT id FROM genres) SELECT id FROM g UNION ALL SELECT id FROM g ORDER BY id",
+    set_star: "SELECT * FROM genres UNION SELECT * FROM genres",
 
     offset: "SELECT * FROM movies OFFSET 3",
     offset_zero: "SELECT * FROM movies OFFSET 0",
@@ -340,6 +633,64 @@ test_query! {
     join_right_outer: "SELECT g.id AS genre_id, m.id AS movie_id FROM genres g RIGHT OUTER JOIN movies m ON m.id = g.id",
     join_right_truncate: "SELECT m.id AS movie_id, g.id AS genre_id FROM movies m RIGHT JOIN genres g ON m.id = g.id",
 
+    join_hash_build_left: "SELECT g.name, m.title FROM genres g JOIN movies m ON m.genre_id = g.id",
+    join_hash_build_right: "SELECT m.title, g.name FROM movies m JOIN genres g ON m.genre_id = g.id",
+    join_hash_null: "SELECT a.title, b.title FROM movies a JOIN movies b ON a.ultrahd = b.ultrahd WHERE a.id < 3 AND b.id < 6",
+    join_hash_null_outer: "SELECT a.title, b.title FROM movies a LEFT JOIN movies b ON a.ultrahd = b.ultrahd AND b.id < 6 WHERE a.id < 4",
+    join_hash_residual: "SELECT m.title, s.name FROM movies m JOIN studios s ON m.studio_id = s.id AND m.released < 2000 + s.id * 5",
+    join_hash_residual_outer: "SELECT g.name, m.title FROM genres g LEFT JOIN movies m ON m.genre_id = g.id AND m.id < g.id * 3",
+    join_merge_chain: "SELECT m.title, g.name, s.name FROM movies m JOIN genres g ON m.id = g.id JOIN studios s ON g.id = s.id",
+    join_merge_duplicates: "SELECT a.title, b.title FROM movies a JOIN movies b ON a.genre_id = b.genre_id WHERE a.genre_id = 1 AND b.genre_id = 1 ORDER BY a.id, b.id",
+    join_merge_inner: "SELECT m.title, g.name FROM movies m JOIN genres g ON m.id = g.id",
+    join_merge_outer: "SELECT g.name, m.title FROM genres g LEFT JOIN movies m ON g.id = m.id",
+    join_merge_unsorted: "SELECT m.title, g.name FROM movies m JOIN genres g ON m.genre_id = g.id",
+
+    join_lateral: r#"
+        SELECT g.name, m.title, m.rating
+        FROM genres g CROSS JOIN LATERAL (
+            SELECT title, rating FROM movies WHERE genre_id = g.id ORDER BY rating DESC LIMIT 2
+        ) m
+        ORDER BY g.id, m.rating DESC
+    "#,
+    join_lateral_comma: "SELECT s.name, c.n FROM studios s, LATERAL (SELECT COUNT(*) FROM movies m WHERE m.studio_id = s.id AND m.rating > 8) c (n)",
+    join_lateral_left: "SELECT g.name, m.title FROM genres g LEFT JOIN LATERAL (SELECT title FROM movies WHERE genre_id = g.id AND rating >= 8.2) m ON TRUE",
+    join_lateral_left_predicate: "SELECT g.name, m.title FROM genres g LEFT JOIN LATERAL (SELECT title, rating FROM movies WHERE genre_id = g.id) m ON m.rating > 8",
+    join_lateral_empty: "SELECT * FROM (SELECT * FROM genres WHERE id > 5) g, LATERAL (SELECT title FROM movies WHERE genre_id = g.id) m",
+    join_lateral_nested: r#"
+        SELECT s.name, m.title, m.genre
+        FROM studios s, LATERAL (
+            SELECT m.title, g.name FROM movies m, LATERAL (SELECT name FROM genres WHERE id = m.genre_id) g
+            WHERE m.studio_id = s.id
+        ) m (title, genre)
+        WHERE s.id = 1
+    "#,
+    join_lateral_right: "SELECT * FROM genres g RIGHT JOIN LATERAL (SELECT * FROM movies WHERE genre_id = g.id) m ON TRUE",
+    join_lateral_table: "SELECT * FROM genres g, LATERAL movies",
+    join_lateral_unreferenced: "SELECT * FROM genres g, (SELECT * FROM movies WHERE genre_id = g.id) m",
+    join_lateral_limit: "SELECT * FROM genres g, LATERAL (SELECT title FROM movies WHERE genre_id = g.id LIMIT g.id) m",
+
+    subquery_from: "SELECT * FROM (SELECT id, name FROM genres WHERE id > 1) AS g (i, n) ORDER BY n",
+    subquery_from_join: "SELECT m.title, g.name FROM movies m JOIN (SELECT * FROM genres WHERE name != 'Action') g ON m.genre_id = g.id WHERE m.rating > 8",
+    subquery_from_alias_missing: "SELECT * FROM (SELECT 1)",
+
+    comment_block: "SELECT /* a comment */ id, /* another\n one */ name FROM genres ORDER BY id",
+    comment_unterminated: "SELECT * FROM genres /* a comment",
+    hint_force_index: "SELECT /*+ FORCE_INDEX(movies studio_id) */ * FROM movies WHERE genre_id = 1 AND studio_id = 3",
+    hint_force_index_alias: "SELECT /*+ FORCE_INDEX(m genre_id) */ m.title FROM movies m WHERE m.id = 1 AND m.genre_id = 1",
+    hint_force_index_unusable: "SELECT /*+ FORCE_INDEX(movies studio_id) */ * FROM movies WHERE genre_id = 2 ORDER BY id",
+    hint_force_index_missing: "SELECT /*+ FORCE_INDEX(movies title) */ * FROM movies WHERE title = 'Heat'",
+    hint_no_index: "SELECT /*+ NO_INDEX(movies) */ * FROM movies WHERE genre_id = 1 AND studio_id = 3",
+    hint_no_hash_join: "SELECT /*+ NO_HASH_JOIN */ m.title, g.name FROM movies m JOIN genres g ON m.genre_id = g.id",
+    hint_no_merge_join: "SELECT /*+ NO_MERGE_JOIN */ m.title, g.name FROM movies m JOIN genres g ON m.id = g.id",
+    hint_no_joins: "SELECT /*+ NO_HASH_JOIN, NO_MERGE_JOIN */ m.title, g.name FROM movies m JOIN genres g ON m.id = g.id",
+    hint_subquery: "SELECT title FROM movies WHERE genre_id IN (SELECT /*+ NO_INDEX(movies) */ id FROM genres WHERE name = 'Action') AND studio_id = 2",
+    hint_not_select: "SELECT * FROM movies /*+ NO_INDEX(movies) */ WHERE genre_id = 3 ORDER BY id",
+    hint_unknown: "SELECT /*+ NO_JOINS */ * FROM genres",
+    hint_arguments: "SELECT /*+ NO_INDEX */ * FROM genres",
+
+    prune_columns: "SELECT title FROM movies WHERE rating > 8 ORDER BY released",
+    prune_columns_join: "SELECT m.title, g.name FROM movies m JOIN genres g ON m.genre_id = g.id ORDER BY m.id",
+
     agg_count_star: "SELECT COUNT(*) FROM movies",
     agg_expr: "SELECT SUM(rating * 10) / COUNT(*) FROM movies",
     agg_nested: "SELECT MAX(MIN(rating)) FROM movies",
@@ -348,6 +699,21 @@ test_query! {
     agg_norows_group: "SELECT MIN(id), MAX(id), SUM(id), COUNT(id), AVG(id) FROM movies WHERE FALSE GROUP BY id",
     agg_const: "SELECT MIN(3), MAX(3), SUM(3), COUNT(3), AVG(3)",
     agg_const_from: "SELECT MIN(3), MAX(3), SUM(3), COUNT(3), AVG(3) FROM genres",
+    agg_norows_extra: "SELECT STDDEV(id), VARIANCE(id), STRING_AGG(title, ','), BOOL_AND(TRUE), BOOL_OR(TRUE) FROM movies WHERE FALSE",
+    agg_string_agg_group: "SELECT studio_id, STRING_AGG(title, '; ' ORDER BY released DESC, title) FROM movies GROUP BY studio_id ORDER BY studio_id",
+    agg_string_agg_having: "SELECT studio_id FROM movies GROUP BY studio_id HAVING STRING_AGG(title, ',' ORDER BY id) LIKE 'S%' ORDER BY studio_id",
+    agg_string_agg_integer: "SELECT STRING_AGG(id, ',') FROM movies",
+    agg_string_agg_separator_expr: "SELECT STRING_AGG(title, title) FROM movies",
+    agg_string_agg_arity: "SELECT STRING_AGG(title) FROM movies",
+    agg_order_unsupported: "SELECT MAX(id ORDER BY id) FROM movies",
+    agg_order_function: "SELECT UPPER(title ORDER BY id) FROM movies",
+    agg_distinct: "SELECT COUNT(DISTINCT genre_id), SUM(DISTINCT genre_id), COUNT(genre_id) FROM movies",
+    agg_distinct_group: "SELECT studio_id, COUNT(DISTINCT genre_id), AVG(DISTINCT rating), MAX(DISTINCT released) FROM movies GROUP BY studio_id ORDER BY studio_id",
+    agg_distinct_string_agg: "SELECT STRING_AGG(DISTINCT country_id, ',' ORDER BY country_id DESC) FROM studios",
+    agg_distinct_rollup: "SELECT genre_id, COUNT(DISTINCT studio_id) FROM movies GROUP BY ROLLUP (genre_id) ORDER BY genre_id",
+    agg_distinct_having: "SELECT studio_id FROM movies GROUP BY studio_id HAVING COUNT(DISTINCT genre_id) > 1 ORDER BY studio_id",
+    agg_distinct_star: "SELECT COUNT(DISTINCT *) FROM movies",
+    agg_distinct_function: "SELECT UPPER(DISTINCT title) FROM movies",
 }
 test_query! { with [
         "CREATE TABLE booleans (id INTEGER PRIMARY KEY, b BOOLEAN)",
@@ -355,6 +721,19 @@ test_query! { with [
     ];
     agg_boolean: "SELECT MIN(b), MAX(b), SUM(b), COUNT(b), AVG(b) FROM booleans WHERE b IS NOT NULL",
     agg_boolean_null: "SELECT MIN(b), MAX(b), SUM(b), COUNT(b), AVG(b) FROM booleans",
+    agg_boolean_and_or: "SELECT BOOL_AND(b), BOOL_OR(b) FROM booleans",
+    agg_boolean_and_or_true: "SELECT BOOL_AND(b), BOOL_OR(b) FROM booleans WHERE b IS NULL OR b = TRUE",
+    agg_boolean_and_or_null: "SELECT BOOL_AND(b), BOOL_OR(b) FROM booleans WHERE b IS NULL",
+    agg_boolean_and_integer: "SELECT BOOL_AND(id) FROM booleans",
+    agg_boolean_distinct: "SELECT COUNT(DISTINCT b), BOOL_OR(DISTINCT b) FROM booleans",
+}
+test_query! { with [
+        "CREATE TABLE digits (d INTEGER PRIMARY KEY)",
+        "INSERT INTO digits VALUES (0), (1), (2), (3), (4), (5), (6), (7), (8), (9)",
+    ];
+    batch_filter: "SELECT COUNT(*), SUM(a.d * 1000 + b.d * 100 + c.d * 10 + e.d) FROM digits a, digits b, digits c, digits e WHERE (a.d + b.d + c.d + e.d) % 7 = 0",
+    batch_projection: "WITH t AS (SELECT a.d * 1000 + b.d * 100 + c.d * 10 + e.d AS n, UPPER('x' || e.d) AS s FROM digits a, digits b, digits c, digits e) SELECT n, s FROM t WHERE n > 9997 OR n = 1023 OR n = 1024",
+    batch_error: "WITH t AS (SELECT a.d * 1000 + b.d * 100 + c.d * 10 + e.d AS n FROM digits a, digits b, digits c, digits e) SELECT n FROM t WHERE 1 / (n - 5000) > 0",
 }
 test_query! { with [
         "CREATE TABLE floats (id INTEGER PRIMARY KEY, f FLOAT)",
@@ -362,6 +741,7 @@ test_query! { with [
     ];
     agg_float: "SELECT MIN(f), MAX(f), SUM(f), COUNT(f), AVG(f) FROM floats WHERE f IS NOT NULL",
     agg_float_null: "SELECT MIN(f), MAX(f), SUM(f), COUNT(f), AVG(f) FROM floats",
+    agg_float_stddev: "SELECT STDDEV(f), VARIANCE(f) FROM floats",
 }
 test_query! { with [
         "CREATE TABLE integers (id INTEGER PRIMARY KEY, i INTEGER)",
@@ -369,6 +749,8 @@ test_query! { with [
     ];
     agg_integer: "SELECT MIN(i), MAX(i), SUM(i), COUNT(i), AVG(i) FROM integers WHERE i IS NOT NULL",
     agg_integer_null: "SELECT MIN(i), MAX(i), SUM(i), COUNT(i), AVG(i) FROM integers",
+    agg_integer_stddev: "SELECT STDDEV(i), VARIANCE(i) FROM integers",
+    agg_integer_stddev_single: "SELECT STDDEV(i), VARIANCE(i) FROM integers WHERE i = 7",
 }
 test_query! { with [
         "CREATE TABLE strings (id INTEGER PRIMARY KEY, s STRING)",
@@ -385,6 +767,11 @@ test_query! { with [
     ];
     agg_string: "SELECT MIN(s), MAX(s), SUM(s), COUNT(s), AVG(s) FROM strings WHERE s IS NOT NULL",
     agg_string_null: "SELECT MIN(s), MAX(s), SUM(s), COUNT(s), AVG(s) FROM strings",
+    agg_string_agg: "SELECT STRING_AGG(s, ',') FROM strings",
+    agg_string_agg_null_separator: "SELECT STRING_AGG(s, NULL) FROM strings",
+    agg_string_agg_order: "SELECT STRING_AGG(s, ', ' ORDER BY s DESC) FROM strings",
+    agg_string_agg_order_nulls: "SELECT STRING_AGG(s, ', ' ORDER BY s NULLS FIRST, id) FROM strings",
+    agg_string_stddev: "SELECT STDDEV(s) FROM strings",
 }
 test_query! {
     group_simple: "SELECT studio_id, MAX(rating) FROM movies GROUP BY studio_id ORDER BY studio_id",
@@ -401,6 +788,26 @@ test_query! {
     group_expr_aggr_selfref: "SELECT studio_id, SUM(rating * 10) / COUNT(*) + studio_id FROM movies GROUP BY studio_id ORDER BY studio_id",
     group_expr_aggr_nogroupref: "SELECT studio_id, SUM(rating * 10) / COUNT(*) + id FROM movies GROUP BY studio_id ORDER BY studio_id",
     group_expr_multigroup: "SELECT studio_id + genre_id AS multi, MAX(rating) AS rating FROM movies GROUP BY studio_id, genre_id ORDER BY rating, multi",
+    group_rollup: "SELECT studio_id, genre_id, COUNT(*) FROM movies GROUP BY ROLLUP (studio_id, genre_id) ORDER BY studio_id NULLS LAST, genre_id NULLS LAST",
+    group_rollup_single: "SELECT genre_id, MAX(rating) FROM movies GROUP BY ROLLUP (genre_id) ORDER BY genre_id NULLS FIRST",
+    group_rollup_expr: "SELECT studio_id * 2 AS twice, SUM(rating) FROM movies GROUP BY ROLLUP (twice) ORDER BY twice",
+    group_rollup_having: "SELECT studio_id, COUNT(*) FROM movies GROUP BY ROLLUP (studio_id) HAVING COUNT(*) > 2 ORDER BY studio_id",
+    group_rollup_empty: "SELECT studio_id, COUNT(*) FROM movies WHERE FALSE GROUP BY ROLLUP (studio_id)",
+    group_cube: "SELECT studio_id, genre_id, COUNT(*) FROM movies GROUP BY CUBE (studio_id, genre_id) ORDER BY studio_id NULLS LAST, genre_id NULLS LAST",
+    group_cube_empty: "SELECT genre_id, COUNT(*) FROM movies WHERE FALSE GROUP BY CUBE (genre_id)",
+    group_rollup_multi: "SELECT COUNT(*) FROM movies GROUP BY ROLLUP (studio_id), genre_id",
+    group_expr_nested: "SELECT released / 10 * 10 + 9 AS decade_end, COUNT(*) FROM movies GROUP BY released / 10 * 10 ORDER BY decade_end",
+    group_expr_function: "SELECT UPPER(title) LIKE '%THE%' AS the, COUNT(*) FROM movies GROUP BY UPPER(title) LIKE '%THE%' ORDER BY the",
+    group_expr_order: "SELECT COUNT(*) FROM movies GROUP BY studio_id % 2 ORDER BY studio_id % 2 DESC",
+    group_expr_having: "SELECT studio_id % 2, COUNT(*) FROM movies GROUP BY studio_id % 2 HAVING studio_id % 2 = 1",
+    group_expr_ungrouped: "SELECT title, COUNT(*) FROM movies GROUP BY studio_id",
+    group_expr_ungrouped_partial: "SELECT studio_id + genre_id, COUNT(*) FROM movies GROUP BY studio_id",
+    group_expr_aggregate: "SELECT COUNT(*) FROM movies GROUP BY MAX(rating)",
+    group_ordinal: "SELECT genre_id, studio_id, COUNT(*) FROM movies GROUP BY 2, 1 ORDER BY genre_id, studio_id",
+    group_ordinal_expr: "SELECT studio_id * 2, COUNT(*) FROM movies GROUP BY 1 ORDER BY studio_id * 2",
+    group_ordinal_invalid: "SELECT studio_id, COUNT(*) FROM movies GROUP BY 3",
+    group_ordinal_aggregate: "SELECT studio_id, COUNT(*) FROM movies GROUP BY 2",
+    group_label_qualified: "SELECT m.studio_id AS s, COUNT(*) FROM movies m GROUP BY s ORDER BY s",
 
     having: "SELECT studio_id, MAX(rating) AS rating FROM movies GROUP BY studio_id HAVING rating > 8 ORDER BY studio_id",
     having_aggr: "SELECT studio_id, MAX(rating) FROM movies GROUP BY studio_id HAVING MIN(rating) > 7 ORDER BY studio_id",
@@ -409,4 +816,294 @@ test_query! {
     having_nogroup: "SELECT id, rating FROM movies HAVING rating > 8 ORDER BY id",
     having_noselect: "SELECT studio_id FROM movies GROUP BY studio_id HAVING MAX(rating) > 8 ORDER BY studio_id",
     having_noaggr: "SELECT studio_id, MAX(rating) AS rating FROM movies GROUP BY studio_id HAVING studio_id >= 3 ORDER BY studio_id",
+
+    with_: "WITH good AS (SELECT id, title, rating FROM movies WHERE rating >= 8) SELECT * FROM good ORDER BY id",
+    with_columns: "WITH good (movie_id, name) AS (SELECT id, title FROM movies WHERE rating >= 8) SELECT name FROM good WHERE movie_id > 1 ORDER BY movie_id",
+    with_columns_excess: "WITH good (a, b, c) AS (SELECT id, title FROM movies) SELECT * FROM good",
+    with_multi: r#"
+        WITH sf AS (SELECT * FROM movies WHERE genre_id = 1),
+            recent AS (SELECT id, title, released FROM sf WHERE released > 2000)
+        SELECT title, released FROM recent ORDER BY released"#,
+    with_join: r#"
+        WITH best AS (SELECT studio_id, MAX(rating) AS rating FROM movies GROUP BY studio_id)
+        SELECT s.name, b.rating FROM studios s JOIN best b ON s.id = b.studio_id ORDER BY s.name"#,
+    with_self_join: r#"
+        WITH m AS (SELECT id, title, studio_id FROM movies WHERE released < 2000)
+        SELECT a.title, b.title FROM m a JOIN m b ON a.studio_id = b.studio_id AND a.id < b.id"#,
+    with_shadow: "WITH movies AS (SELECT id, title FROM movies WHERE id = 1) SELECT * FROM movies",
+    with_duplicate: "WITH a AS (SELECT 1), a AS (SELECT 2) SELECT * FROM a",
+    with_forward_ref: "WITH a AS (SELECT * FROM b), b AS (SELECT * FROM movies) SELECT * FROM a",
+    with_nested: "WITH a AS (WITH b AS (SELECT id FROM genres) SELECT * FROM b) SELECT * FROM a",
+    with_scope: "WITH a AS (WITH b AS (SELECT id FROM genres) SELECT * FROM b) SELECT * FROM b",
+    with_noselect: "WITH a AS (SELECT 1)",
+
+    values: "VALUES (1, 'a'), (2, 'b')",
+    values_expr: "VALUES (1 + 2, UPPER('a')), (NULL, 'b' || 'c')",
+    values_order: "VALUES (1, 'a'), (3, 'c'), (2, 'b') ORDER BY column1 DESC LIMIT 2",
+    values_length: "VALUES (1, 'a'), (2)",
+    values_column_ref: "VALUES (id)",
+    values_from: "SELECT * FROM (VALUES (1, 'a'), (2, 'b')) AS v",
+    values_from_columns: "SELECT id, name FROM (VALUES (1, 'a'), (2, 'b')) AS v (id, name) WHERE id > 1",
+    values_from_columns_partial: "SELECT * FROM (VALUES (1, 'a'), (2, 'b')) v (id)",
+    values_from_columns_excess: "SELECT * FROM (VALUES (1, 'a')) AS v (a, b, c)",
+    values_from_alias_missing: "SELECT * FROM (VALUES (1, 'a'))",
+    values_from_select: "SELECT * FROM (SELECT 1) AS v",
+    values_join: "SELECT m.title, v.label FROM movies m JOIN (VALUES (1, 'first'), (3, 'third')) AS v (id, label) ON m.id = v.id ORDER BY m.id",
+    values_with: "WITH v (id) AS (VALUES (1), (2)) SELECT * FROM v",
+}
+test_query! { with [
+        "CREATE TABLE critics (id INTEGER PRIMARY KEY, name STRING NOT NULL)",
+        "CREATE TABLE reviews (
+            id INTEGER PRIMARY KEY AUTO_INCREMENT,
+            critic_id INTEGER NOT NULL REFERENCES critics,
+            source STRING NOT NULL UNIQUE,
+            score FLOAT INDEX,
+            posted TIMESTAMP DEFAULT NOW()
+        )",
+    ];
+    show_tables: "SHOW TABLES",
+    show_tables_bare: "SHOW",
+    show_create_table: "SHOW CREATE TABLE reviews",
+    show_create_table_unknown: "SHOW CREATE TABLE unknown",
+    describe: "DESCRIBE reviews",
+    describe_unknown: "DESCRIBE unknown",
+    describe_bare: "DESCRIBE",
+}
+test_query! { with [
+        "CREATE TABLE critics (id INTEGER PRIMARY KEY, name STRING NOT NULL)",
+        "COMMENT ON TABLE critics IS 'Film critics'",
+        "COMMENT ON COLUMN critics.name IS 'The critic''s full name'",
+    ];
+    show_create_table_comment: "SHOW CREATE TABLE critics",
+    information_schema_tables_comment: "SELECT * FROM information_schema.tables WHERE table_name = 'critics'",
+    information_schema_columns_comment: "SELECT column_name, comment FROM information_schema.columns WHERE table_name = 'critics'",
+}
+
+test_query! { with [
+        "CREATE TABLE categories (id INTEGER PRIMARY KEY, name STRING NOT NULL)",
+        "INSERT INTO categories VALUES (1, 'odd'), (2, 'even')",
+        "CREATE TABLE items (
+            id INTEGER PRIMARY KEY,
+            category_id INTEGER NOT NULL INDEX REFERENCES categories,
+            code INTEGER NOT NULL INDEX,
+            price INTEGER NOT NULL
+        )",
+        "INSERT INTO items VALUES (1, 2, 10, 1), (2, 1, 20, 2), (3, 2, 30, 3), (4, 1, 40, 4), (5, 2, 50, 5), (6, 1, 60, 6), (7, 2, 70, 7), (8, 1, 80, 8), (9, 2, 90, 9), (10, 1, 100, 10), (11, 2, 110, 11), (12, 1, 120, 12), (13, 2, 130, 13), (14, 1, 140, 14), (15, 2, 150, 15), (16, 1, 160, 16), (17, 2, 170, 17), (18, 1, 180, 18), (19, 2, 190, 19), (20, 1, 200, 20)",
+        "ANALYZE categories",
+        "ANALYZE items",
+    ];
+    analyze_scan_unselective: "SELECT * FROM items WHERE category_id = 1 ORDER BY id",
+    analyze_index_selective: "SELECT * FROM items WHERE code = 70",
+    analyze_index_choice: "SELECT * FROM items WHERE category_id = 1 AND code = 80",
+    analyze_index_in: "SELECT * FROM items WHERE code IN (10, 20, 30, 40, 50, 60)",
+    analyze_key_lookup: "SELECT * FROM items WHERE id = 3",
+    analyze_range_selective: "SELECT * FROM items WHERE code > 170",
+    analyze_range_unselective: "SELECT * FROM items WHERE code > 50",
+    analyze_range_between: "SELECT * FROM items WHERE code BETWEEN 30 AND 50 AND price > 3",
+    analyze_range_pk: "SELECT * FROM items WHERE id < 3 AND code > 170",
+    analyze_join_build: "SELECT * FROM categories c JOIN items i ON i.category_id = c.id WHERE i.price > 16",
+    analyze_join_nested: "SELECT * FROM items i JOIN categories c ON i.category_id = c.id WHERE i.id = 3",
+    analyze_join_outer: "SELECT * FROM categories c LEFT JOIN items i ON i.category_id = c.id AND i.price > 16",
+    analyze_join_unanalyzed: "SELECT m.title, i.id FROM movies m JOIN items i ON i.id = m.id WHERE m.genre_id = 3",
+    analyze_hint_force_index: "SELECT /*+ FORCE_INDEX(items category_id) */ * FROM items WHERE category_id = 1 ORDER BY id",
+    analyze_hint_force_index_range: "SELECT /*+ FORCE_INDEX(items code) */ * FROM items WHERE code > 50",
+    analyze_hint_no_index: "SELECT /*+ NO_INDEX(items) */ * FROM items WHERE code = 70",
+    analyze_hint_fixed_join_order: "SELECT /*+ FIXED_JOIN_ORDER */ * FROM categories c JOIN items i ON i.category_id = c.id WHERE i.price > 16",
+}
+
+test_query! { with [
+        "CREATE TABLE readings (id INTEGER PRIMARY KEY, sensor INTEGER NOT NULL INDEX, day INTEGER NOT NULL INDEX)",
+        "INSERT INTO readings VALUES (1, 1, 1), (2, 1, 2), (3, 1, 3), (4, 1, 4), (5, 1, 5), (6, 2, 1), (7, 2, 2), (8, 2, 3), (9, 2, 4), (10, 2, 5), (11, 3, 1), (12, 3, 2), (13, 3, 3), (14, 3, 4), (15, 3, 5), (16, 4, 1), (17, 4, 2), (18, 4, 3), (19, 4, 4), (20, 4, 5), (21, 5, 1), (22, 5, 2), (23, 5, 3), (24, 5, 4), (25, 5, 5)",
+        "ANALYZE readings",
+    ];
+    analyze_intersection: "SELECT * FROM readings WHERE sensor = 2 AND day = 3",
+    analyze_intersection_unselective: "SELECT * FROM readings WHERE sensor IN (1, 2, 3, 4) AND day IN (1, 2) ORDER BY id",
+    analyze_intersection_filter: "SELECT * FROM readings WHERE sensor = 4 AND day = 1 AND id > 10",
+}
+
+test_query! { with [
+        "CREATE TABLE colors (id INTEGER PRIMARY KEY, name STRING NOT NULL)",
+        "INSERT INTO colors VALUES (1, 'red'), (2, 'green'), (3, 'blue')",
+        "CREATE TABLE shapes (id INTEGER PRIMARY KEY, name STRING NOT NULL)",
+        "INSERT INTO shapes VALUES (1, 'circle'), (2, 'square'), (3, 'triangle'), (4, 'star')",
+        "CREATE TABLE products (
+            id INTEGER PRIMARY KEY,
+            color_id INTEGER NOT NULL REFERENCES colors,
+            shape_id INTEGER NOT NULL REFERENCES shapes,
+            price INTEGER NOT NULL
+        )",
+        "INSERT INTO products VALUES (1, 2, 2, 1), (2, 3, 3, 2), (3, 1, 4, 3), (4, 2, 1, 4), (5, 3, 2, 5), (6, 1, 3, 6), (7, 2, 4, 7), (8, 3, 1, 8), (9, 1, 2, 9), (10, 2, 3, 10), (11, 3, 4, 11), (12, 1, 1, 12), (13, 2, 2, 13), (14, 3, 3, 14), (15, 1, 4, 15), (16, 2, 1, 16), (17, 3, 2, 17), (18, 1, 3, 18), (19, 2, 4, 19), (20, 3, 1, 20), (21, 1, 2, 21), (22, 2, 3, 22), (23, 3, 4, 23), (24, 1, 1, 24)",
+        "ANALYZE colors",
+        "ANALYZE shapes",
+        "ANALYZE products",
+    ];
+    join_order_star: "SELECT p.id, c.name, s.name FROM products p JOIN colors c ON p.color_id = c.id JOIN shapes s ON p.shape_id = s.id ORDER BY p.id",
+    join_order_star_all: "SELECT * FROM products p JOIN colors c ON p.color_id = c.id JOIN shapes s ON p.shape_id = s.id WHERE p.price > 20",
+    join_order_selective: "SELECT p.id, c.name, s.name FROM colors c JOIN shapes s ON TRUE JOIN products p ON p.color_id = c.id AND p.shape_id = s.id WHERE p.price < 3",
+    join_order_cross: "SELECT p.id, c.name, s.name FROM products p, colors c, shapes s WHERE p.color_id = c.id AND p.shape_id = s.id AND p.id = 5",
+    join_order_outer: "SELECT p.id, c.name, s.name FROM products p LEFT JOIN colors c ON p.color_id = c.id JOIN shapes s ON p.shape_id = s.id WHERE p.price > 22",
+    join_order_unanalyzed: "SELECT m.title, g.name, p.id FROM movies m JOIN genres g ON m.genre_id = g.id JOIN products p ON p.id = m.id",
+    join_order_hint_fixed: "SELECT /*+ FIXED_JOIN_ORDER */ p.id, c.name, s.name FROM colors c JOIN shapes s ON TRUE JOIN products p ON p.color_id = c.id AND p.shape_id = s.id WHERE p.price < 3",
+}
+
+test_query! { with [
+        "CREATE TABLE critics (
+            id INTEGER PRIMARY KEY AUTO_INCREMENT,
+            name STRING NOT NULL UNIQUE,
+            mentor_id INTEGER REFERENCES critics ON DELETE SET NULL DEFERRABLE INITIALLY DEFERRED,
+            score FLOAT DEFAULT 5.0,
+            UNIQUE (mentor_id, score)
+        )",
+        "CREATE INDEX critics_score ON critics (score) INCLUDE (name)",
+        "CREATE VIEW mentors AS SELECT * FROM critics WHERE mentor_id IS NULL",
+    ];
+    information_schema_tables: "SELECT * FROM information_schema.tables",
+    information_schema_columns: "SELECT * FROM information_schema.columns WHERE table_name = 'critics'",
+    information_schema_indexes: "SELECT * FROM information_schema.indexes",
+    information_schema_constraints: "SELECT * FROM information_schema.constraints WHERE table_name IN ('critics', 'movies')",
+    information_schema_join: "SELECT t.table_name, COUNT(*) FROM information_schema.tables t JOIN information_schema.columns c ON c.table_name = t.table_name WHERE t.table_type = 'BASE TABLE' GROUP BY t.table_name ORDER BY t.table_name",
+    information_schema_case: "SELECT table_name FROM INFORMATION_SCHEMA.Tables WHERE table_type = 'VIEW'",
+    information_schema_missing: "SELECT * FROM information_schema.missing",
+}
+
+test_query! { with [
+        "CREATE SEQUENCE seq START 5 INCREMENT 5",
+    ];
+    sequence_nextval: "SELECT id, NEXTVAL('seq') FROM genres",
+    sequence_where: "SELECT * FROM genres WHERE id = NEXTVAL('seq')",
+    sequence_order: "SELECT * FROM genres ORDER BY NEXTVAL('seq')",
+}
+
+test_query! { with [
+        "CREATE TABLE users (
+            id INTEGER PRIMARY KEY,
+            name STRING COLLATE NOCASE INDEX,
+            city STRING COLLATE UNICODE_NOCASE,
+            code STRING
+        )",
+        "INSERT INTO users VALUES
+            (1, 'alice', 'Ålesund', 'a'),
+            (2, 'Bob', 'Oslo', 'B'),
+            (3, 'ALICE', 'ÅLESUND', 'A'),
+            (4, 'Émile', 'Paris', 'c'),
+            (5, 'bob', 'OSLO', 'b')",
+    ];
+    collation_equal: "SELECT * FROM users WHERE name = 'Alice' ORDER BY id",
+    collation_equal_ascii: "SELECT * FROM users WHERE name = 'émile' ORDER BY id",
+    collation_equal_unicode: "SELECT * FROM users WHERE city = 'åLesund' ORDER BY id",
+    collation_equal_binary: "SELECT * FROM users WHERE code = 'a'",
+    collation_not_equal: "SELECT * FROM users WHERE name != 'BOB'",
+    collation_compare: "SELECT * FROM users WHERE name > 'B'",
+    collation_between: "SELECT * FROM users WHERE name BETWEEN 'B' AND 'C'",
+    collation_in: "SELECT * FROM users WHERE name IN ('BOB', 'Carol') ORDER BY id",
+    collation_like: "SELECT * FROM users WHERE name LIKE 'AL%'",
+    collation_order: "SELECT * FROM users ORDER BY name DESC, id",
+    collation_order_binary: "SELECT * FROM users ORDER BY code",
+    collation_join: "SELECT a.id, b.id FROM users a JOIN users b ON a.name = b.name WHERE a.id < b.id",
+    collation_compare_mismatch: "SELECT * FROM users WHERE name = city",
+    collation_cte: "WITH u AS (SELECT id, name FROM users) SELECT * FROM u WHERE name = 'BOB'",
+}
+
+test_query! { with [
+        "CREATE TABLE users (
+            id INTEGER PRIMARY KEY,
+            email STRING,
+            email_lower STRING GENERATED ALWAYS AS (LOWER(email)) STORED INDEX
+        )",
+        "INSERT INTO users (id, email) VALUES (1, 'Alice@example.com'), (2, 'bob@example.com')",
+    ];
+    generated_lookup: "SELECT * FROM users WHERE email_lower = 'alice@example.com'",
+    generated_insert_returning: "INSERT INTO users (id, email) VALUES (3, 'Carol@Example.com') RETURNING *",
+    generated_update_returning: "UPDATE users SET email = 'BOB@example.com' WHERE id = 2 RETURNING email_lower",
+}
+
+test_query! { with [
+        "CREATE TYPE mood AS ENUM ('sad', 'ok', 'happy')",
+        "CREATE TABLE people (id INTEGER PRIMARY KEY, mood mood INDEX)",
+        "INSERT INTO people VALUES (1, 'happy'), (2, 'sad'), (3, 'ok'), (4, NULL)",
+    ];
+    enum_select: "SELECT * FROM people",
+    enum_where: "SELECT * FROM people WHERE mood = 'sad'",
+    enum_where_invalid: "SELECT * FROM people WHERE mood = 'meh'",
+    enum_order: "SELECT * FROM people ORDER BY mood",
+    enum_group: "SELECT mood, COUNT(*) FROM people GROUP BY mood ORDER BY mood",
+    enum_cast: "SELECT CAST('ok' AS mood), 'happy'::mood",
+    enum_cast_invalid: "SELECT 'meh'::mood",
+    enum_concat: "SELECT mood || '!' FROM people WHERE id = 1",
+}
+
+test_query! { with [
+        "CREATE TABLE posts (id INTEGER PRIMARY KEY, tags STRING[], scores INTEGER[] DEFAULT '{}')",
+        "INSERT INTO posts VALUES (1, ARRAY['rust', 'db'], ARRAY[3, 1, 2]), (2, '{sql}'::STRING[], ARRAY[5]), (3, NULL, ARRAY[NULL, 4])",
+        "INSERT INTO posts (id, tags) VALUES (4, ARRAY[])",
+    ];
+    array_select: "SELECT * FROM posts",
+    array_literal: "SELECT ARRAY[1, 2.5, NULL], ARRAY['a', 'b c', ''], ARRAY[]",
+    array_literal_mismatch: "SELECT ARRAY[1, 'a']",
+    array_literal_nested: "SELECT ARRAY[ARRAY[1]]",
+    array_element: "SELECT id, tags[1], scores[2], scores[0], scores[9] FROM posts",
+    array_element_expression: "SELECT (ARRAY[10, 20, 30])[1 + 1], -scores[1] FROM posts WHERE id = 1",
+    array_element_invalid: "SELECT scores['a'] FROM posts",
+    array_any: "SELECT id FROM posts WHERE 'db' = ANY (tags)",
+    array_any_null: "SELECT id, 4 = ANY (scores) FROM posts",
+    array_any_coerce: "SELECT id FROM posts WHERE '5' = ANY (scores)",
+    array_any_not_array: "SELECT 1 = ANY (1)",
+    array_unnest: "SELECT * FROM unnest(ARRAY[1, 2, NULL])",
+    array_unnest_alias: "SELECT t.x + 1 FROM unnest(ARRAY[1, 2]) AS t (x) ORDER BY x DESC",
+    array_unnest_join: "SELECT p.id, u.n FROM posts p JOIN unnest(ARRAY[1, 2]) u (n) ON p.id = u.n",
+    array_unnest_null: "SELECT * FROM unnest(NULL)",
+    array_unnest_invalid: "SELECT * FROM unnest(1)",
+    array_unnest_lateral: "SELECT p.id, t.tag FROM posts p CROSS JOIN LATERAL unnest(p.tags) t (tag) WHERE t.tag != 'db'",
+    array_unnest_lateral_left: "SELECT p.id, s.score FROM posts p LEFT JOIN LATERAL unnest(p.scores) s (score) ON TRUE",
+    array_agg: "SELECT array_agg(id) FROM posts",
+    array_agg_order: "SELECT array_agg(tags[1] ORDER BY id DESC) FROM posts",
+    array_agg_group: "SELECT id % 2, array_agg(id ORDER BY id) FROM posts GROUP BY id % 2 ORDER BY id % 2",
+    array_cast: "SELECT '{1, 2, NULL}'::INTEGER[], CAST(ARRAY[1, 2] AS STRING[]), ARRAY['a']::STRING, ARRAY[1]::JSON",
+    array_cast_invalid: "SELECT '{1, x}'::INTEGER[]",
+    array_concat: "SELECT scores || ARRAY[9], 0 || scores, scores || 7 FROM posts WHERE id = 1",
+    array_cardinality: "SELECT id, CARDINALITY(tags) FROM posts",
+    array_compare: "SELECT id FROM posts WHERE scores = ARRAY[5] OR scores > ARRAY[3, 1]",
+    array_order: "SELECT id, scores FROM posts ORDER BY scores",
+}
+
+test_query! { with [
+        "CREATE TABLE tasks (id INTEGER PRIMARY KEY, started TIMESTAMP INDEX, duration INTERVAL)",
+        "INSERT INTO tasks VALUES
+            (1, TIMESTAMP '2024-01-01 09:00', INTERVAL '2 hours'),
+            (2, TIMESTAMP '2024-01-02 12:30', INTERVAL '1 day'),
+            (3, TIMESTAMP '2024-01-31 08:00', INTERVAL '45 minutes'),
+            (4, TIMESTAMP '2024-02-15 00:00', NULL)",
+    ];
+    interval_select: "SELECT * FROM tasks",
+    interval_arithmetic: "SELECT id, started + duration, started - TIMESTAMP '2024-01-01', duration * 2 FROM tasks",
+    interval_range: "SELECT /*+ FORCE_INDEX(tasks started) */ id FROM tasks WHERE started >= TIMESTAMP '2024-02-15' - INTERVAL '1 month'",
+    interval_range_between: "SELECT /*+ FORCE_INDEX(tasks started) */ id FROM tasks WHERE started BETWEEN TIMESTAMP '2024-01-01' AND DATE '2024-01-01' + INTERVAL '1 day 12 hours'",
+    interval_range_date: "SELECT id FROM tasks WHERE started BETWEEN DATE '2024-01-01' AND DATE '2024-01-01' + INTERVAL '1 day 12 hours'",
+    interval_where: "SELECT id FROM tasks WHERE duration > '1 hour'",
+    interval_where_expression: "SELECT id FROM tasks WHERE started + duration > TIMESTAMP '2024-01-03'",
+    interval_order: "SELECT id, duration FROM tasks ORDER BY duration DESC",
+    interval_aggregate: "SELECT MIN(duration), MAX(duration), COUNT(DISTINCT duration) FROM tasks WHERE duration IS NOT NULL",
+    interval_cast: "SELECT duration::STRING, CAST('1 week' AS INTERVAL) FROM tasks WHERE id = 2",
+}
+
+test_query! { with [
+        "CREATE TABLE articles (id INTEGER PRIMARY KEY, title STRING, body STRING)",
+        "INSERT INTO articles VALUES
+            (1, 'Star Wars', 'A long time ago, in a galaxy far, far away.'),
+            (2, 'Star Trek', 'Space: the final frontier.'),
+            (3, 'Galaxy Quest', 'Never give up, never surrender! A galaxy of fun.'),
+            (4, 'Untitled', NULL)",
+        "CREATE FULLTEXT INDEX articles_body ON articles (body)",
+    ];
+    fulltext_match: "SELECT id FROM articles WHERE body @@ 'GALAXY' ORDER BY id",
+    fulltext_match_terms: "SELECT id FROM articles WHERE body @@ 'frontier, surrender' ORDER BY id",
+    fulltext_match_none: "SELECT id FROM articles WHERE body @@ 'wookiee'",
+    fulltext_match_empty: "SELECT id FROM articles WHERE body @@ '!?'",
+    fulltext_match_and: "SELECT id FROM articles WHERE body @@ 'galaxy' AND id > 1 ORDER BY id",
+    fulltext_match_unindexed: "SELECT id FROM articles WHERE title @@ 'star'",
+    fulltext_against: "SELECT id FROM articles WHERE MATCH (body) AGAINST ('far galaxy') ORDER BY id",
+    fulltext_against_not: "SELECT id FROM articles WHERE NOT MATCH (body) AGAINST ('far galaxy')",
+    fulltext_rank: "SELECT id, MATCH (body) AGAINST ('far galaxy') AS score FROM articles WHERE body @@ 'far galaxy' ORDER BY score DESC",
+    fulltext_rank_all: "SELECT id, MATCH (title || ' ' || body) AGAINST ('star galaxy') FROM articles ORDER BY 2 DESC, id",
+    fulltext_invalid: "SELECT id FROM articles WHERE id @@ 'star'",
 }
@@ -31,20 +31,32 @@ macro_rules! test_schema {
                 let txn = engine.begin()?;
                 for table in txn.scan_tables()? {
                     write!(f, "\n{}\n", table)?;
-                    for row in txn.scan(&table.name, None)? {
+                    for row in txn.scan(&table.name, None, false, None, None)? {
                         write!(f, "{:?}\n", row?)?;
                     }
 
-                    for column in table.columns.iter().filter(|c| c.index) {
-                        write!(f, "\nIndex {}.{}\n", table.name, column.name)?;
-                        let mut scan = txn.scan_index(&table.name, &column.name)?;
-                        while let Some((value, pks)) = scan.next().transpose()? {
+                    if let Some(statistics) = txn.read_statistics(&table.name)? {
+                        write!(f, "\nStatistics {}: {:?}\n", table.name, statistics)?;
+                    }
+
+                    if !table.privileges.is_empty() {
+                        write!(f, "\nPrivileges {}: {:?}\n", table.name, table.privileges)?;
+                    }
+
+                    for index in table.get_indexes() {
+                        write!(f, "\nIndex {}.{}\n", table.name, index.name)?;
+                        let mut scan = txn.scan_index(&table.name, &index.name)?;
+                        while let Some((values, pks)) = scan.next().transpose()? {
                             let mut pks = pks.into_iter().collect::<Vec<_>>();
                             pks.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-                            write!(f, "{:?} => {:?}\n", value, pks)?;
+                            let values = values.iter().map(|v| format!("{:?}", v)).collect::<Vec<_>>();
+                            write!(f, "{} => {:?}\n", values.join(", "), pks)?;
                         }
                     }
                 }
+                for view in txn.scan_views()? {
+                    write!(f, "\n{}\n", view)?;
+                }
                 txn.rollback()?;
 
                 Ok(())
@@ -70,6 +82,39 @@ test_schema! {
             "varchar" VARCHAR
         )
     "#,
+    create_table_datatype_datetime: r#"
+        CREATE TABLE name (
+            id INTEGER PRIMARY KEY,
+            "date" DATE,
+            "time" TIME,
+            "timestamp" TIMESTAMP DEFAULT TIMESTAMP '2024-01-31 12:00:00'
+        )
+    "#,
+    create_table_datatype_decimal: r#"
+        CREATE TABLE name (
+            id INTEGER PRIMARY KEY,
+            "decimal" DECIMAL DEFAULT DECIMAL '0.00',
+            "numeric" NUMERIC
+        )
+    "#,
+    create_table_datatype_bytea: r#"
+        CREATE TABLE name (
+            id INTEGER PRIMARY KEY,
+            "bytea" BYTEA DEFAULT x'00ff'
+        )
+    "#,
+    create_table_datatype_json: r#"
+        CREATE TABLE name (
+            id INTEGER PRIMARY KEY,
+            "json" JSON DEFAULT JSON '{"a": [1, 2]}'
+        )
+    "#,
+    create_table_datatype_uuid: r#"
+        CREATE TABLE name (
+            id UUID PRIMARY KEY,
+            "uuid" UUID DEFAULT UUID '00000000-0000-0000-0000-000000000000'
+        )
+    "#,
     create_table_datatype_missing: "CREATE TABLE name (id)",
     create_table_datatype_null: "CREATE TABLE name (id INTEGER PRIMARY KEY, value NULL)",
 
@@ -109,6 +154,15 @@ test_schema! {
     create_table_default_null: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING DEFAULT NULL)",
     create_table_default_null_not: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING NOT NULL DEFAULT NULL)",
     create_table_default_expr: "CREATE TABLE name (id INTEGER PRIMARY KEY, value INTEGER DEFAULT 1 + 2 * 3)",
+    create_table_default_expr_column: "CREATE TABLE name (id INTEGER PRIMARY KEY, value INTEGER DEFAULT id + 1)",
+    create_table_default_expr_function: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING DEFAULT UPPER('foo') || 'bar')",
+    create_table_default_expr_function_conflict: "CREATE TABLE name (id INTEGER PRIMARY KEY, value INTEGER DEFAULT UPPER('foo'))",
+    create_table_default_expr_now: "CREATE TABLE name (id INTEGER PRIMARY KEY, value TIMESTAMP DEFAULT NOW())",
+    create_table_auto_increment: "CREATE TABLE name (id INTEGER PRIMARY KEY AUTO_INCREMENT, value STRING)",
+    create_table_auto_increment_default: "CREATE TABLE name (id INTEGER PRIMARY KEY AUTO_INCREMENT DEFAULT 1)",
+    create_table_auto_increment_nullable: "CREATE TABLE name (id INTEGER PRIMARY KEY, value INTEGER AUTO_INCREMENT NULL)",
+    create_table_auto_increment_string: "CREATE TABLE name (id STRING PRIMARY KEY AUTO_INCREMENT)",
+    create_table_serial: "CREATE TABLE name (id SERIAL PRIMARY KEY, value SERIAL)",
     create_table_default_conflict: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING DEFAULT 7)",
     create_table_default_conflict_float_integer: "CREATE TABLE name (id INTEGER PRIMARY KEY, value FLOAT DEFAULT 7)",
     create_table_default_conflict_integer_float: "CREATE TABLE name (id INTEGER PRIMARY KEY, value INTEGER DEFAULT 3.14)",
@@ -120,6 +174,12 @@ test_schema! {
     create_table_unique_null: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING NULL UNIQUE)",
     create_table_unique_not_null: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING NOT NULL UNIQUE)",
     create_table_unique_default: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING DEFAULT 'foo' UNIQUE)",
+    create_table_unique_constraint: "CREATE TABLE name (id INTEGER PRIMARY KEY, a STRING, b INTEGER, UNIQUE (a, b))",
+    create_table_unique_constraint_missing: "CREATE TABLE name (id INTEGER PRIMARY KEY, a STRING, UNIQUE (a, missing))",
+    create_table_unique_constraint_multiple: "CREATE TABLE name (id INTEGER PRIMARY KEY, a STRING, b INTEGER, UNIQUE (a), UNIQUE (a, b))",
+    create_table_unique_deferred: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING UNIQUE DEFERRABLE INITIALLY DEFERRED)",
+    create_table_deferred_none: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING INITIALLY DEFERRED)",
+    create_table_deferred_pk: "CREATE TABLE name (id INTEGER PRIMARY KEY INITIALLY DEFERRED, value STRING)",
 }
 test_schema! { with ["CREATE TABLE test (id INTEGER PRIMARY KEY)"];
     create_table_exists: "CREATE TABLE test (id INTEGER PRIMARY KEY)",
@@ -135,6 +195,209 @@ test_schema! { with ["CREATE TABLE test (id INTEGER PRIMARY KEY)"];
     create_table_ref_type: "CREATE TABLE other (id INTEGER PRIMARY KEY, test_id STRING REFERENCES test)",
     create_table_ref_self: "CREATE TABLE other (id INTEGER PRIMARY KEY, self_id INTEGER REFERENCES other)",
     create_table_ref_self_type: "CREATE TABLE other (id INTEGER PRIMARY KEY, self_id STRING REFERENCES other)",
+    create_table_ref_actions: "CREATE TABLE other (id INTEGER PRIMARY KEY, test_id INTEGER REFERENCES test ON DELETE CASCADE ON UPDATE SET NULL)",
+    create_table_ref_action_invalid: "CREATE TABLE other (id INTEGER PRIMARY KEY, test_id INTEGER REFERENCES test ON DELETE NULL)",
+    create_table_ref_action_restrict: "CREATE TABLE other (id INTEGER PRIMARY KEY, test_id INTEGER REFERENCES test ON UPDATE RESTRICT)",
+    create_table_ref_action_set_null_not_null: "CREATE TABLE other (id INTEGER PRIMARY KEY, test_id INTEGER NOT NULL REFERENCES test ON DELETE SET NULL)",
+    create_table_ref_deferred: "CREATE TABLE other (id INTEGER PRIMARY KEY, test_id INTEGER REFERENCES test DEFERRABLE INITIALLY DEFERRED)",
+    create_table_ref_deferred_immediate: "CREATE TABLE other (id INTEGER PRIMARY KEY, test_id INTEGER REFERENCES test DEFERRABLE INITIALLY IMMEDIATE)",
+    create_table_ref_deferred_not_deferrable: "CREATE TABLE other (id INTEGER PRIMARY KEY, test_id INTEGER REFERENCES test NOT DEFERRABLE INITIALLY DEFERRED)",
+}
+
+test_schema! { with [
+        "CREATE TABLE other (id INTEGER PRIMARY KEY)",
+        "INSERT INTO other VALUES (1)",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, value INTEGER INDEX)",
+        "INSERT INTO test VALUES (1, 'a', 101), (2, 'b', 102)",
+        "CREATE TABLE empty (id INTEGER PRIMARY KEY)",
+    ];
+    alter_table_bare: "ALTER TABLE",
+    alter_table_missing: "ALTER TABLE missing ADD COLUMN flag BOOLEAN",
+    alter_table_add_column: "ALTER TABLE test ADD COLUMN flag BOOLEAN DEFAULT TRUE",
+    alter_table_add_column_bare: "ALTER TABLE test ADD flag BOOLEAN",
+    alter_table_add_column_exists: "ALTER TABLE test ADD COLUMN name STRING",
+    alter_table_add_column_index: "ALTER TABLE test ADD COLUMN flag BOOLEAN DEFAULT TRUE INDEX",
+    alter_table_add_column_not_null: "ALTER TABLE test ADD COLUMN flag BOOLEAN NOT NULL",
+    alter_table_add_column_not_null_empty: "ALTER TABLE empty ADD COLUMN flag BOOLEAN NOT NULL",
+    alter_table_add_column_pk: "ALTER TABLE test ADD COLUMN pk INTEGER PRIMARY KEY",
+    alter_table_add_column_ref: "ALTER TABLE test ADD COLUMN other_id INTEGER DEFAULT 1 REFERENCES other",
+    alter_table_add_column_ref_missing: "ALTER TABLE test ADD COLUMN other_id INTEGER DEFAULT 9 REFERENCES other",
+    alter_table_add_column_type: "ALTER TABLE test ADD COLUMN flag BOOLEAN DEFAULT 1",
+    alter_table_add_column_unique: "ALTER TABLE test ADD COLUMN flag BOOLEAN DEFAULT TRUE UNIQUE",
+    alter_table_add_column_default_expr: "ALTER TABLE test ADD COLUMN label STRING DEFAULT LOWER('X')",
+    alter_table_add_column_unique_null: "ALTER TABLE test ADD COLUMN flag BOOLEAN UNIQUE",
+    alter_table_drop_column: "ALTER TABLE test DROP COLUMN name",
+    alter_table_drop_column_bare: "ALTER TABLE test DROP name",
+    alter_table_drop_column_index: "ALTER TABLE test DROP COLUMN value",
+    alter_table_drop_column_missing: "ALTER TABLE test DROP COLUMN missing",
+    alter_table_drop_column_pk: "ALTER TABLE test DROP COLUMN id",
+}
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING)",
+        "COMMENT ON COLUMN test.id IS 'The ID'",
+    ];
+    comment_table: "COMMENT ON TABLE test IS 'A test table'",
+    comment_table_missing: "COMMENT ON TABLE missing IS 'A test table'",
+    comment_table_quote: "COMMENT ON TABLE test IS 'It''s a test table'",
+    comment_column: "COMMENT ON COLUMN test.name IS 'The name'",
+    comment_column_missing: "COMMENT ON COLUMN test.missing IS 'The name'",
+    comment_column_null: "COMMENT ON COLUMN test.id IS NULL",
+    comment_bare: "COMMENT ON test IS 'A test table'",
+    comment_integer: "COMMENT ON TABLE test IS 1",
+    comment_reserved: "CREATE TABLE comment (id INTEGER PRIMARY KEY)",
+}
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, version INTEGER DEFAULT 0, upper STRING GENERATED ALWAYS AS (UPPER(name)) STORED)",
+        "CREATE TABLE log (id INTEGER PRIMARY KEY, name STRING)",
+        "CREATE TRIGGER test_log AFTER INSERT ON test FOR EACH ROW EXECUTE (INSERT INTO log VALUES (new.id, new.name))",
+    ];
+    create_trigger: "CREATE TRIGGER test_delete AFTER DELETE ON test FOR EACH ROW EXECUTE (DELETE FROM log WHERE id = old.id);",
+    create_trigger_set: "CREATE TRIGGER test_version BEFORE UPDATE ON test FOR EACH ROW EXECUTE SET version = old.version + 1, name = LOWER(new.name)",
+    create_trigger_set_after: "CREATE TRIGGER test_version AFTER UPDATE ON test FOR EACH ROW EXECUTE SET version = 1",
+    create_trigger_set_delete: "CREATE TRIGGER test_version BEFORE DELETE ON test FOR EACH ROW EXECUTE SET version = 1",
+    create_trigger_set_generated: "CREATE TRIGGER test_upper BEFORE INSERT ON test FOR EACH ROW EXECUTE SET upper = 'x'",
+    create_trigger_set_missing: "CREATE TRIGGER test_missing BEFORE INSERT ON test FOR EACH ROW EXECUTE SET missing = 1",
+    create_trigger_old_insert: "CREATE TRIGGER test_old AFTER INSERT ON test FOR EACH ROW EXECUTE (DELETE FROM log WHERE id = old.id)",
+    create_trigger_new_delete: "CREATE TRIGGER test_new AFTER DELETE ON test FOR EACH ROW EXECUTE (DELETE FROM log WHERE id = new.id)",
+    create_trigger_select: "CREATE TRIGGER test_select AFTER INSERT ON test FOR EACH ROW EXECUTE (SELECT new.id)",
+    create_trigger_unparenthesized: "CREATE TRIGGER test_bare AFTER INSERT ON test FOR EACH ROW EXECUTE DELETE FROM log",
+    create_trigger_duplicate: "CREATE TRIGGER test_log AFTER UPDATE ON test FOR EACH ROW EXECUTE (DELETE FROM log)",
+    create_trigger_missing_table: "CREATE TRIGGER missing_log AFTER INSERT ON missing FOR EACH ROW EXECUTE (DELETE FROM log)",
+    create_trigger_missing_action_table: "CREATE TRIGGER test_missing AFTER INSERT ON test FOR EACH ROW EXECUTE (DELETE FROM missing)",
+    create_trigger_timing: "CREATE TRIGGER test_instead INSTEAD OF INSERT ON test FOR EACH ROW EXECUTE (DELETE FROM log)",
+    create_trigger_each_statement: "CREATE TRIGGER test_statement AFTER INSERT ON test FOR EACH STATEMENT EXECUTE (DELETE FROM log)",
+    create_trigger_reserved: "CREATE TABLE trigger (before INTEGER PRIMARY KEY, after INTEGER, each INTEGER, row INTEGER, execute INTEGER)",
+    drop_trigger: "DROP TRIGGER test_log ON test",
+    drop_trigger_missing: "DROP TRIGGER missing ON test",
+    drop_trigger_if_exists: "DROP TRIGGER IF EXISTS missing ON test",
+    drop_trigger_table_missing: "DROP TRIGGER test_log ON missing",
+}
+test_schema! { with [
+        "CREATE DATABASE app",
+        "CREATE TABLE app.test (id INTEGER PRIMARY KEY, value STRING)",
+        "INSERT INTO app.test VALUES (1, 'a')",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY)",
+        "INSERT INTO test VALUES (1)",
+    ];
+    create_database: "CREATE DATABASE other",
+    create_database_bare: "CREATE DATABASE",
+    create_database_default: "CREATE DATABASE main",
+    create_database_exists: "CREATE DATABASE app",
+    create_database_information_schema: "CREATE DATABASE information_schema",
+    create_database_qualified: r#"CREATE DATABASE "a.b""#,
+    create_table_database: "CREATE TABLE app.other (id INTEGER PRIMARY KEY, test_id INTEGER REFERENCES app.test UNIQUE)",
+    create_table_database_default: "CREATE TABLE main.other (id INTEGER PRIMARY KEY)",
+    create_table_database_exists: "CREATE TABLE app.test (id INTEGER PRIMARY KEY)",
+    create_table_database_missing: "CREATE TABLE missing.test (id INTEGER PRIMARY KEY)",
+    create_table_database_temporary: "CREATE TEMPORARY TABLE app.temp (id INTEGER PRIMARY KEY)",
+    create_index_database: "CREATE INDEX value ON app.test (value)",
+    create_view_database: "CREATE VIEW app.tests AS SELECT * FROM test",
+    alter_table_database: "ALTER TABLE app.test ADD COLUMN flag BOOLEAN DEFAULT TRUE",
+    drop_table_database: "DROP TABLE app.test",
+    comment_database: "COMMENT ON COLUMN app.test.value IS 'The value'",
+}
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, value INTEGER INDEX)",
+        "INSERT INTO test VALUES (1, 'a', 101), (2, 'b', 102)",
+        "ALTER TABLE test ADD COLUMN flag BOOLEAN DEFAULT TRUE INDEX",
+        "INSERT INTO test VALUES (3, 'c', 103, FALSE)",
+    ];
+    alter_table_added_delete: "DELETE FROM test WHERE id = 1",
+    alter_table_added_drop: "ALTER TABLE test DROP COLUMN name",
+    alter_table_added_drop_added: "ALTER TABLE test DROP COLUMN flag",
+    alter_table_added_insert: "INSERT INTO test (id) VALUES (4)",
+    alter_table_added_update: "UPDATE test SET flag = FALSE WHERE id = 1",
+}
+
+test_schema! { with [
+        "CREATE TABLE other (id INTEGER PRIMARY KEY, value INTEGER)",
+        "CREATE INDEX other_value ON other (value)",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, value INTEGER INDEX)",
+        "INSERT INTO test VALUES (1, 'a', 101), (2, 'b', 102), (3, 'b', 103)",
+    ];
+    create_index: "CREATE INDEX test_name ON test (name)",
+    create_index_bare: "CREATE INDEX",
+    create_index_columns_empty: "CREATE INDEX test_name ON test ()",
+    create_index_column_missing: "CREATE INDEX test_name ON test (missing)",
+    create_index_column_multiple: "CREATE INDEX test_name ON test (name, name)",
+    create_index_composite: "CREATE INDEX test_name_value ON test (name, value)",
+    create_index_exists: "CREATE INDEX other_value ON test (name)",
+    create_index_exists_column: "CREATE INDEX value ON test (name)",
+    create_index_include: "CREATE INDEX test_name ON test (name) INCLUDE (value)",
+    create_index_include_duplicate: "CREATE INDEX test_name ON test (name) INCLUDE (name)",
+    create_index_include_missing: "CREATE INDEX test_name ON test (name) INCLUDE (missing)",
+    create_index_table_missing: "CREATE INDEX test_name ON missing (name)",
+    create_index_unnamed: "CREATE INDEX ON test (name)",
+    create_index_expression: "CREATE INDEX test_lower ON test ((LOWER(name)))",
+    create_index_expression_composite: "CREATE INDEX ON test (value, (LOWER(name)), (value * 2))",
+    create_index_expression_missing: "CREATE INDEX ON test ((LOWER(missing)))",
+    create_index_expression_nondeterministic: "CREATE INDEX ON test ((RANDOM()))",
+    create_index_expression_unique: "CREATE UNIQUE INDEX ON test ((UPPER(name)))",
+    create_index_expression_unique_value: "CREATE UNIQUE INDEX ON test ((value % 100))",
+    create_index_fulltext: "CREATE FULLTEXT INDEX test_name ON test (name)",
+    create_index_fulltext_composite: "CREATE FULLTEXT INDEX test_name ON test (name, value)",
+    create_index_fulltext_datatype: "CREATE FULLTEXT INDEX test_value ON test (value)",
+    create_index_fulltext_expression: "CREATE FULLTEXT INDEX ON test ((LOWER(name)))",
+}
+
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING)",
+        "INSERT INTO test VALUES (1, 'Star Wars'), (2, 'star trek: the star'), (3, NULL)",
+        "CREATE FULLTEXT INDEX test_name ON test (name)",
+    ];
+    insert_fulltext: "INSERT INTO test VALUES (4, 'Galaxy Quest'), (5, 'Wars of the Worlds')",
+    update_fulltext: "UPDATE test SET name = 'Star Quest' WHERE id = 1",
+    update_fulltext_null: "UPDATE test SET name = NULL WHERE id = 2",
+    delete_fulltext: "DELETE FROM test WHERE id = 1",
+    drop_index_fulltext: "DROP INDEX test_name",
+    drop_column_fulltext: "ALTER TABLE test DROP COLUMN name",
+}
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, value INTEGER)",
+        "INSERT INTO test VALUES (1, 'a', 1), (2, 'B', 1)",
+        "CREATE UNIQUE INDEX test_lower ON test ((LOWER(name)))",
+        "CREATE INDEX test_value ON test (id, (value + 1))",
+    ];
+    insert_index_expression: "INSERT INTO test VALUES (3, 'C', 2)",
+    insert_index_expression_unique: "INSERT INTO test VALUES (3, 'A', 2)",
+    insert_index_expression_null: "INSERT INTO test VALUES (3, NULL, NULL), (4, NULL, NULL)",
+    update_index_expression: "UPDATE test SET name = 'c', value = 5 WHERE id = 2",
+    update_index_expression_unique: "UPDATE test SET name = 'b' WHERE id = 1",
+    delete_index_expression: "DELETE FROM test WHERE id = 1",
+    alter_table_drop_column_index_expression: "ALTER TABLE test DROP COLUMN name",
+    alter_column_type_index_expression: "ALTER TABLE test ALTER COLUMN value TYPE FLOAT",
+}
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, value INTEGER)",
+        "INSERT INTO test VALUES (1, 'a', 1)",
+        "CREATE INDEX test_value ON test ((value + 1))",
+        "ALTER TABLE test DROP COLUMN name",
+    ];
+    alter_table_drop_column_index_expression_shift: "INSERT INTO test VALUES (2, 5)",
+}
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, value INTEGER)",
+        "INSERT INTO test VALUES (1, 'a', 1), (2, 'b', 1), (3, 'b', 2)",
+        "CREATE INDEX test_name ON test (name) INCLUDE (value)",
+    ];
+    alter_table_drop_column_include_index: "ALTER TABLE test DROP COLUMN value",
+    update_index_include: "UPDATE test SET value = 3 WHERE id = 2",
+}
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, value INTEGER)",
+        "INSERT INTO test VALUES (1, 'a', 1), (2, 'b', 1), (3, 'b', 2)",
+        "CREATE INDEX test_name_value ON test (name, value)",
+    ];
+    drop_index: "DROP INDEX test_name_value",
+    drop_index_bare: "DROP INDEX",
+    drop_index_missing: "DROP INDEX missing",
+    drop_index_if_exists: "DROP INDEX IF EXISTS test_name_value",
+    drop_index_if_exists_missing: "DROP INDEX IF EXISTS missing",
+
+    alter_table_drop_column_composite_index: "ALTER TABLE test DROP COLUMN value",
+    delete_index_composite: "DELETE FROM test WHERE id = 2",
+    insert_index_composite: "INSERT INTO test VALUES (4, 'b', 1)",
+    update_index_composite: "UPDATE test SET value = 2 WHERE id = 2",
 }
 
 test_schema! { with [
@@ -257,6 +520,72 @@ test_schema! { with [
     update_pk_float_null: r#"UPDATE "float" SET pk = NULL WHERE pk = 3.14"#,
 }
 
+test_schema! { with [
+        r#"CREATE TABLE "decimal" (pk DECIMAL PRIMARY KEY)"#,
+        r#"INSERT INTO "decimal" VALUES (DECIMAL '3.14'), (DECIMAL '-2.718'), (DECIMAL '100')"#,
+    ];
+    insert_pk_decimal: r#"INSERT INTO "decimal" VALUES (DECIMAL '0.001')"#,
+    insert_pk_decimal_conflict: r#"INSERT INTO "decimal" VALUES (DECIMAL '3.140')"#,
+    insert_pk_decimal_conflict_integer: r#"INSERT INTO "decimal" VALUES (DECIMAL '1e2')"#,
+    insert_pk_decimal_float: r#"INSERT INTO "decimal" VALUES (3.14)"#,
+    insert_pk_decimal_float_new: r#"INSERT INTO "decimal" VALUES (1.5)"#,
+    insert_pk_decimal_float_infinity: r#"INSERT INTO "decimal" VALUES (INFINITY)"#,
+    insert_pk_decimal_integer: r#"INSERT INTO "decimal" VALUES (7)"#,
+    insert_pk_decimal_integer_conflict: r#"INSERT INTO "decimal" VALUES (100)"#,
+    insert_pk_decimal_null: r#"INSERT INTO "decimal" VALUES (NULL)"#,
+
+    update_pk_decimal: r#"UPDATE "decimal" SET pk = DECIMAL '2.50' WHERE pk = DECIMAL '3.14'"#,
+    update_pk_decimal_float: r#"UPDATE "decimal" SET pk = 2.5 WHERE pk = DECIMAL '3.14'"#,
+}
+
+test_schema! { with [
+        r#"CREATE TABLE "bytea" (pk BYTEA PRIMARY KEY)"#,
+        r#"INSERT INTO "bytea" VALUES (x'dead'), (x''), (x'00')"#,
+    ];
+    insert_pk_bytea: r#"INSERT INTO "bytea" VALUES (x'beef')"#,
+    insert_pk_bytea_conflict: r#"INSERT INTO "bytea" VALUES (x'DEAD')"#,
+    insert_pk_bytea_conflict_empty: r#"INSERT INTO "bytea" VALUES (BYTEA '')"#,
+    insert_pk_bytea_string: r#"INSERT INTO "bytea" VALUES ('dead')"#,
+    insert_pk_bytea_null: r#"INSERT INTO "bytea" VALUES (NULL)"#,
+
+    update_pk_bytea: r#"UPDATE "bytea" SET pk = x'0000' WHERE pk = x'00'"#,
+}
+
+test_schema! { with [
+        r#"CREATE TABLE "json" (id INTEGER PRIMARY KEY, doc JSON)"#,
+        r#"INSERT INTO "json" VALUES (1, JSON '{"a": 1}')"#,
+    ];
+    insert_json: r#"INSERT INTO "json" VALUES (2, JSON '{"b": [true, null], "a": "x"}')"#,
+    insert_json_string: r#"INSERT INTO "json" VALUES (2, '{"a": 1}')"#,
+    insert_json_null: r#"INSERT INTO "json" VALUES (2, NULL)"#,
+
+    update_json_set: r#"UPDATE "json" SET doc = json_set(doc, '$.b', doc->'a')"#,
+}
+
+test_schema! { with [
+        r#"CREATE TABLE "uuid" (pk UUID PRIMARY KEY)"#,
+        r#"INSERT INTO "uuid" VALUES (UUID 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'), (UUID '00000000-0000-0000-0000-000000000001')"#,
+    ];
+    insert_pk_uuid: r#"INSERT INTO "uuid" VALUES (UUID 'f47ac10b-58cc-4372-a567-0e02b2c3d479')"#,
+    insert_pk_uuid_conflict: r#"INSERT INTO "uuid" VALUES (UUID 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11')"#,
+    insert_pk_uuid_string: r#"INSERT INTO "uuid" VALUES ('a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11')"#,
+    insert_pk_uuid_null: r#"INSERT INTO "uuid" VALUES (NULL)"#,
+
+    update_pk_uuid: r#"UPDATE "uuid" SET pk = UUID 'ffffffff-ffff-ffff-ffff-ffffffffffff' WHERE pk = UUID '00000000-0000-0000-0000-000000000001'"#,
+}
+
+test_schema! { with [
+        r#"CREATE TABLE "timestamp" (pk TIMESTAMP PRIMARY KEY)"#,
+        r#"INSERT INTO "timestamp" VALUES (TIMESTAMP '2024-01-31 12:00:00'), (TIMESTAMP '1969-07-20 20:17:40')"#,
+    ];
+    insert_pk_timestamp: r#"INSERT INTO "timestamp" VALUES (TIMESTAMP '1970-01-01 00:00:00.000001')"#,
+    insert_pk_timestamp_conflict: r#"INSERT INTO "timestamp" VALUES (TIMESTAMP '2024-01-31 12:00:00')"#,
+    insert_pk_timestamp_string: r#"INSERT INTO "timestamp" VALUES ('2024-01-31 12:00:00')"#,
+    insert_pk_timestamp_null: r#"INSERT INTO "timestamp" VALUES (NULL)"#,
+
+    update_pk_timestamp: r#"UPDATE "timestamp" SET pk = TIMESTAMP '2000-01-01' WHERE pk = TIMESTAMP '2024-01-31 12:00:00'"#,
+}
+
 test_schema! { with [
         r#"CREATE TABLE "integer" (pk INTEGER PRIMARY KEY)"#,
         r#"INSERT INTO "integer" VALUES (1), (2)"#,
@@ -318,6 +647,76 @@ test_schema! { with [
     insert_default_override: "INSERT INTO defaults VALUES (1, TRUE, TRUE, FALSE, 2.718, 3, 'bar')",
     insert_default_override_null: "INSERT INTO defaults VALUES (1, TRUE, NULL, NULL, NULL, NULL, NULL)",
 }
+test_schema! { with [
+        "CREATE TABLE defaults (id INTEGER PRIMARY KEY, label STRING DEFAULT CONCAT('id', '-', LENGTH('foo')))",
+    ];
+    insert_default_expr: "INSERT INTO defaults (id) VALUES (1), (2)",
+    insert_default_expr_override: "INSERT INTO defaults VALUES (1, 'x')",
+}
+test_schema! { with [
+        "CREATE TABLE counter (id SERIAL PRIMARY KEY, value STRING)",
+    ];
+    insert_auto_increment: "INSERT INTO counter (value) VALUES ('a'), ('b'), ('c')",
+    insert_auto_increment_explicit: "INSERT INTO counter VALUES (7, 'a')",
+    insert_auto_increment_null: "INSERT INTO counter VALUES (NULL, 'a')",
+}
+test_schema! { with [
+        "CREATE TABLE counter (id SERIAL PRIMARY KEY, value STRING)",
+        "INSERT INTO counter VALUES (1, 'a')",
+    ];
+    insert_auto_increment_conflict: "INSERT INTO counter (value) VALUES ('b')",
+}
+
+test_schema! { with [
+        "CREATE TABLE people (id INTEGER PRIMARY KEY, name STRING, age INTEGER)",
+        "INSERT INTO people VALUES (1, 'Alice', 34), (2, 'Bob', 12)",
+    ];
+    create_view: "CREATE VIEW adults AS SELECT id, name FROM people WHERE age >= 18",
+    create_view_columns: "CREATE VIEW named (person_id, person) AS SELECT id, name FROM people;",
+    create_view_columns_excess: "CREATE VIEW named (a, b, c) AS SELECT id, name FROM people",
+    create_view_quoted: r#"CREATE VIEW "select" ("from") AS WITH a AS (SELECT 'a;b' AS x) SELECT x FROM a"#,
+    create_view_exists_table: "CREATE VIEW people AS SELECT 1",
+    create_view_invalid: "CREATE VIEW broken AS SELECT missing FROM people",
+    create_view_missing_table: "CREATE VIEW broken AS SELECT * FROM missing",
+    create_view_not_select: "CREATE VIEW broken AS DELETE FROM people",
+}
+
+test_schema! { with [
+        "CREATE TABLE people (id INTEGER PRIMARY KEY, name STRING, age INTEGER, score FLOAT)",
+        "INSERT INTO people VALUES (1, 'Alice', 34, NULL), (2, 'Bob', 12, 3.5), (3, 'Carol', NULL, NULL)",
+    ];
+    create_table_as: "CREATE TABLE copy AS SELECT * FROM people",
+    create_table_as_expressions: "CREATE TABLE adults AS SELECT name, age * 12 AS months, age >= 18 AS adult FROM people WHERE age IS NOT NULL",
+    create_table_as_with: "CREATE TABLE names AS WITH n AS (SELECT name FROM people) SELECT name FROM n ORDER BY name DESC",
+    create_table_as_empty: "CREATE TABLE empty AS SELECT id, name FROM people WHERE FALSE",
+    create_table_as_null: "CREATE TABLE nulls AS SELECT id, NULL AS missing FROM people",
+    create_table_as_unnamed: "CREATE TABLE unnamed AS SELECT id, age + 1 FROM people",
+    create_table_as_duplicate_column: "CREATE TABLE duplicate AS SELECT p.id, q.id FROM people p, people q",
+    create_table_as_duplicate_key: "CREATE TABLE halves AS SELECT id / 2 AS half FROM people",
+    create_table_as_null_key: "CREATE TABLE ages AS SELECT age FROM people",
+    create_table_as_exists: "CREATE TABLE people AS SELECT 1 AS id",
+    create_table_as_not_select: "CREATE TABLE broken AS DELETE FROM people",
+    create_table_as_bare: "CREATE TABLE broken AS",
+    create_table_temporary: "CREATE TEMPORARY TABLE scratch (id INTEGER PRIMARY KEY, name STRING)",
+    create_table_temporary_as: "CREATE TEMP TABLE scratch AS SELECT * FROM people",
+    create_table_temporary_exists: "CREATE TEMPORARY TABLE people (id INTEGER PRIMARY KEY)",
+    create_table_temporary_no_table: "CREATE TEMPORARY scratch (id INTEGER PRIMARY KEY)",
+}
+test_schema! { with [
+        "CREATE TABLE people (id INTEGER PRIMARY KEY, name STRING, age INTEGER)",
+        "CREATE VIEW adults AS SELECT id, name FROM people WHERE age >= 18",
+    ];
+    create_view_exists: "CREATE VIEW adults AS SELECT 1",
+    create_view_nested: "CREATE VIEW adult_names AS SELECT name FROM adults",
+    create_table_exists_view: "CREATE TABLE adults (id INTEGER PRIMARY KEY)",
+    drop_view: "DROP VIEW adults",
+    drop_view_missing: "DROP VIEW missing",
+    drop_view_if_exists: "DROP VIEW IF EXISTS adults",
+    drop_view_if_exists_missing: "DROP VIEW IF EXISTS missing",
+    drop_view_table: "DROP VIEW people",
+    drop_table_view: "DROP TABLE adults",
+    insert_view: "INSERT INTO adults VALUES (1, 'Alice')",
+}
 
 test_schema! { with [
         r#"CREATE TABLE "unique" (
@@ -346,6 +745,30 @@ test_schema! { with [
     update_unique_boolean_same: r#"UPDATE "unique" SET "boolean" = TRUE WHERE id = 1"#,
     update_unique_nulls: r#"UPDATE "unique" SET "boolean" = NULL, "float" = NULL, "integer" = NULL, "string" = NULL WHERE id = 1"#,
 }
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, a STRING, b INTEGER, UNIQUE (a, b))",
+        "INSERT INTO test VALUES (1, 'a', 1), (2, 'a', 2), (3, 'b', NULL)",
+    ];
+    insert_unique_composite: "INSERT INTO test VALUES (4, 'b', 1)",
+    insert_unique_composite_duplicate: "INSERT INTO test VALUES (4, 'a', 2)",
+    insert_unique_composite_duplicate_batch: "INSERT INTO test VALUES (4, 'c', 1), (5, 'c', 1)",
+    insert_unique_composite_nulls: "INSERT INTO test VALUES (4, 'b', NULL), (5, NULL, NULL)",
+    update_unique_composite: "UPDATE test SET b = 3 WHERE id = 2",
+    update_unique_composite_duplicate: "UPDATE test SET b = 1 WHERE id = 2",
+    update_unique_composite_pk: "UPDATE test SET id = 4 WHERE id = 2",
+    update_unique_composite_swap: "UPDATE test SET b = 3 - b WHERE a = 'a'",
+    update_unique_composite_batch: "UPDATE test SET a = 'c', b = id * 10",
+    update_unique_composite_batch_duplicate: "UPDATE test SET b = 5",
+    update_unique_composite_batch_pk: "UPDATE test SET id = id + 10, b = b * 10",
+}
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING, value INTEGER)",
+        "INSERT INTO test VALUES (1, 'a', 1), (2, 'b', 1), (3, NULL, 2), (4, NULL, 2)",
+    ];
+    create_unique_index: "CREATE UNIQUE INDEX test_name ON test (name)",
+    create_unique_index_composite: "CREATE UNIQUE INDEX test_name_value ON test (name, value)",
+    create_unique_index_duplicate: "CREATE UNIQUE INDEX test_value ON test (value)",
+}
 
 test_schema! { with [
         "CREATE TABLE target (id BOOLEAN PRIMARY KEY)",
@@ -425,6 +848,55 @@ test_schema! { with [
     update_ref_self_self: "UPDATE self SET self_id = 2 WHERE id = 2",
 }
 
+test_schema! { with [
+        "CREATE TABLE self (
+            id INTEGER PRIMARY KEY,
+            self_id INTEGER REFERENCES self DEFERRABLE INITIALLY DEFERRED,
+            value INTEGER UNIQUE INITIALLY DEFERRED
+        )",
+        "INSERT INTO self VALUES (1, 1, 1), (2, 1, 2)",
+    ];
+    delete_ref_deferred_conflict: "DELETE FROM self WHERE id = 1",
+
+    insert_ref_deferred: "INSERT INTO self VALUES (3, 4, 3), (4, 3, 4)",
+    insert_ref_deferred_missing: "INSERT INTO self VALUES (3, 4, 3), (5, 3, 5)",
+    insert_unique_deferred_conflict: "INSERT INTO self VALUES (3, 1, 1)",
+
+    update_unique_deferred: "UPDATE self SET value = 3 - value",
+    update_unique_deferred_conflict: "UPDATE self SET value = 1 WHERE id = 2",
+}
+
+test_schema! { with [
+        "CREATE TABLE target (id INTEGER PRIMARY KEY, value STRING)",
+        "INSERT INTO target VALUES (1, 'a'), (2, 'b'), (3, 'c')",
+        "CREATE TABLE cascading (id INTEGER PRIMARY KEY, target_id INTEGER REFERENCES target ON DELETE CASCADE ON UPDATE CASCADE)",
+        "INSERT INTO cascading VALUES (1, 1), (2, 1), (3, 3)",
+        "CREATE TABLE nulling (id INTEGER PRIMARY KEY, target_id INTEGER INDEX REFERENCES target ON DELETE SET NULL ON UPDATE SET NULL)",
+        "INSERT INTO nulling VALUES (1, 1), (3, 3)",
+        "CREATE TABLE restricting (id INTEGER PRIMARY KEY, target_id INTEGER REFERENCES target ON DELETE RESTRICT)",
+        "INSERT INTO restricting VALUES (2, 2)",
+    ];
+    delete_ref_action: "DELETE FROM target WHERE id = 1",
+    delete_ref_action_all: "DELETE FROM target WHERE id != 2",
+    delete_ref_action_restrict: "DELETE FROM target WHERE id = 2",
+    update_ref_action_pk: "UPDATE target SET id = 9 WHERE id = 1",
+    update_ref_action_pk_restrict: "UPDATE target SET id = 9 WHERE id = 2",
+    update_ref_action_value: "UPDATE target SET value = 'x' WHERE id = 1",
+}
+
+test_schema! { with [
+        "CREATE TABLE self (id INTEGER PRIMARY KEY, parent_id INTEGER REFERENCES self ON DELETE CASCADE ON UPDATE CASCADE)",
+        "INSERT INTO self VALUES (1, NULL), (2, 1), (3, 2), (4, 4), (5, 5)",
+        "INSERT INTO self VALUES (6, 5)",
+        "UPDATE self SET parent_id = 6 WHERE id = 5",
+    ];
+    delete_ref_action_self_chain: "DELETE FROM self WHERE id = 1",
+    delete_ref_action_self_cycle: "DELETE FROM self WHERE id = 5",
+    delete_ref_action_self_self: "DELETE FROM self WHERE id = 4",
+    update_ref_action_self_chain: "UPDATE self SET id = 9 WHERE id = 2",
+    update_ref_action_self_self: "UPDATE self SET id = 9 WHERE id = 4",
+}
+
 test_schema! { with [
         "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING INDEX, value INTEGER)",
         "INSERT INTO test VALUES (1, 'a', 101), (2, 'b', 102), (3, 'b', 103)",
@@ -446,3 +918,293 @@ test_schema! { with [
     update_index_pk: "UPDATE test SET id = 4 WHERE id = 1",
     update_index_null: "UPDATE test SET name = NULL WHERE id = 3",
 }
+
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING INDEX, value INTEGER)",
+        "INSERT INTO test VALUES (1, 'a', 101), (2, 'b', NULL), (3, 'b', 103), (4, 'c', NULL)",
+        "CREATE TABLE other (id INTEGER PRIMARY KEY)",
+        "INSERT INTO other VALUES (1), (2)",
+    ];
+
+    analyze: "ANALYZE",
+    analyze_table: "ANALYZE test",
+    analyze_missing: "ANALYZE missing",
+    analyze_bare_semicolon: "ANALYZE;",
+}
+
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING INDEX, value INTEGER)",
+        "INSERT INTO test VALUES (1, 'a', 101), (2, 'b', NULL), (3, 'b', 103)",
+        "ANALYZE test",
+    ];
+
+    analyze_drop_column: "ALTER TABLE test DROP COLUMN value",
+    analyze_add_column: "ALTER TABLE test ADD COLUMN extra INTEGER DEFAULT 0",
+    analyze_stale: "INSERT INTO test VALUES (4, 'c', 104)",
+}
+
+test_schema! {
+    create_sequence: "CREATE SEQUENCE seq",
+    create_sequence_options: "CREATE SEQUENCE seq INCREMENT BY -1 START WITH 10",
+    create_sequence_bare: "CREATE SEQUENCE",
+    create_sequence_increment_zero: "CREATE SEQUENCE seq INCREMENT 0",
+    create_sequence_start_string: "CREATE SEQUENCE seq START 'a'",
+    create_sequence_database_missing: "CREATE SEQUENCE other.seq",
+}
+
+test_schema! { with [
+        "CREATE SEQUENCE seq",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING)",
+        "INSERT INTO test VALUES (1, 'a'), (2, 'b')",
+    ];
+
+    create_sequence_exists: "CREATE SEQUENCE seq",
+    drop_sequence: "DROP SEQUENCE seq",
+    drop_sequence_missing: "DROP SEQUENCE missing",
+    drop_sequence_if_exists: "DROP SEQUENCE IF EXISTS missing",
+    create_table_default_sequence: "CREATE TABLE other (id INTEGER PRIMARY KEY DEFAULT NEXTVAL('seq'))",
+    create_table_default_sequence_missing: "CREATE TABLE other (id INTEGER PRIMARY KEY DEFAULT NEXTVAL('missing'))",
+    create_table_default_sequence_datatype: "CREATE TABLE other (id INTEGER PRIMARY KEY, name STRING DEFAULT NEXTVAL('seq'))",
+    alter_table_add_column_default_sequence: "ALTER TABLE test ADD COLUMN value INTEGER DEFAULT NEXTVAL('seq') * 10",
+}
+
+test_schema! {
+    create_table_collation: "CREATE TABLE name (id INTEGER PRIMARY KEY, a STRING COLLATE NOCASE, b STRING COLLATE unicode_nocase UNIQUE, c STRING COLLATE BINARY)",
+    create_table_collation_unknown: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING COLLATE missing)",
+    create_table_collation_datatype: "CREATE TABLE name (id INTEGER PRIMARY KEY, value INTEGER COLLATE NOCASE)",
+    create_table_collation_pk: "CREATE TABLE name (id STRING PRIMARY KEY COLLATE NOCASE)",
+}
+
+test_schema! { with [
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name STRING COLLATE NOCASE UNIQUE, city STRING COLLATE UNICODE_NOCASE INDEX)",
+        "INSERT INTO users VALUES (1, 'alice', 'Ålesund'), (2, 'Bob', 'Oslo'), (3, 'carol', 'ÅLESUND')",
+    ];
+    insert_collation: "INSERT INTO users VALUES (4, 'Dave', 'OSLO')",
+    insert_collation_unique: "INSERT INTO users VALUES (4, 'ALICE', 'Bergen')",
+    insert_collation_unique_ascii: "INSERT INTO users VALUES (4, 'ÅLICE', 'Bergen')",
+    update_collation_unique: "UPDATE users SET name = 'BOB' WHERE id = 1",
+    create_index_collation_unique: "CREATE UNIQUE INDEX city_unique ON users (city)",
+    alter_table_add_column_collation: "ALTER TABLE users ADD COLUMN email STRING COLLATE NOCASE UNIQUE",
+}
+
+test_schema! { with [
+        "CREATE TABLE other (id INTEGER PRIMARY KEY)",
+        "INSERT INTO other VALUES (1)",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING NOT NULL, value STRING INDEX, score FLOAT UNIQUE, other_id INTEGER REFERENCES other)",
+        "INSERT INTO test VALUES (1, 'a', '101', 1.2, 1), (2, 'b', NULL, 2.4, NULL), (3, 'c', '3', 1.6, NULL)",
+    ];
+    alter_column_type: "ALTER TABLE test ALTER COLUMN value TYPE INTEGER",
+    alter_column_type_bare: "ALTER TABLE test ALTER value TYPE INTEGER",
+    alter_column_type_set_data: "ALTER TABLE test ALTER COLUMN value SET DATA TYPE INTEGER",
+    alter_column_type_invalid: "ALTER TABLE test ALTER COLUMN name TYPE INTEGER",
+    alter_column_type_unique: "ALTER TABLE test ALTER COLUMN score TYPE INTEGER",
+    alter_column_type_pk: "ALTER TABLE test ALTER COLUMN id TYPE STRING",
+    alter_column_type_ref: "ALTER TABLE test ALTER COLUMN other_id TYPE STRING",
+    alter_column_type_missing: "ALTER TABLE test ALTER COLUMN missing TYPE INTEGER",
+    alter_column_set_not_null: "ALTER TABLE test ALTER COLUMN score SET NOT NULL",
+    alter_column_set_not_null_nulls: "ALTER TABLE test ALTER COLUMN value SET NOT NULL",
+    alter_column_drop_not_null: "ALTER TABLE test ALTER COLUMN name DROP NOT NULL",
+    alter_column_drop_not_null_pk: "ALTER TABLE test ALTER COLUMN id DROP NOT NULL",
+    alter_column_invalid: "ALTER TABLE test ALTER COLUMN name SET DEFAULT 'x'",
+}
+
+test_schema! {
+    create_table_generated: "CREATE TABLE name (id INTEGER PRIMARY KEY, email STRING, email_lower STRING GENERATED ALWAYS AS (LOWER(email)) STORED INDEX)",
+    create_table_generated_missing: "CREATE TABLE name (id INTEGER PRIMARY KEY, value STRING GENERATED ALWAYS AS (missing) STORED)",
+    create_table_generated_generated: "CREATE TABLE name (id INTEGER PRIMARY KEY, a INTEGER GENERATED ALWAYS AS (id * 2) STORED, b INTEGER GENERATED ALWAYS AS (a * 2) STORED)",
+    create_table_generated_default: "CREATE TABLE name (id INTEGER PRIMARY KEY, value INTEGER DEFAULT 1 GENERATED ALWAYS AS (id * 2) STORED)",
+    create_table_generated_pk: "CREATE TABLE name (id INTEGER PRIMARY KEY GENERATED ALWAYS AS (1) STORED)",
+    create_table_generated_volatile: "CREATE TABLE name (id INTEGER PRIMARY KEY, value FLOAT GENERATED ALWAYS AS (RANDOM()) STORED)",
+    create_table_generated_now: "CREATE TABLE name (id INTEGER PRIMARY KEY, value TIMESTAMP GENERATED ALWAYS AS (NOW()) STORED)",
+    create_table_generated_subquery: "CREATE TABLE name (id INTEGER PRIMARY KEY, value INTEGER GENERATED ALWAYS AS ((SELECT 1)) STORED)",
+    create_table_generated_stored: "CREATE TABLE name (id INTEGER PRIMARY KEY, value INTEGER GENERATED ALWAYS AS (id * 2))",
+}
+
+test_schema! { with [
+        "CREATE TABLE other (id INTEGER PRIMARY KEY)",
+        "INSERT INTO other VALUES (1), (2)",
+        r#"CREATE TABLE users (
+            id INTEGER PRIMARY KEY,
+            email STRING,
+            data JSON DEFAULT NULL,
+            other_id INTEGER DEFAULT NULL REFERENCES other ON DELETE SET NULL ON UPDATE CASCADE,
+            email_lower STRING GENERATED ALWAYS AS (LOWER(email)) STORED UNIQUE,
+            age INTEGER GENERATED ALWAYS AS (data->>'age') STORED INDEX,
+            other_label STRING GENERATED ALWAYS AS ('other ' || other_id) STORED
+        )"#,
+        r#"INSERT INTO users (id, email, data, other_id) VALUES (1, 'Alice@example.com', '{"age": 30}'::JSON, 1), (2, 'bob@example.com', NULL, 2)"#,
+    ];
+    insert_generated: r#"INSERT INTO users (id, email, data) VALUES (3, 'Carol@Example.com', '{"age": 41}'::JSON)"#,
+    insert_generated_values: "INSERT INTO users VALUES (3, 'carol@example.com', NULL, NULL)",
+    insert_generated_explicit: "INSERT INTO users (id, email, email_lower) VALUES (3, 'carol@example.com', 'x')",
+    insert_generated_values_explicit: "INSERT INTO users VALUES (3, 'carol@example.com', NULL, NULL, 'x')",
+    insert_generated_unique: "INSERT INTO users (id, email) VALUES (3, 'ALICE@example.com')",
+    insert_generated_cast: r#"INSERT INTO users (id, email, data) VALUES (3, 'carol@example.com', '{"age": "old"}'::JSON)"#,
+    insert_generated_on_conflict: "INSERT INTO users (id, email) VALUES (1, 'ALICE@EXAMPLE.COM') ON CONFLICT (id) DO UPDATE SET email = excluded.email",
+    insert_generated_on_conflict_set: "INSERT INTO users (id, email) VALUES (1, 'x') ON CONFLICT (id) DO UPDATE SET email_lower = 'x'",
+    update_generated: r#"UPDATE users SET email = 'BOB@EXAMPLE.COM', data = '{"age": 25}'::JSON WHERE id = 2"#,
+    update_generated_set: "UPDATE users SET email_lower = 'x' WHERE id = 1",
+    update_generated_cascade: "UPDATE other SET id = 3 WHERE id = 1",
+    delete_generated_set_null: "DELETE FROM other WHERE id = 2",
+    alter_table_add_column_generated: "ALTER TABLE users ADD COLUMN domain STRING GENERATED ALWAYS AS (SUBSTRING(email, POSITION('@' IN email) + 1)) STORED INDEX",
+    alter_table_drop_column_generated: "ALTER TABLE users DROP COLUMN email_lower",
+    alter_table_drop_column_generated_used: "ALTER TABLE users DROP COLUMN email",
+    alter_column_type_generated: "ALTER TABLE users ALTER COLUMN age TYPE STRING",
+    alter_column_type_generated_used: "ALTER TABLE users ALTER COLUMN other_id TYPE STRING",
+}
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, a STRING, b STRING, c STRING GENERATED ALWAYS AS (UPPER(b)) STORED)",
+        "INSERT INTO test VALUES (1, 'a', 'b')",
+        "ALTER TABLE test DROP COLUMN a",
+    ];
+    alter_table_drop_column_generated_shift: "INSERT INTO test VALUES (2, 'x')",
+}
+
+test_schema! {
+    create_type: "CREATE TYPE mood AS ENUM ('sad', 'ok', 'happy')",
+    create_type_empty: "CREATE TYPE mood AS ENUM ()",
+    create_type_duplicate_label: "CREATE TYPE mood AS ENUM ('sad', 'sad')",
+    create_type_label_integer: "CREATE TYPE mood AS ENUM (1)",
+    create_type_database_missing: "CREATE TYPE other.mood AS ENUM ('sad')",
+    create_table_type_missing: "CREATE TABLE test (id INTEGER PRIMARY KEY, value mood)",
+}
+
+test_schema! { with [
+        "CREATE TYPE mood AS ENUM ('sad', 'ok', 'happy')",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, mood mood DEFAULT 'ok' INDEX, name STRING)",
+        "INSERT INTO test VALUES (1, 'happy', 'a'), (2, 'sad', 'b')",
+        "INSERT INTO test (id, name) VALUES (3, 'c')",
+    ];
+
+    create_type_exists: "CREATE TYPE mood AS ENUM ('x')",
+    create_table_type: "CREATE TABLE other (id INTEGER PRIMARY KEY, value mood NOT NULL)",
+    create_table_type_default_invalid: "CREATE TABLE other (id INTEGER PRIMARY KEY, value mood DEFAULT 'meh')",
+    insert_type: "INSERT INTO test VALUES (4, 'ok', 'd')",
+    insert_type_invalid: "INSERT INTO test VALUES (4, 'meh', 'd')",
+    insert_type_case: "INSERT INTO test VALUES (4, 'OK', 'd')",
+    insert_type_integer: "INSERT INTO test VALUES (4, 1, 'd')",
+    update_type: "UPDATE test SET mood = 'ok' WHERE id = 1",
+    update_type_invalid: "UPDATE test SET mood = 'meh' WHERE id = 1",
+    drop_type: "DROP TYPE mood",
+    drop_type_missing: "DROP TYPE missing",
+    drop_type_if_exists: "DROP TYPE IF EXISTS missing",
+    alter_table_add_column_enum: "ALTER TABLE test ADD COLUMN other mood DEFAULT 'sad'",
+    alter_column_type_enum: "ALTER TABLE test ALTER COLUMN name TYPE mood",
+    alter_column_type_enum_string: "ALTER TABLE test ALTER COLUMN mood TYPE STRING",
+}
+
+test_schema! { with [
+        "CREATE TYPE mood AS ENUM ('sad', 'ok', 'happy')",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, name STRING)",
+        "INSERT INTO test VALUES (1, 'ok'), (2, 'happy')",
+    ];
+
+    alter_column_type_enum_valid: "ALTER TABLE test ALTER COLUMN name TYPE mood",
+    drop_type_unused: "DROP TYPE mood",
+}
+
+test_schema! {
+    create_table_array: "CREATE TABLE test (id INTEGER PRIMARY KEY, tags STRING[], scores INTEGER[] NOT NULL DEFAULT '{1,2}')",
+    create_table_array_multidimensional: "CREATE TABLE test (id INTEGER PRIMARY KEY, tags STRING[][])",
+    create_table_array_default_invalid: "CREATE TABLE test (id INTEGER PRIMARY KEY, tags STRING[] DEFAULT ARRAY[1])",
+    create_table_array_primary_key: "CREATE TABLE test (id INTEGER[] PRIMARY KEY)",
+    create_table_array_index: "CREATE TABLE test (id INTEGER PRIMARY KEY, tags STRING[] INDEX)",
+    create_table_array_unique: "CREATE TABLE test (id INTEGER PRIMARY KEY, tags STRING[] UNIQUE)",
+}
+
+test_schema! { with [
+        "CREATE TYPE mood AS ENUM ('sad', 'ok', 'happy')",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, tags STRING[], moods mood[])",
+        "INSERT INTO test VALUES (1, ARRAY['a', NULL], ARRAY['ok'])",
+    ];
+
+    insert_array: "INSERT INTO test VALUES (2, ARRAY['b'], ARRAY['sad', 'happy'])",
+    insert_array_null_elements: "INSERT INTO test VALUES (2, ARRAY[NULL], ARRAY[NULL])",
+    insert_array_datatype_invalid: "INSERT INTO test VALUES (2, ARRAY[1], NULL)",
+    insert_array_enum_invalid: "INSERT INTO test VALUES (2, NULL, ARRAY['meh'])",
+    insert_array_string: "INSERT INTO test VALUES (2, '{a}', NULL)",
+    update_array: "UPDATE test SET tags = tags || 'c' WHERE id = 1",
+    create_index_array: "CREATE INDEX test_tags ON test (tags)",
+    create_index_array_element: "CREATE INDEX test_tags ON test ((tags[1]))",
+    drop_type_array: "DROP TYPE mood",
+}
+
+test_schema! {
+    create_table_interval: "CREATE TABLE test (id INTEGER PRIMARY KEY, duration INTERVAL NOT NULL DEFAULT INTERVAL '1 day 02:00')",
+    create_table_interval_primary_key: "CREATE TABLE test (id INTERVAL PRIMARY KEY)",
+    create_table_interval_index: "CREATE TABLE test (id INTEGER PRIMARY KEY, duration INTERVAL INDEX)",
+}
+
+test_schema! { with [
+        "CREATE TABLE test (id INTEGER PRIMARY KEY, duration INTERVAL)",
+        "INSERT INTO test VALUES (1, INTERVAL '1 month -2 days 03:00:00.5')",
+    ];
+
+    insert_interval: "INSERT INTO test VALUES (2, INTERVAL '-1 year'), (3, NULL)",
+    insert_interval_datatype_invalid: "INSERT INTO test VALUES (2, 1)",
+    update_interval: "UPDATE test SET duration = duration * 2 WHERE id = 1",
+    create_index_interval: "CREATE INDEX test_duration ON test (duration)",
+}
+
+test_schema! {
+    create_user: "CREATE USER alice WITH PASSWORD 'secret'",
+    create_user_no_password: "CREATE USER alice",
+    create_user_root: "CREATE USER root PASSWORD 'secret'",
+    create_user_password_integer: "CREATE USER alice PASSWORD 1",
+    alter_user_missing: "ALTER USER alice PASSWORD 'secret'",
+    drop_user_missing: "DROP USER alice",
+    drop_user_if_exists: "DROP USER IF EXISTS alice",
+}
+
+test_schema! { with [
+        "CREATE USER alice PASSWORD 'secret'",
+    ];
+
+    create_user_exists: "CREATE USER alice PASSWORD 'other'",
+    alter_user: "ALTER USER alice WITH PASSWORD 'other'",
+    alter_user_password_null: "ALTER USER alice PASSWORD NULL",
+    drop_user: "DROP USER alice",
+}
+
+test_schema! {
+    create_role: "CREATE ROLE readers",
+    create_role_root: "CREATE ROLE root",
+    grant_role_missing: "GRANT readers TO alice",
+}
+
+test_schema! { with [
+        "CREATE USER alice PASSWORD 'secret'",
+        "CREATE ROLE readers",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY)",
+    ];
+
+    create_role_exists: "CREATE ROLE alice",
+    grant: "GRANT SELECT, INSERT ON TABLE test TO alice",
+    grant_all: "GRANT ALL PRIVILEGES ON test TO readers",
+    grant_ddl: "GRANT DDL ON test TO alice",
+    grant_references: "GRANT REFERENCES, SELECT ON test TO alice",
+    grant_invalid: "GRANT SELECT, foo ON test TO alice",
+    grant_table_missing: "GRANT SELECT ON missing TO alice",
+    grant_user_missing: "GRANT SELECT ON test TO bob",
+    grant_role: "GRANT readers TO alice",
+    grant_role_not_role: "GRANT alice TO readers",
+    grant_role_user_missing: "GRANT readers TO bob",
+    drop_role: "DROP ROLE readers",
+}
+
+test_schema! { with [
+        "CREATE USER alice PASSWORD 'secret'",
+        "CREATE ROLE readers",
+        "CREATE TABLE test (id INTEGER PRIMARY KEY)",
+        "GRANT ALL ON test TO alice",
+        "GRANT SELECT ON test TO readers",
+        "GRANT readers TO alice",
+    ];
+
+    revoke: "REVOKE INSERT, DELETE ON test FROM alice",
+    revoke_all: "REVOKE ALL ON test FROM alice",
+    revoke_missing: "REVOKE UPDATE ON test FROM readers",
+    revoke_role: "REVOKE readers FROM alice",
+    drop_user_granted: "DROP USER alice",
+    drop_role_granted: "DROP ROLE readers",
+}